@@ -1,14 +1,104 @@
 use config::Config as ConfigBuilder;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::env;
-use std::path::Path;
+use std::path::PathBuf;
+
+pub mod validation;
+
+pub use validation::DEFAULT_CONFIG_TOML;
+use validation::{check_schema, check_semantics};
 
 #[derive(Debug, Deserialize)]
 pub struct SpotConfig {
-    // Configuration for spot trading between two exchanges
-    pub exchange1: String,
+    // Configuration for spot arbitrage across multiple exchanges
+    #[serde(default)]
+    pub exchanges: Vec<String>, // 参与比价的交易所，至少两家，如 ["binance", "okx", "bybit"]
+    #[serde(default)]
+    pub exchange1: String, // 旧版写法，exchanges 为空时与 exchange2 组成交易所列表
+    #[serde(default)]
     pub exchange2: String,
     pub symbol: String,
+    #[serde(default = "default_spot_fee_rate")]
+    pub fee_rate: f64, // 默认单笔手续费率，未在 fee_rates 中单独配置的交易所使用，默认0.001（0.1%）
+    #[serde(default)]
+    pub fee_rates: HashMap<String, f64>, // 各交易所手续费率，如 { binance = 0.001, okx = 0.0008 }
+    #[serde(default = "default_spot_min_profit_rate")]
+    pub min_profit_rate: f64, // 扣除双边手续费后的最小收益率，默认0.0005（0.05%）
+    #[serde(default = "default_spot_trade_amount")]
+    pub trade_amount: f64, // 单次套利的计价币金额上限，默认100
+    #[serde(default = "default_spot_withdrawal_delay_secs")]
+    pub withdrawal_delay_secs: u64, // 默认提币到账时间（秒），默认1800
+    #[serde(default)]
+    pub withdrawal_delays: HashMap<String, u64>, // 各交易所提币到账时间（秒），按转出所计算
+    #[serde(default)]
+    pub balances: HashMap<String, SpotBalance>, // 各交易所初始资金分布，未配置的交易所余额为0
+    #[serde(default = "default_spot_scan_interval_secs")]
+    pub scan_interval_secs: u64, // 行情扫描间隔（秒），默认2
+}
+
+/// 单个交易所上可用于套利的资金
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct SpotBalance {
+    #[serde(default)]
+    pub base: f64, // 基础币数量，用于在高价所卖出
+    #[serde(default)]
+    pub quote: f64, // 计价币数量，用于在低价所买入
+}
+
+impl SpotConfig {
+    /// 参与比价的交易所列表，兼容旧版 exchange1/exchange2 写法，去重并统一为小写
+    pub fn exchange_list(&self) -> Vec<String> {
+        let configured: Vec<&String> = if self.exchanges.is_empty() {
+            vec![&self.exchange1, &self.exchange2]
+        } else {
+            self.exchanges.iter().collect()
+        };
+        let mut exchanges: Vec<String> = Vec::new();
+        for exchange in configured {
+            let exchange = exchange.trim().to_lowercase();
+            if !exchange.is_empty() && !exchanges.contains(&exchange) {
+                exchanges.push(exchange);
+            }
+        }
+        exchanges
+    }
+
+    /// 交易所的手续费率，未单独配置时使用默认手续费率
+    pub fn fee_rate_for(&self, exchange: &str) -> f64 {
+        self.fee_rates
+            .get(exchange)
+            .copied()
+            .unwrap_or(self.fee_rate)
+    }
+
+    /// 从交易所提币的到账时间（秒），未单独配置时使用默认值
+    pub fn withdrawal_delay_for(&self, exchange: &str) -> u64 {
+        self.withdrawal_delays
+            .get(exchange)
+            .copied()
+            .unwrap_or(self.withdrawal_delay_secs)
+    }
+}
+
+fn default_spot_fee_rate() -> f64 {
+    0.001
+}
+
+fn default_spot_min_profit_rate() -> f64 {
+    0.0005
+}
+
+fn default_spot_trade_amount() -> f64 {
+    100.0
+}
+
+fn default_spot_withdrawal_delay_secs() -> u64 {
+    1800
+}
+
+fn default_spot_scan_interval_secs() -> u64 {
+    2
 }
 
 #[derive(Debug, Deserialize)]
@@ -23,9 +113,40 @@ pub struct FuturesConfig {
 pub struct TriangleConfig {
     // Configuration for triangular arbitrage within a single exchange
     pub exchange: String,
+    #[serde(default)]
     pub pair1: String,
+    #[serde(default)]
     pub pair2: String,
+    #[serde(default)]
     pub pair3: String,
+    #[serde(default)]
+    pub base_currency: String, // 基准币，设置后自动发现三角路径，忽略 pair1/pair2/pair3
+    #[serde(default = "default_triangle_fee_rate")]
+    pub fee_rate: f64, // 单笔手续费率，默认0.001（0.1%）
+    #[serde(default)]
+    pub min_profit_rate: f64, // 扣除手续费后的最小收益率，默认0
+    #[serde(default = "default_pairs_cache_secs")]
+    pub pairs_cache_secs: u64, // 交易对元数据缓存时间（秒），默认3600
+    #[serde(default = "default_scan_interval_secs")]
+    pub scan_interval_secs: u64, // 路径扫描间隔（秒），默认5
+    #[serde(default)]
+    pub blacklist_pairs: Vec<String>, // 排除的交易对，如 ["LUNA/USDT"]，自动发现路径时跳过
+    #[serde(default)]
+    pub whitelist_pairs: Vec<String>, // 只使用这些交易对，为空时不限制
+    #[serde(default)]
+    pub min_quote_volume_24h: f64, // 交易对最小24小时成交额（计价币），0表示不限制
+}
+
+fn default_triangle_fee_rate() -> f64 {
+    0.001
+}
+
+fn default_pairs_cache_secs() -> u64 {
+    3600
+}
+
+fn default_scan_interval_secs() -> u64 {
+    5
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -33,66 +154,886 @@ pub struct GridConfig {
     // Configuration for grid trading strategy
     // 交易参数 (Trading parameters)
     pub trading_asset: String,
+    #[serde(default = "default_total_capital")]
+    pub total_capital: f64, // 计划投入的总资金（USDT），需不小于每格交易金额
     pub grid_count: u32,
     pub trade_amount: f64,
     pub max_position: f64,
     pub max_drawdown: f64,
     pub price_precision: u32,
     pub quantity_precision: u32,
+    #[serde(default = "default_auto_precision")]
+    pub auto_precision: bool, // 启动时按交易所元数据（szDecimals）覆盖价格/数量精度，关闭时只校验并警告，默认true
     pub check_interval: u64,
     pub max_order_age_minutes: f64,       // 订单最大存活时间（分钟）
     pub order_status_check_interval: u64, // 订单状态检查间隔（秒）
+    #[serde(default = "default_min_rebuild_interval_secs")]
+    pub min_rebuild_interval_secs: u64, // 两次整网重建的最小间隔（秒），期间只做增量补单，0表示不限制，默认60
+    #[serde(default = "default_cancel_protection")]
+    pub cancel_protection: bool, // 重建网格前按挂单价值（距当前价、潜在利润、等待时长）只撤低价值挂单，默认true
+    #[serde(default = "default_cancel_protection_min_score")]
+    pub cancel_protection_min_score: f64, // 挂单保留评分阈值（0-100），达到该分数的挂单重建时保留，默认80
     pub leverage: u32,
 
     // 网格策略参数 (Grid strategy parameters)
     pub min_grid_spacing: f64,
     pub max_grid_spacing: f64,
-    pub grid_price_offset: f64,
+    pub grid_price_offset: f64, // 网格中心价相对市价的偏移比例，正数上移、负数下移，范围±5%，默认0
+    #[serde(default = "default_grid_center_mode")]
+    pub grid_center_mode: String, // 网格中心价模式："fixed"（当前价）、"short_ma"/"long_ma"（跟随短期/长期均线平移），默认fixed
+    #[serde(default = "default_grid_follow_threshold")]
+    pub grid_follow_threshold: f64, // 跟随均线模式下价格和均线都偏离网格中心超过该比例时平移网格，默认0.02（2%）
+    #[serde(default = "default_grid_follow_interval_secs")]
+    pub grid_follow_interval_secs: u64, // 跟随均线模式下检查平移的间隔（秒），默认300
+    #[serde(default = "default_spacing_mode")]
+    pub spacing_mode: String, // 网格间距模式："fixed"（动态最小间距）或 "atr"（k × ATR），默认fixed
+    #[serde(default = "default_grid_layout")]
+    pub grid_layout: String, // 网格布点算法："geometric"（等比）、"arithmetic"（等差，固定价格步长）、"spread"（做市价差），默认geometric
+    #[serde(default)]
+    pub grid_price_step: f64, // 等差布点的固定价格步长，0表示取中心价 × 基础间距，默认0
+    #[serde(default = "default_maker_spread")]
+    pub maker_spread: f64, // 做市价差布点时买卖第一档之间的价差比例，默认0.002（0.2%）
+    #[serde(default = "default_position_sizing")]
+    pub position_sizing: String, // 买单分批建仓模型："adaptive"（按成本价和网格位置自适应）、"equal"（等额）、"pyramid"（越跌越少）、"reverse_pyramid"（越跌越多，马丁格尔），默认adaptive
+    #[serde(default = "default_position_sizing_ratio")]
+    pub position_sizing_ratio: f64, // 金字塔/反金字塔相邻两档的资金倍数，需大于1，默认1.5
+    #[serde(default = "default_martingale_max_levels")]
+    pub martingale_max_levels: u32, // 反金字塔最多加仓档数，默认4
+    #[serde(default)]
+    pub martingale_max_funds: f64, // 反金字塔持仓加买单的总金额硬上限（USDT），使用 reverse_pyramid 时必须设置
+    #[serde(default = "default_atr_period")]
+    pub atr_period: usize, // ATR 周期（K线根数），默认14
+    #[serde(default = "default_atr_multiplier")]
+    pub atr_multiplier: f64, // ATR 模式下单格间距 = atr_multiplier × ATR，默认1.0
+    #[serde(default = "default_atr_bar_size")]
+    pub atr_bar_size: usize, // 用价格历史近似K线时每根K线包含的价格点数，默认3
+    #[serde(default = "default_grid_bound_pct")]
+    pub grid_lower_bound_pct: f64, // 网格下边界，当前价格下方的比例，默认0.2（20%）
+    #[serde(default = "default_grid_bound_pct")]
+    pub grid_upper_bound_pct: f64, // 网格上边界，当前价格上方的比例，默认0.2（20%）
+    #[serde(default)]
+    pub expected_price_min: Option<f64>, // 启动检查的预期最低价格，当前价格低于此值时拒绝启动，默认不检查
+    #[serde(default)]
+    pub expected_price_max: Option<f64>, // 启动检查的预期最高价格，当前价格高于此值时拒绝启动，默认不检查
 
     // 风险控制参数 (Risk control parameters)
     pub max_single_loss: f64,
     pub max_daily_loss: f64,
-    pub trailing_stop_ratio: f64,     // 浮动止损比例，默认0.1（10%）
+    pub trailing_stop_ratio: f64, // 浮动止损比例，默认0.1（10%）
+    #[serde(default = "default_stop_loss_cooldown_minutes")]
+    pub stop_loss_cooldown_minutes: u64, // 部分止损后的冷却期（分钟），期间只平仓不开新仓，默认30
+    #[serde(default = "default_max_consecutive_losses")]
+    pub max_consecutive_losses: u32, // 连续亏损平仓笔数上限，达到后熔断暂停开新仓并告警，0表示不启用，默认5
+    #[serde(default = "default_loss_circuit_cooldown_minutes")]
+    pub loss_circuit_cooldown_minutes: u64, // 连续亏损熔断的冷却期（分钟），到期自动恢复，0表示只能通过 resume 指令恢复，默认60
+    #[serde(default)]
+    pub trailing_tp_ratio: f64, // 追踪止盈回撤比例，0表示不启用
+    #[serde(default = "default_min_take_profit_trigger")]
+    pub min_take_profit_trigger: f64, // 追踪止盈激活所需的最小浮盈比例，默认0.03（3%）
     pub margin_safety_threshold: f64, // 保证金安全阈值，默认0.3（30%）
-    pub slippage_tolerance: f64,      // 滑点容忍度，默认0.001（0.1%）
-    pub max_orders_per_batch: usize,  // 每批最大订单数，默认5
-    pub order_batch_delay_ms: u64,    // 批次间延迟毫秒数，默认200ms
+    #[serde(default = "default_adopt_existing_position")]
+    pub adopt_existing_position: bool, // 启动时接管账户已有持仓，以交易所持仓数量和均价初始化网格，默认true
+    #[serde(default = "default_position_reconcile_interval_secs")]
+    pub position_reconcile_interval_secs: u64, // 本地持仓与交易所对账间隔（秒），0表示不对账，默认300
+    #[serde(default = "default_position_reconcile_threshold")]
+    pub position_reconcile_threshold: f64, // 对账偏差阈值（相对交易所持仓的比例），超过时以交易所为准修正，默认0.01（1%）
+    pub slippage_tolerance: f64, // 滑点容忍度，默认0.001（0.1%）
+    #[serde(default = "default_close_max_retries")]
+    pub close_max_retries: u32, // 清仓IOC未完全成交时的最大重试次数，默认5
+    #[serde(default = "default_close_slippage_step")]
+    pub close_slippage_step: f64, // 清仓每次重试增加的滑点，默认0.005（0.5%）
+    #[serde(default = "default_market_order_slippage")]
+    pub market_order_slippage: f64, // 过期的高优先级订单转市价单时IOC价格相对当前价的滑点，默认0.01（1%）
+    pub max_orders_per_batch: usize, // 每批最大订单数，默认5
+    #[serde(default = "default_batch_min_success_rate")]
+    pub batch_min_success_rate: f64, // 批量下单成功率低于该值时缩小批次，默认0.8（80%）
+    #[serde(default = "default_api_requests_per_second")]
+    pub api_requests_per_second: f64, // 交易所 API 全局限流，每秒最多请求次数，0表示不限流，默认8
+    #[serde(default = "default_api_burst")]
+    pub api_burst: u32, // 限流令牌桶容量，允许的瞬时突发请求数，默认10
+    #[serde(default = "default_price_stale_secs")]
+    pub price_stale_secs: u64, // WebSocket 价格超过该秒数未更新时改用 REST 轮询价格并告警，0表示不启用，默认10
+    #[serde(default = "default_rest_price_poll_interval_secs")]
+    pub rest_price_poll_interval_secs: u64, // REST 兜底期间拉取价格的间隔（秒），默认3
+    #[serde(default = "default_message_queue_capacity")]
+    pub message_queue_capacity: usize, // 订阅消息队列和积压暂存区的容量，价格和盘口只保留最新值不占队列，暂存区满时丢弃非关键消息，默认1024
+    #[serde(default = "default_min_notional")]
+    pub min_notional: f64, // 单笔订单最小名义价值（USDC），低于该值的网格订单会被跳过，默认10
+    #[serde(default)]
+    pub iceberg_enabled: bool, // 冰山单开关，单格金额超过切片大小时拆成多个子订单分批挂出，默认false
+    #[serde(default)]
+    pub iceberg_slice_size: f64, // 冰山单每个子订单的最大金额（USDT），开启冰山单时必须大于0
+    #[serde(default)]
+    pub prefer_maker: bool, // 网格挂单使用 ALO（post-only），只做 maker，默认false
+    #[serde(default = "default_post_only_reject_mode")]
+    pub post_only_reject_mode: String, // post-only 单会立即成交被拒时的处理："reprice"（挂到盘口最优价重试）或 "skip"（跳过），默认reprice
+    #[serde(default = "default_grid_mode")]
+    pub grid_mode: String, // 网格运行模式："normal"（双向）、"reduce_only"（只挂平仓方向，只减仓）、"accumulate"（只挂建仓方向），默认normal
+    #[serde(default = "default_profit_mode")]
+    pub profit_mode: String, // 已实现利润的处理："compound"（计入总资金继续交易）、"fixed_capital"（隔离出交易资金，本金恒定）或 "withdraw_to_base"（记为待提取金额），默认compound
+    #[serde(default)]
+    pub trading_windows: Vec<String>, // UTC 交易时间窗口，如 ["00:00-06:00", "22:00-02:00", "Mon-Fri 08:00-16:00"]，窗口外保留已有挂单但不开新仓，为空表示全天交易
+    #[serde(default = "default_grid_breakout_secs")]
+    pub grid_breakout_secs: u64, // 价格持续脱离网格区间（低于最低买单或高于最高卖单）多少秒后触发处理，默认300
+    #[serde(default = "default_grid_breakout_action")]
+    pub grid_breakout_action: String, // 价格脱离网格后的处理："rebuild"（撤单按当前价重建）、"pause"（暂停开新仓）或 "alert"（仅告警），默认rebuild
     pub max_holding_time: u64,
+    #[serde(default = "default_holding_timeout_close_mode")]
+    pub holding_timeout_close_mode: String, // 持仓超时平仓方式："market"（IOC市价）或 "limit"（挂限价单），默认market
     pub history_length: usize,
-    pub max_active_orders: usize,    // 每次最多挂单数量（买/卖各自）
-    pub fee_rate: f64,               // 手续费率
-    pub min_profit: f64,             // 最小盈利阈值
+    #[serde(default = "default_kline_intervals")]
+    pub kline_intervals: Vec<String>, // 订阅的K线周期，第一个为指标计算主周期，默认["1m"]
+    #[serde(default = "default_kline_capacity")]
+    pub kline_capacity: usize, // 每个周期缓存的K线数量，默认200
+    #[serde(default = "default_market_state_confirm_ticks")]
+    pub market_state_confirm_ticks: u32, // 市场状态需连续检测到多少次才确认切换（极端/闪崩立即生效），默认3
+    pub max_active_orders: usize, // 每次最多挂单数量（买/卖各自）
+    pub fee_rate: f64,            // 手续费率，未单独配置 maker/taker 费率时两者都使用该值
+    #[serde(default)]
+    pub maker_fee_rate: Option<f64>, // 挂单（maker）费率，网格限价单按此计算盈亏平衡点，负数表示返佣
+    #[serde(default)]
+    pub taker_fee_rate: Option<f64>, // 吃单（taker）费率，清仓/止盈等 IOC 单按此计算
+    pub min_profit: f64, // 每格交易的最小净利润（绝对金额，单位USDC），按 trade_amount 换算为利润率
+    #[serde(default = "default_funding_cost_enabled")]
+    pub funding_cost_enabled: bool, // 永续合约把预期资金费成本计入盈亏平衡判断，默认true
+    #[serde(default = "default_funding_holding_hours")]
+    pub funding_holding_hours: f64, // 预估每格从买入到卖出的持仓时长（小时），预期资金费 = 小时费率 × 该时长，默认8
     pub margin_usage_threshold: f64, // 保证金使用率阈值，默认0.8（80%）
     pub order_update_threshold: f64, // 订单更新阈值（价格变化百分比），默认0.02（2%）
+
+    // 参数优化回滚 (Parameter rollback)
+    #[serde(default = "default_rollback_min_hours")]
+    pub rollback_min_hours: f64, // 参数优化后至少经过多少小时才考虑回滚，默认6小时
+    #[serde(default = "default_rollback_threshold")]
+    pub rollback_threshold: f64, // 性能评分下降超过该值时回滚参数，默认15分
+
+    // 退出参数 (Shutdown parameters)
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64, // 收到 SIGINT/SIGTERM 后撤单清仓的最长时间（秒），超时强制退出，默认60
+    #[serde(default = "default_close_positions_on_exit")]
+    pub close_positions_on_exit: bool, // 用户信号、网络错误、配置错误等退出时是否清仓，止损/保证金不足/紧急关闭始终清仓，默认true
+
+    // 模拟下单参数 (Dry-run parameters)
+    #[serde(default)]
+    pub dry_run: bool, // 模拟下单模式，只在本地撮合不发送真实订单，也可用 --dry-run 开启
+
+    // 行情录制参数 (Market data recording)
+    #[serde(default)]
+    pub record_file: String, // 行情录制文件（JSONL），为空时不录制，也可用 grid --record 指定
+
+    // 状态持久化参数 (State persistence parameters)
+    #[serde(default = "default_state_dir")]
+    pub state_dir: String, // 状态文件目录，默认当前目录
+    #[serde(default)]
+    pub state_file_prefix: String, // 状态文件名前缀，多实例并行时用于区分，默认为空
+    #[serde(default)]
+    pub rebalance_trigger_file: String, // 手动重平衡触发文件名（位于状态目录，带状态文件前缀），文件出现时重平衡一次并删除，默认为空不监控
+    #[serde(default = "default_state_remote_backend")]
+    pub state_remote_backend: String, // 状态快照远程备份："none"（默认，只写本地）、"http"（PUT 到 HTTP 端点）或 "s3"（S3 兼容对象存储）
+    #[serde(default)]
+    pub state_remote_url: String, // 远程地址，状态文件名追加在后面，如 https://backup.example.com/taoli 或 https://s3.us-east-1.amazonaws.com/bucket/prefix
+    #[serde(default = "default_state_remote_token_env")]
+    pub state_remote_token_env: String, // http 后端 Bearer token 所在的环境变量名，未设置时不带认证头，默认 STATE_REMOTE_TOKEN
+    #[serde(default = "default_state_remote_region")]
+    pub state_remote_region: String, // s3 后端的区域，凭证从 AWS_ACCESS_KEY_ID/AWS_SECRET_ACCESS_KEY 读取，默认 us-east-1
+    #[serde(default = "default_state_remote_timeout_secs")]
+    pub state_remote_timeout_secs: u64, // 远程读写超时（秒），默认5
+    #[serde(default = "default_risk_log_max_size_mb")]
+    pub risk_log_max_size_mb: u64, // 风险事件日志 risk_events.jsonl 超过该大小（MB）时滚动，0表示不滚动，默认10
+    #[serde(default = "default_risk_log_max_files")]
+    pub risk_log_max_files: usize, // 风险事件日志滚动后保留的历史文件数，默认5
 }
 
-#[derive(Debug, Deserialize)]
+impl GridConfig {
+    /// 挂单（maker）费率，未配置时使用 fee_rate
+    pub fn maker_fee(&self) -> f64 {
+        self.maker_fee_rate.unwrap_or(self.fee_rate)
+    }
+
+    /// 吃单（taker）费率，未配置时使用 fee_rate
+    pub fn taker_fee(&self) -> f64 {
+        self.taker_fee_rate.unwrap_or(self.fee_rate)
+    }
+}
+
+fn default_auto_precision() -> bool {
+    true
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    60
+}
+
+fn default_close_positions_on_exit() -> bool {
+    true
+}
+
+fn default_funding_cost_enabled() -> bool {
+    true
+}
+
+fn default_funding_holding_hours() -> f64 {
+    8.0
+}
+
+fn default_rollback_min_hours() -> f64 {
+    6.0
+}
+
+fn default_rollback_threshold() -> f64 {
+    15.0
+}
+
+fn default_kline_intervals() -> Vec<String> {
+    vec!["1m".to_string()]
+}
+
+fn default_kline_capacity() -> usize {
+    200
+}
+
+fn default_grid_layout() -> String {
+    "geometric".to_string()
+}
+
+fn default_maker_spread() -> f64 {
+    0.002
+}
+
+fn default_position_sizing() -> String {
+    "adaptive".to_string()
+}
+
+fn default_position_sizing_ratio() -> f64 {
+    1.5
+}
+
+fn default_martingale_max_levels() -> u32 {
+    4
+}
+
+fn default_spacing_mode() -> String {
+    "fixed".to_string()
+}
+
+fn default_grid_center_mode() -> String {
+    "fixed".to_string()
+}
+
+fn default_grid_follow_threshold() -> f64 {
+    0.02
+}
+
+fn default_grid_follow_interval_secs() -> u64 {
+    300
+}
+
+fn default_atr_period() -> usize {
+    14
+}
+
+fn default_atr_multiplier() -> f64 {
+    1.0
+}
+
+fn default_atr_bar_size() -> usize {
+    3
+}
+
+fn default_grid_bound_pct() -> f64 {
+    0.2
+}
+
+fn default_total_capital() -> f64 {
+    1000.0
+}
+
+fn default_adopt_existing_position() -> bool {
+    true
+}
+
+fn default_position_reconcile_interval_secs() -> u64 {
+    300
+}
+
+fn default_position_reconcile_threshold() -> f64 {
+    0.01
+}
+
+fn default_min_notional() -> f64 {
+    10.0
+}
+
+fn default_api_requests_per_second() -> f64 {
+    8.0
+}
+
+fn default_api_burst() -> u32 {
+    10
+}
+
+fn default_price_stale_secs() -> u64 {
+    10
+}
+
+fn default_message_queue_capacity() -> usize {
+    1024
+}
+
+fn default_rest_price_poll_interval_secs() -> u64 {
+    3
+}
+
+fn default_close_max_retries() -> u32 {
+    5
+}
+
+fn default_close_slippage_step() -> f64 {
+    0.005
+}
+
+fn default_market_order_slippage() -> f64 {
+    0.01
+}
+
+fn default_batch_min_success_rate() -> f64 {
+    0.8
+}
+
+fn default_min_rebuild_interval_secs() -> u64 {
+    60
+}
+
+fn default_cancel_protection() -> bool {
+    true
+}
+
+fn default_cancel_protection_min_score() -> f64 {
+    80.0
+}
+
+fn default_post_only_reject_mode() -> String {
+    "reprice".to_string()
+}
+
+fn default_max_consecutive_losses() -> u32 {
+    5
+}
+
+fn default_loss_circuit_cooldown_minutes() -> u64 {
+    60
+}
+
+fn default_stop_loss_cooldown_minutes() -> u64 {
+    30
+}
+
+fn default_profit_mode() -> String {
+    "compound".to_string()
+}
+
+fn default_grid_mode() -> String {
+    "normal".to_string()
+}
+
+fn default_grid_breakout_secs() -> u64 {
+    300
+}
+
+fn default_grid_breakout_action() -> String {
+    "rebuild".to_string()
+}
+
+fn default_market_state_confirm_ticks() -> u32 {
+    3
+}
+
+fn default_holding_timeout_close_mode() -> String {
+    "market".to_string()
+}
+
+fn default_min_take_profit_trigger() -> f64 {
+    0.03
+}
+
+fn default_state_remote_backend() -> String {
+    "none".to_string()
+}
+
+fn default_state_remote_token_env() -> String {
+    "STATE_REMOTE_TOKEN".to_string()
+}
+
+fn default_state_remote_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_state_remote_timeout_secs() -> u64 {
+    5
+}
+
+fn default_state_dir() -> String {
+    ".".to_string()
+}
+
+fn default_risk_log_max_size_mb() -> u64 {
+    10
+}
+
+fn default_risk_log_max_files() -> usize {
+    5
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct AccountConfig {
     // Configuration for account credentials
-    pub private_key: String,
+    #[serde(default)]
+    pub name: String, // 账户名称，多账户模式下用于区分日志和状态文件，默认按序号命名
+    #[serde(default)]
+    pub private_key: String, // 明文私钥，建议改用 private_key_file
+    #[serde(default)]
+    pub private_key_file: Option<String>, // 加密 keystore（JSON）文件路径，设置后优先于明文私钥
+    #[serde(default = "default_keystore_password_env")]
+    pub keystore_password_env: String, // 存放 keystore 密码的环境变量名，默认 KEYSTORE_PASSWORD
     pub real_account_address: Option<String>,
 }
 
+fn default_keystore_password_env() -> String {
+    "KEYSTORE_PASSWORD".to_string()
+}
+
+impl Default for AccountConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            private_key: String::new(),
+            private_key_file: None,
+            keystore_password_env: default_keystore_password_env(),
+            real_account_address: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlertConfig {
+    // Configuration for Telegram / DingTalk alert notifications
+    #[serde(default)]
+    pub enabled: bool, // 是否启用告警通知
+    #[serde(default)]
+    pub telegram_bot_token: String, // Telegram Bot token，留空则不启用
+    #[serde(default)]
+    pub telegram_chat_id: String, // Telegram 接收消息的 chat_id
+    #[serde(default)]
+    pub dingtalk_webhook: String, // 钉钉机器人 webhook 地址，留空则不启用
+    #[serde(default = "default_alert_min_severity")]
+    pub min_severity: u8, // 最低告警级别（1-5），默认4
+    #[serde(default = "default_alert_repeat_interval_secs")]
+    pub repeat_interval_secs: u64, // 相同告警的最小重复间隔（秒），默认300
+    #[serde(default = "default_alert_max_per_minute")]
+    pub max_alerts_per_minute: usize, // 每分钟最多发送告警数，默认10
+    #[serde(default)]
+    pub send_period_reports: bool, // 是否推送每日/每周报表（不受 min_severity 限制），默认false
+}
+
+fn default_alert_min_severity() -> u8 {
+    4
+}
+
+fn default_alert_repeat_interval_secs() -> u64 {
+    300
+}
+
+fn default_alert_max_per_minute() -> usize {
+    10
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            telegram_bot_token: String::new(),
+            telegram_chat_id: String::new(),
+            dingtalk_webhook: String::new(),
+            min_severity: default_alert_min_severity(),
+            repeat_interval_secs: default_alert_repeat_interval_secs(),
+            max_alerts_per_minute: default_alert_max_per_minute(),
+            send_period_reports: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MetricsConfig {
+    // Configuration for the Prometheus /metrics endpoint
+    #[serde(default)]
+    pub enabled: bool, // 是否启用指标服务，默认关闭
+    #[serde(default = "default_metrics_bind_address")]
+    pub bind_address: String, // 监听地址，默认127.0.0.1
+    #[serde(default = "default_metrics_port")]
+    pub port: u16, // 监听端口，默认9100
+}
+
+fn default_metrics_bind_address() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_metrics_port() -> u16 {
+    9100
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: default_metrics_bind_address(),
+            port: default_metrics_port(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ControlConfig {
+    // Configuration for the local runtime control endpoint
+    #[serde(default)]
+    pub enabled: bool, // 是否启用控制接口，默认关闭
+    #[serde(default = "default_control_bind_address")]
+    pub bind_address: String, // 监听地址，默认127.0.0.1，不建议对外暴露
+    #[serde(default = "default_control_port")]
+    pub port: u16, // 监听端口，默认9101
+    #[serde(default = "default_control_token_env")]
+    pub token_env: String, // Bearer token 所在的环境变量名，设置后所有请求需带认证头，默认 CONTROL_TOKEN
+}
+
+fn default_control_bind_address() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_control_port() -> u16 {
+    9101
+}
+
+fn default_control_token_env() -> String {
+    "CONTROL_TOKEN".to_string()
+}
+
+impl Default for ControlConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: default_control_bind_address(),
+            port: default_control_port(),
+            token_env: default_control_token_env(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct AppConfig {
     // Main application configuration encompassing all trading strategies and account settings
+    #[serde(default = "default_language")]
+    pub language: String, // 日志语言：auto（按 LANG 环境变量）、zh、en，默认auto
     pub spot: SpotConfig,
     pub futures: FuturesConfig,
     pub triangle: TriangleConfig,
     pub grid: GridConfig,
-    pub account: AccountConfig,
+    #[serde(default)]
+    pub account: AccountConfig, // 单账户配置，配置了 accounts 时忽略
+    #[serde(default)]
+    pub accounts: Vec<AccountConfig>, // 多账户配置，每个账户独立钱包和状态文件运行同一套网格策略
+    #[serde(default)]
+    pub alert: AlertConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub control: ControlConfig,
 }
 
-pub fn load_config(config_path: &Path) -> Result<AppConfig, Box<dyn std::error::Error>> {
-    // Load configuration from a file path and deserialize it into an AppConfig struct
-    let settings = ConfigBuilder::builder()
-        .add_source(config::File::from(config_path))
+fn default_language() -> String {
+    "auto".to_string()
+}
+
+pub fn load_config(config_paths: &[PathBuf]) -> Result<AppConfig, Box<dyn std::error::Error>> {
+    // Load configuration from one or more file paths and deserialize it into an AppConfig struct
+    // 多个配置文件按顺序合并，后面的文件覆盖前面的同名字段（数组整体替换），
+    // 例如 base.toml 放共享参数，prod.toml 只写需要修改的字段。
+    // 配置文件中的 ${ENV_VAR} 先替换为环境变量的值，再叠加 APP__ 前缀的环境变量覆盖，
+    // 例如 APP__GRID__TRADING_ASSET=ETH 覆盖 [grid] 下的 trading_asset
+    let mut builder = ConfigBuilder::builder();
+    for config_path in config_paths {
+        let contents = std::fs::read_to_string(config_path)
+            .map_err(|e| format!("读取配置文件 {} 失败: {}", config_path.display(), e))?;
+        let contents = expand_env_vars(&contents)?;
+        builder = builder.add_source(config::File::from_str(&contents, config::FileFormat::Toml));
+    }
+
+    let settings = builder
+        .add_source(
+            config::Environment::with_prefix(ENV_PREFIX)
+                .separator(ENV_SEPARATOR)
+                .try_parsing(true),
+        )
         .build()?;
 
-    let mut config: AppConfig = settings.try_deserialize()?;
+    // 先按默认配置检查结构，把缺失字段和类型错误一次性列出，而不是只报第一个反序列化错误
+    let tree: serde_json::Value = settings.clone().try_deserialize()?;
+    let mut report = check_schema(&tree);
+    if report.is_valid() {
+        let config: AppConfig = settings.try_deserialize()?;
+        report.merge(check_semantics(&config));
+        if report.is_valid() {
+            report.print_warnings();
+            return Ok(apply_private_key_env(config));
+        }
+    }
+    report.print_warnings();
+    Err(report.into_error().into())
+}
+
+fn apply_private_key_env(mut config: AppConfig) -> AppConfig {
     // 优先从环境变量读取 private_key
     if let Ok(pk) = env::var("PRIVATE_KEY") {
         config.account.private_key = pk;
     }
-    Ok(config)
+    config
+}
+
+const ENV_PREFIX: &str = "APP";
+const ENV_SEPARATOR: &str = "__";
+
+/// 将配置文本中的 `${ENV_VAR}` 替换为对应环境变量的值，`${ENV_VAR:-默认值}` 在变量未设置或为空时使用默认值
+///
+/// 引号外的 `#` 之后是注释，原样保留；双引号字符串中的值按 TOML 基本字符串转义，
+/// 单引号字符串中的值不能含单引号或控制字符，引号外的值原样替换（用于数字、布尔值）。
+/// 引用的环境变量未设置且没有默认值时返回错误，避免以空值启动。
+fn expand_env_vars(contents: &str) -> Result<String, String> {
+    let mut expanded = String::with_capacity(contents.len());
+
+    for (index, line) in contents.lines().enumerate() {
+        if index > 0 {
+            expanded.push('\n');
+        }
+        let line_number = index + 1;
+
+        // 当前所在字符串的引号，None 表示在引号外
+        let mut quote: Option<char> = None;
+        let mut rest = line;
+        while let Some((pos, ch)) = rest
+            .char_indices()
+            .find(|&(_, c)| matches!(c, '"' | '\'' | '#' | '\\' | '$'))
+        {
+            let next = pos + ch.len_utf8();
+            match ch {
+                '#' if quote.is_none() => break,
+                '\\' if quote == Some('"') => {
+                    // 转义序列连同被转义的字符原样保留
+                    let escaped_len = rest[next..].chars().next().map_or(0, char::len_utf8);
+                    expanded.push_str(&rest[..next + escaped_len]);
+                    rest = &rest[next + escaped_len..];
+                }
+                '"' | '\'' => {
+                    match quote {
+                        None => quote = Some(ch),
+                        Some(open) if open == ch => quote = None,
+                        Some(_) => {}
+                    }
+                    expanded.push_str(&rest[..next]);
+                    rest = &rest[next..];
+                }
+                '$' if rest[next..].starts_with('{') => {
+                    expanded.push_str(&rest[..pos]);
+                    let after = &rest[next + 1..];
+                    let end = after
+                        .find('}')
+                        .ok_or_else(|| format!("配置第{}行的环境变量引用缺少 '}}'", line_number))?;
+                    let value = resolve_env_reference(&after[..end], line_number)?;
+                    match quote {
+                        Some('"') => expanded.push_str(&escape_toml_basic_string(&value)),
+                        Some(_) if value.contains('\'') || value.chars().any(char::is_control) => {
+                            return Err(format!(
+                                "配置第{}行单引号字符串中的环境变量值含单引号或控制字符，请改用双引号",
+                                line_number
+                            ));
+                        }
+                        _ => expanded.push_str(&value),
+                    }
+                    rest = &after[end + 1..];
+                }
+                _ => {
+                    expanded.push_str(&rest[..next]);
+                    rest = &rest[next..];
+                }
+            }
+        }
+        expanded.push_str(rest);
+    }
+
+    Ok(expanded)
+}
+
+/// 解析 `${...}` 中的 `NAME` 或 `NAME:-默认值`
+fn resolve_env_reference(reference: &str, line_number: usize) -> Result<String, String> {
+    let (name, default) = match reference.split_once(":-") {
+        Some((name, default)) => (name.trim(), Some(default)),
+        None => (reference.trim(), None),
+    };
+    if name.is_empty() {
+        return Err(format!("配置第{}行的环境变量名为空", line_number));
+    }
+    match (env::var(name), default) {
+        (Ok(value), None) => Ok(value),
+        (Ok(value), Some(_)) if !value.is_empty() => Ok(value),
+        (_, Some(default)) => Ok(default.to_string()),
+        (Err(_), None) => Err(format!(
+            "配置第{}行引用的环境变量 {} 未设置",
+            line_number, name
+        )),
+    }
+}
+
+/// 按 TOML 基本字符串（双引号）规则转义
+fn escape_toml_basic_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04X}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 在系统临时目录下建独立目录，写入配置文件并返回路径
+    fn write_config_files(test_name: &str, files: &[(&str, &str)]) -> Vec<PathBuf> {
+        let dir =
+            env::temp_dir().join(format!("taoli_config_{}_{}", test_name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        files
+            .iter()
+            .map(|(name, contents)| {
+                let path = dir.join(name);
+                std::fs::write(&path, contents).unwrap();
+                path
+            })
+            .collect()
+    }
+
+    #[test]
+    fn load_config_later_files_override_earlier_keys() {
+        let paths = write_config_files(
+            "merge",
+            &[
+                ("base.toml", DEFAULT_CONFIG_TOML),
+                (
+                    "prod.toml",
+                    "[grid]\ntrading_asset = \"ETH\"\nleverage = 7\n",
+                ),
+            ],
+        );
+        let base: AppConfig = toml::from_str(DEFAULT_CONFIG_TOML).unwrap();
+
+        let config = load_config(&paths).unwrap();
+        assert_eq!(config.grid.trading_asset, "ETH");
+        assert_eq!(config.grid.leverage, 7);
+        // 后面文件没写的字段保留前面文件的值
+        assert_eq!(config.grid.grid_count, base.grid.grid_count);
+        assert_eq!(config.grid.min_grid_spacing, base.grid.min_grid_spacing);
+        assert_eq!(
+            config.account.real_account_address,
+            base.account.real_account_address
+        );
+
+        std::fs::remove_dir_all(paths[0].parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn load_config_reports_missing_file() {
+        let paths = write_config_files("missing", &[("base.toml", DEFAULT_CONFIG_TOML)]);
+        let missing = paths[0].with_file_name("absent.toml");
+
+        let error = load_config(&[paths[0].clone(), missing.clone()])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("读取配置文件"));
+        assert!(error.contains(&missing.display().to_string()));
+
+        std::fs::remove_dir_all(paths[0].parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn expand_env_vars_replaces_set_variable() {
+        env::set_var("TAOLI_TEST_SET_VAR", "0xabc");
+        let expanded = expand_env_vars("private_key = \"${TAOLI_TEST_SET_VAR}\"").unwrap();
+        assert_eq!(expanded, "private_key = \"0xabc\"");
+    }
+
+    #[test]
+    fn expand_env_vars_rejects_unset_variable() {
+        env::remove_var("TAOLI_TEST_UNSET_VAR");
+        let error =
+            expand_env_vars("[account]\nprivate_key = \"${TAOLI_TEST_UNSET_VAR}\"").unwrap_err();
+        assert!(error.contains("第2行"));
+        assert!(error.contains("TAOLI_TEST_UNSET_VAR"));
+    }
+
+    #[test]
+    fn expand_env_vars_uses_default_when_unset_or_empty() {
+        env::remove_var("TAOLI_TEST_DEFAULT_UNSET");
+        env::set_var("TAOLI_TEST_DEFAULT_EMPTY", "");
+        env::set_var("TAOLI_TEST_DEFAULT_SET", "mainnet");
+
+        let config = "a = \"${TAOLI_TEST_DEFAULT_UNSET:-testnet}\"\n\
+                      b = \"${TAOLI_TEST_DEFAULT_EMPTY:-testnet}\"\n\
+                      c = \"${TAOLI_TEST_DEFAULT_SET:-testnet}\"";
+        assert_eq!(
+            expand_env_vars(config).unwrap(),
+            "a = \"testnet\"\nb = \"testnet\"\nc = \"mainnet\""
+        );
+    }
+
+    #[test]
+    fn expand_env_vars_skips_comments_outside_quotes() {
+        env::remove_var("TAOLI_TEST_COMMENT_VAR");
+        env::set_var("TAOLI_TEST_HASH_VAR", "value");
+
+        let config = "# private_key = \"${TAOLI_TEST_COMMENT_VAR}\"\n\
+                      leverage = 3 # 也可写成 ${TAOLI_TEST_COMMENT_VAR}\n\
+                      token = \"a#${TAOLI_TEST_HASH_VAR}\" # ${TAOLI_TEST_COMMENT_VAR}";
+        assert_eq!(
+            expand_env_vars(config).unwrap(),
+            "# private_key = \"${TAOLI_TEST_COMMENT_VAR}\"\n\
+             leverage = 3 # 也可写成 ${TAOLI_TEST_COMMENT_VAR}\n\
+             token = \"a#value\" # ${TAOLI_TEST_COMMENT_VAR}"
+        );
+    }
+
+    #[test]
+    fn expand_env_vars_escapes_quotes_and_backslashes() {
+        let raw = "pa\"ss\\word\nline";
+        env::set_var("TAOLI_TEST_QUOTE_VAR", raw);
+
+        let expanded = expand_env_vars("secret = \"${TAOLI_TEST_QUOTE_VAR}\"").unwrap();
+        let parsed: toml::Value = toml::from_str(&expanded).unwrap();
+        assert_eq!(parsed["secret"].as_str(), Some(raw));
+
+        // 单引号字符串无法转义，含单引号的值直接报错
+        env::set_var("TAOLI_TEST_SINGLE_QUOTE_VAR", "it's");
+        assert!(expand_env_vars("secret = '${TAOLI_TEST_SINGLE_QUOTE_VAR}'").is_err());
+    }
+
+    #[test]
+    fn expand_env_vars_keeps_bare_values_unquoted() {
+        env::set_var("TAOLI_TEST_LEVERAGE_VAR", "5");
+        let expanded = expand_env_vars("leverage = ${TAOLI_TEST_LEVERAGE_VAR}").unwrap();
+        let parsed: toml::Value = toml::from_str(&expanded).unwrap();
+        assert_eq!(parsed["leverage"].as_integer(), Some(5));
+    }
 }
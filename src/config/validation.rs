@@ -0,0 +1,590 @@
+#![allow(dead_code)]
+
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::OnceLock;
+
+use super::AppConfig;
+use crate::strategies::trading_window::TradingWindow;
+use crate::strategies::triangle::Exchange;
+
+/// 内置的默认配置，`init-config` 生成的文件和问题字段的建议值都来自这里
+pub const DEFAULT_CONFIG_TOML: &str = include_str!("../../configs/default.toml");
+
+static DEFAULT_TREE: OnceLock<Value> = OnceLock::new();
+
+/// 默认配置解析成的字段树
+fn default_tree() -> &'static Value {
+    DEFAULT_TREE
+        .get_or_init(|| toml::from_str(DEFAULT_CONFIG_TOML).expect("内置默认配置必须是合法的 TOML"))
+}
+
+/// 单个配置问题
+#[derive(Debug, Clone)]
+pub struct ConfigIssue {
+    pub field: String,              // 字段路径，如 grid.grid_count
+    pub problem: String,            // 问题描述
+    pub suggestion: Option<String>, // 建议写法，通常取自默认配置
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.problem)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "（建议: {}）", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// 配置校验失败，一次列出全部问题
+pub struct ConfigValidationError {
+    pub issues: Vec<ConfigIssue>,
+}
+
+impl fmt::Display for ConfigValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "配置校验失败，共{}个问题:", self.issues.len())?;
+        for issue in &self.issues {
+            write!(f, "\n  - {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+// main 返回错误时按 Debug 输出，这里同样输出逐行的问题列表
+impl fmt::Debug for ConfigValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for ConfigValidationError {}
+
+/// 配置校验结果，错误会阻止启动，警告只提示
+#[derive(Debug, Default)]
+pub struct ConfigReport {
+    pub errors: Vec<ConfigIssue>,
+    pub warnings: Vec<ConfigIssue>,
+}
+
+impl ConfigReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// 记录错误，建议值取默认配置中的同名字段
+    pub fn add_error(&mut self, field: &str, problem: impl Into<String>) {
+        let suggestion = suggested_value(field);
+        self.add_error_with(field, problem, suggestion);
+    }
+
+    pub fn add_error_with(
+        &mut self,
+        field: &str,
+        problem: impl Into<String>,
+        suggestion: Option<String>,
+    ) {
+        self.errors.push(ConfigIssue {
+            field: field.to_string(),
+            problem: problem.into(),
+            suggestion,
+        });
+    }
+
+    pub fn add_warning(
+        &mut self,
+        field: &str,
+        problem: impl Into<String>,
+        suggestion: Option<String>,
+    ) {
+        self.warnings.push(ConfigIssue {
+            field: field.to_string(),
+            problem: problem.into(),
+            suggestion,
+        });
+    }
+
+    /// 配置加载早于日志初始化，警告直接输出到标准错误
+    pub fn print_warnings(&self) {
+        for warning in &self.warnings {
+            eprintln!("⚠️ 配置警告 {}", warning);
+        }
+    }
+
+    pub fn merge(&mut self, other: ConfigReport) {
+        self.errors.extend(other.errors);
+        self.warnings.extend(other.warnings);
+    }
+
+    pub fn into_error(self) -> ConfigValidationError {
+        ConfigValidationError {
+            issues: self.errors,
+        }
+    }
+}
+
+/// 默认配置中字段的建议写法，如 `grid_count = 7`
+fn suggested_value(field: &str) -> Option<String> {
+    let value = lookup(default_tree(), field)?;
+    let key = field.rsplit('.').next().unwrap_or(field);
+    Some(match value {
+        Value::Object(_) => format!("添加 [{}] 段，可参考 init-config 生成的默认配置", field),
+        _ => format!("{} = {}", key, value),
+    })
+}
+
+fn lookup<'a>(tree: &'a Value, field: &str) -> Option<&'a Value> {
+    field
+        .split('.')
+        .try_fold(tree, |node, key| node.as_object()?.get(key))
+}
+
+/// 反序列化前的结构校验：缺失的必填字段、类型不符的字段和未知字段
+///
+/// `settings` 是合并了全部配置文件和环境变量后的字段树，以内置默认配置为模板逐个字段比对。
+pub fn check_schema(settings: &Value) -> ConfigReport {
+    let mut report = ConfigReport::default();
+    let known = known_fields();
+    match (default_tree().as_object(), settings.as_object()) {
+        (Some(defaults), Some(settings)) => {
+            check_table(defaults, settings, "", &known, &mut report);
+        }
+        (_, None) => report.add_error_with("配置", "配置内容为空或不是表结构", None),
+        (None, _) => {}
+    }
+    report
+}
+
+fn check_table(
+    defaults: &Map<String, Value>,
+    settings: &Map<String, Value>,
+    prefix: &str,
+    known: &BTreeSet<String>,
+    report: &mut ConfigReport,
+) {
+    for (key, expected) in defaults {
+        let field = join_path(prefix, key);
+        match (settings.get(key), expected) {
+            (None, _) => {
+                if is_required(&field) {
+                    report.add_error(&field, "缺少必填字段");
+                }
+            }
+            (Some(Value::Object(actual)), Value::Object(expected)) => {
+                check_table(expected, actual, &field, known, report);
+            }
+            (Some(actual), _) => {
+                if !is_compatible(expected, actual) {
+                    report.add_error(
+                        &field,
+                        format!(
+                            "类型错误，应为{}，实际为{} {}",
+                            describe_kind(expected),
+                            describe_kind(actual),
+                            actual
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    for (key, actual) in settings {
+        let field = join_path(prefix, key);
+        if defaults.contains_key(key) {
+            continue;
+        }
+        if !known.contains(&field) {
+            warn_unknown(&field, prefix, known, report);
+            continue;
+        }
+        // 多账户数组的每一项按 [account] 的字段检查拼写
+        if field == "accounts" {
+            for (index, account) in actual.as_array().into_iter().flatten().enumerate() {
+                for key in account
+                    .as_object()
+                    .into_iter()
+                    .flat_map(|account| account.keys())
+                {
+                    if !known.contains(&join_path("account", key))
+                        && !known.contains(&join_path("accounts", key))
+                    {
+                        let field = format!("accounts[{}].{}", index, key);
+                        warn_unknown(&field, "account", known, report);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn join_path(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+/// 从默认配置中去掉该字段后无法反序列化，说明结构体没有为它提供默认值
+fn is_required(field: &str) -> bool {
+    let mut probe = default_tree().clone();
+    let mut node = &mut probe;
+    let mut keys = field.split('.').peekable();
+    while let Some(key) = keys.next() {
+        let Some(table) = node.as_object_mut() else {
+            return false;
+        };
+        if keys.peek().is_none() {
+            table.remove(key);
+            break;
+        }
+        match table.get_mut(key) {
+            Some(child) => node = child,
+            None => return false,
+        }
+    }
+    serde_json::from_value::<AppConfig>(probe).is_err()
+}
+
+/// 配置加载时字符串会按需转换为数字和布尔值，这里只拦截无法转换的值
+fn is_compatible(expected: &Value, actual: &Value) -> bool {
+    match (expected, actual) {
+        (Value::Number(expected), Value::Number(actual)) => expected.is_f64() || actual.is_u64(),
+        (Value::Number(expected), Value::String(actual)) => {
+            if expected.is_f64() {
+                actual.trim().parse::<f64>().is_ok()
+            } else {
+                actual.trim().parse::<u64>().is_ok()
+            }
+        }
+        (Value::Bool(_), Value::Bool(_)) => true,
+        (Value::Bool(_), Value::String(actual)) => matches!(
+            actual.trim().to_lowercase().as_str(),
+            "true" | "false" | "on" | "off" | "yes" | "no" | "1" | "0"
+        ),
+        (Value::String(_), actual) => !actual.is_array() && !actual.is_object(),
+        (Value::Array(_), Value::Array(_)) => true,
+        (Value::Object(_), Value::Object(_)) => true,
+        _ => false,
+    }
+}
+
+fn describe_kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "空值",
+        Value::Bool(_) => "布尔值",
+        Value::Number(number) if number.is_f64() => "小数",
+        Value::Number(number) if number.is_i64() && !number.is_u64() => "负整数",
+        Value::Number(_) => "非负整数",
+        Value::String(_) => "字符串",
+        Value::Array(_) => "数组",
+        Value::Object(_) => "表",
+    }
+}
+
+/// 未知字段不会被读取，多半是拼写错误，给出同一分组中最接近的字段名
+fn warn_unknown(field: &str, section: &str, known: &BTreeSet<String>, report: &mut ConfigReport) {
+    let key = field.rsplit('.').next().unwrap_or(field);
+    let closest = known
+        .iter()
+        .filter_map(|candidate| {
+            let (candidate_section, candidate_key) = candidate
+                .rsplit_once('.')
+                .unwrap_or(("", candidate.as_str()));
+            (candidate_section == section).then_some(candidate_key)
+        })
+        .map(|candidate| (edit_distance(key, candidate), candidate))
+        .filter(|(distance, _)| *distance <= 2)
+        .min();
+    report.add_warning(
+        field,
+        "未知字段，将被忽略",
+        closest.map(|(_, candidate)| format!("是否想写 {}", candidate)),
+    );
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// 默认配置中出现过的全部字段，包括注释掉的可选字段（如 `# expected_price_min = 0.5`）
+fn known_fields() -> BTreeSet<String> {
+    let mut fields = BTreeSet::new();
+    let mut section = String::new();
+    for line in DEFAULT_CONFIG_TOML.lines() {
+        let line = line.trim_start_matches('#').trim();
+        if let Some(name) = line
+            .strip_prefix("[[")
+            .and_then(|rest| rest.strip_suffix("]]"))
+            .or_else(|| {
+                line.strip_prefix('[')
+                    .and_then(|rest| rest.strip_suffix(']'))
+            })
+        {
+            section = name.trim().to_string();
+            fields.insert(section.clone());
+            continue;
+        }
+        let Some((key, _)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        if !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            fields.insert(join_path(&section, key));
+        }
+    }
+    fields
+}
+
+/// 反序列化后的语义校验，收集全部取值范围和组合问题
+pub fn check_semantics(config: &AppConfig) -> ConfigReport {
+    let mut report = ConfigReport::default();
+    let grid = &config.grid;
+
+    // 无法识别的语言按 auto 处理，只提示
+    if config.language.trim() != "auto"
+        && crate::i18n::Language::from_code(&config.language).is_none()
+    {
+        report.add_warning(
+            "language",
+            format!("无法识别的日志语言 \"{}\"，将按 auto 处理", config.language),
+            suggested_value("language"),
+        );
+    }
+
+    if grid.trading_asset.trim().is_empty() {
+        report.add_error("grid.trading_asset", "交易币种不能为空");
+    }
+    if grid.total_capital <= 0.0 {
+        report.add_error("grid.total_capital", "总资金必须大于0");
+    }
+    if grid.trade_amount <= 0.0 {
+        report.add_error("grid.trade_amount", "每格交易金额必须大于0");
+    } else if grid.total_capital < grid.trade_amount {
+        report.add_error_with(
+            "grid.trade_amount",
+            format!(
+                "每格交易金额({:.2})不能大于总资金({:.2})",
+                grid.trade_amount, grid.total_capital
+            ),
+            Some(format!("trade_amount <= {:.2}", grid.total_capital)),
+        );
+    }
+    if grid.max_position <= 0.0 {
+        report.add_error("grid.max_position", "最大持仓必须大于0");
+    }
+    if grid.grid_count == 0 {
+        report.add_error("grid.grid_count", "网格数量必须大于0");
+    }
+    if grid.min_grid_spacing <= 0.0 {
+        report.add_error("grid.min_grid_spacing", "最小网格间距必须大于0");
+    }
+    if grid.max_grid_spacing <= grid.min_grid_spacing {
+        report.add_error("grid.max_grid_spacing", "最大网格间距必须大于最小网格间距");
+    }
+
+    for (field, value) in [
+        ("grid.grid_lower_bound_pct", grid.grid_lower_bound_pct),
+        ("grid.grid_upper_bound_pct", grid.grid_upper_bound_pct),
+    ] {
+        if value <= 0.0 || value >= 1.0 {
+            report.add_error(field, format!("必须在(0,1)之间，当前为{}", value));
+        }
+    }
+    for (field, value) in [
+        ("grid.max_drawdown", grid.max_drawdown),
+        ("grid.max_single_loss", grid.max_single_loss),
+        ("grid.max_daily_loss", grid.max_daily_loss),
+        ("grid.margin_usage_threshold", grid.margin_usage_threshold),
+    ] {
+        if value <= 0.0 || value > 1.0 {
+            report.add_error(field, format!("必须在(0,1]之间，当前为{}", value));
+        }
+    }
+
+    if grid.leverage == 0 || grid.leverage > 100 {
+        report.add_error(
+            "grid.leverage",
+            format!("杠杆倍数必须在1-100之间，当前为{}", grid.leverage),
+        );
+    }
+    if grid.check_interval == 0 {
+        report.add_error("grid.check_interval", "检查间隔必须大于0秒");
+    }
+    if grid.kline_intervals.is_empty() {
+        report.add_error("grid.kline_intervals", "至少需要配置一个K线周期");
+    }
+
+    for (field, value, allowed) in [
+        (
+            "grid.grid_mode",
+            &grid.grid_mode,
+            &["normal", "reduce_only", "accumulate"][..],
+        ),
+        (
+            "grid.profit_mode",
+            &grid.profit_mode,
+            &["compound", "fixed_capital", "withdraw_to_base"][..],
+        ),
+        (
+            "grid.spacing_mode",
+            &grid.spacing_mode,
+            &["fixed", "atr"][..],
+        ),
+        (
+            "grid.grid_layout",
+            &grid.grid_layout,
+            &["geometric", "arithmetic", "spread"][..],
+        ),
+        (
+            "grid.position_sizing",
+            &grid.position_sizing,
+            &["adaptive", "equal", "pyramid", "reverse_pyramid"][..],
+        ),
+        (
+            "grid.grid_center_mode",
+            &grid.grid_center_mode,
+            &["fixed", "short_ma", "long_ma"][..],
+        ),
+        (
+            "grid.grid_breakout_action",
+            &grid.grid_breakout_action,
+            &["rebuild", "pause", "alert"][..],
+        ),
+        (
+            "grid.holding_timeout_close_mode",
+            &grid.holding_timeout_close_mode,
+            &["market", "limit"][..],
+        ),
+        (
+            "grid.post_only_reject_mode",
+            &grid.post_only_reject_mode,
+            &["reprice", "skip"][..],
+        ),
+        (
+            "grid.state_remote_backend",
+            &grid.state_remote_backend,
+            &["none", "http", "s3"][..],
+        ),
+    ] {
+        if !allowed.contains(&value.as_str()) {
+            report.add_error(
+                field,
+                format!("不支持的取值 \"{}\"，可选: {}", value, allowed.join("/")),
+            );
+        }
+    }
+
+    for spec in &grid.trading_windows {
+        if let Err(e) = TradingWindow::parse(spec) {
+            report.add_error("grid.trading_windows", e.to_string());
+        }
+    }
+
+    if !(1..=5).contains(&config.alert.min_severity) {
+        report.add_error(
+            "alert.min_severity",
+            format!("告警级别必须在1-5之间，当前为{}", config.alert.min_severity),
+        );
+    }
+    if config.metrics.enabled
+        && config.control.enabled
+        && config.metrics.port == config.control.port
+        && config.metrics.bind_address == config.control.bind_address
+    {
+        report.add_error(
+            "control.port",
+            format!(
+                "与 metrics.port 使用了同一个监听端口 {}",
+                config.control.port
+            ),
+        );
+    }
+
+    // 现货套利的交易所配置只在运行 spot 子命令时使用，这里只提示
+    let exchanges = config.spot.exchange_list();
+    if exchanges.len() < 2 {
+        report.add_warning(
+            "spot.exchanges",
+            format!("现货跨所套利至少需要两家交易所，当前为 {:?}", exchanges),
+            suggested_value("spot.exchanges"),
+        );
+    }
+    for exchange in &exchanges {
+        if Exchange::from_code(exchange).is_err() {
+            report.add_warning(
+                "spot.exchanges",
+                format!("不支持的交易所 \"{}\"，可选: binance/okx/bybit", exchange),
+                None,
+            );
+        }
+    }
+    for exchange in config
+        .spot
+        .fee_rates
+        .keys()
+        .chain(config.spot.withdrawal_delays.keys())
+        .chain(config.spot.balances.keys())
+    {
+        if !exchanges.contains(exchange) {
+            report.add_warning(
+                "spot",
+                format!(
+                    "交易所 \"{}\" 不在 exchanges 列表中，其配置不会生效",
+                    exchange
+                ),
+                None,
+            );
+        }
+    }
+
+    // 三角套利交易对过滤只在自动发现路径时使用
+    if config.triangle.min_quote_volume_24h < 0.0 {
+        report.add_error(
+            "triangle.min_quote_volume_24h",
+            format!(
+                "不能为负数，当前为 {}",
+                config.triangle.min_quote_volume_24h
+            ),
+        );
+    }
+    for pair in &config.triangle.whitelist_pairs {
+        if config.triangle.blacklist_pairs.contains(pair) {
+            report.add_warning(
+                "triangle.whitelist_pairs",
+                format!("交易对 \"{}\" 同时在黑名单中，将被排除", pair),
+                None,
+            );
+        }
+    }
+
+    let mut names = BTreeSet::new();
+    for (index, account) in config.accounts.iter().enumerate() {
+        if !account.name.is_empty() && !names.insert(account.name.as_str()) {
+            report.add_error_with(
+                &format!("accounts[{}].name", index),
+                format!("账户名称 \"{}\" 重复，状态文件会互相覆盖", account.name),
+                Some("为每个账户设置不同的 name".to_string()),
+            );
+        }
+    }
+
+    report
+}
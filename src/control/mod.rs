@@ -0,0 +1,370 @@
+#![allow(dead_code)]
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use log::{error, info, warn};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::config::ControlConfig;
+
+/// 等待主循环处理指令的最长时间（清仓可能需要多次重试）
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 运行时控制指令
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlCommand {
+    Status,    // 查询状态
+    Pause,     // 暂停开新仓
+    Resume,    // 恢复交易
+    Rebalance, // 立即重平衡
+    Flatten,   // 撤销所有订单并清仓
+    Withdrawn, // 确认待提取利润已转出
+}
+
+impl ControlCommand {
+    pub fn from_path(path: &str) -> Option<Self> {
+        match path.trim_matches('/') {
+            "status" => Some(ControlCommand::Status),
+            "pause" => Some(ControlCommand::Pause),
+            "resume" => Some(ControlCommand::Resume),
+            "rebalance" => Some(ControlCommand::Rebalance),
+            "flatten" => Some(ControlCommand::Flatten),
+            "withdrawn" => Some(ControlCommand::Withdrawn),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ControlCommand::Status => "查询状态",
+            ControlCommand::Pause => "暂停交易",
+            ControlCommand::Resume => "恢复交易",
+            ControlCommand::Rebalance => "强制重平衡",
+            ControlCommand::Flatten => "强制清仓",
+            ControlCommand::Withdrawn => "确认利润已提取",
+        }
+    }
+
+    pub fn as_english(&self) -> &'static str {
+        match self {
+            ControlCommand::Status => "Status",
+            ControlCommand::Pause => "Pause",
+            ControlCommand::Resume => "Resume",
+            ControlCommand::Rebalance => "Rebalance",
+            ControlCommand::Flatten => "Flatten",
+            ControlCommand::Withdrawn => "Withdrawn",
+        }
+    }
+
+    /// 查询状态以外的指令会改变策略行为，只接受 POST
+    fn is_mutating(&self) -> bool {
+        !matches!(self, ControlCommand::Status)
+    }
+}
+
+crate::impl_bilingual!(ControlCommand);
+
+/// 指令执行结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ControlResponse {
+    pub ok: bool,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl ControlResponse {
+    pub fn ok(message: impl Into<String>) -> Self {
+        Self {
+            ok: true,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    pub fn with_data(message: impl Into<String>, data: serde_json::Value) -> Self {
+        Self {
+            ok: true,
+            message: message.into(),
+            data: Some(data),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+/// 发往策略主循环的指令，主循环处理后通过 `reply` 返回结果
+#[derive(Debug)]
+pub struct ControlRequest {
+    pub command: ControlCommand,
+    pub reply: oneshot::Sender<ControlResponse>,
+}
+
+/// 创建指令通道，发送端交给控制服务，接收端交给策略主循环
+pub fn control_channel() -> (mpsc::Sender<ControlRequest>, mpsc::Receiver<ControlRequest>) {
+    mpsc::channel(16)
+}
+
+/// 启动本地控制接口（后台任务），配置未启用时直接返回
+///
+/// `accounts` 为账户名称到指令通道的映射；只有一个账户时可省略 `?account=` 参数。
+pub fn spawn_control_server(
+    config: &ControlConfig,
+    accounts: HashMap<String, mpsc::Sender<ControlRequest>>,
+) {
+    if !config.enabled || accounts.is_empty() {
+        return;
+    }
+
+    let addr: SocketAddr = match format!("{}:{}", config.bind_address, config.port).parse() {
+        Ok(addr) => addr,
+        Err(e) => {
+            warn!(
+                "⚠️ 控制接口地址无效: {}:{} ({:?})，不启动控制接口",
+                config.bind_address, config.port, e
+            );
+            return;
+        }
+    };
+
+    let token = std::env::var(&config.token_env)
+        .ok()
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty());
+    if !bind_allowed(&addr, token.as_deref()) {
+        error!(
+            "❌ 控制接口监听在非回环地址 {} 但未设置 {} 环境变量，拒绝启动控制接口",
+            addr, config.token_env
+        );
+        return;
+    }
+    if token.is_none() {
+        warn!(
+            "⚠️ 未设置 {}，控制接口不做认证，仅限本机访问",
+            config.token_env
+        );
+    }
+
+    let accounts = Arc::new(accounts);
+    let token = Arc::new(token);
+    tokio::spawn(async move {
+        let make_svc = make_service_fn(move |_conn| {
+            let accounts = accounts.clone();
+            let token = token.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let accounts = accounts.clone();
+                    let token = token.clone();
+                    async move {
+                        Ok::<_, Infallible>(handle_request(req, &accounts, token.as_deref()).await)
+                    }
+                }))
+            }
+        });
+
+        let server = match Server::try_bind(&addr) {
+            Ok(builder) => builder.serve(make_svc),
+            Err(e) => {
+                error!("❌ 控制接口绑定 {} 失败: {:?}", addr, e);
+                return;
+            }
+        };
+
+        info!(
+            "🎛️ 控制接口已启动: http://{} (GET /status, POST /pause /resume /rebalance /flatten /withdrawn)",
+            addr
+        );
+        if let Err(e) = server.await {
+            error!("❌ 控制接口异常退出: {:?}", e);
+        }
+    });
+}
+
+/// 回环地址允许无认证访问，其他地址必须配置 token
+fn bind_allowed(addr: &SocketAddr, token: Option<&str>) -> bool {
+    addr.ip().is_loopback() || token.is_some()
+}
+
+/// 未配置 token 时放行；配置后要求 `Authorization: Bearer <token>`
+fn authorized(req: &Request<Body>, token: Option<&str>) -> bool {
+    let Some(expected) = token else {
+        return true;
+    };
+    req.headers()
+        .get(hyper::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|provided| constant_time_eq(provided.trim().as_bytes(), expected.as_bytes()))
+        .unwrap_or(false)
+}
+
+/// 逐字节比较全部内容，避免通过响应耗时猜测 token
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+async fn handle_request(
+    req: Request<Body>,
+    accounts: &HashMap<String, mpsc::Sender<ControlRequest>>,
+    token: Option<&str>,
+) -> Response<Body> {
+    if !authorized(&req, token) {
+        let mut response = json_response(
+            StatusCode::UNAUTHORIZED,
+            &ControlResponse::error("未授权，请携带 Authorization: Bearer <token>"),
+        );
+        response.headers_mut().insert(
+            hyper::header::WWW_AUTHENTICATE,
+            hyper::header::HeaderValue::from_static("Bearer"),
+        );
+        return response;
+    }
+
+    let Some(command) = ControlCommand::from_path(req.uri().path()) else {
+        return json_response(StatusCode::NOT_FOUND, &ControlResponse::error("未知指令"));
+    };
+
+    let allowed = if command.is_mutating() {
+        req.method() == Method::POST
+    } else {
+        req.method() == Method::GET || req.method() == Method::POST
+    };
+    if !allowed {
+        return json_response(
+            StatusCode::METHOD_NOT_ALLOWED,
+            &ControlResponse::error("请求方法不支持，修改类指令需使用 POST"),
+        );
+    }
+
+    let account = req.uri().query().and_then(|query| {
+        query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("account="))
+            .map(str::to_string)
+    });
+    let sender = match account {
+        Some(name) => accounts.get(&name),
+        None if accounts.len() == 1 => accounts.values().next(),
+        None => None,
+    };
+    let Some(sender) = sender else {
+        let mut names: Vec<&String> = accounts.keys().collect();
+        names.sort();
+        return json_response(
+            StatusCode::BAD_REQUEST,
+            &ControlResponse::error(format!("请通过 ?account= 指定账户，可选: {:?}", names)),
+        );
+    };
+
+    info!("🎛️ 收到控制指令: {}", command.as_str());
+    let (reply, response) = oneshot::channel();
+    if sender
+        .send(ControlRequest { command, reply })
+        .await
+        .is_err()
+    {
+        return json_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            &ControlResponse::error("策略主循环已退出"),
+        );
+    }
+
+    match tokio::time::timeout(COMMAND_TIMEOUT, response).await {
+        Ok(Ok(result)) => {
+            let status = if result.ok {
+                StatusCode::OK
+            } else {
+                StatusCode::CONFLICT
+            };
+            json_response(status, &result)
+        }
+        Ok(Err(_)) => json_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            &ControlResponse::error("策略主循环未返回结果"),
+        ),
+        Err(_) => json_response(
+            StatusCode::GATEWAY_TIMEOUT,
+            &ControlResponse::error("等待策略主循环处理指令超时"),
+        ),
+    }
+}
+
+fn json_response(status: StatusCode, body: &ControlResponse) -> Response<Body> {
+    let body = serde_json::to_string(body).unwrap_or_else(|_| "{}".to_string());
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static("application/json; charset=utf-8"),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(path: &str, authorization: Option<&str>) -> Request<Body> {
+        let mut builder = Request::builder().method(Method::POST).uri(path);
+        if let Some(value) = authorization {
+            builder = builder.header(hyper::header::AUTHORIZATION, value);
+        }
+        builder.body(Body::empty()).expect("构造请求失败")
+    }
+
+    #[test]
+    fn non_loopback_bind_requires_token() {
+        let loopback: SocketAddr = "127.0.0.1:9101".parse().unwrap();
+        let loopback_v6: SocketAddr = "[::1]:9101".parse().unwrap();
+        let public: SocketAddr = "0.0.0.0:9101".parse().unwrap();
+
+        assert!(bind_allowed(&loopback, None));
+        assert!(bind_allowed(&loopback_v6, None));
+        assert!(!bind_allowed(&public, None));
+        assert!(bind_allowed(&public, Some("secret")));
+    }
+
+    #[test]
+    fn bearer_token_is_checked_when_configured() {
+        assert!(authorized(&request("/flatten", None), None));
+
+        let token = Some("secret");
+        assert!(authorized(
+            &request("/flatten", Some("Bearer secret")),
+            token
+        ));
+        assert!(!authorized(&request("/flatten", None), token));
+        assert!(!authorized(
+            &request("/flatten", Some("Bearer wrong")),
+            token
+        ));
+        assert!(!authorized(
+            &request("/flatten", Some("Bearer secret2")),
+            token
+        ));
+        assert!(!authorized(&request("/flatten", Some("secret")), token));
+    }
+
+    #[tokio::test]
+    async fn unauthorized_request_is_rejected_before_dispatch() {
+        let (sender, mut receiver) = control_channel();
+        let accounts = HashMap::from([("main".to_string(), sender)]);
+
+        let response = handle_request(request("/flatten", None), &accounts, Some("secret")).await;
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,131 @@
+#![allow(dead_code)]
+
+use std::sync::OnceLock;
+
+/// 日志与输出语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Chinese, // 中文
+    English, // 英文
+}
+
+impl Language {
+    /// 解析语言代码（zh/zh_CN.UTF-8/en/en_US.UTF-8 等），无法识别时返回 None
+    pub fn from_code(code: &str) -> Option<Self> {
+        let code = code.trim().to_lowercase();
+        if code.starts_with("zh") {
+            Some(Language::Chinese)
+        } else if code.starts_with("en") {
+            Some(Language::English)
+        } else {
+            None
+        }
+    }
+
+    /// 根据配置确定语言：配置为 auto 或留空时读取 `LANG` 环境变量，默认中文
+    pub fn detect(configured: &str) -> Self {
+        if let Some(lang) = Self::from_code(configured) {
+            return lang;
+        }
+        std::env::var("LANG")
+            .ok()
+            .and_then(|lang| Self::from_code(&lang))
+            .unwrap_or(Language::Chinese)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Language::Chinese => "中文",
+            Language::English => "英文",
+        }
+    }
+
+    pub fn as_english(&self) -> &'static str {
+        match self {
+            Language::Chinese => "Chinese",
+            Language::English => "English",
+        }
+    }
+}
+
+static LANGUAGE: OnceLock<Language> = OnceLock::new();
+
+/// 设置全局语言，只在启动时生效一次
+pub fn set_language(lang: Language) {
+    let _ = LANGUAGE.set(lang);
+}
+
+/// 当前全局语言，未设置时按 `LANG` 环境变量检测
+pub fn current_language() -> Language {
+    *LANGUAGE.get_or_init(|| Language::detect("auto"))
+}
+
+/// 按当前语言选择文本
+pub fn tr(zh: &'static str, en: &'static str) -> &'static str {
+    match current_language() {
+        Language::Chinese => zh,
+        Language::English => en,
+    }
+}
+
+/// 具有中英文名称的类型（已有 `as_str()/as_english()` 的枚举）
+pub trait Bilingual {
+    fn chinese_name(&self) -> &'static str;
+
+    fn english_name(&self) -> &'static str;
+
+    /// 按指定语言显示
+    fn display(&self, lang: Language) -> &'static str {
+        match lang {
+            Language::Chinese => self.chinese_name(),
+            Language::English => self.english_name(),
+        }
+    }
+
+    /// 按当前全局语言显示
+    fn localized(&self) -> &'static str {
+        self.display(current_language())
+    }
+}
+
+/// 为已实现 `as_str()/as_english()` 的类型实现 `Bilingual`
+#[macro_export]
+macro_rules! impl_bilingual {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl $crate::i18n::Bilingual for $ty {
+                fn chinese_name(&self) -> &'static str {
+                    self.as_str()
+                }
+
+                fn english_name(&self) -> &'static str {
+                    self.as_english()
+                }
+            }
+        )+
+    };
+}
+
+/// 按当前语言输出日志：`tr_log!(info, "中文 {}", "English {}", arg)`
+#[macro_export]
+macro_rules! tr_log {
+    ($level:ident, $zh:literal, $en:literal $(, $arg:expr)* $(,)?) => {
+        match $crate::i18n::current_language() {
+            $crate::i18n::Language::Chinese => log::$level!($zh $(, $arg)*),
+            $crate::i18n::Language::English => log::$level!($en $(, $arg)*),
+        }
+    };
+}
+
+/// 按当前语言输出到标准输出：`tr_println!("中文 {}", "English {}", arg)`
+#[macro_export]
+macro_rules! tr_println {
+    ($zh:literal, $en:literal $(, $arg:expr)* $(,)?) => {
+        match $crate::i18n::current_language() {
+            $crate::i18n::Language::Chinese => println!($zh $(, $arg)*),
+            $crate::i18n::Language::English => println!($en $(, $arg)*),
+        }
+    };
+}
+
+impl_bilingual!(Language);
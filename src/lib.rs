@@ -1,2 +1,6 @@
 pub mod config;
+pub mod control;
+pub mod i18n;
+pub mod metrics;
+pub mod notify;
 pub mod strategies;
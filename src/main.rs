@@ -1,4 +1,8 @@
 mod config;
+mod control;
+mod i18n;
+mod metrics;
+mod notify;
 mod strategies;
 
 use clap::{Parser, Subcommand};
@@ -7,9 +11,13 @@ use std::path::PathBuf;
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
-    /// 配置文件路径 (可选，默认使用当前目录下的config.toml)
+    /// 配置文件路径 (可选，默认使用当前目录下的config.toml)，可多次传入，后者覆盖前者
     #[arg(short, long)]
-    config: Option<PathBuf>,
+    config: Vec<PathBuf>,
+
+    /// 模拟下单模式：走完整策略逻辑但不发送真实订单
+    #[arg(long, global = true)]
+    dry_run: bool,
 
     #[command(subcommand)]
     command: Commands,
@@ -24,34 +32,131 @@ enum Commands {
     /// 三角套利
     Triangle,
     /// 网格交易
-    Grid,
-    /// 复制默认配置文件到当前目录
+    Grid {
+        /// 把实时价格和成交推送录制到该文件 (JSONL)，覆盖配置中的 record_file
+        #[arg(long)]
+        record: Option<PathBuf>,
+    },
+    /// 以模拟下单模式回放录制的行情，复现实盘的价格和成交序列
+    Replay {
+        /// 录制文件路径 (由 grid --record 或配置 record_file 生成)
+        file: PathBuf,
+        /// 回放倍速，1.0 为按录制时的时间间隔
+        #[arg(long, default_value_t = 1.0)]
+        speed: f64,
+        /// 同时回放录制的成交事件（默认只回放价格，成交由模拟执行器撮合）
+        #[arg(long)]
+        with_fills: bool,
+    },
+    /// 生成默认配置文件到当前目录（内置 configs/default.toml）
     InitConfig,
+    /// 只校验配置、钱包和交易所连通性，不运行策略；有错误时以非0退出码退出
+    Validate,
+    /// 从网格状态文件导出交易流水
+    Trades {
+        /// 导出文件路径 (可选，默认为当前目录下的 trades.<格式>)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// 导出格式：csv 或 json
+        #[arg(short, long, default_value = "csv")]
+        format: String,
+        /// 状态文件路径 (可选，默认使用配置中的状态目录下的 grid_state.json)
+        #[arg(long)]
+        state_file: Option<PathBuf>,
+    },
+    /// 查询持久化的历史风险事件
+    RiskLog {
+        /// 日志文件路径 (可选，默认使用配置中的状态目录下的 risk_events.jsonl)
+        #[arg(long)]
+        file: Option<PathBuf>,
+        /// 起始时间 (RFC3339 或 YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
+        /// 结束时间 (RFC3339 或 YYYY-MM-DD)
+        #[arg(long)]
+        until: Option<String>,
+        /// 事件类型，如 MarginInsufficient 或 margin_insufficient
+        #[arg(long = "type")]
+        event_type: Option<String>,
+        /// 最低严重程度 (1-5)
+        #[arg(long)]
+        min_severity: Option<u8>,
+        /// 只显示最近 N 条
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// 在历史价格上搜索网格参数并输出回测表现最好的组合
+    Optimize {
+        /// 历史价格 CSV（表头含 close 或 price 列，或第二列为价格）
+        #[arg(short, long)]
+        data: PathBuf,
+        /// 结果输出路径 (JSON)
+        #[arg(short, long, default_value = "optimize_results.json")]
+        output: PathBuf,
+        /// 搜索方式：grid 或 random
+        #[arg(short, long, default_value = "grid")]
+        method: String,
+        /// 最多回测的参数组合数
+        #[arg(long, default_value_t = 100)]
+        max_iterations: usize,
+        /// 输出排名前 N 的组合
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+        /// 排序指标：roi、sharpe 或 drawdown
+        #[arg(long, default_value = "roi")]
+        rank_by: String,
+        /// 网格间距取值，如 0.002,0.004 或 0.001:0.01:0.001 (可选，默认使用配置值)
+        #[arg(long)]
+        min_grid_spacing: Option<String>,
+        /// 网格数量取值 (可选，默认使用配置值)
+        #[arg(long)]
+        grid_count: Option<String>,
+        /// 每格交易金额取值 (可选，默认使用配置值)
+        #[arg(long)]
+        trade_amount: Option<String>,
+        /// 随机搜索的种子 (可选，默认使用当前时间)
+        #[arg(long)]
+        seed: Option<u64>,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
-    let config_path = cli.config.unwrap_or_else(|| PathBuf::from("config.toml"));
+    let config_paths = if cli.config.is_empty() {
+        vec![PathBuf::from("config.toml")]
+    } else {
+        cli.config
+    };
     let app_config = if matches!(cli.command, Commands::InitConfig) {
         None
     } else {
-        Some(config::load_config(&config_path)?)
+        Some(config::load_config(&config_paths)?)
     };
 
+    // 设置日志语言：配置 language 字段优先，auto 时读取 LANG 环境变量
+    let language = app_config
+        .as_ref()
+        .map(|config| config.language.as_str())
+        .unwrap_or("auto");
+    i18n::set_language(i18n::Language::detect(language));
+
     match cli.command {
         Commands::Spot => {
             let config = app_config.unwrap();
-            println!(
-                "执行现货套利: 交易所1={}, 交易所2={}, 交易对={}",
-                config.spot.exchange1, config.spot.exchange2, config.spot.symbol
+            tr_println!(
+                "执行现货套利: 交易所={}, 交易对={}",
+                "Running spot arbitrage: exchanges={}, symbol={}",
+                config.spot.exchange_list().join(","),
+                config.spot.symbol
             );
-            // TODO: 实现现货套利逻辑
+            strategies::spot::run_spot_strategy(&config.spot).await?;
         }
         Commands::Futures => {
             let config = app_config.unwrap();
-            println!(
+            tr_println!(
                 "执行期现套利: 现货交易所={}, 期货交易所={}, 交易对={}",
+                "Running futures-spot arbitrage: spot exchange={}, futures exchange={}, symbol={}",
                 config.futures.spot_exchange,
                 config.futures.futures_exchange,
                 config.futures.symbol
@@ -60,28 +165,208 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
         Commands::Triangle => {
             let config = app_config.unwrap();
-            println!(
+            tr_println!(
                 "执行三角套利: 交易所={}, 交易对1={}, 交易对2={}, 交易对3={}",
+                "Running triangular arbitrage: exchange={}, pair1={}, pair2={}, pair3={}",
                 config.triangle.exchange,
                 config.triangle.pair1,
                 config.triangle.pair2,
                 config.triangle.pair3
             );
-            // TODO: 实现三角套利逻辑
+            // 设置了基准币时自动发现路径并扫描收益
+            // TODO: 实现手动指定交易对的三角套利逻辑
+            if !config.triangle.base_currency.is_empty() {
+                strategies::triangle::run_triangle_scanner(&config.triangle).await?;
+            }
+        }
+        Commands::Grid { record } => {
+            let mut config = app_config.unwrap();
+            if cli.dry_run {
+                config.grid.dry_run = true;
+            }
+            if let Some(record) = record {
+                config.grid.record_file = record.to_string_lossy().into_owned();
+            }
+            strategies::grid::run_grid_strategy(config, None).await?;
+        }
+        Commands::Replay {
+            file,
+            speed,
+            with_fills,
+        } => {
+            let config = app_config.unwrap();
+            let options = strategies::replay::ReplayOptions {
+                path: file,
+                speed,
+                include_fills: with_fills,
+            };
+            strategies::grid::run_grid_strategy(config, Some(options)).await?;
+        }
+        Commands::Validate => {
+            let mut config = app_config.unwrap();
+            if cli.dry_run {
+                config.grid.dry_run = true;
+            }
+            let report = strategies::grid::validate_deployment(&config).await;
+            report.print();
+            if !report.is_ok() {
+                return Err(format!(
+                    "配置校验未通过: {} 项错误",
+                    report.count(strategies::grid::CheckStatus::Failed)
+                )
+                .into());
+            }
+        }
+        Commands::Trades {
+            output,
+            format,
+            state_file,
+        } => {
+            use strategies::export::{export_trades, load_performance_history, ExportFormat};
+
+            let config = app_config.unwrap();
+            let format = ExportFormat::from_code(&format)
+                .ok_or_else(|| format!("不支持的导出格式: {}（可选 csv/json）", format))?;
+            let state_file = state_file.unwrap_or_else(|| {
+                PathBuf::from(strategies::grid::state_file_path(
+                    &config.grid,
+                    "grid_state.json",
+                ))
+            });
+            let output =
+                output.unwrap_or_else(|| PathBuf::from(format!("trades.{}", format.extension())));
+
+            let records = load_performance_history(&state_file)?;
+            let count = export_trades(&records, &output, format)?;
+            tr_println!(
+                "已导出{}条交易流水到: {}",
+                "Exported {} trades to: {}",
+                count,
+                output.display()
+            );
         }
-        Commands::Grid => {
+        Commands::RiskLog {
+            file,
+            since,
+            until,
+            event_type,
+            min_severity,
+            limit,
+        } => {
+            use strategies::risk_log::{
+                format_entry, load_risk_events, parse_time_filter, RiskLogFilter,
+            };
+
             let config = app_config.unwrap();
-            strategies::grid::run_grid_strategy(config).await?;
+            let file = file.unwrap_or_else(|| {
+                PathBuf::from(strategies::grid::state_file_path(
+                    &config.grid,
+                    "risk_events.jsonl",
+                ))
+            });
+            let filter = RiskLogFilter {
+                since: since.as_deref().map(parse_time_filter).transpose()?,
+                until: until.as_deref().map(parse_time_filter).transpose()?,
+                event_type,
+                min_severity,
+            };
+
+            let entries = load_risk_events(&file, &filter)?;
+            let skip = limit.map_or(0, |limit| entries.len().saturating_sub(limit));
+            for entry in &entries[skip..] {
+                println!("{}", format_entry(entry));
+            }
+            tr_println!(
+                "共{}条风险事件，显示{}条",
+                "{} risk events matched, {} shown",
+                entries.len(),
+                entries.len() - skip
+            );
+        }
+        Commands::Optimize {
+            data,
+            output,
+            method,
+            max_iterations,
+            top,
+            rank_by,
+            min_grid_spacing,
+            grid_count,
+            trade_amount,
+            seed,
+        } => {
+            use i18n::Bilingual;
+            use std::sync::Arc;
+            use strategies::backtest::load_price_history;
+            use strategies::optimize::{
+                default_seed, rank_results, run_optimization, write_results, RankMetric,
+                SearchMethod, SearchSpace,
+            };
+
+            env_logger::init();
+
+            let config = app_config.unwrap();
+            let method = SearchMethod::from_code(&method)
+                .ok_or_else(|| format!("不支持的搜索方式: {}（可选 grid/random）", method))?;
+            let metric = RankMetric::from_code(&rank_by).ok_or_else(|| {
+                format!("不支持的排序指标: {}（可选 roi/sharpe/drawdown）", rank_by)
+            })?;
+            let space = SearchSpace::new(
+                &config.grid,
+                min_grid_spacing.as_deref(),
+                grid_count.as_deref(),
+                trade_amount.as_deref(),
+            )?;
+
+            let prices = load_price_history(&data)?;
+            let candidates =
+                space.candidates(method, max_iterations, seed.unwrap_or_else(default_seed));
+            let evaluated = candidates.len();
+            let results =
+                run_optimization(Arc::new(prices), Arc::new(config.grid), candidates).await?;
+            let ranked = rank_results(results, metric, top);
+            write_results(&output, method, metric, evaluated, &ranked)?;
+
+            tr_println!(
+                "{}完成，共评估{}组参数，按{}排名前{}的组合:",
+                "{} finished, evaluated {} parameter sets, ranked by {}, top {}:",
+                method.localized(),
+                evaluated,
+                metric.localized(),
+                ranked.len()
+            );
+            for (rank, result) in ranked.iter().enumerate() {
+                println!(
+                    "{:>2}. spacing={:.4} grid_count={} trade_amount={:.2} | ROI={:.2}% Sharpe={:.3} MDD={:.2}% trades={}",
+                    rank + 1,
+                    result.params.min_grid_spacing,
+                    result.params.grid_count,
+                    result.params.trade_amount,
+                    result.roi * 100.0,
+                    result.sharpe_ratio,
+                    result.max_drawdown * 100.0,
+                    result.total_trades
+                );
+            }
+            tr_println!("结果已写入: {}", "Results written to: {}", output.display());
         }
         Commands::InitConfig => {
             use std::fs;
-            let default_config_path = PathBuf::from("configs/default.toml");
+            // 写入编译时内置的 configs/default.toml，与当前版本的配置结构保持一致
             let target_config_path = PathBuf::from("config.toml");
             if target_config_path.exists() {
-                println!("配置文件已存在: {}", target_config_path.display());
+                tr_println!(
+                    "配置文件已存在: {}",
+                    "Config file already exists: {}",
+                    target_config_path.display()
+                );
             } else {
-                fs::copy(&default_config_path, &target_config_path)?;
-                println!("已复制默认配置文件到: {}", target_config_path.display());
+                fs::write(&target_config_path, config::DEFAULT_CONFIG_TOML)?;
+                tr_println!(
+                    "已生成默认配置文件: {}",
+                    "Generated default config: {}",
+                    target_config_path.display()
+                );
             }
         }
     }
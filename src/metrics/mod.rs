@@ -0,0 +1,193 @@
+#![allow(dead_code)]
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use log::{error, info, warn};
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+
+use crate::config::MetricsConfig;
+
+/// 对外暴露的指标快照，由策略主循环定期刷新
+#[derive(Debug, Clone, Default)]
+pub struct MetricsSnapshot {
+    pub trading_asset: String,
+    pub current_price: f64,
+    pub position_quantity: f64,
+    pub position_avg_price: f64,
+    pub available_funds: f64,
+    pub realized_profit: f64,
+    pub unrealized_pnl: f64,
+    pub active_buy_orders: usize,
+    pub active_sell_orders: usize,
+    pub connection_quality_score: f64,
+    pub last_margin_ratio: f64,
+    pub risk_events_total: u64,
+    pub critical_risk_events_total: u64,
+    pub trading_paused: bool,
+}
+
+/// 线程安全的指标注册表
+#[derive(Debug, Clone, Default)]
+pub struct MetricsRegistry {
+    snapshot: Arc<RwLock<MetricsSnapshot>>,
+}
+
+impl MetricsRegistry {
+    pub fn new(trading_asset: &str) -> Self {
+        Self {
+            snapshot: Arc::new(RwLock::new(MetricsSnapshot {
+                trading_asset: trading_asset.to_string(),
+                ..Default::default()
+            })),
+        }
+    }
+
+    /// 修改指标快照
+    pub fn update<F: FnOnce(&mut MetricsSnapshot)>(&self, f: F) {
+        let mut snapshot = match self.snapshot.write() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        f(&mut snapshot);
+    }
+
+    /// 记录一次风险事件
+    pub fn record_risk_event(&self, is_critical: bool) {
+        self.update(|s| {
+            s.risk_events_total += 1;
+            if is_critical {
+                s.critical_risk_events_total += 1;
+            }
+        });
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        match self.snapshot.read() {
+            Ok(guard) => guard.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// 生成 Prometheus 文本格式
+    pub fn render(&self) -> String {
+        let s = self.snapshot();
+        let label = format!("asset=\"{}\"", s.trading_asset.replace('"', "\\\""));
+        let mut out = String::new();
+
+        let gauges: [(&str, &str, f64); 11] = [
+            ("taoli_current_price", "当前价格", s.current_price),
+            (
+                "taoli_position_quantity",
+                "当前持仓数量",
+                s.position_quantity,
+            ),
+            ("taoli_position_avg_price", "持仓均价", s.position_avg_price),
+            ("taoli_available_funds", "可用资金", s.available_funds),
+            ("taoli_realized_profit", "已实现利润", s.realized_profit),
+            ("taoli_unrealized_pnl", "持仓未实现盈亏", s.unrealized_pnl),
+            (
+                "taoli_active_buy_orders",
+                "活跃买单数",
+                s.active_buy_orders as f64,
+            ),
+            (
+                "taoli_active_sell_orders",
+                "活跃卖单数",
+                s.active_sell_orders as f64,
+            ),
+            (
+                "taoli_connection_quality_score",
+                "连接质量评分(0-100)",
+                s.connection_quality_score,
+            ),
+            ("taoli_margin_ratio", "最近保证金率", s.last_margin_ratio),
+            (
+                "taoli_trading_paused",
+                "风险控制是否暂停交易(1=暂停)",
+                if s.trading_paused { 1.0 } else { 0.0 },
+            ),
+        ];
+
+        for (name, help, value) in gauges.iter() {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} gauge", name);
+            let _ = writeln!(out, "{}{{{}}} {}", name, label, value);
+        }
+
+        let _ = writeln!(out, "# HELP taoli_risk_events_total 风险事件计数");
+        let _ = writeln!(out, "# TYPE taoli_risk_events_total counter");
+        let _ = writeln!(
+            out,
+            "taoli_risk_events_total{{{},severity=\"all\"}} {}",
+            label, s.risk_events_total
+        );
+        let _ = writeln!(
+            out,
+            "taoli_risk_events_total{{{},severity=\"critical\"}} {}",
+            label, s.critical_risk_events_total
+        );
+
+        out
+    }
+}
+
+/// 启动 `/metrics` HTTP 端点（后台任务），配置未启用时直接返回
+pub fn spawn_metrics_server(config: &MetricsConfig, registry: MetricsRegistry) {
+    if !config.enabled {
+        return;
+    }
+
+    let addr: SocketAddr = match format!("{}:{}", config.bind_address, config.port).parse() {
+        Ok(addr) => addr,
+        Err(e) => {
+            warn!(
+                "⚠️ 指标服务地址无效: {}:{} ({:?})，不启动指标服务",
+                config.bind_address, config.port, e
+            );
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        let make_svc = make_service_fn(move |_conn| {
+            let registry = registry.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let registry = registry.clone();
+                    async move { Ok::<_, Infallible>(handle_request(req, &registry)) }
+                }))
+            }
+        });
+
+        let server = match Server::try_bind(&addr) {
+            Ok(builder) => builder.serve(make_svc),
+            Err(e) => {
+                error!("❌ 指标服务绑定 {} 失败: {:?}", addr, e);
+                return;
+            }
+        };
+
+        info!("📈 Prometheus 指标服务已启动: http://{}/metrics", addr);
+        if let Err(e) = server.await {
+            error!("❌ 指标服务异常退出: {:?}", e);
+        }
+    });
+}
+
+fn handle_request(req: Request<Body>, registry: &MetricsRegistry) -> Response<Body> {
+    if req.method() == Method::GET && req.uri().path() == "/metrics" {
+        let mut response = Response::new(Body::from(registry.render()));
+        response.headers_mut().insert(
+            hyper::header::CONTENT_TYPE,
+            hyper::header::HeaderValue::from_static("text/plain; version=0.0.4; charset=utf-8"),
+        );
+        response
+    } else {
+        let mut response = Response::new(Body::from("not found"));
+        *response.status_mut() = StatusCode::NOT_FOUND;
+        response
+    }
+}
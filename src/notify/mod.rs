@@ -0,0 +1,359 @@
+#![allow(dead_code)]
+
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::AlertConfig;
+
+/// 告警级别，与风险事件的 1-5 级严重程度一一对应
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Low = 1,      // 低
+    Minor = 2,    // 较低
+    Medium = 3,   // 中等
+    High = 4,     // 高
+    Critical = 5, // 严重
+}
+
+impl Severity {
+    /// 从 1-5 级数值转换，超出范围时取边界值
+    pub fn from_level(level: u8) -> Self {
+        match level {
+            0 | 1 => Severity::Low,
+            2 => Severity::Minor,
+            3 => Severity::Medium,
+            4 => Severity::High,
+            _ => Severity::Critical,
+        }
+    }
+
+    pub fn level(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Low => "低",
+            Severity::Minor => "较低",
+            Severity::Medium => "中等",
+            Severity::High => "高",
+            Severity::Critical => "严重",
+        }
+    }
+
+    pub fn as_english(&self) -> &'static str {
+        match self {
+            Severity::Low => "Low",
+            Severity::Minor => "Minor",
+            Severity::Medium => "Medium",
+            Severity::High => "High",
+            Severity::Critical => "Critical",
+        }
+    }
+
+    fn emoji(&self) -> &'static str {
+        match self {
+            Severity::Low | Severity::Minor => "ℹ️",
+            Severity::Medium => "⚠️",
+            Severity::High => "🚨",
+            Severity::Critical => "🆘",
+        }
+    }
+}
+
+crate::impl_bilingual!(Severity);
+
+/// 告警通道错误
+#[derive(Debug, thiserror::Error)]
+pub enum NotifyError {
+    #[error("请求发送失败: {0}")]
+    Request(String),
+    #[error("通道返回错误: {0}")]
+    Rejected(String),
+}
+
+/// 告警通道
+#[async_trait]
+pub trait AlertChannel: Send + Sync {
+    /// 通道名称，用于日志
+    fn name(&self) -> &'static str;
+
+    /// 发送一条告警
+    async fn send(&self, level: Severity, msg: &str) -> Result<(), NotifyError>;
+}
+
+/// Telegram Bot 通道
+pub struct TelegramChannel {
+    client: reqwest::Client,
+    bot_token: String,
+    chat_id: String,
+}
+
+impl TelegramChannel {
+    pub fn new(bot_token: String, chat_id: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            bot_token,
+            chat_id,
+        }
+    }
+}
+
+#[async_trait]
+impl AlertChannel for TelegramChannel {
+    fn name(&self) -> &'static str {
+        "Telegram"
+    }
+
+    async fn send(&self, level: Severity, msg: &str) -> Result<(), NotifyError> {
+        let url = format!("https://api.telegram.org/bot{}/sendMessage", self.bot_token);
+        let body = serde_json::json!({
+            "chat_id": self.chat_id,
+            "text": format_alert(level, msg),
+            "disable_web_page_preview": true,
+        });
+
+        let response = self
+            .client
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| NotifyError::Request(format!("{:?}", e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(NotifyError::Rejected(format!("{} {}", status, text)));
+        }
+
+        Ok(())
+    }
+}
+
+/// 钉钉机器人 webhook 通道
+pub struct DingTalkChannel {
+    client: reqwest::Client,
+    webhook: String,
+}
+
+impl DingTalkChannel {
+    pub fn new(webhook: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            webhook,
+        }
+    }
+}
+
+#[async_trait]
+impl AlertChannel for DingTalkChannel {
+    fn name(&self) -> &'static str {
+        "钉钉"
+    }
+
+    async fn send(&self, level: Severity, msg: &str) -> Result<(), NotifyError> {
+        let body = serde_json::json!({
+            "msgtype": "text",
+            "text": { "content": format_alert(level, msg) },
+        });
+
+        let response = self
+            .client
+            .post(&self.webhook)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| NotifyError::Request(format!("{:?}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(NotifyError::Rejected(response.status().to_string()));
+        }
+
+        // 钉钉即使失败也返回200，需要检查 errcode
+        let result: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| NotifyError::Request(format!("{:?}", e)))?;
+        match result.get("errcode").and_then(|c| c.as_i64()) {
+            Some(0) | None => Ok(()),
+            Some(code) => Err(NotifyError::Rejected(format!(
+                "errcode={}, errmsg={}",
+                code,
+                result
+                    .get("errmsg")
+                    .and_then(|m| m.as_str())
+                    .unwrap_or_default()
+            ))),
+        }
+    }
+}
+
+fn format_alert(level: Severity, msg: &str) -> String {
+    format!(
+        "{} [taoli-tools][{}] {}",
+        level.emoji(),
+        level.as_str(),
+        msg
+    )
+}
+
+/// 限流状态
+struct RateLimitState {
+    last_sent: HashMap<String, Instant>, // 每条消息最近一次发送时间
+    recent: VecDeque<Instant>,           // 最近一分钟内的发送记录
+}
+
+/// 告警管理器，负责级别过滤、限流并分发到各个通道
+pub struct AlertManager {
+    channels: Vec<Box<dyn AlertChannel>>,
+    min_severity: Severity,
+    repeat_interval: Duration,
+    max_per_minute: usize,
+    send_period_reports: bool,
+    send_timeout: Duration,
+    state: Mutex<RateLimitState>,
+}
+
+impl std::fmt::Debug for AlertManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AlertManager")
+            .field(
+                "channels",
+                &self.channels.iter().map(|c| c.name()).collect::<Vec<_>>(),
+            )
+            .field("min_severity", &self.min_severity)
+            .field("repeat_interval", &self.repeat_interval)
+            .field("max_per_minute", &self.max_per_minute)
+            .finish()
+    }
+}
+
+impl AlertManager {
+    /// 根据配置创建告警管理器，未启用或未配置通道时不会发送任何消息
+    pub fn from_config(config: &AlertConfig) -> Self {
+        let mut channels: Vec<Box<dyn AlertChannel>> = Vec::new();
+
+        if config.enabled {
+            if !config.telegram_bot_token.is_empty() && !config.telegram_chat_id.is_empty() {
+                channels.push(Box::new(TelegramChannel::new(
+                    config.telegram_bot_token.clone(),
+                    config.telegram_chat_id.clone(),
+                )));
+            }
+            if !config.dingtalk_webhook.is_empty() {
+                channels.push(Box::new(DingTalkChannel::new(
+                    config.dingtalk_webhook.clone(),
+                )));
+            }
+
+            if channels.is_empty() {
+                warn!("⚠️ 告警已启用但未配置任何通道（Telegram/钉钉）");
+            } else {
+                info!(
+                    "📣 告警通知已启用 - 通道: {}, 最低级别: {}",
+                    channels
+                        .iter()
+                        .map(|c| c.name())
+                        .collect::<Vec<_>>()
+                        .join("/"),
+                    config.min_severity
+                );
+            }
+        }
+
+        Self {
+            channels,
+            min_severity: Severity::from_level(config.min_severity),
+            repeat_interval: Duration::from_secs(config.repeat_interval_secs),
+            max_per_minute: config.max_alerts_per_minute,
+            send_period_reports: config.send_period_reports,
+            send_timeout: Duration::from_secs(10),
+            state: Mutex::new(RateLimitState {
+                last_sent: HashMap::new(),
+                recent: VecDeque::new(),
+            }),
+        }
+    }
+
+    /// 不发送任何告警的空管理器
+    pub fn disabled() -> Self {
+        Self::from_config(&AlertConfig::default())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.channels.is_empty()
+    }
+
+    /// 发送告警，低于最低级别或被限流的消息会被丢弃，发送失败只记录日志
+    pub async fn alert(&self, level: Severity, msg: &str) {
+        if !self.is_enabled() || level < self.min_severity {
+            return;
+        }
+
+        if !self.try_acquire(msg) {
+            debug!("🔕 告警被限流: {}", msg);
+            return;
+        }
+
+        for channel in &self.channels {
+            match tokio::time::timeout(self.send_timeout, channel.send(level, msg)).await {
+                Ok(Ok(())) => debug!("📣 告警已发送到{}", channel.name()),
+                Ok(Err(e)) => warn!("⚠️ 发送{}告警失败: {}", channel.name(), e),
+                Err(_) => warn!("⚠️ 发送{}告警超时", channel.name()),
+            }
+        }
+    }
+
+    /// 推送每日/每周报表，需配置 send_period_reports，不受最低告警级别限制
+    pub async fn report(&self, msg: &str) {
+        if !self.send_period_reports || !self.is_enabled() {
+            return;
+        }
+
+        for channel in &self.channels {
+            match tokio::time::timeout(self.send_timeout, channel.send(Severity::Low, msg)).await {
+                Ok(Ok(())) => debug!("📣 报表已发送到{}", channel.name()),
+                Ok(Err(e)) => warn!("⚠️ 发送{}报表失败: {}", channel.name(), e),
+                Err(_) => warn!("⚠️ 发送{}报表超时", channel.name()),
+            }
+        }
+    }
+
+    /// 限流检查：相同消息在间隔内只发一次，全局每分钟不超过上限
+    fn try_acquire(&self, msg: &str) -> bool {
+        let now = Instant::now();
+        let mut state = match self.state.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        if let Some(last) = state.last_sent.get(msg) {
+            if now.duration_since(*last) < self.repeat_interval {
+                return false;
+            }
+        }
+
+        while let Some(front) = state.recent.front() {
+            if now.duration_since(*front) >= Duration::from_secs(60) {
+                state.recent.pop_front();
+            } else {
+                break;
+            }
+        }
+        if state.recent.len() >= self.max_per_minute {
+            return false;
+        }
+
+        let repeat_interval = self.repeat_interval;
+        state
+            .last_sent
+            .retain(|_, sent| now.duration_since(*sent) < repeat_interval);
+        state.last_sent.insert(msg.to_string(), now);
+        state.recent.push_back(now);
+        true
+    }
+}
@@ -0,0 +1,168 @@
+#![allow(dead_code)]
+
+use serde::Serialize;
+use std::path::Path;
+
+use super::error::GridStrategyError;
+use super::performance::PerformanceMetrics;
+use crate::config::GridConfig;
+
+/// 回测使用的网格参数
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct GridParams {
+    pub min_grid_spacing: f64, // 网格间距（比例）
+    pub grid_count: u32,       // 当前价格下方的买入网格数
+    pub trade_amount: f64,     // 每格交易金额
+}
+
+impl GridParams {
+    pub fn from_config(grid_config: &GridConfig) -> Self {
+        Self {
+            min_grid_spacing: grid_config.min_grid_spacing,
+            grid_count: grid_config.grid_count,
+            trade_amount: grid_config.trade_amount,
+        }
+    }
+}
+
+/// 单组参数的回测结果
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestResult {
+    pub params: GridParams,
+    pub roi: f64,           // 最终收益率
+    pub sharpe_ratio: f64,  // 按每个价格点权益收益率计算的夏普比率
+    pub max_drawdown: f64,  // 权益最大回撤比例
+    pub total_trades: u32,  // 完成的买卖回合数
+    pub win_rate: f64,      // 胜率（%）
+    pub final_capital: f64, // 以最后价格计算的最终权益
+}
+
+// 单个买入网格的状态：等待买入，或持仓等待在上一格卖出
+#[derive(Debug, Clone, Copy)]
+struct GridLevel {
+    buy_price: f64,
+    sell_price: f64,
+    holding: Option<(f64, f64)>, // (数量, 含手续费的买入成本)
+}
+
+/// 在历史价格上回测固定网格
+///
+/// 以第一个价格为中心向下铺设 `grid_count` 个买入网格，每格成交后在上方一个间距处卖出。
+/// 初始资金为 `total_capital`，手续费按 maker 费率双边收取，资金不足时跳过买入。
+pub fn backtest_grid(
+    prices: &[f64],
+    params: GridParams,
+    grid_config: &GridConfig,
+) -> BacktestResult {
+    let initial_capital = grid_config.total_capital;
+    let fee_rate = grid_config.maker_fee();
+    let mut metrics = PerformanceMetrics::new();
+    let mut cash = initial_capital;
+
+    let center = prices.first().copied().unwrap_or(0.0);
+    let mut levels: Vec<GridLevel> = (1..=params.grid_count)
+        .map(|i| {
+            let buy_price = center * (1.0 - params.min_grid_spacing * i as f64);
+            GridLevel {
+                buy_price,
+                sell_price: buy_price * (1.0 + params.min_grid_spacing),
+                holding: None,
+            }
+        })
+        .filter(|level| level.buy_price > 0.0)
+        .collect();
+
+    let mut peak_equity = initial_capital;
+    let mut last_equity = initial_capital;
+    let mut returns = Vec::with_capacity(prices.len());
+
+    for &price in prices {
+        for level in levels.iter_mut() {
+            match level.holding {
+                None if price <= level.buy_price => {
+                    let cost = params.trade_amount * (1.0 + fee_rate);
+                    if cash >= cost {
+                        cash -= cost;
+                        level.holding = Some((params.trade_amount / level.buy_price, cost));
+                    }
+                }
+                Some((quantity, cost)) if price >= level.sell_price => {
+                    let proceeds = quantity * level.sell_price * (1.0 - fee_rate);
+                    cash += proceeds;
+                    metrics.update_trade(proceeds - cost);
+                    level.holding = None;
+                }
+                _ => {}
+            }
+        }
+
+        let position: f64 = levels
+            .iter()
+            .filter_map(|level| level.holding.map(|(quantity, _)| quantity))
+            .sum();
+        let equity = cash + position * price;
+
+        if last_equity > 0.0 {
+            returns.push((equity - last_equity) / last_equity);
+        }
+        last_equity = equity;
+        peak_equity = peak_equity.max(equity);
+        if peak_equity > 0.0 {
+            metrics.update_drawdown((peak_equity - equity) / peak_equity);
+        }
+    }
+
+    metrics.calculate_sharpe_ratio(&returns, 0.0);
+
+    BacktestResult {
+        params,
+        roi: if initial_capital > 0.0 {
+            (last_equity - initial_capital) / initial_capital
+        } else {
+            0.0
+        },
+        sharpe_ratio: metrics.sharpe_ratio,
+        max_drawdown: metrics.max_drawdown,
+        total_trades: metrics.total_trades,
+        win_rate: metrics.win_rate,
+        final_capital: last_equity,
+    }
+}
+
+/// 读取历史价格 CSV
+///
+/// 有表头时取 `close` 或 `price` 列，否则取第二列（单列文件取第一列）；无法解析的行跳过。
+pub fn load_price_history(path: &Path) -> Result<Vec<f64>, GridStrategyError> {
+    let contents = std::fs::read_to_string(path).map_err(|e| {
+        GridStrategyError::ConfigError(format!("读取历史数据 {} 失败: {:?}", path.display(), e))
+    })?;
+
+    let mut lines = contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .peekable();
+    let header_column = lines.peek().and_then(|first| {
+        let columns: Vec<String> = first.split(',').map(|c| c.trim().to_lowercase()).collect();
+        columns.iter().position(|c| c == "close" || c == "price")
+    });
+    if header_column.is_some() {
+        lines.next();
+    }
+
+    let prices: Vec<f64> = lines
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let column = header_column.unwrap_or(if fields.len() > 1 { 1 } else { 0 });
+            fields.get(column)?.parse::<f64>().ok()
+        })
+        .filter(|price| price.is_finite() && *price > 0.0)
+        .collect();
+
+    if prices.len() < 2 {
+        return Err(GridStrategyError::ConfigError(format!(
+            "历史数据 {} 中有效价格不足2个",
+            path.display()
+        )));
+    }
+    Ok(prices)
+}
@@ -6,12 +6,17 @@ use std::time::{Duration, Instant};
 
 /// 批处理任务优化器
 ///
-/// 该优化器通过分析历史执行时间来动态调整批处理大小，
-/// 以达到最佳的执行性能和资源利用率。
+/// 该优化器通过分析历史执行时间和下单成功率来动态调整批处理大小，
+/// 以达到最佳的执行性能和资源利用率。执行再快，大量订单被拒（如触发限流）也没有意义，
+/// 因此成功率低于阈值时优先缩小批次。
 #[derive(Debug, Clone)]
 pub struct BatchTaskOptimizer {
     /// 最近执行时间的滑动窗口
     last_execution_times: VecDeque<Duration>,
+    /// 最近每次执行的（成功数, 失败数）滑动窗口，与执行时间一一对应
+    last_order_results: VecDeque<(usize, usize)>,
+    /// 最低可接受成功率（0-1），低于该值时缩小批次
+    min_success_rate: f64,
     /// 当前最优批次大小
     optimal_batch_size: usize,
     /// 调整因子（每次调整的幅度）
@@ -30,7 +35,7 @@ pub struct BatchTaskOptimizer {
     last_adjustment_time: Instant,
     /// 调整冷却时间
     adjustment_cooldown: Duration,
-    /// 性能趋势（正值表示性能改善，负值表示性能下降）
+    /// 性能趋势：最近执行时间相对之前的变化率（正值表示变慢即性能下降，负值表示性能改善）
     performance_trend: f64,
 }
 
@@ -43,6 +48,8 @@ impl BatchTaskOptimizer {
     pub fn new(initial_batch_size: usize, target_execution_time: Duration) -> Self {
         Self {
             last_execution_times: VecDeque::new(),
+            last_order_results: VecDeque::new(),
+            min_success_rate: 0.8, // 成功率低于80%时缩小批次
             optimal_batch_size: initial_batch_size,
             adjustment_factor: 0.1, // 10%的调整幅度
             min_batch_size: 1,
@@ -56,7 +63,7 @@ impl BatchTaskOptimizer {
         }
     }
 
-    /// 基于历史执行时间自动调整最优批次大小
+    /// 基于历史执行时间和成功率自动调整最优批次大小
     ///
     /// # 参数
     /// * `task_count` - 当前待处理的任务数量
@@ -79,26 +86,30 @@ impl BatchTaskOptimizer {
             return self.optimal_batch_size.min(task_count);
         }
 
-        // 计算平均执行时间和性能趋势
+        // 计算平均执行时间、成功率和性能趋势
         let avg_execution_time = self.calculate_average_execution_time();
         let performance_variance = self.calculate_performance_variance();
+        let success_rate = self.calculate_success_rate();
 
         // 更新性能趋势
         self.update_performance_trend(avg_execution_time);
 
         // 决定是否需要调整批次大小
-        let should_adjust = self.should_adjust_batch_size(avg_execution_time, performance_variance);
+        let should_adjust =
+            self.should_adjust_batch_size(avg_execution_time, performance_variance, success_rate);
 
         if should_adjust {
-            let new_batch_size = self.calculate_new_batch_size(avg_execution_time, task_count);
+            let new_batch_size =
+                self.calculate_new_batch_size(avg_execution_time, success_rate, task_count);
 
             if new_batch_size != self.optimal_batch_size {
                 info!(
-                    "📊 批处理优化器调整: {} -> {} (平均执行时间: {:.2}秒, 目标: {:.2}秒)",
+                    "📊 批处理优化器调整: {} -> {} (平均执行时间: {:.2}秒, 目标: {:.2}秒, 成功率: {:.1}%)",
                     self.optimal_batch_size,
                     new_batch_size,
                     avg_execution_time.as_secs_f64(),
-                    self.target_execution_time.as_secs_f64()
+                    self.target_execution_time.as_secs_f64(),
+                    success_rate * 100.0
                 );
 
                 self.optimal_batch_size = new_batch_size;
@@ -122,17 +133,23 @@ impl BatchTaskOptimizer {
         self.optimal_batch_size.min(task_count)
     }
 
-    /// 记录执行时间，用于未来优化
+    /// 记录执行时间和下单结果，用于未来优化
     ///
     /// # 参数
     /// * `duration` - 本次执行的时间
-    pub fn record_execution_time(&mut self, duration: Duration) {
+    /// * `succeeded` - 本次成功的订单数
+    /// * `failed` - 本次失败（被拒、超时）的订单数
+    pub fn record_execution_time(&mut self, duration: Duration, succeeded: usize, failed: usize) {
         self.last_execution_times.push_back(duration);
+        self.last_order_results.push_back((succeeded, failed));
 
         // 保持窗口大小
         if self.last_execution_times.len() > self.performance_window_size {
             self.last_execution_times.pop_front();
         }
+        if self.last_order_results.len() > self.performance_window_size {
+            self.last_order_results.pop_front();
+        }
 
         // 记录性能统计
         if self.last_execution_times.len() >= 3 {
@@ -142,17 +159,12 @@ impl BatchTaskOptimizer {
             // 每10次记录输出一次性能统计
             if self.last_execution_times.len() % 10 == 0 {
                 info!(
-                    "📈 批处理性能统计: 平均时间={:.2}秒, 方差={:.4}, 当前批次大小={}, 趋势={}",
+                    "📈 批处理性能统计: 平均时间={:.2}秒, 方差={:.4}, 成功率={:.1}%, 当前批次大小={}, 趋势={}",
                     avg_time.as_secs_f64(),
                     variance,
+                    self.calculate_success_rate() * 100.0,
                     self.optimal_batch_size,
-                    if self.performance_trend > 0.0 {
-                        "改善"
-                    } else if self.performance_trend < 0.0 {
-                        "下降"
-                    } else {
-                        "稳定"
-                    }
+                    self.trend_label()
                 );
             }
         }
@@ -168,6 +180,21 @@ impl BatchTaskOptimizer {
         total_duration / self.last_execution_times.len() as u32
     }
 
+    /// 计算窗口内的下单成功率，没有下单记录时视为100%
+    fn calculate_success_rate(&self) -> f64 {
+        let (succeeded, failed) = self
+            .last_order_results
+            .iter()
+            .fold((0, 0), |(s, f), (succeeded, failed)| {
+                (s + succeeded, f + failed)
+            });
+        let total = succeeded + failed;
+        if total == 0 {
+            return 1.0;
+        }
+        succeeded as f64 / total as f64
+    }
+
     /// 计算性能方差
     fn calculate_performance_variance(&self) -> f64 {
         if self.last_execution_times.len() < 2 {
@@ -216,28 +243,58 @@ impl BatchTaskOptimizer {
                 earlier_times.iter().sum::<Duration>().as_secs_f64() / earlier_times.len() as f64;
 
             // 计算趋势：负值表示性能改善（时间减少），正值表示性能下降
-            self.performance_trend = (recent_avg - earlier_avg) / earlier_avg;
+            // 之前的执行时间全为0（如模拟执行器）时无法计算变化率，视为稳定
+            self.performance_trend = if earlier_avg > f64::EPSILON {
+                (recent_avg - earlier_avg) / earlier_avg
+            } else {
+                0.0
+            };
+        }
+    }
+
+    /// 性能趋势描述，变化率在 ±5% 以内视为稳定
+    fn trend_label(&self) -> &'static str {
+        if self.performance_trend > 0.05 {
+            "下降"
+        } else if self.performance_trend < -0.05 {
+            "改善"
+        } else {
+            "稳定"
         }
     }
 
     /// 判断是否应该调整批次大小
-    fn should_adjust_batch_size(&self, avg_execution_time: Duration, variance: f64) -> bool {
+    fn should_adjust_batch_size(
+        &self,
+        avg_execution_time: Duration,
+        variance: f64,
+        success_rate: f64,
+    ) -> bool {
         let time_diff_ratio =
             (avg_execution_time.as_secs_f64() - self.target_execution_time.as_secs_f64()).abs()
                 / self.target_execution_time.as_secs_f64();
 
-        // 如果时间差异超过20%或方差过大，则需要调整
-        time_diff_ratio > 0.2 || variance > 0.3
+        // 如果成功率低于阈值、时间差异超过20%或方差过大，则需要调整
+        success_rate < self.min_success_rate || time_diff_ratio > 0.2 || variance > 0.3
     }
 
     /// 计算新的批次大小
-    fn calculate_new_batch_size(&self, avg_execution_time: Duration, task_count: usize) -> usize {
+    fn calculate_new_batch_size(
+        &self,
+        avg_execution_time: Duration,
+        success_rate: f64,
+        task_count: usize,
+    ) -> usize {
         let current_time = avg_execution_time.as_secs_f64();
         let target_time = self.target_execution_time.as_secs_f64();
 
         let mut new_size = self.optimal_batch_size;
 
-        if current_time > target_time * 1.2 {
+        if success_rate < self.min_success_rate {
+            // 成功率过低，按成功率缩小批次（至少缩小一个调整幅度，最多减半），不因执行快而放大
+            let reduction_factor = success_rate.clamp(0.5, 1.0 - self.adjustment_factor);
+            new_size = ((self.optimal_batch_size as f64) * reduction_factor) as usize;
+        } else if current_time > target_time * 1.2 {
             // 执行时间过长，减少批次大小
             let reduction_factor = 1.0 - self.adjustment_factor;
             new_size = ((self.optimal_batch_size as f64) * reduction_factor) as usize;
@@ -247,11 +304,11 @@ impl BatchTaskOptimizer {
             new_size = ((self.optimal_batch_size as f64) * increase_factor) as usize;
         }
 
-        // 考虑性能趋势进行微调
+        // 考虑性能趋势进行微调（成功率过低时只缩小）
         if self.performance_trend > 0.1 {
             // 性能下降，保守调整
             new_size = (new_size as f64 * 0.95) as usize;
-        } else if self.performance_trend < -0.1 {
+        } else if self.performance_trend < -0.1 && success_rate >= self.min_success_rate {
             // 性能改善，可以更积极调整
             new_size = (new_size as f64 * 1.05) as usize;
         }
@@ -285,6 +342,7 @@ impl BatchTaskOptimizer {
             当前批次大小: {}\n\
             目标执行时间: {:.2}秒\n\
             平均执行时间: {:.2}秒\n\
+            下单成功率: {:.1}% (阈值: {:.0}%)\n\
             性能方差: {:.4}\n\
             执行效率: {:.1}%\n\
             性能趋势: {}\n\
@@ -296,15 +354,11 @@ impl BatchTaskOptimizer {
             self.optimal_batch_size,
             self.target_execution_time.as_secs_f64(),
             avg_time.as_secs_f64(),
+            self.calculate_success_rate() * 100.0,
+            self.min_success_rate * 100.0,
             variance,
             efficiency,
-            if self.performance_trend > 0.05 {
-                "下降"
-            } else if self.performance_trend < -0.05 {
-                "改善"
-            } else {
-                "稳定"
-            },
+            self.trend_label(),
             self.consecutive_adjustments,
             self.last_execution_times.len(),
             self.adjustment_factor * 100.0,
@@ -317,6 +371,7 @@ impl BatchTaskOptimizer {
     /// 重置优化器状态
     pub fn reset(&mut self) {
         self.last_execution_times.clear();
+        self.last_order_results.clear();
         self.consecutive_adjustments = 0;
         self.last_adjustment_time = Instant::now();
         self.adjustment_cooldown = Duration::from_secs(30);
@@ -330,6 +385,19 @@ impl BatchTaskOptimizer {
         info!("🎯 目标执行时间已更新为: {:.2}秒", target.as_secs_f64());
     }
 
+    /// 设置最低可接受成功率（0-1）
+    pub fn set_min_success_rate(&mut self, min_success_rate: f64) {
+        if (0.0..=1.0).contains(&min_success_rate) {
+            self.min_success_rate = min_success_rate;
+            info!(
+                "🎯 最低下单成功率已更新为: {:.0}%",
+                min_success_rate * 100.0
+            );
+        } else {
+            warn!("⚠️ 无效的最低成功率: {}", min_success_rate);
+        }
+    }
+
     /// 设置批次大小范围
     pub fn set_batch_size_range(&mut self, min_size: usize, max_size: usize) {
         if min_size > 0 && max_size >= min_size {
@@ -355,7 +423,7 @@ impl BatchTaskOptimizer {
         self.calculate_average_execution_time()
     }
 
-    /// 获取性能趋势
+    /// 获取性能趋势（正值表示执行变慢，负值表示执行变快）
     pub fn get_performance_trend(&self) -> f64 {
         self.performance_trend
     }
@@ -368,7 +436,7 @@ impl BatchTaskOptimizer {
 
         let avg_time = self.calculate_average_execution_time();
         let variance = self.calculate_performance_variance();
-        self.should_adjust_batch_size(avg_time, variance)
+        self.should_adjust_batch_size(avg_time, variance, self.calculate_success_rate())
     }
 
     /// 强制调整批次大小
@@ -394,8 +462,15 @@ impl BatchTaskOptimizer {
 
         let avg_time_secs = avg_time.as_secs_f64();
         let target_time_secs = target_time.as_secs_f64();
+        let success_rate = self.calculate_success_rate();
 
-        if avg_time_secs > target_time_secs * 1.2 {
+        if success_rate < self.min_success_rate {
+            Some(format!(
+                "建议减少批次大小，当前下单成功率({:.1}%)低于阈值({:.0}%)",
+                success_rate * 100.0,
+                self.min_success_rate * 100.0
+            ))
+        } else if avg_time_secs > target_time_secs * 1.2 {
             Some(format!(
                 "建议减少批次大小，当前执行时间({:.2}秒)超出目标时间({:.2}秒)20%以上",
                 avg_time_secs, target_time_secs
@@ -415,6 +490,11 @@ impl BatchTaskOptimizer {
         self.target_execution_time
     }
 
+    /// 获取窗口内的下单成功率（0-1）
+    pub fn get_success_rate(&self) -> f64 {
+        self.calculate_success_rate()
+    }
+
     /// 获取历史记录数量
     pub fn get_execution_history_count(&self) -> usize {
         self.last_execution_times.len()
@@ -431,3 +511,48 @@ impl Default for BatchTaskOptimizer {
         Self::new(10, Duration::from_secs(5))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trend_after(millis: &[u64]) -> (f64, &'static str) {
+        let mut optimizer = BatchTaskOptimizer::default();
+        for &ms in millis {
+            optimizer.record_execution_time(Duration::from_millis(ms), 1, 0);
+        }
+        optimizer.update_performance_trend(optimizer.calculate_average_execution_time());
+        (optimizer.get_performance_trend(), optimizer.trend_label())
+    }
+
+    #[test]
+    fn rising_execution_time_is_reported_as_degrading() {
+        let (trend, label) = trend_after(&[100, 100, 100, 200, 200, 200]);
+        assert!(trend > 0.05);
+        assert_eq!(label, "下降");
+    }
+
+    #[test]
+    fn falling_execution_time_is_reported_as_improving() {
+        let (trend, label) = trend_after(&[200, 200, 200, 100, 100, 100]);
+        assert!(trend < -0.05);
+        assert_eq!(label, "改善");
+    }
+
+    #[test]
+    fn flat_execution_time_is_stable() {
+        assert_eq!(trend_after(&[100, 102, 99, 101, 100, 100]).1, "稳定");
+    }
+
+    #[test]
+    fn zero_earlier_times_do_not_produce_nan() {
+        let (trend, label) = trend_after(&[0, 0, 0, 50, 50, 50]);
+        assert!(trend.is_finite());
+        assert_eq!(label, "稳定");
+    }
+
+    #[test]
+    fn too_few_samples_leave_trend_unchanged() {
+        assert_eq!(trend_after(&[100, 300, 500, 700]), (0.0, "稳定"));
+    }
+}
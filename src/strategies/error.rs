@@ -46,6 +46,9 @@ pub enum GridStrategyError {
 
     #[error("网络连接失败: {0}")]
     NetworkError(String),
+
+    #[error("状态存储失败: {0}")]
+    StateError(String),
 }
 
 impl GridStrategyError {
@@ -119,6 +122,11 @@ impl GridStrategyError {
         Self::NetworkError(msg.into())
     }
 
+    /// 创建状态存储错误
+    pub fn state_error(msg: impl Into<String>) -> Self {
+        Self::StateError(msg.into())
+    }
+
     /// 判断是否为致命错误（需要停止交易）
     pub fn is_fatal(&self) -> bool {
         matches!(
@@ -168,6 +176,7 @@ impl GridStrategyError {
             Self::MarketAnalysisError(_) => 2,
             Self::FundAllocationError(_) => 3,
             Self::RebalanceError(_) => 2,
+            Self::StateError(_) => 3,
         }
     }
 
@@ -188,6 +197,7 @@ impl GridStrategyError {
             Self::StopLossError(_) => "止损错误",
             Self::MarginInsufficient(_) => "保证金不足",
             Self::NetworkError(_) => "网络错误",
+            Self::StateError(_) => "状态存储错误",
         }
     }
 
@@ -261,6 +271,7 @@ pub struct ErrorStatistics {
     pub stop_loss_errors: u64,
     pub margin_insufficient: u64,
     pub network_errors: u64,
+    pub state_errors: u64,
 }
 
 impl ErrorStatistics {
@@ -282,6 +293,7 @@ impl ErrorStatistics {
             GridStrategyError::StopLossError(_) => self.stop_loss_errors += 1,
             GridStrategyError::MarginInsufficient(_) => self.margin_insufficient += 1,
             GridStrategyError::NetworkError(_) => self.network_errors += 1,
+            GridStrategyError::StateError(_) => self.state_errors += 1,
         }
     }
 
@@ -302,6 +314,7 @@ impl ErrorStatistics {
             (self.stop_loss_errors, "止损错误"),
             (self.margin_insufficient, "保证金不足"),
             (self.network_errors, "网络错误"),
+            (self.state_errors, "状态存储错误"),
         ];
 
         errors
@@ -334,7 +347,8 @@ impl ErrorStatistics {
             重平衡错误: {}\n\
             止损错误: {}\n\
             保证金不足: {}\n\
-            网络错误: {}",
+            网络错误: {}\n\
+            状态存储错误: {}",
             self.total_errors,
             self.config_errors,
             self.wallet_errors,
@@ -349,7 +363,8 @@ impl ErrorStatistics {
             self.rebalance_errors,
             self.stop_loss_errors,
             self.margin_insufficient,
-            self.network_errors
+            self.network_errors,
+            self.state_errors
         )
     }
 }
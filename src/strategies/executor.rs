@@ -0,0 +1,583 @@
+#![allow(dead_code)]
+
+use async_trait::async_trait;
+use ethers::signers::LocalWallet;
+use hyperliquid_rust_sdk::{
+    ClientCancelRequest, ClientOrder, ClientOrderRequest, ExchangeClient, ExchangeDataStatus,
+    ExchangeDataStatuses, ExchangeResponse, ExchangeResponseStatus, FilledOrder, Message,
+    RestingOrder, UserStateResponse,
+};
+use log::{info, warn};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::rate_limiter::acquire_api_permit;
+
+/// 下单执行器
+///
+/// 策略通过该 trait 下单、撤单和设置杠杆，实盘使用 `ExchangeClient`（经全局 API 限流），
+/// dry-run 模式使用 `SimulatedExecutor`，方法签名与 `ExchangeClient` 保持一致。
+#[async_trait]
+pub trait OrderExecutor: Send + Sync {
+    /// 提交订单
+    async fn order(
+        &self,
+        order: ClientOrderRequest,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus, hyperliquid_rust_sdk::Error>;
+
+    /// 撤销订单
+    async fn cancel(
+        &self,
+        cancel: ClientCancelRequest,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus, hyperliquid_rust_sdk::Error>;
+
+    /// 设置杠杆
+    async fn update_leverage(
+        &self,
+        leverage: u32,
+        coin: &str,
+        is_cross: bool,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus, hyperliquid_rust_sdk::Error>;
+
+    /// 是否为模拟执行器
+    fn is_simulated(&self) -> bool {
+        false
+    }
+
+    /// 推送最新市场价格，模拟执行器据此撮合挂单
+    fn on_market_price(&self, _coin: &str, _price: f64) {}
+
+    /// 模拟账户状态（余额、持仓、保证金），只有模拟执行器返回 Some
+    fn simulated_user_state(&self) -> Option<UserStateResponse> {
+        None
+    }
+}
+
+#[async_trait]
+impl OrderExecutor for ExchangeClient {
+    async fn order(
+        &self,
+        order: ClientOrderRequest,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus, hyperliquid_rust_sdk::Error> {
+        acquire_api_permit().await;
+        ExchangeClient::order(self, order, wallet).await
+    }
+
+    async fn cancel(
+        &self,
+        cancel: ClientCancelRequest,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus, hyperliquid_rust_sdk::Error> {
+        acquire_api_permit().await;
+        ExchangeClient::cancel(self, cancel, wallet).await
+    }
+
+    async fn update_leverage(
+        &self,
+        leverage: u32,
+        coin: &str,
+        is_cross: bool,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus, hyperliquid_rust_sdk::Error> {
+        acquire_api_permit().await;
+        ExchangeClient::update_leverage(self, leverage, coin, is_cross, wallet).await
+    }
+}
+
+#[async_trait]
+impl<T: OrderExecutor + ?Sized> OrderExecutor for Arc<T> {
+    async fn order(
+        &self,
+        order: ClientOrderRequest,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus, hyperliquid_rust_sdk::Error> {
+        (**self).order(order, wallet).await
+    }
+
+    async fn cancel(
+        &self,
+        cancel: ClientCancelRequest,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus, hyperliquid_rust_sdk::Error> {
+        (**self).cancel(cancel, wallet).await
+    }
+
+    async fn update_leverage(
+        &self,
+        leverage: u32,
+        coin: &str,
+        is_cross: bool,
+        wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus, hyperliquid_rust_sdk::Error> {
+        (**self)
+            .update_leverage(leverage, coin, is_cross, wallet)
+            .await
+    }
+
+    fn is_simulated(&self) -> bool {
+        (**self).is_simulated()
+    }
+
+    fn on_market_price(&self, coin: &str, price: f64) {
+        (**self).on_market_price(coin, price)
+    }
+
+    fn simulated_user_state(&self) -> Option<UserStateResponse> {
+        (**self).simulated_user_state()
+    }
+}
+
+/// 模拟挂单
+#[derive(Debug, Clone)]
+struct SimulatedOrder {
+    coin: String,
+    is_buy: bool,
+    limit_px: f64,
+    sz: f64,
+    cloid: Option<uuid::Uuid>, // 下单时带的客户端订单ID，成交事件原样带回
+}
+
+/// 模拟持仓，szi 为带方向的持仓数量（多为正、空为负）
+#[derive(Debug, Clone, Default)]
+struct SimulatedPosition {
+    szi: f64,
+    entry_px: f64,
+}
+
+/// 模拟账户（paper trading）
+///
+/// 余额为初始资金加上已实现盈亏，账户价值、保证金占用和可提取金额按最新价格在本地计算。
+#[derive(Debug, Default)]
+struct SimulatedAccount {
+    balance: f64,
+    leverage: u32,
+    positions: HashMap<String, SimulatedPosition>,
+}
+
+impl SimulatedAccount {
+    /// 按成交更新持仓和余额，返回成交前的持仓数量和本次已实现盈亏
+    fn apply_fill(&mut self, coin: &str, is_buy: bool, sz: f64, px: f64) -> (f64, f64) {
+        let position = self.positions.entry(coin.to_string()).or_default();
+        let start_position = position.szi;
+        let signed_sz = if is_buy { sz } else { -sz };
+
+        let mut closed_pnl = 0.0;
+        if position.szi == 0.0 || position.szi.signum() == signed_sz.signum() {
+            // 开仓或加仓：按数量加权更新开仓均价
+            let new_szi = position.szi + signed_sz;
+            position.entry_px = (position.entry_px * position.szi.abs() + px * sz) / new_szi.abs();
+            position.szi = new_szi;
+        } else {
+            // 减仓或反手：平掉的部分结算盈亏，反手部分以成交价开仓
+            let closed = sz.min(position.szi.abs());
+            closed_pnl = closed * (px - position.entry_px) * position.szi.signum();
+            position.szi += signed_sz;
+            if position.szi.abs() < 1e-12 {
+                position.szi = 0.0;
+                position.entry_px = 0.0;
+            } else if position.szi.signum() == signed_sz.signum() {
+                position.entry_px = px;
+            }
+        }
+
+        self.balance += closed_pnl;
+        (start_position, closed_pnl)
+    }
+
+    /// 按交易所 user_state 接口的格式生成账户状态
+    fn user_state_json(&self, last_prices: &HashMap<String, f64>) -> serde_json::Value {
+        let leverage = self.leverage.max(1);
+        let mut unrealized_total = 0.0;
+        let mut notional_total = 0.0;
+        let mut signed_notional_total = 0.0;
+        let mut asset_positions = Vec::new();
+
+        for (coin, position) in &self.positions {
+            if position.szi == 0.0 {
+                continue;
+            }
+            let mark_px = last_prices.get(coin).copied().unwrap_or(position.entry_px);
+            let unrealized = position.szi * (mark_px - position.entry_px);
+            let notional = position.szi.abs() * mark_px;
+            let margin_used = notional / leverage as f64;
+            unrealized_total += unrealized;
+            notional_total += notional;
+            signed_notional_total += position.szi * mark_px;
+
+            asset_positions.push(serde_json::json!({
+                "position": {
+                    "coin": coin,
+                    "entryPx": position.entry_px.to_string(),
+                    "leverage": { "type": "cross", "value": leverage, "rawUsd": null },
+                    "liquidationPx": null,
+                    "marginUsed": margin_used.to_string(),
+                    "positionValue": notional.to_string(),
+                    "returnOnEquity": if margin_used > 0.0 { unrealized / margin_used } else { 0.0 }.to_string(),
+                    "szi": position.szi.to_string(),
+                    "unrealizedPnl": unrealized.to_string(),
+                    "maxLeverage": 50,
+                    "cumFunding": { "allTime": "0", "sinceOpen": "0", "sinceChange": "0" },
+                },
+                "type": "oneWay",
+            }));
+        }
+
+        let account_value = self.balance + unrealized_total;
+        let total_margin_used = notional_total / leverage as f64;
+        let withdrawable = (account_value - total_margin_used).max(0.0);
+        let margin_summary = serde_json::json!({
+            "accountValue": account_value.to_string(),
+            "totalMarginUsed": total_margin_used.to_string(),
+            "totalNtlPos": notional_total.to_string(),
+            "totalRawUsd": (account_value - signed_notional_total).to_string(),
+        });
+
+        serde_json::json!({
+            "assetPositions": asset_positions,
+            "marginSummary": margin_summary,
+            "crossMarginSummary": margin_summary,
+            "withdrawable": withdrawable.to_string(),
+        })
+    }
+}
+
+/// 模拟订单簿状态
+#[derive(Debug, Default)]
+struct SimulatedBook {
+    next_oid: u64,
+    next_tid: u64,
+    last_prices: HashMap<String, f64>,
+    resting: HashMap<u64, SimulatedOrder>,
+    account: SimulatedAccount,
+}
+
+/// 模拟执行器（dry-run）
+///
+/// 不发送任何网络请求：生成递增的假订单ID，按当前价判断订单是否立即成交，
+/// 未成交的挂单在后续价格穿越时成交，并通过消息通道推送与实盘相同格式的成交事件。
+/// 同时维护一个以 `initial_balance` 起步的模拟账户，账户查询不需要真实资金。
+pub struct SimulatedExecutor {
+    book: Mutex<SimulatedBook>,
+    fill_sender: Mutex<Option<UnboundedSender<Message>>>,
+}
+
+impl SimulatedExecutor {
+    pub fn new(initial_balance: f64) -> Self {
+        Self {
+            book: Mutex::new(SimulatedBook {
+                next_oid: 1,
+                next_tid: 1,
+                account: SimulatedAccount {
+                    balance: initial_balance,
+                    leverage: 1,
+                    positions: HashMap::new(),
+                },
+                ..Default::default()
+            }),
+            fill_sender: Mutex::new(None),
+        }
+    }
+
+    /// 设置成交事件推送通道（与行情订阅共用同一个通道）
+    pub fn set_fill_sender(&self, sender: UnboundedSender<Message>) {
+        let mut guard = match self.fill_sender.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        *guard = Some(sender);
+    }
+
+    fn lock_book(&self) -> std::sync::MutexGuard<'_, SimulatedBook> {
+        match self.book.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn is_marketable(is_buy: bool, limit_px: f64, market_price: f64) -> bool {
+        if is_buy {
+            limit_px >= market_price
+        } else {
+            limit_px <= market_price
+        }
+    }
+
+    /// 记入模拟账户并推送模拟成交事件，格式与实盘 websocket 用户事件一致
+    fn emit_fill(&self, tid: u64, oid: u64, order: &SimulatedOrder, fill_px: f64) {
+        let (start_position, closed_pnl) =
+            self.lock_book()
+                .account
+                .apply_fill(&order.coin, order.is_buy, order.sz, fill_px);
+
+        let sender = match self.fill_sender.lock() {
+            Ok(guard) => guard.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        };
+        let Some(sender) = sender else {
+            return;
+        };
+
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let payload = serde_json::json!({
+            "channel": "user",
+            "data": {
+                "fills": [{
+                    "coin": order.coin,
+                    "side": if order.is_buy { "B" } else { "A" },
+                    "px": fill_px.to_string(),
+                    "sz": order.sz.to_string(),
+                    "time": time,
+                    "hash": format!("dry-run-{}", tid),
+                    "startPosition": start_position.to_string(),
+                    "dir": if order.is_buy { "Open Long" } else { "Close Long" },
+                    "closedPnl": closed_pnl.to_string(),
+                    "oid": oid,
+                    "cloid": order.cloid.map(|cloid| format!("0x{}", cloid.simple())),
+                    "crossed": false,
+                    "fee": "0",
+                    "tid": tid,
+                    "feeToken": "USDC",
+                }]
+            }
+        });
+
+        match serde_json::from_value::<Message>(payload) {
+            Ok(message) => {
+                if sender.send(message).is_err() {
+                    warn!("⚠️ [模拟] 成交事件推送失败，消息通道已关闭");
+                }
+            }
+            Err(e) => warn!("⚠️ [模拟] 构造成交事件失败: {:?}", e),
+        }
+    }
+
+    fn ok_response(
+        response_type: &str,
+        statuses: Vec<ExchangeDataStatus>,
+    ) -> ExchangeResponseStatus {
+        ExchangeResponseStatus::Ok(ExchangeResponse {
+            response_type: response_type.to_string(),
+            data: Some(ExchangeDataStatuses { statuses }),
+        })
+    }
+}
+
+impl Default for SimulatedExecutor {
+    fn default() -> Self {
+        Self::new(0.0)
+    }
+}
+
+#[async_trait]
+impl OrderExecutor for SimulatedExecutor {
+    async fn order(
+        &self,
+        order: ClientOrderRequest,
+        _wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus, hyperliquid_rust_sdk::Error> {
+        let is_ioc = matches!(&order.order_type, ClientOrder::Limit(limit) if limit.tif == "Ioc");
+        let is_alo = matches!(&order.order_type, ClientOrder::Limit(limit) if limit.tif == "Alo");
+        let simulated = SimulatedOrder {
+            coin: order.asset.clone(),
+            is_buy: order.is_buy,
+            limit_px: order.limit_px,
+            sz: order.sz,
+            cloid: order.cloid,
+        };
+
+        let (oid, tid, market_price) = {
+            let mut book = self.lock_book();
+            let oid = book.next_oid;
+            book.next_oid += 1;
+            let tid = book.next_tid;
+            let market_price = book.last_prices.get(&order.asset).copied();
+            (oid, tid, market_price)
+        };
+
+        // 尚无行情时，IOC 按限价成交，其余挂单等待价格推送
+        let filled = match market_price {
+            Some(price) => Self::is_marketable(order.is_buy, order.limit_px, price),
+            None => is_ioc,
+        };
+
+        // post-only 单会立即成交时与交易所一样直接拒绝
+        if filled && is_alo {
+            let price = market_price.unwrap_or(order.limit_px);
+            info!(
+                "🧪 [模拟] post-only订单会立即成交被拒: {} {} {:.4} @ {:.4}",
+                if order.is_buy { "买入" } else { "卖出" },
+                order.asset,
+                order.sz,
+                order.limit_px
+            );
+            return Ok(Self::ok_response(
+                "order",
+                vec![ExchangeDataStatus::Error(format!(
+                    "Post only order would have immediately matched, bbo was {}@{}. asset={}",
+                    price, price, order.asset
+                ))],
+            ));
+        }
+
+        if filled {
+            let fill_px = market_price.unwrap_or(order.limit_px);
+            self.lock_book().next_tid += 1;
+            info!(
+                "🧪 [模拟] 订单立即成交: ID={}, {} {} {:.4} @ {:.4}",
+                oid,
+                if order.is_buy { "买入" } else { "卖出" },
+                order.asset,
+                order.sz,
+                fill_px
+            );
+            self.emit_fill(tid, oid, &simulated, fill_px);
+            return Ok(Self::ok_response(
+                "order",
+                vec![ExchangeDataStatus::Filled(FilledOrder {
+                    total_sz: order.sz.to_string(),
+                    avg_px: fill_px.to_string(),
+                    oid,
+                })],
+            ));
+        }
+
+        if is_ioc {
+            info!(
+                "🧪 [模拟] IOC订单未能立即成交: {} {} {:.4} @ {:.4}",
+                if order.is_buy { "买入" } else { "卖出" },
+                order.asset,
+                order.sz,
+                order.limit_px
+            );
+            return Ok(Self::ok_response(
+                "order",
+                vec![ExchangeDataStatus::Error(
+                    "Order could not immediately match against any resting orders.".to_string(),
+                )],
+            ));
+        }
+
+        info!(
+            "🧪 [模拟] 挂单: ID={}, {} {} {:.4} @ {:.4}",
+            oid,
+            if order.is_buy { "买入" } else { "卖出" },
+            order.asset,
+            order.sz,
+            order.limit_px
+        );
+        self.lock_book().resting.insert(oid, simulated);
+
+        Ok(Self::ok_response(
+            "order",
+            vec![ExchangeDataStatus::Resting(RestingOrder { oid })],
+        ))
+    }
+
+    async fn cancel(
+        &self,
+        cancel: ClientCancelRequest,
+        _wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus, hyperliquid_rust_sdk::Error> {
+        let removed = self.lock_book().resting.remove(&cancel.oid);
+        let status = if removed.is_some() {
+            info!("🧪 [模拟] 撤单: ID={}", cancel.oid);
+            ExchangeDataStatus::Success
+        } else {
+            ExchangeDataStatus::Error(
+                "Order was never placed, already canceled, or filled.".to_string(),
+            )
+        };
+        Ok(Self::ok_response("cancel", vec![status]))
+    }
+
+    async fn update_leverage(
+        &self,
+        leverage: u32,
+        coin: &str,
+        is_cross: bool,
+        _wallet: Option<&LocalWallet>,
+    ) -> Result<ExchangeResponseStatus, hyperliquid_rust_sdk::Error> {
+        self.lock_book().account.leverage = leverage;
+        info!(
+            "🧪 [模拟] 设置杠杆: {} {}x ({})",
+            coin,
+            leverage,
+            if is_cross { "全仓" } else { "逐仓" }
+        );
+        Ok(ExchangeResponseStatus::Ok(ExchangeResponse {
+            response_type: "default".to_string(),
+            data: None,
+        }))
+    }
+
+    fn is_simulated(&self) -> bool {
+        true
+    }
+
+    fn simulated_user_state(&self) -> Option<UserStateResponse> {
+        let state = {
+            let book = self.lock_book();
+            book.account.user_state_json(&book.last_prices)
+        };
+        match serde_json::from_value::<UserStateResponse>(state) {
+            Ok(user_state) => Some(user_state),
+            Err(e) => {
+                warn!("⚠️ [模拟] 构造账户状态失败: {:?}", e);
+                None
+            }
+        }
+    }
+
+    fn on_market_price(&self, coin: &str, price: f64) {
+        if !price.is_finite() || price <= 0.0 {
+            return;
+        }
+
+        let fills: Vec<(u64, u64, SimulatedOrder)> = {
+            let mut book = self.lock_book();
+            book.last_prices.insert(coin.to_string(), price);
+
+            let mut crossed: Vec<u64> = book
+                .resting
+                .iter()
+                .filter(|(_, order)| {
+                    order.coin == coin && Self::is_marketable(order.is_buy, order.limit_px, price)
+                })
+                .map(|(oid, _)| *oid)
+                .collect();
+            crossed.sort_unstable();
+
+            let mut fills = Vec::with_capacity(crossed.len());
+            for oid in crossed {
+                if let Some(order) = book.resting.remove(&oid) {
+                    let tid = book.next_tid;
+                    book.next_tid += 1;
+                    fills.push((tid, oid, order));
+                }
+            }
+            fills
+        };
+
+        for (tid, oid, order) in fills {
+            info!(
+                "🧪 [模拟] 挂单成交: ID={}, {} {} {:.4} @ {:.4} (当前价 {:.4})",
+                oid,
+                if order.is_buy { "买入" } else { "卖出" },
+                order.coin,
+                order.sz,
+                order.limit_px,
+                price
+            );
+            self.emit_fill(tid, oid, &order, order.limit_px);
+        }
+    }
+}
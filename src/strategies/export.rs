@@ -0,0 +1,174 @@
+#![allow(dead_code)]
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use log::{info, warn};
+use serde::Deserialize;
+use std::path::Path;
+use std::time::SystemTime;
+
+use super::error::GridStrategyError;
+use super::performance::PerformanceRecord;
+
+/// 交易流水导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,  // CSV 表格
+    Json, // JSON 数组
+}
+
+impl ExportFormat {
+    /// 解析格式名称（csv/json），无法识别时返回 None
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.trim().to_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// 默认文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV表格",
+            ExportFormat::Json => "JSON数组",
+        }
+    }
+
+    pub fn as_english(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Json => "JSON",
+        }
+    }
+}
+
+crate::impl_bilingual!(ExportFormat);
+
+/// 状态文件中导出所需的部分，其余字段忽略
+#[derive(Debug, Deserialize)]
+struct TradeHistoryFile {
+    #[serde(default)]
+    performance_history: Vec<PerformanceRecord>,
+}
+
+/// 从网格状态文件读取交易流水，无需启动策略
+pub fn load_performance_history(
+    state_file: &Path,
+) -> Result<Vec<PerformanceRecord>, GridStrategyError> {
+    let contents = std::fs::read_to_string(state_file).map_err(|e| {
+        GridStrategyError::ConfigError(format!(
+            "读取状态文件 {} 失败: {:?}",
+            state_file.display(),
+            e
+        ))
+    })?;
+    let history: TradeHistoryFile = serde_json::from_str(&contents)
+        .map_err(|e| GridStrategyError::ConfigError(format!("解析状态文件失败: {:?}", e)))?;
+    Ok(history.performance_history)
+}
+
+/// 导出交易流水，返回导出的记录数
+///
+/// 时间戳使用 RFC3339（UTC），NaN/Inf 等非有限数值在 CSV 中留空、在 JSON 中写为 null。
+pub fn export_trades(
+    records: &[PerformanceRecord],
+    path: &Path,
+    format: ExportFormat,
+) -> Result<usize, GridStrategyError> {
+    let non_finite = records
+        .iter()
+        .filter(|r| !r.price.is_finite() || !r.profit.is_finite() || !r.total_capital.is_finite())
+        .count();
+    if non_finite > 0 {
+        warn!("⚠️ {}条交易记录包含 NaN/Inf 数值，导出时留空", non_finite);
+    }
+
+    let contents = match format {
+        ExportFormat::Csv => render_csv(records),
+        ExportFormat::Json => render_json(records)?,
+    };
+
+    std::fs::write(path, contents).map_err(|e| {
+        GridStrategyError::ConfigError(format!("写入导出文件 {} 失败: {:?}", path.display(), e))
+    })?;
+
+    info!(
+        "📤 已导出{}条交易流水到 {} ({})",
+        records.len(),
+        path.display(),
+        format.as_str()
+    );
+    Ok(records.len())
+}
+
+fn render_csv(records: &[PerformanceRecord]) -> String {
+    let mut out = String::from("timestamp,action,side,order_id,price,profit,fee,total_capital\n");
+    for record in records {
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\n",
+            format_timestamp(record.timestamp),
+            escape_csv(&record.action),
+            escape_csv(&record.side),
+            record.order_id.map(|id| id.to_string()).unwrap_or_default(),
+            format_number(record.price),
+            format_number(record.profit),
+            format_number(record.fee),
+            format_number(record.total_capital)
+        ));
+    }
+    out
+}
+
+fn render_json(records: &[PerformanceRecord]) -> Result<String, GridStrategyError> {
+    let rows: Vec<serde_json::Value> = records
+        .iter()
+        .map(|record| {
+            serde_json::json!({
+                "timestamp": format_timestamp(record.timestamp),
+                "action": record.action,
+                "side": record.side,
+                "order_id": record.order_id,
+                "price": finite_or_none(record.price),
+                "profit": finite_or_none(record.profit),
+                "fee": finite_or_none(record.fee),
+                "total_capital": finite_or_none(record.total_capital),
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&rows)
+        .map_err(|e| GridStrategyError::ConfigError(format!("序列化交易流水失败: {:?}", e)))
+}
+
+fn format_timestamp(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn finite_or_none(value: f64) -> Option<f64> {
+    if value.is_finite() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+fn format_number(value: f64) -> String {
+    finite_or_none(value)
+        .map(|v| v.to_string())
+        .unwrap_or_default()
+}
+
+// 含逗号、引号或换行的字段按 RFC4180 加引号
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
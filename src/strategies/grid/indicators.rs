@@ -0,0 +1,1043 @@
+// 技术指标与市场状态分析
+
+use super::*;
+
+// 市场趋势枚举
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(super) enum MarketTrend {
+    Upward,   // 上升
+    Downward, // 下降
+    Sideways, // 震荡
+}
+
+// 市场状态枚举
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(super) enum MarketState {
+    Normal,         // 正常市场
+    HighVolatility, // 高波动市场
+    Extreme,        // 极端市场状况
+    ThinLiquidity,  // 流动性不足
+    Flash,          // 闪崩/闪涨
+    Consolidation,  // 盘整状态
+}
+
+impl MarketState {
+    pub(super) fn as_str(&self) -> &'static str {
+        match self {
+            MarketState::Normal => "正常市场",
+            MarketState::HighVolatility => "高波动市场",
+            MarketState::Extreme => "极端市场状况",
+            MarketState::ThinLiquidity => "流动性不足",
+            MarketState::Flash => "闪崩/闪涨",
+            MarketState::Consolidation => "盘整状态",
+        }
+    }
+
+    pub(super) fn as_english(&self) -> &'static str {
+        match self {
+            MarketState::Normal => "Normal",
+            MarketState::HighVolatility => "High Volatility",
+            MarketState::Extreme => "Extreme",
+            MarketState::ThinLiquidity => "Thin Liquidity",
+            MarketState::Flash => "Flash Move",
+            MarketState::Consolidation => "Consolidation",
+        }
+    }
+
+    pub(super) fn risk_level(&self) -> u8 {
+        match self {
+            MarketState::Normal => 1,
+            MarketState::Consolidation => 1,
+            MarketState::HighVolatility => 3,
+            MarketState::ThinLiquidity => 4,
+            MarketState::Extreme => 5,
+            MarketState::Flash => 5,
+        }
+    }
+
+    pub(super) fn requires_conservative_strategy(&self) -> bool {
+        matches!(
+            self,
+            MarketState::Extreme | MarketState::Flash | MarketState::ThinLiquidity
+        )
+    }
+
+    pub(super) fn should_pause_trading(&self) -> bool {
+        matches!(self, MarketState::Extreme | MarketState::Flash)
+    }
+
+    pub(super) fn grid_reduction_factor(&self) -> f64 {
+        match self {
+            MarketState::Normal => 1.0,
+            MarketState::Consolidation => 1.0,
+            MarketState::HighVolatility => 0.8,
+            MarketState::ThinLiquidity => 0.6,
+            MarketState::Extreme => 0.4,
+            MarketState::Flash => 0.2,
+        }
+    }
+}
+
+// 市场状态切换记录（最多保留的条数）
+pub(super) const MAX_MARKET_STATE_TRANSITIONS: usize = 50;
+
+// 市场状态切换记录
+#[derive(Debug, Clone)]
+pub(super) struct MarketStateTransition {
+    pub(super) timestamp: SystemTime,
+    pub(super) from: MarketState,
+    pub(super) to: MarketState,
+    pub(super) confirm_ticks: u32, // 切换前新状态连续出现的次数
+}
+
+// 市场状态确认器：新状态需连续出现 N 次才切换，避免指标在阈值附近抖动导致频繁改网格
+#[derive(Debug, Clone)]
+pub(super) struct MarketStateTracker {
+    pub(super) confirmed: MarketState,
+    pub(super) candidate: Option<MarketState>,
+    pub(super) candidate_ticks: u32,
+    pub(super) transitions: VecDeque<MarketStateTransition>,
+}
+
+impl Default for MarketStateTracker {
+    fn default() -> Self {
+        Self {
+            confirmed: MarketState::Normal,
+            candidate: None,
+            candidate_ticks: 0,
+            transitions: VecDeque::with_capacity(MAX_MARKET_STATE_TRANSITIONS),
+        }
+    }
+}
+
+impl MarketStateTracker {
+    /// 当前确认的市场状态
+    pub(super) fn confirmed(&self) -> &MarketState {
+        &self.confirmed
+    }
+
+    /// 输入本次检测到的状态，返回确认后的状态
+    ///
+    /// 需要暂停交易的状态（极端、闪崩）立即生效，其余状态需连续出现 `confirm_ticks` 次。
+    pub(super) fn observe(&mut self, detected: MarketState, confirm_ticks: u32) -> MarketState {
+        if detected == self.confirmed {
+            self.candidate = None;
+            self.candidate_ticks = 0;
+            return detected;
+        }
+
+        if self.candidate.as_ref() == Some(&detected) {
+            self.candidate_ticks += 1;
+        } else {
+            self.candidate = Some(detected.clone());
+            self.candidate_ticks = 1;
+        }
+
+        if detected.should_pause_trading() || self.candidate_ticks >= confirm_ticks.max(1) {
+            info!(
+                "🔀 市场状态切换: {} -> {} (连续{}次)",
+                self.confirmed.localized(),
+                detected.localized(),
+                self.candidate_ticks
+            );
+            if self.transitions.len() >= MAX_MARKET_STATE_TRANSITIONS {
+                self.transitions.pop_front();
+            }
+            self.transitions.push_back(MarketStateTransition {
+                timestamp: SystemTime::now(),
+                from: self.confirmed.clone(),
+                to: detected.clone(),
+                confirm_ticks: self.candidate_ticks,
+            });
+            self.confirmed = detected;
+            self.candidate = None;
+            self.candidate_ticks = 0;
+        } else {
+            debug!(
+                "⏳ 市场状态待确认: {} -> {} ({}/{})",
+                self.confirmed.localized(),
+                detected.localized(),
+                self.candidate_ticks,
+                confirm_ticks
+            );
+        }
+
+        self.confirmed.clone()
+    }
+
+    /// 最近的状态切换记录
+    pub(super) fn recent_transitions(&self) -> &VecDeque<MarketStateTransition> {
+        &self.transitions
+    }
+}
+
+impl MarketTrend {
+    pub(super) fn as_str(&self) -> &'static str {
+        match self {
+            MarketTrend::Upward => "上升",
+            MarketTrend::Downward => "下降",
+            MarketTrend::Sideways => "震荡",
+        }
+    }
+
+    /// 获取趋势的英文名称
+    pub(super) fn as_english(&self) -> &'static str {
+        match self {
+            MarketTrend::Upward => "Upward",
+            MarketTrend::Downward => "Downward",
+            MarketTrend::Sideways => "Sideways",
+        }
+    }
+
+    /// 判断是否为上升趋势
+    pub(super) fn is_bullish(&self) -> bool {
+        matches!(self, MarketTrend::Upward)
+    }
+
+    /// 判断是否为下降趋势
+    pub(super) fn is_bearish(&self) -> bool {
+        matches!(self, MarketTrend::Downward)
+    }
+
+    /// 判断是否为震荡趋势
+    pub(super) fn is_sideways(&self) -> bool {
+        matches!(self, MarketTrend::Sideways)
+    }
+}
+
+// 市场分析结果
+#[derive(Debug, Clone)]
+pub(super) struct MarketAnalysis {
+    pub(super) volatility: f64,
+    pub(super) trend: MarketTrend,
+    pub(super) rsi: f64,
+    pub(super) short_ma: f64,
+    pub(super) long_ma: f64,
+    pub(super) price_change_5min: f64,    // 5分钟价格变化率
+    pub(super) market_state: MarketState, // 市场状态
+    pub(super) liquidity_score: f64,      // 流动性评分 (0-100)
+    pub(super) price_stability: f64,      // 价格稳定性 (0-100)
+    #[allow(dead_code)]
+    pub(super) volume_anomaly: f64, // 成交量异常度 (0-100)
+}
+
+// 分母绝对值小于该值时视为0，指标返回中性值
+const DENOMINATOR_EPSILON: f64 = 1e-12;
+
+/// 有效价格：有限且大于0，指标计算跳过其余的价格点
+pub(super) fn is_valid_price(price: f64) -> bool {
+    price.is_finite() && price > 0.0
+}
+
+/// 过滤掉非正和非有限的价格
+fn valid_prices(prices: &[f64]) -> Vec<f64> {
+    prices
+        .iter()
+        .copied()
+        .filter(|&p| is_valid_price(p))
+        .collect()
+}
+
+// 计算K线振幅
+pub(super) fn calculate_amplitude(klines: &[f64]) -> (f64, f64) {
+    let klines = valid_prices(klines);
+    // 少于2根K线无法计算振幅
+    if klines.len() < 2 {
+        return (0.0, 0.0);
+    }
+
+    let mut positive_amplitudes = Vec::new();
+    let mut negative_amplitudes = Vec::new();
+
+    for pair in klines.windows(2) {
+        let change = (pair[1] - pair[0]) / pair[0];
+        if change > 0.0 {
+            positive_amplitudes.push(change);
+        } else {
+            negative_amplitudes.push(change.abs());
+        }
+    }
+
+    let avg_positive = if !positive_amplitudes.is_empty() {
+        positive_amplitudes.iter().sum::<f64>() / positive_amplitudes.len() as f64
+    } else {
+        0.0
+    };
+
+    let avg_negative = if !negative_amplitudes.is_empty() {
+        negative_amplitudes.iter().sum::<f64>() / negative_amplitudes.len() as f64
+    } else {
+        0.0
+    };
+
+    (avg_positive, avg_negative)
+}
+
+// 计算真实K线振幅：平均上影幅度（最高/开盘）和下影幅度（开盘/最低）
+pub(super) fn calculate_kline_amplitude(klines: &[Kline]) -> (f64, f64) {
+    let valid: Vec<&Kline> = klines
+        .iter()
+        .filter(|k| is_valid_price(k.open) && k.high.is_finite() && k.low.is_finite())
+        .collect();
+    if valid.is_empty() {
+        return (0.0, 0.0);
+    }
+
+    let avg_up = valid
+        .iter()
+        .map(|k| (k.high - k.open) / k.open)
+        .sum::<f64>()
+        / valid.len() as f64;
+    let avg_down =
+        valid.iter().map(|k| (k.open - k.low) / k.open).sum::<f64>() / valid.len() as f64;
+
+    (avg_up, avg_down)
+}
+
+// 用价格历史近似K线，每 bar_size 个价格点合成一根
+pub(super) fn klines_from_prices(prices: &[f64], bar_size: usize) -> Vec<Kline> {
+    if bar_size == 0 {
+        return Vec::new();
+    }
+
+    valid_prices(prices)
+        .chunks(bar_size)
+        .map(|chunk| Kline {
+            time_open: 0,
+            open: chunk[0],
+            high: chunk.iter().fold(f64::MIN, |a, &b| a.max(b)),
+            low: chunk.iter().fold(f64::MAX, |a, &b| a.min(b)),
+            close: chunk[chunk.len() - 1],
+            volume: 0.0,
+        })
+        .collect()
+}
+
+// 计算ATR（平均真实波幅），使用 Wilder 平滑
+pub(super) fn calculate_atr(klines: &[Kline], period: usize) -> f64 {
+    if period == 0 || klines.len() < 2 {
+        return 0.0;
+    }
+
+    let true_ranges: Vec<f64> = klines
+        .windows(2)
+        .map(|w| {
+            let prev_close = w[0].close;
+            (w[1].high - w[1].low)
+                .max((w[1].high - prev_close).abs())
+                .max((w[1].low - prev_close).abs())
+        })
+        .filter(|tr| tr.is_finite())
+        .collect();
+    if true_ranges.is_empty() {
+        return 0.0;
+    }
+
+    // 数据不足一个周期时使用简单平均
+    if true_ranges.len() < period {
+        return true_ranges.iter().sum::<f64>() / true_ranges.len() as f64;
+    }
+
+    let mut atr = true_ranges[..period].iter().sum::<f64>() / period as f64;
+    for tr in &true_ranges[period..] {
+        atr = (atr * (period - 1) as f64 + tr) / period as f64;
+    }
+    atr
+}
+
+// ATR 间距模式：单格间距 = k × ATR / 当前价格，限制在配置的最小/最大间距之间，数据不足时返回 None
+// 优先使用订阅的真实K线，不足一个周期时用价格历史近似
+pub(super) fn atr_grid_spacing(
+    price_history: &[f64],
+    klines: &[Kline],
+    current_price: f64,
+    grid_config: &crate::config::GridConfig,
+) -> Option<f64> {
+    let approximated;
+    let klines = if klines.len() > grid_config.atr_period {
+        klines
+    } else {
+        approximated = klines_from_prices(price_history, grid_config.atr_bar_size);
+        approximated.as_slice()
+    };
+    if current_price <= 0.0 || klines.len() <= grid_config.atr_period {
+        return None;
+    }
+
+    let atr = calculate_atr(klines, grid_config.atr_period);
+    if atr <= 0.0 {
+        return None;
+    }
+
+    Some(
+        (grid_config.atr_multiplier * atr / current_price)
+            .clamp(grid_config.min_grid_spacing, grid_config.max_grid_spacing),
+    )
+}
+
+// 计算市场波动率
+// 非正价格（如0）会导致除零，计算前跳过
+pub(super) fn calculate_market_volatility(price_history: &[f64]) -> f64 {
+    let price_history = valid_prices(price_history);
+    if price_history.len() < 2 {
+        return 0.0;
+    }
+
+    let price_changes: Vec<f64> = price_history
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]) / pair[0])
+        .collect();
+
+    // 计算标准差
+    let mean = price_changes.iter().sum::<f64>() / price_changes.len() as f64;
+    let variance = price_changes
+        .iter()
+        .map(|x| (x - mean).powi(2))
+        .sum::<f64>()
+        / price_changes.len() as f64;
+
+    let volatility = variance.sqrt() * (price_history.len() as f64).sqrt();
+    if volatility.is_finite() {
+        volatility
+    } else {
+        0.0
+    }
+}
+
+// 计算移动平均线
+pub(super) fn calculate_moving_average(prices: &[f64], period: usize) -> f64 {
+    let prices = valid_prices(prices);
+    if prices.is_empty() || period == 0 {
+        return 0.0;
+    }
+
+    if prices.len() < period {
+        return prices.iter().sum::<f64>() / prices.len() as f64;
+    }
+
+    let start_index = prices.len() - period;
+    prices[start_index..].iter().sum::<f64>() / period as f64
+}
+
+// 计算RSI指标
+pub(super) fn calculate_rsi(prices: &[f64], period: usize) -> f64 {
+    let prices = valid_prices(prices);
+    if period == 0 || prices.len() < period + 1 {
+        return 50.0; // 默认中性值
+    }
+
+    let mut gains = 0.0;
+    let mut losses = 0.0;
+
+    for i in (prices.len() - period)..prices.len() {
+        let change = prices[i] - prices[i - 1];
+        if change > 0.0 {
+            gains += change;
+        } else {
+            losses += change.abs();
+        }
+    }
+
+    if losses < DENOMINATOR_EPSILON {
+        // 价格完全不变时没有涨跌，返回中性值
+        return if gains < DENOMINATOR_EPSILON {
+            50.0
+        } else {
+            100.0
+        };
+    }
+
+    let rs = gains / losses;
+    100.0 - (100.0 / (1.0 + rs))
+}
+
+// RSI 周期
+pub(super) const RSI_PERIOD: usize = 14;
+// 短期/长期均线周期
+pub(super) const SHORT_MA_PERIOD: usize = 7;
+pub(super) const LONG_MA_PERIOD: usize = 25;
+// 累计多少次更新后重新精确求和，消除滑动窗口加减带来的浮点误差
+pub(super) const INDICATOR_RESYNC_INTERVAL: u64 = 1000;
+
+/// 增量技术指标状态
+///
+/// 每次推入新价格时以 O(1) 更新均线、EMA、Wilder 平滑 RSI 和波动率，
+/// 避免主循环每个 tick 都对整个价格历史重新求和。
+#[derive(Debug, Clone)]
+pub(super) struct IndicatorState {
+    pub(super) last_price: Option<f64>,
+    pub(super) updates_since_resync: u64,
+    // 简单移动平均（滑动窗口 + 累计和）
+    pub(super) short_window: VecDeque<f64>,
+    pub(super) short_sum: f64,
+    pub(super) long_window: VecDeque<f64>,
+    pub(super) long_sum: f64,
+    // 指数移动平均
+    pub(super) short_ema: Option<f64>,
+    pub(super) long_ema: Option<f64>,
+    // Wilder 平滑 RSI
+    pub(super) rsi_seed_count: usize,
+    pub(super) avg_gain: f64,
+    pub(super) avg_loss: f64,
+    // 波动率：价格变化率的滑动窗口
+    pub(super) volatility_window_size: usize,
+    pub(super) returns: VecDeque<f64>,
+    pub(super) returns_sum: f64,
+    pub(super) returns_sq_sum: f64,
+}
+
+impl Default for IndicatorState {
+    fn default() -> Self {
+        Self::new(60)
+    }
+}
+
+impl IndicatorState {
+    /// `history_length` 与价格历史长度保持一致，用于波动率窗口
+    pub(super) fn new(history_length: usize) -> Self {
+        let volatility_window_size = history_length.saturating_sub(1).max(1);
+        Self {
+            last_price: None,
+            updates_since_resync: 0,
+            short_window: VecDeque::with_capacity(SHORT_MA_PERIOD + 1),
+            short_sum: 0.0,
+            long_window: VecDeque::with_capacity(LONG_MA_PERIOD + 1),
+            long_sum: 0.0,
+            short_ema: None,
+            long_ema: None,
+            rsi_seed_count: 0,
+            avg_gain: 0.0,
+            avg_loss: 0.0,
+            volatility_window_size,
+            returns: VecDeque::with_capacity(volatility_window_size + 1),
+            returns_sum: 0.0,
+            returns_sq_sum: 0.0,
+        }
+    }
+
+    /// 推入新价格并增量更新全部指标
+    pub(super) fn push(&mut self, price: f64) {
+        if !is_valid_price(price) {
+            return;
+        }
+
+        self.updates_since_resync += 1;
+
+        // 简单移动平均
+        Self::push_window(
+            &mut self.short_window,
+            &mut self.short_sum,
+            price,
+            SHORT_MA_PERIOD,
+        );
+        Self::push_window(
+            &mut self.long_window,
+            &mut self.long_sum,
+            price,
+            LONG_MA_PERIOD,
+        );
+
+        // 指数移动平均
+        self.short_ema = Some(Self::next_ema(self.short_ema, price, SHORT_MA_PERIOD));
+        self.long_ema = Some(Self::next_ema(self.long_ema, price, LONG_MA_PERIOD));
+
+        if let Some(last_price) = self.last_price {
+            let change = price - last_price;
+            let gain = change.max(0.0);
+            let loss = (-change).max(0.0);
+
+            // RSI：前 period 个变化取简单平均作为种子，之后使用 Wilder 平滑
+            if self.rsi_seed_count < RSI_PERIOD {
+                self.avg_gain += gain / RSI_PERIOD as f64;
+                self.avg_loss += loss / RSI_PERIOD as f64;
+                self.rsi_seed_count += 1;
+            } else {
+                let period = RSI_PERIOD as f64;
+                self.avg_gain = (self.avg_gain * (period - 1.0) + gain) / period;
+                self.avg_loss = (self.avg_loss * (period - 1.0) + loss) / period;
+            }
+
+            // 波动率
+            let ret = change / last_price;
+            self.returns.push_back(ret);
+            self.returns_sum += ret;
+            self.returns_sq_sum += ret * ret;
+            if self.returns.len() > self.volatility_window_size {
+                if let Some(old) = self.returns.pop_front() {
+                    self.returns_sum -= old;
+                    self.returns_sq_sum -= old * old;
+                }
+            }
+        }
+
+        self.last_price = Some(price);
+
+        if self.updates_since_resync >= INDICATOR_RESYNC_INTERVAL {
+            self.resync_sums();
+        }
+    }
+
+    pub(super) fn push_window(
+        window: &mut VecDeque<f64>,
+        sum: &mut f64,
+        price: f64,
+        period: usize,
+    ) {
+        window.push_back(price);
+        *sum += price;
+        if window.len() > period {
+            if let Some(old) = window.pop_front() {
+                *sum -= old;
+            }
+        }
+    }
+
+    pub(super) fn next_ema(prev: Option<f64>, price: f64, period: usize) -> f64 {
+        match prev {
+            Some(prev) => {
+                let alpha = 2.0 / (period as f64 + 1.0);
+                prev + alpha * (price - prev)
+            }
+            None => price,
+        }
+    }
+
+    /// 重新精确计算累计和（摊还 O(1)）
+    pub(super) fn resync_sums(&mut self) {
+        self.short_sum = self.short_window.iter().sum();
+        self.long_sum = self.long_window.iter().sum();
+        self.returns_sum = self.returns.iter().sum();
+        self.returns_sq_sum = self.returns.iter().map(|r| r * r).sum();
+        self.updates_since_resync = 0;
+    }
+
+    /// 已累积的价格数量（不超过长期均线窗口）
+    pub(super) fn sample_count(&self) -> usize {
+        self.long_window.len()
+    }
+
+    pub(super) fn short_ma(&self) -> f64 {
+        if self.short_window.is_empty() {
+            return 0.0;
+        }
+        self.short_sum / self.short_window.len() as f64
+    }
+
+    pub(super) fn long_ma(&self) -> f64 {
+        if self.long_window.is_empty() {
+            return 0.0;
+        }
+        self.long_sum / self.long_window.len() as f64
+    }
+
+    pub(super) fn short_ema(&self) -> f64 {
+        self.short_ema.unwrap_or(0.0)
+    }
+
+    pub(super) fn long_ema(&self) -> f64 {
+        self.long_ema.unwrap_or(0.0)
+    }
+
+    pub(super) fn rsi(&self) -> f64 {
+        if self.rsi_seed_count < RSI_PERIOD {
+            return 50.0; // 默认中性值
+        }
+        if self.avg_loss < DENOMINATOR_EPSILON {
+            // 与 calculate_rsi 一致：价格完全不变时返回中性值
+            return if self.avg_gain < DENOMINATOR_EPSILON {
+                50.0
+            } else {
+                100.0
+            };
+        }
+        let rs = self.avg_gain / self.avg_loss;
+        100.0 - (100.0 / (1.0 + rs))
+    }
+
+    /// 与 `calculate_market_volatility` 口径一致：变化率标准差 × sqrt(价格数量)
+    pub(super) fn volatility(&self) -> f64 {
+        let n = self.returns.len();
+        if n == 0 {
+            return 0.0;
+        }
+        let mean = self.returns_sum / n as f64;
+        let variance = (self.returns_sq_sum / n as f64 - mean * mean).max(0.0);
+        variance.sqrt() * ((n + 1) as f64).sqrt()
+    }
+}
+
+// 波动率状态阈值（进入/退出），退出阈值更宽松形成迟滞，避免在阈值附近来回切换
+pub(super) const EXTREME_VOLATILITY_ENTER: f64 = 0.08;
+pub(super) const EXTREME_VOLATILITY_EXIT: f64 = 0.07;
+pub(super) const HIGH_VOLATILITY_ENTER: f64 = 0.03;
+pub(super) const HIGH_VOLATILITY_EXIT: f64 = 0.025;
+pub(super) const CONSOLIDATION_VOLATILITY_ENTER: f64 = 0.005;
+pub(super) const CONSOLIDATION_VOLATILITY_EXIT: f64 = 0.006;
+
+// 检测市场状态，`previous_state` 为当前已确认的状态，用于选择进入或退出阈值
+pub(super) fn detect_market_state(
+    price_history: &[f64],
+    volatility: f64,
+    price_change_5min: f64,
+    rsi: f64,
+    previous_state: &MarketState,
+) -> (MarketState, f64, f64, f64) {
+    let mut liquidity_score = 100.0;
+    let mut volume_anomaly = 0.0;
+
+    // 已处于某状态时用退出阈值判断，未处于时用进入阈值判断
+    let extreme_threshold = if *previous_state == MarketState::Extreme {
+        EXTREME_VOLATILITY_EXIT
+    } else {
+        EXTREME_VOLATILITY_ENTER
+    };
+    let high_volatility_threshold = if matches!(
+        previous_state,
+        MarketState::HighVolatility | MarketState::Extreme
+    ) {
+        HIGH_VOLATILITY_EXIT
+    } else {
+        HIGH_VOLATILITY_ENTER
+    };
+    let consolidation_threshold = if *previous_state == MarketState::Consolidation {
+        CONSOLIDATION_VOLATILITY_EXIT
+    } else {
+        CONSOLIDATION_VOLATILITY_ENTER
+    };
+
+    // 1. 基于波动率判断
+    let (volatility_state, mut price_stability) = if volatility > extreme_threshold {
+        // 极端波动 (日波动率 > 8%)
+        volume_anomaly = 80.0;
+        (MarketState::Extreme, 10.0)
+    } else if volatility > 0.05 {
+        // 高波动 (日波动率 > 5%)
+        volume_anomaly = 60.0;
+        (MarketState::HighVolatility, 30.0)
+    } else if volatility > high_volatility_threshold {
+        // 中等波动 (日波动率 > 3%)
+        volume_anomaly = 30.0;
+        (MarketState::HighVolatility, 60.0)
+    } else if volatility < consolidation_threshold {
+        // 极低波动，可能是盘整
+        (MarketState::Consolidation, 95.0)
+    } else {
+        // 正常波动
+        (MarketState::Normal, 80.0)
+    };
+
+    // 2. 基于短期价格变化判断闪崩/闪涨
+    let flash_threshold = 0.05; // 5分钟内5%的变化
+    if price_change_5min.abs() > flash_threshold {
+        price_stability = 5.0;
+        volume_anomaly = 95.0;
+        liquidity_score = 20.0;
+        return (
+            MarketState::Flash,
+            liquidity_score,
+            price_stability,
+            volume_anomaly,
+        );
+    }
+
+    // 3. 基于RSI判断极端状态
+    if rsi > 85.0 || rsi < 15.0 {
+        // RSI极端值，可能是超买超卖
+        price_stability = (price_stability * 0.7_f64).max(20.0_f64);
+        volume_anomaly = (volume_anomaly + 20.0_f64).min(100.0_f64);
+
+        if volatility > 0.05 {
+            return (
+                MarketState::Extreme,
+                liquidity_score,
+                price_stability,
+                volume_anomaly,
+            );
+        }
+    }
+
+    // 4. 流动性评估
+    if price_history.len() >= 10 {
+        let recent_prices = &price_history[price_history.len() - 10..];
+        let price_gaps: Vec<f64> = recent_prices
+            .windows(2)
+            .map(|w| (w[1] / w[0] - 1.0).abs())
+            .collect();
+
+        let avg_gap = price_gaps.iter().sum::<f64>() / price_gaps.len() as f64;
+        let max_gap = price_gaps.iter().fold(0.0_f64, |a, &b| a.max(b));
+
+        // 如果价格跳跃过大，可能是流动性不足
+        if max_gap > 0.02 || avg_gap > 0.005 {
+            liquidity_score = f64::max(100.0 - max_gap * 2000.0, 10.0);
+            if liquidity_score < 40.0 {
+                return (
+                    MarketState::ThinLiquidity,
+                    liquidity_score,
+                    price_stability,
+                    volume_anomaly,
+                );
+            }
+        }
+    }
+
+    // 5. 综合判断
+    let final_state = match volatility_state {
+        MarketState::Extreme => {
+            if liquidity_score < 50.0 {
+                MarketState::Extreme
+            } else {
+                MarketState::HighVolatility
+            }
+        }
+        other => other,
+    };
+
+    (
+        final_state,
+        liquidity_score,
+        price_stability,
+        volume_anomaly,
+    )
+}
+
+// 分析市场趋势
+// 订阅的真实K线足够时使用K线收盘价计算；否则指标状态已累积足够数据时直接读取增量结果，再否则回退到全量计算
+pub(super) fn analyze_market_trend(
+    price_history: &[f64],
+    indicators: &IndicatorState,
+    klines: &[Kline],
+    previous_state: &MarketState,
+) -> MarketAnalysis {
+    let kline_closes: Vec<f64> = if klines.len() > LONG_MA_PERIOD {
+        klines.iter().map(|k| k.close).collect()
+    } else {
+        Vec::new()
+    };
+    let use_klines = !kline_closes.is_empty();
+
+    if !use_klines && price_history.len() < 25 {
+        return MarketAnalysis {
+            volatility: 0.0,
+            trend: MarketTrend::Sideways,
+            rsi: 50.0,
+            short_ma: price_history.last().copied().unwrap_or(0.0),
+            long_ma: price_history.last().copied().unwrap_or(0.0),
+            price_change_5min: 0.0,
+            market_state: MarketState::Normal,
+            liquidity_score: 100.0,
+            price_stability: 100.0,
+            volume_anomaly: 0.0,
+        };
+    }
+
+    let (volatility, short_ma, long_ma, rsi) = if use_klines {
+        (
+            calculate_market_volatility(&kline_closes),
+            calculate_moving_average(&kline_closes, SHORT_MA_PERIOD),
+            calculate_moving_average(&kline_closes, LONG_MA_PERIOD),
+            calculate_rsi(&kline_closes, RSI_PERIOD),
+        )
+    } else if indicators.sample_count() >= LONG_MA_PERIOD {
+        (
+            indicators.volatility(),
+            indicators.short_ma(),
+            indicators.long_ma(),
+            indicators.rsi(),
+        )
+    } else {
+        (
+            calculate_market_volatility(price_history),
+            calculate_moving_average(price_history, SHORT_MA_PERIOD),
+            calculate_moving_average(price_history, LONG_MA_PERIOD),
+            calculate_rsi(price_history, RSI_PERIOD),
+        )
+    };
+
+    // 计算5分钟价格变化：有K线时按开盘时间找5分钟前的K线，否则假设最后几个数据点代表最近5分钟
+    let price_change_5min = if use_klines {
+        let last = klines[klines.len() - 1];
+        let target_time = last.time_open.saturating_sub(5 * 60 * 1000);
+        let old_price = klines
+            .iter()
+            .rev()
+            .find(|k| k.time_open <= target_time)
+            .map(|k| k.close)
+            .unwrap_or(klines[0].open);
+        if old_price > 0.0 {
+            (last.close - old_price) / old_price
+        } else {
+            0.0
+        }
+    } else if price_history.len() >= 5 {
+        let recent_price = price_history[price_history.len() - 1];
+        let old_price = price_history[price_history.len() - 5];
+        (recent_price - old_price) / old_price
+    } else {
+        0.0
+    };
+
+    // 判断趋势
+    let trend = if short_ma > long_ma * 1.05 && rsi > 55.0 {
+        MarketTrend::Upward
+    } else if short_ma < long_ma * 0.95 && rsi < 45.0 {
+        MarketTrend::Downward
+    } else {
+        MarketTrend::Sideways
+    };
+
+    // 检测市场状态
+    let series = if use_klines {
+        kline_closes.as_slice()
+    } else {
+        price_history
+    };
+    let (market_state, liquidity_score, price_stability, volume_anomaly) =
+        detect_market_state(series, volatility, price_change_5min, rsi, previous_state);
+
+    MarketAnalysis {
+        volatility,
+        trend,
+        rsi,
+        short_ma,
+        long_ma,
+        price_change_5min,
+        market_state,
+        liquidity_score,
+        price_stability,
+        volume_anomaly,
+    }
+}
+
+/// 计算趋势强度
+pub(super) fn calculate_trend_strength(price_history: &[f64]) -> f64 {
+    let price_history = valid_prices(price_history);
+    if price_history.len() < 10 {
+        return 0.5; // 默认中等强度
+    }
+
+    let recent_prices = &price_history[price_history.len() - 10..];
+    let first_price = recent_prices[0];
+
+    // 计算线性回归斜率作为趋势强度
+    let n = recent_prices.len() as f64;
+    let sum_x: f64 = (0..recent_prices.len()).map(|i| i as f64).sum();
+    let sum_y: f64 = recent_prices.iter().sum();
+    let sum_xy: f64 = recent_prices
+        .iter()
+        .enumerate()
+        .map(|(i, &price)| i as f64 * price)
+        .sum();
+    let sum_x2: f64 = (0..recent_prices.len()).map(|i| (i as f64).powi(2)).sum();
+
+    let denominator = n * sum_x2 - sum_x.powi(2);
+    if denominator.abs() < DENOMINATOR_EPSILON {
+        return 0.5;
+    }
+
+    let slope = (n * sum_xy - sum_x * sum_y) / denominator;
+    let trend_strength = (slope / first_price).abs().min(1.0);
+    if trend_strength.is_finite() {
+        trend_strength
+    } else {
+        0.5
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amplitude_of_empty_and_single_price_is_zero() {
+        assert_eq!(calculate_amplitude(&[]), (0.0, 0.0));
+        assert_eq!(calculate_amplitude(&[100.0]), (0.0, 0.0));
+        // 过滤掉无效价格后只剩一根同样无法计算
+        assert_eq!(calculate_amplitude(&[0.0, 100.0, f64::NAN]), (0.0, 0.0));
+    }
+
+    #[test]
+    fn amplitude_averages_up_and_down_moves_separately() {
+        let (up, down) = calculate_amplitude(&[100.0, 110.0, 99.0]);
+        assert!((up - 0.1).abs() < 1e-12);
+        assert!((down - 0.1).abs() < 1e-12);
+    }
+
+    fn flat_kline(price: f64) -> Kline {
+        Kline {
+            time_open: 0,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: 0.0,
+        }
+    }
+
+    #[test]
+    fn trend_strength_is_neutral_for_short_windows_and_flat_for_constant_prices() {
+        assert_eq!(calculate_trend_strength(&[]), 0.5);
+        assert_eq!(calculate_trend_strength(&[100.0; 9]), 0.5);
+        // 非正价格先被过滤，剩余不足10个时同样返回中性值
+        let mut prices = vec![100.0; 9];
+        prices.push(0.0);
+        assert_eq!(calculate_trend_strength(&prices), 0.5);
+
+        assert_eq!(calculate_trend_strength(&[100.0; 20]), 0.0);
+    }
+
+    #[test]
+    fn volatility_skips_non_positive_prices() {
+        assert_eq!(calculate_market_volatility(&[]), 0.0);
+        assert_eq!(calculate_market_volatility(&[100.0]), 0.0);
+        assert_eq!(calculate_market_volatility(&[100.0; 10]), 0.0);
+
+        let volatility = calculate_market_volatility(&[100.0, 0.0, 101.0, -5.0, 99.0]);
+        assert!(volatility.is_finite() && volatility > 0.0);
+    }
+
+    #[test]
+    fn moving_average_and_rsi_handle_short_and_empty_windows() {
+        assert_eq!(calculate_moving_average(&[], 5), 0.0);
+        assert_eq!(calculate_moving_average(&[100.0, 102.0], 0), 0.0);
+        assert_eq!(calculate_moving_average(&[100.0, 102.0], 5), 101.0);
+        assert_eq!(calculate_moving_average(&[1.0, 2.0, 3.0, 4.0], 2), 3.5);
+
+        assert_eq!(calculate_rsi(&[], RSI_PERIOD), 50.0);
+        assert_eq!(calculate_rsi(&[100.0; 5], 0), 50.0);
+        assert_eq!(calculate_rsi(&[100.0; RSI_PERIOD], RSI_PERIOD), 50.0);
+        assert_eq!(calculate_rsi(&[100.0; 30], RSI_PERIOD), 50.0);
+
+        let rising: Vec<f64> = (1..=30).map(|i| i as f64).collect();
+        assert_eq!(calculate_rsi(&rising, RSI_PERIOD), 100.0);
+    }
+
+    #[test]
+    fn atr_handles_degenerate_input() {
+        assert_eq!(calculate_atr(&[], 14), 0.0);
+        assert_eq!(calculate_atr(&[flat_kline(100.0)], 14), 0.0);
+        assert_eq!(calculate_atr(&[flat_kline(100.0); 5], 0), 0.0);
+        assert_eq!(calculate_atr(&[flat_kline(100.0); 30], 14), 0.0);
+        assert!(klines_from_prices(&[100.0, 101.0], 0).is_empty());
+    }
+
+    #[test]
+    fn incremental_indicators_stay_neutral_on_constant_prices() {
+        let mut state = IndicatorState::new(60);
+        assert_eq!(state.short_ma(), 0.0);
+        assert_eq!(state.rsi(), 50.0);
+        assert_eq!(state.volatility(), 0.0);
+
+        state.push(0.0);
+        state.push(f64::NAN);
+        assert_eq!(state.sample_count(), 0);
+
+        for _ in 0..40 {
+            state.push(100.0);
+        }
+        assert_eq!(state.short_ma(), 100.0);
+        assert_eq!(state.long_ema(), 100.0);
+        assert_eq!(state.rsi(), 50.0);
+        assert_eq!(state.volatility(), 0.0);
+    }
+}
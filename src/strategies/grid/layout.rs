@@ -0,0 +1,379 @@
+// 网格布点算法：等比（geometric）、等差（arithmetic）和做市价差（spread）
+
+/// 单侧最多生成的档位数，防止间距异常时无限布点
+const MAX_LAYOUT_LEVELS: usize = 1000;
+
+/// 单个网格档位
+#[derive(Debug, Clone, Copy)]
+pub(super) struct PriceLevel {
+    pub(super) price: f64,
+    pub(super) spacing: f64, // 该档到对手单价格的间距比例，买单对手价 = price × (1 + spacing)
+}
+
+/// 布点输入：网格中心、起始价、边界和已经过振幅/资金/ATR 调整的基础间距
+#[derive(Debug, Clone, Copy)]
+pub(super) struct LayoutCenter {
+    pub(super) is_buy: bool,
+    pub(super) center_price: f64, // 网格中心价
+    pub(super) market_price: f64, // 当前市价，挂单不越过市价
+    pub(super) start_price: f64,  // 起始价，第一档从这里向外布
+    pub(super) bound_price: f64,  // 边界价，买单为下限、卖单为上限
+    pub(super) base_spacing: f64, // 基础间距比例
+    pub(super) cost_price: f64,   // 持仓成本价，无持仓为0
+    pub(super) best_bid: Option<f64>,
+    pub(super) best_ask: Option<f64>,
+}
+
+pub(super) trait GridLayout: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// 从中心向外生成一侧的档位，买单价格递减、卖单价格递增
+    fn generate_levels(
+        &self,
+        center: &LayoutCenter,
+        grid_config: &crate::config::GridConfig,
+    ) -> Vec<PriceLevel>;
+}
+
+/// 等比布点：每档按比例间距 price × (1 ± spacing) 递推，有持仓时按成本价调整间距
+pub(super) struct GeometricLayout;
+
+/// 等差布点：相邻档位价格差固定为 grid_price_step（未配置时取中心价 × 基础间距）
+pub(super) struct ArithmeticLayout;
+
+/// 做市价差布点：第一档贴近盘口，买卖第一档相距 maker_spread，外侧档位按基础间距等比展开
+pub(super) struct SpreadLayout;
+
+/// 按 `grid_layout` 配置选择布点算法
+pub(super) fn grid_layout(grid_config: &crate::config::GridConfig) -> Box<dyn GridLayout> {
+    match grid_config.grid_layout.as_str() {
+        "arithmetic" => Box::new(ArithmeticLayout),
+        "spread" => Box::new(SpreadLayout),
+        _ => Box::new(GeometricLayout),
+    }
+}
+
+/// 从 `first` 开始按 `next` 递推档位，直到下一档越过边界；越界的档位不返回
+fn step_levels(
+    center: &LayoutCenter,
+    first: Option<PriceLevel>,
+    mut next: impl FnMut(f64) -> PriceLevel,
+) -> Vec<PriceLevel> {
+    let within_bound = |price: f64| {
+        if center.is_buy {
+            price > center.bound_price
+        } else {
+            price < center.bound_price
+        }
+    };
+
+    let mut levels = Vec::new();
+    let mut price = center.start_price;
+    if let Some(level) = first {
+        levels.push(level);
+        price = level.price;
+    }
+
+    while levels.len() < MAX_LAYOUT_LEVELS && price > 0.0 && within_bound(price) {
+        let level = next(price);
+        let moved = if center.is_buy {
+            level.price < price
+        } else {
+            level.price > price
+        };
+        if !moved || !level.price.is_finite() || level.price <= 0.0 || !within_bound(level.price) {
+            break;
+        }
+        price = level.price;
+        levels.push(level);
+    }
+    levels
+}
+
+impl GridLayout for GeometricLayout {
+    fn name(&self) -> &'static str {
+        "geometric"
+    }
+
+    fn generate_levels(
+        &self,
+        center: &LayoutCenter,
+        _grid_config: &crate::config::GridConfig,
+    ) -> Vec<PriceLevel> {
+        let base_spacing = center.base_spacing;
+        let cost_price = center.cost_price;
+
+        if center.is_buy {
+            step_levels(center, None, |price| {
+                // 成本价导向的间距调整：高于成本价时拉大间距避免高位密集买入，低于成本价时略微收窄
+                let cost_adjusted_spacing = if cost_price > 0.0 {
+                    let distance_from_cost = (price - cost_price) / cost_price;
+                    if distance_from_cost > 0.0 {
+                        base_spacing * (1.0 + distance_from_cost * 2.0)
+                    } else {
+                        base_spacing * (1.0 + distance_from_cost * 0.5).max(0.8)
+                    }
+                } else {
+                    base_spacing
+                };
+
+                // 距离网格中心越远，间距越大
+                let market_distance = (center.center_price - price) / center.center_price;
+                let market_adjusted_spacing = cost_adjusted_spacing * (1.0 + market_distance * 1.5);
+                let spacing = market_adjusted_spacing.min(base_spacing * 3.0);
+
+                PriceLevel {
+                    price: price - price * spacing,
+                    spacing,
+                }
+            })
+        } else {
+            step_levels(center, None, |price| {
+                // 有持仓时按成本价计算间距，保证每层都有足够利润
+                let increment = if cost_price > 0.0 {
+                    (cost_price * base_spacing).max(cost_price * 0.002)
+                } else {
+                    price * base_spacing
+                };
+                PriceLevel {
+                    price: price + increment,
+                    spacing: increment / price,
+                }
+            })
+        }
+    }
+}
+
+impl GridLayout for ArithmeticLayout {
+    fn name(&self) -> &'static str {
+        "arithmetic"
+    }
+
+    fn generate_levels(
+        &self,
+        center: &LayoutCenter,
+        grid_config: &crate::config::GridConfig,
+    ) -> Vec<PriceLevel> {
+        let step = if grid_config.grid_price_step > 0.0 {
+            grid_config.grid_price_step
+        } else {
+            center.center_price * center.base_spacing
+        };
+
+        step_levels(center, None, |price| {
+            let level_price = if center.is_buy {
+                price - step
+            } else {
+                price + step
+            };
+            PriceLevel {
+                price: level_price,
+                spacing: step / level_price,
+            }
+        })
+    }
+}
+
+impl GridLayout for SpreadLayout {
+    fn name(&self) -> &'static str {
+        "spread"
+    }
+
+    fn generate_levels(
+        &self,
+        center: &LayoutCenter,
+        grid_config: &crate::config::GridConfig,
+    ) -> Vec<PriceLevel> {
+        let half_spread = grid_config.maker_spread / 2.0;
+
+        // 第一档：中心价两侧各半个价差，不越过盘口最优价和市价，避免吃单
+        let first_price = if center.is_buy {
+            let quote = center.best_bid.unwrap_or(center.market_price);
+            (center.center_price * (1.0 - half_spread)).min(quote)
+        } else {
+            let quote = center.best_ask.unwrap_or(center.market_price);
+            (center.center_price * (1.0 + half_spread)).max(quote)
+        };
+        let within_bound = if center.is_buy {
+            first_price > center.bound_price
+        } else {
+            first_price < center.bound_price
+        };
+        if !within_bound || first_price <= 0.0 {
+            return Vec::new();
+        }
+
+        let first = PriceLevel {
+            price: first_price,
+            spacing: grid_config.maker_spread,
+        };
+        step_levels(center, Some(first), |price| {
+            let level_price = if center.is_buy {
+                price * (1.0 - center.base_spacing)
+            } else {
+                price * (1.0 + center.base_spacing)
+            };
+            PriceLevel {
+                price: level_price,
+                spacing: center.base_spacing,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::default_grid_config;
+    use super::*;
+
+    const CENTER: f64 = 100.0;
+    const LOWER: f64 = 90.0;
+    const UPPER: f64 = 110.0;
+    const SPACING: f64 = 0.01;
+
+    fn layout_config(layout: &str, price_step: f64) -> crate::config::GridConfig {
+        let mut grid_config = default_grid_config();
+        grid_config.grid_layout = layout.to_string();
+        grid_config.grid_price_step = price_step;
+        grid_config
+    }
+
+    fn center(is_buy: bool) -> LayoutCenter {
+        LayoutCenter {
+            is_buy,
+            center_price: CENTER,
+            market_price: CENTER,
+            start_price: CENTER,
+            bound_price: if is_buy { LOWER } else { UPPER },
+            base_spacing: SPACING,
+            cost_price: 0.0,
+            best_bid: None,
+            best_ask: None,
+        }
+    }
+
+    fn both_sides(grid_config: &crate::config::GridConfig) -> (Vec<PriceLevel>, Vec<PriceLevel>) {
+        let layout = grid_layout(grid_config);
+        (
+            layout.generate_levels(&center(true), grid_config),
+            layout.generate_levels(&center(false), grid_config),
+        )
+    }
+
+    #[test]
+    fn grid_layout_selects_configured_algorithm() {
+        for (configured, expected) in [
+            ("geometric", "geometric"),
+            ("arithmetic", "arithmetic"),
+            ("spread", "spread"),
+            ("unknown", "geometric"),
+        ] {
+            assert_eq!(
+                grid_layout(&layout_config(configured, 0.0)).name(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn levels_are_monotonic_and_inside_bounds() {
+        for (layout, price_step) in [
+            ("geometric", 0.0),
+            ("arithmetic", 0.0),
+            ("arithmetic", 1.5),
+            ("spread", 0.0),
+        ] {
+            let grid_config = layout_config(layout, price_step);
+            let (buys, sells) = both_sides(&grid_config);
+            assert!(
+                !buys.is_empty() && !sells.is_empty(),
+                "{} 未生成档位",
+                layout
+            );
+
+            for pair in buys.windows(2) {
+                assert!(pair[1].price < pair[0].price, "{} 买单价格未递减", layout);
+            }
+            for pair in sells.windows(2) {
+                assert!(pair[1].price > pair[0].price, "{} 卖单价格未递增", layout);
+            }
+            for level in &buys {
+                assert!(
+                    level.price > LOWER && level.price < CENTER,
+                    "{} 买单 {} 越界",
+                    layout,
+                    level.price
+                );
+            }
+            for level in &sells {
+                assert!(
+                    level.price < UPPER && level.price > CENTER,
+                    "{} 卖单 {} 越界",
+                    layout,
+                    level.price
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn arithmetic_step_uses_configured_price_step_or_falls_back() {
+        // (grid_price_step, 期望步长)：0 时取中心价 × 基础间距
+        for (price_step, expected_step) in [(2.0, 2.0), (0.0, CENTER * SPACING)] {
+            let grid_config = layout_config("arithmetic", price_step);
+            let (buys, sells) = both_sides(&grid_config);
+
+            assert!((buys[0].price - (CENTER - expected_step)).abs() < 1e-9);
+            assert!((sells[0].price - (CENTER + expected_step)).abs() < 1e-9);
+            for pair in buys.windows(2) {
+                assert!((pair[0].price - pair[1].price - expected_step).abs() < 1e-9);
+            }
+            for pair in sells.windows(2) {
+                assert!((pair[1].price - pair[0].price - expected_step).abs() < 1e-9);
+            }
+            for level in &buys {
+                assert!((level.spacing - expected_step / level.price).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn spread_first_levels_are_maker_spread_apart() {
+        for maker_spread in [0.002, 0.01] {
+            let mut grid_config = layout_config("spread", 0.0);
+            grid_config.maker_spread = maker_spread;
+            let (buys, sells) = both_sides(&grid_config);
+
+            let width = (sells[0].price - buys[0].price) / CENTER;
+            assert!(
+                (width - maker_spread).abs() < 1e-9,
+                "价差 {} 不等于 {}",
+                width,
+                maker_spread
+            );
+            assert!((buys[1].price - buys[0].price * (1.0 - SPACING)).abs() < 1e-9);
+            assert!((sells[1].price - sells[0].price * (1.0 + SPACING)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn spread_first_level_does_not_cross_the_book() {
+        let mut grid_config = layout_config("spread", 0.0);
+        grid_config.maker_spread = 0.01;
+        let layout = grid_layout(&grid_config);
+
+        let mut buy_center = center(true);
+        buy_center.best_bid = Some(99.0);
+        let mut sell_center = center(false);
+        sell_center.best_ask = Some(101.0);
+
+        assert_eq!(
+            layout.generate_levels(&buy_center, &grid_config)[0].price,
+            99.0
+        );
+        assert_eq!(
+            layout.generate_levels(&sell_center, &grid_config)[0].price,
+            101.0
+        );
+    }
+}
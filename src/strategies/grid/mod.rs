@@ -0,0 +1,8240 @@
+#![allow(dead_code)]
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use ethers::signers::{LocalWallet, Signer};
+use hyperliquid_rust_sdk::{
+    BaseUrl, ClientCancelRequest, ClientLimit, ClientOrder, ClientOrderRequest, ExchangeClient,
+    ExchangeDataStatus, ExchangeResponseStatus, InfoClient, Message, Subscription, UserData,
+};
+use log::{debug, error, info, warn};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+// 导入错误类型
+use super::error::GridStrategyError;
+
+// 导入性能类型
+use super::performance::system_time_serde;
+use super::performance::{
+    DrawdownPoint, EquityPoint, FillLatencyStats, PerformanceAnalyzer, PerformanceRecord,
+    PerformanceSnapshot, PeriodSummary, RollingSharpePoint, SlippageStats,
+};
+// 导入批处理优化器
+use super::batch_optimizer::BatchTaskOptimizer;
+// 导入下单执行器
+use super::executor::{OrderExecutor, SimulatedExecutor};
+use super::kline::{interval_millis, Kline, KlineBuffer, SUPPORTED_INTERVALS};
+use super::message_queue::spawn_message_relay;
+use super::orderbook::OrderBook;
+use super::preflight::{resolve_asset_precision, run_preflight_checks};
+use super::price_feed::PriceFeedMonitor;
+use super::rate_limiter::{acquire_api_permit, init_global_rate_limiter};
+use super::replay::{load_recording, spawn_recording_relay, spawn_replay, ReplayOptions};
+use super::risk_log::RiskEventLog;
+use super::state_backend::{
+    flush_remote_uploads, init_remote_state_backend, load_state_snapshot, save_state_snapshot,
+};
+use super::trading_window::{
+    describe_trading_windows, is_in_trading_window, parse_trading_windows,
+};
+// 导入指标导出
+use crate::control::{
+    control_channel, spawn_control_server, ControlCommand, ControlRequest, ControlResponse,
+};
+use crate::metrics::{spawn_metrics_server, MetricsRegistry};
+// 导入告警通知
+use crate::notify::{AlertManager, Severity};
+// 导入多语言支持
+use crate::i18n::Bilingual;
+use crate::tr_log;
+
+mod connection;
+mod indicators;
+mod layout;
+mod order_manager;
+mod params;
+mod risk;
+mod validate;
+
+use connection::*;
+use indicators::*;
+use layout::*;
+use order_manager::*;
+use params::*;
+use risk::*;
+pub use validate::{validate_deployment, CheckStatus};
+
+/// 安全的时间差计算，处理时间倒退的情况
+fn safe_duration_since(now: SystemTime, earlier: SystemTime) -> Duration {
+    match now.duration_since(earlier) {
+        Ok(duration) => duration,
+        Err(e) => {
+            // 时间倒退了，记录警告并返回一个合理的默认值
+            warn!("⚠️ 检测到系统时间倒退: {:?}", e);
+            // 返回一个较大的值，确保时间检查会触发
+            Duration::from_secs(3600) // 1小时，确保定期检查会执行
+        }
+    }
+}
+
+// 系统时钟异常标志：时间戳走兜底逻辑时置位，主循环据此暂停开新仓
+static CLOCK_ANOMALY: AtomicBool = AtomicBool::new(false);
+
+// 最近一次正常读取的时间戳及对应的单调时钟，用于时钟异常时推算时间
+static CLOCK_BASE: Mutex<Option<(u64, Instant)>> = Mutex::new(None);
+
+/// 系统时钟是否处于异常状态
+fn clock_anomaly_detected() -> bool {
+    CLOCK_ANOMALY.load(Ordering::SeqCst)
+}
+
+/// 安全的Unix时间戳获取
+///
+/// 系统时间早于Unix纪元时，以最近一次正常时间戳加上单调时钟的流逝时间作为兜底，
+/// 保证返回值不回退，同时置位时钟异常标志。
+fn safe_unix_timestamp() -> u64 {
+    let mut base = CLOCK_BASE.lock().unwrap_or_else(|e| e.into_inner());
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => {
+            let now = duration.as_secs();
+            *base = Some((now, Instant::now()));
+            if CLOCK_ANOMALY.swap(false, Ordering::SeqCst) {
+                info!("✅ 系统时钟已恢复正常");
+            }
+            now
+        }
+        Err(_) => {
+            if !CLOCK_ANOMALY.swap(true, Ordering::SeqCst) {
+                error!("🚨 系统时间早于Unix纪元，改用单调时钟推算时间戳并暂停开新仓");
+            }
+            // 从未正常读取过时间时，以2024-01-01为起点
+            let (last_good, at) = *base.get_or_insert((1704067200, Instant::now()));
+            last_good + at.elapsed().as_secs()
+        }
+    }
+}
+
+/// 获取互斥锁，锁已中毒（持有者panic）时记录日志并继续使用内部数据
+///
+/// 网格状态只包含数值字段，panic 时最多丢失一次未完成的更新，
+/// 继续运行比让整个策略随之崩溃更安全。
+fn lock_or_recover<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| {
+        warn!("⚠️ 互斥锁已中毒，恢复内部数据后继续运行");
+        poisoned.into_inner()
+    })
+}
+
+/// 最近处理过的成交事件，按 (订单ID, 交易哈希) 去重
+///
+/// 订单对账补发的成交可能与延迟到达的 websocket 推送重复，重复处理会让持仓被累加两次。
+struct FillDeduplicator {
+    seen: std::collections::HashSet<(u64, String)>,
+    order: VecDeque<(u64, String)>,
+    capacity: usize,
+}
+
+impl FillDeduplicator {
+    fn new(capacity: usize) -> Self {
+        Self {
+            seen: std::collections::HashSet::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// 记录一笔成交，已处理过时返回 false
+    fn insert(&mut self, oid: u64, hash: &str) -> bool {
+        let key = (oid, hash.to_string());
+        if !self.seen.insert(key.clone()) {
+            return false;
+        }
+        self.order.push_back(key);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+/// 根据配置的状态目录和前缀拼接状态文件路径
+pub(crate) fn state_file_path(grid_config: &crate::config::GridConfig, file_name: &str) -> String {
+    std::path::Path::new(&grid_config.state_dir)
+        .join(format!("{}{}", grid_config.state_file_prefix, file_name))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// 安全的时间间隔检查
+fn should_execute_periodic_task(
+    last_execution: SystemTime,
+    interval_seconds: u64,
+    task_name: &str,
+) -> bool {
+    let now = SystemTime::now();
+    let duration = safe_duration_since(now, last_execution);
+    let should_execute = duration.as_secs() >= interval_seconds;
+
+    if should_execute {
+        debug!(
+            "⏰ 执行定期任务: {} (间隔: {}秒)",
+            task_name,
+            duration.as_secs()
+        );
+    }
+
+    should_execute
+}
+
+/// 按 `grid_mode` 决定建网格时挂哪一侧，返回 (挂买单, 挂卖单)
+///
+/// reduce_only 只挂与持仓方向相反的平仓单，无持仓时两侧都不挂；
+/// accumulate 只挂与持仓同向的建仓单，无持仓时按做多方向挂买单。
+fn grid_mode_sides(
+    grid_config: &crate::config::GridConfig,
+    position_quantity: f64,
+) -> (bool, bool) {
+    match grid_config.grid_mode.as_str() {
+        "reduce_only" => (position_quantity < 0.0, position_quantity > 0.0),
+        "accumulate" => (position_quantity >= 0.0, position_quantity < 0.0),
+        _ => (true, true),
+    }
+}
+
+// 退出原因枚举
+#[derive(Debug, Clone, PartialEq)]
+enum ShutdownReason {
+    UserSignal,         // 用户信号 (SIGINT/SIGTERM)
+    StopLossTriggered,  // 止损触发
+    MarginInsufficient, // 保证金不足
+    NetworkError,       // 网络错误
+    ConfigurationError, // 配置错误
+    EmergencyShutdown,  // 紧急关闭
+    NormalExit,         // 正常退出
+}
+
+impl ShutdownReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ShutdownReason::UserSignal => "用户信号",
+            ShutdownReason::StopLossTriggered => "止损触发",
+            ShutdownReason::MarginInsufficient => "保证金不足",
+            ShutdownReason::NetworkError => "网络错误",
+            ShutdownReason::ConfigurationError => "配置错误",
+            ShutdownReason::EmergencyShutdown => "紧急关闭",
+            ShutdownReason::NormalExit => "正常退出",
+        }
+    }
+
+    /// 获取英文名称
+    fn as_english(&self) -> &'static str {
+        match self {
+            ShutdownReason::UserSignal => "User Signal",
+            ShutdownReason::StopLossTriggered => "Stop Loss Triggered",
+            ShutdownReason::MarginInsufficient => "Margin Insufficient",
+            ShutdownReason::NetworkError => "Network Error",
+            ShutdownReason::ConfigurationError => "Configuration Error",
+            ShutdownReason::EmergencyShutdown => "Emergency Shutdown",
+            ShutdownReason::NormalExit => "Normal Exit",
+        }
+    }
+
+    fn requires_position_close(&self) -> bool {
+        matches!(
+            self,
+            ShutdownReason::StopLossTriggered
+                | ShutdownReason::MarginInsufficient
+                | ShutdownReason::EmergencyShutdown
+        )
+    }
+
+    fn is_emergency(&self) -> bool {
+        matches!(
+            self,
+            ShutdownReason::MarginInsufficient | ShutdownReason::EmergencyShutdown
+        )
+    }
+
+    /// 退出事件的严重程度（1-5），用于告警通知
+    fn severity_level(&self) -> u8 {
+        match self {
+            ShutdownReason::MarginInsufficient | ShutdownReason::EmergencyShutdown => 5,
+            ShutdownReason::StopLossTriggered
+            | ShutdownReason::NetworkError
+            | ShutdownReason::ConfigurationError => 4,
+            ShutdownReason::UserSignal | ShutdownReason::NormalExit => 2,
+        }
+    }
+}
+
+// 持仓批次（每笔买单成交记录一批，用于最大持仓时间检查）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct PositionLot {
+    price: f64,
+    quantity: f64,
+    #[serde(with = "system_time_serde")]
+    opened_at: SystemTime, // 建仓时间
+    #[serde(default)]
+    close_order_id: Option<u64>, // 超时平仓挂单ID（限价模式）
+    #[serde(default)]
+    close_requested_at: u64, // 最近一次提交超时平仓的时间戳（秒），0表示未提交
+}
+
+// 超时平仓提交后的冷却时间（秒），避免成交回报到达前重复下单
+const HOLDING_CLOSE_RETRY_SECS: u64 = 60;
+
+// 资金费率刷新间隔（秒），资金费每小时结算，费率在小时内随溢价变化
+const FUNDING_RATE_REFRESH_SECS: u64 = 600;
+
+// 滑点长期超过容忍度时的告警间隔（秒）
+const SLIPPAGE_ALERT_INTERVAL_SECS: u64 = 3600;
+
+// 网格状态结构体
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct GridState {
+    total_capital: f64,
+    available_funds: f64, // 网格可用资金，只由交易所可提取余额同步（sync_available_funds），本地不做增减
+    position_quantity: f64,
+    position_avg_price: f64,
+    realized_profit: f64,
+    #[serde(default)]
+    grid_profit: f64, // 已实现盈亏中配对买卖完成的网格利润
+    #[serde(default)]
+    position_pnl: f64, // 已实现盈亏中持仓平仓（非配对卖单、止盈止损）的方向性盈亏
+    #[serde(default)]
+    unrealized_pnl: f64, // 持仓浮动盈亏，每次价格更新时按持仓均价和最新价刷新
+    #[serde(default)]
+    profit_reserve: f64, // 从交易资金中隔离的利润：fixed_capital 模式为利润储备，withdraw_to_base 模式为待提取金额
+    #[serde(default)]
+    profit_withdrawn: f64, // withdraw_to_base 模式下已确认提取的利润累计
+    highest_price_after_position: f64, // 持仓后最高价
+    trailing_stop_price: f64,          // 浮动止损价
+    stop_loss_status: StopLossStatus,  // 止损状态
+    #[serde(default)]
+    stop_loss_cooldown_until: u64, // 止损冷却期结束时间（Unix秒），冷却期间只平仓不开新仓
+    #[serde(with = "system_time_serde")]
+    last_rebalance_time: SystemTime,
+    historical_volatility: f64,
+    #[serde(flatten)]
+    performance: PerformanceAnalyzer, // 成交记录和性能指标，状态文件中仍为 performance_history/current_metrics
+    #[serde(with = "system_time_serde")]
+    last_margin_check: SystemTime, // 上次保证金检查时间
+    connection_retry_count: u32, // 连接重试次数
+    #[serde(with = "system_time_serde")]
+    last_order_batch_time: SystemTime, // 上次批量下单时间
+    dynamic_params: DynamicGridParams, // 动态网格参数
+    // 智能订单更新相关字段
+    #[serde(with = "system_time_serde")]
+    last_price_update: SystemTime, // 上次价格更新时间
+    last_grid_price: f64, // 上次网格创建时的价格
+    #[serde(default)]
+    grid_center_price: f64, // 当前网格的中心价，跟随均线模式据此判断是否平移
+    order_update_threshold: f64, // 订单更新阈值（价格变化百分比）
+    max_order_age_minutes: f64, // 订单最大存活时间（分钟）
+    // 自适应订单管理
+    adaptive_order_config: AdaptiveOrderConfig, // 自适应订单配置
+    // 增量技术指标（不持久化，随价格历史重新累积）
+    #[serde(skip)]
+    indicators: IndicatorState,
+    // 本地盘口快照（不持久化，由 L2Book 订阅实时更新）
+    #[serde(skip)]
+    order_book: OrderBook,
+    // 多周期K线缓存（不持久化，启动时拉取历史K线并由 Candle 订阅更新）
+    #[serde(skip)]
+    klines: KlineBuffer,
+    // 市场状态确认器（不持久化，重启后从正常状态重新确认）
+    #[serde(skip)]
+    market_state_tracker: MarketStateTracker,
+    // 持仓批次（按建仓时间先进先出）
+    #[serde(default)]
+    position_lots: VecDeque<PositionLot>,
+    // 挂单到成交的耗时统计
+    #[serde(default)]
+    fill_latency: FillLatencyStats,
+    // 滑点单（清仓、止损、止盈等）预期价与实际成交价的偏差统计
+    #[serde(default)]
+    slippage: SlippageStats,
+    // 本账户滑点单的预期成交价，只在运行期间使用，不写入状态文件
+    #[serde(skip)]
+    expected_fill_prices: ExpectedFillPrices,
+    // 下单回报即为 Filled 并已直接结算的订单（订单ID -> 尚未收到成交回报的数量），成交回报到达时跳过
+    // 需要持久化：重启前已结算、重启后才收到的成交回报也不能重复计入
+    #[serde(default)]
+    settled_on_placement: HashMap<u64, f64>,
+    // 连续亏损熔断
+    #[serde(default)]
+    consecutive_losses: u32, // 当前连续亏损的平仓笔数，盈利平仓时清零
+    #[serde(default)]
+    loss_circuit_tripped_at: u64, // 连续亏损熔断触发时间（Unix秒），0表示未熔断
+    // 永续合约资金费
+    #[serde(skip)]
+    funding_rate: f64, // 最新小时资金费率（不持久化，启动后定期拉取）
+    #[serde(default)]
+    funding_paid: f64, // 累计已付资金费（USDC），收取资金费时为负
+}
+
+impl GridState {
+    /// 记录订单从挂出到首次成交的耗时，并同步自适应订单配置中的平均成交时间
+    fn record_fill_latency(&mut self, order: &OrderInfo, fill_time_ms: u64) {
+        if order.created_at == 0 {
+            return; // 旧版状态文件恢复的订单没有挂单时间
+        }
+        let latency_secs = (fill_time_ms / 1000).saturating_sub(order.created_at);
+        self.fill_latency.record(order.is_buy, latency_secs);
+        if let Some(average) = self.fill_latency.average_minutes() {
+            self.adaptive_order_config.average_fill_time_minutes = average;
+        }
+    }
+
+    /// 距上次整网重建不足 `min_rebuild_interval_secs` 时返回已间隔的秒数，`None` 表示可以重建
+    ///
+    /// 智能订单更新、定期重平衡、网格突破重建和参数回滚都会撤单重挂，共用该防抖；
+    /// 挂单为空时直接建网不需要撤单，不受限制。
+    fn rebuild_too_soon(
+        &self,
+        grid_config: &crate::config::GridConfig,
+        now: SystemTime,
+    ) -> Option<u64> {
+        let elapsed = now
+            .duration_since(self.last_price_update)
+            .unwrap_or(Duration::from_secs(0))
+            .as_secs();
+        (elapsed < grid_config.min_rebuild_interval_secs).then_some(elapsed)
+    }
+
+    /// 部分止损后进入冷却期
+    fn start_stop_loss_cooldown(&mut self, minutes: u64) {
+        self.stop_loss_cooldown_until = safe_unix_timestamp() + minutes * 60;
+    }
+
+    /// 给定时刻（Unix秒）是否处于止损冷却期
+    fn in_stop_loss_cooldown(&self, now: u64) -> bool {
+        now < self.stop_loss_cooldown_until
+    }
+
+    /// 是否处于止损冷却期，冷却期结束时清除标记
+    fn check_stop_loss_cooldown(&mut self) -> bool {
+        if self.stop_loss_cooldown_until == 0 {
+            return false;
+        }
+        if self.in_stop_loss_cooldown(safe_unix_timestamp()) {
+            return true;
+        }
+        info!("✅ 止损冷却期结束，恢复开新仓");
+        self.stop_loss_cooldown_until = 0;
+        false
+    }
+
+    /// 记录一笔平仓盈亏并维护连续亏损计数，达到上限时触发熔断，返回是否本次触发
+    fn record_trade_outcome(&mut self, profit: f64, max_consecutive_losses: u32) -> bool {
+        if profit > 0.0 {
+            self.consecutive_losses = 0;
+            return false;
+        }
+        if profit < 0.0 {
+            self.consecutive_losses += 1;
+        }
+        if max_consecutive_losses == 0
+            || self.consecutive_losses < max_consecutive_losses
+            || self.loss_circuit_tripped_at > 0
+        {
+            return false;
+        }
+        self.loss_circuit_tripped_at = safe_unix_timestamp();
+        true
+    }
+
+    /// 给定时刻（Unix秒）是否处于连续亏损熔断中，冷却期为0时一直熔断到手动恢复
+    fn in_loss_circuit(&self, cooldown_minutes: u64, now: u64) -> bool {
+        self.loss_circuit_tripped_at > 0
+            && (cooldown_minutes == 0 || now < self.loss_circuit_tripped_at + cooldown_minutes * 60)
+    }
+
+    /// 是否处于连续亏损熔断中，冷却期结束时自动恢复；冷却期为0时只能手动恢复
+    fn check_loss_circuit(&mut self, cooldown_minutes: u64) -> bool {
+        if self.loss_circuit_tripped_at == 0 {
+            return false;
+        }
+        if self.in_loss_circuit(cooldown_minutes, safe_unix_timestamp()) {
+            return true;
+        }
+        info!("✅ 连续亏损熔断冷却期结束，恢复开新仓");
+        self.reset_loss_circuit();
+        false
+    }
+
+    /// 解除连续亏损熔断并清零计数
+    fn reset_loss_circuit(&mut self) {
+        self.loss_circuit_tripped_at = 0;
+        self.consecutive_losses = 0;
+    }
+
+    /// 按最新价格刷新持仓浮动盈亏，持仓数量为负（空头）时价格下跌为盈利
+    fn update_unrealized_pnl(&mut self, current_price: f64) {
+        self.unrealized_pnl = if self.position_avg_price > 0.0 && current_price > 0.0 {
+            (current_price - self.position_avg_price) * self.position_quantity
+        } else {
+            0.0
+        };
+    }
+
+    /// 总盈亏 = 已实现盈亏 + 未实现盈亏
+    /// 每格持仓期间的预期资金费成本（占持仓价值比例）
+    fn funding_cost(&self, grid_config: &crate::config::GridConfig) -> f64 {
+        expected_funding_cost(self.funding_rate, grid_config)
+    }
+
+    fn total_pnl(&self) -> f64 {
+        self.realized_profit + self.unrealized_pnl
+    }
+
+    /// 结算一笔已实现盈亏，并按 `profit_mode` 调整网格使用的总资金和可用资金
+    ///
+    /// compound 把盈亏计入总资金；fixed_capital 把盈利存入利润储备，亏损先由储备弥补，
+    /// 本金保持不变；withdraw_to_base 把盈利记为待提取金额，亏损由交易资金承担。
+    /// 可用资金不在这里扣减，下次同步交易所余额时自动扣除利润储备。
+    /// `grid_pair` 为配对对冲卖单的成交，盈亏计入网格利润，否则计入持仓盈亏。
+    fn settle_realized_profit(&mut self, profit: f64, grid_pair: bool, profit_mode: &str) {
+        self.realized_profit += profit;
+        if grid_pair {
+            self.grid_profit += profit;
+        } else {
+            self.position_pnl += profit;
+        }
+        let reserved = match profit_mode {
+            "compound" => {
+                self.total_capital = (self.total_capital + profit).max(0.0);
+                0.0
+            }
+            "fixed_capital" => (self.profit_reserve + profit).max(0.0) - self.profit_reserve,
+            "withdraw_to_base" => profit.max(0.0),
+            _ => 0.0,
+        };
+        if reserved != 0.0 {
+            self.profit_reserve += reserved;
+            info!(
+                "💸 利润处理({}) - 本笔盈亏: {:.4}, 隔离利润: {:.4}",
+                profit_mode, profit, self.profit_reserve
+            );
+        }
+    }
+
+    /// 交易所可提取余额扣除隔离利润后才是网格可用资金
+    ///
+    /// 这是 `available_funds` 唯一的更新入口：持仓保证金、挂单占用和成交盈亏都已反映在
+    /// 交易所余额里，本地再记账会和交易所重复计算。
+    fn sync_available_funds(&mut self, withdrawable: f64) {
+        self.available_funds = (withdrawable - self.profit_reserve).max(0.0);
+    }
+
+    /// 下单回报即为 Filled 的订单：按成交均价立即更新持仓和利润，并登记订单ID，随后到达的成交回报据此跳过
+    ///
+    /// 卖出按 `cost_price`（缺省为持仓均价）结算利润并返回，买入只更新持仓，返回 None。
+    fn settle_immediate_fill(
+        &mut self,
+        oid: u64,
+        is_buy: bool,
+        fill_price: f64,
+        fill_size: f64,
+        cost_price: Option<f64>,
+        grid_config: &crate::config::GridConfig,
+    ) -> Option<f64> {
+        // 下单即成交一定是吃单，按 taker 费率计算
+        let fee_rate = grid_config.taker_fee();
+        self.settled_on_placement.insert(oid, fill_size);
+
+        if is_buy {
+            let total_value =
+                self.position_avg_price * self.position_quantity + fill_price * fill_size;
+            let net_size = fill_size * (1.0 - fee_rate.max(0.0));
+            self.position_quantity += net_size;
+            self.record_position_lot(fill_price, net_size);
+            if self.position_quantity > 0.0 {
+                self.position_avg_price = total_value / self.position_quantity;
+            }
+            return None;
+        }
+
+        let cost_price = cost_price.unwrap_or(self.position_avg_price);
+        self.position_quantity -= fill_size;
+        let sell_revenue = fill_price * fill_size * (1.0 - fee_rate);
+        let profit = sell_revenue - cost_price * fill_size;
+        self.settle_realized_profit(profit, false, &grid_config.profit_mode);
+
+        let record = PerformanceRecord::new(
+            fill_price,
+            "SELL".to_string(),
+            profit,
+            self.available_funds + sell_revenue + self.position_quantity * fill_price,
+        )
+        .with_fill("sell", fill_price * fill_size * fee_rate, Some(oid));
+        self.performance.add_trade(record);
+        if self.record_trade_outcome(profit, grid_config.max_consecutive_losses) {
+            warn!(
+                "🧯 连续亏损熔断: 连续{}笔平仓亏损，暂停开新仓",
+                self.consecutive_losses
+            );
+        }
+        self.sync_position_lots();
+        Some(profit)
+    }
+
+    /// 成交回报对应的订单已在下单时结算时返回 true，跳过的数量累计到已结算数量后移除登记
+    fn skip_settled_fill(&mut self, oid: u64, fill_size: f64) -> bool {
+        let Some(remaining) = self.settled_on_placement.get_mut(&oid) else {
+            return false;
+        };
+        *remaining -= fill_size;
+        if *remaining <= 1e-9 {
+            self.settled_on_placement.remove(&oid);
+        }
+        true
+    }
+
+    /// 确认待提取利润已从账户转出，返回本次确认的金额
+    fn confirm_profit_withdrawal(&mut self) -> f64 {
+        let amount = self.profit_reserve;
+        self.profit_withdrawn += amount;
+        self.profit_reserve = 0.0;
+        amount
+    }
+
+    /// 记录一笔新建仓
+    fn record_position_lot(&mut self, price: f64, quantity: f64) {
+        if quantity <= 0.0 {
+            return;
+        }
+        self.position_lots.push_back(PositionLot {
+            price,
+            quantity,
+            opened_at: SystemTime::now(),
+            close_order_id: None,
+            close_requested_at: 0,
+        });
+    }
+
+    /// 使持仓批次与实际持仓数量一致：减仓时按先进先出扣减，缺少的部分以当前时间补记
+    fn sync_position_lots(&mut self) {
+        let target = self.position_quantity.max(0.0);
+        let mut total: f64 = self.position_lots.iter().map(|lot| lot.quantity).sum();
+
+        while total > target + 1e-9 {
+            let excess = total - target;
+            match self.position_lots.front_mut() {
+                Some(lot) if lot.quantity <= excess + 1e-9 => {
+                    total -= lot.quantity;
+                    self.position_lots.pop_front();
+                }
+                Some(lot) => {
+                    lot.quantity -= excess;
+                    total = target;
+                }
+                None => break,
+            }
+        }
+
+        if target - total > 1e-9 {
+            let price = self.position_avg_price;
+            self.record_position_lot(price, target - total);
+        }
+    }
+
+    /// 超过最大持仓时间且没有在途平仓单的批次数量及最长持仓秒数
+    fn expired_position(
+        &self,
+        max_holding_secs: u64,
+        sell_orders: &HashMap<u64, OrderInfo>,
+    ) -> (f64, u64) {
+        let now = SystemTime::now();
+        let now_secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let mut quantity = 0.0;
+        let mut oldest_secs = 0;
+
+        for lot in &self.position_lots {
+            let held_secs = now
+                .duration_since(lot.opened_at)
+                .unwrap_or_default()
+                .as_secs();
+            if held_secs < max_holding_secs {
+                continue;
+            }
+            let order_pending = lot
+                .close_order_id
+                .map(|oid| sell_orders.contains_key(&oid))
+                .unwrap_or(false);
+            let recently_requested =
+                now_secs.saturating_sub(lot.close_requested_at) < HOLDING_CLOSE_RETRY_SECS;
+            if order_pending || recently_requested {
+                continue;
+            }
+            quantity += lot.quantity;
+            oldest_secs = oldest_secs.max(held_secs);
+        }
+
+        (quantity, oldest_secs)
+    }
+
+    /// 标记已提交超时平仓的批次
+    fn mark_expired_lots_closing(&mut self, max_holding_secs: u64, close_order_id: Option<u64>) {
+        let now = SystemTime::now();
+        let now_secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        for lot in self.position_lots.iter_mut() {
+            let held_secs = now
+                .duration_since(lot.opened_at)
+                .unwrap_or_default()
+                .as_secs();
+            if held_secs >= max_holding_secs {
+                lot.close_requested_at = now_secs;
+                if close_order_id.is_some() {
+                    lot.close_order_id = close_order_id;
+                }
+            }
+        }
+    }
+}
+
+// 动态资金分配结果
+#[derive(Debug, Clone)]
+struct DynamicFundAllocation {
+    buy_order_funds: f64,
+    sell_order_funds: f64,
+    buy_spacing_adjustment: f64,
+    sell_spacing_adjustment: f64,
+    position_ratio: f64,
+}
+
+/// 自适应双向网格策略
+#[derive(Debug, Clone)]
+enum GridStrategy {
+    Neutral,     // 中性网格：50%买单 + 50%卖单
+    BullishBias, // 偏多网格：70%买单 + 30%卖单
+    BearishBias, // 偏空网格：30%买单 + 70%卖单
+    PureBull,    // 纯多网格：90%买单 + 10%卖单
+    PureBear,    // 纯空网格：10%买单 + 90%卖单
+}
+
+impl GridStrategy {
+    fn as_str(&self) -> &'static str {
+        match self {
+            GridStrategy::Neutral => "中性网格",
+            GridStrategy::BullishBias => "偏多网格",
+            GridStrategy::BearishBias => "偏空网格",
+            GridStrategy::PureBull => "纯多网格",
+            GridStrategy::PureBear => "纯空网格",
+        }
+    }
+
+    /// 获取英文名称
+    fn as_english(&self) -> &'static str {
+        match self {
+            GridStrategy::Neutral => "Neutral Grid",
+            GridStrategy::BullishBias => "Bullish Bias Grid",
+            GridStrategy::BearishBias => "Bearish Bias Grid",
+            GridStrategy::PureBull => "Pure Bull Grid",
+            GridStrategy::PureBear => "Pure Bear Grid",
+        }
+    }
+
+    fn buy_ratio(&self) -> f64 {
+        match self {
+            GridStrategy::Neutral => 0.5,
+            GridStrategy::BullishBias => 0.7,
+            GridStrategy::BearishBias => 0.3,
+            GridStrategy::PureBull => 0.9,
+            GridStrategy::PureBear => 0.1,
+        }
+    }
+
+    fn sell_ratio(&self) -> f64 {
+        1.0 - self.buy_ratio()
+    }
+}
+
+/// 增强的资金分配结构
+#[derive(Debug, Clone)]
+struct AdaptiveFundAllocation {
+    buy_order_funds: f64,
+    sell_order_funds: f64,
+    buy_spacing_adjustment: f64,
+    sell_spacing_adjustment: f64,
+    position_ratio: f64,
+    grid_strategy: GridStrategy,
+    // 做空相关
+    short_position_funds: f64, // 做空持仓资金
+    max_short_exposure: f64,   // 最大做空敞口
+    // 风险控制
+    risk_adjustment_factor: f64, // 风险调整因子
+}
+
+// 双语枚举统一通过 display(lang)/localized() 显示
+crate::impl_bilingual!(
+    OrderPriority,
+    ExpiryStrategy,
+    StopLossStatus,
+    ShutdownReason,
+    MarketState,
+    MarketTrend,
+    GridStrategy,
+    StopLossAction,
+    TakeProfitAction,
+    RiskEventType,
+    ConnectionStatus,
+    ConnectionEventType,
+);
+
+// 格式化价格到指定精度
+fn format_price(price: f64, precision: u32) -> f64 {
+    let multiplier = 10.0_f64.powi(precision as i32);
+    (price * multiplier).round() / multiplier
+}
+
+// 盘口深度检查：单笔网格订单吃单的成交均价偏离超过滑点容忍度时标记为流动性不足
+fn apply_order_book_liquidity(
+    market_analysis: &mut MarketAnalysis,
+    order_book: &OrderBook,
+    grid_config: &crate::config::GridConfig,
+    current_price: f64,
+) {
+    if !order_book.is_ready() || current_price <= 0.0 {
+        return;
+    }
+
+    let mid_price = order_book.mid_price().unwrap_or(current_price);
+    let reference_size = grid_config.trade_amount / current_price;
+    let mut worst_impact: f64 = 0.0;
+    let mut depth_sufficient = true;
+
+    for is_buy in [true, false] {
+        if let Some(estimate) = order_book.estimate_fill_price(is_buy, reference_size) {
+            worst_impact = worst_impact.max((estimate.avg_price - mid_price).abs() / mid_price);
+            depth_sufficient &= estimate.fully_filled;
+        }
+    }
+
+    if !depth_sufficient || worst_impact > grid_config.slippage_tolerance {
+        market_analysis.market_state = MarketState::ThinLiquidity;
+        market_analysis.liquidity_score = market_analysis.liquidity_score.min(30.0);
+        warn!(
+            "⚠️ 盘口深度不足 - 单笔数量: {:.4}, 估算冲击: {:.4}%, 深度足够: {}",
+            reference_size,
+            worst_impact * 100.0,
+            depth_sufficient
+        );
+    }
+}
+
+// 按盘口深度限制单笔订单数量，超出滑点容忍度范围内的深度时缩小单量
+fn limit_quantity_by_depth(
+    order_book: &OrderBook,
+    grid_config: &crate::config::GridConfig,
+    is_buy: bool,
+    quantity: f64,
+) -> f64 {
+    match order_book.max_size_within_slippage(is_buy, grid_config.slippage_tolerance) {
+        Some(max_size) if max_size < quantity => {
+            info!(
+                "📖 盘口深度限制 - {}单数量: {:.4} -> {:.4}",
+                if is_buy { "买" } else { "卖" },
+                quantity,
+                max_size
+            );
+            max_size
+        }
+        _ => quantity,
+    }
+}
+
+// 计算动态资金分配
+/// 智能网格策略选择
+fn determine_adaptive_grid_strategy(
+    market_analysis: &MarketAnalysis,
+    grid_state: &GridState,
+    price_history: &[f64],
+) -> GridStrategy {
+    let trend_strength = calculate_trend_strength(price_history);
+    let volatility_level = market_analysis.volatility;
+    let rsi = market_analysis.rsi;
+
+    // 计算综合市场信号
+    let mut bullish_score = 0.0;
+    let mut bearish_score = 0.0;
+
+    // 1. 趋势信号 (权重: 40%)
+    match market_analysis.trend {
+        MarketTrend::Upward => bullish_score += 0.4 * trend_strength,
+        MarketTrend::Downward => bearish_score += 0.4 * trend_strength,
+        MarketTrend::Sideways => {
+            // 震荡市场，偏向中性
+            bullish_score += 0.2;
+            bearish_score += 0.2;
+        }
+    }
+
+    // 2. RSI信号 (权重: 20%)
+    if rsi < 30.0 {
+        bullish_score += 0.2; // 超卖，偏多
+    } else if rsi > 70.0 {
+        bearish_score += 0.2; // 超买，偏空
+    }
+
+    // 3. 价格变化信号 (权重: 20%)
+    let price_change = market_analysis.price_change_5min;
+    if price_change > 0.02 {
+        bullish_score += 0.2;
+    } else if price_change < -0.02 {
+        bearish_score += 0.2;
+    }
+
+    // 4. 持仓偏向调整 (权重: 20%)
+    let position_bias = if grid_state.total_capital > 0.0 {
+        (grid_state.position_quantity * grid_state.position_avg_price) / grid_state.total_capital
+    } else {
+        0.0
+    };
+
+    if position_bias > 0.6 {
+        bearish_score += 0.2; // 持仓过多，偏向卖出
+    } else if position_bias < 0.2 {
+        bullish_score += 0.2; // 持仓过少，偏向买入
+    }
+
+    // 根据得分确定策略
+    let score_diff = bullish_score - bearish_score;
+
+    info!(
+        "📊 网格策略评分 - 看多: {:.2}, 看空: {:.2}, 差值: {:.2}, 趋势强度: {:.2}",
+        bullish_score, bearish_score, score_diff, trend_strength
+    );
+
+    if score_diff > 0.3 {
+        if volatility_level > 0.05 {
+            GridStrategy::BullishBias
+        } else {
+            GridStrategy::PureBull
+        }
+    } else if score_diff < -0.3 {
+        if volatility_level > 0.05 {
+            GridStrategy::BearishBias
+        } else {
+            GridStrategy::PureBear
+        }
+    } else {
+        GridStrategy::Neutral
+    }
+}
+
+/// 增强的动态资金分配
+fn calculate_adaptive_fund_allocation(
+    grid_state: &GridState,
+    current_price: f64,
+    grid_config: &crate::config::GridConfig,
+    market_analysis: &MarketAnalysis,
+    price_history: &[f64],
+) -> AdaptiveFundAllocation {
+    // 确定网格策略
+    let grid_strategy =
+        determine_adaptive_grid_strategy(market_analysis, grid_state, price_history);
+
+    // 计算持仓比例
+    let position_ratio = if grid_state.total_capital > 0.0 {
+        (grid_state.position_quantity * current_price) / grid_state.total_capital
+    } else {
+        0.0
+    };
+
+    // 风险调整因子
+    let risk_adjustment = match market_analysis.market_state {
+        MarketState::Normal => 1.0,
+        MarketState::HighVolatility => 0.8,
+        MarketState::Extreme => 0.6,
+        MarketState::ThinLiquidity => 0.7,
+        MarketState::Flash => 0.5,
+        MarketState::Consolidation => 1.1,
+    };
+
+    // 基础资金分配
+    let total_grid_funds = grid_state.available_funds * 0.8 * risk_adjustment; // 80%资金用于网格
+    let buy_funds = total_grid_funds * grid_strategy.buy_ratio();
+    let sell_funds = total_grid_funds * grid_strategy.sell_ratio();
+
+    // 计算单网格资金
+    let grid_count = grid_config.grid_count as f64;
+    let buy_order_funds = buy_funds / (grid_count * grid_strategy.buy_ratio()).max(1.0);
+    let sell_order_funds = sell_funds / (grid_count * grid_strategy.sell_ratio()).max(1.0);
+
+    // 做空相关计算
+    let max_short_exposure = grid_state.total_capital * 0.3; // 最大30%做空敞口
+    let short_position_funds = sell_order_funds * grid_strategy.sell_ratio();
+
+    // 间距调整
+    let volatility_factor = (1.0 + market_analysis.volatility * 2.0).min(2.0);
+    let buy_spacing_adjustment = match grid_strategy {
+        GridStrategy::PureBull | GridStrategy::BullishBias => 0.8 * volatility_factor,
+        GridStrategy::Neutral => 1.0 * volatility_factor,
+        _ => 1.2 * volatility_factor,
+    };
+
+    let sell_spacing_adjustment = match grid_strategy {
+        GridStrategy::PureBear | GridStrategy::BearishBias => 0.8 * volatility_factor,
+        GridStrategy::Neutral => 1.0 * volatility_factor,
+        _ => 1.2 * volatility_factor,
+    };
+
+    info!(
+        "🎯 自适应网格策略: {} - 买单资金: {:.2} ({:.0}%), 卖单资金: {:.2} ({:.0}%), 风险调整: {:.2}",
+        grid_strategy.localized(),
+        buy_order_funds,
+        grid_strategy.buy_ratio() * 100.0,
+        sell_order_funds,
+        grid_strategy.sell_ratio() * 100.0,
+        risk_adjustment
+    );
+
+    AdaptiveFundAllocation {
+        buy_order_funds,
+        sell_order_funds,
+        buy_spacing_adjustment,
+        sell_spacing_adjustment,
+        position_ratio,
+        grid_strategy,
+        short_position_funds,
+        max_short_exposure,
+        risk_adjustment_factor: risk_adjustment,
+    }
+}
+
+/*
+修复说明：
+
+1. 问题根源：
+   - 原代码使用 grid_state.total_capital * 0.5 导致资金过少
+   - 依赖 grid_state.available_funds，当全部资金用于持仓时变为0
+   - 没有为止盈订单预留专门资金
+
+2. 修复方案：
+   - 不再依赖 available_funds，直接使用 total_capital
+   - 有持仓时使用20%总资金专门用于止盈网格
+   - 无持仓时使用80%总资金正常分配
+   - 设置合理的单笔订单限制（0.5%-15%）
+
+3. 预期效果：
+   - 有多头持仓时能创建卖单止盈
+   - 有空头持仓时能创建买单止盈
+   - 资金使用率控制在合理范围内
+   - 避免订单数量为0的问题
+*/
+
+fn calculate_dynamic_fund_allocation(
+    grid_state: &GridState,
+    current_price: f64,
+    grid_config: &crate::config::GridConfig,
+) -> DynamicFundAllocation {
+    // 计算持仓比例
+    let position_ratio = if grid_state.total_capital > 0.0 {
+        (grid_state.position_quantity * current_price) / grid_state.total_capital
+    } else {
+        0.0
+    };
+
+    // 资金偏向系数：持仓越多，买单资金越少，卖单资金越多
+    let buy_fund_bias = (1.0 - position_ratio * 2.0).max(0.2);
+    let sell_fund_bias = (1.0 + position_ratio).min(2.0);
+
+    // 根据价格位置动态调整网格密度
+    let price_range = grid_config.max_grid_spacing - grid_config.min_grid_spacing;
+    let price_position = if price_range > 0.0 {
+        ((current_price - grid_config.min_grid_spacing) / price_range)
+            .max(0.0)
+            .min(1.0)
+    } else {
+        0.5
+    };
+
+    // 价格越低，买单间距越小；价格越高，卖单间距越小
+    let buy_spacing_adjustment = 1.0 + (price_position * 0.5);
+    let sell_spacing_adjustment = 1.0 + ((1.0 - price_position) * 0.5);
+
+    // 计算动态单网格资金 - 修复持仓后无法止盈的问题
+    let total_capital = grid_state.total_capital.max(0.01); // 防止除零
+
+    // 智能资金分配：根据持仓情况调整策略
+    let effective_funds = if grid_state.position_quantity.abs() > 0.001 {
+        // 有持仓时：为止盈订单预留专门资金（不依赖available_funds）
+        total_capital * 0.2 // 使用20%总资金用于止盈网格
+    } else {
+        // 无持仓时：使用正常资金分配
+        total_capital * 0.8 // 使用80%总资金
+    };
+
+    let base_fund_per_grid = effective_funds / grid_config.grid_count as f64;
+    let buy_order_funds = base_fund_per_grid * buy_fund_bias;
+    let sell_order_funds = base_fund_per_grid * sell_fund_bias;
+
+    // 设置合理的资金限制
+    let max_single_grid_fund = total_capital * 0.15; // 最大15%
+    let min_single_grid_fund = total_capital * 0.005; // 最小0.5%
+    let buy_order_funds = buy_order_funds
+        .min(max_single_grid_fund)
+        .max(min_single_grid_fund);
+    let sell_order_funds = sell_order_funds
+        .min(max_single_grid_fund)
+        .max(min_single_grid_fund);
+
+    // 详细日志
+    info!(
+        "💰 智能资金分配 - 总资金: {:.4}, 持仓: {:.4}, 有效资金: {:.4} ({:.1}%), 买单: {:.4}, 卖单: {:.4}",
+        total_capital,
+        grid_state.position_quantity,
+        effective_funds,
+        (effective_funds / total_capital) * 100.0,
+        buy_order_funds,
+        sell_order_funds
+    );
+
+    DynamicFundAllocation {
+        buy_order_funds,
+        sell_order_funds,
+        buy_spacing_adjustment,
+        sell_spacing_adjustment,
+        position_ratio,
+    }
+}
+
+// 处理买单成交
+async fn handle_buy_fill(
+    exchange_client: &dyn OrderExecutor,
+    grid_config: &crate::config::GridConfig,
+    fill_price: f64,
+    quantities: FillQuantities,
+    grid_spacing: f64,
+    trading_paused: bool,
+    position_quantity: f64,
+    active_orders: &mut Vec<u64>,
+    buy_orders: &mut HashMap<u64, OrderInfo>,
+    sell_orders: &mut HashMap<u64, OrderInfo>,
+) -> Result<(), GridStrategyError> {
+    info!(
+        "🟢 处理买单成交: 价格={}, 对冲数量={}, 重建数量={}",
+        fill_price, quantities.hedge, quantities.rebuild
+    );
+
+    // reduce_only 模式下买单只用于平空，成交后不再挂新单
+    if grid_config.grid_mode == "reduce_only" {
+        info!("🎚️ reduce_only 模式，买单成交后不再挂新单");
+        return Ok(());
+    }
+
+    // 计算基础卖出价格
+    let base_sell_price = fill_price * (1.0 + grid_spacing);
+
+    // 计算考虑手续费和最小利润的实际卖出价格
+    let min_sell_price = calculate_min_sell_price(
+        fill_price,
+        grid_config.maker_fee(),
+        min_profit_rate(grid_config),
+    );
+    let actual_sell_price = base_sell_price.max(min_sell_price);
+    let formatted_sell_price = format_price(actual_sell_price, grid_config.price_precision);
+
+    // 检查是否超出网格上限
+    let upper_limit =
+        fill_price * (1.0 + grid_config.max_grid_spacing * grid_config.grid_count as f64);
+    if formatted_sell_price > upper_limit {
+        warn!(
+            "⚠️ 卖出价格({:.4})超出网格上限({:.4})，可能影响网格完整性",
+            formatted_sell_price, upper_limit
+        );
+    }
+
+    // 考虑买入时的手续费损失，调整卖出数量
+    let sell_quantity = format_price(
+        quantities.hedge * (1.0 - grid_config.maker_fee().max(0.0)),
+        grid_config.quantity_precision,
+    );
+
+    // 创建卖单
+    let cloid = new_cloid();
+    let sell_order = ClientOrderRequest {
+        asset: grid_config.trading_asset.clone(),
+        is_buy: false,
+        reduce_only: false,
+        limit_px: formatted_sell_price,
+        sz: sell_quantity,
+        cloid: Some(cloid),
+        order_type: ClientOrder::Limit(ClientLimit {
+            tif: grid_order_tif(grid_config),
+        }),
+    };
+
+    // accumulate 模式只建仓，不挂对冲卖单
+    if grid_config.grid_mode == "accumulate" {
+        info!("🎚️ accumulate 模式，跳过对冲卖单");
+    } else if quantities.hedge <= 0.0 {
+        debug!("⏳ 部分成交量不足最小下单金额，累积后再挂对冲卖单");
+    } else if !meets_min_notional(formatted_sell_price, sell_quantity, grid_config) {
+        warn!(
+            "⚠️ 对冲卖单名义价值({:.2})低于最小下单金额({:.2})，跳过",
+            formatted_sell_price * sell_quantity,
+            grid_config.min_notional
+        );
+    } else if !quantities.partial
+        && has_nearby_order(sell_orders, formatted_sell_price, price_tick(grid_config))
+    {
+        info!(
+            "⏭️ 价位 {:.4} 已有卖单，跳过重复的对冲卖单",
+            formatted_sell_price
+        );
+    } else {
+        let sell_info = OrderInfo {
+            is_buy: false,
+            price: formatted_sell_price,
+            quantity: sell_quantity,
+            cost_price: Some(fill_price),
+            potential_sell_price: None,
+            allocated_funds: 0.0,
+            iceberg: None,
+            created_at: safe_unix_timestamp(),
+            cloid: Some(cloid),
+            filled_quantity: 0.0,
+            unhedged_quantity: 0.0,
+            grid_pair: true,
+        };
+        for (oid, order_info) in submit_grid_order_sliced(
+            exchange_client,
+            sell_order,
+            sell_info,
+            grid_config,
+            "对冲卖单",
+        )
+        .await
+        {
+            info!(
+                "🔴【对冲卖单】✅ 卖单已提交: ID={}, 价格={}, 数量={}, 成本价={}",
+                oid, formatted_sell_price, order_info.quantity, fill_price
+            );
+            active_orders.push(oid);
+            sell_orders.insert(oid, order_info);
+        }
+    }
+
+    // 部分成交时原订单剩余部分仍在挂单，全部成交后再重建
+    if quantities.rebuild <= 0.0 {
+        return Ok(());
+    }
+
+    // 风控暂停期间不开新仓，只保留平掉本次买入的对冲卖单
+    if trading_paused {
+        info!("⏸️ 交易已暂停，跳过重建买单");
+        return Ok(());
+    }
+
+    if has_nearby_order(buy_orders, fill_price, price_tick(grid_config)) {
+        info!("⏭️ 价位 {:.4} 已有买单，跳过重建买单", fill_price);
+        return Ok(());
+    }
+
+    // 同一档位反复成交会不断累积持仓，重建后持仓价值超过上限时只保留对冲卖单
+    let fill_size = quantities.rebuild;
+    let position_value_after = (position_quantity.abs() + fill_size) * fill_price;
+    if position_value_after > max_buy_exposure(grid_config) {
+        warn!(
+            "⚠️ 重建买单后持仓价值({:.2})将超过最大持仓({:.2})，跳过重建买单",
+            position_value_after,
+            max_buy_exposure(grid_config)
+        );
+        return Ok(());
+    }
+
+    if !meets_min_notional(fill_price, fill_size, grid_config) {
+        warn!(
+            "⚠️ 重建买单名义价值({:.2})低于最小下单金额({:.2})，跳过",
+            fill_price * fill_size,
+            grid_config.min_notional
+        );
+        return Ok(());
+    }
+
+    // 在相同价格重新创建买单
+    let cloid = new_cloid();
+    let new_buy_order = ClientOrderRequest {
+        asset: grid_config.trading_asset.clone(),
+        is_buy: true,
+        reduce_only: false,
+        limit_px: fill_price,
+        sz: fill_size,
+        cloid: Some(cloid),
+        order_type: ClientOrder::Limit(ClientLimit {
+            tif: grid_order_tif(grid_config),
+        }),
+    };
+
+    let buy_info = OrderInfo {
+        is_buy: true,
+        price: fill_price,
+        quantity: fill_size,
+        cost_price: None,
+        potential_sell_price: None,
+        allocated_funds: 0.0,
+        iceberg: None,
+        created_at: safe_unix_timestamp(),
+        cloid: Some(cloid),
+        filled_quantity: 0.0,
+        unhedged_quantity: 0.0,
+        grid_pair: false,
+    };
+    for (oid, order_info) in submit_grid_order_sliced(
+        exchange_client,
+        new_buy_order,
+        buy_info,
+        grid_config,
+        "重建买单",
+    )
+    .await
+    {
+        info!(
+            "🟢【重建买单】✅ 买单已提交: ID={}, 价格={}, 数量={}",
+            oid, fill_price, order_info.quantity
+        );
+        active_orders.push(oid);
+        buy_orders.insert(oid, order_info);
+    }
+
+    Ok(())
+}
+
+// 处理卖单成交
+async fn handle_sell_fill(
+    exchange_client: &dyn OrderExecutor,
+    grid_config: &crate::config::GridConfig,
+    fill_price: f64,
+    fill_size: f64,
+    cost_price: Option<f64>,
+    grid_spacing: f64,
+    funding_cost: f64,
+    trading_paused: bool,
+    active_orders: &mut Vec<u64>,
+    buy_orders: &mut HashMap<u64, OrderInfo>,
+    sell_orders: &mut HashMap<u64, OrderInfo>,
+) -> Result<(), GridStrategyError> {
+    info!(
+        "🔴 处理卖单成交: 价格={}, 数量={}, 成本价={:?}",
+        fill_price, fill_size, cost_price
+    );
+
+    // 计算实际利润
+    let actual_cost_price = cost_price.unwrap_or_else(|| {
+        let estimated = fill_price - grid_spacing * fill_price;
+        warn!("⚠️ 未找到成本价，估算为: {:.4}", estimated);
+        estimated
+    });
+
+    // 已付资金费单独累计，这里只算价差和手续费
+    let actual_profit_rate =
+        calculate_expected_profit_rate(actual_cost_price, fill_price, grid_config.maker_fee(), 0.0);
+
+    info!(
+        "💰 交易完成 - 成本价: {:.4}, 卖出价: {:.4}, 利润率: {:.4}%",
+        actual_cost_price,
+        fill_price,
+        actual_profit_rate * 100.0
+    );
+
+    // 风控暂停期间不开新仓，新买单和重建卖单都跳过
+    if trading_paused {
+        info!("⏸️ 交易已暂停，跳过新买单和重建卖单");
+        return Ok(());
+    }
+
+    // reduce_only 模式只减仓，卖单成交后不再挂新单
+    if grid_config.grid_mode == "reduce_only" {
+        info!("🎚️ reduce_only 模式，卖单成交后不再挂新单");
+        return Ok(());
+    }
+
+    // 计算潜在买入价格
+    let base_buy_price = fill_price * (1.0 - grid_spacing);
+    let formatted_buy_price = format_price(base_buy_price, grid_config.price_precision);
+
+    // 检查新买入点的预期利润率
+    let potential_sell_price = formatted_buy_price * (1.0 + grid_spacing);
+    let expected_profit_rate = calculate_expected_profit_rate(
+        formatted_buy_price,
+        potential_sell_price,
+        grid_config.maker_fee(),
+        funding_cost,
+    );
+    let required_profit_rate = min_profit_rate(grid_config);
+
+    let buy_quantity = format_price(
+        grid_config.trade_amount / formatted_buy_price,
+        grid_config.quantity_precision,
+    );
+
+    if has_nearby_order(buy_orders, formatted_buy_price, price_tick(grid_config)) {
+        info!("⏭️ 价位 {:.4} 已有买单，跳过新买单", formatted_buy_price);
+    } else if !meets_min_notional(formatted_buy_price, buy_quantity, grid_config) {
+        warn!(
+            "⚠️ 新买单名义价值({:.2})低于最小下单金额({:.2})，跳过",
+            formatted_buy_price * buy_quantity,
+            grid_config.min_notional
+        );
+    } else if expected_profit_rate >= required_profit_rate {
+        // 创建新买单
+        let cloid = new_cloid();
+        let new_buy_order = ClientOrderRequest {
+            asset: grid_config.trading_asset.clone(),
+            is_buy: true,
+            reduce_only: false,
+            limit_px: formatted_buy_price,
+            sz: buy_quantity,
+            cloid: Some(cloid),
+            order_type: ClientOrder::Limit(ClientLimit {
+                tif: grid_order_tif(grid_config),
+            }),
+        };
+
+        let buy_info = OrderInfo {
+            is_buy: true,
+            price: formatted_buy_price,
+            quantity: buy_quantity,
+            cost_price: None,
+            potential_sell_price: None,
+            allocated_funds: 0.0,
+            iceberg: None,
+            created_at: safe_unix_timestamp(),
+            cloid: Some(cloid),
+            filled_quantity: 0.0,
+            unhedged_quantity: 0.0,
+            grid_pair: false,
+        };
+        for (oid, order_info) in submit_grid_order_sliced(
+            exchange_client,
+            new_buy_order,
+            buy_info,
+            grid_config,
+            "新买单",
+        )
+        .await
+        {
+            info!(
+                "🟢【新买单】✅ 买单已提交: ID={}, 价格={}, 数量={}, 预期利润率={:.4}%",
+                oid,
+                formatted_buy_price,
+                order_info.quantity,
+                expected_profit_rate * 100.0
+            );
+            active_orders.push(oid);
+            buy_orders.insert(oid, order_info);
+        }
+    } else {
+        warn!(
+            "⚠️ 网格点 {:.4} 的预期利润率({:.4}%)不满足最小要求({:.4}%)，跳过此买单",
+            formatted_buy_price,
+            expected_profit_rate * 100.0,
+            required_profit_rate * 100.0
+        );
+    }
+
+    // 根据策略决定是否在相同价格再次创建卖单
+    // 检查是否有足够的资产和是否应该在相同价格创建卖单
+    // 只有盈利的情况下才重建卖单，accumulate 模式只建仓不重建卖单
+    let should_recreate_sell = actual_profit_rate > 0.0 && grid_config.grid_mode != "accumulate";
+
+    if should_recreate_sell && has_nearby_order(sell_orders, fill_price, price_tick(grid_config)) {
+        info!("⏭️ 价位 {:.4} 已有卖单，跳过重建卖单", fill_price);
+    } else if should_recreate_sell && !meets_min_notional(fill_price, fill_size, grid_config) {
+        warn!(
+            "⚠️ 重建卖单名义价值({:.2})低于最小下单金额({:.2})，跳过",
+            fill_price * fill_size,
+            grid_config.min_notional
+        );
+    } else if should_recreate_sell {
+        // 在相同价格重新创建卖单
+        let cloid = new_cloid();
+        let new_sell_order = ClientOrderRequest {
+            asset: grid_config.trading_asset.clone(),
+            is_buy: false,
+            reduce_only: false,
+            limit_px: fill_price,
+            sz: fill_size,
+            cloid: Some(cloid),
+            order_type: ClientOrder::Limit(ClientLimit {
+                tif: grid_order_tif(grid_config),
+            }),
+        };
+
+        // 估算新卖单的成本价（当前价格减去网格间距）
+        let estimated_cost_price = fill_price * (1.0 - grid_spacing);
+        let sell_info = OrderInfo {
+            is_buy: false,
+            price: fill_price,
+            quantity: fill_size,
+            cost_price: Some(estimated_cost_price),
+            potential_sell_price: None,
+            allocated_funds: 0.0,
+            iceberg: None,
+            created_at: safe_unix_timestamp(),
+            cloid: Some(cloid),
+            filled_quantity: 0.0,
+            unhedged_quantity: 0.0,
+            grid_pair: false,
+        };
+        for (oid, order_info) in submit_grid_order_sliced(
+            exchange_client,
+            new_sell_order,
+            sell_info,
+            grid_config,
+            "重建卖单",
+        )
+        .await
+        {
+            info!(
+                "🔴【重建卖单】✅ 卖单已提交: ID={}, 价格={}, 数量={}",
+                oid, fill_price, order_info.quantity
+            );
+            active_orders.push(oid);
+            sell_orders.insert(oid, order_info);
+        }
+    } else {
+        info!("📊 利润率不足或策略不建议重建卖单，跳过重建");
+    }
+
+    Ok(())
+}
+
+/// 给定时刻是否在配置的 UTC 交易时间窗口内，窗口已在启动时校验，解析失败时按全天交易处理
+fn in_configured_trading_window(
+    grid_config: &crate::config::GridConfig,
+    now: DateTime<Utc>,
+) -> bool {
+    parse_trading_windows(&grid_config.trading_windows)
+        .map(|windows| is_in_trading_window(&windows, now))
+        .unwrap_or(true)
+}
+
+/// 是否暂停开新仓：风控/手动/时钟异常暂停标志、止损冷却、连续亏损熔断或不在交易时间窗口内
+///
+/// 撤单、止损和平仓不受影响。冷却和熔断只读取状态，到期后的清除由主循环的 `check_*` 负责。
+fn trading_paused(
+    grid_state: &GridState,
+    grid_config: &crate::config::GridConfig,
+    stop_trading: bool,
+    now: DateTime<Utc>,
+) -> bool {
+    let now_secs = u64::try_from(now.timestamp()).unwrap_or(0);
+    stop_trading
+        || grid_state.in_stop_loss_cooldown(now_secs)
+        || grid_state.in_loss_circuit(grid_config.loss_circuit_cooldown_minutes, now_secs)
+        || !in_configured_trading_window(grid_config, now)
+}
+
+// 查询账户信息，dry-run 模式下返回本地模拟账户的状态
+async fn get_account_info(
+    exchange_client: &dyn OrderExecutor,
+    info_client: &InfoClient,
+    user_address: ethers::types::Address,
+) -> Result<hyperliquid_rust_sdk::UserStateResponse, GridStrategyError> {
+    if exchange_client.is_simulated() {
+        return exchange_client
+            .simulated_user_state()
+            .ok_or_else(|| GridStrategyError::ClientError("获取模拟账户信息失败".to_string()));
+    }
+
+    acquire_api_permit().await;
+    info_client
+        .user_state(user_address)
+        .await
+        .map_err(|e| GridStrategyError::ClientError(format!("获取账户信息失败: {:?}", e)))
+}
+
+/// 拉取账户可提取余额并同步网格可用资金，返回交易所原始可提取余额
+async fn refresh_available_funds(
+    exchange_client: &dyn OrderExecutor,
+    info_client: &InfoClient,
+    user_address: ethers::types::Address,
+    grid_state: &mut GridState,
+) -> Result<f64, GridStrategyError> {
+    let account_info = get_account_info(exchange_client, info_client, user_address).await?;
+    let withdrawable = account_info.withdrawable.parse().unwrap_or(0.0);
+    grid_state.sync_available_funds(withdrawable);
+    Ok(withdrawable)
+}
+
+/// 拉取最新资金费率，只对永续合约生效，失败时保留上次的费率
+async fn refresh_funding_rate(
+    info_client: &InfoClient,
+    grid_state: &mut GridState,
+    grid_config: &crate::config::GridConfig,
+) {
+    if !grid_config.funding_cost_enabled || !is_perpetual_asset(&grid_config.trading_asset) {
+        return;
+    }
+
+    // 资金费每小时结算一次，取最近几小时内最新的一条
+    let start_time = (safe_unix_timestamp().saturating_sub(3 * 3600)) * 1000;
+    acquire_api_permit().await;
+    match info_client
+        .funding_history(grid_config.trading_asset.clone(), start_time, None)
+        .await
+    {
+        Ok(history) => {
+            let latest = history
+                .iter()
+                .max_by_key(|entry| entry.time)
+                .and_then(|entry| entry.funding_rate.parse::<f64>().ok());
+            if let Some(rate) = latest {
+                grid_state.funding_rate = rate;
+                debug!(
+                    "💸 资金费率已更新 - {}: {:.6}%/小时, 预期每格成本: {:.4}%",
+                    grid_config.trading_asset,
+                    rate * 100.0,
+                    grid_state.funding_cost(grid_config) * 100.0
+                );
+            }
+        }
+        Err(e) => warn!("⚠️ 获取资金费率失败: {:?}", e),
+    }
+}
+
+/// 从账户信息中读取交易资产的持仓数量（空头为负）和开仓均价，没有持仓时返回 (0, 0)
+fn exchange_position(
+    account_info: &hyperliquid_rust_sdk::UserStateResponse,
+    asset: &str,
+) -> Result<(f64, f64), GridStrategyError> {
+    let position = account_info
+        .asset_positions
+        .iter()
+        .map(|asset_position| &asset_position.position)
+        .find(|position| position.coin == asset);
+
+    match position {
+        Some(position) => {
+            let quantity = position.szi.parse::<f64>().map_err(|e| {
+                GridStrategyError::PriceParseError(format!("解析交易所持仓数量失败: {:?}", e))
+            })?;
+            let entry_price = position
+                .entry_px
+                .as_deref()
+                .and_then(|px| px.parse::<f64>().ok())
+                .unwrap_or(0.0);
+            Ok((quantity, entry_price))
+        }
+        None => Ok((0.0, 0.0)),
+    }
+}
+
+/// 启动时接管账户里已有的持仓
+///
+/// 以交易所的持仓数量和均价为准初始化本地持仓，持仓占用的保证金已体现在交易所可提取余额里，
+/// 后续布置卖单网格、止损和持仓上限都基于接管后的持仓计算。
+fn adopt_existing_position(
+    account_info: &hyperliquid_rust_sdk::UserStateResponse,
+    grid_state: &mut GridState,
+    grid_config: &crate::config::GridConfig,
+) -> Result<(), GridStrategyError> {
+    let (exchange_quantity, exchange_entry_price) =
+        exchange_position(account_info, &grid_config.trading_asset)?;
+
+    let local_quantity = grid_state.position_quantity;
+    let min_quantity = 1.0 / 10f64.powi(grid_config.quantity_precision as i32);
+    if (exchange_quantity - local_quantity).abs() < min_quantity {
+        if exchange_quantity.abs() >= min_quantity {
+            info!(
+                "✅ 本地持仓与账户持仓一致 - 数量: {:.4}, 均价: {:.4}",
+                exchange_quantity, grid_state.position_avg_price
+            );
+        }
+        return Ok(());
+    }
+
+    info!(
+        "📥 接管账户已有持仓 - 本地: {:.4}, 账户: {:.4}, 均价: {:.4}",
+        local_quantity, exchange_quantity, exchange_entry_price
+    );
+    if exchange_quantity < 0.0 {
+        warn!("⚠️ 账户持有空头仓位，网格将按持仓模式只挂减仓买单直到空头平掉");
+    }
+
+    grid_state.position_quantity = exchange_quantity;
+    if exchange_quantity == 0.0 {
+        grid_state.position_avg_price = 0.0;
+    } else if exchange_entry_price > 0.0 {
+        grid_state.position_avg_price = exchange_entry_price;
+    }
+    grid_state.sync_position_lots();
+
+    Ok(())
+}
+
+/// 本地持仓与交易所真实持仓对账
+///
+/// 偏差超过 `position_reconcile_threshold`（相对交易所持仓的比例，且至少一个最小数量单位）时，
+/// 以交易所为准修正本地持仓数量、均价和持仓批次，返回修正前后的持仓数量。
+async fn reconcile_position(
+    exchange_client: &dyn OrderExecutor,
+    info_client: &InfoClient,
+    user_address: ethers::types::Address,
+    grid_state: &mut GridState,
+    grid_config: &crate::config::GridConfig,
+) -> Result<Option<(f64, f64)>, GridStrategyError> {
+    let account_info = get_account_info(exchange_client, info_client, user_address).await?;
+    let (exchange_quantity, exchange_entry_price) =
+        exchange_position(&account_info, &grid_config.trading_asset)?;
+
+    let local_quantity = grid_state.position_quantity;
+    let deviation = (exchange_quantity - local_quantity).abs();
+    let min_quantity = 1.0 / 10f64.powi(grid_config.quantity_precision as i32);
+    let tolerance =
+        (exchange_quantity.abs() * grid_config.position_reconcile_threshold).max(min_quantity);
+    if deviation < tolerance {
+        debug!(
+            "✅ 持仓对账一致 - 本地: {:.4}, 交易所: {:.4}",
+            local_quantity, exchange_quantity
+        );
+        return Ok(None);
+    }
+
+    warn!(
+        "⚠️ 持仓对账偏差 - 本地: {:.4}, 交易所: {:.4}, 偏差: {:.4}，以交易所为准修正",
+        local_quantity, exchange_quantity, deviation
+    );
+    grid_state.position_quantity = exchange_quantity;
+    if exchange_quantity == 0.0 {
+        grid_state.position_avg_price = 0.0;
+    } else if exchange_entry_price > 0.0 {
+        grid_state.position_avg_price = exchange_entry_price;
+    }
+    grid_state.sync_position_lots();
+
+    Ok(Some((local_quantity, exchange_quantity)))
+}
+
+// 创建动态网格
+/// 跟随均线模式下网格中心锚定的均线，fixed 模式或均线尚未就绪时返回 None
+fn grid_follow_ma(
+    grid_config: &crate::config::GridConfig,
+    market_analysis: &MarketAnalysis,
+) -> Option<f64> {
+    let ma = match grid_config.grid_center_mode.as_str() {
+        "short_ma" => market_analysis.short_ma,
+        "long_ma" => market_analysis.long_ma,
+        _ => return None,
+    };
+    (ma.is_finite() && ma > 0.0).then_some(ma)
+}
+
+/// 把网格中心平移到 `new_center`：撤掉落在新网格区间外的远端挂单，返回撤单数量
+///
+/// 近端挂单由随后的订单平衡检查按当前价补齐，不需要整体重建网格。
+async fn shift_grid_center(
+    exchange_client: &dyn OrderExecutor,
+    grid_config: &crate::config::GridConfig,
+    grid_state: &mut GridState,
+    new_center: f64,
+    active_orders: &mut Vec<u64>,
+    buy_orders: &mut HashMap<u64, OrderInfo>,
+    sell_orders: &mut HashMap<u64, OrderInfo>,
+) -> usize {
+    let old_center = grid_state.grid_center_price;
+    let lower_price = new_center * (1.0 - grid_config.grid_lower_bound_pct);
+    let upper_price = new_center * (1.0 + grid_config.grid_upper_bound_pct);
+    info!(
+        "↕️ 网格中心平移 {:.4} -> {:.4}，新区间 [{:.4}, {:.4}]",
+        old_center, new_center, lower_price, upper_price
+    );
+
+    // 中心上移时远端是下方的买单，下移时远端是上方的卖单
+    let far_orders: Vec<(u64, bool)> = if new_center > old_center {
+        buy_orders
+            .iter()
+            .filter(|(_, order)| order.price < lower_price)
+            .map(|(oid, _)| (*oid, true))
+            .collect()
+    } else {
+        sell_orders
+            .iter()
+            .filter(|(_, order)| order.price > upper_price)
+            .map(|(oid, _)| (*oid, false))
+            .collect()
+    };
+
+    let mut cancelled = 0;
+    for (oid, is_buy) in far_orders {
+        match cancel_order(exchange_client, oid).await {
+            Ok(_) => {
+                active_orders.retain(|&x| x != oid);
+                if is_buy {
+                    buy_orders.remove(&oid);
+                } else {
+                    sell_orders.remove(&oid);
+                }
+                cancelled += 1;
+            }
+            Err(e) => warn!("⚠️ 平移网格时撤销远端订单失败 - ID: {}, 错误: {:?}", oid, e),
+        }
+    }
+
+    grid_state.grid_center_price = new_center;
+    info!(
+        "✅ 网格平移完成，撤销远端订单{}个，近端订单将按当前价补齐",
+        cancelled
+    );
+    cancelled
+}
+
+// 动态网格数量的参考波动率：波动率等于该值时使用配置的网格数量
+const GRID_COUNT_REFERENCE_VOLATILITY: f64 = 0.015;
+// 波动率带来的网格数量缩放范围
+const GRID_COUNT_MIN_FACTOR: f64 = 0.5;
+const GRID_COUNT_MAX_FACTOR: f64 = 1.5;
+
+/// 按波动率和市场状态计算本次使用的网格数量
+///
+/// 低波动时网格更多更密，吃小波动；高波动时网格更少更宽。波动率缩放限制在
+/// 0.5-1.5 倍之间，再叠加市场状态的缩减系数。
+fn calculate_dynamic_grid_count(
+    base_count: u32,
+    volatility: f64,
+    market_state: &MarketState,
+) -> u32 {
+    let volatility_factor = if volatility.is_finite() && volatility > 0.0 {
+        (GRID_COUNT_REFERENCE_VOLATILITY / volatility)
+            .clamp(GRID_COUNT_MIN_FACTOR, GRID_COUNT_MAX_FACTOR)
+    } else {
+        1.0
+    };
+    let factor = volatility_factor * market_state.grid_reduction_factor();
+    ((base_count as f64 * factor).round() as u32).max(1)
+}
+
+async fn create_dynamic_grid(
+    exchange_client: &dyn OrderExecutor,
+    grid_config: &crate::config::GridConfig,
+    grid_state: &mut GridState,
+    current_price: f64,
+    price_history: &[f64],
+    active_orders: &mut Vec<u64>,
+    buy_orders: &mut HashMap<u64, OrderInfo>,
+    sell_orders: &mut HashMap<u64, OrderInfo>,
+    _order_manager: &mut OrderManager,
+) -> Result<(), GridStrategyError> {
+    info!("🔄 开始创建动态网格...");
+
+    // 分析市场状态
+    let mut market_analysis = analyze_market_trend(
+        price_history,
+        &grid_state.indicators,
+        grid_state.klines.primary(),
+        grid_state.market_state_tracker.confirmed(),
+    );
+    apply_order_book_liquidity(
+        &mut market_analysis,
+        &grid_state.order_book,
+        grid_config,
+        current_price,
+    );
+    market_analysis.market_state = grid_state.market_state_tracker.observe(
+        market_analysis.market_state,
+        grid_config.market_state_confirm_ticks,
+    );
+
+    tr_log!(
+        info,
+        "📊 市场状态检测 - 状态: {}, 风险等级: {}, 流动性: {:.1}, 稳定性: {:.1}",
+        "📊 Market state - state: {}, risk level: {}, liquidity: {:.1}, stability: {:.1}",
+        market_analysis.market_state.localized(),
+        market_analysis.market_state.risk_level(),
+        market_analysis.liquidity_score,
+        market_analysis.price_stability
+    );
+
+    // 检查是否应暂停交易
+    if market_analysis.market_state.should_pause_trading() {
+        tr_log!(
+            warn,
+            "🚨 市场状态异常，暂停网格交易: {}",
+            "🚨 Abnormal market state, grid trading paused: {}",
+            market_analysis.market_state.localized()
+        );
+        return Ok(());
+    }
+
+    // 按网格模式决定挂单方向
+    let (place_buys, place_sells) = grid_mode_sides(grid_config, grid_state.position_quantity);
+    let reduce_only_orders = grid_config.grid_mode == "reduce_only";
+    if !place_buys && !place_sells {
+        info!("ℹ️ reduce_only 模式下当前无持仓，不创建网格");
+        return Ok(());
+    }
+    if grid_config.grid_mode != "normal" {
+        info!(
+            "🎚️ 网格模式: {} - 挂买单: {}, 挂卖单: {}",
+            grid_config.grid_mode, place_buys, place_sells
+        );
+    }
+
+    // 获取自适应资金分配
+    let mut fund_allocation = calculate_adaptive_fund_allocation(
+        grid_state,
+        current_price,
+        grid_config,
+        &market_analysis,
+        price_history,
+    );
+
+    // 使用振幅计算调整网格间距
+    let amplitude_adjustment = if grid_state.klines.primary().len() >= 10 {
+        // 有足够的真实K线，使用K线高低点计算振幅
+        let (avg_up, avg_down) = calculate_kline_amplitude(grid_state.klines.primary());
+        let market_volatility = (avg_up + avg_down) / 2.0;
+        (1.0 + market_volatility * 2.0).clamp(0.5, 2.0)
+    } else if price_history.len() >= 10 {
+        // 有足够的价格历史数据，使用振幅计算
+        let (avg_up, avg_down) = calculate_amplitude(price_history);
+        let market_volatility = (avg_up + avg_down) / 2.0;
+        (1.0 + market_volatility * 2.0).max(0.5).min(2.0)
+    } else if grid_state.historical_volatility > 0.0 {
+        // 使用历史波动率作为振幅调整因子
+        (grid_state.historical_volatility * 10.0).max(0.5).min(2.0)
+    } else {
+        1.0 // 默认不调整
+    };
+
+    // 应用振幅调整到间距
+    fund_allocation.buy_spacing_adjustment *= amplitude_adjustment;
+    fund_allocation.sell_spacing_adjustment *= amplitude_adjustment;
+
+    // ATR 间距模式：单格间距直接取 k × ATR，数据不足时回退到固定模式
+    let atr_spacing = if grid_config.spacing_mode == "atr" {
+        let spacing = atr_grid_spacing(
+            price_history,
+            grid_state.klines.primary(),
+            current_price,
+            grid_config,
+        );
+        match spacing {
+            Some(spacing) => info!(
+                "📐 ATR间距模式 - 单格间距: {:.4}% (系数: {:.2})",
+                spacing * 100.0,
+                grid_config.atr_multiplier
+            ),
+            None => warn!("⚠️ 价格历史不足以计算ATR，本次使用固定间距模式"),
+        }
+        spacing
+    } else {
+        None
+    };
+
+    // 基于波动率和市场状态调整网格数量
+    let grid_reduction = market_analysis.market_state.grid_reduction_factor();
+    let mut adjusted_grid_count = calculate_dynamic_grid_count(
+        grid_config.grid_count,
+        market_analysis.volatility,
+        &market_analysis.market_state,
+    );
+    // 资金必须覆盖动态后的网格数，保证每格资金不低于最小下单名义价值
+    if grid_config.min_notional > 0.0 {
+        let max_count_by_funds =
+            ((grid_state.total_capital / grid_config.min_notional).floor() as u32).max(1);
+        if adjusted_grid_count > max_count_by_funds {
+            warn!(
+                "⚠️ 资金不足以覆盖{}格，网格数量限制为{} (总资金: {:.2}, 最小名义价值: {:.2})",
+                adjusted_grid_count,
+                max_count_by_funds,
+                grid_state.total_capital,
+                grid_config.min_notional
+            );
+            adjusted_grid_count = max_count_by_funds;
+        }
+    }
+    if adjusted_grid_count != grid_config.grid_count {
+        info!(
+            "📏 动态网格数量 - 配置: {}, 本次: {} (波动率: {:.2}%, 市场状态: {})",
+            grid_config.grid_count,
+            adjusted_grid_count,
+            market_analysis.volatility * 100.0,
+            market_analysis.market_state.localized()
+        );
+    }
+
+    // 检查当前订单数量，严格控制总数不超过配置限制
+    let current_total_orders = active_orders.len();
+    let remaining_order_slots = if current_total_orders >= grid_config.max_active_orders as usize {
+        warn!(
+            "⚠️ 当前订单数量({})已达到或超过配置限制({}), 停止创建新订单",
+            current_total_orders, grid_config.max_active_orders
+        );
+        return Ok(());
+    } else {
+        grid_config.max_active_orders as usize - current_total_orders
+    };
+
+    // 自适应网格：买单和卖单数量应该相等，平分剩余订单槽位；单向模式下槽位全部给挂单方向
+    // 如果剩余槽位是奇数，优先给买单（因为网格策略通常从买入开始）
+    let (max_new_buy_orders, max_new_sell_orders) = match (place_buys, place_sells) {
+        (true, false) => (remaining_order_slots, 0),
+        (false, true) => (0, remaining_order_slots),
+        _ => (
+            remaining_order_slots - remaining_order_slots / 2,
+            remaining_order_slots / 2,
+        ),
+    };
+
+    let final_buy_limit = adjusted_grid_count.min(max_new_buy_orders as u32);
+    let final_sell_limit = adjusted_grid_count.min(max_new_sell_orders as u32);
+
+    info!("📊 订单数量控制 - 当前总订单: {}, 配置限制: {}, 剩余槽位: {}, 最大新买单: {}, 最大新卖单: {}",
+          current_total_orders, grid_config.max_active_orders, remaining_order_slots,
+          final_buy_limit, final_sell_limit);
+
+    if market_analysis
+        .market_state
+        .requires_conservative_strategy()
+    {
+        // 保守策略：减少资金使用，增加间距
+        fund_allocation.buy_spacing_adjustment *= 1.2;
+        fund_allocation.sell_spacing_adjustment *= 1.2;
+        fund_allocation.buy_order_funds *= 0.8;
+        fund_allocation.sell_order_funds *= 0.8;
+
+        warn!(
+            "⚠️ 启用保守策略 - 网格缩减: {:.0}%, 间距增加: 20%",
+            (1.0 - grid_reduction) * 100.0
+        );
+    }
+
+    info!(
+        "💰 资金分配 - 买单资金: {:.2}, 卖单资金: {:.2}, 持仓比例: {:.2}%, 振幅调整: {:.2}",
+        fund_allocation.buy_order_funds,
+        fund_allocation.sell_order_funds,
+        fund_allocation.position_ratio * 100.0,
+        amplitude_adjustment
+    );
+
+    // 添加详细的调试信息
+    info!(
+        "🔍 网格创建调试信息 - 当前价格: {:.4}, 总资金: {:.2}, 可用资金: {:.2}, 网格数量: {}",
+        current_price, grid_state.total_capital, grid_state.available_funds, adjusted_grid_count
+    );
+
+    info!(
+        "🔍 动态参数 - 最小间距: {:.6}, 最大间距: {:.6}, 交易金额: {:.2}",
+        grid_state.dynamic_params.current_min_spacing,
+        grid_state.dynamic_params.current_max_spacing,
+        grid_state.dynamic_params.current_trade_amount
+    );
+
+    info!(
+        "🔍 配置参数 - 最小利润: {:.4}, 手续费率: {:.6}, 价格精度: {}, 数量精度: {}",
+        grid_config.min_profit,
+        grid_config.maker_fee(),
+        grid_config.price_precision,
+        grid_config.quantity_precision
+    );
+
+    // 网格中心价：跟随均线模式下锚定均线，再按 grid_price_offset 整体上移/下移网格，挂单仍不越过市价
+    let center_base_price = match grid_follow_ma(grid_config, &market_analysis) {
+        Some(ma) => {
+            info!(
+                "📈 网格中心锚定{} - 均线: {:.4}, 市价: {:.4}",
+                grid_config.grid_center_mode, ma, current_price
+            );
+            ma
+        }
+        None => current_price,
+    };
+    let grid_center_price = center_base_price * (1.0 + grid_config.grid_price_offset);
+    grid_state.grid_center_price = grid_center_price;
+    if grid_config.grid_price_offset != 0.0 {
+        info!(
+            "🎯 网格中心价偏移 {:+.2}% - 市价: {:.4}, 中心价: {:.4}",
+            grid_config.grid_price_offset * 100.0,
+            current_price,
+            grid_center_price
+        );
+    }
+
+    // 创建买单 - 智能价格设置策略
+    let buy_start_price =
+        if grid_state.position_avg_price > 0.0 && grid_state.position_quantity > 0.0 {
+            // 如果有持仓，基于成本价和当前价格的智能组合设置买单起始价格
+            let cost_weight = grid_state.position_quantity
+                / (grid_state.position_quantity + fund_allocation.buy_order_funds / current_price);
+            let cost_based_price = grid_state.position_avg_price * 0.98; // 成本价下方2%
+            let market_based_price = grid_center_price * 0.995; // 中心价下方0.5%
+
+            // 加权平均，持仓越多越偏向成本价
+            let weighted_price =
+                cost_based_price * cost_weight + market_based_price * (1.0 - cost_weight);
+
+            info!(
+                "🎯 智能买单起始价 - 成本价: {:.4}, 市价: {:.4}, 权重: {:.2}, 起始价: {:.4}",
+                grid_state.position_avg_price, current_price, cost_weight, weighted_price
+            );
+
+            weighted_price.min(current_price * 0.999) // 确保不高于市价
+        } else {
+            // 如果没有持仓，从中心价稍下方开始，且不高于市价
+            (grid_center_price * 0.995).min(current_price * 0.999) // 中心价下方0.5%
+        };
+
+    let max_buy_funds = grid_state.available_funds * 0.7; // 最多使用70%资金做买单
+    let mut allocated_buy_funds = 0.0;
+    let mut buy_count = 0;
+    // reduce_only 模式下买单只用于平空，累计数量不超过空头持仓（扣除保留的买单）
+    let kept_buy_quantity: f64 = buy_orders.values().map(|order| order.quantity).sum();
+    let mut reducible_short_quantity =
+        ((-grid_state.position_quantity).max(0.0) - kept_buy_quantity).max(0.0);
+
+    // 反金字塔的总金额硬上限：现有多头持仓和保留的买单都计入
+    let mut martingale_exposure = grid_state.position_quantity.max(0.0) * current_price
+        + buy_orders
+            .values()
+            .map(|order| order.price * order.quantity)
+            .sum::<f64>();
+
+    // 收集要批量创建的买单
+    let mut pending_buy_orders: Vec<ClientOrderRequest> = Vec::new();
+    let mut pending_buy_order_info: Vec<OrderInfo> = Vec::new();
+
+    // 网格价格区间
+    let grid_lower_price = grid_center_price * (1.0 - grid_config.grid_lower_bound_pct);
+    let grid_upper_price = grid_center_price * (1.0 + grid_config.grid_upper_bound_pct);
+
+    // 按配置的布点算法生成档位，买卖两侧共用同一算法
+    let layout = grid_layout(grid_config);
+    let best_bid = grid_state.order_book.best_bid();
+    let best_ask = grid_state.order_book.best_ask();
+    let buy_levels = layout.generate_levels(
+        &LayoutCenter {
+            is_buy: true,
+            center_price: grid_center_price,
+            market_price: current_price,
+            start_price: buy_start_price,
+            bound_price: grid_lower_price,
+            base_spacing: atr_spacing.unwrap_or(
+                grid_state.dynamic_params.current_min_spacing
+                    * fund_allocation.buy_spacing_adjustment
+                    * amplitude_adjustment,
+            ),
+            cost_price: grid_state.position_avg_price,
+            best_bid,
+            best_ask,
+        },
+        grid_config,
+    );
+
+    info!(
+        "🔄 开始智能买单循环 - 布点: {}, 建仓模型: {}, 档位: {}, 起始价: {:.4} (持仓成本: {:.4}), 下限: {:.4}, 最大资金: {:.2}, 最大买单数: {}",
+        layout.name(),
+        grid_config.position_sizing,
+        buy_levels.len(),
+        buy_start_price,
+        grid_state.position_avg_price,
+        grid_lower_price,
+        max_buy_funds,
+        final_buy_limit
+    );
+
+    let reverse_pyramid = grid_config.position_sizing == "reverse_pyramid";
+    for (level_index, level) in buy_levels.into_iter().enumerate() {
+        if allocated_buy_funds >= max_buy_funds || buy_count >= final_buy_limit {
+            break;
+        }
+        if reverse_pyramid && level_index >= grid_config.martingale_max_levels as usize {
+            info!(
+                "🚫 反金字塔已达最大加仓档数({})，停止创建买单",
+                grid_config.martingale_max_levels
+            );
+            break;
+        }
+        let current_buy_price = level.price;
+        let final_spacing = level.spacing;
+
+        info!(
+            "📏 网格档位 - 价格: {:.4}, 间距: {:.6}",
+            current_buy_price, final_spacing
+        );
+
+        // 智能资金分配策略 - 基于成本价和价格位置
+        let dynamic_trade_amount = grid_state.dynamic_params.current_trade_amount;
+        let base_grid_funds =
+            fund_allocation.buy_order_funds * dynamic_trade_amount / grid_config.trade_amount;
+
+        // 成本价导向的资金分配（adaptive），其余模型按档位序号计算
+        let mut current_grid_funds = if grid_config.position_sizing != "adaptive" {
+            position_sizing_funds(grid_config, base_grid_funds, level_index)
+        } else if grid_state.position_avg_price > 0.0 {
+            let distance_from_cost =
+                (current_buy_price - grid_state.position_avg_price) / grid_state.position_avg_price;
+            if distance_from_cost < -0.1 {
+                // 远低于成本价（超过10%）：增加资金分配，抄底机会
+                base_grid_funds * 1.5
+            } else if distance_from_cost < 0.0 {
+                // 低于成本价：正常或略微增加资金分配
+                base_grid_funds * (1.0 + (-distance_from_cost) * 0.5)
+            } else {
+                // 高于成本价：减少资金分配，避免追高
+                base_grid_funds * (1.0 - distance_from_cost * 2.0).max(0.3)
+            }
+        } else {
+            // 无持仓时：基于距离网格中心的远近分配资金
+            let market_distance = (grid_center_price - current_buy_price) / grid_center_price;
+            base_grid_funds * (1.0 + market_distance * 2.0) // 距离中心越远，资金越多
+        };
+
+        // 确保资金在合理范围内，显式的建仓模型不做裁剪，保持各档比例
+        if grid_config.position_sizing == "adaptive" {
+            current_grid_funds = current_grid_funds
+                .max(fund_allocation.buy_order_funds * 0.3) // 最小30%
+                .min(fund_allocation.buy_order_funds * 2.0); // 最大200%
+        }
+
+        // 反金字塔越往下资金越多，总额不超过硬上限
+        if reverse_pyramid {
+            let remaining = (grid_config.martingale_max_funds - martingale_exposure).max(0.0);
+            if current_grid_funds > remaining {
+                info!(
+                    "🧱 反金字塔总额达到上限 - 已占用: {:.2}, 上限: {:.2}, 本档资金: {:.2} -> {:.2}",
+                    martingale_exposure,
+                    grid_config.martingale_max_funds,
+                    current_grid_funds,
+                    remaining
+                );
+                current_grid_funds = remaining;
+            }
+        }
+
+        // 检查资金限制
+        if allocated_buy_funds + current_grid_funds > max_buy_funds {
+            current_grid_funds = max_buy_funds - allocated_buy_funds;
+        }
+
+        // 盘口深度不足时缩小单笔买单
+        let depth_limited_quantity = limit_quantity_by_depth(
+            &grid_state.order_book,
+            grid_config,
+            true,
+            current_grid_funds / current_buy_price,
+        );
+        current_grid_funds = current_grid_funds.min(depth_limited_quantity * current_buy_price);
+
+        if current_grid_funds < fund_allocation.buy_order_funds * 0.1 {
+            info!(
+                "🚫 买单资金不足 - 当前网格资金: {:.2}, 最小要求: {:.2}, 停止创建买单",
+                current_grid_funds,
+                fund_allocation.buy_order_funds * 0.1
+            );
+            break; // 资金太少，停止创建买单
+        }
+
+        let buy_quantity = format_price(
+            current_grid_funds / current_buy_price,
+            grid_config.quantity_precision,
+        );
+        let buy_quantity = if reduce_only_orders {
+            buy_quantity.min(reducible_short_quantity)
+        } else {
+            buy_quantity
+        };
+        if reduce_only_orders && buy_quantity <= 0.0 {
+            info!("🚫 空头持仓已全部挂出平仓买单，停止创建买单");
+            break;
+        }
+
+        // 验证潜在利润
+        let potential_sell_price = current_buy_price * (1.0 + final_spacing);
+        let expected_profit_rate = calculate_expected_profit_rate(
+            current_buy_price,
+            potential_sell_price,
+            grid_config.maker_fee(),
+            grid_state.funding_cost(grid_config),
+        );
+
+        info!(
+            "🔍 买单利润检查 - 买入价: {:.4}, 潜在卖出价: {:.4}, 预期利润率: {:.4}%, 最小要求: {:.4}%",
+            current_buy_price,
+            potential_sell_price,
+            expected_profit_rate * 100.0,
+            min_profit_rate(grid_config) * 100.0
+        );
+
+        if expected_profit_rate >= min_profit_rate(grid_config) {
+            let formatted_price = format_price(current_buy_price, grid_config.price_precision);
+            if has_nearby_order(buy_orders, formatted_price, price_tick(grid_config)) {
+                info!("⏭️ 价位 {:.4} 已有买单，跳过重复挂单", formatted_price);
+                continue;
+            }
+            if !meets_min_notional(formatted_price, buy_quantity, grid_config) {
+                info!(
+                    "⏭️ 买单名义价值({:.2})低于最小下单金额({:.2})，跳过该档",
+                    formatted_price * buy_quantity,
+                    grid_config.min_notional
+                );
+                continue;
+            }
+
+            let cloid = new_cloid();
+            let buy_order = ClientOrderRequest {
+                asset: grid_config.trading_asset.clone(),
+                is_buy: true,
+                reduce_only: reduce_only_orders,
+                limit_px: formatted_price,
+                sz: buy_quantity,
+                cloid: Some(cloid),
+                order_type: ClientOrder::Limit(ClientLimit {
+                    tif: grid_order_tif(grid_config),
+                }),
+            };
+
+            // 收集订单信息，准备批量创建
+            pending_buy_orders.push(buy_order);
+            pending_buy_order_info.push(OrderInfo {
+                is_buy: true,
+                price: formatted_price,
+                quantity: buy_quantity,
+                cost_price: None,
+                potential_sell_price: Some(potential_sell_price),
+                allocated_funds: 0.0, // 资金占用以交易所余额为准，本地不记账
+                iceberg: None,
+                created_at: safe_unix_timestamp(),
+                cloid: Some(cloid),
+                filled_quantity: 0.0,
+                unhedged_quantity: 0.0,
+                grid_pair: false,
+            });
+
+            allocated_buy_funds += current_grid_funds;
+            martingale_exposure += formatted_price * buy_quantity;
+            buy_count += 1;
+            if reduce_only_orders {
+                reducible_short_quantity -= buy_quantity;
+            }
+
+            info!(
+                "✅ 买单准备就绪 - 价格: {:.4}, 数量: {:.4}, 资金: {:.2}, 累计资金: {:.2}",
+                formatted_price, buy_quantity, current_grid_funds, allocated_buy_funds
+            );
+        } else {
+            info!(
+                "❌ 买单利润不足 - 预期利润率: {:.4}% < 最小要求: {:.4}%, 跳过此价格",
+                expected_profit_rate * 100.0,
+                min_profit_rate(grid_config) * 100.0
+            );
+        }
+    }
+
+    // 增强版批量创建买单 - 包含资源管理和错误恢复
+    if !pending_buy_orders.is_empty() {
+        let order_count = pending_buy_orders.len();
+        info!("📦 开始增强批量创建{}个买单", order_count);
+
+        // 资源预检查
+        if order_count > 200 {
+            warn!("⚠️ 买单数量较多({}个)，将启用保守模式", order_count);
+        }
+
+        // 使用超时控制的批量创建
+        let creation_timeout = Duration::from_secs(if order_count > 100 { 600 } else { 300 });
+        let (pending_buy_orders, pending_buy_order_info) =
+            expand_iceberg_orders(pending_buy_orders, pending_buy_order_info, grid_config);
+        let order_count = pending_buy_orders.len();
+        let mut temp_batch_optimizer = BatchTaskOptimizer::new(
+            grid_config.max_orders_per_batch.max(5),
+            Duration::from_secs(3),
+        );
+        let creation_result = tokio::time::timeout(
+            creation_timeout,
+            create_orders_in_batches(
+                exchange_client,
+                pending_buy_orders,
+                grid_config,
+                grid_state,
+                &mut temp_batch_optimizer,
+            ),
+        )
+        .await;
+
+        match creation_result {
+            Ok(Ok((created_order_ids, failed_order_infos))) => {
+                // 批量创建成功
+                let success_count = created_order_ids.len();
+                let success_rate = success_count as f64 / order_count as f64 * 100.0;
+
+                // 将创建成功的订单按 cloid 对应回订单信息，添加到管理列表
+                for (order_id, order_info) in
+                    match_created_orders(&created_order_ids, &pending_buy_order_info)
+                {
+                    info!(
+                        "🟢 买单创建成功: ID={}, 价格={:.4}, 数量={:.4}, 资金={:.2}",
+                        order_id, order_info.price, order_info.quantity, order_info.allocated_funds
+                    );
+                    active_orders.push(order_id);
+                    buy_orders.insert(order_id, order_info);
+                }
+
+                info!(
+                    "✅ 批量买单创建完成: {}/{} (成功率: {:.1}%)",
+                    success_count, order_count, success_rate
+                );
+
+                // 处理失败的订单进行重试
+                if !failed_order_infos.is_empty() && failed_order_infos.len() <= 20 {
+                    info!("🔄 开始重试{}个失败的买单", failed_order_infos.len());
+
+                    let retry_result =
+                        retry_failed_order_infos(exchange_client, failed_order_infos, grid_config)
+                            .await;
+
+                    match retry_result {
+                        Ok(retry_successful_orders) => {
+                            // 将重试成功的订单也添加到管理列表，优先使用原始订单信息
+                            for (order_id, retried_info) in retry_successful_orders {
+                                let order_info = pending_buy_order_info
+                                    .iter()
+                                    .find(|info| match (info.cloid, retried_info.cloid) {
+                                        (Some(cloid), Some(retried)) => cloid == retried,
+                                        _ => {
+                                            (info.price - retried_info.price).abs() < f64::EPSILON
+                                                && (info.quantity - retried_info.quantity).abs()
+                                                    < f64::EPSILON
+                                        }
+                                    })
+                                    .cloned()
+                                    .unwrap_or(retried_info);
+                                info!(
+                                    "🔄✅ 重试买单成功: ID={}, 价格={:.4}, 数量={:.4}",
+                                    order_id, order_info.price, order_info.quantity
+                                );
+                                active_orders.push(order_id);
+                                buy_orders.insert(order_id, order_info);
+                            }
+                        }
+                        Err(e) => {
+                            warn!("🔄❌ 重试买单失败: {:?}", e);
+                        }
+                    }
+                }
+
+                // 根据成功率调整后续策略
+                if success_rate < 70.0 {
+                    warn!(
+                        "⚠️ 买单创建成功率较低({:.1}%)，调整资金分配策略",
+                        success_rate
+                    );
+                    // 按实际成功比例调整已分配资金
+                    allocated_buy_funds *= success_rate / 100.0;
+                    buy_count = success_count as u32;
+                } else if success_rate >= 95.0 {
+                    info!("🎯 买单创建成功率优秀({:.1}%)，保持当前策略", success_rate);
+                }
+            }
+            Ok(Err(e)) => {
+                error!("❌ 批量买单创建失败: {:?}", e);
+
+                // 智能错误恢复策略
+                if pending_buy_order_info.len() <= 20 {
+                    warn!("🔄 订单数量较少，尝试单个创建模式");
+                    let recovery_result = create_orders_individually(
+                        exchange_client,
+                        &pending_buy_order_info,
+                        grid_config,
+                        active_orders,
+                        buy_orders,
+                        true, // is_buy_order
+                    )
+                    .await;
+
+                    match recovery_result {
+                        Ok(recovery_count) => {
+                            info!("🔄✅ 单个创建模式成功创建{}个买单", recovery_count);
+                            allocated_buy_funds *= recovery_count as f64 / order_count as f64;
+                            buy_count = recovery_count as u32;
+                        }
+                        Err(recovery_err) => {
+                            error!("🔄❌ 单个创建模式也失败: {:?}", recovery_err);
+                            // 完全回滚资金分配
+                            allocated_buy_funds = 0.0;
+                            buy_count = 0;
+                        }
+                    }
+                } else {
+                    warn!("⚠️ 订单数量过多，跳过恢复尝试，完全回滚");
+                    // 完全回滚资金分配
+                    allocated_buy_funds = 0.0;
+                    buy_count = 0;
+                }
+            }
+            Err(_timeout) => {
+                error!("⏰ 批量买单创建超时({}秒)", creation_timeout.as_secs());
+
+                // 超时后的保守恢复策略
+                warn!("🔄 超时后尝试创建少量关键买单");
+                let critical_orders: Vec<_> = pending_buy_order_info
+                    .into_iter()
+                    .take(10) // 只创建前10个最重要的订单
+                    .collect();
+
+                if !critical_orders.is_empty() {
+                    let recovery_result = create_orders_individually(
+                        exchange_client,
+                        &critical_orders,
+                        grid_config,
+                        active_orders,
+                        buy_orders,
+                        true,
+                    )
+                    .await;
+
+                    match recovery_result {
+                        Ok(recovery_count) => {
+                            info!("🔄✅ 关键买单创建成功: {}", recovery_count);
+                            allocated_buy_funds *= recovery_count as f64 / order_count as f64;
+                            buy_count = recovery_count as u32;
+                        }
+                        Err(_) => {
+                            warn!("🔄❌ 关键买单创建也失败，完全回滚");
+                            allocated_buy_funds = 0.0;
+                            buy_count = 0;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // 创建卖单 - 基于成本价设置，确保盈利
+    let sell_start_price = if grid_state.position_avg_price > 0.0 {
+        // 如果有持仓，基于成本价设置卖单起始价格
+        let min_profitable_price = calculate_min_sell_price(
+            grid_state.position_avg_price,
+            grid_config.maker_fee(),
+            min_profit_rate(grid_config),
+        );
+        // 确保卖单价格不低于最小盈利价格，但也不要过于偏离中心价
+        let market_based_price = (grid_center_price * 1.005).max(current_price * 1.001); // 中心价上浮0.5%
+        min_profitable_price.max(market_based_price)
+    } else {
+        // 如果没有持仓，基于中心价设置，且不低于市价
+        (grid_center_price * 1.005).max(current_price * 1.001) // 中心价上浮0.5%
+    };
+
+    // 自适应卖单数量计算，reduce_only 模式下卖单只平多，总量不超过多头持仓
+    let max_sell_quantity = if reduce_only_orders {
+        grid_state.position_quantity.max(0.0)
+    } else {
+        match fund_allocation.grid_strategy {
+            GridStrategy::PureBear | GridStrategy::BearishBias => {
+                // 做空策略：允许超过持仓的卖单（做空）
+                let existing_position = grid_state.position_quantity * 0.8;
+                let short_allowance = fund_allocation.max_short_exposure / current_price;
+                existing_position + short_allowance
+            }
+            _ => {
+                // 其他策略：基于持仓和资金的卖单
+                let existing_position = grid_state.position_quantity * 0.8;
+                let cash_based_quantity = fund_allocation.sell_order_funds
+                    * grid_config.grid_count as f64
+                    / current_price;
+                existing_position.max(cash_based_quantity)
+            }
+        }
+    };
+
+    // 保留的卖单已占用部分可卖数量
+    let mut allocated_sell_quantity: f64 = sell_orders.values().map(|order| order.quantity).sum();
+    let mut sell_count = 0;
+
+    // 收集要批量创建的卖单
+    let mut pending_sell_orders: Vec<ClientOrderRequest> = Vec::new();
+    let mut pending_sell_order_info: Vec<OrderInfo> = Vec::new();
+
+    let sell_levels = layout.generate_levels(
+        &LayoutCenter {
+            is_buy: false,
+            center_price: grid_center_price,
+            market_price: current_price,
+            start_price: sell_start_price,
+            bound_price: grid_upper_price,
+            base_spacing: atr_spacing.unwrap_or(
+                grid_state.dynamic_params.current_min_spacing
+                    * fund_allocation.sell_spacing_adjustment
+                    * amplitude_adjustment,
+            ),
+            cost_price: grid_state.position_avg_price,
+            best_bid,
+            best_ask,
+        },
+        grid_config,
+    );
+
+    info!(
+        "🔄 开始卖单循环 - 布点: {}, 档位: {}, 初始卖出价: {:.4} (基于成本价: {:.4}), 价格上限: {:.4}, 最大数量: {:.4}, 最大卖单数: {}",
+        layout.name(),
+        sell_levels.len(),
+        sell_start_price,
+        grid_state.position_avg_price,
+        grid_upper_price,
+        max_sell_quantity,
+        final_sell_limit
+    );
+
+    for level in sell_levels {
+        if allocated_sell_quantity >= max_sell_quantity || sell_count >= final_sell_limit {
+            break;
+        }
+        let mut current_sell_price = level.price;
+
+        // 自适应卖单数量计算
+        let price_coefficient = (current_sell_price - grid_center_price) / grid_center_price;
+        let mut current_grid_quantity = match fund_allocation.grid_strategy {
+            GridStrategy::PureBear | GridStrategy::BearishBias => {
+                // 做空策略：基于资金计算数量
+                fund_allocation.sell_order_funds / current_sell_price
+                    * (1.0 + price_coefficient * 0.5)
+            }
+            _ => {
+                // 其他策略：基于持仓和资金
+                let position_based = if grid_state.position_quantity > 0.0 {
+                    grid_state.position_quantity / grid_config.grid_count as f64 * 0.8
+                } else {
+                    0.0
+                };
+                let fund_based = fund_allocation.sell_order_funds / current_sell_price;
+                position_based.max(fund_based)
+            }
+        };
+
+        // 确保不超过可售数量
+        if allocated_sell_quantity + current_grid_quantity > max_sell_quantity {
+            current_grid_quantity = max_sell_quantity - allocated_sell_quantity;
+        }
+
+        // 盘口深度不足时缩小单笔卖单
+        current_grid_quantity = limit_quantity_by_depth(
+            &grid_state.order_book,
+            grid_config,
+            false,
+            current_grid_quantity,
+        );
+
+        // 最小数量检查
+        let min_order_value = fund_allocation.sell_order_funds * 0.05; // 最小5%
+        if current_grid_quantity * current_sell_price < min_order_value {
+            info!(
+                "🚫 卖单价值过小 - 当前价值: {:.2}, 最小要求: {:.2}, 停止创建卖单",
+                current_grid_quantity * current_sell_price,
+                min_order_value
+            );
+            break;
+        }
+
+        // 严格验证利润要求 - 基于成本价
+        if grid_state.position_avg_price > 0.0 {
+            let funding_cost = grid_state.funding_cost(grid_config);
+            let actual_profit_rate = calculate_expected_profit_rate(
+                grid_state.position_avg_price,
+                current_sell_price,
+                grid_config.maker_fee(),
+                funding_cost,
+            );
+            let min_required_profit_rate = min_profit_rate(grid_config);
+
+            if actual_profit_rate < min_required_profit_rate {
+                // 如果利润不足，调整价格到最小盈利要求（含预期资金费成本）
+                let min_required_price = calculate_min_sell_price(
+                    grid_state.position_avg_price,
+                    grid_config.maker_fee(),
+                    min_required_profit_rate + funding_cost,
+                );
+                current_sell_price = min_required_price;
+
+                info!(
+                    "📈 调整卖单价格确保盈利 - 成本价: {:.4}, 调整后价格: {:.4}, 预期利润率: {:.4}%",
+                    grid_state.position_avg_price,
+                    current_sell_price,
+                    calculate_expected_profit_rate(grid_state.position_avg_price, current_sell_price, grid_config.maker_fee(), funding_cost) * 100.0
+                );
+            } else {
+                info!(
+                    "✅ 卖单利润验证通过 - 成本价: {:.4}, 卖出价: {:.4}, 利润率: {:.4}%",
+                    grid_state.position_avg_price,
+                    current_sell_price,
+                    actual_profit_rate * 100.0
+                );
+            }
+        }
+
+        if current_grid_quantity > 0.0 {
+            let formatted_price = format_price(current_sell_price, grid_config.price_precision);
+            if has_nearby_order(sell_orders, formatted_price, price_tick(grid_config)) {
+                info!("⏭️ 价位 {:.4} 已有卖单，跳过重复挂单", formatted_price);
+                continue;
+            }
+            let formatted_quantity =
+                format_price(current_grid_quantity, grid_config.quantity_precision);
+            if !meets_min_notional(formatted_price, formatted_quantity, grid_config) {
+                info!(
+                    "⏭️ 卖单名义价值({:.2})低于最小下单金额({:.2})，跳过该档",
+                    formatted_price * formatted_quantity,
+                    grid_config.min_notional
+                );
+                continue;
+            }
+
+            let cloid = new_cloid();
+            let sell_order = ClientOrderRequest {
+                asset: grid_config.trading_asset.clone(),
+                is_buy: false,
+                reduce_only: reduce_only_orders,
+                limit_px: formatted_price,
+                sz: formatted_quantity,
+                cloid: Some(cloid),
+                order_type: ClientOrder::Limit(ClientLimit {
+                    tif: grid_order_tif(grid_config),
+                }),
+            };
+
+            // 收集卖单信息，准备批量创建
+            pending_sell_orders.push(sell_order);
+            pending_sell_order_info.push(OrderInfo {
+                is_buy: false,
+                price: formatted_price,
+                quantity: formatted_quantity,
+                cost_price: Some(grid_state.position_avg_price),
+                potential_sell_price: None,
+                allocated_funds: 0.0, // 资金占用以交易所余额为准，本地不记账
+                iceberg: None,
+                created_at: safe_unix_timestamp(),
+                cloid: Some(cloid),
+                filled_quantity: 0.0,
+                unhedged_quantity: 0.0,
+                grid_pair: false,
+            });
+
+            allocated_sell_quantity += formatted_quantity;
+            sell_count += 1;
+        }
+    }
+
+    // 批量创建卖单
+    if !pending_sell_orders.is_empty() {
+        let (pending_sell_orders, pending_sell_order_info) =
+            expand_iceberg_orders(pending_sell_orders, pending_sell_order_info, grid_config);
+        let sell_order_count = pending_sell_orders.len();
+        info!("📦 开始批量创建{}个卖单", sell_order_count);
+
+        let mut temp_batch_optimizer = BatchTaskOptimizer::new(
+            grid_config.max_orders_per_batch.max(5),
+            Duration::from_secs(3),
+        );
+        match create_orders_in_batches(
+            exchange_client,
+            pending_sell_orders,
+            grid_config,
+            grid_state,
+            &mut temp_batch_optimizer,
+        )
+        .await
+        {
+            Ok((created_order_ids, _failed_sell_order_infos)) => {
+                // 将创建成功的订单按 cloid 对应回订单信息，添加到管理列表
+                for (order_id, order_info) in
+                    match_created_orders(&created_order_ids, &pending_sell_order_info)
+                {
+                    info!(
+                        "🔴 卖单创建成功: ID={}, 价格={:.4}, 数量={:.4}",
+                        order_id, order_info.price, order_info.quantity
+                    );
+                    active_orders.push(order_id);
+                    sell_orders.insert(order_id, order_info);
+                }
+                info!(
+                    "✅ 批量卖单创建完成: {}/{}",
+                    created_order_ids.len(),
+                    sell_order_count
+                );
+            }
+            Err(e) => {
+                warn!("❌ 批量卖单创建失败: {:?}", e);
+                // 回滚数量分配
+                allocated_sell_quantity = 0.0;
+                sell_count = 0;
+            }
+        }
+    }
+
+    // 可用资金只按交易所余额同步，挂单和成交都不在本地扣减
+
+    tr_log!(info, "✅ 自适应网格创建完成 - 策略: {}, 买单数量: {}, 卖单数量: {}, 已分配买单资金: {:.2}, 已分配卖单数量: {:.4}, 最大做空敞口: {:.2}",
+        "✅ Adaptive grid created - strategy: {}, buy orders: {}, sell orders: {}, allocated buy funds: {:.2}, allocated sell quantity: {:.4}, max short exposure: {:.2}",
+        fund_allocation.grid_strategy.localized(), buy_count, sell_count, allocated_buy_funds, allocated_sell_quantity, fund_allocation.max_short_exposure);
+
+    Ok(())
+}
+
+// 重平衡网格
+// 智能订单更新函数
+async fn smart_update_orders(
+    exchange_client: &dyn OrderExecutor,
+    grid_config: &crate::config::GridConfig,
+    grid_state: &mut GridState,
+    current_price: f64,
+    price_history: &[f64],
+    active_orders: &mut Vec<u64>,
+    buy_orders: &mut HashMap<u64, OrderInfo>,
+    sell_orders: &mut HashMap<u64, OrderInfo>,
+    _batch_optimizer: &mut BatchTaskOptimizer,
+) -> Result<bool, GridStrategyError> {
+    let now = SystemTime::now();
+
+    // 分析市场状况
+    let mut market_analysis = analyze_market_trend(
+        price_history,
+        &grid_state.indicators,
+        grid_state.klines.primary(),
+        grid_state.market_state_tracker.confirmed(),
+    );
+    market_analysis.market_state = grid_state.market_state_tracker.observe(
+        market_analysis.market_state,
+        grid_config.market_state_confirm_ticks,
+    );
+
+    // 计算订单成功率
+    let total_orders = buy_orders.len() + sell_orders.len();
+    let current_success_rate = if total_orders > 0 {
+        // 简化的成功率计算，实际应该基于历史成交数据
+        0.8 // 默认80%成功率，可以根据实际情况调整
+    } else {
+        0.8
+    };
+
+    // 使用自适应配置计算动态订单存活时间
+    let adaptive_max_age = {
+        let mut adaptive_config = grid_state.adaptive_order_config.clone();
+        let result = adaptive_config.calculate_adaptive_max_age(
+            &market_analysis,
+            grid_state,
+            current_success_rate,
+        );
+        grid_state.adaptive_order_config = adaptive_config;
+        result
+    };
+
+    // 更新 max_order_age_minutes 为自适应值
+    grid_state.max_order_age_minutes = adaptive_max_age;
+
+    // 检查是否需要更新订单
+    let price_change_ratio =
+        (current_price - grid_state.last_grid_price).abs() / grid_state.last_grid_price;
+    let time_since_last_update = now
+        .duration_since(grid_state.last_price_update)
+        .unwrap_or(Duration::from_secs(0));
+
+    // 检查订单年龄
+    let order_age_minutes = time_since_last_update.as_secs() as f64 / 60.0;
+    let orders_too_old = order_age_minutes >= adaptive_max_age;
+
+    // 检查买单是否远离当前价格
+    let mut orders_too_far = false;
+    if !buy_orders.is_empty() {
+        let highest_buy_price = buy_orders
+            .values()
+            .map(|order| order.price)
+            .fold(0.0, f64::max);
+
+        // 如果最高买单价格低于当前价格的95%，认为订单太远
+        if highest_buy_price < current_price * 0.95 {
+            orders_too_far = true;
+            info!(
+                "🔄 买单价格过远 - 最高买单: {:.4}, 当前价格: {:.4}, 差距: {:.2}%",
+                highest_buy_price,
+                current_price,
+                (current_price - highest_buy_price) / current_price * 100.0
+            );
+        }
+    }
+
+    let should_update =
+        price_change_ratio >= grid_state.order_update_threshold || orders_too_old || orders_too_far;
+
+    // 防抖：距上次整网重建不足最小间隔时不撤单重挂，缺失的挂单由订单平衡检查增量补齐
+    if should_update {
+        if let Some(elapsed) = grid_state.rebuild_too_soon(grid_config, now) {
+            info!(
+                "⏳ 网格重建被抑制 - 距上次重建仅{}秒（最小间隔{}秒），价格变化: {:.2}%, 订单过远: {}，期间只做增量调整",
+                elapsed,
+                grid_config.min_rebuild_interval_secs,
+                price_change_ratio * 100.0,
+                orders_too_far
+            );
+            return Ok(false);
+        }
+    }
+
+    if should_update {
+        info!(
+            "🔄 触发智能订单更新 - 价格变化: {:.2}%, 订单年龄: {:.1}分钟, 订单过远: {}, 阈值: {:.2}%, 自适应存活时间: {:.1}分钟",
+            price_change_ratio * 100.0,
+            order_age_minutes,
+            orders_too_far,
+            grid_state.order_update_threshold * 100.0,
+            adaptive_max_age
+        );
+
+        // 取消现有订单，保留即将成交、锁定好价格的高价值挂单
+        if !active_orders.is_empty() {
+            info!("🗑️ 评估 {} 个现有订单...", active_orders.len());
+            cancel_orders_by_value(
+                exchange_client,
+                grid_config,
+                active_orders,
+                buy_orders,
+                sell_orders,
+                current_price,
+            )
+            .await?;
+
+            // 等待订单取消完成
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+
+        // 重新创建网格
+        let mut temp_order_manager = OrderManager::new(100);
+        create_dynamic_grid(
+            exchange_client,
+            grid_config,
+            grid_state,
+            current_price,
+            price_history,
+            active_orders,
+            buy_orders,
+            sell_orders,
+            &mut temp_order_manager,
+        )
+        .await?;
+
+        // 更新状态
+        grid_state.last_price_update = now;
+        grid_state.last_grid_price = current_price;
+        grid_state.last_order_batch_time = now;
+
+        info!("✅ 智能订单更新完成");
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// 撤销超过 `max_order_age_minutes` 未刷新的网格挂单，并清空本地挂单记录
+///
+/// 按上次批量下单时间判断整批网格订单是否过期，与优先级队列中的单个订单过期无关。
+async fn cancel_stale_grid_orders(
+    exchange_client: &dyn OrderExecutor,
+    grid_config: &crate::config::GridConfig,
+    grid_state: &GridState,
+    active_orders: &mut Vec<u64>,
+    buy_orders: &mut HashMap<u64, OrderInfo>,
+    sell_orders: &mut HashMap<u64, OrderInfo>,
+) -> Result<(), GridStrategyError> {
+    let now = SystemTime::now();
+    let max_age = Duration::from_secs((grid_state.max_order_age_minutes * 60.0) as u64);
+
+    let time_since_creation = now
+        .duration_since(grid_state.last_order_batch_time)
+        .unwrap_or(Duration::from_secs(0));
+
+    if time_since_creation >= max_age {
+        let expired_count = active_orders.len();
+        if expired_count > 0 {
+            info!("⏰ 发现 {} 个过期订单，开始清理...", expired_count);
+
+            // 取消过期订单
+            for &order_id in active_orders.iter() {
+                match cancel_order_with_asset(exchange_client, order_id, &grid_config.trading_asset)
+                    .await
+                {
+                    Ok(_) => info!("✅ 过期订单 {} 已取消", order_id),
+                    Err(e) => warn!("❌ 取消过期订单 {} 失败: {:?}", order_id, e),
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+
+            // 清理本地记录
+            active_orders.clear();
+            buy_orders.clear();
+            sell_orders.clear();
+
+            info!("🧹 过期订单清理完成");
+        }
+    }
+
+    Ok(())
+}
+
+async fn adaptive_order_rebalance(
+    exchange_client: &dyn OrderExecutor,
+    grid_config: &crate::config::GridConfig,
+    grid_state: &mut GridState,
+    current_price: f64,
+    price_history: &[f64],
+    active_orders: &mut Vec<u64>,
+    buy_orders: &mut HashMap<u64, OrderInfo>,
+    sell_orders: &mut HashMap<u64, OrderInfo>,
+    current_buy_count: usize,
+    current_sell_count: usize,
+    target_buy_count: usize,
+    target_sell_count: usize,
+) -> Result<(), GridStrategyError> {
+    info!(
+        "🔄 开始自适应订单补全 - 当前买单: {}, 目标买单: {}, 当前卖单: {}, 目标卖单: {}",
+        current_buy_count, target_buy_count, current_sell_count, target_sell_count
+    );
+
+    // 检查当前总订单数是否已达到限制
+    let current_total = active_orders.len();
+    if current_total >= grid_config.max_active_orders as usize {
+        warn!(
+            "⚠️ 当前订单数量({})已达到配置限制({}), 跳过补全",
+            current_total, grid_config.max_active_orders
+        );
+        return Ok(());
+    }
+
+    // 计算需要补充的订单数量
+    let buy_deficit = if current_buy_count < target_buy_count {
+        target_buy_count - current_buy_count
+    } else {
+        0
+    };
+
+    let sell_deficit = if current_sell_count < target_sell_count {
+        target_sell_count - current_sell_count
+    } else {
+        0
+    };
+
+    // 检查是否有足够持仓创建卖单
+    let available_position = grid_state.position_quantity * 0.8; // 保留20%作为缓冲
+    let has_existing_sell_orders = current_sell_count > 0;
+    // 如果已经有卖单存在，说明之前有持仓，可以继续创建卖单
+    let can_create_sell_orders = available_position > 0.0 || has_existing_sell_orders;
+
+    info!(
+        "📊 持仓检查 - 当前持仓: {:.4}, 可用持仓: {:.4}, 现有卖单: {}, 可创建卖单: {}",
+        grid_state.position_quantity,
+        available_position,
+        current_sell_count,
+        can_create_sell_orders
+    );
+
+    // 确保不超过总订单限制
+    let remaining_slots = grid_config.max_active_orders as usize - current_total;
+    let total_needed = buy_deficit + sell_deficit;
+
+    if total_needed == 0 {
+        info!("✅ 订单数量已平衡，无需补全");
+        return Ok(());
+    }
+
+    if total_needed > remaining_slots {
+        warn!(
+            "⚠️ 需要补充{}个订单，但只有{}个剩余槽位，按比例分配",
+            total_needed, remaining_slots
+        );
+
+        // 智能分配：如果没有持仓，优先补充买单
+        let (actual_buy_add, actual_sell_add) = if !can_create_sell_orders {
+            // 没有持仓，全部分配给买单
+            info!("💡 没有持仓，优先补充买单");
+            (remaining_slots.min(buy_deficit), 0)
+        } else {
+            // 有持仓，按比例分配
+            let buy_ratio = buy_deficit as f64 / total_needed as f64;
+            let actual_buy_add = (remaining_slots as f64 * buy_ratio).round() as usize;
+            let actual_sell_add = remaining_slots - actual_buy_add;
+            (actual_buy_add, actual_sell_add)
+        };
+
+        info!(
+            "📊 智能分配 - 补充买单: {}, 补充卖单: {}",
+            actual_buy_add, actual_sell_add
+        );
+
+        // 补充买单
+        if actual_buy_add > 0 {
+            supplement_buy_orders(
+                exchange_client,
+                grid_config,
+                grid_state,
+                current_price,
+                price_history,
+                active_orders,
+                buy_orders,
+                actual_buy_add,
+            )
+            .await?;
+        }
+
+        // 补充卖单（只有在有持仓时才补充）
+        if actual_sell_add > 0 && can_create_sell_orders {
+            supplement_sell_orders(
+                exchange_client,
+                grid_config,
+                grid_state,
+                current_price,
+                price_history,
+                active_orders,
+                sell_orders,
+                actual_sell_add,
+            )
+            .await?;
+        } else if actual_sell_add > 0 && !can_create_sell_orders {
+            info!("⚠️ 跳过卖单补充，因为没有足够持仓");
+        }
+    } else {
+        // 有足够的槽位，智能补充
+        let effective_sell_deficit = if can_create_sell_orders {
+            sell_deficit
+        } else {
+            0
+        };
+        let effective_buy_deficit = if !can_create_sell_orders && sell_deficit > 0 {
+            // 如果无法创建卖单，将卖单缺口转为买单
+            buy_deficit + sell_deficit
+        } else {
+            buy_deficit
+        };
+
+        info!(
+            "📊 智能补充 - 补充买单: {}, 补充卖单: {} (持仓限制)",
+            effective_buy_deficit, effective_sell_deficit
+        );
+
+        // 补充买单
+        if effective_buy_deficit > 0 {
+            supplement_buy_orders(
+                exchange_client,
+                grid_config,
+                grid_state,
+                current_price,
+                price_history,
+                active_orders,
+                buy_orders,
+                effective_buy_deficit,
+            )
+            .await?;
+        }
+
+        // 补充卖单（只有在有持仓时才补充）
+        if effective_sell_deficit > 0 {
+            supplement_sell_orders(
+                exchange_client,
+                grid_config,
+                grid_state,
+                current_price,
+                price_history,
+                active_orders,
+                sell_orders,
+                effective_sell_deficit,
+            )
+            .await?;
+        }
+    }
+
+    info!("✅ 自适应订单补全完成");
+    Ok(())
+}
+
+async fn supplement_buy_orders(
+    exchange_client: &dyn OrderExecutor,
+    grid_config: &crate::config::GridConfig,
+    grid_state: &mut GridState,
+    current_price: f64,
+    _price_history: &[f64],
+    active_orders: &mut Vec<u64>,
+    buy_orders: &mut HashMap<u64, OrderInfo>,
+    count: usize,
+) -> Result<(), GridStrategyError> {
+    info!("🟢 补充{}个买单...", count);
+
+    // 获取当前最低买单价格，避免价格重叠
+    let mut lowest_buy_price =
+        buy_orders
+            .values()
+            .map(|order| order.price)
+            .fold(current_price, |acc, price| {
+                if price < current_price && price < acc {
+                    price
+                } else {
+                    acc
+                }
+            });
+
+    // 如果没有现有买单，从当前价格开始
+    if lowest_buy_price >= current_price {
+        lowest_buy_price = current_price * 0.995; // 从当前价格下方0.5%开始
+    }
+
+    let mut pending_orders = Vec::new();
+    let mut pending_order_infos = Vec::new();
+
+    for i in 0..count {
+        // 计算新的买单价格，确保不重叠
+        let spacing = grid_state.dynamic_params.current_min_spacing * (1.0 + i as f64 * 0.1);
+        let buy_price = lowest_buy_price * (1.0 - spacing * (i + 1) as f64);
+
+        if buy_price <= current_price * (1.0 - grid_config.grid_lower_bound_pct) {
+            warn!("⚠️ 买单价格过低，停止补充");
+            break;
+        }
+
+        let trade_amount = grid_state.dynamic_params.current_trade_amount;
+        let quantity = format_price(trade_amount / buy_price, grid_config.quantity_precision);
+        let formatted_price = format_price(buy_price, grid_config.price_precision);
+        if has_nearby_order(buy_orders, formatted_price, price_tick(grid_config)) {
+            info!("⏭️ 价位 {:.4} 已有买单，跳过补充", formatted_price);
+            continue;
+        }
+        if !meets_min_notional(formatted_price, quantity, grid_config) {
+            info!(
+                "⏭️ 买单名义价值({:.2})低于最小下单金额({:.2})，跳过补充",
+                formatted_price * quantity,
+                grid_config.min_notional
+            );
+            continue;
+        }
+
+        let cloid = new_cloid();
+        let order = ClientOrderRequest {
+            asset: grid_config.trading_asset.clone(),
+            is_buy: true,
+            reduce_only: false,
+            limit_px: formatted_price,
+            sz: quantity,
+            cloid: Some(cloid),
+            order_type: ClientOrder::Limit(ClientLimit {
+                tif: grid_order_tif(grid_config),
+            }),
+        };
+
+        pending_orders.push(order);
+        pending_order_infos.push(OrderInfo {
+            is_buy: true,
+            price: formatted_price,
+            quantity,
+            cost_price: None,
+            potential_sell_price: Some(buy_price * (1.0 + spacing * 2.0)),
+            allocated_funds: 0.0,
+            iceberg: None,
+            created_at: safe_unix_timestamp(),
+            cloid: Some(cloid),
+            filled_quantity: 0.0,
+            unhedged_quantity: 0.0,
+            grid_pair: false,
+        });
+    }
+
+    if !pending_orders.is_empty() {
+        let (pending_orders, pending_order_infos) =
+            expand_iceberg_orders(pending_orders, pending_order_infos, grid_config);
+        let mut temp_batch_optimizer = BatchTaskOptimizer::new(
+            grid_config.max_orders_per_batch.max(5),
+            Duration::from_secs(3),
+        );
+
+        match create_orders_in_batches(
+            exchange_client,
+            pending_orders,
+            grid_config,
+            grid_state,
+            &mut temp_batch_optimizer,
+        )
+        .await
+        {
+            Ok((created_order_ids, _)) => {
+                for (order_id, order_info) in
+                    match_created_orders(&created_order_ids, &pending_order_infos)
+                {
+                    info!(
+                        "🟢 补充买单成功: ID={}, 价格={:.4}",
+                        order_id, order_info.price
+                    );
+                    active_orders.push(order_id);
+                    buy_orders.insert(order_id, order_info);
+                }
+            }
+            Err(e) => {
+                warn!("⚠️ 补充买单失败: {:?}", e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn supplement_sell_orders(
+    exchange_client: &dyn OrderExecutor,
+    grid_config: &crate::config::GridConfig,
+    grid_state: &mut GridState,
+    current_price: f64,
+    _price_history: &[f64],
+    active_orders: &mut Vec<u64>,
+    sell_orders: &mut HashMap<u64, OrderInfo>,
+    count: usize,
+) -> Result<(), GridStrategyError> {
+    info!("🔴 补充{}个卖单...", count);
+
+    // 获取当前最高卖单价格，避免价格重叠
+    let mut highest_sell_price =
+        sell_orders
+            .values()
+            .map(|order| order.price)
+            .fold(current_price, |acc, price| {
+                if price > current_price && price > acc {
+                    price
+                } else {
+                    acc
+                }
+            });
+
+    // 如果没有现有卖单，从当前价格开始
+    if highest_sell_price <= current_price {
+        highest_sell_price = current_price * 1.005; // 从当前价格上方0.5%开始
+    }
+
+    let mut pending_orders = Vec::new();
+    let mut pending_order_infos = Vec::new();
+
+    for i in 0..count {
+        // 计算新的卖单价格，确保不重叠
+        let spacing = grid_state.dynamic_params.current_min_spacing * (1.0 + i as f64 * 0.1);
+        let sell_price = highest_sell_price * (1.0 + spacing * (i + 1) as f64);
+
+        if sell_price >= current_price * (1.0 + grid_config.grid_upper_bound_pct) {
+            warn!("⚠️ 卖单价格过高，停止补充");
+            break;
+        }
+
+        // 检查是否有足够的持仓进行卖出
+        let available_quantity = grid_state.position_quantity * 0.8; // 保留20%作为缓冲
+        let has_existing_sell_orders = sell_orders.len() > 0;
+
+        // 如果没有持仓但有现有卖单，说明之前有持仓，可以继续创建卖单
+        if available_quantity <= 0.0 && !has_existing_sell_orders {
+            warn!("⚠️ 没有足够持仓且无现有卖单，无法创建卖单");
+            break;
+        }
+
+        if available_quantity <= 0.0 && has_existing_sell_orders {
+            info!("💡 虽然当前持仓为0，但有现有卖单，继续创建卖单以保持网格平衡");
+        }
+
+        let trade_amount = grid_state.dynamic_params.current_trade_amount;
+        let quantity = if available_quantity > 0.0 {
+            // 有持仓时，使用持仓限制
+            format_price(
+                (trade_amount / sell_price).min(available_quantity / count as f64),
+                grid_config.quantity_precision,
+            )
+        } else {
+            // 没有持仓但有现有卖单时，使用标准交易量
+            format_price(trade_amount / sell_price, grid_config.quantity_precision)
+        };
+        let formatted_price = format_price(sell_price, grid_config.price_precision);
+        if has_nearby_order(sell_orders, formatted_price, price_tick(grid_config)) {
+            info!("⏭️ 价位 {:.4} 已有卖单，跳过补充", formatted_price);
+            continue;
+        }
+        if !meets_min_notional(formatted_price, quantity, grid_config) {
+            info!(
+                "⏭️ 卖单名义价值({:.2})低于最小下单金额({:.2})，跳过补充",
+                formatted_price * quantity,
+                grid_config.min_notional
+            );
+            continue;
+        }
+
+        let cloid = new_cloid();
+        let order = ClientOrderRequest {
+            asset: grid_config.trading_asset.clone(),
+            is_buy: false,
+            reduce_only: false,
+            limit_px: formatted_price,
+            sz: quantity,
+            cloid: Some(cloid),
+            order_type: ClientOrder::Limit(ClientLimit {
+                tif: grid_order_tif(grid_config),
+            }),
+        };
+
+        pending_orders.push(order);
+        pending_order_infos.push(OrderInfo {
+            is_buy: false,
+            price: formatted_price,
+            quantity,
+            cost_price: Some(grid_state.position_avg_price),
+            potential_sell_price: None,
+            allocated_funds: 0.0,
+            iceberg: None,
+            created_at: safe_unix_timestamp(),
+            cloid: Some(cloid),
+            filled_quantity: 0.0,
+            unhedged_quantity: 0.0,
+            grid_pair: false,
+        });
+    }
+
+    if !pending_orders.is_empty() {
+        let (pending_orders, pending_order_infos) =
+            expand_iceberg_orders(pending_orders, pending_order_infos, grid_config);
+        let mut temp_batch_optimizer = BatchTaskOptimizer::new(
+            grid_config.max_orders_per_batch.max(5),
+            Duration::from_secs(3),
+        );
+
+        match create_orders_in_batches(
+            exchange_client,
+            pending_orders,
+            grid_config,
+            grid_state,
+            &mut temp_batch_optimizer,
+        )
+        .await
+        {
+            Ok((created_order_ids, _)) => {
+                for (order_id, order_info) in
+                    match_created_orders(&created_order_ids, &pending_order_infos)
+                {
+                    info!(
+                        "🔴 补充卖单成功: ID={}, 价格={:.4}",
+                        order_id, order_info.price
+                    );
+                    active_orders.push(order_id);
+                    sell_orders.insert(order_id, order_info);
+                }
+            }
+            Err(e) => {
+                warn!("⚠️ 补充卖单失败: {:?}", e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn rebalance_grid(
+    exchange_client: &dyn OrderExecutor,
+    grid_config: &crate::config::GridConfig,
+    grid_state: &mut GridState,
+    current_price: f64,
+    price_history: &[f64],
+    active_orders: &mut Vec<u64>,
+    buy_orders: &mut HashMap<u64, OrderInfo>,
+    sell_orders: &mut HashMap<u64, OrderInfo>,
+) -> Result<(), GridStrategyError> {
+    info!("🔄 开始网格重平衡...");
+
+    // 分析市场状况
+    let mut market_analysis = analyze_market_trend(
+        price_history,
+        &grid_state.indicators,
+        grid_state.klines.primary(),
+        grid_state.market_state_tracker.confirmed(),
+    );
+    market_analysis.market_state = grid_state.market_state_tracker.observe(
+        market_analysis.market_state,
+        grid_config.market_state_confirm_ticks,
+    );
+
+    tr_log!(
+        info,
+        "📊 市场分析 - 波动率: {:.4}, 趋势: {}, RSI: {:.2}",
+        "📊 Market analysis - volatility: {:.4}, trend: {}, RSI: {:.2}",
+        market_analysis.volatility,
+        market_analysis.trend.localized(),
+        market_analysis.rsi
+    );
+
+    // 更新历史波动率（使用移动平均方式平滑更新）
+    if grid_state.historical_volatility == 0.0 {
+        grid_state.historical_volatility = market_analysis.volatility;
+    } else {
+        grid_state.historical_volatility =
+            grid_state.historical_volatility * 0.7 + market_analysis.volatility * 0.3;
+    }
+
+    // 根据利润表现调整风险系数
+    let profit_rate = grid_state.realized_profit / grid_state.total_capital;
+    let risk_adjustment = if profit_rate > 0.05 {
+        // 利润>5%
+        info!("📈 利润表现良好({:.2}%)，提高风险系数", profit_rate * 100.0);
+        1.1 // 提高风险系数
+    } else if profit_rate < 0.01 {
+        // 利润<1%
+        info!("📉 利润表现不佳({:.2}%)，降低风险系数", profit_rate * 100.0);
+        0.9 // 降低风险系数
+    } else {
+        1.0 // 保持默认风险系数
+    };
+
+    // 应用风险调整到网格参数
+    grid_state.historical_volatility *= risk_adjustment;
+
+    // 根据市场分析和风险调整动态调整策略参数
+    let mut adjusted_fund_allocation =
+        calculate_dynamic_fund_allocation(grid_state, current_price, grid_config);
+
+    // 根据趋势调整网格策略
+    if market_analysis.trend.is_bullish() {
+        // 上升趋势：增加买单密度，减少卖单密度
+        adjusted_fund_allocation.buy_spacing_adjustment *= 0.8 * risk_adjustment;
+        adjusted_fund_allocation.sell_spacing_adjustment *= 1.2;
+        tr_log!(
+            info,
+            "📈 检测到{}趋势, 调整买单密度",
+            "📈 {} trend detected, increasing buy density",
+            market_analysis.trend.localized()
+        );
+    } else if market_analysis.trend.is_bearish() {
+        // 下降趋势：减少买单密度，增加卖单密度
+        adjusted_fund_allocation.buy_spacing_adjustment *= 1.2;
+        adjusted_fund_allocation.sell_spacing_adjustment *= 0.8 * risk_adjustment;
+        tr_log!(
+            info,
+            "📉 检测到{}趋势, 调整卖单密度",
+            "📉 {} trend detected, increasing sell density",
+            market_analysis.trend.localized()
+        );
+    } else if market_analysis.trend.is_sideways() {
+        // 震荡趋势：保持均衡的网格密度，应用风险调整
+        adjusted_fund_allocation.buy_spacing_adjustment *= risk_adjustment;
+        adjusted_fund_allocation.sell_spacing_adjustment *= risk_adjustment;
+        tr_log!(
+            info,
+            "📊 检测到{}趋势, 保持均衡网格",
+            "📊 {} trend detected, keeping balanced grid",
+            market_analysis.trend.localized()
+        );
+    }
+
+    // 使用 RSI 指标调整交易激进程度
+    if market_analysis.rsi > 70.0 {
+        // 超买状态，减少买单资金
+        adjusted_fund_allocation.buy_order_funds *= 0.7;
+        info!("⚠️ RSI超买({:.1})，减少买单资金", market_analysis.rsi);
+    } else if market_analysis.rsi < 30.0 {
+        // 超卖状态，增加买单资金
+        adjusted_fund_allocation.buy_order_funds *= 1.3;
+        info!("💡 RSI超卖({:.1})，增加买单资金", market_analysis.rsi);
+    }
+
+    // 使用移动平均线进行趋势确认
+    if market_analysis.short_ma > market_analysis.long_ma * 1.02 {
+        // 短期均线明显高于长期均线，确认上升趋势
+        adjusted_fund_allocation.buy_order_funds *= 1.1;
+        info!("📈 均线确认上升趋势，增加买单资金");
+    } else if market_analysis.short_ma < market_analysis.long_ma * 0.98 {
+        // 短期均线明显低于长期均线，确认下降趋势
+        adjusted_fund_allocation.buy_order_funds *= 0.9;
+        info!("📉 均线确认下降趋势，减少买单资金");
+    }
+
+    // 根据5分钟价格变化调整紧急程度
+    if market_analysis.price_change_5min.abs() > 0.03 {
+        // 5分钟变化超过3%
+        if market_analysis.price_change_5min > 0.0 {
+            // 快速上涨，减少买单
+            adjusted_fund_allocation.buy_order_funds *= 0.8;
+            info!(
+                "🚀 快速上涨({:.2}%)，减少买单",
+                market_analysis.price_change_5min * 100.0
+            );
+        } else {
+            // 快速下跌，增加买单机会
+            adjusted_fund_allocation.buy_order_funds *= 1.2;
+            info!(
+                "💥 快速下跌({:.2}%)，增加买单机会",
+                market_analysis.price_change_5min * 100.0
+            );
+        }
+    }
+
+    // 取消现有订单，保留即将成交、锁定好价格的高价值挂单
+    info!("🗑️ 取消现有订单...");
+    cancel_orders_by_value(
+        exchange_client,
+        grid_config,
+        active_orders,
+        buy_orders,
+        sell_orders,
+        current_price,
+    )
+    .await?;
+
+    // 等待订单取消完成
+    sleep(Duration::from_secs(2)).await;
+
+    // 更新网格状态
+    // 这里可以根据市场分析调整网格参数
+
+    // 重新创建网格
+    // 注意：这里需要传递订单管理器，但rebalance_grid函数没有接收它
+    // 暂时使用一个临时的订单管理器
+    let mut temp_order_manager = OrderManager::new(100);
+    create_dynamic_grid(
+        exchange_client,
+        grid_config,
+        grid_state,
+        current_price,
+        price_history,
+        active_orders,
+        buy_orders,
+        sell_orders,
+        &mut temp_order_manager,
+    )
+    .await?;
+
+    grid_state.last_rebalance_time = SystemTime::now();
+    // 定期重平衡也是整网重建，计入智能订单更新的最小重建间隔
+    grid_state.last_price_update = grid_state.last_rebalance_time;
+
+    info!("✅ 网格重平衡完成");
+    Ok(())
+}
+
+// 取消所有订单 - 改进版本，接受交易资产参数
+/// 状态报告的结构化版本，供控制接口 status 指令返回
+fn build_status_snapshot(
+    grid_state: &GridState,
+    current_price: Option<f64>,
+    buy_orders: &HashMap<u64, OrderInfo>,
+    sell_orders: &HashMap<u64, OrderInfo>,
+    grid_config: &crate::config::GridConfig,
+    trading_paused: bool,
+    price_feed_status: serde_json::Value,
+) -> serde_json::Value {
+    let price = current_price.unwrap_or(grid_state.position_avg_price);
+    let current_total_value = grid_state.available_funds
+        + grid_state.profit_reserve
+        + grid_state.position_quantity * price;
+    let metrics = &grid_state.performance.metrics;
+
+    serde_json::json!({
+        "timestamp": safe_unix_timestamp(),
+        "trading_asset": grid_config.trading_asset,
+        "current_price": current_price,
+        "trading_paused": trading_paused,
+        "in_trading_window": in_configured_trading_window(grid_config, Utc::now()),
+        "trading_windows": grid_config.trading_windows,
+        "price_feed": price_feed_status,
+        "grid_mode": grid_config.grid_mode,
+        "total_capital": grid_state.total_capital,
+        "available_funds": grid_state.available_funds,
+        "position_quantity": grid_state.position_quantity,
+        "position_avg_price": grid_state.position_avg_price,
+        "current_total_value": current_total_value,
+        "realized_profit": grid_state.realized_profit,
+        "grid_profit": grid_state.grid_profit,
+        "position_pnl": grid_state.position_pnl,
+        "unrealized_pnl": grid_state.unrealized_pnl,
+        "total_pnl": grid_state.total_pnl(),
+        "profit_mode": grid_config.profit_mode,
+        "profit_reserve": grid_state.profit_reserve,
+        "profit_withdrawn": grid_state.profit_withdrawn,
+        "active_buy_orders": buy_orders.len(),
+        "active_sell_orders": sell_orders.len(),
+        "trailing_stop_price": grid_state.trailing_stop_price,
+        "stop_loss_status": grid_state.stop_loss_status.as_english(),
+        "stop_loss_cooldown_until": grid_state.stop_loss_cooldown_until,
+        "consecutive_losses": grid_state.consecutive_losses,
+        "loss_circuit_tripped_at": grid_state.loss_circuit_tripped_at,
+        "metrics": {
+            "total_trades": metrics.total_trades,
+            "winning_trades": metrics.winning_trades,
+            "losing_trades": metrics.losing_trades,
+            "win_rate": metrics.win_rate,
+            "profit_factor": metrics.profit_factor,
+            "average_win": metrics.average_win,
+            "average_loss": metrics.average_loss,
+            "largest_win": metrics.largest_win,
+            "largest_loss": metrics.largest_loss,
+            "max_drawdown": metrics.max_drawdown,
+        },
+        "connection_retry_count": grid_state.connection_retry_count,
+        "max_order_age_minutes": grid_state.max_order_age_minutes,
+        "slippage": {
+            "samples": grid_state.slippage.samples(),
+            "average": grid_state.slippage.average(),
+            "recent_average": grid_state.slippage.recent_average(),
+            "max": grid_state.slippage.max_slippage(),
+            "total_cost": grid_state.slippage.total_cost(),
+        },
+        "fill_latency": {
+            "average_minutes": grid_state.fill_latency.average_minutes(),
+            "buy": grid_state.fill_latency.buy_summary(),
+            "sell": grid_state.fill_latency.sell_summary(),
+        },
+    })
+}
+
+/// 控制接口 flatten 指令：撤销所有订单并平掉当前持仓
+async fn flatten_positions(
+    exchange_client: &dyn OrderExecutor,
+    grid_config: &crate::config::GridConfig,
+    grid_state: &mut GridState,
+    active_orders: &mut Vec<u64>,
+    buy_orders: &mut HashMap<u64, OrderInfo>,
+    sell_orders: &mut HashMap<u64, OrderInfo>,
+    current_price: f64,
+) -> Result<(), GridStrategyError> {
+    if !active_orders.is_empty() {
+        info!(
+            "🗑️ 控制指令清仓 - 取消所有活跃订单 ({} 个)",
+            active_orders.len()
+        );
+        cancel_all_orders(exchange_client, active_orders, &grid_config.trading_asset).await?;
+        buy_orders.clear();
+        sell_orders.clear();
+    }
+
+    if grid_state.position_quantity <= 0.0 {
+        info!("✅ 控制指令清仓 - 当前无持仓");
+        return Ok(());
+    }
+
+    info!(
+        "📉 控制指令清仓 - 持仓数量: {:.4}",
+        grid_state.position_quantity
+    );
+    let (remaining_long, _) = close_all_positions(
+        exchange_client,
+        grid_config,
+        &grid_state.order_book,
+        &mut grid_state.expected_fill_prices,
+        grid_state.position_quantity,
+        0.0,
+        current_price,
+    )
+    .await?;
+
+    if remaining_long > 0.0 {
+        grid_state.position_quantity = remaining_long;
+        return Err(GridStrategyError::OrderError(format!(
+            "清仓未完成，仍有未平持仓 {:.4}",
+            remaining_long
+        )));
+    }
+    grid_state.position_quantity = 0.0;
+    grid_state.position_avg_price = 0.0;
+    info!("✅ 控制指令清仓完成");
+    Ok(())
+}
+
+// 监控资金使用和订单限制
+fn monitor_fund_allocation(
+    grid_state: &GridState,
+    buy_orders: &HashMap<u64, OrderInfo>,
+    sell_orders: &HashMap<u64, OrderInfo>,
+    grid_config: &crate::config::GridConfig,
+) -> Result<(), GridStrategyError> {
+    // 计算总分配资金
+    let total_allocated = buy_orders.values().map(|o| o.allocated_funds).sum::<f64>();
+    let fund_usage_rate = if grid_state.total_capital > 0.0 {
+        total_allocated / grid_state.total_capital
+    } else {
+        0.0
+    };
+
+    // 检查资金使用率
+    if fund_usage_rate > 0.9 {
+        return Err(GridStrategyError::FundAllocationError(format!(
+            "资金使用率过高: {:.2}%",
+            fund_usage_rate * 100.0
+        )));
+    }
+
+    // 检查订单数量限制
+    let total_orders = buy_orders.len() + sell_orders.len();
+    if total_orders > grid_config.max_active_orders {
+        return Err(GridStrategyError::FundAllocationError(format!(
+            "活跃订单数量({})超过限制({})",
+            total_orders, grid_config.max_active_orders
+        )));
+    }
+
+    // 检查单个订单的资金分配是否合理
+    for (oid, order_info) in buy_orders.iter() {
+        if order_info.allocated_funds > grid_state.total_capital * 0.2 {
+            warn!(
+                "⚠️ 订单{}分配资金过多: {:.2}",
+                oid, order_info.allocated_funds
+            );
+        }
+    }
+
+    info!(
+        "📊 资金监控 - 使用率: {:.2}%, 活跃订单: {}, 总分配: {:.2}",
+        fund_usage_rate * 100.0,
+        total_orders,
+        total_allocated
+    );
+
+    Ok(())
+}
+
+// 生成状态报告
+/// 生成指定日期（UTC）的日报并写入按日期命名的报告文件
+fn generate_daily_report(
+    grid_state: &GridState,
+    grid_config: &crate::config::GridConfig,
+    date: NaiveDate,
+) -> String {
+    write_period_report(
+        grid_state,
+        grid_config,
+        "每日交易报表",
+        &format!("daily_report_{}.txt", date.format("%Y-%m-%d")),
+        date.format("%Y-%m-%d").to_string(),
+        date,
+        1,
+    )
+}
+
+/// 生成从 `week_start`（周一，UTC）开始一周的周报并写入报告文件
+fn generate_weekly_report(
+    grid_state: &GridState,
+    grid_config: &crate::config::GridConfig,
+    week_start: NaiveDate,
+) -> String {
+    let week = week_start.iso_week();
+    let period = format!("{}-W{:02}", week.year(), week.week());
+    write_period_report(
+        grid_state,
+        grid_config,
+        "每周交易报表",
+        &format!("weekly_report_{}.txt", period),
+        period.clone(),
+        week_start,
+        7,
+    )
+}
+
+/// 汇总 `start_date` 起 `days` 天内的成交记录，写入状态目录下的报告文件
+fn write_period_report(
+    grid_state: &GridState,
+    grid_config: &crate::config::GridConfig,
+    title: &str,
+    file_name: &str,
+    period: String,
+    start_date: NaiveDate,
+    days: u64,
+) -> String {
+    let to_system_time =
+        |date: NaiveDate| SystemTime::from(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    let start = to_system_time(start_date);
+    let end = to_system_time(start_date + chrono::Days::new(days));
+
+    let summary = PeriodSummary::from_records(period, &grid_state.performance.records, start, end);
+    let report = summary.generate_report(title);
+
+    let path = state_file_path(grid_config, file_name);
+    match std::fs::write(&path, &report) {
+        Ok(_) => info!("💾 {}已保存到: {}", title, path),
+        Err(e) => warn!("⚠️ 保存{}失败: {:?}", title, e),
+    }
+    report
+}
+
+/// 利润处理模式的中文描述
+fn describe_profit_mode(profit_mode: &str) -> &'static str {
+    match profit_mode {
+        "compound" => "复投",
+        "fixed_capital" => "固定本金",
+        "withdraw_to_base" => "提取利润",
+        _ => "未知",
+    }
+}
+
+fn generate_status_report(
+    grid_state: &GridState,
+    current_price: f64,
+    buy_orders: &HashMap<u64, OrderInfo>,
+    sell_orders: &HashMap<u64, OrderInfo>,
+    grid_config: &crate::config::GridConfig,
+    price_feed_status: &str,
+) -> String {
+    // 计算总资产（挂单不占用资金，隔离的利润仍计入总资产）
+    let current_total_value = grid_state.available_funds
+        + grid_state.profit_reserve
+        + grid_state.position_quantity * current_price;
+
+    let position_ratio = if grid_state.total_capital > 0.0 {
+        (grid_state.position_quantity * current_price) / grid_state.total_capital * 100.0
+    } else {
+        0.0
+    };
+    let asset_change = (current_total_value / grid_state.total_capital - 1.0) * 100.0;
+    let profit_rate = grid_state.realized_profit / grid_state.total_capital * 100.0;
+    let metrics = &grid_state.performance.metrics;
+    let market_state_history = grid_state
+        .market_state_tracker
+        .recent_transitions()
+        .iter()
+        .rev()
+        .take(3)
+        .map(|t| {
+            format!(
+                "{}->{}({}s前)",
+                t.from.localized(),
+                t.to.localized(),
+                t.timestamp.elapsed().unwrap_or_default().as_secs()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!(
+        "===== 网格交易状态报告 =====\n\
+        时间: {}\n\
+        交易对: {}\n\
+        当前价格: {:.4}\n\
+        价格来源: {}\n\
+        交易窗口: {} ({})\n\
+        网格间距: {:.4}% - {:.4}%\n\
+        初始资金: {:.2}\n\
+        可用资金: {:.2}\n\
+        持仓数量: {:.4}\n\
+        持仓均价: {:.4}\n\
+        持仓比例: {:.2}%\n\
+        当前总资产: {:.2}\n\
+        资产变化: {:.2}%\n\
+        已实现盈亏: {:.2}\n\
+        - 网格利润: {:.2} (配对买卖)\n\
+        - 持仓盈亏: {:.2} (已平仓 {:.2} + 未实现 {:.2})\n\
+        未实现盈亏: {:.2}\n\
+        总盈亏: {:.2}\n\
+        利润处理: {} (隔离利润 {:.2}, 已提取 {:.2})\n\
+        已付资金费: {:.4} (当前费率 {:.4}%/小时, 预期每格成本 {:.4}%)\n\
+        利润率: {:.2}%\n\
+        活跃买单数: {}\n\
+        活跃卖单数: {}\n\
+        浮动止损价: {:.4}\n\
+        止损状态: {}\n\
+        连续亏损: {}笔{}\n\
+        市场状态: {} (最近切换: {})\n\
+        历史交易数: {}\n\
+        平仓交易数: {} (盈利 {} / 亏损 {})\n\
+        胜率: {:.1}%\n\
+        盈亏比: {:.2}\n\
+        平均盈利/亏损: {:.2} / {:.2}\n\
+        最大单笔盈利/亏损: {:.2} / {:.2}\n\
+        最大回撤: {:.2}%\n\
+        连接重试次数: {}\n\
+        自适应订单存活时间: {:.1}分钟\n\
+        订单成功率: {:.1}%\n\
+        平均成交时间: {:.1}分钟\n\
+        买单成交耗时: {}\n\
+        卖单成交耗时: {}\n\
+        滑点单成交滑点: {} (容忍度 {:.3}%)\n\
+        ==============================",
+        format!("{:?}", safe_unix_timestamp()),
+        grid_config.trading_asset,
+        current_price,
+        price_feed_status,
+        if in_configured_trading_window(grid_config, Utc::now()) {
+            "窗口内"
+        } else {
+            "窗口外，暂停开新仓"
+        },
+        parse_trading_windows(&grid_config.trading_windows)
+            .map(|windows| describe_trading_windows(&windows))
+            .unwrap_or_default(),
+        grid_config.min_grid_spacing * 100.0,
+        grid_config.max_grid_spacing * 100.0,
+        grid_state.total_capital,
+        grid_state.available_funds,
+        grid_state.position_quantity,
+        grid_state.position_avg_price,
+        position_ratio,
+        current_total_value,
+        asset_change,
+        grid_state.realized_profit,
+        grid_state.grid_profit,
+        grid_state.position_pnl + grid_state.unrealized_pnl,
+        grid_state.position_pnl,
+        grid_state.unrealized_pnl,
+        grid_state.unrealized_pnl,
+        grid_state.total_pnl(),
+        describe_profit_mode(&grid_config.profit_mode),
+        grid_state.profit_reserve,
+        grid_state.profit_withdrawn,
+        grid_state.funding_paid,
+        grid_state.funding_rate * 100.0,
+        grid_state.funding_cost(grid_config) * 100.0,
+        profit_rate,
+        buy_orders.len(),
+        sell_orders.len(),
+        grid_state.trailing_stop_price,
+        grid_state.stop_loss_status.as_str(),
+        grid_state.consecutive_losses,
+        if grid_state.loss_circuit_tripped_at > 0 {
+            " (熔断中，暂停开新仓)"
+        } else {
+            ""
+        },
+        grid_state.market_state_tracker.confirmed().as_str(),
+        if market_state_history.is_empty() {
+            "无".to_string()
+        } else {
+            market_state_history
+        },
+        grid_state.performance.records.len(),
+        metrics.total_trades,
+        metrics.winning_trades,
+        metrics.losing_trades,
+        metrics.win_rate * 100.0,
+        metrics.profit_factor,
+        metrics.average_win,
+        metrics.average_loss,
+        metrics.largest_win,
+        metrics.largest_loss,
+        metrics.max_drawdown * 100.0,
+        grid_state.connection_retry_count,
+        grid_state.max_order_age_minutes,
+        grid_state.adaptive_order_config.order_success_rate * 100.0,
+        grid_state.adaptive_order_config.average_fill_time_minutes,
+        grid_state.fill_latency.buy_summary().describe(),
+        grid_state.fill_latency.sell_summary().describe(),
+        grid_state.slippage.describe(),
+        grid_config.slippage_tolerance * 100.0
+    )
+}
+
+/// 按优先级加载交易钱包：加密 keystore（private_key_file + 环境变量中的密码）优先，
+/// 其次是明文 private_key（PRIVATE_KEY 环境变量会覆盖配置文件中的值）
+fn load_wallet(account: &crate::config::AccountConfig) -> Result<LocalWallet, GridStrategyError> {
+    if let Some(keystore_path) = account
+        .private_key_file
+        .as_deref()
+        .filter(|path| !path.trim().is_empty())
+    {
+        let password = std::env::var(&account.keystore_password_env).map_err(|_| {
+            GridStrategyError::WalletError(format!(
+                "已配置 keystore 文件 {}，但环境变量 {} 未设置密码",
+                keystore_path, account.keystore_password_env
+            ))
+        })?;
+        let wallet = LocalWallet::decrypt_keystore(keystore_path, password).map_err(|e| {
+            GridStrategyError::WalletError(format!(
+                "keystore 解密失败 ({}): {:?}",
+                keystore_path, e
+            ))
+        })?;
+        if !account.private_key.trim().is_empty() {
+            warn!("⚠️ 已使用 keystore 钱包，忽略配置中的明文 private_key");
+        }
+        info!("🔐 已从 keystore 加载钱包: {}", keystore_path);
+        return Ok(wallet);
+    }
+
+    if account.private_key.trim().is_empty() {
+        return Err(GridStrategyError::WalletError(
+            "未配置私钥：请设置 private_key_file（推荐）或 private_key".to_string(),
+        ));
+    }
+
+    warn!("⚠️ 正在使用明文私钥，建议迁移到加密 keystore（account.private_key_file）");
+    account
+        .private_key
+        .parse()
+        .map_err(|e| GridStrategyError::WalletError(format!("私钥解析失败: {:?}", e)))
+}
+
+pub async fn run_grid_strategy(
+    mut app_config: crate::config::AppConfig,
+    replay: Option<ReplayOptions>,
+) -> Result<(), GridStrategyError> {
+    env_logger::init();
+
+    // 回放模式强制模拟下单，且只回放单个账户
+    if let Some(options) = &replay {
+        if !app_config.accounts.is_empty() {
+            return Err(GridStrategyError::ConfigError(
+                "回放模式只支持单账户配置".to_string(),
+            ));
+        }
+        if !options.speed.is_finite() || options.speed <= 0.0 {
+            return Err(GridStrategyError::ConfigError(format!(
+                "回放倍速必须大于0，当前为{}",
+                options.speed
+            )));
+        }
+        app_config.grid.dry_run = true;
+        app_config.grid.record_file.clear();
+    }
+
+    // dry-run 模式使用独立的状态文件，避免覆盖实盘状态
+    if app_config.grid.dry_run {
+        app_config.grid.state_file_prefix =
+            format!("dry_run_{}", app_config.grid.state_file_prefix);
+    }
+
+    // 设置信号处理，所有账户共用同一个退出信号和重平衡信号
+    let (shutdown_flag, cancellation_token) =
+        setup_signal_handler(Duration::from_secs(app_config.grid.shutdown_timeout_secs));
+    let rebalance_signal = setup_rebalance_signal_handler();
+
+    // 验证配置参数
+    validate_grid_config(&app_config.grid)?;
+
+    // 交易所按 IP 限频，所有账户共用一个限流器
+    init_global_rate_limiter(
+        app_config.grid.api_requests_per_second,
+        app_config.grid.api_burst,
+    );
+
+    // 远程状态后端所有账户共用，状态文件名已按账户区分
+    init_remote_state_backend(&app_config.grid)?;
+
+    // 初始化告警通知
+    let alert_manager = Arc::new(AlertManager::from_config(&app_config.alert));
+
+    // 初始化Prometheus指标导出（默认关闭）
+    let metrics_registry = MetricsRegistry::new(&app_config.grid.trading_asset);
+    spawn_metrics_server(&app_config.metrics, metrics_registry.clone());
+
+    if app_config.accounts.is_empty() {
+        let (control_sender, control_receiver) = control_channel();
+        spawn_control_server(
+            &app_config.control,
+            HashMap::from([(account_label(&app_config.account, 0), control_sender)]),
+        );
+        let runtime = AccountRuntime {
+            alert_manager,
+            metrics_registry,
+            market_client: None,
+            control_receiver,
+            shutdown_flag,
+            cancellation_token,
+            rebalance_signal,
+            replay,
+        };
+        let result = run_grid_account(app_config.grid, app_config.account, runtime).await;
+        flush_remote_uploads().await;
+        return result;
+    }
+
+    // ===== 多账户模式 =====
+    // 共用一个行情客户端订阅价格、盘口和K线，每个账户独立钱包、交易客户端、用户事件订阅和状态文件
+    let labels: Vec<String> = app_config
+        .accounts
+        .iter()
+        .enumerate()
+        .map(|(index, account)| account_label(account, index))
+        .collect();
+    for (index, label) in labels.iter().enumerate() {
+        if labels[..index].contains(label) {
+            return Err(GridStrategyError::ConfigError(format!(
+                "账户名称重复: {}",
+                label
+            )));
+        }
+    }
+    if app_config.metrics.enabled {
+        warn!("⚠️ 多账户模式下监控指标只导出第一个账户 {}", labels[0]);
+    }
+
+    let market_client = Arc::new(tokio::sync::Mutex::new(
+        InfoClient::new(None, Some(BaseUrl::Mainnet))
+            .await
+            .map_err(|e| {
+                GridStrategyError::ClientError(format!("行情客户端初始化失败: {:?}", e))
+            })?,
+    ));
+
+    info!(
+        "👥 多账户模式 - 账户数: {}, 账户: {:?}",
+        labels.len(),
+        labels
+    );
+
+    let mut control_senders = HashMap::new();
+    let mut tasks = tokio::task::JoinSet::new();
+    for (index, (account, label)) in app_config.accounts.into_iter().zip(labels).enumerate() {
+        let mut grid_config = app_config.grid.clone();
+        grid_config.state_file_prefix = format!("{}{}_", grid_config.state_file_prefix, label);
+        if !grid_config.record_file.trim().is_empty() {
+            grid_config.record_file = format!("{}.{}", grid_config.record_file.trim(), label);
+        }
+        let registry = if index == 0 {
+            metrics_registry.clone()
+        } else {
+            MetricsRegistry::new(&grid_config.trading_asset)
+        };
+
+        let (control_sender, control_receiver) = control_channel();
+        control_senders.insert(label.clone(), control_sender);
+        let runtime = AccountRuntime {
+            alert_manager: alert_manager.clone(),
+            metrics_registry: registry,
+            market_client: Some(market_client.clone()),
+            control_receiver,
+            shutdown_flag: shutdown_flag.clone(),
+            cancellation_token: cancellation_token.clone(),
+            rebalance_signal: rebalance_signal.clone(),
+            replay: None,
+        };
+        tasks.spawn(async move {
+            info!("🚀 启动账户 {} 的网格策略", label);
+            let result = run_grid_account(grid_config, account, runtime).await;
+            (label, result)
+        });
+    }
+    spawn_control_server(&app_config.control, control_senders);
+
+    // 等待所有账户完成安全退出
+    let mut failed_accounts = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((label, Ok(()))) => info!("✅ 账户 {} 已退出", label),
+            Ok((label, Err(e))) => {
+                error!("❌ 账户 {} 运行失败: {:?}", label, e);
+                failed_accounts.push(label);
+            }
+            Err(e) => {
+                error!("❌ 账户任务异常终止: {:?}", e);
+                failed_accounts.push("未知".to_string());
+            }
+        }
+    }
+    flush_remote_uploads().await;
+
+    if failed_accounts.is_empty() {
+        Ok(())
+    } else {
+        Err(GridStrategyError::ClientError(format!(
+            "以下账户运行失败: {}",
+            failed_accounts.join(", ")
+        )))
+    }
+}
+
+/// 账户标识：优先使用配置的名称，否则按序号命名
+fn account_label(account: &crate::config::AccountConfig, index: usize) -> String {
+    if account.name.trim().is_empty() {
+        format!("account{}", index + 1)
+    } else {
+        account.name.trim().to_string()
+    }
+}
+
+/// 单个账户运行时依赖的共享组件
+struct AccountRuntime {
+    alert_manager: Arc<AlertManager>,
+    metrics_registry: MetricsRegistry,
+    market_client: Option<Arc<tokio::sync::Mutex<InfoClient>>>, // 多账户共用的行情客户端
+    control_receiver: mpsc::Receiver<ControlRequest>,           // 控制接口指令
+    shutdown_flag: Arc<AtomicBool>,
+    cancellation_token: CancellationToken,
+    rebalance_signal: Arc<AtomicU64>, // 收到 SIGUSR1 的累计次数，各账户比较计数判断是否有新的重平衡请求
+    replay: Option<ReplayOptions>,    // 回放模式：行情来自录制文件而非实时订阅
+}
+
+/// 运行单个账户的网格策略
+///
+/// `market_client` 为 None 时价格、盘口和K线也通过本账户的客户端订阅。
+async fn run_grid_account(
+    mut grid_config: crate::config::GridConfig,
+    account: crate::config::AccountConfig,
+    runtime: AccountRuntime,
+) -> Result<(), GridStrategyError> {
+    let AccountRuntime {
+        alert_manager,
+        metrics_registry,
+        market_client,
+        mut control_receiver,
+        shutdown_flag,
+        cancellation_token,
+        rebalance_signal,
+        replay,
+    } = runtime;
+    let start_time = SystemTime::now();
+
+    // 初始化钱包，dry-run 模式未配置私钥时使用临时随机钱包，不需要真实账户
+    let has_wallet_config = !account.private_key.trim().is_empty()
+        || account
+            .private_key_file
+            .as_deref()
+            .is_some_and(|path| !path.trim().is_empty());
+    let wallet = if grid_config.dry_run && !has_wallet_config {
+        warn!("🧪 dry-run 模式未配置私钥，使用临时随机钱包");
+        LocalWallet::new(&mut ethers::core::rand::thread_rng())
+    } else {
+        load_wallet(&account)?
+    };
+    let user_address = if let Some(addr) = &account.real_account_address {
+        addr.parse().expect("real_account_address 格式错误")
+    } else {
+        wallet.address()
+    };
+    info!("实际查询的钱包地址: {:?}", user_address);
+
+    // 初始化客户端
+    let mut info_client = InfoClient::new(None, Some(BaseUrl::Mainnet))
+        .await
+        .map_err(|e| GridStrategyError::ClientError(format!("信息客户端初始化失败: {:?}", e)))?;
+
+    // 按交易所元数据校验或覆盖价格/数量精度
+    resolve_asset_precision(&info_client, &mut grid_config).await;
+    let grid_config = &grid_config;
+
+    // dry-run 模式下使用模拟执行器，走完整策略逻辑但不发送任何真实订单
+    let simulated_executor = if grid_config.dry_run {
+        warn!("🧪 dry-run 模式已启用：订单仅在本地模拟撮合，不会发送到交易所");
+        info!(
+            "🧪 模拟账户初始余额: {:.2} USDC（来自配置 total_capital）",
+            grid_config.total_capital
+        );
+        Some(Arc::new(SimulatedExecutor::new(grid_config.total_capital)))
+    } else {
+        None
+    };
+
+    let exchange_client: Arc<dyn OrderExecutor> = match &simulated_executor {
+        Some(simulated) => simulated.clone(),
+        None => Arc::new(
+            ExchangeClient::new(None, wallet, Some(BaseUrl::Mainnet), None, None)
+                .await
+                .map_err(|e| {
+                    GridStrategyError::ClientError(format!("交易客户端初始化失败: {:?}", e))
+                })?,
+        ),
+    };
+
+    // ===== 获取账户真实资金 =====
+
+    // 获取账户信息以确定真实的总资金
+    let account_info =
+        get_account_info(exchange_client.as_ref(), &info_client, user_address).await?;
+    let real_total_capital = account_info
+        .margin_summary
+        .account_value
+        .parse::<f64>()
+        .map_err(|e| GridStrategyError::PriceParseError(format!("解析账户总价值失败: {:?}", e)))?;
+
+    tr_log!(info, "=== 交易参数 ===", "=== Trading Parameters ===");
+    tr_log!(
+        info,
+        "交易资产: {}",
+        "Trading asset: {}",
+        grid_config.trading_asset
+    );
+    tr_log!(
+        info,
+        "账户真实资金: {:.2} USDT (从账户地址读取)",
+        "Account capital: {:.2} USDT (read from account address)",
+        real_total_capital
+    );
+    tr_log!(
+        info,
+        "配置总资金: {:.2} USDT",
+        "Configured capital: {:.2} USDT",
+        grid_config.total_capital
+    );
+    if real_total_capital < grid_config.total_capital {
+        warn!(
+            "⚠️ 账户真实资金({:.2})低于配置总资金({:.2})，资金分配以账户真实资金为准",
+            real_total_capital, grid_config.total_capital
+        );
+    }
+    tr_log!(
+        info,
+        "网格数量: {}",
+        "Grid count: {}",
+        grid_config.grid_count
+    );
+    tr_log!(
+        info,
+        "每格交易金额: {}",
+        "Trade amount per grid: {}",
+        grid_config.trade_amount
+    );
+    tr_log!(
+        info,
+        "最大持仓: {}",
+        "Max position: {}",
+        grid_config.max_position
+    );
+    tr_log!(
+        info,
+        "最大回撤: {}%",
+        "Max drawdown: {}%",
+        grid_config.max_drawdown * 100.0
+    );
+    tr_log!(
+        info,
+        "价格精度: {}",
+        "Price precision: {}",
+        grid_config.price_precision
+    );
+    tr_log!(
+        info,
+        "数量精度: {}",
+        "Quantity precision: {}",
+        grid_config.quantity_precision
+    );
+    tr_log!(
+        info,
+        "检查间隔: {}秒",
+        "Check interval: {}s",
+        grid_config.check_interval
+    );
+    tr_log!(info, "杠杆倍数: {}x", "Leverage: {}x", grid_config.leverage);
+    tr_log!(
+        info,
+        "最小网格间距: {}%",
+        "Min grid spacing: {}%",
+        grid_config.min_grid_spacing * 100.0
+    );
+    tr_log!(
+        info,
+        "最大网格间距: {}%",
+        "Max grid spacing: {}%",
+        grid_config.max_grid_spacing * 100.0
+    );
+    tr_log!(
+        info,
+        "网格价格偏移: {}%",
+        "Grid price offset: {}%",
+        grid_config.grid_price_offset * 100.0
+    );
+    tr_log!(
+        info,
+        "单笔最大亏损: {}%",
+        "Max single loss: {}%",
+        grid_config.max_single_loss * 100.0
+    );
+    tr_log!(
+        info,
+        "每日最大亏损: {}%",
+        "Max daily loss: {}%",
+        grid_config.max_daily_loss * 100.0
+    );
+    tr_log!(
+        info,
+        "最大持仓时间: {}小时",
+        "Max holding time: {}h",
+        grid_config.max_holding_time / 3600
+    );
+
+    // 启动就绪检查（含杠杆设置），任一硬性检查失败则拒绝启动
+    let preflight_report = run_preflight_checks(
+        &info_client,
+        exchange_client.as_ref(),
+        &account_info,
+        grid_config,
+    )
+    .await;
+    preflight_report.log();
+    if !preflight_report.is_ready() {
+        let reasons: Vec<String> = preflight_report
+            .blocking_failures()
+            .iter()
+            .map(|c| format!("{}: {}", c.name, c.detail))
+            .collect();
+        return Err(GridStrategyError::ConfigError(format!(
+            "启动就绪检查未通过 - {}",
+            reasons.join("; ")
+        )));
+    }
+
+    // ===== 状态恢复与初始化 =====
+
+    // 0. 确保状态目录存在
+    if let Err(e) = std::fs::create_dir_all(&grid_config.state_dir) {
+        warn!("⚠️ 创建状态目录 {} 失败: {:?}", grid_config.state_dir, e);
+    }
+
+    // 1. 创建状态备份
+    if let Err(e) = backup_state_files(grid_config) {
+        warn!("⚠️ 创建状态备份失败: {:?}", e);
+    }
+
+    // 2. 清理过期备份文件（保留7天）
+    match cleanup_old_backups(grid_config, 7) {
+        Ok(removed) if removed > 0 => info!("🗑️ 已清理 {} 个过期备份文件", removed),
+        Ok(_) => {}
+        Err(e) => warn!("⚠️ 清理过期备份失败: {:?}", e),
+    }
+
+    // 3. 尝试加载网格状态
+    let mut grid_state = match load_grid_state(&state_file_path(grid_config, "grid_state.json"))
+        .await?
+    {
+        Some(loaded_state) => {
+            info!("🔄 检测到已保存的网格状态，正在恢复...");
+
+            // 验证加载的状态是否与当前配置兼容
+            if let Err(e) = validate_loaded_state(&loaded_state, grid_config) {
+                warn!("⚠️ 状态验证失败: {:?}", e);
+                warn!("将使用默认状态重新开始");
+                GridState {
+                    total_capital: real_total_capital,
+                    available_funds: real_total_capital,
+                    position_quantity: 0.0,
+                    position_avg_price: 0.0,
+                    realized_profit: 0.0,
+                    grid_profit: 0.0,
+                    position_pnl: 0.0,
+                    highest_price_after_position: 0.0,
+                    trailing_stop_price: 0.0,
+                    stop_loss_status: StopLossStatus::Normal,
+                    stop_loss_cooldown_until: 0,
+                    last_rebalance_time: SystemTime::now(),
+                    historical_volatility: 0.0,
+                    performance: PerformanceAnalyzer::unbounded(),
+                    last_margin_check: SystemTime::now(),
+                    connection_retry_count: 0,
+                    last_order_batch_time: SystemTime::now(),
+                    dynamic_params: DynamicGridParams::load_from_file(
+                        &state_file_path(grid_config, "dynamic_grid_params.json"),
+                        grid_config,
+                    ),
+                    // 智能订单更新相关字段
+                    last_price_update: SystemTime::now(),
+                    last_grid_price: 0.0,
+                    grid_center_price: 0.0,
+                    order_update_threshold: grid_config.order_update_threshold, // 从配置文件读取价格变化触发更新阈值
+                    // 修改为存活10s
+                    max_order_age_minutes: grid_config.max_order_age_minutes,
+                    // 自适应订单管理
+                    adaptive_order_config: AdaptiveOrderConfig::new(),
+                    indicators: IndicatorState::new(grid_config.history_length),
+                    order_book: OrderBook::new(),
+                    klines: KlineBuffer::new(
+                        &grid_config.kline_intervals,
+                        grid_config.kline_capacity,
+                    ),
+                    market_state_tracker: MarketStateTracker::default(),
+                    position_lots: VecDeque::new(),
+                    fill_latency: FillLatencyStats::default(),
+                    slippage: SlippageStats::default(),
+                    expected_fill_prices: ExpectedFillPrices::default(),
+                    settled_on_placement: HashMap::new(),
+                    consecutive_losses: 0,
+                    loss_circuit_tripped_at: 0,
+                    funding_rate: 0.0,
+                    funding_paid: 0.0,
+                    profit_reserve: 0.0,
+                    profit_withdrawn: 0.0,
+                    unrealized_pnl: 0.0,
+                }
+            } else {
+                info!("✅ 网格状态验证通过，继续使用已保存状态");
+                info!("📊 恢复状态摘要:");
+                info!("   - 总资金: {:.2}", loaded_state.total_capital);
+                info!("   - 可用资金: {:.2}", loaded_state.available_funds);
+                info!("   - 持仓数量: {:.4}", loaded_state.position_quantity);
+                info!("   - 持仓均价: {:.4}", loaded_state.position_avg_price);
+                info!(
+                    "   - 已实现利润: {:.2} (网格利润 {:.2}, 持仓盈亏 {:.2})",
+                    loaded_state.realized_profit,
+                    loaded_state.grid_profit,
+                    loaded_state.position_pnl
+                );
+                info!(
+                    "   - 历史交易数: {}",
+                    loaded_state.performance.records.len()
+                );
+                info!(
+                    "   - 止损状态: {}",
+                    loaded_state.stop_loss_status.localized()
+                );
+
+                // 更新一些时间相关的字段
+                let mut state = loaded_state;
+                state.last_margin_check = SystemTime::now();
+                state.last_order_batch_time = SystemTime::now();
+                state.connection_retry_count = 0; // 重置连接重试计数
+                state.indicators = IndicatorState::new(grid_config.history_length);
+                state.klines =
+                    KlineBuffer::new(&grid_config.kline_intervals, grid_config.kline_capacity);
+                // 旧状态文件没有持仓批次记录时，以当前时间补记
+                state.sync_position_lots();
+                // 切换为复投模式时，之前隔离的利润重新计入交易资金，可用资金随后按交易所余额同步
+                if grid_config.profit_mode == "compound" && state.profit_reserve > 0.0 {
+                    info!(
+                        "💸 复投模式：隔离利润 {:.2} 重新计入总资金",
+                        state.profit_reserve
+                    );
+                    state.total_capital += state.profit_reserve;
+                    state.profit_reserve = 0.0;
+                }
+                state
+            }
+        }
+        None => {
+            info!("📄 未找到已保存的网格状态，使用默认配置初始化");
+            GridState {
+                total_capital: real_total_capital,
+                available_funds: real_total_capital,
+                position_quantity: 0.0,
+                position_avg_price: 0.0,
+                realized_profit: 0.0,
+                grid_profit: 0.0,
+                position_pnl: 0.0,
+                highest_price_after_position: 0.0,
+                trailing_stop_price: 0.0,
+                stop_loss_status: StopLossStatus::Normal,
+                stop_loss_cooldown_until: 0,
+                last_rebalance_time: SystemTime::now(),
+                historical_volatility: 0.0,
+                performance: PerformanceAnalyzer::unbounded(),
+                last_margin_check: SystemTime::now(),
+                connection_retry_count: 0,
+                last_order_batch_time: SystemTime::now(),
+                dynamic_params: DynamicGridParams::load_from_file(
+                    &state_file_path(grid_config, "dynamic_grid_params.json"),
+                    grid_config,
+                ),
+                // 智能订单更新相关字段
+                last_price_update: SystemTime::now(),
+                last_grid_price: 0.0,
+                grid_center_price: 0.0,
+                order_update_threshold: grid_config.order_update_threshold, // 从配置文件读取价格变化触发更新阈值
+                max_order_age_minutes: grid_config.max_order_age_minutes,
+                // 自适应订单管理
+                adaptive_order_config: AdaptiveOrderConfig::new(),
+                indicators: IndicatorState::new(grid_config.history_length),
+                order_book: OrderBook::new(),
+                klines: KlineBuffer::new(&grid_config.kline_intervals, grid_config.kline_capacity),
+                market_state_tracker: MarketStateTracker::default(),
+                position_lots: VecDeque::new(),
+                fill_latency: FillLatencyStats::default(),
+                slippage: SlippageStats::default(),
+                expected_fill_prices: ExpectedFillPrices::default(),
+                settled_on_placement: HashMap::new(),
+                consecutive_losses: 0,
+                loss_circuit_tripped_at: 0,
+                funding_rate: 0.0,
+                funding_paid: 0.0,
+                profit_reserve: 0.0,
+                profit_withdrawn: 0.0,
+                unrealized_pnl: 0.0,
+            }
+        }
+    };
+
+    // 3.1 接管账户已有持仓，避免把手动建的仓位当成0导致风控和卖单网格失效
+    if grid_config.adopt_existing_position {
+        adopt_existing_position(&account_info, &mut grid_state, grid_config)?;
+    } else {
+        let (exchange_quantity, _) = exchange_position(&account_info, &grid_config.trading_asset)?;
+        if (exchange_quantity - grid_state.position_quantity).abs() > f64::EPSILON {
+            warn!(
+                "⚠️ 账户持仓({:.4})与本地持仓({:.4})不一致，adopt_existing_position 已关闭，不接管",
+                exchange_quantity, grid_state.position_quantity
+            );
+        }
+    }
+
+    // 3.2 可用资金以交易所可提取余额为准，不沿用状态文件里保存的值
+    grid_state.sync_available_funds(account_info.withdrawable.parse().unwrap_or(0.0));
+    info!(
+        "💰 可用资金已按交易所余额同步: {:.2}",
+        grid_state.available_funds
+    );
+
+    // 4. 尝试加载订单状态
+    let (mut active_orders, mut buy_orders, mut sell_orders) =
+        match load_orders_state(&state_file_path(grid_config, "orders_state.json")).await? {
+            Some((orders, buys, sells)) => {
+                info!("🔄 检测到已保存的订单状态，正在恢复...");
+                info!("📊 恢复订单摘要:");
+                info!("   - 活跃订单: {}", orders.len());
+                info!("   - 买单: {}", buys.len());
+                info!("   - 卖单: {}", sells.len());
+
+                // 注意：这里恢复的订单可能已经不存在或状态已改变
+                // 在后续的订单状态检查中会自动同步
+                (orders, buys, sells)
+            }
+            None => {
+                info!("📄 未找到已保存的订单状态，使用空状态初始化");
+                (Vec::new(), HashMap::new(), HashMap::new())
+            }
+        };
+
+    // ===== 初始化风险控制模块 =====
+
+    // 创建风险控制标志
+    let stop_trading_flag = Arc::new(AtomicBool::new(false));
+    let mut manual_pause = false; // 通过控制接口暂停，只能由 resume 指令解除
+    let mut manual_rebalance = false; // 控制接口、SIGUSR1 或触发文件请求的重平衡，在下一轮价格更新时执行
+    let mut grid_breakout_since: Option<SystemTime> = None; // 价格开始脱离网格区间的时间
+
+    info!("🛡️ 风险控制模块已初始化");
+    info!("   - 检查间隔: 30秒");
+    info!(
+        "   - 保证金安全阈值: {:.1}%",
+        grid_config.margin_safety_threshold * 100.0
+    );
+    info!(
+        "   - 最大回撤限制: {:.1}%",
+        grid_config.max_drawdown * 100.0
+    );
+    info!(
+        "   - 每日最大亏损: {:.1}%",
+        grid_config.max_daily_loss * 100.0
+    );
+
+    // 风险控制状态
+    let mut last_risk_check = SystemTime::now();
+    let mut risk_events: Vec<RiskEvent> = Vec::new();
+    // 风险事件同时追加写入日志文件，重启后可用 risk-log 子命令查询
+    let risk_event_log = RiskEventLog::new(
+        state_file_path(grid_config, "risk_events.jsonl"),
+        grid_config.risk_log_max_size_mb * 1024 * 1024,
+        grid_config.risk_log_max_files,
+    );
+    // 修复：初始化为配置值，稍后在获取到第一个价格时更新为实际流动资产
+    let mut daily_start_capital = grid_state.total_capital;
+    let mut daily_start_time = SystemTime::now();
+    let mut consecutive_failures = 0u32;
+    let mut last_margin_ratio = 100.0f64;
+    let mut daily_start_capital_initialized = false; // 标记是否已初始化每日起始资本
+
+    // ===== 初始化订单优先级管理器 =====
+
+    let mut order_manager = OrderManager::new((grid_config.grid_count * 2) as usize); // 最大订单数为网格数的2倍
+
+    info!("📋 订单优先级管理器已初始化");
+    info!("   - 最大订单数: {}", order_manager.max_orders);
+    info!(
+        "   - 清理间隔: {}分钟",
+        order_manager.cleanup_interval.as_secs() / 60
+    );
+    info!(
+        "   - 优先级分布: {:?}",
+        order_manager.get_priority_distribution()
+    );
+
+    // ===== 初始化批处理优化器 =====
+
+    let mut batch_optimizer = BatchTaskOptimizer::new(
+        grid_config.max_orders_per_batch.max(5), // 初始批次大小，最少5个
+        Duration::from_secs(3),                  // 目标执行时间3秒
+    );
+
+    // 根据配置设置批次大小范围和最低成功率
+    batch_optimizer.set_batch_size_range(1, grid_config.max_orders_per_batch.max(100));
+    batch_optimizer.set_min_success_rate(grid_config.batch_min_success_rate);
+
+    info!("⚡ 批处理优化器已初始化");
+    info!(
+        "   - 初始批次大小: {}",
+        batch_optimizer.get_optimal_batch_size()
+    );
+    info!(
+        "   - 目标执行时间: {:.2}秒",
+        batch_optimizer.get_target_execution_time().as_secs_f64()
+    );
+    let (min_size, max_size) = batch_optimizer.get_batch_size_range();
+    info!("   - 批次大小范围: {} - {}", min_size, max_size);
+
+    // ===== 初始化连接管理器 =====
+
+    let mut connection_manager = ConnectionManager::new();
+
+    info!("🔗 连接管理器已初始化");
+    info!(
+        "   - 心跳间隔: {}秒",
+        connection_manager.heartbeat_interval.as_secs()
+    );
+    info!(
+        "   - 心跳超时: {}秒",
+        connection_manager.heartbeat_timeout.as_secs()
+    );
+    info!(
+        "   - 最大重连次数: {}",
+        connection_manager.max_reconnect_attempts
+    );
+    info!(
+        "   - 重连基础延迟: {}秒",
+        connection_manager.reconnect_base_delay.as_secs()
+    );
+    info!(
+        "   - 最大重连延迟: {}秒",
+        connection_manager.max_reconnect_delay.as_secs()
+    );
+    info!(
+        "   - 自适应心跳: {}",
+        if connection_manager.adaptive_heartbeat {
+            "启用"
+        } else {
+            "禁用"
+        }
+    );
+
+    // WebSocket 为主价格源，推送中断时用 REST 轮询兜底；回放模式的行情来自录制文件，不做兜底
+    let mut price_feed = if replay.is_some() {
+        PriceFeedMonitor::disabled()
+    } else {
+        PriceFeedMonitor::new(
+            grid_config.price_stale_secs,
+            grid_config.rest_price_poll_interval_secs,
+        )
+    };
+    if price_feed.is_enabled() {
+        info!(
+            "   - 价格兜底: WebSocket 超过{}秒未更新时每{}秒通过 REST 拉取价格",
+            grid_config.price_stale_secs, grid_config.rest_price_poll_interval_secs
+        );
+    }
+
+    // 初始连接检查
+    match connection_manager.check_connection(&info_client).await {
+        Ok(true) => {
+            info!("✅ 初始连接检查成功");
+        }
+        Ok(false) => {
+            warn!("⚠️ 初始连接检查失败，但系统将继续运行");
+        }
+        Err(e) => {
+            warn!("⚠️ 初始连接检查出错: {}, 系统将继续运行", e);
+        }
+    }
+
+    let mut last_connection_check = Instant::now();
+    let mut last_connection_report = Instant::now();
+
+    let mut last_price: Option<f64> = None;
+
+    let mut report_date = Utc::now().date_naive(); // 当前统计日（UTC）
+    let mut last_status_report = SystemTime::now();
+    let mut last_state_save = SystemTime::now(); // 添加状态保存时间跟踪
+    let mut last_position_reconcile = SystemTime::now();
+    let mut last_funding_refresh = SystemTime::now();
+    let mut last_grid_follow = SystemTime::now();
+    let mut was_in_trading_window = true;
+    let mut fill_deduplicator = FillDeduplicator::new(1000);
+
+    // 价格历史记录，容量预留为窗口的两倍，使 make_contiguous 的搬移开销均摊为 O(1)
+    let mut price_history: VecDeque<f64> = VecDeque::with_capacity(grid_config.history_length * 2);
+
+    // 创建消息通道：订阅消息经有界转发进入主循环，价格和盘口处理不过来时只保留最新值，
+    // 用户事件排队不丢；回放模式按顺序处理全部消息
+    let (sender, mut receiver) =
+        spawn_message_relay(grid_config.message_queue_capacity, replay.is_none());
+
+    // 模拟成交事件与实盘用户事件走同一个通道
+    if let Some(simulated) = &simulated_executor {
+        simulated.set_fill_sender(sender.clone());
+    }
+
+    if let Some(options) = &replay {
+        // 回放模式：价格和成交事件来自录制文件，不订阅实时推送
+        let records = load_recording(&options.path)?;
+        spawn_replay(records, options, sender.clone());
+    } else {
+        // 配置了录制文件时，订阅推送先经过录制任务再转发到主循环
+        let record_file = grid_config.record_file.trim();
+        let feed_sender = if record_file.is_empty() {
+            sender.clone()
+        } else {
+            spawn_recording_relay(std::path::Path::new(record_file), sender.clone())?
+        };
+
+        // 行情订阅：多账户时走共用的行情客户端，否则使用本账户客户端
+        let mut market_guard = match &market_client {
+            Some(client) => Some(client.lock().await),
+            None => None,
+        };
+        let market_info_client: &mut InfoClient = match market_guard.as_deref_mut() {
+            Some(client) => client,
+            None => &mut info_client,
+        };
+
+        // 订阅中间价格
+        market_info_client
+            .subscribe(Subscription::AllMids, feed_sender.clone())
+            .await
+            .map_err(|e| GridStrategyError::SubscriptionError(format!("订阅价格失败: {:?}", e)))?;
+
+        // 订阅盘口深度，用于估算滑点和调整下单数量
+        market_info_client
+            .subscribe(
+                Subscription::L2Book {
+                    coin: grid_config.trading_asset.clone(),
+                },
+                feed_sender.clone(),
+            )
+            .await
+            .map_err(|e| {
+                GridStrategyError::SubscriptionError(format!("订阅盘口深度失败: {:?}", e))
+            })?;
+
+        // 拉取历史K线并订阅K线更新，用于计算真实K线指标
+        for interval in &grid_config.kline_intervals {
+            let Some(interval_ms) = interval_millis(interval) else {
+                continue;
+            };
+            let end_time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64;
+            let start_time =
+                end_time.saturating_sub(interval_ms * grid_config.kline_capacity as u64);
+
+            acquire_api_permit().await;
+            match market_info_client
+                .candles_snapshot(
+                    grid_config.trading_asset.clone(),
+                    interval.clone(),
+                    start_time,
+                    end_time,
+                )
+                .await
+            {
+                Ok(candles) => {
+                    grid_state
+                        .klines
+                        .extend(interval, candles.iter().filter_map(Kline::from_snapshot));
+                    info!(
+                        "📊 已加载{}周期历史K线: {}根",
+                        interval,
+                        grid_state.klines.get(interval).len()
+                    );
+                }
+                Err(e) => warn!("⚠️ 拉取{}周期历史K线失败: {:?}，等待订阅推送", interval, e),
+            }
+
+            market_info_client
+                .subscribe(
+                    Subscription::Candle {
+                        coin: grid_config.trading_asset.clone(),
+                        interval: interval.clone(),
+                    },
+                    feed_sender.clone(),
+                )
+                .await
+                .map_err(|e| {
+                    GridStrategyError::SubscriptionError(format!(
+                        "订阅{}周期K线失败: {:?}",
+                        interval, e
+                    ))
+                })?;
+        }
+
+        drop(market_guard);
+
+        // 用户事件按账户订阅
+        info_client
+            .subscribe(
+                Subscription::UserEvents { user: user_address },
+                feed_sender.clone(),
+            )
+            .await
+            .map_err(|e| {
+                GridStrategyError::SubscriptionError(format!("订阅用户事件失败: {:?}", e))
+            })?;
+
+        refresh_funding_rate(&info_client, &mut grid_state, grid_config).await;
+    }
+
+    tr_log!(
+        info,
+        "🚀 资金管理型动态网格交易策略已启动",
+        "🚀 Fund-managed dynamic grid strategy started"
+    );
+
+    // 循环内触发的退出原因，跳出循环后统一执行安全退出
+    let mut shutdown_reason: Option<ShutdownReason> = None;
+    let mut seen_rebalance_signals = rebalance_signal.load(Ordering::SeqCst);
+    let mut last_slippage_alert = 0u64;
+
+    loop {
+        let now = SystemTime::now();
+
+        // 检查是否收到退出信号
+        if shutdown_flag.load(Ordering::SeqCst) {
+            info!("🔔 检测到退出信号，开始安全退出流程...");
+            shutdown_reason = Some(ShutdownReason::UserSignal);
+            break;
+        }
+
+        // 处理控制接口指令（由主循环统一修改状态）
+        while let Ok(request) = control_receiver.try_recv() {
+            info!("🎛️ 处理控制指令: {}", request.command.as_str());
+            let response = match request.command {
+                ControlCommand::Status => ControlResponse::with_data(
+                    "ok",
+                    build_status_snapshot(
+                        &grid_state,
+                        last_price,
+                        &buy_orders,
+                        &sell_orders,
+                        grid_config,
+                        stop_trading_flag.load(Ordering::SeqCst),
+                        price_feed.status_json(connection_manager.last_data_received),
+                    ),
+                ),
+                ControlCommand::Pause => {
+                    manual_pause = true;
+                    stop_trading_flag.store(true, Ordering::SeqCst);
+                    ControlResponse::ok("已暂停开新仓，已有订单保留")
+                }
+                ControlCommand::Resume => {
+                    manual_pause = false;
+                    if grid_state.loss_circuit_tripped_at > 0 {
+                        info!("🔓 通过控制接口解除连续亏损熔断");
+                        grid_state.reset_loss_circuit();
+                    }
+                    if clock_anomaly_detected() {
+                        ControlResponse::error("系统时钟异常，暂不能恢复交易")
+                    } else {
+                        stop_trading_flag.store(false, Ordering::SeqCst);
+                        ControlResponse::ok("已恢复交易")
+                    }
+                }
+                ControlCommand::Rebalance => {
+                    manual_rebalance = true;
+                    ControlResponse::ok("已安排在下一轮价格更新时重平衡网格")
+                }
+                ControlCommand::Flatten => match last_price {
+                    Some(current_price) => {
+                        manual_pause = true;
+                        stop_trading_flag.store(true, Ordering::SeqCst);
+                        match flatten_positions(
+                            &exchange_client,
+                            grid_config,
+                            &mut grid_state,
+                            &mut active_orders,
+                            &mut buy_orders,
+                            &mut sell_orders,
+                            current_price,
+                        )
+                        .await
+                        {
+                            Ok(()) => ControlResponse::ok("已撤销所有订单并清仓，交易保持暂停"),
+                            Err(e) => ControlResponse::error(format!("清仓失败: {}", e)),
+                        }
+                    }
+                    None => ControlResponse::error("尚未获取到价格，无法清仓"),
+                },
+                ControlCommand::Withdrawn => {
+                    if grid_config.profit_mode != "withdraw_to_base" {
+                        ControlResponse::error("当前利润处理模式不是 withdraw_to_base")
+                    } else {
+                        let amount = grid_state.confirm_profit_withdrawal();
+                        info!(
+                            "💸 已确认提取利润 {:.2}，累计提取 {:.2}",
+                            amount, grid_state.profit_withdrawn
+                        );
+                        ControlResponse::ok(format!("已确认提取利润 {:.2}", amount))
+                    }
+                }
+            };
+            let _ = request.reply.send(response);
+        }
+
+        // SIGUSR1 或触发文件请求的手动重平衡，和控制接口一样在下一轮价格更新时执行
+        if let Some(source) = take_manual_rebalance_request(
+            &rebalance_signal,
+            &mut seen_rebalance_signals,
+            grid_config,
+        ) {
+            info!(
+                "🔁 收到{}重平衡请求，安排在下一轮价格更新时重平衡网格",
+                source
+            );
+            manual_rebalance = true;
+        }
+
+        // 每日边界（UTC）：生成日报，跨周时生成周报，并重置当日统计
+        let today = Utc::now().date_naive();
+        if today > report_date {
+            let daily_report = generate_daily_report(&grid_state, grid_config, report_date);
+            info!("\n{}", daily_report);
+            alert_manager
+                .report(&format!("{}\n{}", grid_config.trading_asset, daily_report))
+                .await;
+
+            if today.iso_week() != report_date.iso_week() {
+                let week_start = report_date
+                    - chrono::Days::new(report_date.weekday().num_days_from_monday() as u64);
+                let weekly_report = generate_weekly_report(&grid_state, grid_config, week_start);
+                info!("\n{}", weekly_report);
+                alert_manager
+                    .report(&format!("{}\n{}", grid_config.trading_asset, weekly_report))
+                    .await;
+            }
+
+            report_date = today;
+            daily_start_time = now;
+            daily_start_capital_initialized = false; // 下一个价格 tick 按账户总资产重新初始化
+            consecutive_failures = 0;
+            info!("🔄 每日统计已重置");
+        }
+
+        // 获取当前价格和处理消息：WebSocket 推送为主，推送超时后按间隔轮询 REST 兜底
+        let last_ws_data = connection_manager.last_data_received;
+        let (message, from_rest) = if price_feed.should_poll_rest(last_ws_data) {
+            let rest_result = fetch_rest_mids_message(&info_client).await;
+            if price_feed.record_rest_poll(rest_result.is_ok()) {
+                let message = format!(
+                    "{} WebSocket 价格已{}秒未更新，切换为 REST 轮询价格",
+                    grid_config.trading_asset,
+                    last_ws_data.elapsed().as_secs()
+                );
+                warn!("⚠️ {}", message);
+                alert_manager.alert(Severity::High, &message).await;
+            }
+            match rest_result {
+                Ok(message) => (Some(message), true),
+                Err(e) => {
+                    warn!("⚠️ REST 拉取价格失败: {}", e);
+                    continue;
+                }
+            }
+        } else {
+            match price_feed.next_wait(last_ws_data) {
+                Some(wait) => match tokio::time::timeout(wait, receiver.recv()).await {
+                    Ok(message) => (message, false),
+                    Err(_) => continue, // 等待超时，回到循环开头检查是否需要 REST 兜底
+                },
+                None => (receiver.recv().await, false),
+            }
+        };
+
+        match message {
+            Some(Message::AllMids(all_mids)) => {
+                if !from_rest {
+                    connection_manager.last_data_received = Instant::now();
+                    if let Some(duration) = price_feed.on_ws_price() {
+                        let message = format!(
+                            "{} WebSocket 价格推送已恢复，REST 兜底持续{}秒",
+                            grid_config.trading_asset,
+                            duration.as_secs()
+                        );
+                        info!("✅ {}", message);
+                        alert_manager.alert(Severity::Medium, &message).await;
+                    }
+                }
+                let all_mids = all_mids.data.mids;
+                if let Some(current_price) = all_mids.get(&grid_config.trading_asset) {
+                    let current_price: f64 = current_price.parse().map_err(|e| {
+                        GridStrategyError::PriceParseError(format!("价格解析失败: {:?}", e))
+                    })?;
+
+                    // 可用资金以交易所可提取余额为准，隔离的利润不参与网格资金分配
+                    refresh_available_funds(
+                        exchange_client.as_ref(),
+                        &info_client,
+                        user_address,
+                        &mut grid_state,
+                    )
+                    .await?;
+
+                    // 初始化每日起始资本（仅在第一次获取价格时）
+                    if !daily_start_capital_initialized {
+                        // 获取真实的账户总资产作为起始资本
+                        let account_info_result =
+                            get_account_info(exchange_client.as_ref(), &info_client, user_address)
+                                .await;
+                        daily_start_capital = match account_info_result {
+                            Ok(account_info) => {
+                                if let Some(account_value) = account_info
+                                    .margin_summary
+                                    .account_value
+                                    .parse::<f64>()
+                                    .ok()
+                                {
+                                    account_value
+                                } else {
+                                    // 如果解析失败，使用流动资产作为备选
+                                    grid_state.available_funds
+                                        + grid_state.position_quantity * current_price
+                                }
+                            }
+                            Err(_) => {
+                                // 如果获取账户信息失败，使用流动资产作为备选
+                                grid_state.available_funds
+                                    + grid_state.position_quantity * current_price
+                            }
+                        };
+                        daily_start_capital_initialized = true;
+                        info!(
+                            "📊 每日起始资本已初始化: {:.2} USDC (基于真实账户总资产)",
+                            daily_start_capital
+                        );
+                    }
+
+                    // 更新价格历史
+                    price_history.push_back(current_price);
+                    if price_history.len() > grid_config.history_length {
+                        price_history.pop_front();
+                    }
+                    price_history.make_contiguous();
+                    let price_history = price_history.as_slices().0;
+                    grid_state.indicators.push(current_price);
+                    exchange_client.on_market_price(&grid_config.trading_asset, current_price);
+                    grid_state.update_unrealized_pnl(current_price);
+
+                    // 打印价格变化
+                    if let Some(last) = last_price {
+                        let price_change = ((current_price - last) / last) * 100.0;
+                        info!(
+                            "📈 价格变化: {:.4}% (从 {:.4} 到 {:.4})",
+                            price_change, last, current_price
+                        );
+                    }
+                    last_price = Some(current_price);
+
+                    // 0. 定期状态保存（每5分钟保存一次）
+                    if let Err(e) = periodic_state_save(
+                        grid_config,
+                        &grid_state,
+                        &active_orders,
+                        &buy_orders,
+                        &sell_orders,
+                        &mut last_state_save,
+                        300, // 5分钟 = 300秒
+                    )
+                    .await
+                    {
+                        warn!("⚠️ 定期状态保存失败: {:?}", e);
+                    }
+
+                    // 1. 止损检查 - 获取真实账户总价值
+                    let account_total_value = match get_account_info(
+                        exchange_client.as_ref(),
+                        &info_client,
+                        user_address,
+                    )
+                    .await
+                    {
+                        Ok(account_info) => {
+                            // 尝试解析账户总价值
+                            account_info
+                                .margin_summary
+                                .account_value
+                                .parse::<f64>()
+                                .ok()
+                        }
+                        Err(_) => None, // 如果获取失败，传入None跳过总资产止损检查
+                    };
+
+                    let stop_result = check_stop_loss(
+                        &mut grid_state,
+                        current_price,
+                        grid_config,
+                        price_history,
+                        active_orders.len(),
+                        account_total_value,
+                    );
+
+                    if stop_result.action.requires_action() {
+                        tr_log!(
+                            warn,
+                            "🚨 触发止损: {}, 原因: {}, 当前状态: {}",
+                            "🚨 Stop loss triggered: {}, reason: {}, status: {}",
+                            stop_result.action.localized(),
+                            stop_result.reason,
+                            grid_state.stop_loss_status.localized()
+                        );
+
+                        execute_stop_loss(
+                            &exchange_client,
+                            grid_config,
+                            &mut grid_state,
+                            &stop_result,
+                            &mut active_orders,
+                            &mut buy_orders,
+                            &mut sell_orders,
+                            current_price,
+                        )
+                        .await?;
+
+                        if stop_result.action.is_partial_stop()
+                            && grid_config.stop_loss_cooldown_minutes > 0
+                        {
+                            grid_state
+                                .start_stop_loss_cooldown(grid_config.stop_loss_cooldown_minutes);
+                            warn!(
+                                "🧊 部分止损后进入冷却期 {} 分钟，期间只平仓不开新仓",
+                                grid_config.stop_loss_cooldown_minutes
+                            );
+                        }
+
+                        if stop_result.action.is_full_stop() {
+                            error!("🛑 策略已全部止损，开始安全退出");
+
+                            shutdown_reason = Some(ShutdownReason::StopLossTriggered);
+                            break;
+                        }
+                    } else {
+                        // 1.1 追踪止盈检查
+                        let take_profit_result =
+                            check_take_profit(&mut grid_state, current_price, grid_config);
+
+                        if take_profit_result.action.requires_action() {
+                            tr_log!(
+                                info,
+                                "💰 触发止盈: {}, 原因: {}",
+                                "💰 Take profit triggered: {}, reason: {}",
+                                take_profit_result.action.localized(),
+                                take_profit_result.reason
+                            );
+
+                            if let Err(e) = execute_take_profit(
+                                &exchange_client,
+                                grid_config,
+                                &mut grid_state,
+                                &take_profit_result,
+                                &mut active_orders,
+                                &mut sell_orders,
+                                current_price,
+                            )
+                            .await
+                            {
+                                warn!("⚠️ 追踪止盈执行失败: {:?}", e);
+                            }
+                        }
+
+                        // 1.2 最大持仓时间检查
+                        if let Err(e) = check_holding_timeout(
+                            &exchange_client,
+                            grid_config,
+                            &mut grid_state,
+                            &mut active_orders,
+                            &mut sell_orders,
+                            current_price,
+                        )
+                        .await
+                        {
+                            warn!("⚠️ 持仓超时平仓失败: {:?}", e);
+                        }
+                    }
+
+                    // 检查止损状态是否允许继续交易
+                    if !grid_state.stop_loss_status.can_continue_trading() {
+                        warn!(
+                            "⚠️ 止损状态({})不允许继续交易",
+                            grid_state.stop_loss_status.localized()
+                        );
+                        if grid_state.stop_loss_status.is_failed() {
+                            error!("❌ 止损执行失败，策略退出");
+                            break;
+                        }
+                    }
+
+                    // 1.5. 风险控制检查
+                    let risk_check_interval = Duration::from_secs(30); // 30秒检查一次
+                    if now.duration_since(last_risk_check).unwrap_or_default()
+                        >= risk_check_interval
+                    {
+                        last_risk_check = now;
+
+                        // 执行风险检查
+                        let mut new_risk_events = Vec::new();
+                        let mut should_pause_trading = false;
+                        let mut should_emergency_exit = false;
+
+                        // 检查保证金率
+                        match check_margin_ratio(
+                            exchange_client.as_ref(),
+                            &info_client,
+                            user_address,
+                            grid_config,
+                        )
+                        .await
+                        {
+                            Ok(margin_ratio) => {
+                                last_margin_ratio = margin_ratio;
+                                consecutive_failures = 0; // 重置失败计数
+
+                                if margin_ratio < grid_config.margin_safety_threshold {
+                                    let event = RiskEvent::new(
+                                        RiskEventType::MarginInsufficient,
+                                        format!(
+                                            "保证金率({:.1}%)低于安全阈值({:.1}%)",
+                                            margin_ratio * 100.0,
+                                            grid_config.margin_safety_threshold * 100.0
+                                        ),
+                                        margin_ratio,
+                                        grid_config.margin_safety_threshold,
+                                    );
+                                    new_risk_events.push(event);
+                                    should_pause_trading = true;
+
+                                    if margin_ratio < grid_config.margin_safety_threshold * 0.8 {
+                                        should_emergency_exit = true;
+                                    }
+                                }
+                            }
+                            Err(e) => {
+                                warn!("⚠️ 保证金率检查失败: {:?}", e);
+                                consecutive_failures += 1;
+
+                                if consecutive_failures >= 3 {
+                                    let event = RiskEvent::new(
+                                        RiskEventType::NetworkIssue,
+                                        format!("连续{}次保证金检查失败", consecutive_failures),
+                                        consecutive_failures as f64,
+                                        3.0,
+                                    );
+                                    new_risk_events.push(event);
+                                }
+                            }
+                        }
+
+                        // 检查最大回撤
+                        if grid_state.performance.metrics.max_drawdown > grid_config.max_drawdown {
+                            let event = RiskEvent::new(
+                                RiskEventType::MaxDrawdownExceeded,
+                                format!(
+                                    "最大回撤({:.2}%)超过限制({:.2}%)",
+                                    grid_state.performance.metrics.max_drawdown * 100.0,
+                                    grid_config.max_drawdown * 100.0
+                                ),
+                                grid_state.performance.metrics.max_drawdown,
+                                grid_config.max_drawdown,
+                            );
+                            new_risk_events.push(event);
+                            should_pause_trading = true;
+                        }
+
+                        // 检查每日亏损 - 需要获取账户真实总资产（包括保证金占用）
+                        let account_info_result =
+                            get_account_info(exchange_client.as_ref(), &info_client, user_address)
+                                .await;
+                        let current_capital = match account_info_result {
+                            Ok(account_info) => {
+                                // 计算真实总资产：使用账户总价值
+                                if let Some(account_value) = account_info
+                                    .margin_summary
+                                    .account_value
+                                    .parse::<f64>()
+                                    .ok()
+                                {
+                                    account_value
+                                } else {
+                                    // 如果解析失败，使用流动资产作为备选
+                                    grid_state.available_funds
+                                        + grid_state.position_quantity * current_price
+                                }
+                            }
+                            Err(_) => {
+                                // 如果获取账户信息失败，使用流动资产作为备选
+                                grid_state.available_funds
+                                    + grid_state.position_quantity * current_price
+                            }
+                        };
+
+                        let daily_loss_ratio =
+                            (daily_start_capital - current_capital) / daily_start_capital;
+
+                        // 添加调试信息，帮助理解风险控制计算
+                        if daily_loss_ratio > 0.01 || daily_loss_ratio < -0.01 {
+                            info!(
+                                "📊 每日资产变化 - 起始: {:.2}, 当前: {:.2}, 变化: {:.2} ({:.2}%), 限制: {:.1}%",
+                                daily_start_capital,
+                                current_capital,
+                                current_capital - daily_start_capital,
+                                daily_loss_ratio * 100.0,
+                                grid_config.max_daily_loss * 100.0
+                            );
+                        }
+
+                        if daily_loss_ratio > grid_config.max_daily_loss {
+                            let event = RiskEvent::new(
+                                RiskEventType::DailyLossExceeded,
+                                format!(
+                                    "每日亏损({:.2}%)超过限制({:.2}%)",
+                                    daily_loss_ratio * 100.0,
+                                    grid_config.max_daily_loss * 100.0
+                                ),
+                                daily_loss_ratio,
+                                grid_config.max_daily_loss,
+                            );
+                            new_risk_events.push(event);
+                            should_pause_trading = true;
+                        }
+
+                        // 检查持仓规模
+                        let position_value = grid_state.position_quantity.abs() * current_price;
+                        if position_value > grid_config.max_position {
+                            let event = RiskEvent::new(
+                                RiskEventType::PositionSizeExceeded,
+                                format!(
+                                    "持仓价值({:.2})超过最大限制({:.2})",
+                                    position_value, grid_config.max_position
+                                ),
+                                position_value,
+                                grid_config.max_position,
+                            );
+                            new_risk_events.push(event);
+                        }
+
+                        // 检查市场波动率
+                        if price_history.len() >= 10 {
+                            let volatility = calculate_market_volatility(price_history);
+                            if volatility > 0.15 {
+                                // 15%的波动率阈值
+                                let event = RiskEvent::new(
+                                    RiskEventType::VolatilitySpike,
+                                    format!("市场波动率({:.2}%)异常高", volatility * 100.0),
+                                    volatility,
+                                    0.15,
+                                );
+                                new_risk_events.push(event);
+                            }
+                        }
+
+                        // 检查价格跳空
+                        if price_history.len() >= 2 {
+                            let last_price_val = price_history[price_history.len() - 2];
+                            let price_gap =
+                                ((current_price - last_price_val) / last_price_val).abs();
+
+                            if price_gap > 0.05 {
+                                // 5%的价格跳空阈值
+                                let event = RiskEvent::new(
+                                    RiskEventType::PriceGap,
+                                    format!("价格跳空({:.2}%)过大", price_gap * 100.0),
+                                    price_gap,
+                                    0.05,
+                                );
+                                new_risk_events.push(event);
+                                should_pause_trading = true;
+                            }
+                        }
+
+                        // 检查价格是否脱离网格区间（低于最低买单或高于最高卖单）
+                        let lowest_buy = buy_orders.values().map(|o| o.price).reduce(f64::min);
+                        let highest_sell = sell_orders.values().map(|o| o.price).reduce(f64::max);
+                        let breakout = match (lowest_buy, highest_sell) {
+                            (Some(low), _) if current_price < low => Some((low, "跌破最低买单")),
+                            (_, Some(high)) if current_price > high => Some((high, "突破最高卖单")),
+                            _ => None,
+                        };
+                        match breakout {
+                            Some((boundary, direction)) => {
+                                let since = *grid_breakout_since.get_or_insert(now);
+                                let outside_secs =
+                                    now.duration_since(since).unwrap_or_default().as_secs();
+                                if outside_secs >= grid_config.grid_breakout_secs {
+                                    let event = RiskEvent::new(
+                                        RiskEventType::GridBreakout,
+                                        format!(
+                                            "价格{:.4}{}{:.4}已持续{}秒",
+                                            current_price, direction, boundary, outside_secs
+                                        ),
+                                        current_price,
+                                        boundary,
+                                    );
+                                    new_risk_events.push(event);
+                                    // 重新计时，避免每轮检查重复触发
+                                    grid_breakout_since = None;
+                                }
+                            }
+                            None => grid_breakout_since = None,
+                        }
+
+                        // 处理新的风险事件
+                        for mut event in new_risk_events {
+                            info!(
+                                "🚨 检测到风险事件: {} - {}",
+                                event.event_type.localized(),
+                                event.description
+                            );
+
+                            let action = match event.event_type {
+                                RiskEventType::MarginInsufficient => {
+                                    stop_trading_flag.store(true, Ordering::SeqCst);
+                                    "暂停交易，等待保证金补充".to_string()
+                                }
+                                RiskEventType::MaxDrawdownExceeded => {
+                                    stop_trading_flag.store(true, Ordering::SeqCst);
+                                    "触发最大回撤保护，暂停交易".to_string()
+                                }
+                                RiskEventType::DailyLossExceeded => {
+                                    stop_trading_flag.store(true, Ordering::SeqCst);
+                                    "每日亏损超限，暂停交易".to_string()
+                                }
+                                RiskEventType::PositionSizeExceeded => {
+                                    "建议减仓，降低持仓风险".to_string()
+                                }
+                                RiskEventType::VolatilitySpike => {
+                                    "市场波动加剧，建议减少网格密度".to_string()
+                                }
+                                RiskEventType::PriceGap => {
+                                    "价格跳空，暂停交易等待市场稳定".to_string()
+                                }
+                                RiskEventType::GridBreakout => {
+                                    match grid_config.grid_breakout_action.as_str() {
+                                        "rebuild" => {
+                                            if let Some(elapsed) =
+                                                grid_state.rebuild_too_soon(grid_config, now)
+                                            {
+                                                // 仍在区间外时持续 grid_breakout_secs 后会再次触发
+                                                format!(
+                                                    "距上次整网重建仅{}秒（最小间隔{}秒），暂不重建网格",
+                                                    elapsed, grid_config.min_rebuild_interval_secs
+                                                )
+                                            } else {
+                                                // 撤销全部网格订单，下一轮按当前价格重建网格
+                                                match cancel_all_orders(
+                                                    &exchange_client,
+                                                    &mut active_orders,
+                                                    &grid_config.trading_asset,
+                                                )
+                                                .await
+                                                {
+                                                    Ok(()) => {
+                                                        buy_orders.clear();
+                                                        sell_orders.clear();
+                                                        grid_state.last_price_update = now;
+                                                        "已撤销网格订单，按当前价格重建网格"
+                                                            .to_string()
+                                                    }
+                                                    Err(e) => {
+                                                        format!("撤单失败，未能重建网格: {}", e)
+                                                    }
+                                                }
+                                            }
+                                        }
+                                        "pause" => {
+                                            stop_trading_flag.store(true, Ordering::SeqCst);
+                                            "价格脱离网格，暂停开新仓".to_string()
+                                        }
+                                        _ => "价格脱离网格，仅告警".to_string(),
+                                    }
+                                }
+                                _ => "风险事件已记录".to_string(),
+                            };
+
+                            metrics_registry.record_risk_event(event.is_critical());
+
+                            // 高风险事件推送告警
+                            if event.is_critical() {
+                                alert_manager
+                                    .alert(
+                                        Severity::from_level(event.severity),
+                                        &format!(
+                                            "{} 风险事件: {} - {}，处理: {}",
+                                            grid_config.trading_asset,
+                                            event.event_type.as_str(),
+                                            event.description,
+                                            action
+                                        ),
+                                    )
+                                    .await;
+                            }
+
+                            event.mark_handled(action.clone());
+                            if let Err(e) = risk_event_log.append(&event) {
+                                warn!("⚠️ 写入风险事件日志失败: {}", e);
+                            }
+                            risk_events.push(event);
+
+                            info!("✅ 风险事件处理完成: {}", action);
+                        }
+
+                        // 保留最近50个风险事件
+                        if risk_events.len() > 50 {
+                            risk_events.drain(0..risk_events.len() - 50);
+                        }
+
+                        // 检查是否需要紧急退出
+                        if should_emergency_exit {
+                            error!("🚨 触发紧急风险控制，立即退出");
+
+                            shutdown_reason = Some(ShutdownReason::EmergencyShutdown);
+                            break;
+                        }
+
+                        // 检查是否需要暂停交易
+                        if should_pause_trading && !stop_trading_flag.load(Ordering::SeqCst) {
+                            warn!("⚠️ 风险控制触发，暂停新的交易操作");
+                            stop_trading_flag.store(true, Ordering::SeqCst);
+                        }
+
+                        // 1.6. 订单优先级管理
+                        // 更新市场条件
+                        if price_history.len() >= 2 {
+                            let volatility = calculate_market_volatility(price_history);
+                            let price_change = ((current_price
+                                - price_history[price_history.len() - 2])
+                                / price_history[price_history.len() - 2])
+                                .abs();
+                            order_manager.update_market_conditions(
+                                current_price,
+                                volatility,
+                                price_change,
+                            );
+                        }
+
+                        // 处理优先级队列中的过期订单
+                        if let Err(e) = process_expired_priority_orders(
+                            &exchange_client,
+                            &mut order_manager,
+                            grid_config,
+                            &mut grid_state,
+                            current_price,
+                        )
+                        .await
+                        {
+                            warn!("⚠️ 处理过期订单失败: {:?}", e);
+                        }
+
+                        // 处理紧急订单
+                        let urgent_orders = order_manager.get_urgent_orders();
+                        if !urgent_orders.is_empty() {
+                            info!("🚨 检测到{}个紧急订单需要处理", urgent_orders.len());
+                            for urgent_order in urgent_orders {
+                                if urgent_order.needs_immediate_attention() {
+                                    info!(
+                                        "⚡ 处理紧急订单: {} - 剩余时间: {:?}秒",
+                                        urgent_order.priority.localized(),
+                                        urgent_order.remaining_seconds()
+                                    );
+
+                                    // 这里可以添加紧急订单的特殊处理逻辑
+                                    // 例如：提高订单优先级、使用市价单等
+                                }
+                            }
+                        }
+
+                        // 定期显示风险报告（每小时一次）
+                        if risk_events.len() > 0
+                            && now
+                                .duration_since(daily_start_time)
+                                .unwrap_or_default()
+                                .as_secs()
+                                % 3600
+                                < 30
+                        {
+                            let recent_events: Vec<_> = risk_events
+                                .iter()
+                                .filter(|e| {
+                                    now.duration_since(e.timestamp)
+                                        .unwrap_or_default()
+                                        .as_secs()
+                                        < 24 * 3600
+                                })
+                                .collect();
+                            let critical_events =
+                                recent_events.iter().filter(|e| e.is_critical()).count();
+
+                            info!("📊 风险控制报告:");
+                            info!("   - 最近24小时风险事件: {}", recent_events.len());
+                            info!("   - 其中严重事件: {}", critical_events);
+                            info!("   - 连续失败次数: {}", consecutive_failures);
+                            info!("   - 最近保证金率: {:.2}%", last_margin_ratio * 100.0);
+                        }
+                    }
+
+                    // 刷新监控指标
+                    metrics_registry.update(|m| {
+                        m.current_price = current_price;
+                        m.position_quantity = grid_state.position_quantity;
+                        m.position_avg_price = grid_state.position_avg_price;
+                        m.available_funds = grid_state.available_funds;
+                        m.realized_profit = grid_state.realized_profit;
+                        m.unrealized_pnl = grid_state.unrealized_pnl;
+                        m.active_buy_orders = buy_orders.len();
+                        m.active_sell_orders = sell_orders.len();
+                        m.connection_quality_score =
+                            connection_manager.get_quality().overall_score();
+                        m.last_margin_ratio = last_margin_ratio;
+                        m.trading_paused = stop_trading_flag.load(Ordering::SeqCst);
+                    });
+
+                    // 系统时钟异常时各类时间差计算不可信，暂停开新仓直到时钟恢复
+                    if clock_anomaly_detected() && !stop_trading_flag.swap(true, Ordering::SeqCst) {
+                        alert_manager
+                            .alert(
+                                Severity::Critical,
+                                &format!(
+                                    "{} 系统时钟异常（早于Unix纪元），已暂停开新仓",
+                                    grid_config.trading_asset
+                                ),
+                            )
+                            .await;
+                    }
+
+                    // 检查风险控制标志
+                    if stop_trading_flag.load(Ordering::SeqCst) {
+                        warn!("⚠️ 风险控制已激活，暂停开新仓（撤单、止损和平仓照常执行）");
+
+                        // 添加详细的调试信息
+                        info!("🔍 风险控制调试信息:");
+                        info!("   - 最近风险事件数量: {}", risk_events.len());
+                        if !risk_events.is_empty() {
+                            let recent_events: Vec<_> = risk_events
+                                .iter()
+                                .filter(|e| {
+                                    SystemTime::now()
+                                        .duration_since(e.timestamp)
+                                        .unwrap_or_default()
+                                        .as_secs()
+                                        < 300 // 最近5分钟
+                                })
+                                .collect();
+                            info!("   - 最近5分钟风险事件: {}", recent_events.len());
+                            for event in recent_events.iter().take(3) {
+                                info!(
+                                    "     * {}: {}",
+                                    event.event_type.localized(),
+                                    event.description
+                                );
+                            }
+                        }
+
+                        // 检查是否可以重置风险控制标志
+                        let should_reset = risk_events.is_empty()
+                            || risk_events.iter().all(|e| {
+                                SystemTime::now()
+                                    .duration_since(e.timestamp)
+                                    .unwrap_or_default()
+                                    .as_secs()
+                                    > 600 // 10分钟前的事件
+                            });
+
+                        if should_reset && clock_anomaly_detected() {
+                            debug!("⏸️ 系统时钟仍异常，保持暂停开新仓");
+                        } else if should_reset && manual_pause {
+                            debug!("⏸️ 已通过控制接口手动暂停，等待 resume 指令");
+                        } else if should_reset {
+                            info!("🔄 风险事件已过期，重置风险控制标志，恢复交易");
+                            stop_trading_flag.store(false, Ordering::SeqCst);
+                        }
+                    }
+                    let in_stop_loss_cooldown = grid_state.check_stop_loss_cooldown();
+                    if in_stop_loss_cooldown {
+                        debug!(
+                            "🧊 止损冷却中，剩余 {} 秒，暂停开新仓",
+                            grid_state
+                                .stop_loss_cooldown_until
+                                .saturating_sub(safe_unix_timestamp())
+                        );
+                    }
+                    let utc_now = Utc::now();
+                    let in_trading_window = in_configured_trading_window(grid_config, utc_now);
+                    if in_trading_window != was_in_trading_window {
+                        if in_trading_window {
+                            info!("🕒 进入交易时间窗口，恢复开新仓");
+                        } else {
+                            info!("🌙 离开交易时间窗口，保留已有挂单，暂停开新仓");
+                        }
+                        was_in_trading_window = in_trading_window;
+                    }
+                    let in_loss_circuit =
+                        grid_state.check_loss_circuit(grid_config.loss_circuit_cooldown_minutes);
+                    if in_loss_circuit {
+                        debug!(
+                            "🧯 连续亏损熔断中（{}笔），暂停开新仓",
+                            grid_state.consecutive_losses
+                        );
+                    }
+                    let trading_paused = trading_paused(
+                        &grid_state,
+                        grid_config,
+                        stop_trading_flag.load(Ordering::SeqCst),
+                        utc_now,
+                    );
+
+                    // 1.6. 智能订单更新检查（会重建网格，暂停期间跳过）
+                    if trading_paused {
+                        debug!("⏸️ 交易已暂停，跳过智能订单更新");
+                    } else if let Err(e) = smart_update_orders(
+                        &exchange_client,
+                        grid_config,
+                        &mut grid_state,
+                        current_price,
+                        price_history,
+                        &mut active_orders,
+                        &mut buy_orders,
+                        &mut sell_orders,
+                        &mut batch_optimizer,
+                    )
+                    .await
+                    {
+                        warn!("⚠️ 智能订单更新失败: {:?}", e);
+                    }
+
+                    // 1.7. 撤销过期的网格挂单
+                    if let Err(e) = cancel_stale_grid_orders(
+                        &exchange_client,
+                        grid_config,
+                        &grid_state,
+                        &mut active_orders,
+                        &mut buy_orders,
+                        &mut sell_orders,
+                    )
+                    .await
+                    {
+                        warn!("⚠️ 过期订单清理失败: {:?}", e);
+                    }
+
+                    // 1.8. 连接管理器检查（按自适应心跳间隔）
+                    if connection_manager.should_check_connection() {
+                        last_connection_check = Instant::now();
+
+                        let reconnect_error =
+                            match connection_manager.check_connection(&info_client).await {
+                                Ok(_) if connection_manager.get_quality().is_poor() => {
+                                    warn!(
+                                        "⚠️ 连接质量过差(评分: {:.1})，强制重连",
+                                        connection_manager.get_quality().overall_score()
+                                    );
+                                    match connection_manager.force_reconnect(&info_client).await {
+                                        Ok(()) => {
+                                            info!("✅ 强制重连成功");
+                                            None
+                                        }
+                                        Err(e) => Some(e),
+                                    }
+                                }
+                                Ok(false) => {
+                                    warn!("⚠️ 连接质量下降，尝试重连");
+
+                                    match connection_manager.attempt_reconnect(&info_client).await {
+                                        Ok(true) => {
+                                            info!("✅ 连接重连成功");
+                                            None
+                                        }
+                                        Ok(false) => {
+                                            warn!("⚠️ 连接重连失败，但系统继续运行");
+                                            None
+                                        }
+                                        Err(e) => Some(e),
+                                    }
+                                }
+                                Ok(true) => None,
+                                Err(e) => {
+                                    warn!("⚠️ 连接检查失败: {}", e);
+                                    connection_manager.on_connection_lost(&e);
+                                    None
+                                }
+                            };
+
+                        if let Some(e) = reconnect_error {
+                            error!("❌ 连接重连过程出错: {}", e);
+
+                            // 如果连接完全失败，考虑暂停交易
+                            if connection_manager.get_status() == &ConnectionStatus::Failed {
+                                warn!("🚨 连接完全失败，暂停交易操作");
+                                stop_trading_flag.store(true, Ordering::SeqCst);
+                                alert_manager
+                                    .alert(
+                                        Severity::High,
+                                        &format!(
+                                            "{} 网络连接完全失败，已暂停交易: {}",
+                                            grid_config.trading_asset, e
+                                        ),
+                                    )
+                                    .await;
+
+                                // 记录网络风险事件
+                                let network_event = RiskEvent::new(
+                                    RiskEventType::NetworkIssue,
+                                    format!("网络连接失败: {}", e),
+                                    0.0,
+                                    1.0,
+                                );
+                                metrics_registry.record_risk_event(network_event.is_critical());
+                                if let Err(e) = risk_event_log.append(&network_event) {
+                                    warn!("⚠️ 写入风险事件日志失败: {}", e);
+                                }
+                                risk_events.push(network_event);
+                            }
+                        }
+                    }
+
+                    // 定期显示连接报告（每10分钟一次）
+                    if last_connection_report.elapsed() >= Duration::from_secs(600) {
+                        last_connection_report = Instant::now();
+                        let report = connection_manager.get_connection_report();
+                        info!(
+                            "📡 连接状态报告 (上次检查: {}秒前):\n{}",
+                            last_connection_check.elapsed().as_secs(),
+                            report
+                        );
+                    }
+
+                    // 1.9. 跟随均线平移网格：价格和均线都偏离网格中心超过阈值时，撤远端、补近端
+                    if !trading_paused
+                        && grid_config.grid_center_mode != "fixed"
+                        && !active_orders.is_empty()
+                        && grid_state.grid_center_price > 0.0
+                        && should_execute_periodic_task(
+                            last_grid_follow,
+                            grid_config.grid_follow_interval_secs,
+                            "网格跟随均线",
+                        )
+                    {
+                        last_grid_follow = now;
+                        let market_analysis = analyze_market_trend(
+                            price_history,
+                            &grid_state.indicators,
+                            grid_state.klines.primary(),
+                            grid_state.market_state_tracker.confirmed(),
+                        );
+                        if let Some(ma) = grid_follow_ma(grid_config, &market_analysis) {
+                            let center = grid_state.grid_center_price;
+                            let target_center = ma * (1.0 + grid_config.grid_price_offset);
+                            let price_deviation = (current_price - center) / center;
+                            let ma_deviation = (target_center - center) / center;
+                            if price_deviation.abs() > grid_config.grid_follow_threshold
+                                && ma_deviation.abs() > grid_config.grid_follow_threshold
+                                && price_deviation.signum() == ma_deviation.signum()
+                            {
+                                shift_grid_center(
+                                    &exchange_client,
+                                    grid_config,
+                                    &mut grid_state,
+                                    target_center,
+                                    &mut active_orders,
+                                    &mut buy_orders,
+                                    &mut sell_orders,
+                                )
+                                .await;
+                            }
+                        }
+                    }
+
+                    // 2. 手动请求的重平衡只重建网格，不做自动参数优化；暂停期间保留请求，恢复后执行
+                    if manual_rebalance && !trading_paused {
+                        manual_rebalance = false;
+                        info!("🔁 执行手动重平衡");
+                        rebalance_grid(
+                            &exchange_client,
+                            grid_config,
+                            &mut grid_state,
+                            current_price,
+                            price_history,
+                            &mut active_orders,
+                            &mut buy_orders,
+                            &mut sell_orders,
+                        )
+                        .await?;
+                    }
+
+                    // 2.1 检查是否需要重平衡（每24小时），距上次整网重建不足最小间隔时顺延到下一轮
+                    let rebalance_interval = 24 * 60 * 60; // 24小时
+                    if !trading_paused
+                        && now
+                            .duration_since(grid_state.last_rebalance_time)
+                            .unwrap_or_default()
+                            .as_secs()
+                            >= rebalance_interval
+                        && grid_state.rebuild_too_soon(grid_config, now).is_none()
+                    {
+                        info!("🔄 开始定期重平衡...");
+
+                        // 在重平衡前自动优化参数
+                        if grid_state.performance.records.len() >= 20 {
+                            info!("📈 开始自动网格参数优化");
+                            let optimization_applied = auto_optimize_grid_parameters(
+                                &mut grid_state,
+                                grid_config,
+                                price_history,
+                            );
+
+                            // 运行中更新的参数不合法时不再继续交易
+                            let params_validation = validate_dynamic_parameters(
+                                &grid_state.dynamic_params,
+                                grid_config,
+                                "运行时更新",
+                            );
+                            if !params_validation.is_valid {
+                                error!(
+                                    "🚨 运行时参数更新后校验失败: {}，开始安全退出",
+                                    params_validation.errors.join("; ")
+                                );
+                                shutdown_reason = Some(ShutdownReason::ConfigurationError);
+                                break;
+                            }
+
+                            if !optimization_applied {
+                                // 如果没有应用自动优化，则显示建议
+                                info!("📊 显示网格参数优化建议");
+                                analyze_grid_performance_and_suggest_optimization(
+                                    grid_config,
+                                    &grid_state,
+                                );
+                            }
+
+                            // 创建一个临时的配置副本进行优化分析
+                            let mut temp_min_spacing = grid_config.min_grid_spacing;
+                            let mut temp_max_spacing = grid_config.max_grid_spacing;
+
+                            // 手动应用优化逻辑
+                            if grid_state.performance.records.len() >= 10 {
+                                let recent_records: Vec<&PerformanceRecord> = grid_state
+                                    .performance
+                                    .records
+                                    .iter()
+                                    .rev()
+                                    .take(20)
+                                    .collect();
+
+                                let recent_profit: f64 =
+                                    recent_records.iter().map(|r| r.profit).sum();
+                                let recent_win_rate =
+                                    recent_records.iter().filter(|r| r.profit > 0.0).count() as f64
+                                        / recent_records.len() as f64;
+
+                                // 根据表现调整网格间距
+                                if recent_profit > 0.0 && recent_win_rate > 0.6 {
+                                    // 表现良好，可以稍微增加网格间距以获得更大利润
+                                    temp_min_spacing *= 1.05;
+                                    temp_max_spacing *= 1.05;
+                                    info!("🔧 参数优化建议 - 表现良好，建议增加网格间距");
+                                } else if recent_profit < 0.0 || recent_win_rate < 0.4 {
+                                    // 表现不佳，减少网格间距以提高成交频率
+                                    temp_min_spacing *= 0.95;
+                                    temp_max_spacing *= 0.95;
+                                    info!("🔧 参数优化建议 - 表现不佳，建议减少网格间距");
+                                }
+
+                                // 确保网格间距在合理范围内
+                                temp_min_spacing = temp_min_spacing.max(0.001).min(0.05);
+                                temp_max_spacing = temp_max_spacing.max(temp_min_spacing).min(0.1);
+
+                                // 显示优化建议
+                                if (temp_min_spacing - grid_config.min_grid_spacing).abs() > 0.0001
+                                {
+                                    info!(
+                                        "🔧 参数优化建议 - 最小网格间距: {:.4}% -> {:.4}%",
+                                        grid_config.min_grid_spacing * 100.0,
+                                        temp_min_spacing * 100.0
+                                    );
+                                }
+                                if (temp_max_spacing - grid_config.max_grid_spacing).abs() > 0.0001
+                                {
+                                    info!(
+                                        "🔧 参数优化建议 - 最大网格间距: {:.4}% -> {:.4}%",
+                                        grid_config.max_grid_spacing * 100.0,
+                                        temp_max_spacing * 100.0
+                                    );
+                                }
+                            }
+
+                            info!("💡 参数优化分析完成，建议在配置文件中手动调整参数");
+                        }
+
+                        rebalance_grid(
+                            &exchange_client,
+                            grid_config,
+                            &mut grid_state,
+                            current_price,
+                            price_history,
+                            &mut active_orders,
+                            &mut buy_orders,
+                            &mut sell_orders,
+                        )
+                        .await?;
+                    }
+
+                    // 3. 定期检查订单状态（可配置间隔）
+                    if should_execute_periodic_task(
+                        grid_state.last_order_batch_time,
+                        grid_config.order_status_check_interval,
+                        "订单状态检查",
+                    ) {
+                        if exchange_client.is_simulated() {
+                            // 模拟模式下成交由模拟执行器推送，无需查询交易所挂单
+                        } else if let Err(e) = sync_open_orders(
+                            &info_client,
+                            user_address,
+                            &mut active_orders,
+                            &mut buy_orders,
+                            &mut sell_orders,
+                            &sender,
+                        )
+                        .await
+                        {
+                            warn!("⚠️ 订单状态检查失败: {:?}", e);
+                        }
+                        grid_state.last_order_batch_time = now;
+                    }
+
+                    // 3.1 自适应订单平衡检查和补全
+                    // 冰山单的子订单按一个逻辑订单计数
+                    let buy_count = logical_order_count(&buy_orders);
+                    let sell_count = logical_order_count(&sell_orders);
+                    let total_orders = buy_count + sell_count;
+
+                    // 计算理想的买卖单数量（基于配置限制）
+                    let ideal_total_orders = (grid_config.max_active_orders as usize)
+                        .min(grid_config.grid_count as usize * 2);
+                    let ideal_buy_count = ideal_total_orders / 2;
+                    let ideal_sell_count = ideal_total_orders / 2;
+
+                    // 详细的调试信息
+                    if total_orders > 0 {
+                        info!("🔍 订单平衡检查 - 买单: {}, 卖单: {}, 总计: {}, 理想总数: {}, 配置限制: {}",
+                              buy_count, sell_count, total_orders, ideal_total_orders, grid_config.max_active_orders);
+
+                        let balance_diff = (buy_count as i32 - sell_count as i32).abs();
+                        let is_quantity_low = buy_count + sell_count < ideal_total_orders / 2;
+                        let is_missing_orders = total_orders < ideal_total_orders;
+                        let is_below_grid_requirement =
+                            total_orders < grid_config.grid_count as usize * 2;
+
+                        info!("🔍 平衡分析 - 买卖差异: {}, 数量过少: {}, 缺少订单: {}, 低于网格要求: {}",
+                              balance_diff, is_quantity_low, is_missing_orders, is_below_grid_requirement);
+
+                        // 详细分析每个触发条件
+                        if buy_count == 0 && sell_count > 0 {
+                            info!("🔍 触发条件1: 只有卖单，没有买单");
+                        }
+                        if sell_count == 0 && buy_count > 0 {
+                            info!("🔍 触发条件2: 只有买单，没有卖单");
+                        }
+                        if buy_count + sell_count < ideal_total_orders / 2 {
+                            info!(
+                                "🔍 触发条件3: 订单数量过少 ({} < {})",
+                                buy_count + sell_count,
+                                ideal_total_orders / 2
+                            );
+                        }
+                        if balance_diff > 3 {
+                            info!(
+                                "🔍 触发条件4: 买卖单数量严重不平衡 (差异: {})",
+                                balance_diff
+                            );
+                        }
+                        if total_orders < ideal_total_orders && total_orders > 0 {
+                            info!(
+                                "🔍 触发条件5: 总订单数不足但不为空 ({} < {})",
+                                total_orders, ideal_total_orders
+                            );
+                        }
+                        if total_orders > 0 && total_orders < grid_config.grid_count as usize * 2 {
+                            info!(
+                                "🔍 触发条件6: 订单数少于配置要求 ({} < {})",
+                                total_orders,
+                                grid_config.grid_count as usize * 2
+                            );
+                        }
+                    }
+
+                    // 检查是否需要补全订单
+                    let should_recreate_grid = active_orders.is_empty();
+                    let should_rebalance_orders = !should_recreate_grid
+                        && (
+                            (buy_count == 0 && sell_count > 0) ||  // 只有卖单，没有买单
+                        (sell_count == 0 && buy_count > 0) ||  // 只有买单，没有卖单
+                        (buy_count + sell_count < ideal_total_orders / 2) ||  // 订单数量过少
+                        ((buy_count as i32 - sell_count as i32).abs() > 3) ||  // 买卖单数量严重不平衡
+                        (total_orders < ideal_total_orders && total_orders > 0) ||  // 总订单数不足但不为空
+                        (total_orders > 0 && total_orders < grid_config.grid_count as usize * 2)
+                            // 订单数少于配置要求
+                        );
+
+                    if trading_paused && (should_recreate_grid || should_rebalance_orders) {
+                        info!("⏸️ 交易已暂停，跳过网格创建和订单补全");
+                    } else if should_recreate_grid {
+                        // 没有挂单可撤，直接建网，不受最小重建间隔限制
+                        info!("📊 没有活跃订单，创建动态网格...");
+
+                        create_dynamic_grid(
+                            &exchange_client,
+                            grid_config,
+                            &mut grid_state,
+                            current_price,
+                            price_history,
+                            &mut active_orders,
+                            &mut buy_orders,
+                            &mut sell_orders,
+                            &mut order_manager,
+                        )
+                        .await?;
+                    } else if should_rebalance_orders {
+                        // 自适应订单补全逻辑
+                        info!("🔄 检测到订单不平衡，执行自适应补全...");
+                        info!(
+                            "📊 当前状态 - 买单: {}, 卖单: {}, 总计: {}, 理想总数: {}",
+                            buy_count, sell_count, total_orders, ideal_total_orders
+                        );
+
+                        // 执行智能订单补全
+                        adaptive_order_rebalance(
+                            &exchange_client,
+                            grid_config,
+                            &mut grid_state,
+                            current_price,
+                            price_history,
+                            &mut active_orders,
+                            &mut buy_orders,
+                            &mut sell_orders,
+                            buy_count,
+                            sell_count,
+                            ideal_buy_count,
+                            ideal_sell_count,
+                        )
+                        .await?;
+
+                        // 如果配置了批量订单，可以在这里使用批量创建功能
+                        if grid_config.max_orders_per_batch > 1 {
+                            info!(
+                                "💡 批量订单配置已启用 - 批次大小: {}, 限流: {}次/秒",
+                                grid_config.max_orders_per_batch,
+                                grid_config.api_requests_per_second
+                            );
+                        }
+                    }
+
+                    // 4. 资金分配监控
+                    if let Err(e) =
+                        monitor_fund_allocation(&grid_state, &buy_orders, &sell_orders, grid_config)
+                    {
+                        warn!("⚠️ 资金分配监控警告: {:?}", e);
+                    }
+
+                    // 4.1 保证金监控（每5分钟检查一次）
+                    if should_execute_periodic_task(grid_state.last_margin_check, 300, "保证金监控")
+                    {
+                        // 首先检查连接状态
+                        match ensure_connection(&info_client, &mut grid_state).await {
+                            Ok(true) => {
+                                // 连接正常，进行保证金检查
+                                match check_margin_ratio(
+                                    exchange_client.as_ref(),
+                                    &info_client,
+                                    user_address,
+                                    grid_config,
+                                )
+                                .await
+                                {
+                                    Ok(margin_ratio) => {
+                                        info!("💳 保证金率: {:.1}%", margin_ratio * 100.0);
+                                        grid_state.last_margin_check = now;
+                                    }
+                                    Err(e) => {
+                                        error!("🚨 保证金监控失败: {:?}", e);
+                                        // 如果是保证金不足，触发紧急止损
+                                        if matches!(e, GridStrategyError::MarginInsufficient(_)) {
+                                            warn!("🚨 保证金不足，执行紧急止损");
+                                            let emergency_stop = StopLossResult {
+                                                action: StopLossAction::FullStop,
+                                                reason: "保证金不足".to_string(),
+                                                stop_quantity: grid_state.position_quantity,
+                                            };
+                                            if let Err(stop_err) = execute_stop_loss(
+                                                &exchange_client,
+                                                grid_config,
+                                                &mut grid_state,
+                                                &emergency_stop,
+                                                &mut active_orders,
+                                                &mut buy_orders,
+                                                &mut sell_orders,
+                                                current_price,
+                                            )
+                                            .await
+                                            {
+                                                error!("❌ 紧急止损执行失败: {:?}", stop_err);
+                                            }
+
+                                            // 保证金不足时安全退出
+                                            shutdown_reason =
+                                                Some(ShutdownReason::MarginInsufficient);
+                                            break;
+                                        }
+                                    }
+                                }
+                            }
+                            Ok(false) => {
+                                warn!("⚠️ 网络连接不稳定，跳过本次检查");
+                            }
+                            Err(e) => {
+                                error!("❌ 连接检查失败: {:?}", e);
+                                // 连接失败次数过多，退出策略
+                                if grid_state.connection_retry_count > 10 {
+                                    error!("🚨 网络连接失败次数过多，开始安全退出");
+                                    shutdown_reason = Some(ShutdownReason::NetworkError);
+                                    break;
+                                }
+                            }
+                        }
+                    }
+
+                    // 4.2 持仓对账，模拟模式下持仓由本地撮合产生，不与交易所对账
+                    if grid_config.position_reconcile_interval_secs > 0
+                        && !exchange_client.is_simulated()
+                        && should_execute_periodic_task(
+                            last_position_reconcile,
+                            grid_config.position_reconcile_interval_secs,
+                            "持仓对账",
+                        )
+                    {
+                        last_position_reconcile = now;
+                        match reconcile_position(
+                            exchange_client.as_ref(),
+                            &info_client,
+                            user_address,
+                            &mut grid_state,
+                            grid_config,
+                        )
+                        .await
+                        {
+                            Ok(Some((local_quantity, exchange_quantity))) => {
+                                alert_manager
+                                    .alert(
+                                        Severity::High,
+                                        &format!(
+                                            "{} 本地持仓({:.4})与交易所持仓({:.4})不一致，已以交易所为准修正",
+                                            grid_config.trading_asset,
+                                            local_quantity,
+                                            exchange_quantity
+                                        ),
+                                    )
+                                    .await;
+                            }
+                            Ok(None) => {}
+                            Err(e) => warn!("⚠️ 持仓对账失败: {:?}", e),
+                        }
+                    }
+
+                    // 4.3 刷新资金费率，回放模式下没有实时行情接口
+                    if replay.is_none()
+                        && should_execute_periodic_task(
+                            last_funding_refresh,
+                            FUNDING_RATE_REFRESH_SECS,
+                            "资金费率刷新",
+                        )
+                    {
+                        last_funding_refresh = now;
+                        refresh_funding_rate(&info_client, &mut grid_state, grid_config).await;
+                    }
+
+                    // 5. 定期状态报告和参数管理（每小时）
+                    if should_execute_periodic_task(last_status_report, 3600, "状态报告") {
+                        // 更新性能指标
+                        let (total_capital, realized_profit) =
+                            (grid_state.total_capital, grid_state.realized_profit);
+                        grid_state
+                            .performance
+                            .recalculate_metrics(total_capital, realized_profit);
+
+                        // 检查是否需要回滚（基于当前性能）
+                        let current_performance_score =
+                            if grid_state.performance.metrics.total_profit > 0.0 {
+                                50.0
+                            } else {
+                                0.0
+                            } + grid_state.performance.metrics.win_rate * 30.0
+                                + if grid_state.performance.metrics.total_profit
+                                    / grid_state.performance.metrics.total_trades.max(1) as f64
+                                    > 0.0
+                                {
+                                    20.0
+                                } else {
+                                    0.0
+                                };
+
+                        if let Some(checkpoint) = grid_state
+                            .dynamic_params
+                            .should_rollback(current_performance_score)
+                        {
+                            warn!("🔄 定期检查发现性能下降，执行参数回滚");
+                            let checkpoint_clone = checkpoint.clone();
+                            grid_state
+                                .dynamic_params
+                                .rollback_to_checkpoint(&checkpoint_clone);
+
+                            let params_validation = validate_dynamic_parameters(
+                                &grid_state.dynamic_params,
+                                grid_config,
+                                "参数回滚",
+                            );
+                            if !params_validation.is_valid {
+                                error!(
+                                    "🚨 回滚后的参数校验失败: {}，开始安全退出",
+                                    params_validation.errors.join("; ")
+                                );
+                                shutdown_reason = Some(ShutdownReason::ConfigurationError);
+                                break;
+                            }
+
+                            // 保存回滚后的参数
+                            if let Err(e) = grid_state.dynamic_params.save_to_file(
+                                &state_file_path(grid_config, "dynamic_grid_params.json"),
+                            ) {
+                                warn!("⚠️ 保存回滚参数失败: {:?}", e);
+                            }
+
+                            // 回滚后需要重新创建网格，距上次整网重建过近时等下次重建再按回滚参数挂单
+                            if let Some(elapsed) = grid_state.rebuild_too_soon(grid_config, now) {
+                                info!(
+                                    "⏳ 距上次整网重建仅{}秒（最小间隔{}秒），回滚参数在下次重建时生效",
+                                    elapsed, grid_config.min_rebuild_interval_secs
+                                );
+                            } else {
+                                info!("🔄 参数回滚后重新创建网格");
+                                cancel_all_orders(
+                                    &exchange_client,
+                                    &mut active_orders,
+                                    &grid_config.trading_asset,
+                                )
+                                .await?;
+                                buy_orders.clear();
+                                sell_orders.clear();
+                                grid_state.last_price_update = now;
+                            }
+                        } else {
+                            // 定期保存当前参数状态
+                            if let Err(e) = grid_state.dynamic_params.save_to_file(
+                                &state_file_path(grid_config, "dynamic_grid_params.json"),
+                            ) {
+                                warn!("⚠️ 定期保存动态参数失败: {:?}", e);
+                            }
+                        }
+
+                        let report = generate_status_report(
+                            &grid_state,
+                            current_price,
+                            &buy_orders,
+                            &sell_orders,
+                            grid_config,
+                            &price_feed.status_line(connection_manager.last_data_received),
+                        );
+                        info!("\n{}", report);
+
+                        // 输出详细性能指标
+                        info!("📊 详细性能指标:");
+                        info!(
+                            "   总交易数: {} (胜: {}, 负: {})",
+                            grid_state.performance.metrics.total_trades,
+                            grid_state.performance.metrics.winning_trades,
+                            grid_state.performance.metrics.losing_trades
+                        );
+                        info!(
+                            "   胜率: {:.1}%, 利润因子: {:.2}, 夏普比率: {:.2}",
+                            grid_state.performance.metrics.win_rate * 100.0,
+                            grid_state.performance.metrics.profit_factor,
+                            grid_state.performance.metrics.sharpe_ratio
+                        );
+                        info!(
+                            "   总利润: {:.2}, 最大回撤: {:.2}%",
+                            grid_state.performance.metrics.total_profit,
+                            grid_state.performance.metrics.max_drawdown * 100.0
+                        );
+                        info!(
+                            "   平均盈利: {:.2}, 平均亏损: {:.2}",
+                            grid_state.performance.metrics.average_win,
+                            grid_state.performance.metrics.average_loss
+                        );
+                        info!(
+                            "   最大单笔盈利: {:.2}, 最大单笔亏损: {:.2}",
+                            grid_state.performance.metrics.largest_win,
+                            grid_state.performance.metrics.largest_loss
+                        );
+
+                        // 订单优先级管理状态报告
+                        let order_stats = order_manager.get_statistics_report();
+                        info!("📋 订单优先级管理状态:");
+                        for line in order_stats.lines() {
+                            info!("   {}", line);
+                        }
+
+                        // 批处理优化器状态报告
+                        let batch_report = batch_optimizer.get_performance_report();
+                        info!("⚡ 批处理优化器状态:");
+                        for line in batch_report.lines() {
+                            info!("   {}", line);
+                        }
+
+                        last_status_report = now;
+                    }
+                }
+            }
+
+            Some(Message::User(user_event)) => {
+                match user_event.data {
+                    UserData::Fills(fills) => {
+                        for fill in fills {
+                            if !fill_deduplicator.insert(fill.oid, &fill.hash) {
+                                debug!("⏭️ 重复的成交事件，跳过: ID={}", fill.oid);
+                                continue;
+                            }
+                            let fill_price: f64 = fill.px.parse().map_err(|e| {
+                                GridStrategyError::PriceParseError(format!(
+                                    "成交价格解析失败: {:?}",
+                                    e
+                                ))
+                            })?;
+                            let fill_size: f64 = fill.sz.parse().map_err(|e| {
+                                GridStrategyError::QuantityParseError(format!(
+                                    "成交数量解析失败: {:?}",
+                                    e
+                                ))
+                            })?;
+
+                            info!(
+                                "📋 订单成交: ID={}, 方向={}, 价格={}, 数量={}",
+                                fill.oid, fill.side, fill_price, fill_size
+                            );
+
+                            // 主动吃单（crossed）按 taker 费率，挂单成交按 maker 费率
+                            let fill_fee_rate = if fill.crossed {
+                                grid_config.taker_fee()
+                            } else {
+                                grid_config.maker_fee()
+                            };
+
+                            // 按滑点价下的单：对比预期价和实际成交价，最近的平均滑点超过容忍度时告警
+                            if let Some(expected_price) = fill
+                                .cloid
+                                .as_deref()
+                                .and_then(|cloid| grid_state.expected_fill_prices.get(cloid))
+                            {
+                                let slippage = grid_state.slippage.record(
+                                    fill.side == "B",
+                                    expected_price,
+                                    fill_price,
+                                    fill_size,
+                                );
+                                info!(
+                                    "📐 滑点单成交 - 预期价: {:.4}, 成交价: {:.4}, 滑点: {:.3}%, 累计滑点成本: {:.4}",
+                                    expected_price,
+                                    fill_price,
+                                    slippage * 100.0,
+                                    grid_state.slippage.total_cost()
+                                );
+                                if let Some(recent) = grid_state.slippage.recent_average() {
+                                    let now_secs = safe_unix_timestamp();
+                                    if recent > grid_config.slippage_tolerance
+                                        && now_secs.saturating_sub(last_slippage_alert)
+                                            >= SLIPPAGE_ALERT_INTERVAL_SECS
+                                    {
+                                        last_slippage_alert = now_secs;
+                                        let message = format!(
+                                            "{} 最近滑点单平均滑点 {:.3}% 高于 slippage_tolerance {:.3}%，配置偏乐观，建议调大滑点容忍度",
+                                            grid_config.trading_asset,
+                                            recent * 100.0,
+                                            grid_config.slippage_tolerance * 100.0
+                                        );
+                                        warn!("⚠️ {}", message);
+                                        alert_manager.alert(Severity::Medium, &message).await;
+                                    }
+                                }
+                            }
+
+                            // 下单回报即为 Filled 的订单已在下单时结算，这里不再重复计入持仓和利润
+                            if grid_state.skip_settled_fill(fill.oid, fill_size) {
+                                debug!("⏭️ 订单{}已在下单时按成交回报结算，跳过", fill.oid);
+                                continue;
+                            }
+
+                            // 优先按交易所订单ID匹配本地订单，找不到时按 cloid 匹配
+                            let local_oid = resolve_fill_order_id(
+                                if fill.side == "B" {
+                                    &buy_orders
+                                } else {
+                                    &sell_orders
+                                },
+                                fill.oid,
+                                fill.cloid.as_deref(),
+                            );
+
+                            // 部分成交后订单仍有剩余挂单
+                            let mut order_still_open = false;
+
+                            // 更新持仓信息
+                            if fill.side == "B" {
+                                // 买单成交，更新持仓
+                                let buy_value = fill_price * fill_size;
+                                let total_value = grid_state.position_avg_price
+                                    * grid_state.position_quantity
+                                    + buy_value;
+                                // 返佣以USDC结算，不增加持仓数量
+                                let net_size = fill_size * (1.0 - fill_fee_rate.max(0.0));
+                                grid_state.position_quantity += net_size;
+                                grid_state.record_position_lot(fill_price, net_size);
+
+                                if grid_state.position_quantity > 0.0 {
+                                    grid_state.position_avg_price =
+                                        total_value / grid_state.position_quantity;
+                                }
+
+                                // 部分成交时订单保留在挂单列表中，按实际成交量对冲
+                                if let Some(order) = buy_orders.get_mut(&local_oid) {
+                                    let first_fill = order.filled_quantity <= 0.0;
+                                    let completed =
+                                        record_order_fill(order, fill_size, grid_config);
+                                    let hedge_size = if order.iceberg.is_none() {
+                                        take_hedge_quantity(
+                                            order,
+                                            fill_size,
+                                            fill_price,
+                                            completed,
+                                            grid_config,
+                                        )
+                                    } else {
+                                        0.0
+                                    };
+                                    let order_info = order.clone();
+                                    if completed {
+                                        buy_orders.remove(&local_oid);
+                                    } else {
+                                        order_still_open = true;
+                                        info!(
+                                            "🧩 买单部分成交 - ID={}, 已成交: {:.4}/{:.4}, 剩余部分继续挂单",
+                                            local_oid, order_info.filled_quantity, order_info.quantity
+                                        );
+                                    }
+                                    if first_fill {
+                                        grid_state.record_fill_latency(&order_info, fill.time);
+                                    }
+
+                                    // 验证订单信息
+                                    if (order_info.price - fill_price).abs() > fill_price * 0.001 {
+                                        warn!(
+                                            "⚠️ 订单价格不匹配: 预期 {:.4}, 实际 {:.4}",
+                                            order_info.price, fill_price
+                                        );
+                                    }
+
+                                    // 使用潜在卖出价格进行利润预测
+                                    if let Some(potential_price) = order_info.potential_sell_price {
+                                        let expected_profit = fill_price
+                                            * (1.0 + grid_config.maker_fee())
+                                            * fill_size
+                                            * calculate_expected_profit_rate(
+                                                fill_price,
+                                                potential_price,
+                                                grid_config.maker_fee(),
+                                                grid_state.funding_cost(grid_config),
+                                            );
+                                        info!(
+                                            "💡 预期利润: {:.2} (潜在卖价: {:.4})",
+                                            expected_profit, potential_price
+                                        );
+                                    }
+
+                                    // 冰山单整组成交完才挂对冲单和重建单；普通订单按成交量对冲，全部成交后重建
+                                    let quantities = if order_info.iceberg.is_some() {
+                                        settle_iceberg_fill(&mut buy_orders, &order_info, fill_size)
+                                            .map(|group_size| FillQuantities {
+                                                hedge: group_size,
+                                                rebuild: group_size,
+                                                partial: false,
+                                            })
+                                    } else if hedge_size > 0.0 || completed {
+                                        Some(FillQuantities {
+                                            hedge: hedge_size,
+                                            rebuild: if completed {
+                                                order_info.filled_quantity
+                                            } else {
+                                                0.0
+                                            },
+                                            partial: !completed
+                                                || order_info.filled_quantity > fill_size,
+                                        })
+                                    } else {
+                                        None
+                                    };
+                                    if let Some(quantities) = quantities {
+                                        if let Err(e) = handle_buy_fill(
+                                            &exchange_client,
+                                            grid_config,
+                                            fill_price,
+                                            quantities,
+                                            grid_config.min_grid_spacing,
+                                            trading_paused(
+                                                &grid_state,
+                                                grid_config,
+                                                stop_trading_flag.load(Ordering::SeqCst),
+                                                Utc::now(),
+                                            ),
+                                            grid_state.position_quantity,
+                                            &mut active_orders,
+                                            &mut buy_orders,
+                                            &mut sell_orders,
+                                        )
+                                        .await
+                                        {
+                                            warn!("处理买单成交失败: {:?}", e);
+                                        }
+                                    }
+
+                                    info!("💰 买单成交处理完成 - 原始订单价格: {:.4}, 数量: {:.4}, 分配资金: {:.2}",
+                                        order_info.price, order_info.quantity, order_info.allocated_funds);
+                                } else {
+                                    warn!("⚠️ 未找到买单订单信息: ID={}", fill.oid);
+                                }
+                            } else {
+                                // 卖单成交，更新持仓和利润
+                                grid_state.position_quantity -= fill_size;
+
+                                // 计算利润，部分成交时订单保留在挂单列表中
+                                if let Some(order) = sell_orders.get_mut(&local_oid) {
+                                    let first_fill = order.filled_quantity <= 0.0;
+                                    let completed =
+                                        record_order_fill(order, fill_size, grid_config);
+                                    let order_info = order.clone();
+                                    if completed {
+                                        sell_orders.remove(&local_oid);
+                                    } else {
+                                        order_still_open = true;
+                                        info!(
+                                            "🧩 卖单部分成交 - ID={}, 已成交: {:.4}/{:.4}, 剩余部分继续挂单",
+                                            local_oid, order_info.filled_quantity, order_info.quantity
+                                        );
+                                    }
+                                    if first_fill {
+                                        grid_state.record_fill_latency(&order_info, fill.time);
+                                    }
+
+                                    let cost_price = order_info
+                                        .cost_price
+                                        .unwrap_or(grid_state.position_avg_price);
+                                    let sell_revenue =
+                                        fill_price * fill_size * (1.0 - fill_fee_rate);
+                                    let buy_cost = cost_price * fill_size;
+                                    let profit = sell_revenue - buy_cost;
+
+                                    grid_state.settle_realized_profit(
+                                        profit,
+                                        order_info.grid_pair,
+                                        &grid_config.profit_mode,
+                                    );
+
+                                    // 记录交易历史，手续费优先使用交易所返回的实际值
+                                    let fee = fill
+                                        .fee
+                                        .parse::<f64>()
+                                        .unwrap_or(fill_price * fill_size * fill_fee_rate);
+                                    let record = PerformanceRecord::new(
+                                        fill_price,
+                                        "SELL".to_string(),
+                                        profit,
+                                        // 可用资金要等成交后同步交易所余额才包含本笔卖出收入
+                                        grid_state.available_funds
+                                            + sell_revenue
+                                            + grid_state.position_quantity * fill_price,
+                                    )
+                                    .with_fill(
+                                        "sell",
+                                        fee,
+                                        Some(fill.oid),
+                                    );
+                                    grid_state.performance.add_trade(record.clone());
+
+                                    // 连续亏损熔断：达到上限时立即暂停开新仓并告警
+                                    if grid_state.record_trade_outcome(
+                                        profit,
+                                        grid_config.max_consecutive_losses,
+                                    ) {
+                                        let mut event = RiskEvent::new(
+                                            RiskEventType::ConsecutiveLosses,
+                                            format!(
+                                                "连续{}笔平仓亏损",
+                                                grid_state.consecutive_losses
+                                            ),
+                                            grid_state.consecutive_losses as f64,
+                                            grid_config.max_consecutive_losses as f64,
+                                        );
+                                        let action =
+                                            if grid_config.loss_circuit_cooldown_minutes > 0 {
+                                                format!(
+                                                    "暂停开新仓，{}分钟后自动恢复",
+                                                    grid_config.loss_circuit_cooldown_minutes
+                                                )
+                                            } else {
+                                                "暂停开新仓，等待 resume 指令恢复".to_string()
+                                            };
+                                        warn!("🧯 连续亏损熔断: {}，{}", event.description, action);
+                                        metrics_registry.record_risk_event(event.is_critical());
+                                        alert_manager
+                                            .alert(
+                                                Severity::from_level(event.severity),
+                                                &format!(
+                                                    "{} 风险事件: {} - {}，处理: {}",
+                                                    grid_config.trading_asset,
+                                                    event.event_type.as_str(),
+                                                    event.description,
+                                                    action
+                                                ),
+                                            )
+                                            .await;
+                                        event.mark_handled(action);
+                                        if let Err(e) = risk_event_log.append(&event) {
+                                            warn!("⚠️ 写入风险事件日志失败: {}", e);
+                                        }
+                                        risk_events.push(event);
+                                    }
+
+                                    // 输出交易记录详情
+                                    info!("📝 交易记录 - 时间: {:?}, 动作: {}, 订单: {:?}, 价格: {:.4}, 利润: {:.2}, 手续费: {:.4}, 总资产: {:.2}",
+                                        record.timestamp.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+                                        record.action, record.order_id, record.price, record.profit, record.fee, record.total_capital);
+
+                                    info!("💰 卖单成交 - 成本价: {:.4}, 卖出价: {:.4}, 利润: {:.2}, 利润率: {:.2}%",
+                                        cost_price, fill_price, profit, (profit / buy_cost) * 100.0);
+
+                                    // 冰山单整组成交完、普通订单全部成交后才挂新买单和重建卖单
+                                    let settled = if order_info.iceberg.is_some() {
+                                        settle_iceberg_fill(
+                                            &mut sell_orders,
+                                            &order_info,
+                                            fill_size,
+                                        )
+                                    } else if completed {
+                                        Some(order_info.filled_quantity)
+                                    } else {
+                                        None
+                                    };
+                                    if let Some(group_size) = settled {
+                                        if let Err(e) = handle_sell_fill(
+                                            &exchange_client,
+                                            grid_config,
+                                            fill_price,
+                                            group_size,
+                                            Some(cost_price),
+                                            grid_config.min_grid_spacing,
+                                            grid_state.funding_cost(grid_config),
+                                            trading_paused(
+                                                &grid_state,
+                                                grid_config,
+                                                stop_trading_flag.load(Ordering::SeqCst),
+                                                Utc::now(),
+                                            ),
+                                            &mut active_orders,
+                                            &mut buy_orders,
+                                            &mut sell_orders,
+                                        )
+                                        .await
+                                        {
+                                            warn!("处理卖单成交失败: {:?}", e);
+                                        }
+                                    }
+                                }
+                            }
+
+                            // 全部成交后从活跃订单列表中移除
+                            if !order_still_open {
+                                active_orders.retain(|&x| x != fill.oid && x != local_oid);
+                            }
+                        }
+
+                        // 成交改变了余额和保证金占用，重新同步可用资金
+                        if let Err(e) = refresh_available_funds(
+                            exchange_client.as_ref(),
+                            &info_client,
+                            user_address,
+                            &mut grid_state,
+                        )
+                        .await
+                        {
+                            warn!("⚠️ 成交后同步可用资金失败: {:?}", e);
+                        }
+                    }
+                    UserData::Funding(funding) if funding.coin == grid_config.trading_asset => {
+                        // usdc 为账户收到的资金费，付费时为负
+                        let received = funding.usdc.parse::<f64>().unwrap_or(0.0);
+                        grid_state.funding_paid -= received;
+                        if let Ok(rate) = funding.funding_rate.parse::<f64>() {
+                            grid_state.funding_rate = rate;
+                        }
+                        info!(
+                            "💸 资金费结算 - 持仓: {}, 费率: {}, 本次{}: {:.4}, 累计已付: {:.4}",
+                            funding.szi,
+                            funding.funding_rate,
+                            if received < 0.0 { "支付" } else { "收取" },
+                            received.abs(),
+                            grid_state.funding_paid
+                        );
+                    }
+                    _ => {
+                        // 处理其他用户事件
+                    }
+                }
+            }
+
+            Some(Message::Candle(candle)) => {
+                if candle.data.coin == grid_config.trading_asset {
+                    if let Some(kline) = Kline::from_candle(&candle.data) {
+                        grid_state.klines.update(&candle.data.interval, kline);
+                    }
+                }
+                // K线推送与价格推送独立，更新缓存后直接处理下一条消息
+                continue;
+            }
+
+            Some(Message::L2Book(l2_book)) => {
+                if l2_book.data.coin == grid_config.trading_asset {
+                    grid_state.order_book.update(&l2_book.data);
+                }
+                // 盘口推送频繁，更新快照后直接处理下一条消息
+                continue;
+            }
+
+            Some(Message::NoData) if replay.is_some() => {
+                // 回放任务推送完所有录制消息后发送的结束标记
+                info!("⏹️ 行情回放结束");
+                break;
+            }
+
+            Some(_) => {
+                // 处理其他类型的消息
+                continue;
+            }
+
+            None => {
+                warn!("⚠️ 消息通道已关闭");
+                break;
+            }
+        }
+
+        // 等待下一次检查
+        tokio::select! {
+            _ = sleep(Duration::from_secs(grid_config.check_interval)) => {},
+            _ = cancellation_token.cancelled() => {
+                info!("🔔 收到取消信号，退出主循环");
+                break;
+            }
+        }
+    }
+
+    // 执行安全退出流程 - 无论退出原因如何都需要取消订单
+    info!("🏁 开始策略安全退出流程");
+    let current_price = last_price.unwrap_or(0.0);
+
+    // 确定退出原因：优先使用循环内记录的原因
+    let shutdown_reason = shutdown_reason.unwrap_or(if shutdown_flag.load(Ordering::SeqCst) {
+        ShutdownReason::UserSignal
+    } else {
+        ShutdownReason::NormalExit
+    });
+
+    if let Err(e) = safe_shutdown(
+        &exchange_client,
+        grid_config,
+        &mut grid_state,
+        &mut active_orders,
+        &mut buy_orders,
+        &mut sell_orders,
+        current_price,
+        shutdown_reason,
+        start_time,
+        &alert_manager,
+    )
+    .await
+    {
+        error!("❌ 安全退出过程中发生错误: {:?}", e);
+
+        // 如果安全退出失败，至少尝试取消所有订单
+        if !active_orders.is_empty() {
+            warn!("⚠️ 安全退出失败，尝试紧急取消所有订单");
+
+            if let Err(cancel_err) =
+                cancel_all_orders(&exchange_client, &mut active_orders, "FARTCOIN").await
+            {
+                error!("❌ 紧急取消订单也失败: {:?}", cancel_err);
+                error!("🚨 请手动在交易所界面取消剩余订单!");
+
+                // 输出剩余订单ID供手动取消
+                if !active_orders.is_empty() {
+                    error!("📝 剩余未取消订单ID: {:?}", active_orders);
+                }
+            } else {
+                info!("✅ 紧急订单取消成功");
+                active_orders.clear();
+                buy_orders.clear();
+                sell_orders.clear();
+            }
+        }
+    }
+
+    info!("🏁 网格交易策略已安全结束");
+    Ok(())
+}
+
+// 安全解析字符串为f64，支持空值和无效值处理
+fn safe_parse_f64(
+    value: &str,
+    field_name: &str,
+    default_value: f64,
+) -> Result<f64, GridStrategyError> {
+    // 处理空字符串或仅包含空白字符的情况
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        warn!(
+            "⚠️ 字段 '{}' 为空，使用默认值: {}",
+            field_name, default_value
+        );
+        return Ok(default_value);
+    }
+
+    // 尝试解析数值
+    match trimmed.parse::<f64>() {
+        Ok(parsed_value) => {
+            // 检查是否为有效数值（非NaN、非无穷大）
+            if parsed_value.is_finite() && parsed_value >= 0.0 {
+                Ok(parsed_value)
+            } else {
+                warn!(
+                    "⚠️ 字段 '{}' 包含无效数值: {}，使用默认值: {}",
+                    field_name, parsed_value, default_value
+                );
+                Ok(default_value)
+            }
+        }
+        Err(e) => {
+            warn!(
+                "⚠️ 字段 '{}' 解析失败: '{}' -> {:?}，使用默认值: {}",
+                field_name, trimmed, e, default_value
+            );
+            Ok(default_value)
+        }
+    }
+}
+
+// 安全退出函数
+async fn safe_shutdown(
+    exchange_client: &dyn OrderExecutor,
+    grid_config: &crate::config::GridConfig,
+    grid_state: &mut GridState,
+    active_orders: &mut Vec<u64>,
+    buy_orders: &mut HashMap<u64, OrderInfo>,
+    sell_orders: &mut HashMap<u64, OrderInfo>,
+    current_price: f64,
+    reason: ShutdownReason,
+    start_time: SystemTime,
+    alert_manager: &AlertManager,
+) -> Result<(), GridStrategyError> {
+    tr_log!(
+        info,
+        "🛑 开始安全退出 - 原因: {}",
+        "🛑 Starting safe shutdown - reason: {}",
+        reason.localized()
+    );
+
+    alert_manager
+        .alert(
+            Severity::from_level(reason.severity_level()),
+            &format!(
+                "{} 策略开始安全退出 - 原因: {}, 当前价格: {:.4}, 持仓: {:.4}, 活跃订单: {}",
+                grid_config.trading_asset,
+                reason.as_str(),
+                current_price,
+                grid_state.position_quantity,
+                active_orders.len()
+            ),
+        )
+        .await;
+
+    let shutdown_start = SystemTime::now();
+
+    // 1. 取消所有未成交订单
+    if !active_orders.is_empty() {
+        info!("🗑️ 取消所有活跃订单 ({} 个)...", active_orders.len());
+
+        // 紧急情况下使用更短的超时时间
+        let cancel_timeout = if reason.is_emergency() {
+            Duration::from_secs(10)
+        } else {
+            Duration::from_secs(30)
+        };
+
+        let cancel_result = tokio::time::timeout(
+            cancel_timeout,
+            cancel_all_orders(exchange_client, active_orders, &grid_config.trading_asset),
+        )
+        .await;
+
+        match cancel_result {
+            Ok(Ok(_)) => {
+                info!("✅ 所有订单已成功取消");
+                buy_orders.clear();
+                sell_orders.clear();
+            }
+            Ok(Err(e)) => {
+                warn!("⚠️ 部分订单取消失败: {:?}", e);
+            }
+            Err(_) => {
+                warn!("⚠️ 订单取消超时，继续执行后续步骤");
+            }
+        }
+    }
+
+    // 2. 根据退出原因和配置决定是否清仓：止损、保证金不足和紧急关闭必须清仓，
+    //    其余原因按 close_positions_on_exit 配置处理
+    let should_close_positions =
+        reason.requires_position_close() || grid_config.close_positions_on_exit;
+
+    if should_close_positions && grid_state.position_quantity > 0.0 {
+        info!(
+            "📉 执行清仓操作 - 持仓数量: {:.4}",
+            grid_state.position_quantity
+        );
+
+        let close_timeout = if reason.is_emergency() {
+            Duration::from_secs(15)
+        } else {
+            Duration::from_secs(60)
+        };
+
+        let close_result = tokio::time::timeout(
+            close_timeout,
+            close_all_positions(
+                exchange_client,
+                grid_config,
+                &grid_state.order_book,
+                &mut grid_state.expected_fill_prices,
+                grid_state.position_quantity,
+                0.0, // 假设只有多头持仓
+                current_price,
+            ),
+        )
+        .await;
+
+        match close_result {
+            Ok(Ok((remaining_long, _))) if remaining_long > 0.0 => {
+                error!(
+                    "🚨 清仓未完成，退出时仍有未平持仓: {:.4}，请手动处理",
+                    remaining_long
+                );
+                grid_state.position_quantity = remaining_long;
+            }
+            Ok(Ok(_)) => {
+                info!("✅ 清仓操作完成");
+                grid_state.position_quantity = 0.0;
+                grid_state.position_avg_price = 0.0;
+            }
+            Ok(Err(e)) => {
+                error!("❌ 清仓操作失败: {:?}", e);
+                // 记录失败但继续执行后续步骤
+            }
+            Err(_) => {
+                error!("❌ 清仓操作超时");
+            }
+        }
+    } else if grid_state.position_quantity > 0.0 {
+        warn!(
+            "⚠️ 退出时仍有持仓 {:.4}，根据配置未执行清仓",
+            grid_state.position_quantity
+        );
+    }
+
+    // 3. 保存性能数据和状态
+    info!("💾 保存性能数据和状态...");
+
+    if let Err(e) = save_performance_data(grid_state, start_time, reason.clone()).await {
+        warn!("⚠️ 保存性能数据失败: {:?}", e);
+    }
+
+    // 4. 保存动态参数
+    if let Err(e) = grid_state
+        .dynamic_params
+        .save_to_file(&state_file_path(grid_config, "dynamic_grid_params.json"))
+    {
+        warn!("⚠️ 保存动态参数失败: {:?}", e);
+    }
+
+    // 5. 生成最终报告
+    let final_report = generate_final_report(grid_state, current_price, start_time, reason.clone());
+    info!("\n{}", final_report);
+
+    let shutdown_duration = shutdown_start.elapsed().unwrap_or_default();
+    info!(
+        "✅ 安全退出完成 - 耗时: {:.2}秒",
+        shutdown_duration.as_secs_f64()
+    );
+
+    Ok(())
+}
+
+// 保存性能数据
+async fn save_performance_data(
+    grid_state: &GridState,
+    start_time: SystemTime,
+    reason: ShutdownReason,
+) -> Result<(), GridStrategyError> {
+    let current_time = SystemTime::now();
+    let trading_duration = current_time.duration_since(start_time).unwrap_or_default();
+
+    // 计算最终性能指标
+    let final_metrics = grid_state
+        .performance
+        .compute_metrics(grid_state.total_capital, grid_state.realized_profit);
+
+    let snapshot = PerformanceSnapshot::from_metrics(
+        &final_metrics,
+        grid_state.total_capital,
+        grid_state.available_funds,
+        grid_state.position_quantity,
+        grid_state.position_avg_price,
+        grid_state.realized_profit,
+        trading_duration.as_secs_f64() / 3600.0,
+        grid_state.total_capital, // 使用当前总资金作为初始资金
+    );
+
+    // 保存到文件
+    let filename = format!("performance_snapshot_{}.json", safe_unix_timestamp());
+
+    match serde_json::to_string_pretty(&snapshot) {
+        Ok(json_data) => {
+            match std::fs::write(&filename, json_data) {
+                Ok(_) => {
+                    info!("💾 性能快照已保存到: {}", filename);
+
+                    // 同时保存详细的交易历史
+                    save_trading_history(grid_state, reason).await?;
+                }
+                Err(e) => {
+                    return Err(GridStrategyError::ConfigError(format!(
+                        "保存性能快照失败: {:?}",
+                        e
+                    )));
+                }
+            }
+        }
+        Err(e) => {
+            return Err(GridStrategyError::ConfigError(format!(
+                "序列化性能数据失败: {:?}",
+                e
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+// 交易历史导出中滚动夏普比率的窗口（成交笔数）
+const ROLLING_SHARPE_WINDOW: usize = 20;
+
+// 保存交易历史
+async fn save_trading_history(
+    grid_state: &GridState,
+    reason: ShutdownReason,
+) -> Result<(), GridStrategyError> {
+    if grid_state.performance.records.is_empty() {
+        return Ok(());
+    }
+
+    let filename = format!("trading_history_{}.json", safe_unix_timestamp());
+
+    #[derive(serde::Serialize)]
+    struct TradingHistoryExport {
+        shutdown_reason: String,
+        export_time: u64,
+        total_trades: usize,
+        trades: Vec<PerformanceRecord>,
+        equity_curve: Vec<EquityPoint>,
+        drawdown_series: Vec<DrawdownPoint>,
+        rolling_sharpe: Vec<RollingSharpePoint>,
+    }
+
+    let export_data = TradingHistoryExport {
+        shutdown_reason: reason.as_str().to_string(),
+        export_time: safe_unix_timestamp(),
+        total_trades: grid_state.performance.records.len(),
+        trades: grid_state.performance.records.clone(),
+        equity_curve: grid_state.performance.equity_curve(),
+        drawdown_series: grid_state
+            .performance
+            .drawdown_series(grid_state.total_capital),
+        rolling_sharpe: grid_state.performance.rolling_sharpe(ROLLING_SHARPE_WINDOW),
+    };
+
+    match serde_json::to_string_pretty(&export_data) {
+        Ok(json_data) => match std::fs::write(&filename, json_data) {
+            Ok(_) => {
+                info!(
+                    "📊 交易历史已保存到: {} ({} 笔交易)",
+                    filename,
+                    grid_state.performance.records.len()
+                );
+            }
+            Err(e) => {
+                warn!("⚠️ 保存交易历史失败: {:?}", e);
+            }
+        },
+        Err(e) => {
+            warn!("⚠️ 序列化交易历史失败: {:?}", e);
+        }
+    }
+
+    Ok(())
+}
+
+// 生成最终报告
+fn generate_final_report(
+    grid_state: &GridState,
+    current_price: f64,
+    start_time: SystemTime,
+    reason: ShutdownReason,
+) -> String {
+    let current_time = SystemTime::now();
+    let trading_duration = current_time.duration_since(start_time).unwrap_or_default();
+    let final_metrics = grid_state
+        .performance
+        .compute_metrics(grid_state.total_capital, grid_state.realized_profit);
+
+    let final_total_value =
+        grid_state.available_funds + grid_state.position_quantity * current_price;
+    let total_return = final_total_value - grid_state.total_capital;
+    let roi = if grid_state.total_capital > 0.0 {
+        (final_total_value / grid_state.total_capital - 1.0) * 100.0
+    } else {
+        0.0
+    };
+
+    let annualized_return = if trading_duration.as_secs() > 0 {
+        let years = trading_duration.as_secs_f64() / (365.25 * 24.0 * 3600.0);
+        if years > 0.0 {
+            ((final_total_value / grid_state.total_capital).powf(1.0 / years) - 1.0) * 100.0
+        } else {
+            0.0
+        }
+    } else {
+        0.0
+    };
+
+    format!(
+        "===== 网格交易策略最终报告 =====\n\
+        退出原因: {}\n\
+        退出时间: {}\n\
+        运行时长: {:.2} 小时\n\
+        \n\
+        === 资金状况 ===\n\
+        初始资金: {:.2}\n\
+        最终资产: {:.2}\n\
+        绝对收益: {:.2}\n\
+        投资回报率: {:.2}%\n\
+        年化收益率: {:.2}%\n\
+        已实现利润: {:.2}\n\
+        网格利润: {:.2}\n\
+        持仓平仓盈亏: {:.2}\n\
+        \n\
+        === 持仓状况 ===\n\
+        当前价格: {:.4}\n\
+        持仓数量: {:.4}\n\
+        持仓均价: {:.4}\n\
+        持仓价值: {:.2}\n\
+        可用资金: {:.2}\n\
+        \n\
+        === 交易统计 ===\n\
+        总交易数: {}\n\
+        盈利交易: {}\n\
+        亏损交易: {}\n\
+        胜率: {:.1}%\n\
+        利润因子: {:.2}\n\
+        夏普比率: {:.2}\n\
+        最大回撤: {:.2}%\n\
+        平均盈利: {:.2}\n\
+        平均亏损: {:.2}\n\
+        最大单笔盈利: {:.2}\n\
+        最大单笔亏损: {:.2}\n\
+        滑点单成交滑点: {}\n\
+        \n\
+        === 风险指标 ===\n\
+        最大回撤: {:.2}%\n\
+        波动率: {:.2}%\n\
+        风险调整收益: {:.2}\n\
+        \n\
+        === 策略参数 ===\n\
+        当前最小网格间距: {:.4}%\n\
+        当前最大网格间距: {:.4}%\n\
+        当前交易金额: {:.2}\n\
+        参数优化次数: {}\n\
+        \n\
+        ==============================",
+        reason.as_str(),
+        format!("{:?}", safe_unix_timestamp()),
+        trading_duration.as_secs_f64() / 3600.0,
+        grid_state.total_capital,
+        final_total_value,
+        total_return,
+        roi,
+        annualized_return,
+        grid_state.realized_profit,
+        grid_state.grid_profit,
+        grid_state.position_pnl,
+        current_price,
+        grid_state.position_quantity,
+        grid_state.position_avg_price,
+        grid_state.position_quantity * current_price,
+        grid_state.available_funds,
+        final_metrics.total_trades,
+        final_metrics.winning_trades,
+        final_metrics.losing_trades,
+        final_metrics.win_rate * 100.0,
+        final_metrics.profit_factor,
+        final_metrics.sharpe_ratio,
+        final_metrics.max_drawdown * 100.0,
+        final_metrics.average_win,
+        final_metrics.average_loss,
+        final_metrics.largest_win,
+        final_metrics.largest_loss,
+        grid_state.slippage.describe(),
+        final_metrics.max_drawdown * 100.0,
+        grid_state.historical_volatility * 100.0,
+        if final_metrics.max_drawdown > 0.0 {
+            roi / (final_metrics.max_drawdown * 100.0)
+        } else {
+            0.0
+        },
+        grid_state.dynamic_params.current_min_spacing * 100.0,
+        grid_state.dynamic_params.current_max_spacing * 100.0,
+        grid_state.dynamic_params.current_trade_amount,
+        grid_state.dynamic_params.optimization_count,
+    )
+}
+
+// 设置信号处理：Unix 下同时捕获 SIGINT 和 SIGTERM（docker stop），Windows 下捕获 Ctrl+C
+//
+// 收到信号后设置退出标志并取消主循环，由主循环执行 safe_shutdown 撤单清仓；
+// 清仓超过 shutdown_timeout 或再次收到信号时强制退出进程，避免卡死。
+fn setup_signal_handler(shutdown_timeout: Duration) -> (Arc<AtomicBool>, CancellationToken) {
+    let shutdown_flag = Arc::new(AtomicBool::new(false));
+    let cancellation_token = CancellationToken::new();
+
+    let flag_clone = shutdown_flag.clone();
+    let token_clone = cancellation_token.clone();
+
+    tokio::spawn(async move {
+        let signal_name = wait_for_shutdown_signal().await;
+        info!("🔔 接收到 {} 信号，开始安全退出...", signal_name);
+
+        flag_clone.store(true, Ordering::SeqCst);
+        token_clone.cancel();
+
+        tokio::select! {
+            _ = sleep(shutdown_timeout) => {
+                error!(
+                    "🚨 安全退出超过{}秒仍未完成，强制退出，请检查交易所剩余挂单和持仓!",
+                    shutdown_timeout.as_secs()
+                );
+            }
+            signal_name = wait_for_shutdown_signal() => {
+                warn!("⚠️ 再次接收到 {} 信号，强制退出", signal_name);
+            }
+        }
+        std::process::exit(1);
+    });
+
+    (shutdown_flag, cancellation_token)
+}
+
+/// 监听 SIGUSR1，每收到一次把计数加一，由各账户主循环比较计数后安排重平衡
+///
+/// 非 Unix 平台没有 SIGUSR1，计数保持为0，只能通过触发文件或控制接口请求重平衡。
+fn setup_rebalance_signal_handler() -> Arc<AtomicU64> {
+    let counter = Arc::new(AtomicU64::new(0));
+
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::user_defined1()) {
+            Ok(mut sigusr1) => {
+                let counter = counter.clone();
+                tokio::spawn(async move {
+                    while sigusr1.recv().await.is_some() {
+                        info!("🔔 接收到 SIGUSR1 信号，请求重平衡网格");
+                        counter.fetch_add(1, Ordering::SeqCst);
+                    }
+                });
+            }
+            Err(e) => error!("❌ 注册 SIGUSR1 信号处理失败: {:?}", e),
+        }
+    }
+
+    counter
+}
+
+/// 检查是否有新的手动重平衡请求，返回请求来源
+///
+/// 信号计数比上次看到的大即为新请求；触发文件存在时先删除再返回，删除失败时不触发，避免每轮重复重平衡。
+fn take_manual_rebalance_request(
+    rebalance_signal: &AtomicU64,
+    seen_signals: &mut u64,
+    grid_config: &crate::config::GridConfig,
+) -> Option<&'static str> {
+    let signals = rebalance_signal.load(Ordering::SeqCst);
+    if signals != *seen_signals {
+        *seen_signals = signals;
+        return Some("SIGUSR1");
+    }
+
+    let trigger_file = grid_config.rebalance_trigger_file.trim();
+    if trigger_file.is_empty() {
+        return None;
+    }
+    let path = state_file_path(grid_config, trigger_file);
+    if !std::path::Path::new(&path).exists() {
+        return None;
+    }
+    match std::fs::remove_file(&path) {
+        Ok(()) => Some("触发文件"),
+        Err(e) => {
+            warn!("⚠️ 删除重平衡触发文件 {} 失败，忽略本次请求: {:?}", path, e);
+            None
+        }
+    }
+}
+
+/// 等待退出信号，返回信号名称
+async fn wait_for_shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match (
+            signal(SignalKind::interrupt()),
+            signal(SignalKind::terminate()),
+        ) {
+            (Ok(mut sigint), Ok(mut sigterm)) => {
+                tokio::select! {
+                    _ = sigint.recv() => "SIGINT",
+                    _ = sigterm.recv() => "SIGTERM",
+                }
+            }
+            (sigint, sigterm) => {
+                if let Err(e) = sigint {
+                    error!("❌ 注册 SIGINT 信号处理失败: {:?}", e);
+                }
+                if let Err(e) = sigterm {
+                    error!("❌ 注册 SIGTERM 信号处理失败: {:?}", e);
+                }
+                wait_for_ctrl_c().await
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        wait_for_ctrl_c().await
+    }
+}
+
+async fn wait_for_ctrl_c() -> &'static str {
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        error!("❌ 注册 Ctrl+C 信号处理失败: {:?}，无法响应退出信号", e);
+        std::future::pending::<()>().await;
+    }
+    "Ctrl+C"
+}
+
+// ===== 状态持久化与恢复功能 =====
+
+/// 保存网格状态到文件（配置了远程后端时同时上传）
+async fn save_grid_state(grid_state: &GridState, file_path: &str) -> Result<(), GridStrategyError> {
+    let serialized = serde_json::to_string_pretty(grid_state)
+        .map_err(|e| GridStrategyError::ConfigError(format!("序列化状态失败: {:?}", e)))?;
+
+    save_state_snapshot(file_path, &serialized).await?;
+
+    info!("✅ 网格状态已保存到: {}", file_path);
+    Ok(())
+}
+
+/// 从文件加载网格状态，本地不存在时尝试从远程后端恢复
+async fn load_grid_state(file_path: &str) -> Result<Option<GridState>, GridStrategyError> {
+    match load_state_snapshot(file_path).await? {
+        Some(contents) => {
+            let grid_state = serde_json::from_str(&contents).map_err(|e| {
+                GridStrategyError::ConfigError(format!("解析状态文件失败: {:?}", e))
+            })?;
+
+            info!("✅ 成功加载网格状态");
+            Ok(Some(grid_state))
+        }
+        None => {
+            info!("📄 未找到状态文件，将使用默认设置");
+            Ok(None)
+        }
+    }
+}
+
+/// 保存订单状态到文件（配置了远程后端时同时上传）
+async fn save_orders_state(
+    active_orders: &[u64],
+    buy_orders: &HashMap<u64, OrderInfo>,
+    sell_orders: &HashMap<u64, OrderInfo>,
+    file_path: &str,
+) -> Result<(), GridStrategyError> {
+    #[derive(serde::Serialize)]
+    struct OrdersState {
+        active_orders: Vec<u64>,
+        buy_orders: HashMap<u64, OrderInfo>,
+        sell_orders: HashMap<u64, OrderInfo>,
+        save_time: u64,
+    }
+
+    let orders_state = OrdersState {
+        active_orders: active_orders.to_vec(),
+        buy_orders: buy_orders.clone(),
+        sell_orders: sell_orders.clone(),
+        save_time: safe_unix_timestamp(),
+    };
+
+    let serialized = serde_json::to_string_pretty(&orders_state)
+        .map_err(|e| GridStrategyError::ConfigError(format!("序列化订单状态失败: {:?}", e)))?;
+
+    save_state_snapshot(file_path, &serialized).await?;
+
+    info!(
+        "✅ 订单状态已保存到: {} (活跃订单: {}, 买单: {}, 卖单: {})",
+        file_path,
+        active_orders.len(),
+        buy_orders.len(),
+        sell_orders.len()
+    );
+    Ok(())
+}
+
+/// 从文件加载订单状态，本地不存在时尝试从远程后端恢复
+async fn load_orders_state(
+    file_path: &str,
+) -> Result<Option<(Vec<u64>, HashMap<u64, OrderInfo>, HashMap<u64, OrderInfo>)>, GridStrategyError>
+{
+    #[derive(serde::Deserialize)]
+    struct OrdersState {
+        active_orders: Vec<u64>,
+        buy_orders: HashMap<u64, OrderInfo>,
+        sell_orders: HashMap<u64, OrderInfo>,
+        save_time: u64,
+    }
+
+    match load_state_snapshot(file_path).await? {
+        Some(contents) => {
+            let mut orders_state: OrdersState = serde_json::from_str(&contents).map_err(|e| {
+                GridStrategyError::ConfigError(format!("解析订单状态文件失败: {:?}", e))
+            })?;
+
+            // 检查状态文件的时效性（超过1小时的状态文件可能已过期）
+            let current_time = safe_unix_timestamp();
+            let state_age = current_time - orders_state.save_time;
+
+            if state_age > 3600 {
+                // 1小时
+                warn!(
+                    "⚠️ 订单状态文件已过期 ({:.1} 小时前)，将忽略",
+                    state_age as f64 / 3600.0
+                );
+                return Ok(None);
+            }
+
+            info!(
+                "✅ 成功加载订单状态 (活跃订单: {}, 买单: {}, 卖单: {})",
+                orders_state.active_orders.len(),
+                orders_state.buy_orders.len(),
+                orders_state.sell_orders.len()
+            );
+
+            // 旧版状态文件没有方向字段，按所在列表补全
+            for order in orders_state.buy_orders.values_mut() {
+                order.is_buy = true;
+            }
+            for order in orders_state.sell_orders.values_mut() {
+                order.is_buy = false;
+            }
+
+            Ok(Some((
+                orders_state.active_orders,
+                orders_state.buy_orders,
+                orders_state.sell_orders,
+            )))
+        }
+        None => {
+            info!("📄 未找到订单状态文件，将使用空状态");
+            Ok(None)
+        }
+    }
+}
+
+/// 定期保存状态（在主循环中调用）
+async fn periodic_state_save(
+    grid_config: &crate::config::GridConfig,
+    grid_state: &GridState,
+    active_orders: &[u64],
+    buy_orders: &HashMap<u64, OrderInfo>,
+    sell_orders: &HashMap<u64, OrderInfo>,
+    last_save_time: &mut SystemTime,
+    save_interval_seconds: u64,
+) -> Result<(), GridStrategyError> {
+    let now = SystemTime::now();
+
+    // 检查是否到了保存时间
+    if now
+        .duration_since(*last_save_time)
+        .unwrap_or_default()
+        .as_secs()
+        >= save_interval_seconds
+    {
+        // 保存网格状态
+        if let Err(e) =
+            save_grid_state(grid_state, &state_file_path(grid_config, "grid_state.json")).await
+        {
+            warn!("⚠️ 保存网格状态失败: {:?}", e);
+        }
+
+        // 保存订单状态
+        if let Err(e) = save_orders_state(
+            active_orders,
+            buy_orders,
+            sell_orders,
+            &state_file_path(grid_config, "orders_state.json"),
+        )
+        .await
+        {
+            warn!("⚠️ 保存订单状态失败: {:?}", e);
+        }
+
+        *last_save_time = now;
+        info!("💾 定期状态保存完成");
+    }
+
+    Ok(())
+}
+
+/// 验证加载的状态是否与当前配置兼容
+fn validate_loaded_state(
+    grid_state: &GridState,
+    grid_config: &crate::config::GridConfig,
+) -> Result<bool, GridStrategyError> {
+    let is_valid = true;
+    let mut warnings = Vec::new();
+
+    // 检查总资金是否合理 - 移除对配置文件total_capital的依赖
+    if grid_state.total_capital <= 0.0 {
+        warnings.push(format!(
+            "状态文件中的总资金无效: {:.2}",
+            grid_state.total_capital
+        ));
+    }
+
+    // 检查动态参数是否在合理范围内
+    if grid_state.dynamic_params.current_min_spacing < grid_config.min_grid_spacing * 0.5
+        || grid_state.dynamic_params.current_min_spacing > grid_config.max_grid_spacing
+    {
+        warnings.push(format!(
+            "最小网格间距超出范围: {:.4}% (配置范围: {:.4}%-{:.4}%)",
+            grid_state.dynamic_params.current_min_spacing * 100.0,
+            grid_config.min_grid_spacing * 100.0,
+            grid_config.max_grid_spacing * 100.0
+        ));
+    }
+
+    // 检查交易金额是否合理 - 移除对配置文件total_capital的依赖
+    if grid_state.dynamic_params.current_trade_amount > grid_state.total_capital * 0.5 {
+        warnings.push(format!(
+            "交易金额过大: {:.2} (总资金的{:.1}%)",
+            grid_state.dynamic_params.current_trade_amount,
+            grid_state.dynamic_params.current_trade_amount / grid_state.total_capital * 100.0
+        ));
+    }
+
+    // 输出警告信息
+    if !warnings.is_empty() {
+        warn!("⚠️ 加载的状态存在以下问题:");
+        for warning in warnings {
+            warn!("   - {}", warning);
+        }
+        warn!("建议检查状态文件或重新开始");
+    }
+
+    Ok(is_valid)
+}
+
+// 启动时备份的状态文件：(文件名前缀, 描述)
+const STATE_BACKUP_FILES: [(&str, &str); 3] = [
+    ("grid_state", "网格状态"),
+    ("orders_state", "订单状态"),
+    ("dynamic_grid_params", "动态参数"),
+];
+
+/// 备份文件名 `<前缀><名称>_backup_<Unix秒>.json` 中的时间戳，不是该状态文件的备份时返回 None
+fn backup_timestamp(file_name: &str, prefix: &str, name: &str) -> Option<u64> {
+    file_name
+        .strip_prefix(prefix)?
+        .strip_prefix(name)?
+        .strip_prefix("_backup_")?
+        .strip_suffix(".json")
+        .filter(|timestamp| !timestamp.is_empty() && timestamp.bytes().all(|b| b.is_ascii_digit()))?
+        .parse()
+        .ok()
+}
+
+/// 创建状态备份：每个存在的状态文件复制为 `<名称>_backup_<Unix秒>.json`
+fn backup_state_files(grid_config: &crate::config::GridConfig) -> Result<(), GridStrategyError> {
+    let timestamp = safe_unix_timestamp();
+
+    for (name, description) in &STATE_BACKUP_FILES {
+        let source = state_file_path(grid_config, &format!("{}.json", name));
+        if !std::path::Path::new(&source).exists() {
+            continue;
+        }
+        let backup_name =
+            state_file_path(grid_config, &format!("{}_backup_{}.json", name, timestamp));
+        if std::path::Path::new(&backup_name).exists() {
+            debug!("📋 {}本秒已有备份，跳过: {}", description, backup_name);
+            continue;
+        }
+        std::fs::copy(&source, &backup_name).map_err(|e| {
+            GridStrategyError::ConfigError(format!("备份{}失败: {:?}", description, e))
+        })?;
+        info!("📋 {}已备份到: {}", description, backup_name);
+    }
+
+    Ok(())
+}
+
+/// 清理过期的备份文件，返回删除的文件数
+///
+/// 按文件名中的备份时间戳（而不是文件修改时间）判断，早于 `max_backup_age_days` 天的备份才删除；
+/// 只匹配本实例前缀下 `STATE_BACKUP_FILES` 的备份，当前状态文件和其他实例的备份不受影响。
+fn cleanup_old_backups(
+    grid_config: &crate::config::GridConfig,
+    max_backup_age_days: u64,
+) -> Result<usize, GridStrategyError> {
+    let current_time = safe_unix_timestamp();
+    let max_age_seconds = max_backup_age_days * 24 * 60 * 60;
+    let state_dir = if grid_config.state_dir.is_empty() {
+        "."
+    } else {
+        grid_config.state_dir.as_str()
+    };
+
+    let entries = std::fs::read_dir(state_dir).map_err(|e| {
+        GridStrategyError::ConfigError(format!("读取状态目录 {} 失败: {:?}", state_dir, e))
+    })?;
+
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let file_name = entry.file_name();
+        let Some(file_name) = file_name.to_str() else {
+            continue;
+        };
+        let Some(timestamp) = STATE_BACKUP_FILES.iter().find_map(|(name, _)| {
+            backup_timestamp(file_name, &grid_config.state_file_prefix, name)
+        }) else {
+            continue;
+        };
+        // 时间戳晚于当前时间（时钟回拨）时按未过期处理
+        if current_time.saturating_sub(timestamp) <= max_age_seconds {
+            continue;
+        }
+        match std::fs::remove_file(entry.path()) {
+            Ok(()) => {
+                removed += 1;
+                info!("🗑️ 已删除过期备份文件: {}", file_name);
+            }
+            Err(e) => warn!("⚠️ 删除过期备份文件失败: {} - {:?}", file_name, e),
+        }
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 无历史状态时的初始网格状态，字段取值与启动时新建状态一致
+    pub(super) fn test_grid_state(grid_config: &crate::config::GridConfig) -> GridState {
+        GridState {
+            total_capital: 1000.0,
+            available_funds: 1000.0,
+            position_quantity: 0.0,
+            position_avg_price: 0.0,
+            realized_profit: 0.0,
+            grid_profit: 0.0,
+            position_pnl: 0.0,
+            highest_price_after_position: 0.0,
+            trailing_stop_price: 0.0,
+            stop_loss_status: StopLossStatus::Normal,
+            stop_loss_cooldown_until: 0,
+            last_rebalance_time: SystemTime::now(),
+            historical_volatility: 0.0,
+            performance: PerformanceAnalyzer::unbounded(),
+            last_margin_check: SystemTime::now(),
+            connection_retry_count: 0,
+            last_order_batch_time: SystemTime::now(),
+            dynamic_params: DynamicGridParams::new(grid_config),
+            last_price_update: SystemTime::now(),
+            last_grid_price: 0.0,
+            grid_center_price: 0.0,
+            order_update_threshold: grid_config.order_update_threshold,
+            max_order_age_minutes: grid_config.max_order_age_minutes,
+            adaptive_order_config: AdaptiveOrderConfig::new(),
+            indicators: IndicatorState::new(grid_config.history_length),
+            order_book: OrderBook::new(),
+            klines: KlineBuffer::new(&grid_config.kline_intervals, grid_config.kline_capacity),
+            market_state_tracker: MarketStateTracker::default(),
+            position_lots: VecDeque::new(),
+            fill_latency: FillLatencyStats::default(),
+            slippage: SlippageStats::default(),
+            expected_fill_prices: ExpectedFillPrices::default(),
+            settled_on_placement: HashMap::new(),
+            consecutive_losses: 0,
+            loss_circuit_tripped_at: 0,
+            funding_rate: 0.0,
+            funding_paid: 0.0,
+            profit_reserve: 0.0,
+            profit_withdrawn: 0.0,
+            unrealized_pnl: 0.0,
+        }
+    }
+
+    /// 内置默认配置中的网格参数，grid 下各模块的测试共用
+    pub(super) fn default_grid_config() -> crate::config::GridConfig {
+        toml::from_str::<crate::config::AppConfig>(crate::config::DEFAULT_CONFIG_TOML)
+            .expect("内置默认配置必须能解析")
+            .grid
+    }
+
+    /// 在系统临时目录下为每个测试建独立的状态目录
+    fn test_grid_config(test_name: &str) -> crate::config::GridConfig {
+        let state_dir = std::env::temp_dir().join(format!(
+            "taoli_grid_{}_{}_{}",
+            test_name,
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&state_dir).unwrap();
+
+        let mut grid_config = default_grid_config();
+        grid_config.state_dir = state_dir.to_string_lossy().into_owned();
+        grid_config.state_file_prefix = "t_".to_string();
+        grid_config
+    }
+
+    fn write_state_file(grid_config: &crate::config::GridConfig, file_name: &str, content: &str) {
+        let path = std::path::Path::new(&grid_config.state_dir).join(file_name);
+        std::fs::write(path, content).unwrap();
+    }
+
+    fn state_dir_files(grid_config: &crate::config::GridConfig) -> Vec<String> {
+        let mut files: Vec<String> = std::fs::read_dir(&grid_config.state_dir)
+            .unwrap()
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn trading_paused_combines_all_pause_conditions() {
+        use chrono::TimeZone;
+
+        let mut grid_config = default_grid_config();
+        grid_config.trading_windows.clear();
+        grid_config.loss_circuit_cooldown_minutes = 30;
+        let now = Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap();
+        let now_secs = now.timestamp() as u64;
+        let mut grid_state = test_grid_state(&grid_config);
+
+        assert!(!trading_paused(&grid_state, &grid_config, false, now));
+        // 风控、手动或时钟异常暂停标志
+        assert!(trading_paused(&grid_state, &grid_config, true, now));
+
+        // 止损冷却
+        grid_state.stop_loss_cooldown_until = now_secs + 60;
+        assert!(trading_paused(&grid_state, &grid_config, false, now));
+        grid_state.stop_loss_cooldown_until = now_secs;
+        assert!(!trading_paused(&grid_state, &grid_config, false, now));
+
+        // 连续亏损熔断：冷却期内暂停，冷却期为0时一直暂停
+        grid_state.loss_circuit_tripped_at = now_secs - 10 * 60;
+        assert!(trading_paused(&grid_state, &grid_config, false, now));
+        grid_state.loss_circuit_tripped_at = now_secs - 31 * 60;
+        assert!(!trading_paused(&grid_state, &grid_config, false, now));
+        grid_config.loss_circuit_cooldown_minutes = 0;
+        assert!(trading_paused(&grid_state, &grid_config, false, now));
+        grid_state.loss_circuit_tripped_at = 0;
+
+        // 交易时间窗口
+        grid_config.trading_windows = vec!["00:00-06:00".to_string()];
+        assert!(trading_paused(&grid_state, &grid_config, false, now));
+        grid_config.trading_windows = vec!["22:00-13:00".to_string()];
+        assert!(!trading_paused(&grid_state, &grid_config, false, now));
+    }
+
+    #[test]
+    fn rebuild_too_soon_respects_min_rebuild_interval() {
+        let mut grid_config = default_grid_config();
+        grid_config.min_rebuild_interval_secs = 60;
+        let mut grid_state = test_grid_state(&grid_config);
+        let rebuilt_at = SystemTime::now();
+        grid_state.last_price_update = rebuilt_at;
+
+        assert_eq!(
+            grid_state.rebuild_too_soon(&grid_config, rebuilt_at + Duration::from_secs(30)),
+            Some(30)
+        );
+        assert_eq!(
+            grid_state.rebuild_too_soon(&grid_config, rebuilt_at + Duration::from_secs(60)),
+            None
+        );
+        // 时钟回拨时按刚重建处理
+        assert_eq!(
+            grid_state.rebuild_too_soon(&grid_config, rebuilt_at - Duration::from_secs(5)),
+            Some(0)
+        );
+
+        grid_config.min_rebuild_interval_secs = 0;
+        assert_eq!(grid_state.rebuild_too_soon(&grid_config, rebuilt_at), None);
+    }
+
+    #[test]
+    fn backup_copies_existing_state_files_with_timestamp_suffix() {
+        let grid_config = test_grid_config("backup");
+        write_state_file(&grid_config, "t_grid_state.json", "{\"grid\":1}");
+        write_state_file(&grid_config, "t_orders_state.json", "{\"orders\":2}");
+
+        backup_state_files(&grid_config).unwrap();
+
+        let files = state_dir_files(&grid_config);
+        let grid_backups: Vec<&String> = files
+            .iter()
+            .filter(|file| backup_timestamp(file, "t_", "grid_state").is_some())
+            .collect();
+        let order_backups: Vec<&String> = files
+            .iter()
+            .filter(|file| backup_timestamp(file, "t_", "orders_state").is_some())
+            .collect();
+        assert_eq!(grid_backups.len(), 1);
+        assert_eq!(order_backups.len(), 1);
+        // 不存在的动态参数文件不生成备份
+        assert!(!files
+            .iter()
+            .any(|file| backup_timestamp(file, "t_", "dynamic_grid_params").is_some()));
+
+        let backup_content = std::fs::read_to_string(
+            std::path::Path::new(&grid_config.state_dir).join(grid_backups[0]),
+        )
+        .unwrap();
+        assert_eq!(backup_content, "{\"grid\":1}");
+
+        std::fs::remove_dir_all(&grid_config.state_dir).unwrap();
+    }
+
+    #[test]
+    fn cleanup_removes_only_expired_backups_of_this_instance() {
+        let grid_config = test_grid_config("cleanup");
+        let now = safe_unix_timestamp();
+        let expired = now - 8 * 24 * 60 * 60;
+        let recent = now - 24 * 60 * 60;
+
+        let expired_files = [
+            format!("t_grid_state_backup_{}.json", expired),
+            format!("t_orders_state_backup_{}.json", expired),
+            format!("t_dynamic_grid_params_backup_{}.json", expired),
+        ];
+        let kept_files = [
+            format!("t_grid_state_backup_{}.json", recent),
+            format!("t_orders_state_backup_{}.json", now),
+            // 当前状态文件
+            "t_grid_state.json".to_string(),
+            // 其他实例的过期备份
+            format!("other_grid_state_backup_{}.json", expired),
+            // 时间戳无法解析或不是状态备份的文件
+            "t_grid_state_backup_latest.json".to_string(),
+            format!("t_notes_backup_{}.json", expired),
+            "notes.txt".to_string(),
+        ];
+        for file in expired_files.iter().chain(kept_files.iter()) {
+            write_state_file(&grid_config, file, "{}");
+        }
+
+        assert_eq!(cleanup_old_backups(&grid_config, 7).unwrap(), 3);
+
+        let mut expected: Vec<String> = kept_files.to_vec();
+        expected.sort();
+        assert_eq!(state_dir_files(&grid_config), expected);
+
+        // 保留天数内的备份再次清理时不受影响
+        assert_eq!(cleanup_old_backups(&grid_config, 7).unwrap(), 0);
+
+        std::fs::remove_dir_all(&grid_config.state_dir).unwrap();
+    }
+
+    #[test]
+    fn immediate_buy_fill_updates_position_and_registers_order() {
+        let grid_config = default_grid_config();
+        let mut grid_state = test_grid_state(&grid_config);
+        let fee_rate = grid_config.taker_fee();
+
+        let profit = grid_state.settle_immediate_fill(7, true, 100.0, 2.0, None, &grid_config);
+
+        assert_eq!(profit, None);
+        let net_size = 2.0 * (1.0 - fee_rate);
+        assert!((grid_state.position_quantity - net_size).abs() < 1e-12);
+        assert!((grid_state.position_avg_price - 200.0 / net_size).abs() < 1e-9);
+        assert_eq!(grid_state.settled_on_placement.get(&7), Some(&2.0));
+    }
+
+    #[test]
+    fn immediate_sell_fill_settles_profit_at_taker_fee() {
+        let grid_config = default_grid_config();
+        let mut grid_state = test_grid_state(&grid_config);
+        grid_state.position_quantity = 3.0;
+        grid_state.position_avg_price = 100.0;
+        let fee_rate = grid_config.taker_fee();
+
+        let profit = grid_state
+            .settle_immediate_fill(8, false, 110.0, 1.0, Some(95.0), &grid_config)
+            .expect("卖出应返回利润");
+
+        let expected = 110.0 * (1.0 - fee_rate) - 95.0;
+        assert!((profit - expected).abs() < 1e-9);
+        assert!((grid_state.realized_profit - expected).abs() < 1e-9);
+        assert!((grid_state.position_quantity - 2.0).abs() < 1e-12);
+        assert_eq!(grid_state.settled_on_placement.get(&8), Some(&1.0));
+    }
+
+    #[test]
+    fn skip_settled_fill_consumes_partial_fills() {
+        let grid_config = default_grid_config();
+        let mut grid_state = test_grid_state(&grid_config);
+        grid_state.settle_immediate_fill(9, true, 100.0, 1.0, None, &grid_config);
+
+        assert!(!grid_state.skip_settled_fill(10, 1.0));
+        assert!(grid_state.skip_settled_fill(9, 0.4));
+        assert!(grid_state.settled_on_placement.contains_key(&9));
+        assert!(grid_state.skip_settled_fill(9, 0.6));
+        assert!(!grid_state.settled_on_placement.contains_key(&9));
+        // 登记已清除，同一订单之后的成交回报正常处理
+        assert!(!grid_state.skip_settled_fill(9, 0.1));
+    }
+
+    #[test]
+    fn settled_fills_survive_state_reload() {
+        let grid_config = default_grid_config();
+        let mut grid_state = test_grid_state(&grid_config);
+        grid_state.settle_immediate_fill(11, true, 100.0, 1.0, None, &grid_config);
+        let position_quantity = grid_state.position_quantity;
+
+        let json = serde_json::to_string(&grid_state).unwrap();
+        let mut restored: GridState = serde_json::from_str(&json).unwrap();
+
+        // 重启后才到达的成交回报仍按已结算跳过，持仓不会重复计入
+        assert!(restored.skip_settled_fill(11, 1.0));
+        assert!((restored.position_quantity - position_quantity).abs() < 1e-12);
+        assert!(!restored.skip_settled_fill(11, 1.0));
+    }
+}
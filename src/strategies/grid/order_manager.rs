@@ -0,0 +1,2570 @@
+// 订单管理：订单结构、优先级队列、冰山单拆分、批量下单与撤单
+
+use super::*;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(super) enum OrderStatus {
+    Pending,         // 待处理
+    Active,          // 活跃
+    Filled,          // 已成交
+    Cancelled,       // 已取消
+    Rejected,        // 被拒绝
+    PartiallyFilled, // 部分成交
+}
+
+// 增强的订单信息结构体
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(super) struct EnhancedOrderInfo {
+    pub(super) order_id: u64,
+    pub(super) price: f64,
+    pub(super) quantity: f64,
+    pub(super) filled_quantity: f64,
+    pub(super) cost_price: Option<f64>,
+    pub(super) potential_sell_price: Option<f64>,
+    pub(super) allocated_funds: f64,
+    pub(super) status: OrderStatus,
+    #[serde(with = "system_time_serde")]
+    pub(super) created_time: SystemTime,
+    #[serde(with = "system_time_serde")]
+    pub(super) last_update_time: SystemTime,
+    pub(super) retry_count: u32,
+}
+
+// 订单信息结构体
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(super) struct OrderInfo {
+    #[serde(default)]
+    pub(super) is_buy: bool, // 买卖方向，旧版状态文件没有该字段，加载时按所在列表补全
+    pub(super) price: f64,
+    pub(super) quantity: f64,
+    pub(super) cost_price: Option<f64>, // 对于卖单，记录对应的买入成本价
+    pub(super) potential_sell_price: Option<f64>, // 对于买单，记录潜在卖出价格
+    pub(super) allocated_funds: f64,    // 分配的资金
+    #[serde(default)]
+    pub(super) iceberg: Option<IcebergSlice>, // 冰山单子订单信息，普通订单为 None
+    #[serde(default)]
+    pub(super) created_at: u64, // 挂单时间（Unix秒），用于统计成交耗时，旧版状态文件为0
+    #[serde(default)]
+    pub(super) cloid: Option<Uuid>, // 客户端订单ID，同一逻辑订单重试时复用，旧版状态文件为 None
+    #[serde(default)]
+    pub(super) filled_quantity: f64, // 部分成交时的累计成交量，订单全部成交前保留在挂单列表中
+    #[serde(default)]
+    pub(super) unhedged_quantity: f64, // 已成交但还没挂出对冲单的数量，不足最小下单金额时先累积
+    #[serde(default)]
+    pub(super) grid_pair: bool, // 买单成交后挂出的配对对冲卖单，成交利润计入网格利润，其余卖单计入持仓盈亏
+}
+
+/// 一次买单成交需要挂出的对冲卖单和重建买单数量
+#[derive(Debug, Clone, Copy)]
+pub(super) struct FillQuantities {
+    pub(super) hedge: f64,    // 本次对冲的数量，未对冲量不足最小下单金额时为0
+    pub(super) rebuild: f64,  // 订单全部成交后在原价重建的数量，部分成交时为0
+    pub(super) partial: bool, // 订单分多次成交，同价位已有的卖单是本订单之前的对冲单
+}
+
+/// 冰山单子订单：同一逻辑订单拆出的子订单共享组ID，成交和撤单按整组处理
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(super) struct IcebergSlice {
+    pub(super) group_id: u64,
+    pub(super) filled_quantity: f64, // 组内已成交的数量，记录在尚未成交的子订单上
+}
+
+// ============================================================================
+// 订单优先级和过期管理模块
+// ============================================================================
+
+/// 订单优先级枚举
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub(super) enum OrderPriority {
+    High,   // 高优先级，如止损单、紧急平仓单
+    Normal, // 普通网格单
+    Low,    // 低优先级，如远离当前价格的网格单
+}
+
+impl OrderPriority {
+    /// 获取中文描述
+    pub(super) fn as_str(&self) -> &'static str {
+        match self {
+            OrderPriority::High => "高优先级",
+            OrderPriority::Normal => "普通优先级",
+            OrderPriority::Low => "低优先级",
+        }
+    }
+
+    /// 获取英文描述
+    pub(super) fn as_english(&self) -> &'static str {
+        match self {
+            OrderPriority::High => "High",
+            OrderPriority::Normal => "Normal",
+            OrderPriority::Low => "Low",
+        }
+    }
+
+    /// 获取优先级数值（数值越大优先级越高）
+    pub(super) fn priority_value(&self) -> u8 {
+        match self {
+            OrderPriority::High => 3,
+            OrderPriority::Normal => 2,
+            OrderPriority::Low => 1,
+        }
+    }
+
+    /// 判断是否为高优先级
+    pub(super) fn is_high(&self) -> bool {
+        matches!(self, OrderPriority::High)
+    }
+
+    /// 判断是否为低优先级
+    pub(super) fn is_low(&self) -> bool {
+        matches!(self, OrderPriority::Low)
+    }
+
+    /// 获取建议的超时时间（秒）
+    pub(super) fn suggested_timeout_seconds(&self) -> u64 {
+        match self {
+            OrderPriority::High => 30,    // 高优先级订单30秒超时
+            OrderPriority::Normal => 300, // 普通订单5分钟超时
+            OrderPriority::Low => 1800,   // 低优先级订单30分钟超时
+        }
+    }
+}
+
+/// 订单过期策略
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(super) enum ExpiryStrategy {
+    Cancel,          // 过期后取消订单
+    Reprice,         // 过期后重新定价
+    Extend,          // 延长过期时间
+    ConvertToMarket, // 转换为市价单（仅限高优先级）
+}
+
+impl ExpiryStrategy {
+    /// 获取中文描述
+    pub(super) fn as_str(&self) -> &'static str {
+        match self {
+            ExpiryStrategy::Cancel => "取消订单",
+            ExpiryStrategy::Reprice => "重新定价",
+            ExpiryStrategy::Extend => "延长时间",
+            ExpiryStrategy::ConvertToMarket => "转市价单",
+        }
+    }
+
+    /// 获取英文描述
+    pub(super) fn as_english(&self) -> &'static str {
+        match self {
+            ExpiryStrategy::Cancel => "Cancel",
+            ExpiryStrategy::Reprice => "Reprice",
+            ExpiryStrategy::Extend => "Extend",
+            ExpiryStrategy::ConvertToMarket => "Convert to Market",
+        }
+    }
+
+    /// 判断是否需要立即处理
+    pub(super) fn requires_immediate_action(&self) -> bool {
+        matches!(self, ExpiryStrategy::ConvertToMarket)
+    }
+}
+
+/// 带优先级的订单信息
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(super) struct PrioritizedOrderInfo {
+    // 基础订单信息
+    pub(super) base_info: OrderInfo,
+
+    // 优先级管理
+    pub(super) priority: OrderPriority,
+
+    // 过期管理
+    #[serde(with = "system_time_serde")]
+    pub(super) created_time: SystemTime,
+    pub(super) expiry_time: Option<SystemTime>,
+    pub(super) expiry_strategy: ExpiryStrategy,
+
+    // 订单状态
+    pub(super) order_id: Option<u64>,
+    pub(super) retry_count: u32,
+    pub(super) last_retry_time: Option<SystemTime>,
+
+    // 市场条件
+    pub(super) distance_from_current_price: f64, // 与当前价格的距离（百分比）
+    pub(super) market_urgency: f64,              // 市场紧急度评分 (0-100)
+
+    // 执行统计
+    pub(super) execution_attempts: u32,
+    pub(super) total_wait_time: Duration,
+    pub(super) average_fill_time: Option<Duration>,
+}
+
+impl PrioritizedOrderInfo {
+    /// 创建新的优先级订单
+    pub(super) fn new(
+        base_info: OrderInfo,
+        priority: OrderPriority,
+        expiry_strategy: ExpiryStrategy,
+        current_price: f64,
+    ) -> Self {
+        let created_time = SystemTime::now();
+        let expiry_time =
+            Some(created_time + Duration::from_secs(priority.suggested_timeout_seconds()));
+
+        // 计算与当前价格的距离
+        let distance_from_current_price =
+            ((base_info.price - current_price) / current_price * 100.0).abs();
+
+        Self {
+            base_info,
+            priority,
+            created_time,
+            expiry_time,
+            expiry_strategy,
+            order_id: None,
+            retry_count: 0,
+            last_retry_time: None,
+            distance_from_current_price,
+            market_urgency: 50.0, // 默认中等紧急度
+            execution_attempts: 0,
+            total_wait_time: Duration::new(0, 0),
+            average_fill_time: None,
+        }
+    }
+
+    /// 创建高优先级订单（止损单等）
+    pub(super) fn new_high_priority(
+        base_info: OrderInfo,
+        current_price: f64,
+        timeout_seconds: Option<u64>,
+    ) -> Self {
+        let mut order = Self::new(
+            base_info,
+            OrderPriority::High,
+            ExpiryStrategy::ConvertToMarket,
+            current_price,
+        );
+
+        if let Some(timeout) = timeout_seconds {
+            order.expiry_time = Some(order.created_time + Duration::from_secs(timeout));
+        }
+
+        order.market_urgency = 90.0; // 高紧急度
+        order
+    }
+
+    /// 创建低优先级订单（远离价格的网格单）
+    pub(super) fn new_low_priority(base_info: OrderInfo, current_price: f64) -> Self {
+        let mut order = Self::new(
+            base_info,
+            OrderPriority::Low,
+            ExpiryStrategy::Cancel,
+            current_price,
+        );
+        order.market_urgency = 20.0; // 低紧急度
+        order
+    }
+
+    /// 检查订单是否过期
+    pub(super) fn is_expired(&self) -> bool {
+        if let Some(expiry_time) = self.expiry_time {
+            SystemTime::now() > expiry_time
+        } else {
+            false
+        }
+    }
+
+    /// 获取剩余时间（秒）
+    pub(super) fn remaining_seconds(&self) -> Option<u64> {
+        if let Some(expiry_time) = self.expiry_time {
+            expiry_time
+                .duration_since(SystemTime::now())
+                .ok()
+                .map(|d| d.as_secs())
+        } else {
+            None
+        }
+    }
+
+    /// 延长过期时间
+    pub(super) fn extend_expiry(&mut self, additional_seconds: u64) {
+        if let Some(expiry_time) = self.expiry_time {
+            self.expiry_time = Some(expiry_time + Duration::from_secs(additional_seconds));
+        } else {
+            self.expiry_time = Some(SystemTime::now() + Duration::from_secs(additional_seconds));
+        }
+    }
+
+    /// 更新市场紧急度
+    pub(super) fn update_market_urgency(&mut self, volatility: f64, price_change: f64) {
+        // 基于市场波动率和价格变化计算紧急度
+        let volatility_factor = (volatility * 100.0).min(50.0);
+        let price_change_factor = (price_change.abs() * 100.0).min(30.0);
+        let distance_factor = (100.0 - self.distance_from_current_price).max(0.0) * 0.2;
+
+        self.market_urgency =
+            (volatility_factor + price_change_factor + distance_factor).min(100.0);
+    }
+
+    /// 记录执行尝试
+    pub(super) fn record_execution_attempt(&mut self) {
+        self.execution_attempts += 1;
+        self.total_wait_time += self.created_time.elapsed().unwrap_or_default();
+    }
+
+    /// 设置订单ID
+    pub(super) fn set_order_id(&mut self, order_id: u64) {
+        self.order_id = Some(order_id);
+    }
+
+    /// 记录重试
+    pub(super) fn record_retry(&mut self) {
+        self.retry_count += 1;
+        self.last_retry_time = Some(SystemTime::now());
+    }
+
+    /// 获取综合优先级评分
+    pub(super) fn get_priority_score(&self) -> f64 {
+        let base_priority = self.priority.priority_value() as f64 * 30.0;
+        let urgency_score = self.market_urgency * 0.4;
+        let distance_penalty = self.distance_from_current_price * 0.1;
+        let time_bonus = if self.is_expired() { 20.0 } else { 0.0 };
+
+        (base_priority + urgency_score - distance_penalty + time_bonus).max(0.0)
+    }
+
+    /// 判断是否需要立即处理
+    pub(super) fn needs_immediate_attention(&self) -> bool {
+        self.priority.is_high()
+            || self.is_expired()
+            || self.market_urgency > 80.0
+            || self.retry_count > 3
+    }
+
+    /// 获取建议的处理策略
+    pub(super) fn get_suggested_action(&self, _current_price: f64) -> String {
+        if self.is_expired() {
+            format!("订单已过期，建议{}", self.expiry_strategy.as_str())
+        } else if self.distance_from_current_price > 5.0 {
+            "订单距离当前价格较远，建议降低优先级".to_string()
+        } else if self.market_urgency > 80.0 {
+            "市场紧急度高，建议提高优先级".to_string()
+        } else {
+            "正常处理".to_string()
+        }
+    }
+}
+
+/// 订单管理器
+#[derive(Debug)]
+pub(super) struct OrderManager {
+    pub(super) prioritized_orders: Vec<PrioritizedOrderInfo>,
+    pub(super) max_orders: usize,
+    pub(super) last_cleanup_time: SystemTime,
+    pub(super) cleanup_interval: Duration,
+
+    // 统计信息
+    pub(super) total_orders_created: u64,
+    pub(super) total_orders_expired: u64,
+    pub(super) total_orders_repriced: u64,
+    pub(super) total_high_priority_orders: u64,
+
+    // 性能指标
+    pub(super) average_execution_time: Duration,
+    pub(super) success_rate: f64,
+    pub(super) priority_distribution: HashMap<OrderPriority, u32>,
+}
+
+impl OrderManager {
+    /// 创建新的订单管理器
+    pub(super) fn new(max_orders: usize) -> Self {
+        Self {
+            prioritized_orders: Vec::new(),
+            max_orders,
+            last_cleanup_time: SystemTime::now(),
+            cleanup_interval: Duration::from_secs(60), // 每分钟清理一次
+            total_orders_created: 0,
+            total_orders_expired: 0,
+            total_orders_repriced: 0,
+            total_high_priority_orders: 0,
+            average_execution_time: Duration::new(0, 0),
+            success_rate: 100.0,
+            priority_distribution: HashMap::new(),
+        }
+    }
+
+    /// 添加订单
+    pub(super) fn add_order(
+        &mut self,
+        order: PrioritizedOrderInfo,
+    ) -> Result<(), GridStrategyError> {
+        // 检查是否超过最大订单数
+        if self.prioritized_orders.len() >= self.max_orders {
+            // 尝试移出过期订单（队列已满时直接丢弃，不再处理其过期策略）
+            let dropped = self.drain_expired_orders();
+            if !dropped.is_empty() {
+                warn!("⚠️ 订单队列已满，丢弃{}个过期订单", dropped.len());
+            }
+
+            // 如果仍然超过限制，移除最低优先级的订单
+            if self.prioritized_orders.len() >= self.max_orders {
+                self.remove_lowest_priority_order();
+            }
+        }
+
+        // 更新统计信息
+        self.total_orders_created += 1;
+        if order.priority.is_high() {
+            self.total_high_priority_orders += 1;
+        }
+
+        // 更新优先级分布
+        *self
+            .priority_distribution
+            .entry(order.priority.clone())
+            .or_insert(0) += 1;
+
+        // 插入订单后整体重排，评分会随市场条件和过期状态变化，不能假设原数组有序
+        self.prioritized_orders.push(order);
+        self.sort_by_priority();
+
+        info!(
+            "📋 添加订单到管理器 - 当前订单数: {}, 总创建数: {}",
+            self.prioritized_orders.len(),
+            self.total_orders_created
+        );
+
+        Ok(())
+    }
+
+    /// 按优先级评分从高到低排序，评分相同时保持插入顺序
+    ///
+    /// 评分依赖当前时间（是否过期），排序前每个订单只计算一次评分，保证比较结果前后一致。
+    pub(super) fn sort_by_priority(&mut self) {
+        let mut scored: Vec<(f64, PrioritizedOrderInfo)> = self
+            .prioritized_orders
+            .drain(..)
+            .map(|order| (order.get_priority_score(), order))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        self.prioritized_orders = scored.into_iter().map(|(_, order)| order).collect();
+    }
+
+    /// 获取下一个要处理的订单
+    pub(super) fn get_next_order(&mut self) -> Option<&mut PrioritizedOrderInfo> {
+        // 过期状态会改变评分，取订单前重新排序，返回最高优先级的订单
+        self.sort_by_priority();
+
+        self.prioritized_orders.first_mut()
+    }
+
+    /// 获取所有需要立即处理的订单
+    pub(super) fn get_urgent_orders(&mut self) -> Vec<&mut PrioritizedOrderInfo> {
+        self.prioritized_orders
+            .iter_mut()
+            .filter(|order| order.needs_immediate_attention())
+            .collect()
+    }
+
+    /// 获取过期订单
+    pub(super) fn get_expired_orders(&self) -> Vec<&PrioritizedOrderInfo> {
+        self.prioritized_orders
+            .iter()
+            .filter(|order| order.is_expired())
+            .collect()
+    }
+
+    /// 从内存队列中移出过期订单（只操作队列，不涉及交易所撤单）
+    ///
+    /// 受 `cleanup_interval` 节流，返回移出的订单交由 `process_expired_priority_orders` 按过期策略处理。
+    pub(super) fn drain_expired_orders(&mut self) -> Vec<PrioritizedOrderInfo> {
+        let now = SystemTime::now();
+
+        // 如果还没到清理时间，跳过
+        if now
+            .duration_since(self.last_cleanup_time)
+            .unwrap_or_default()
+            < self.cleanup_interval
+        {
+            return Vec::new();
+        }
+
+        let (expired, remaining): (Vec<_>, Vec<_>) = self
+            .prioritized_orders
+            .drain(..)
+            .partition(|order| order.is_expired());
+
+        self.prioritized_orders = remaining;
+        self.total_orders_expired += expired.len() as u64;
+        self.last_cleanup_time = now;
+
+        if !expired.is_empty() {
+            info!(
+                "🧹 清理过期订单 - 清理数量: {}, 剩余订单: {}",
+                expired.len(),
+                self.prioritized_orders.len()
+            );
+        }
+
+        expired
+    }
+
+    /// 移除最低优先级的订单
+    pub(super) fn remove_lowest_priority_order(&mut self) -> Option<PrioritizedOrderInfo> {
+        if self.prioritized_orders.is_empty() {
+            return None;
+        }
+
+        // 找到优先级最低的订单
+        let min_pos = self
+            .prioritized_orders
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                a.get_priority_score()
+                    .partial_cmp(&b.get_priority_score())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .map(|(pos, _)| pos)?;
+
+        let removed = self.prioritized_orders.remove(min_pos);
+
+        warn!(
+            "⚠️ 移除最低优先级订单 - 优先级: {}, 剩余订单: {}",
+            removed.priority.localized(),
+            self.prioritized_orders.len()
+        );
+
+        Some(removed)
+    }
+
+    /// 更新所有订单的市场紧急度
+    pub(super) fn update_market_conditions(
+        &mut self,
+        current_price: f64,
+        volatility: f64,
+        price_change: f64,
+    ) {
+        for order in &mut self.prioritized_orders {
+            // 更新与当前价格的距离
+            order.distance_from_current_price =
+                ((order.base_info.price - current_price) / current_price * 100.0).abs();
+
+            // 更新市场紧急度
+            order.update_market_urgency(volatility, price_change);
+        }
+
+        // 紧急度和距离变化后评分随之改变，重新排序
+        self.sort_by_priority();
+    }
+
+    /// 根据订单ID查找订单
+    pub(super) fn find_order_by_id(&mut self, order_id: u64) -> Option<&mut PrioritizedOrderInfo> {
+        self.prioritized_orders
+            .iter_mut()
+            .find(|order| order.order_id == Some(order_id))
+    }
+
+    /// 移除订单
+    pub(super) fn remove_order(&mut self, order_id: u64) -> Option<PrioritizedOrderInfo> {
+        if let Some(pos) = self
+            .prioritized_orders
+            .iter()
+            .position(|order| order.order_id == Some(order_id))
+        {
+            Some(self.prioritized_orders.remove(pos))
+        } else {
+            None
+        }
+    }
+
+    /// 获取订单统计报告
+    pub(super) fn get_statistics_report(&self) -> String {
+        let high_priority_count = self
+            .prioritized_orders
+            .iter()
+            .filter(|o| o.priority.is_high())
+            .count();
+        let normal_priority_count = self
+            .prioritized_orders
+            .iter()
+            .filter(|o| o.priority == OrderPriority::Normal)
+            .count();
+        let low_priority_count = self
+            .prioritized_orders
+            .iter()
+            .filter(|o| o.priority.is_low())
+            .count();
+        let expired_count = self
+            .prioritized_orders
+            .iter()
+            .filter(|o| o.is_expired())
+            .count();
+        let urgent_count = self
+            .prioritized_orders
+            .iter()
+            .filter(|o| o.needs_immediate_attention())
+            .count();
+
+        format!(
+            "📊 订单管理器统计报告\n\
+            ├─ 当前订单数: {}\n\
+            ├─ 高优先级: {} | 普通: {} | 低优先级: {}\n\
+            ├─ 过期订单: {} | 紧急订单: {}\n\
+            ├─ 总创建数: {} | 总过期数: {} | 重定价数: {}\n\
+            ├─ 成功率: {:.1}% | 平均执行时间: {:.2}秒\n\
+            └─ 最大容量: {} | 使用率: {:.1}%",
+            self.prioritized_orders.len(),
+            high_priority_count,
+            normal_priority_count,
+            low_priority_count,
+            expired_count,
+            urgent_count,
+            self.total_orders_created,
+            self.total_orders_expired,
+            self.total_orders_repriced,
+            self.success_rate,
+            self.average_execution_time.as_secs_f64(),
+            self.max_orders,
+            (self.prioritized_orders.len() as f64 / self.max_orders as f64) * 100.0
+        )
+    }
+
+    /// 获取优先级分布
+    pub(super) fn get_priority_distribution(&self) -> &HashMap<OrderPriority, u32> {
+        &self.priority_distribution
+    }
+
+    /// 重置统计信息
+    pub(super) fn reset_statistics(&mut self) {
+        self.total_orders_created = 0;
+        self.total_orders_expired = 0;
+        self.total_orders_repriced = 0;
+        self.total_high_priority_orders = 0;
+        self.priority_distribution.clear();
+        self.success_rate = 100.0;
+        self.average_execution_time = Duration::new(0, 0);
+    }
+}
+
+/// 网格挂单的有效方式：开启 prefer_maker 时使用 ALO（post-only），否则 GTC
+pub(super) fn grid_order_tif(grid_config: &crate::config::GridConfig) -> String {
+    if grid_config.prefer_maker {
+        "Alo".to_string()
+    } else {
+        "Gtc".to_string()
+    }
+}
+
+/// 解析单个限价单的下单响应，返回订单ID
+///
+/// 挂单（Resting）和立即成交（Filled）都算下单成功：立即成交的订单照常记录，
+/// 随后的成交回报按订单ID找到它并挂出对冲单。交易所拒绝或响应中没有订单状态时返回错误。
+pub(super) fn parse_order_response(
+    response: &ExchangeResponseStatus,
+) -> Result<u64, GridStrategyError> {
+    let response = match response {
+        ExchangeResponseStatus::Ok(response) => response,
+        ExchangeResponseStatus::Err(e) => {
+            return Err(GridStrategyError::OrderError(format!(
+                "订单被交易所拒绝: {}",
+                e
+            )))
+        }
+    };
+    let status = response
+        .data
+        .as_ref()
+        .and_then(|data| data.statuses.first())
+        .ok_or_else(|| GridStrategyError::OrderError("订单响应中未找到订单状态".to_string()))?;
+
+    match status {
+        ExchangeDataStatus::Resting(order) => Ok(order.oid),
+        ExchangeDataStatus::Filled(order) => {
+            info!(
+                "⚡ 订单立即成交: ID={}, 数量={}, 均价={}",
+                order.oid, order.total_sz, order.avg_px
+            );
+            Ok(order.oid)
+        }
+        ExchangeDataStatus::Error(e) => {
+            Err(GridStrategyError::OrderError(format!("订单被拒绝: {}", e)))
+        }
+        other => Err(GridStrategyError::OrderError(format!(
+            "订单响应中未找到订单ID: {:?}",
+            other
+        ))),
+    }
+}
+
+/// 下单即成交时返回 (订单ID, 成交数量, 成交均价)，挂单或被拒时为 None
+pub(super) fn immediate_fill(response: &ExchangeResponseStatus) -> Option<(u64, f64, f64)> {
+    let ExchangeResponseStatus::Ok(response) = response else {
+        return None;
+    };
+    match response.data.as_ref()?.statuses.first()? {
+        ExchangeDataStatus::Filled(order) => Some((
+            order.oid,
+            order.total_sz.parse().ok()?,
+            order.avg_px.parse().ok()?,
+        )),
+        _ => None,
+    }
+}
+
+/// post-only 单因会立即成交而被交易所拒绝时，返回拒绝信息
+pub(super) fn post_only_rejection(response: &ExchangeResponseStatus) -> Option<&str> {
+    let ExchangeResponseStatus::Ok(response) = response else {
+        return None;
+    };
+    match response.data.as_ref()?.statuses.first()? {
+        ExchangeDataStatus::Error(e) if e.contains("Post only") => Some(e.as_str()),
+        _ => None,
+    }
+}
+
+/// 计算 post-only 被拒后的重新定价：挂到己方最优价（买单挂买一、卖单挂卖一），
+/// 拒绝信息中没有盘口价格时向远离成交的方向移动一个最小价格单位
+pub(super) fn post_only_reprice(
+    rejection: &str,
+    is_buy: bool,
+    limit_px: f64,
+    grid_config: &crate::config::GridConfig,
+) -> f64 {
+    // Hyperliquid 拒绝信息格式："... bbo was <买一>@<卖一>. asset=..."
+    let bbo = rejection
+        .split("bbo was ")
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|pair| {
+            let (bid, ask) = pair.trim_end_matches('.').split_once('@')?;
+            Some((bid.parse::<f64>().ok()?, ask.parse::<f64>().ok()?))
+        });
+
+    let tick = price_tick(grid_config);
+    let repriced = match (bbo, is_buy) {
+        (Some((bid, _)), true) => bid.min(limit_px - tick),
+        (Some((_, ask)), false) => ask.max(limit_px + tick),
+        (None, true) => limit_px - tick,
+        (None, false) => limit_px + tick,
+    };
+    format_price(repriced, grid_config.price_precision)
+}
+
+/// 最小价格单位（价格精度的一档）
+pub(super) fn price_tick(grid_config: &crate::config::GridConfig) -> f64 {
+    10f64.powi(-(grid_config.price_precision as i32))
+}
+
+/// 检查是否已有与 `price` 相差不超过 `tolerance` 的挂单，用于避免同价位重复挂单
+pub(super) fn has_nearby_order(
+    orders: &HashMap<u64, OrderInfo>,
+    price: f64,
+    tolerance: f64,
+) -> bool {
+    // 价格经过 format_price 舍入，放宽一点容忍浮点误差
+    let tolerance = tolerance * (1.0 + 1e-6);
+    orders
+        .values()
+        .any(|order| (order.price - price).abs() <= tolerance)
+}
+
+/// 检查订单名义价值（价格 × 数量）是否达到交易所最小下单金额，不足时会被交易所拒单
+pub(super) fn meets_min_notional(
+    price: f64,
+    size: f64,
+    grid_config: &crate::config::GridConfig,
+) -> bool {
+    price * size >= grid_config.min_notional
+}
+
+/// 记录一次成交，返回订单是否已全部成交
+///
+/// 剩余数量不足半个最小数量单位时视为全部成交，否则订单保留在挂单列表中继续等待成交。
+pub(super) fn record_order_fill(
+    order: &mut OrderInfo,
+    fill_size: f64,
+    grid_config: &crate::config::GridConfig,
+) -> bool {
+    order.filled_quantity += fill_size;
+    let min_quantity = 1.0 / 10f64.powi(grid_config.quantity_precision as i32);
+    order.quantity - order.filled_quantity < min_quantity * 0.5
+}
+
+/// 取出本次应对冲的数量
+///
+/// 部分成交的数量先累积，达到最小下单金额时一起对冲；订单全部成交时取出剩余的全部未对冲量。
+pub(super) fn take_hedge_quantity(
+    order: &mut OrderInfo,
+    fill_size: f64,
+    fill_price: f64,
+    completed: bool,
+    grid_config: &crate::config::GridConfig,
+) -> f64 {
+    order.unhedged_quantity += fill_size;
+    if completed || meets_min_notional(fill_price, order.unhedged_quantity, grid_config) {
+        std::mem::take(&mut order.unhedged_quantity)
+    } else {
+        0.0
+    }
+}
+
+/// 为逻辑订单生成客户端订单ID（cloid）
+///
+/// 重试时复用同一个 cloid，前一次请求实际已被交易所接受时重复的 cloid 会被拒绝，避免重复下单。
+pub(super) fn new_cloid() -> Uuid {
+    Uuid::new_v4()
+}
+
+/// 滑点单都是 IOC，成交回报在几秒内到达，登记超过该时长（秒）的预期价直接清理
+const EXPECTED_FILL_PRICE_TTL_SECS: u64 = 300;
+
+/// 按滑点价下单时登记的预期成交价，成交回报按 cloid 查出计算实际滑点，每个账户各自持有
+#[derive(Debug, Clone, Default)]
+pub(super) struct ExpectedFillPrices {
+    prices: HashMap<Uuid, (f64, u64)>, // cloid -> (预期价, 登记时间)
+}
+
+impl ExpectedFillPrices {
+    /// 登记滑点单的预期成交价（加滑点之前的参考价）
+    pub(super) fn register(&mut self, cloid: Uuid, expected_price: f64) {
+        let now = safe_unix_timestamp();
+        self.prices.retain(|_, (_, registered_at)| {
+            now.saturating_sub(*registered_at) < EXPECTED_FILL_PRICE_TTL_SECS
+        });
+        self.prices.insert(cloid, (expected_price, now));
+    }
+
+    /// 查询成交对应的预期成交价，IOC 单可能分多笔成交，查询后不删除
+    pub(super) fn get(&self, cloid: &str) -> Option<f64> {
+        let cloid = parse_cloid(cloid)?;
+        self.prices
+            .get(&cloid)
+            .map(|(expected_price, _)| *expected_price)
+    }
+}
+
+/// 解析成交事件中的 cloid（0x 开头的十六进制字符串）
+pub(super) fn parse_cloid(value: &str) -> Option<Uuid> {
+    Uuid::parse_str(value.trim_start_matches("0x")).ok()
+}
+
+/// 按 cloid 把下单成功的订单对应回本地订单信息，返回 (交易所订单ID, 订单信息)
+///
+/// 批量下单中途有失败时，成功列表与待下单列表不再按下标对齐；没有 cloid 的结果按剩余订单顺序对应。
+pub(super) fn match_created_orders(
+    created: &[(u64, Option<Uuid>)],
+    infos: &[OrderInfo],
+) -> Vec<(u64, OrderInfo)> {
+    let mut used = vec![false; infos.len()];
+    let mut matched = Vec::with_capacity(created.len());
+    for &(oid, cloid) in created {
+        let index = cloid
+            .and_then(|cloid| infos.iter().position(|info| info.cloid == Some(cloid)))
+            .filter(|&index| !used[index])
+            .or_else(|| (0..infos.len()).find(|&index| !used[index]));
+        if let Some(index) = index {
+            used[index] = true;
+            matched.push((oid, infos[index].clone()));
+        }
+    }
+    matched
+}
+
+/// 成交事件对应的本地订单ID：优先按交易所订单ID匹配，找不到时按成交事件带回的 cloid 匹配
+pub(super) fn resolve_fill_order_id(
+    orders: &HashMap<u64, OrderInfo>,
+    oid: u64,
+    cloid: Option<&str>,
+) -> u64 {
+    if orders.contains_key(&oid) {
+        return oid;
+    }
+    let Some(cloid) = cloid.and_then(parse_cloid) else {
+        return oid;
+    };
+    match orders.iter().find(|(_, order)| order.cloid == Some(cloid)) {
+        Some((&local_oid, _)) => {
+            info!(
+                "🔗 按 cloid 匹配到本地订单 - 交易所ID: {}, 本地ID: {}",
+                oid, local_oid
+            );
+            local_oid
+        }
+        None => oid,
+    }
+}
+
+// 冰山单组ID序号，与启动时间组合，避免和状态文件中已有的组冲突
+pub(super) static ICEBERG_GROUP_SEQ: AtomicU64 = AtomicU64::new(0);
+
+pub(super) fn next_iceberg_group_id() -> u64 {
+    let seq = ICEBERG_GROUP_SEQ.fetch_add(1, Ordering::SeqCst);
+    safe_unix_timestamp() * 1000 + seq % 1000
+}
+
+/// 按冰山单配置拆分网格订单，未开启或订单金额不超过切片大小时原样返回
+///
+/// 子订单数量按切片金额向上取整，数量均分后按精度舍入，最后一个子订单补足舍入误差。
+pub(super) fn split_iceberg_order(
+    order: ClientOrderRequest,
+    info: OrderInfo,
+    grid_config: &crate::config::GridConfig,
+) -> Vec<(ClientOrderRequest, OrderInfo)> {
+    let slice_size = grid_config.iceberg_slice_size;
+    let order_value = order.limit_px * order.sz;
+    if !grid_config.iceberg_enabled || slice_size <= 0.0 || order_value <= slice_size {
+        return vec![(order, info)];
+    }
+
+    // 子订单金额不能低于最小名义价值，否则会被交易所拒单
+    let max_slices = if grid_config.min_notional > 0.0 {
+        ((order_value / grid_config.min_notional).floor() as usize).max(1)
+    } else {
+        usize::MAX
+    };
+    let slice_count = ((order_value / slice_size).ceil() as usize).min(max_slices);
+    if slice_count <= 1 {
+        return vec![(order, info)];
+    }
+    let slice_quantity = format_price(
+        order.sz / slice_count as f64,
+        grid_config.quantity_precision,
+    );
+    if slice_quantity <= 0.0 {
+        return vec![(order, info)];
+    }
+
+    let group_id = next_iceberg_group_id();
+    let request = OrderRequestInfo::from_client_order_request(&order);
+    let mut remaining = order.sz;
+    let mut slices = Vec::with_capacity(slice_count);
+    for index in 0..slice_count {
+        let quantity = if index + 1 == slice_count {
+            format_price(remaining, grid_config.quantity_precision)
+        } else {
+            slice_quantity.min(remaining)
+        };
+        if quantity <= 0.0 {
+            break;
+        }
+        remaining -= quantity;
+
+        let mut slice_request = request.clone();
+        slice_request.sz = quantity;
+        let mut slice_info = info.clone();
+        slice_info.quantity = quantity;
+        slice_info.allocated_funds = info.allocated_funds * quantity / order.sz;
+        slice_info.iceberg = Some(IcebergSlice {
+            group_id,
+            filled_quantity: 0.0,
+        });
+        // 每个子订单是独立的交易所订单，需要各自的 cloid
+        slice_info.cloid = Some(new_cloid());
+        slice_request.cloid = slice_info.cloid;
+        slices.push((slice_request.to_client_order_request(), slice_info));
+    }
+    slices
+}
+
+/// 批量下单前把需要拆分的订单展开成冰山子订单，返回的订单与订单信息一一对应
+pub(super) fn expand_iceberg_orders(
+    orders: Vec<ClientOrderRequest>,
+    infos: Vec<OrderInfo>,
+    grid_config: &crate::config::GridConfig,
+) -> (Vec<ClientOrderRequest>, Vec<OrderInfo>) {
+    if !grid_config.iceberg_enabled {
+        return (orders, infos);
+    }
+
+    let logical_count = orders.len();
+    let (orders, infos): (Vec<_>, Vec<_>) = orders
+        .into_iter()
+        .zip(infos)
+        .flat_map(|(order, info)| split_iceberg_order(order, info, grid_config))
+        .unzip();
+    if orders.len() > logical_count {
+        info!(
+            "🧊 冰山单拆分: {}个网格订单展开为{}个子订单",
+            logical_count,
+            orders.len()
+        );
+    }
+    (orders, infos)
+}
+
+/// 提交单个网格限价单，开启冰山单且金额超过切片大小时拆成子订单依次挂出
+///
+/// 子订单依次提交（节奏由全局 API 限流器控制），返回挂单成功的订单ID及订单信息。
+pub(super) async fn submit_grid_order_sliced(
+    exchange_client: &dyn OrderExecutor,
+    order: ClientOrderRequest,
+    info: OrderInfo,
+    grid_config: &crate::config::GridConfig,
+    label: &str,
+) -> Vec<(u64, OrderInfo)> {
+    let slices = split_iceberg_order(order, info, grid_config);
+    let slice_count = slices.len();
+    let mut placed = Vec::with_capacity(slice_count);
+
+    for (slice, slice_info) in slices {
+        match submit_grid_order(exchange_client, slice, grid_config).await {
+            Ok(response) => match parse_order_response(&response) {
+                Ok(oid) => placed.push((oid, slice_info)),
+                Err(e) => warn!("❌ {}失败: {}", label, e),
+            },
+            Err(e) => warn!("❌ {}失败: {:?}", label, e),
+        }
+    }
+
+    if slice_count > 1 {
+        info!(
+            "🧊 {}拆分为{}个冰山子订单，挂单成功{}个",
+            label,
+            slice_count,
+            placed.len()
+        );
+    }
+    placed
+}
+
+/// 冰山子订单成交后的整组处理
+///
+/// 组内仍有挂单时把累计成交量记录到其余子订单上并返回 None；整组成交完（或其余子订单
+/// 已撤销）时返回整组累计成交量。普通订单直接返回本次成交量。
+pub(super) fn settle_iceberg_fill(
+    orders: &mut HashMap<u64, OrderInfo>,
+    filled: &OrderInfo,
+    fill_size: f64,
+) -> Option<f64> {
+    let Some(slice) = &filled.iceberg else {
+        return Some(fill_size);
+    };
+
+    let total_filled = slice.filled_quantity + fill_size;
+    let mut remaining = 0;
+    for order in orders.values_mut() {
+        if let Some(sibling) = order
+            .iceberg
+            .as_mut()
+            .filter(|sibling| sibling.group_id == slice.group_id)
+        {
+            sibling.filled_quantity = total_filled;
+            remaining += 1;
+        }
+    }
+
+    if remaining > 0 {
+        info!(
+            "🧊 冰山子订单成交 - 组内累计成交: {:.4}, 剩余子订单: {}",
+            total_filled, remaining
+        );
+        None
+    } else {
+        info!("🧊 冰山单整组成交完成 - 累计成交: {:.4}", total_filled);
+        Some(total_filled)
+    }
+}
+
+/// 撤单时按整组处理：返回与 `oid` 同属一个冰山组的全部订单ID，普通订单只返回自身
+pub(super) fn iceberg_group_oids(orders: &HashMap<u64, OrderInfo>, oid: u64) -> Vec<u64> {
+    let Some(group_id) = orders
+        .get(&oid)
+        .and_then(|order| order.iceberg.as_ref())
+        .map(|slice| slice.group_id)
+    else {
+        return vec![oid];
+    };
+    orders
+        .iter()
+        .filter(|(_, order)| {
+            order
+                .iceberg
+                .as_ref()
+                .is_some_and(|slice| slice.group_id == group_id)
+        })
+        .map(|(oid, _)| *oid)
+        .collect()
+}
+
+/// 逻辑订单数量，同一冰山组的子订单只计一次
+pub(super) fn logical_order_count(orders: &HashMap<u64, OrderInfo>) -> usize {
+    let mut groups = std::collections::HashSet::new();
+    orders
+        .values()
+        .filter(|order| match &order.iceberg {
+            Some(slice) => groups.insert(slice.group_id),
+            None => true,
+        })
+        .count()
+}
+
+/// 提交网格限价单，post-only 单被拒时按 `post_only_reject_mode` 跳过或重新定价后重试一次
+pub(super) async fn submit_grid_order(
+    exchange_client: &dyn OrderExecutor,
+    order: ClientOrderRequest,
+    grid_config: &crate::config::GridConfig,
+) -> Result<ExchangeResponseStatus, hyperliquid_rust_sdk::Error> {
+    let asset = order.asset.clone();
+    let (is_buy, reduce_only, limit_px, sz, cloid) = (
+        order.is_buy,
+        order.reduce_only,
+        order.limit_px,
+        order.sz,
+        order.cloid,
+    );
+
+    let response = exchange_client.order(order, None).await?;
+    let Some(rejection) = post_only_rejection(&response) else {
+        return Ok(response);
+    };
+
+    if grid_config.post_only_reject_mode == "skip" {
+        warn!(
+            "⚠️ post-only {}单 @ {:.4} 会立即成交被拒，按配置跳过: {}",
+            if is_buy { "买" } else { "卖" },
+            limit_px,
+            rejection
+        );
+        return Ok(response);
+    }
+
+    let repriced = post_only_reprice(rejection, is_buy, limit_px, grid_config);
+    if repriced <= 0.0 {
+        return Ok(response);
+    }
+    info!(
+        "🔁 post-only {}单被拒，重新定价 {:.4} -> {:.4}",
+        if is_buy { "买" } else { "卖" },
+        limit_px,
+        repriced
+    );
+
+    exchange_client
+        .order(
+            ClientOrderRequest {
+                asset,
+                is_buy,
+                reduce_only,
+                limit_px: repriced,
+                sz,
+                cloid,
+                order_type: ClientOrder::Limit(ClientLimit {
+                    tif: grid_order_tif(grid_config),
+                }),
+            },
+            None,
+        )
+        .await
+}
+
+/// 创建带优先级的订单
+pub(super) async fn create_order_with_priority(
+    exchange_client: &dyn OrderExecutor,
+    order_info: PrioritizedOrderInfo,
+    grid_config: &crate::config::GridConfig,
+) -> Result<u64, GridStrategyError> {
+    let start_time = SystemTime::now();
+
+    // 记录订单创建尝试
+    info!(
+        "🎯 创建{}订单 - 价格: {:.4}, 数量: {:.4}, 优先级: {}",
+        if order_info.base_info.is_buy {
+            "买入"
+        } else {
+            "卖出"
+        },
+        order_info.base_info.price,
+        order_info.base_info.quantity,
+        order_info.priority.localized()
+    );
+
+    // 根据优先级调整订单参数
+    let (timeout, retry_count) = match order_info.priority {
+        OrderPriority::High => (Duration::from_secs(10), 5), // 高优先级：10秒超时，5次重试
+        OrderPriority::Normal => (Duration::from_secs(30), 3), // 普通：30秒超时，3次重试
+        OrderPriority::Low => (Duration::from_secs(60), 1),  // 低优先级：60秒超时，1次重试
+    };
+
+    // 执行订单创建（带重试机制），每次重试使用同一个 cloid，前一次实际已下单时交易所会拒绝重复订单
+    let cloid = order_info.base_info.cloid.unwrap_or_else(new_cloid);
+    let mut last_error = None;
+    for attempt in 1..=retry_count {
+        // 重新创建订单请求（因为ClientOrderRequest不支持clone）
+        let order_request = ClientOrderRequest {
+            asset: grid_config.trading_asset.clone(),
+            is_buy: order_info.base_info.is_buy,
+            reduce_only: false,
+            limit_px: order_info.base_info.price,
+            sz: order_info.base_info.quantity,
+            order_type: ClientOrder::Limit(ClientLimit {
+                tif: grid_order_tif(grid_config),
+            }),
+            cloid: Some(cloid),
+        };
+
+        match tokio::time::timeout(
+            timeout,
+            submit_grid_order(exchange_client, order_request, grid_config),
+        )
+        .await
+        {
+            Ok(Ok(response)) => match parse_order_response(&response) {
+                Ok(oid) => {
+                    let execution_time = start_time.elapsed().unwrap_or_default();
+                    info!(
+                        "✅ {}订单创建成功 - ID: {}, 执行时间: {:.2}秒, 尝试次数: {}",
+                        order_info.priority.localized(),
+                        oid,
+                        execution_time.as_secs_f64(),
+                        attempt
+                    );
+
+                    return Ok(oid);
+                }
+                Err(e) => {
+                    warn!(
+                        "⚠️ {}订单创建失败 - 尝试 {}/{}: {}",
+                        order_info.priority.localized(),
+                        attempt,
+                        retry_count,
+                        e
+                    );
+                    last_error = Some(e);
+                }
+            },
+            Ok(Err(e)) => {
+                let error_msg = format!("订单创建失败: {}", e);
+                warn!(
+                    "⚠️ {}订单创建失败 - 尝试 {}/{}: {}",
+                    order_info.priority.localized(),
+                    attempt,
+                    retry_count,
+                    error_msg
+                );
+                last_error = Some(GridStrategyError::OrderError(error_msg));
+            }
+            Err(_) => {
+                let error_msg = format!("订单创建超时 ({:.1}秒)", timeout.as_secs_f64());
+                warn!(
+                    "⚠️ {}订单创建超时 - 尝试 {}/{}: {}",
+                    order_info.priority.localized(),
+                    attempt,
+                    retry_count,
+                    error_msg
+                );
+                last_error = Some(GridStrategyError::OrderError(error_msg));
+            }
+        }
+
+        // 如果不是最后一次尝试，等待一段时间再重试
+        if attempt < retry_count {
+            let delay = Duration::from_millis(500 * attempt as u64); // 递增延迟
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    // 所有尝试都失败了
+    let final_error =
+        last_error.unwrap_or_else(|| GridStrategyError::OrderError("未知订单创建错误".to_string()));
+
+    error!(
+        "❌ {}订单创建最终失败 - 已尝试{}次: {}",
+        order_info.priority.localized(),
+        retry_count,
+        final_error
+    );
+
+    Err(final_error)
+}
+
+/// 处理优先级队列中的过期订单：从队列移出后按各自的过期策略在交易所撤单、重定价或延期
+pub(super) async fn process_expired_priority_orders(
+    exchange_client: &dyn OrderExecutor,
+    order_manager: &mut OrderManager,
+    grid_config: &crate::config::GridConfig,
+    grid_state: &mut GridState,
+    current_price: f64,
+) -> Result<(), GridStrategyError> {
+    let expired_orders = order_manager.drain_expired_orders();
+
+    if expired_orders.is_empty() {
+        return Ok(());
+    }
+
+    info!("⏰ 检查到{}个过期订单，开始处理", expired_orders.len());
+
+    for mut expired_order in expired_orders {
+        match expired_order.expiry_strategy {
+            ExpiryStrategy::Cancel => {
+                // 取消订单
+                if let Some(order_id) = expired_order.order_id {
+                    match cancel_order(exchange_client, order_id).await {
+                        Ok(_) => {
+                            info!("✅ 成功取消过期订单 - ID: {}", order_id);
+                        }
+                        Err(e) => {
+                            warn!("⚠️ 取消过期订单失败 - ID: {}, 错误: {}", order_id, e);
+                        }
+                    }
+                }
+            }
+
+            ExpiryStrategy::Reprice => {
+                // 重新定价订单
+                if let Some(order_id) = expired_order.order_id {
+                    // 先取消原订单
+                    if let Err(e) = cancel_order(exchange_client, order_id).await {
+                        warn!("⚠️ 取消待重定价订单失败 - ID: {}, 错误: {}", order_id, e);
+                        continue;
+                    }
+
+                    // 根据当前市场价格重新定价
+                    let price_adjustment = if expired_order.base_info.is_buy {
+                        // 买单：降低价格以提高成交概率
+                        -0.001 * current_price
+                    } else {
+                        // 卖单：提高价格以提高成交概率
+                        0.001 * current_price
+                    };
+
+                    expired_order.base_info.price += price_adjustment;
+                    let new_price = expired_order.base_info.price; // 保存价格用于日志
+                    expired_order.expiry_time = Some(SystemTime::now() + Duration::from_secs(300)); // 延长5分钟
+                    expired_order.record_retry();
+
+                    // 重新创建订单
+                    match create_order_with_priority(
+                        exchange_client,
+                        expired_order.clone(),
+                        grid_config,
+                    )
+                    .await
+                    {
+                        Ok(new_order_id) => {
+                            expired_order.set_order_id(new_order_id);
+                            order_manager.add_order(expired_order)?;
+                            order_manager.total_orders_repriced += 1;
+                            info!(
+                                "✅ 成功重定价订单 - 新ID: {}, 新价格: {:.4}",
+                                new_order_id, new_price
+                            );
+                        }
+                        Err(e) => {
+                            warn!("⚠️ 重定价订单失败: {}", e);
+                        }
+                    }
+                }
+            }
+
+            ExpiryStrategy::Extend => {
+                // 延长过期时间
+                let order_id = expired_order.order_id; // 保存订单ID用于日志
+                expired_order.extend_expiry(expired_order.priority.suggested_timeout_seconds());
+                order_manager.add_order(expired_order)?;
+                info!("⏰ 延长订单过期时间 - ID: {:?}", order_id);
+            }
+
+            ExpiryStrategy::ConvertToMarket => {
+                // 转换为市价单（仅限高优先级）
+                if expired_order.priority.is_high() {
+                    warn!("🚨 高优先级订单过期，转换为市价单处理");
+                    if let Err(e) = convert_expired_order_to_market(
+                        exchange_client,
+                        &expired_order,
+                        grid_config,
+                        grid_state,
+                        current_price,
+                    )
+                    .await
+                    {
+                        error!("❌ 过期订单转市价单失败: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 撤掉过期的高优先级挂单，改用带滑点的 IOC 单立即成交
+///
+/// Hyperliquid 没有原生市价单，按 `当前价 × (1 ± market_order_slippage)` 挂 IOC 模拟，
+/// 未成交部分由交易所直接取消。持仓变化由成交回报处理。
+pub(super) async fn convert_expired_order_to_market(
+    exchange_client: &dyn OrderExecutor,
+    expired_order: &PrioritizedOrderInfo,
+    grid_config: &crate::config::GridConfig,
+    grid_state: &mut GridState,
+    current_price: f64,
+) -> Result<(), GridStrategyError> {
+    if let Some(order_id) = expired_order.order_id {
+        // 撤单失败通常是已经成交或已被撤销，不能再重复下市价单
+        cancel_order(exchange_client, order_id).await.map_err(|e| {
+            GridStrategyError::OrderError(format!(
+                "取消过期订单失败，放弃转市价单 - ID: {}, 错误: {}",
+                order_id, e
+            ))
+        })?;
+    }
+
+    let is_buy = expired_order.base_info.is_buy;
+    let limit_px = if is_buy {
+        current_price * (1.0 + grid_config.market_order_slippage)
+    } else {
+        current_price * (1.0 - grid_config.market_order_slippage)
+    };
+    let limit_px = format_price(limit_px, grid_config.price_precision);
+    let quantity = format_price(
+        expired_order.base_info.quantity,
+        grid_config.quantity_precision,
+    );
+    if quantity <= 0.0 {
+        return Ok(());
+    }
+
+    info!(
+        "⚡ 过期订单转市价{}单 - 原价格: {:.4}, IOC价格: {:.4}, 数量: {:.4}",
+        if is_buy { "买" } else { "卖" },
+        expired_order.base_info.price,
+        limit_px,
+        quantity
+    );
+
+    let cloid = new_cloid();
+    grid_state
+        .expected_fill_prices
+        .register(cloid, current_price);
+    let market_order = ClientOrderRequest {
+        asset: grid_config.trading_asset.clone(),
+        is_buy,
+        reduce_only: false,
+        limit_px,
+        sz: quantity,
+        cloid: Some(cloid),
+        order_type: ClientOrder::Limit(ClientLimit {
+            tif: "Ioc".to_string(), // IOC确保快速成交或取消
+        }),
+    };
+
+    let response = exchange_client
+        .order(market_order, None)
+        .await
+        .map_err(|e| GridStrategyError::OrderError(format!("市价单提交失败: {:?}", e)))?;
+
+    // 下单即成交时按回报直接结算持仓和利润，随后到达的成交回报会被跳过
+    match immediate_fill(&response) {
+        Some((oid, fill_size, fill_price)) => {
+            let profit = grid_state.settle_immediate_fill(
+                oid,
+                is_buy,
+                fill_price,
+                fill_size,
+                expired_order.base_info.cost_price,
+                grid_config,
+            );
+            info!(
+                "✅ 过期订单已按市价成交: ID={}, 数量={:.4}, 均价={:.4}, 利润: {}",
+                oid,
+                fill_size,
+                fill_price,
+                profit.map_or("-".to_string(), |profit| format!("{:.2}", profit))
+            );
+        }
+        None => {
+            parse_order_response(&response)
+                .map_err(|e| GridStrategyError::OrderError(format!("市价单被拒绝: {}", e)))?;
+            warn!("⚠️ 过期订单的IOC市价单未成交，可能需要调大 market_order_slippage");
+        }
+    }
+    Ok(())
+}
+
+pub(super) async fn cancel_all_orders(
+    exchange_client: &dyn OrderExecutor,
+    active_orders: &mut Vec<u64>,
+    trading_asset: &str,
+) -> Result<(), GridStrategyError> {
+    if active_orders.is_empty() {
+        info!("📝 无活跃订单需要取消");
+        return Ok(());
+    }
+
+    info!("🗑️ 开始取消 {} 个活跃订单...", active_orders.len());
+
+    let mut canceled_count = 0;
+    let mut failed_count = 0;
+
+    // 逐个取消，请求节奏由执行器内的全局令牌桶控制
+    for &oid in active_orders.iter() {
+        match cancel_order_with_asset(exchange_client, oid, trading_asset).await {
+            Ok(_) => {
+                canceled_count += 1;
+                info!("✅ 订单 {} 已成功取消", oid);
+            }
+            Err(e) => {
+                failed_count += 1;
+                warn!("❌ 取消订单 {} 失败: {:?}", oid, e);
+            }
+        }
+    }
+
+    info!(
+        "📊 订单取消统计: 成功 {}, 失败 {}, 总计 {}",
+        canceled_count,
+        failed_count,
+        active_orders.len()
+    );
+
+    // 清空订单列表
+    active_orders.clear();
+
+    if failed_count > 0 {
+        warn!("⚠️ 有 {} 个订单取消失败，可能需要手动处理", failed_count);
+    }
+
+    Ok(())
+}
+
+/// 撤单前评估挂单的保留价值，复用 `PrioritizedOrderInfo::get_priority_score`
+///
+/// 距当前价越近（即将成交）、锁定的潜在利润越高、已等待越久，紧急度越高；
+/// 距当前价超过3个最大间距的挂单按低优先级计分。分数越高越值得保留。
+pub(super) fn order_retention_score(
+    info: &OrderInfo,
+    current_price: f64,
+    grid_config: &crate::config::GridConfig,
+) -> f64 {
+    let max_spacing = grid_config.max_grid_spacing.max(f64::EPSILON);
+    let distance = ((info.price - current_price) / current_price).abs();
+    let priority = if distance > max_spacing * 3.0 {
+        OrderPriority::Low
+    } else {
+        OrderPriority::Normal
+    };
+    let mut order = PrioritizedOrderInfo::new(
+        info.clone(),
+        priority,
+        ExpiryStrategy::Cancel,
+        current_price,
+    );
+
+    // 潜在利润：买单按记录的对手卖价，卖单按持仓成本价
+    let fee_rate = grid_config.maker_fee();
+    let profit_rate = if info.is_buy {
+        info.potential_sell_price
+            .map(|sell_price| calculate_expected_profit_rate(info.price, sell_price, fee_rate, 0.0))
+    } else {
+        info.cost_price
+            .filter(|&cost| cost > 0.0)
+            .map(|cost| calculate_expected_profit_rate(cost, info.price, fee_rate, 0.0))
+    }
+    .unwrap_or(0.0);
+
+    let proximity_score = (1.0 - distance / (max_spacing * 3.0)).clamp(0.0, 1.0) * 50.0;
+    let profit_score =
+        (profit_rate / min_profit_rate(grid_config).max(f64::EPSILON)).clamp(0.0, 2.0) * 15.0;
+    let age_secs = safe_unix_timestamp().saturating_sub(info.created_at);
+    let wait_score = (age_secs as f64 / 3600.0).min(1.0) * 20.0;
+    order.market_urgency = (proximity_score + profit_score + wait_score).min(100.0);
+
+    order.get_priority_score()
+}
+
+/// 重平衡/更新网格前的撤单：保留评分达到 `cancel_protection_min_score` 的挂单，只撤其余挂单
+///
+/// 未开启 `cancel_protection` 时撤销全部挂单。返回保留的挂单数量，
+/// 保留的挂单仍在 `active_orders` 和买卖订单表中，重建网格时会跳过这些价位。
+pub(super) async fn cancel_orders_by_value(
+    exchange_client: &dyn OrderExecutor,
+    grid_config: &crate::config::GridConfig,
+    active_orders: &mut Vec<u64>,
+    buy_orders: &mut HashMap<u64, OrderInfo>,
+    sell_orders: &mut HashMap<u64, OrderInfo>,
+    current_price: f64,
+) -> Result<usize, GridStrategyError> {
+    if !grid_config.cancel_protection || current_price <= 0.0 {
+        cancel_all_orders(exchange_client, active_orders, &grid_config.trading_asset).await?;
+        buy_orders.clear();
+        sell_orders.clear();
+        return Ok(0);
+    }
+
+    let mut kept = Vec::new();
+    let mut to_cancel = Vec::new();
+    for &oid in active_orders.iter() {
+        let info = buy_orders.get(&oid).or_else(|| sell_orders.get(&oid));
+        match info {
+            Some(info) => {
+                let score = order_retention_score(info, current_price, grid_config);
+                if score >= grid_config.cancel_protection_min_score {
+                    info!(
+                        "🛡️ 保留高价值挂单 - ID: {}, {}单, 价格: {:.4}, 评分: {:.1}",
+                        oid,
+                        if info.is_buy { "买" } else { "卖" },
+                        info.price,
+                        score
+                    );
+                    kept.push(oid);
+                } else {
+                    debug!("🗑️ 挂单 {} 评分 {:.1}，需要撤销", oid, score);
+                    to_cancel.push(oid);
+                }
+            }
+            None => to_cancel.push(oid),
+        }
+    }
+
+    for oid in &to_cancel {
+        buy_orders.remove(oid);
+        sell_orders.remove(oid);
+    }
+    if !to_cancel.is_empty() {
+        cancel_all_orders(exchange_client, &mut to_cancel, &grid_config.trading_asset).await?;
+    }
+
+    info!(
+        "📊 智能撤单 - 撤销: {}, 保留: {} (最低保留评分: {:.1})",
+        active_orders.len() - kept.len(),
+        kept.len(),
+        grid_config.cancel_protection_min_score
+    );
+    let kept_count = kept.len();
+    *active_orders = kept;
+    Ok(kept_count)
+}
+
+// 取消单个订单 - 带资产参数的版本
+pub(super) async fn cancel_order_with_asset(
+    exchange_client: &dyn OrderExecutor,
+    oid: u64,
+    trading_asset: &str,
+) -> Result<(), GridStrategyError> {
+    let cancel_request = ClientCancelRequest {
+        asset: trading_asset.to_string(),
+        oid,
+    };
+
+    match exchange_client.cancel(cancel_request, None).await {
+        Ok(_) => {
+            info!("✅ 订单 {} ({}) 已取消", oid, trading_asset);
+            Ok(())
+        }
+        Err(e) => {
+            warn!("❌ 取消订单 {} ({}) 失败: {:?}", oid, trading_asset, e);
+            Err(GridStrategyError::OrderError(format!(
+                "取消订单失败: {:?}",
+                e
+            )))
+        }
+    }
+}
+
+// 保持向后兼容的旧版本函数
+pub(super) async fn cancel_order(
+    exchange_client: &dyn OrderExecutor,
+    oid: u64,
+) -> Result<(), GridStrategyError> {
+    // 使用默认资产名称的后备方案
+    cancel_order_with_asset(exchange_client, oid, "BTC").await
+}
+
+// 订单创建结果统计
+#[derive(Debug, Clone)]
+pub(super) struct OrderCreationStats {
+    pub(super) total_orders: usize,
+    pub(super) successful_orders: usize,
+    pub(super) failed_orders: usize,
+    pub(super) retried_orders: usize,
+    pub(super) processing_time: Duration,
+    pub(super) success_rate: f64,
+}
+
+impl OrderCreationStats {
+    pub(super) fn new(total: usize) -> Self {
+        Self {
+            total_orders: total,
+            successful_orders: 0,
+            failed_orders: 0,
+            retried_orders: 0,
+            processing_time: Duration::default(),
+            success_rate: 0.0,
+        }
+    }
+
+    pub(super) fn update_success_rate(&mut self) {
+        self.success_rate = if self.total_orders > 0 {
+            self.successful_orders as f64 / self.total_orders as f64 * 100.0
+        } else {
+            0.0
+        };
+    }
+}
+
+// 增强版批量订单创建 - 包含资源管理、超时控制和错误恢复
+pub(super) async fn create_orders_in_batches(
+    exchange_client: &dyn OrderExecutor,
+    orders: Vec<ClientOrderRequest>,
+    grid_config: &crate::config::GridConfig,
+    grid_state: &mut GridState,
+    batch_optimizer: &mut BatchTaskOptimizer,
+) -> Result<(Vec<(u64, Option<Uuid>)>, Vec<OrderRequestInfo>), GridStrategyError> {
+    let start_time = SystemTime::now();
+    let mut created_order_ids = Vec::new();
+    let mut all_failed_order_infos = Vec::new();
+
+    if orders.is_empty() {
+        return Ok((created_order_ids, all_failed_order_infos));
+    }
+
+    // 资源限制检查
+    let max_total_orders = 500; // 单次最多创建500个订单
+    if orders.len() > max_total_orders {
+        warn!(
+            "⚠️ 订单数量({})超过限制({})，将只处理前{}个订单",
+            orders.len(),
+            max_total_orders,
+            max_total_orders
+        );
+    }
+
+    let orders_to_process: Vec<_> = orders.into_iter().take(max_total_orders).collect();
+    let mut stats = OrderCreationStats::new(orders_to_process.len());
+
+    // 请求节奏由全局 API 限流器控制，这里不再单独等待批次间延迟
+    // 使用批处理优化器确定最优批次大小
+    let optimal_batch_size = batch_optimizer.optimize_batch_size(orders_to_process.len());
+
+    info!(
+        "📦 开始智能批量创建订单 - 总数: {}, 优化批次大小: {}, 限流: {}次/秒",
+        orders_to_process.len(),
+        optimal_batch_size,
+        grid_config.api_requests_per_second
+    );
+    info!(
+        "⚡ 批处理优化器状态: 目标时间={:.2}秒, 历史记录={}次, 近期成功率={:.1}%",
+        batch_optimizer.get_target_execution_time().as_secs_f64(),
+        batch_optimizer.get_execution_history_count(),
+        batch_optimizer.get_success_rate() * 100.0
+    );
+
+    // 超时控制 - 总体处理时间限制
+    let max_total_time = Duration::from_secs(300); // 5分钟总超时
+    let batch_timeout = Duration::from_secs(30); // 单批次30秒超时
+
+    // 分批处理订单
+    let mut order_iter = orders_to_process.into_iter();
+    let mut batch_count = 0;
+
+    loop {
+        // 检查总体超时
+        if start_time.elapsed().unwrap_or_default() > max_total_time {
+            warn!("⚠️ 批量订单创建总体超时，停止处理剩余订单");
+            break;
+        }
+
+        let mut current_batch = Vec::new();
+
+        // 收集当前批次的订单
+        for _ in 0..optimal_batch_size {
+            if let Some(order) = order_iter.next() {
+                current_batch.push(order);
+            } else {
+                break;
+            }
+        }
+
+        if current_batch.is_empty() {
+            break;
+        }
+
+        batch_count += 1;
+        let batch_start_time = SystemTime::now();
+        let current_batch_len = current_batch.len(); // 在移动前保存长度
+        info!(
+            "📋 处理第{}批订单，数量: {}",
+            batch_count, current_batch_len
+        );
+
+        // 批次级别的超时控制
+        let batch_result = tokio::time::timeout(
+            batch_timeout,
+            process_order_batch(exchange_client, current_batch, grid_config),
+        )
+        .await;
+
+        match batch_result {
+            Ok(Ok((successful_ids, failed_order_infos))) => {
+                // 批次处理成功
+                let successful_count = successful_ids.len();
+                let failed_count = failed_order_infos.len();
+
+                created_order_ids.extend(successful_ids);
+                stats.successful_orders += successful_count;
+                stats.failed_orders += failed_count;
+
+                // 收集失败的订单信息用于重试
+                all_failed_order_infos.extend(failed_order_infos);
+
+                let batch_time = batch_start_time.elapsed().unwrap_or_default();
+                info!(
+                    "✅ 第{}批处理完成 - 成功: {}, 失败: {}, 耗时: {}ms",
+                    batch_count,
+                    successful_count,
+                    failed_count,
+                    batch_time.as_millis()
+                );
+            }
+            Ok(Err(e)) => {
+                // 批次处理失败
+                warn!("❌ 第{}批处理失败: {:?}", batch_count, e);
+                stats.failed_orders += current_batch_len;
+            }
+            Err(_) => {
+                // 批次超时
+                warn!("⏰ 第{}批处理超时", batch_count);
+                stats.failed_orders += current_batch_len;
+            }
+        }
+    }
+
+    // 重试失败的订单（最多重试一次）
+    if !all_failed_order_infos.is_empty() && all_failed_order_infos.len() <= 50 {
+        info!("🔄 开始重试{}个失败的订单", all_failed_order_infos.len());
+
+        let retry_result = tokio::time::timeout(
+            Duration::from_secs(60), // 重试阶段1分钟超时
+            retry_failed_order_infos(exchange_client, all_failed_order_infos.clone(), grid_config),
+        )
+        .await;
+
+        match retry_result {
+            Ok(Ok(retry_successful_orders)) => {
+                created_order_ids.extend(
+                    retry_successful_orders
+                        .iter()
+                        .map(|(oid, info)| (*oid, info.cloid)),
+                );
+                stats.successful_orders += retry_successful_orders.len();
+                stats.retried_orders = retry_successful_orders.len();
+                info!("✅ 重试完成 - 成功: {}", retry_successful_orders.len());
+                // 清空已重试的失败订单
+                all_failed_order_infos.clear();
+            }
+            Ok(Err(e)) => {
+                warn!("❌ 重试失败: {:?}", e);
+            }
+            Err(_) => {
+                warn!("⏰ 重试超时");
+            }
+        }
+    } else if all_failed_order_infos.len() > 50 {
+        warn!(
+            "⚠️ 失败订单数量过多({}个)，跳过重试",
+            all_failed_order_infos.len()
+        );
+    }
+
+    // 更新统计信息
+    stats.processing_time = start_time.elapsed().unwrap_or_default();
+    stats.update_success_rate();
+
+    // 记录批处理执行时间和首轮下单结果到优化器（重试成功的订单在首轮计为失败）
+    batch_optimizer.record_execution_time(
+        stats.processing_time,
+        stats.successful_orders - stats.retried_orders,
+        stats.failed_orders,
+    );
+
+    // 更新最后批次时间
+    grid_state.last_order_batch_time = SystemTime::now();
+
+    // 输出详细统计
+    info!("📊 批量订单创建统计:");
+    info!("   总订单数: {}", stats.total_orders);
+    info!("   成功创建: {}", stats.successful_orders);
+    info!("   创建失败: {}", stats.failed_orders);
+    info!("   重试成功: {}", stats.retried_orders);
+    info!("   成功率: {:.1}%", stats.success_rate);
+    info!("   总耗时: {}ms", stats.processing_time.as_millis());
+    info!(
+        "   平均每订单: {:.1}ms",
+        stats.processing_time.as_millis() as f64 / stats.total_orders as f64
+    );
+
+    // 性能警告
+    if stats.success_rate < 80.0 {
+        warn!(
+            "⚠️ 订单创建成功率较低({:.1}%)，建议检查网络连接和API限制",
+            stats.success_rate
+        );
+    }
+
+    if stats.processing_time.as_secs() > 120 {
+        warn!(
+            "⚠️ 订单创建耗时较长({}秒)，建议优化批次大小",
+            stats.processing_time.as_secs()
+        );
+    }
+
+    info!(
+        "✅ 增强批量订单创建完成 - 成功创建: {}/{}",
+        created_order_ids.len(),
+        stats.total_orders
+    );
+    Ok((created_order_ids, all_failed_order_infos))
+}
+
+// 订单信息结构体，用于重建失败的订单
+#[derive(Debug, Clone)]
+pub(super) struct OrderRequestInfo {
+    pub(super) asset: String,
+    pub(super) is_buy: bool,
+    pub(super) reduce_only: bool,
+    pub(super) limit_px: f64,
+    pub(super) sz: f64,
+    pub(super) tif: String,
+    pub(super) cloid: Option<Uuid>,
+}
+
+impl OrderRequestInfo {
+    pub(super) fn from_client_order_request(order: &ClientOrderRequest) -> Self {
+        Self {
+            asset: order.asset.clone(),
+            is_buy: order.is_buy,
+            reduce_only: order.reduce_only,
+            limit_px: order.limit_px,
+            sz: order.sz,
+            tif: match &order.order_type {
+                ClientOrder::Limit(limit) => limit.tif.clone(),
+                _ => "Gtc".to_string(),
+            },
+            cloid: order.cloid,
+        }
+    }
+
+    pub(super) fn to_client_order_request(&self) -> ClientOrderRequest {
+        ClientOrderRequest {
+            asset: self.asset.clone(),
+            is_buy: self.is_buy,
+            reduce_only: self.reduce_only,
+            limit_px: self.limit_px,
+            sz: self.sz,
+            cloid: self.cloid,
+            order_type: ClientOrder::Limit(ClientLimit {
+                tif: self.tif.clone(),
+            }),
+        }
+    }
+
+    /// 根据请求中的价格和数量还原订单信息
+    pub(super) fn to_order_info(&self) -> OrderInfo {
+        OrderInfo {
+            is_buy: self.is_buy,
+            price: self.limit_px,
+            quantity: self.sz,
+            cost_price: None,
+            potential_sell_price: None,
+            allocated_funds: if self.is_buy {
+                self.limit_px * self.sz
+            } else {
+                0.0 // 卖单不占用资金
+            },
+            iceberg: None,
+            created_at: safe_unix_timestamp(),
+            cloid: self.cloid,
+            filled_quantity: 0.0,
+            unhedged_quantity: 0.0,
+            grid_pair: false,
+        }
+    }
+}
+
+// 处理单个批次的订单
+pub(super) async fn process_order_batch(
+    exchange_client: &dyn OrderExecutor,
+    orders: Vec<ClientOrderRequest>,
+    grid_config: &crate::config::GridConfig,
+) -> Result<(Vec<(u64, Option<Uuid>)>, Vec<OrderRequestInfo>), GridStrategyError> {
+    let mut successful_ids = Vec::new();
+    let mut failed_order_infos = Vec::new();
+
+    for order in orders {
+        // 保存订单信息用于失败重试
+        let order_info = OrderRequestInfo::from_client_order_request(&order);
+
+        // 单个订单超时控制
+        let order_result = tokio::time::timeout(
+            Duration::from_secs(10), // 单个订单10秒超时
+            submit_grid_order(exchange_client, order, grid_config),
+        )
+        .await;
+
+        match order_result {
+            Ok(Ok(response)) => match parse_order_response(&response) {
+                Ok(oid) => {
+                    successful_ids.push((oid, order_info.cloid));
+                    info!("✅ 订单创建成功: ID={}", oid);
+                }
+                Err(e) => {
+                    warn!("❌ 订单创建失败: {}", e);
+                    failed_order_infos.push(order_info);
+                }
+            },
+            Ok(Err(e)) => {
+                warn!("❌ 订单创建失败: {:?}", e);
+                failed_order_infos.push(order_info);
+            }
+            Err(_) => {
+                warn!("⏰ 订单创建超时");
+                failed_order_infos.push(order_info);
+            }
+        }
+    }
+
+    info!(
+        "📊 批次处理完成 - 成功: {}, 失败: {}",
+        successful_ids.len(),
+        failed_order_infos.len()
+    );
+    Ok((successful_ids, failed_order_infos))
+}
+
+// 重试失败的订单
+#[allow(dead_code)]
+pub(super) async fn retry_failed_orders(
+    exchange_client: &dyn OrderExecutor,
+    failed_orders: Vec<ClientOrderRequest>,
+    grid_config: &crate::config::GridConfig,
+) -> Result<Vec<u64>, GridStrategyError> {
+    let mut successful_ids = Vec::new();
+
+    info!("🔄 开始重试{}个失败订单", failed_orders.len());
+
+    // 请求节奏由执行器内的全局令牌桶控制
+    for order in failed_orders {
+        let retry_result = tokio::time::timeout(
+            Duration::from_secs(15), // 重试时使用更长的超时时间
+            submit_grid_order(exchange_client, order, grid_config),
+        )
+        .await;
+
+        match retry_result {
+            Ok(Ok(response)) => match parse_order_response(&response) {
+                Ok(oid) => {
+                    successful_ids.push(oid);
+                    info!("🔄✅ 重试订单成功: ID={}", oid);
+                }
+                Err(e) => warn!("🔄❌ 重试订单失败: {}", e),
+            },
+            Ok(Err(e)) => {
+                warn!("🔄❌ 重试订单失败: {:?}", e);
+            }
+            Err(_) => {
+                warn!("🔄⏰ 重试订单超时");
+            }
+        }
+    }
+
+    info!("🔄✅ 重试完成 - 成功: {}", successful_ids.len());
+    Ok(successful_ids)
+}
+
+// 重试失败的订单信息，返回成功订单的ID及其对应的订单信息
+pub(super) async fn retry_failed_order_infos(
+    exchange_client: &dyn OrderExecutor,
+    failed_order_infos: Vec<OrderRequestInfo>,
+    grid_config: &crate::config::GridConfig,
+) -> Result<Vec<(u64, OrderInfo)>, GridStrategyError> {
+    let mut successful_orders = Vec::new();
+
+    info!("🔄 开始重试{}个失败订单", failed_order_infos.len());
+
+    // 请求节奏由执行器内的全局令牌桶控制
+    for request_info in failed_order_infos {
+        // 重建订单请求
+        let order = request_info.to_client_order_request();
+
+        let retry_result = tokio::time::timeout(
+            Duration::from_secs(15), // 重试时使用更长的超时时间
+            submit_grid_order(exchange_client, order, grid_config),
+        )
+        .await;
+
+        match retry_result {
+            Ok(Ok(response)) => match parse_order_response(&response) {
+                Ok(oid) => {
+                    successful_orders.push((oid, request_info.to_order_info()));
+                    info!("🔄✅ 重试订单成功: ID={}", oid);
+                }
+                Err(e) => warn!("🔄❌ 重试订单失败: {}", e),
+            },
+            Ok(Err(e)) => {
+                warn!("🔄❌ 重试订单失败: {:?}", e);
+            }
+            Err(_) => {
+                warn!("🔄⏰ 重试订单超时");
+            }
+        }
+    }
+
+    info!("🔄✅ 重试完成 - 成功: {}", successful_orders.len());
+    Ok(successful_orders)
+}
+
+// 单个创建订单模式 - 用于批量创建失败后的恢复
+pub(super) async fn create_orders_individually(
+    exchange_client: &dyn OrderExecutor,
+    order_infos: &[OrderInfo],
+    grid_config: &crate::config::GridConfig,
+    active_orders: &mut Vec<u64>,
+    orders_map: &mut HashMap<u64, OrderInfo>,
+    is_buy_order: bool,
+) -> Result<usize, GridStrategyError> {
+    let mut success_count = 0;
+
+    info!(
+        "🔄 开始单个创建模式 - 订单数: {}, 类型: {}",
+        order_infos.len(),
+        if is_buy_order { "买单" } else { "卖单" }
+    );
+
+    // 请求节奏由执行器内的全局令牌桶控制，订单之间不再固定等待
+    for order_info in order_infos {
+        // 没有 cloid 的订单在这里生成，并写回保存的订单信息，成交事件才能按 cloid 匹配
+        let mut order_info = order_info.clone();
+        let cloid = *order_info.cloid.get_or_insert_with(new_cloid);
+
+        // 创建订单请求
+        let order_request = ClientOrderRequest {
+            asset: grid_config.trading_asset.clone(),
+            is_buy: is_buy_order,
+            reduce_only: false,
+            limit_px: order_info.price,
+            sz: order_info.quantity,
+            cloid: Some(cloid),
+            order_type: ClientOrder::Limit(ClientLimit {
+                tif: grid_order_tif(grid_config),
+            }),
+        };
+
+        // 单个订单超时控制
+        let order_result = tokio::time::timeout(
+            Duration::from_secs(15), // 单个订单15秒超时
+            submit_grid_order(exchange_client, order_request, grid_config),
+        )
+        .await;
+
+        match order_result {
+            Ok(Ok(response)) => match parse_order_response(&response) {
+                Ok(oid) => {
+                    active_orders.push(oid);
+                    orders_map.insert(oid, order_info.clone());
+                    success_count += 1;
+
+                    info!(
+                        "🔄✅ 单个{}创建成功: ID={}, 价格={:.4}, 数量={:.4}",
+                        if is_buy_order { "买单" } else { "卖单" },
+                        oid,
+                        order_info.price,
+                        order_info.quantity
+                    );
+                }
+                Err(e) => warn!(
+                    "🔄❌ 单个{}创建失败: {}",
+                    if is_buy_order { "买单" } else { "卖单" },
+                    e
+                ),
+            },
+            Ok(Err(e)) => {
+                warn!(
+                    "🔄❌ 单个{}创建失败: {:?}",
+                    if is_buy_order { "买单" } else { "卖单" },
+                    e
+                );
+            }
+            Err(_) => {
+                warn!(
+                    "🔄⏰ 单个{}创建超时",
+                    if is_buy_order { "买单" } else { "卖单" }
+                );
+            }
+        }
+    }
+
+    info!(
+        "🔄✅ 单个创建模式完成 - 成功: {}/{}",
+        success_count,
+        order_infos.len()
+    );
+    Ok(success_count)
+}
+
+// 订单状态对账：以交易所未成交订单列表为准同步本地挂单
+//
+// 本地有而交易所没有的订单：在最近成交记录中找到成交的，按实盘成交事件格式重新推送到主循环处理；
+// 查询确认已撤销/被拒的直接从本地移除；仍为 open 的（列表刚好滞后）保留。
+pub(super) async fn sync_open_orders(
+    info_client: &InfoClient,
+    user_address: ethers::types::Address,
+    active_orders: &mut Vec<u64>,
+    buy_orders: &mut HashMap<u64, OrderInfo>,
+    sell_orders: &mut HashMap<u64, OrderInfo>,
+    fill_sender: &mpsc::UnboundedSender<Message>,
+) -> Result<(), GridStrategyError> {
+    let start_time = SystemTime::now();
+
+    // 使用超时控制的API调用
+    acquire_api_permit().await;
+    let open_orders = match tokio::time::timeout(
+        Duration::from_secs(10),
+        info_client.open_orders(user_address),
+    )
+    .await
+    {
+        Ok(Ok(orders)) => orders,
+        Ok(Err(e)) => {
+            return Err(GridStrategyError::ClientError(format!(
+                "获取开放订单失败: {:?}",
+                e
+            )));
+        }
+        Err(_) => {
+            warn!("⚠️ 获取开放订单超时，跳过本次检查");
+            return Ok(()); // 超时时不返回错误，避免阻塞主流程
+        }
+    };
+    let open_order_ids: std::collections::HashSet<u64> =
+        open_orders.iter().map(|order| order.oid).collect();
+
+    let mut missing: Vec<u64> = buy_orders
+        .keys()
+        .chain(sell_orders.keys())
+        .chain(active_orders.iter())
+        .filter(|oid| !open_order_ids.contains(oid))
+        .copied()
+        .collect();
+    missing.sort_unstable();
+    missing.dedup();
+
+    info!(
+        "🔍 订单状态对账 - 本地挂单: {}, 交易所挂单: {}, 待核实: {}",
+        buy_orders.len() + sell_orders.len(),
+        open_order_ids.len(),
+        missing.len()
+    );
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    acquire_api_permit().await;
+    let recent_fills = match info_client.user_fills(user_address).await {
+        Ok(fills) => fills,
+        Err(e) => {
+            return Err(GridStrategyError::ClientError(format!(
+                "获取成交记录失败: {:?}",
+                e
+            )));
+        }
+    };
+
+    let mut missed_fills = Vec::new();
+    let mut removed = Vec::new();
+    for oid in missing {
+        let cloid = buy_orders
+            .get(&oid)
+            .or_else(|| sell_orders.get(&oid))
+            .and_then(|order| order.cloid)
+            .map(|cloid| format!("0x{}", cloid.simple()));
+        let fills: Vec<_> = recent_fills.iter().filter(|fill| fill.oid == oid).collect();
+        if !fills.is_empty() {
+            info!(
+                "📋 订单{}已在交易所成交（{}笔），补发成交事件",
+                oid,
+                fills.len()
+            );
+            missed_fills.extend(fills.into_iter().map(|fill| {
+                serde_json::json!({
+                    "coin": fill.coin,
+                    "side": fill.side,
+                    "px": fill.px,
+                    "sz": fill.sz,
+                    "time": fill.time,
+                    "hash": fill.hash,
+                    "startPosition": fill.start_position,
+                    "dir": fill.dir,
+                    "closedPnl": fill.closed_pnl,
+                    "oid": fill.oid,
+                    "cloid": cloid,
+                    "crossed": fill.crossed,
+                    "fee": fill.fee,
+                    "tid": 0,
+                    "feeToken": "USDC",
+                })
+            }));
+            continue;
+        }
+
+        // 成交记录里没有，逐个查询订单最终状态
+        acquire_api_permit().await;
+        let status = match info_client.query_order_by_oid(user_address, oid).await {
+            Ok(response) => response
+                .order
+                .map(|order| order.status)
+                .unwrap_or(response.status),
+            Err(e) => {
+                warn!("⚠️ 查询订单{}状态失败: {:?}，下次对账再处理", oid, e);
+                continue;
+            }
+        };
+        match status.as_str() {
+            "open" => debug!("📋 订单{}仍在挂单中，保留", oid),
+            "filled" => {
+                warn!(
+                    "⚠️ 订单{}已成交但不在最近成交记录中，移除本地挂单，持仓由对账修正",
+                    oid
+                );
+                removed.push(oid);
+            }
+            other => {
+                info!(
+                    "📋 订单{}已在交易所结束（状态: {}），移除本地挂单",
+                    oid, other
+                );
+                removed.push(oid);
+            }
+        }
+    }
+
+    for oid in &removed {
+        buy_orders.remove(oid);
+        sell_orders.remove(oid);
+    }
+    active_orders.retain(|oid| !removed.contains(oid));
+
+    let missed_count = missed_fills.len();
+    if !missed_fills.is_empty() {
+        let payload = serde_json::json!({
+            "channel": "user",
+            "data": { "fills": missed_fills },
+        });
+        match serde_json::from_value::<Message>(payload) {
+            Ok(message) => {
+                if fill_sender.send(message).is_err() {
+                    warn!("⚠️ 补发成交事件失败，消息通道已关闭");
+                }
+            }
+            Err(e) => warn!("⚠️ 构造补发成交事件失败: {:?}", e),
+        }
+    }
+
+    info!(
+        "✅ 订单状态对账完成 - 处理时间: {}ms, 补发成交: {}, 移除订单: {}",
+        start_time.elapsed().unwrap_or_default().as_millis(),
+        missed_count,
+        removed.len()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyperliquid_rust_sdk::{ExchangeDataStatuses, ExchangeResponse, FilledOrder, RestingOrder};
+
+    fn response(statuses: Vec<ExchangeDataStatus>) -> ExchangeResponseStatus {
+        ExchangeResponseStatus::Ok(ExchangeResponse {
+            response_type: "order".to_string(),
+            data: Some(ExchangeDataStatuses { statuses }),
+        })
+    }
+
+    fn resting(oid: u64) -> ExchangeDataStatus {
+        ExchangeDataStatus::Resting(RestingOrder { oid })
+    }
+
+    fn filled(oid: u64, total_sz: &str, avg_px: &str) -> ExchangeDataStatus {
+        ExchangeDataStatus::Filled(FilledOrder {
+            total_sz: total_sz.to_string(),
+            avg_px: avg_px.to_string(),
+            oid,
+        })
+    }
+
+    fn order_error(response: &ExchangeResponseStatus) -> String {
+        match parse_order_response(response) {
+            Err(GridStrategyError::OrderError(message)) => message,
+            other => panic!("expected OrderError, got {:?}", other),
+        }
+    }
+
+    fn order_at(price: f64) -> OrderInfo {
+        OrderInfo {
+            is_buy: true,
+            price,
+            quantity: 1.0,
+            cost_price: None,
+            potential_sell_price: None,
+            allocated_funds: price,
+            iceberg: None,
+            created_at: 0,
+            cloid: None,
+            filled_quantity: 0.0,
+            unhedged_quantity: 0.0,
+            grid_pair: false,
+        }
+    }
+
+    #[test]
+    fn get_next_order_returns_highest_priority() {
+        let mut manager = OrderManager::new(10);
+        manager
+            .add_order(PrioritizedOrderInfo::new_low_priority(
+                order_at(95.0),
+                100.0,
+            ))
+            .unwrap();
+        manager
+            .add_order(PrioritizedOrderInfo::new(
+                order_at(99.0),
+                OrderPriority::Normal,
+                ExpiryStrategy::Reprice,
+                100.0,
+            ))
+            .unwrap();
+        manager
+            .add_order(PrioritizedOrderInfo::new_high_priority(
+                order_at(98.0),
+                100.0,
+                None,
+            ))
+            .unwrap();
+        manager
+            .add_order(PrioritizedOrderInfo::new_low_priority(
+                order_at(90.0),
+                100.0,
+            ))
+            .unwrap();
+
+        let next = manager.get_next_order().expect("队列不应为空");
+        assert_eq!(next.priority, OrderPriority::High);
+        assert_eq!(next.base_info.price, 98.0);
+
+        let priorities: Vec<OrderPriority> = manager
+            .prioritized_orders
+            .iter()
+            .map(|order| order.priority.clone())
+            .collect();
+        assert_eq!(
+            priorities,
+            vec![
+                OrderPriority::High,
+                OrderPriority::Normal,
+                OrderPriority::Low,
+                OrderPriority::Low
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_by_priority_keeps_insertion_order_for_equal_scores() {
+        let mut manager = OrderManager::new(10);
+        for price in [97.0, 97.0, 97.0] {
+            manager
+                .add_order(PrioritizedOrderInfo::new_low_priority(
+                    order_at(price),
+                    100.0,
+                ))
+                .unwrap();
+        }
+        manager.prioritized_orders[0].order_id = Some(1);
+        manager.prioritized_orders[1].order_id = Some(2);
+        manager.prioritized_orders[2].order_id = Some(3);
+
+        manager.sort_by_priority();
+        let ids: Vec<Option<u64>> = manager
+            .prioritized_orders
+            .iter()
+            .map(|order| order.order_id)
+            .collect();
+        assert_eq!(ids, vec![Some(1), Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn parse_order_response_accepts_resting_and_filled() {
+        assert_eq!(
+            parse_order_response(&response(vec![resting(11)])).unwrap(),
+            11
+        );
+        assert_eq!(
+            parse_order_response(&response(vec![filled(12, "0.5", "100.0")])).unwrap(),
+            12
+        );
+    }
+
+    #[test]
+    fn parse_order_response_rejects_errors_and_missing_status() {
+        let rejected = response(vec![ExchangeDataStatus::Error(
+            "Insufficient margin".to_string(),
+        )]);
+        assert!(order_error(&rejected).contains("Insufficient margin"));
+
+        let exchange_error = ExchangeResponseStatus::Err("rate limited".to_string());
+        assert!(order_error(&exchange_error).contains("rate limited"));
+
+        assert!(order_error(&response(Vec::new())).contains("未找到订单状态"));
+
+        let no_data = ExchangeResponseStatus::Ok(ExchangeResponse {
+            response_type: "order".to_string(),
+            data: None,
+        });
+        assert!(order_error(&no_data).contains("未找到订单状态"));
+
+        assert!(order_error(&response(vec![ExchangeDataStatus::Success])).contains("未找到订单ID"));
+    }
+
+    #[test]
+    fn parse_order_response_uses_first_status_of_mixed_batch() {
+        // 单笔下单只看第一个状态，后面的状态不影响结果
+        let resting_first = response(vec![
+            resting(21),
+            ExchangeDataStatus::Error("Post only order would have immediately matched".to_string()),
+            filled(22, "1", "99.5"),
+        ]);
+        assert_eq!(parse_order_response(&resting_first).unwrap(), 21);
+
+        let filled_first = response(vec![filled(23, "1", "99.5"), resting(24)]);
+        assert_eq!(parse_order_response(&filled_first).unwrap(), 23);
+
+        let error_first = response(vec![
+            ExchangeDataStatus::Error("Order has invalid price".to_string()),
+            resting(25),
+        ]);
+        assert!(order_error(&error_first).contains("invalid price"));
+    }
+
+    #[test]
+    fn immediate_fill_reads_only_filled_status() {
+        assert_eq!(
+            immediate_fill(&response(vec![filled(31, "0.25", "101.5")])),
+            Some((31, 0.25, 101.5))
+        );
+        assert_eq!(immediate_fill(&response(vec![resting(32)])), None);
+        assert_eq!(
+            immediate_fill(&response(vec![ExchangeDataStatus::Error(
+                "Insufficient margin".to_string()
+            )])),
+            None
+        );
+        assert_eq!(immediate_fill(&response(Vec::new())), None);
+        assert_eq!(
+            immediate_fill(&ExchangeResponseStatus::Err("rate limited".to_string())),
+            None
+        );
+        // 数量或均价无法解析时不当作立即成交
+        assert_eq!(
+            immediate_fill(&response(vec![filled(33, "abc", "101.5")])),
+            None
+        );
+    }
+}
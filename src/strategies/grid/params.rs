@@ -0,0 +1,2189 @@
+// 网格参数：动态参数、自适应订单配置、参数校验与优化
+
+use super::*;
+
+// 参数回滚检查点
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(super) struct ParameterCheckpoint {
+    pub(super) min_spacing: f64,
+    pub(super) max_spacing: f64,
+    pub(super) trade_amount: f64,
+    pub(super) checkpoint_time: u64, // Unix timestamp
+    pub(super) performance_before: f64,
+    pub(super) reason: String,
+}
+
+// 缺失字段（旧版状态文件）按以下默认值反序列化
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(super) struct AdaptiveOrderConfig {
+    // 基础配置
+    #[serde(default = "default_base_max_age_minutes")]
+    pub(super) base_max_age_minutes: f64, // 基础最大存活时间
+    #[serde(default = "default_min_age_minutes")]
+    pub(super) min_age_minutes: f64, // 最小存活时间
+    #[serde(default = "default_max_age_minutes")]
+    pub(super) max_age_minutes: f64, // 最大存活时间
+
+    // 市场状况适应
+    #[serde(default = "default_adaptive_factor")]
+    pub(super) volatility_factor: f64, // 波动率因子 (0.5-2.0)
+    #[serde(default = "default_adaptive_factor")]
+    pub(super) trend_factor: f64, // 趋势因子 (0.5-2.0)
+    #[serde(default = "default_adaptive_factor")]
+    pub(super) liquidity_factor: f64, // 流动性因子 (0.5-2.0)
+
+    // 性能适应
+    #[serde(default = "default_adaptive_factor")]
+    pub(super) success_rate_factor: f64, // 成功率因子
+    #[serde(default = "default_adaptive_factor")]
+    pub(super) profit_factor: f64, // 盈利因子
+
+    // 动态调整历史
+    #[serde(default)]
+    pub(super) adjustment_history: VecDeque<f64>, // 调整历史记录
+    #[serde(default = "safe_unix_timestamp")]
+    pub(super) last_adjustment_time: u64, // 上次调整时间
+    #[serde(default)]
+    pub(super) adjustment_count: u32, // 调整次数
+
+    // 统计信息
+    #[serde(default = "default_average_fill_time_minutes")]
+    pub(super) average_fill_time_minutes: f64, // 平均成交时间
+    #[serde(default = "default_order_success_rate")]
+    pub(super) order_success_rate: f64, // 订单成功率
+    #[serde(default = "default_recent_volatility")]
+    pub(super) recent_volatility: f64, // 最近波动率
+}
+
+fn default_base_max_age_minutes() -> f64 {
+    30.0
+}
+
+fn default_min_age_minutes() -> f64 {
+    0.5
+}
+
+fn default_max_age_minutes() -> f64 {
+    120.0
+}
+
+fn default_adaptive_factor() -> f64 {
+    1.0
+}
+
+fn default_average_fill_time_minutes() -> f64 {
+    15.0
+}
+
+fn default_order_success_rate() -> f64 {
+    0.8
+}
+
+fn default_recent_volatility() -> f64 {
+    0.02
+}
+
+// 组合因子的限幅范围：各因子连乘的理论范围约为 0.0076–7.4，先限幅再乘基础存活时间
+const MIN_COMBINED_AGE_FACTOR: f64 = 0.1;
+const MAX_COMBINED_AGE_FACTOR: f64 = 5.0;
+
+impl AdaptiveOrderConfig {
+    pub(super) fn new() -> Self {
+        Self {
+            base_max_age_minutes: 5.0, // 基础5分钟（与配置文件一致）
+            min_age_minutes: 2.0,      // 最小2分钟
+            max_age_minutes: 15.0,     // 最大15分钟
+
+            volatility_factor: 1.0,
+            trend_factor: 1.0,
+            liquidity_factor: 1.0,
+
+            success_rate_factor: 1.0,
+            profit_factor: 1.0,
+
+            adjustment_history: VecDeque::new(),
+            last_adjustment_time: safe_unix_timestamp(),
+            adjustment_count: 0,
+
+            average_fill_time_minutes: 15.0,
+            order_success_rate: 0.8,
+            recent_volatility: 0.02,
+        }
+    }
+
+    /// 根据市场状况计算自适应的订单最大存活时间
+    ///
+    /// 存活时间 = 基础存活时间 × 组合因子，组合因子为以下五个因子的乘积：
+    /// - 波动率因子：高波动（>5%）0.3，低波动（<1%）1.5–2.0，其余 1.0
+    /// - 趋势因子：单边趋势 0.6，震荡 1.4
+    /// - 市场状态因子：0.1（闪崩/闪涨）– 2.0（流动性不足）
+    /// - 成功率因子：成功率 <50% 为 0.6，>90% 为 1.2，其余 1.0
+    /// - 盈利因子：亏损超过总资金2% 为 0.7，盈利 1.1，其余 1.0
+    ///
+    /// 组合因子先限制在 0.1–5.0，结果再按 `min_age_minutes`–`max_age_minutes` 裁剪。
+    pub(super) fn calculate_adaptive_max_age(
+        &mut self,
+        market_analysis: &MarketAnalysis,
+        grid_state: &GridState,
+        current_success_rate: f64,
+    ) -> f64 {
+        let now = safe_unix_timestamp();
+
+        // 1. 基于市场波动率调整
+        let volatility_adjustment = if market_analysis.volatility > 0.05 {
+            // 高波动市场：缩短订单存活时间，快速响应
+            0.3 + (0.05 - market_analysis.volatility.min(0.05)) * 10.0
+        } else if market_analysis.volatility < 0.01 {
+            // 低波动市场：延长订单存活时间，减少频繁更新
+            1.5 + (0.01 - market_analysis.volatility) * 50.0
+        } else {
+            // 正常波动：基础倍数
+            1.0
+        };
+
+        // 2. 基于市场趋势调整
+        let trend_adjustment = match market_analysis.trend {
+            MarketTrend::Upward | MarketTrend::Downward => {
+                // 明显趋势：缩短存活时间，快速跟随趋势
+                0.6
+            }
+            MarketTrend::Sideways => {
+                // 震荡市场：延长存活时间，减少无效更新
+                1.4
+            }
+        };
+
+        // 3. 基于市场状态调整
+        let market_state_adjustment = match market_analysis.market_state {
+            MarketState::Normal => 1.0,
+            MarketState::HighVolatility => 0.4, // 高波动：快速更新
+            MarketState::Extreme => 0.2,        // 极端市场：极快更新
+            MarketState::ThinLiquidity => 2.0,  // 流动性不足：延长等待
+            MarketState::Flash => 0.1,          // 闪崩/闪涨：立即更新
+            MarketState::Consolidation => 1.8,  // 盘整：延长等待
+        };
+
+        // 4. 基于订单成功率调整
+        let success_rate_adjustment = if current_success_rate > 0.9 {
+            // 高成功率：可以延长等待时间
+            1.2
+        } else if current_success_rate < 0.5 {
+            // 低成功率：缩短等待时间，快速调整
+            0.6
+        } else {
+            1.0
+        };
+
+        // 5. 基于盈利情况调整
+        let profit_adjustment = if grid_state.realized_profit > 0.0 {
+            // 盈利状态：稍微保守，延长等待
+            1.1
+        } else if grid_state.realized_profit < -grid_state.total_capital * 0.02 {
+            // 亏损超过2%：激进调整，缩短等待
+            0.7
+        } else {
+            1.0
+        };
+
+        // 6. 综合计算，组合因子先限幅，避免极端组合把存活时间压到接近0或放大数倍
+        let raw_factor = volatility_adjustment
+            * trend_adjustment
+            * market_state_adjustment
+            * success_rate_adjustment
+            * profit_adjustment;
+        let combined_factor = raw_factor.clamp(MIN_COMBINED_AGE_FACTOR, MAX_COMBINED_AGE_FACTOR);
+        if combined_factor != raw_factor {
+            debug!(
+                "⏱️ 订单存活时间组合因子 {:.4} 超出范围，限幅为 {:.2} (波动率 {:.2} × 趋势 {:.2} × 市场状态 {:.2} × 成功率 {:.2} × 盈利 {:.2})",
+                raw_factor,
+                combined_factor,
+                volatility_adjustment,
+                trend_adjustment,
+                market_state_adjustment,
+                success_rate_adjustment,
+                profit_adjustment
+            );
+        }
+
+        let adaptive_age = self.base_max_age_minutes * combined_factor;
+
+        // 7. 应用边界限制
+        let final_age = adaptive_age
+            .max(self.min_age_minutes)
+            .min(self.max_age_minutes);
+
+        // 8. 记录调整历史
+        if (now - self.last_adjustment_time) > 300 {
+            // 5分钟记录一次
+            self.adjustment_history.push_back(final_age);
+            if self.adjustment_history.len() > 20 {
+                self.adjustment_history.pop_front();
+            }
+            self.last_adjustment_time = now;
+            self.adjustment_count += 1;
+        }
+
+        // 9. 更新统计信息
+        self.volatility_factor = volatility_adjustment;
+        self.trend_factor = trend_adjustment;
+        self.liquidity_factor = market_state_adjustment;
+        self.success_rate_factor = success_rate_adjustment;
+        self.profit_factor = profit_adjustment;
+        self.recent_volatility = market_analysis.volatility;
+        self.order_success_rate = current_success_rate;
+
+        final_age
+    }
+
+    /// 获取自适应配置报告
+    pub(super) fn get_adaptive_report(&self) -> String {
+        let avg_age = if self.adjustment_history.is_empty() {
+            self.base_max_age_minutes
+        } else {
+            self.adjustment_history.iter().sum::<f64>() / self.adjustment_history.len() as f64
+        };
+
+        format!(
+            "📊 自适应订单配置状态:\n\
+             ├─ 当前基础存活时间: {:.1}分钟\n\
+             ├─ 平均调整后时间: {:.1}分钟\n\
+             ├─ 调整范围: {:.1}-{:.1}分钟\n\
+             ├─ 波动率因子: {:.2}x\n\
+             ├─ 趋势因子: {:.2}x\n\
+             ├─ 流动性因子: {:.2}x\n\
+             ├─ 成功率因子: {:.2}x\n\
+             ├─ 盈利因子: {:.2}x\n\
+             ├─ 调整次数: {}\n\
+             ├─ 订单成功率: {:.1}%\n\
+             └─ 最近波动率: {:.2}%",
+            self.base_max_age_minutes,
+            avg_age,
+            self.min_age_minutes,
+            self.max_age_minutes,
+            self.volatility_factor,
+            self.trend_factor,
+            self.liquidity_factor,
+            self.success_rate_factor,
+            self.profit_factor,
+            self.adjustment_count,
+            self.order_success_rate * 100.0,
+            self.recent_volatility * 100.0
+        )
+    }
+
+    /// 重置统计信息
+    pub(super) fn reset_stats(&mut self) {
+        self.adjustment_history.clear();
+        self.adjustment_count = 0;
+        self.last_adjustment_time = safe_unix_timestamp();
+    }
+
+    /// 手动调整基础参数
+    pub(super) fn adjust_base_parameters(
+        &mut self,
+        new_base_minutes: f64,
+        new_min: f64,
+        new_max: f64,
+    ) {
+        self.base_max_age_minutes = new_base_minutes.max(0.1).min(240.0);
+        self.min_age_minutes = new_min.max(0.1).min(60.0);
+        self.max_age_minutes = new_max.max(1.0).min(480.0);
+
+        // 确保逻辑关系正确
+        if self.min_age_minutes > self.base_max_age_minutes {
+            self.min_age_minutes = self.base_max_age_minutes * 0.1;
+        }
+        if self.max_age_minutes < self.base_max_age_minutes {
+            self.max_age_minutes = self.base_max_age_minutes * 3.0;
+        }
+    }
+}
+
+// 动态网格参数结构体
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(super) struct DynamicGridParams {
+    pub(super) current_min_spacing: f64,
+    pub(super) current_max_spacing: f64,
+    pub(super) current_trade_amount: f64,
+    pub(super) last_optimization_time: u64, // 改为Unix timestamp便于序列化
+    pub(super) optimization_count: u32,
+    pub(super) performance_window: VecDeque<f64>, // 滑动窗口性能记录
+    pub(super) checkpoints: VecDeque<ParameterCheckpoint>, // 回滚检查点
+    pub(super) last_checkpoint_time: u64,
+    pub(super) rollback_threshold: f64, // 回滚阈值（性能下降超过此值时回滚）
+    #[serde(default = "default_rollback_min_secs")]
+    pub(super) rollback_min_secs: u64, // 检查点创建后至少经过多少秒才考虑回滚
+}
+
+pub(super) fn default_rollback_min_secs() -> u64 {
+    6 * 60 * 60
+}
+
+impl DynamicGridParams {
+    pub(super) fn new(grid_config: &crate::config::GridConfig) -> Self {
+        Self {
+            current_min_spacing: grid_config.min_grid_spacing,
+            current_max_spacing: grid_config.max_grid_spacing,
+            current_trade_amount: grid_config.trade_amount,
+            last_optimization_time: safe_unix_timestamp(),
+            optimization_count: 0,
+            performance_window: VecDeque::new(),
+            checkpoints: VecDeque::new(),
+            last_checkpoint_time: safe_unix_timestamp(),
+            rollback_threshold: grid_config.rollback_threshold,
+            rollback_min_secs: (grid_config.rollback_min_hours * 3600.0) as u64,
+        }
+    }
+
+    // 从文件加载参数
+    pub(super) fn load_from_file(file_path: &str, grid_config: &crate::config::GridConfig) -> Self {
+        match std::fs::read_to_string(file_path) {
+            Ok(content) => {
+                match serde_json::from_str::<DynamicGridParams>(&content) {
+                    Ok(mut params) => {
+                        info!(
+                            "✅ 成功加载动态参数 - 优化次数: {}, 检查点数: {}",
+                            params.optimization_count,
+                            params.checkpoints.len()
+                        );
+
+                        // 回滚参数以配置为准，不沿用文件中的旧值
+                        params.rollback_threshold = grid_config.rollback_threshold;
+                        params.rollback_min_secs = (grid_config.rollback_min_hours * 3600.0) as u64;
+
+                        // 使用增强的参数验证
+                        let validation_result =
+                            validate_dynamic_parameters(&params, grid_config, "加载时");
+                        validation_result.log_results("动态参数加载");
+
+                        // 如果验证失败，进行参数修复
+                        if !validation_result.is_valid {
+                            warn!("⚠️ 加载的动态参数验证失败，进行自动修复");
+
+                            // 修复最小间距
+                            if params.current_min_spacing < grid_config.min_grid_spacing * 0.1
+                                || params.current_min_spacing > grid_config.max_grid_spacing
+                            {
+                                warn!(
+                                    "🔧 修复最小间距: {:.4}% -> {:.4}%",
+                                    params.current_min_spacing * 100.0,
+                                    grid_config.min_grid_spacing * 100.0
+                                );
+                                params.current_min_spacing = grid_config.min_grid_spacing;
+                            }
+
+                            // 修复最大间距
+                            if params.current_max_spacing < params.current_min_spacing
+                                || params.current_max_spacing > grid_config.max_grid_spacing * 2.0
+                            {
+                                warn!(
+                                    "🔧 修复最大间距: {:.4}% -> {:.4}%",
+                                    params.current_max_spacing * 100.0,
+                                    grid_config.max_grid_spacing * 100.0
+                                );
+                                params.current_max_spacing = grid_config.max_grid_spacing;
+                            }
+
+                            // 修复交易金额 - 移除对total_capital的依赖
+                            if params.current_trade_amount < grid_config.trade_amount * 0.1
+                                || params.current_trade_amount > grid_config.trade_amount * 10.0
+                            {
+                                warn!(
+                                    "🔧 修复交易金额: {:.2} -> {:.2}",
+                                    params.current_trade_amount, grid_config.trade_amount
+                                );
+                                params.current_trade_amount = grid_config.trade_amount;
+                            }
+
+                            // 修复回滚阈值
+                            if params.rollback_threshold <= 0.0 {
+                                warn!("🔧 修复回滚阈值: {:.1} -> 15.0", params.rollback_threshold);
+                                params.rollback_threshold = 15.0;
+                            }
+
+                            // 清理过多的检查点
+                            if params.checkpoints.len() > 20 {
+                                let excess = params.checkpoints.len() - 10;
+                                params.checkpoints.drain(0..excess);
+                                warn!("🔧 清理{}个过期检查点", excess);
+                            }
+
+                            // 清理过多的性能窗口数据
+                            if params.performance_window.len() > 50 {
+                                let excess = params.performance_window.len() - 20;
+                                params.performance_window.drain(0..excess);
+                                warn!("🔧 清理{}个过期性能数据", excess);
+                            }
+
+                            // 重新验证修复后的参数
+                            let fixed_validation =
+                                validate_dynamic_parameters(&params, grid_config, "修复后");
+                            if fixed_validation.is_valid {
+                                info!("✅ 参数修复成功");
+                            } else {
+                                warn!("⚠️ 参数修复后仍有问题，将使用默认参数");
+                                return Self::new(grid_config);
+                            }
+                        }
+
+                        params
+                    }
+                    Err(e) => {
+                        warn!("⚠️ 解析动态参数文件失败: {:?}，使用默认参数", e);
+                        Self::new(grid_config)
+                    }
+                }
+            }
+            Err(_) => {
+                info!("📄 动态参数文件不存在，创建新的参数配置");
+                Self::new(grid_config)
+            }
+        }
+    }
+
+    // 保存参数到文件
+    pub(super) fn save_to_file(&self, file_path: &str) -> Result<(), GridStrategyError> {
+        match serde_json::to_string_pretty(self) {
+            Ok(content) => match std::fs::write(file_path, content) {
+                Ok(_) => {
+                    info!("💾 动态参数已保存到文件: {}", file_path);
+                    Ok(())
+                }
+                Err(e) => {
+                    error!("❌ 保存动态参数失败: {:?}", e);
+                    Err(GridStrategyError::ConfigError(format!(
+                        "保存参数失败: {:?}",
+                        e
+                    )))
+                }
+            },
+            Err(e) => {
+                error!("❌ 序列化动态参数失败: {:?}", e);
+                Err(GridStrategyError::ConfigError(format!(
+                    "序列化参数失败: {:?}",
+                    e
+                )))
+            }
+        }
+    }
+
+    // 创建检查点
+    pub(super) fn create_checkpoint(&mut self, reason: String, current_performance: f64) {
+        let checkpoint = ParameterCheckpoint {
+            min_spacing: self.current_min_spacing,
+            max_spacing: self.current_max_spacing,
+            trade_amount: self.current_trade_amount,
+            checkpoint_time: safe_unix_timestamp(),
+            performance_before: current_performance,
+            reason: reason.clone(),
+        };
+
+        self.checkpoints.push_back(checkpoint);
+        self.last_checkpoint_time = safe_unix_timestamp();
+
+        // 保持最多10个检查点
+        if self.checkpoints.len() > 10 {
+            self.checkpoints.pop_front();
+        }
+
+        info!(
+            "📍 创建参数检查点 - 原因: {}, 性能: {:.1}, 检查点数: {}",
+            reason,
+            current_performance,
+            self.checkpoints.len()
+        );
+    }
+
+    // 检查是否需要回滚
+    pub(super) fn should_rollback(&self, current_performance: f64) -> Option<&ParameterCheckpoint> {
+        let latest_checkpoint = self.checkpoints.back()?;
+        let performance_decline = latest_checkpoint.performance_before - current_performance;
+
+        // 检查时间条件：优化后至少经过配置的冷却时间才考虑回滚
+        let time_since_checkpoint =
+            safe_unix_timestamp().saturating_sub(latest_checkpoint.checkpoint_time);
+
+        if time_since_checkpoint >= self.rollback_min_secs
+            && performance_decline > self.rollback_threshold
+        {
+            info!(
+                "🔄 检测到性能下降 {:.1}分，超过阈值 {:.1}分，建议回滚",
+                performance_decline, self.rollback_threshold
+            );
+            Some(latest_checkpoint)
+        } else {
+            None
+        }
+    }
+
+    // 执行回滚
+    pub(super) fn rollback_to_checkpoint(&mut self, checkpoint: &ParameterCheckpoint) {
+        info!("🔄 执行参数回滚:");
+        info!("   回滚原因: {}", checkpoint.reason);
+        info!("   回滚前性能: {:.1}", checkpoint.performance_before);
+        info!(
+            "   最小间距: {:.4}% -> {:.4}%",
+            self.current_min_spacing * 100.0,
+            checkpoint.min_spacing * 100.0
+        );
+        info!(
+            "   最大间距: {:.4}% -> {:.4}%",
+            self.current_max_spacing * 100.0,
+            checkpoint.max_spacing * 100.0
+        );
+        info!(
+            "   交易金额: {:.2} -> {:.2}",
+            self.current_trade_amount, checkpoint.trade_amount
+        );
+
+        self.current_min_spacing = checkpoint.min_spacing;
+        self.current_max_spacing = checkpoint.max_spacing;
+        self.current_trade_amount = checkpoint.trade_amount;
+
+        // 移除已回滚的检查点
+        self.checkpoints.pop_back();
+
+        info!("✅ 参数回滚完成");
+    }
+
+    /// 根据近期性能评分和市场分析小步调整网格间距与交易金额
+    ///
+    /// 调整前以当前参数创建检查点，调整结果经 `validate_parameter_optimization` 把关，
+    /// 未通过时恢复原参数。返回是否应用了新参数。
+    pub(super) fn optimize_parameters(
+        &mut self,
+        recent_performance: &[f64],
+        market_analysis: &MarketAnalysis,
+        grid_config: &crate::config::GridConfig,
+    ) -> bool {
+        let Some(&latest_score) = recent_performance.last() else {
+            return false;
+        };
+        let average_score =
+            recent_performance.iter().sum::<f64>() / recent_performance.len() as f64;
+        let improving = latest_score >= average_score;
+
+        // 间距：波动越大越宽，盘整时收窄以提高成交频率
+        let mut spacing_multiplier: f64 = match market_analysis.market_state {
+            MarketState::Extreme | MarketState::Flash => 1.03,
+            MarketState::HighVolatility => 1.02,
+            MarketState::Consolidation => 0.98,
+            MarketState::Normal | MarketState::ThinLiquidity => 1.0,
+        };
+        if market_analysis.volatility > 0.02 {
+            spacing_multiplier *= 1.01;
+        } else if market_analysis.volatility < 0.005 {
+            spacing_multiplier *= 0.99;
+        }
+
+        // 交易金额：表现好且未走弱时小幅放大，表现差时收缩
+        let mut amount_multiplier: f64 = 1.0;
+        if average_score >= 70.0 && improving {
+            spacing_multiplier *= 1.01;
+            amount_multiplier = 1.02;
+        } else if average_score <= 30.0 {
+            spacing_multiplier *= 0.99;
+            amount_multiplier = 0.95;
+        }
+        if matches!(
+            market_analysis.market_state,
+            MarketState::Extreme | MarketState::Flash | MarketState::ThinLiquidity
+        ) {
+            amount_multiplier = amount_multiplier.min(0.97);
+        }
+
+        // 每次最多调整5%间距、10%金额
+        let spacing_multiplier = spacing_multiplier.clamp(0.95, 1.05);
+        let amount_multiplier = amount_multiplier.clamp(0.9, 1.05);
+        if (spacing_multiplier - 1.0).abs() < 1e-4 && (amount_multiplier - 1.0).abs() < 1e-4 {
+            info!(
+                "📊 性能评分 {:.1}（均值 {:.1}），市场{}，参数保持不变",
+                latest_score,
+                average_score,
+                market_analysis.market_state.as_str()
+            );
+            return false;
+        }
+
+        let old_params = self.clone();
+        let reason = format!(
+            "自动优化（评分 {:.1}，市场{}，波动率 {:.2}%）",
+            latest_score,
+            market_analysis.market_state.as_str(),
+            market_analysis.volatility * 100.0
+        );
+        self.create_checkpoint(reason, latest_score);
+
+        // 间距下限不低于盈亏平衡间距的1.25倍
+        let min_spacing_floor = (grid_config.min_grid_spacing * 0.5)
+            .max(break_even_spacing(grid_config.maker_fee()) * 1.25);
+        let min_spacing_ceiling = (grid_config.max_grid_spacing * 0.8).max(min_spacing_floor);
+        self.current_min_spacing = (self.current_min_spacing * spacing_multiplier)
+            .clamp(min_spacing_floor, min_spacing_ceiling);
+        self.current_max_spacing = (self.current_max_spacing * spacing_multiplier).clamp(
+            self.current_min_spacing * 1.5,
+            grid_config
+                .max_grid_spacing
+                .max(self.current_min_spacing * 1.5),
+        );
+
+        let amount_floor = grid_config.trade_amount * 0.3;
+        let amount_ceiling = (grid_config.total_capital * 0.1)
+            .min(grid_config.trade_amount * 3.0)
+            .max(amount_floor);
+        self.current_trade_amount =
+            (self.current_trade_amount * amount_multiplier).clamp(amount_floor, amount_ceiling);
+
+        self.last_optimization_time = safe_unix_timestamp();
+        self.optimization_count += 1;
+
+        let validation =
+            validate_parameter_optimization(&old_params, self, grid_config, latest_score);
+        validation.log_results("参数优化");
+        if !validation.is_valid {
+            error!("❌ 参数优化验证失败，恢复优化前参数");
+            *self = old_params;
+            return false;
+        }
+
+        info!("✅ 自动优化完成 (第{}次):", self.optimization_count);
+        info!(
+            "   最小网格间距: {:.4}% -> {:.4}%",
+            old_params.current_min_spacing * 100.0,
+            self.current_min_spacing * 100.0
+        );
+        info!(
+            "   最大网格间距: {:.4}% -> {:.4}%",
+            old_params.current_max_spacing * 100.0,
+            self.current_max_spacing * 100.0
+        );
+        info!(
+            "   交易金额: {:.2} -> {:.2}",
+            old_params.current_trade_amount, self.current_trade_amount
+        );
+        true
+    }
+}
+
+// 计算考虑手续费后的最小卖出价格
+pub(super) fn calculate_min_sell_price(buy_price: f64, fee_rate: f64, min_profit_rate: f64) -> f64 {
+    let buy_cost = buy_price * (1.0 + fee_rate);
+    buy_cost * (1.0 + min_profit_rate) / (1.0 - fee_rate)
+}
+
+// 最小利润率：min_profit 是每格交易的最小净利润（绝对金额，单位USDC），
+// 除以每格交易金额 trade_amount 换算为相对买入成本的比率
+pub(super) fn min_profit_rate(grid_config: &crate::config::GridConfig) -> f64 {
+    grid_config.min_profit / grid_config.trade_amount
+}
+
+// 按建仓模型计算第 level_index 档（0为离中心最近）买单的资金
+// equal 每档相同；pyramid 每往下一档除以 position_sizing_ratio；reverse_pyramid 每往下一档乘以该倍数
+pub(super) fn position_sizing_funds(
+    grid_config: &crate::config::GridConfig,
+    base_funds: f64,
+    level_index: usize,
+) -> f64 {
+    let ratio = grid_config.position_sizing_ratio;
+    match grid_config.position_sizing.as_str() {
+        "pyramid" => base_funds / ratio.powi(level_index as i32),
+        "reverse_pyramid" => base_funds * ratio.powi(level_index as i32),
+        _ => base_funds,
+    }
+}
+
+// 买入方向的持仓价值上限：反金字塔模式下再受 martingale_max_funds 限制
+pub(super) fn max_buy_exposure(grid_config: &crate::config::GridConfig) -> f64 {
+    if grid_config.position_sizing == "reverse_pyramid" {
+        grid_config
+            .max_position
+            .min(grid_config.martingale_max_funds)
+    } else {
+        grid_config.max_position
+    }
+}
+
+// 买卖均按 fee_rate 计费时一买一卖的盈亏平衡间距：sell*(1-f) = buy*(1+f)
+// maker 返佣（负费率）时为负数，此时任意正间距都能盈利
+pub(super) fn break_even_spacing(fee_rate: f64) -> f64 {
+    2.0 * fee_rate / (1.0 - fee_rate)
+}
+
+// 计算预期利润率（负费率即返佣，会增加卖出收入、降低买入成本）
+// funding_cost 为持仓期间预期支付的资金费（占持仓价值比例），现货或只算已实现盈亏时传0
+pub(super) fn calculate_expected_profit_rate(
+    buy_price: f64,
+    sell_price: f64,
+    fee_rate: f64,
+    funding_cost: f64,
+) -> f64 {
+    let buy_cost = buy_price * (1.0 + fee_rate);
+    let sell_revenue = sell_price * (1.0 - fee_rate);
+    (sell_revenue - buy_cost) / buy_cost - funding_cost
+}
+
+// Hyperliquid 现货交易对名称为 "PURR/USDC" 或 "@107" 形式，其余为永续合约
+pub(super) fn is_perpetual_asset(asset: &str) -> bool {
+    !asset.starts_with('@') && !asset.contains('/')
+}
+
+// 每格持仓期间的预期资金费成本：最新小时费率 × funding_holding_hours
+// 网格买入后持多仓，费率为正时多头付费；费率为负时不计入收益，保守处理
+pub(super) fn expected_funding_cost(
+    funding_rate: f64,
+    grid_config: &crate::config::GridConfig,
+) -> f64 {
+    if !grid_config.funding_cost_enabled || !is_perpetual_asset(&grid_config.trading_asset) {
+        return 0.0;
+    }
+    funding_rate.max(0.0) * grid_config.funding_holding_hours
+}
+
+// 参数验证结果结构体
+#[derive(Debug, Clone)]
+pub(super) struct ValidationResult {
+    pub(super) is_valid: bool,
+    pub(super) warnings: Vec<String>,
+    pub(super) errors: Vec<String>,
+    pub(super) suggestions: Vec<String>,
+}
+
+impl ValidationResult {
+    pub(super) fn new() -> Self {
+        Self {
+            is_valid: true,
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            suggestions: Vec::new(),
+        }
+    }
+
+    pub(super) fn add_error(&mut self, error: String) {
+        self.is_valid = false;
+        self.errors.push(error);
+    }
+
+    pub(super) fn add_warning(&mut self, warning: String) {
+        self.warnings.push(warning);
+    }
+
+    pub(super) fn add_suggestion(&mut self, suggestion: String) {
+        self.suggestions.push(suggestion);
+    }
+
+    pub(super) fn log_results(&self, context: &str) {
+        if !self.errors.is_empty() {
+            error!("❌ {}参数验证失败:", context);
+            for error in &self.errors {
+                error!("   - {}", error);
+            }
+        }
+
+        if !self.warnings.is_empty() {
+            warn!("⚠️ {}参数验证警告:", context);
+            for warning in &self.warnings {
+                warn!("   - {}", warning);
+            }
+        }
+
+        if !self.suggestions.is_empty() {
+            info!("💡 {}参数优化建议:", context);
+            for suggestion in &self.suggestions {
+                info!("   - {}", suggestion);
+            }
+        }
+
+        if self.is_valid && self.warnings.is_empty() && self.suggestions.is_empty() {
+            info!("✅ {}参数验证通过", context);
+        }
+    }
+}
+
+// 验证网格配置参数
+pub(super) fn validate_grid_config(
+    grid_config: &crate::config::GridConfig,
+) -> Result<(), GridStrategyError> {
+    // 检查基本参数
+    if grid_config.total_capital <= 0.0 {
+        return Err(GridStrategyError::ConfigError(
+            "总资金必须大于0".to_string(),
+        ));
+    }
+
+    if grid_config.trade_amount <= 0.0 {
+        return Err(GridStrategyError::ConfigError(
+            "每格交易金额必须大于0".to_string(),
+        ));
+    }
+
+    if grid_config.total_capital < grid_config.trade_amount {
+        return Err(GridStrategyError::ConfigError(format!(
+            "总资金({:.2})不能小于每格交易金额({:.2})",
+            grid_config.total_capital, grid_config.trade_amount
+        )));
+    }
+
+    if grid_config.max_position <= 0.0 {
+        return Err(GridStrategyError::ConfigError(
+            "最大持仓必须大于0".to_string(),
+        ));
+    }
+
+    if grid_config.grid_count == 0 {
+        return Err(GridStrategyError::ConfigError(
+            "网格数量必须大于0".to_string(),
+        ));
+    }
+
+    // 检查网格间距
+    if grid_config.min_grid_spacing <= 0.0 {
+        return Err(GridStrategyError::ConfigError(
+            "最小网格间距必须大于0".to_string(),
+        ));
+    }
+
+    if grid_config.max_grid_spacing <= grid_config.min_grid_spacing {
+        return Err(GridStrategyError::ConfigError(
+            "最大网格间距必须大于最小网格间距".to_string(),
+        ));
+    }
+
+    // 检查参数回滚设置
+    if grid_config.rollback_min_hours < 0.0 {
+        return Err(GridStrategyError::ConfigError(
+            "回滚冷却时间不能为负数".to_string(),
+        ));
+    }
+
+    if grid_config.rollback_threshold <= 0.0 {
+        return Err(GridStrategyError::ConfigError(
+            "回滚阈值必须大于0".to_string(),
+        ));
+    }
+
+    // 检查网格区间比例
+    if grid_config.grid_lower_bound_pct <= 0.0 || grid_config.grid_lower_bound_pct >= 1.0 {
+        return Err(GridStrategyError::ConfigError(
+            "网格下边界比例必须在(0,1)之间".to_string(),
+        ));
+    }
+
+    if grid_config.grid_upper_bound_pct <= 0.0 || grid_config.grid_upper_bound_pct >= 1.0 {
+        return Err(GridStrategyError::ConfigError(
+            "网格上边界比例必须在(0,1)之间".to_string(),
+        ));
+    }
+
+    // 检查手续费率：fee_rate 为基础费率，maker/taker 都单独配置时也要校验
+    if !(0.0..=0.1).contains(&grid_config.fee_rate) {
+        return Err(GridStrategyError::ConfigError(
+            "手续费率必须在0-10%之间".to_string(),
+        ));
+    }
+
+    // maker/taker 未单独配置时等于 fee_rate，maker 允许负费率（返佣）
+    if !(-0.01..=0.1).contains(&grid_config.maker_fee()) {
+        return Err(GridStrategyError::ConfigError(
+            "maker费率必须在-1%到10%之间（负数表示返佣）".to_string(),
+        ));
+    }
+    if !(0.0..=0.1).contains(&grid_config.taker_fee()) {
+        return Err(GridStrategyError::ConfigError(
+            "taker费率必须在0-10%之间".to_string(),
+        ));
+    }
+
+    // 检查网格间距是否足够覆盖手续费（网格买卖均为挂单，按 maker 费率；返佣时下限为负，不限制）
+    let min_required_spacing = break_even_spacing(grid_config.maker_fee()) * 1.25; // 至少是盈亏平衡间距的1.25倍
+    if grid_config.min_grid_spacing < min_required_spacing {
+        return Err(GridStrategyError::ConfigError(format!(
+            "最小网格间距({:.4}%)过小，无法覆盖手续费成本，建议至少设置为{:.4}%",
+            grid_config.min_grid_spacing * 100.0,
+            min_required_spacing * 100.0
+        )));
+    }
+
+    // 检查风险控制参数
+    if grid_config.max_drawdown <= 0.0 || grid_config.max_drawdown > 1.0 {
+        return Err(GridStrategyError::ConfigError(
+            "最大回撤必须在0-100%之间".to_string(),
+        ));
+    }
+
+    if grid_config.max_single_loss <= 0.0 || grid_config.max_single_loss > 1.0 {
+        return Err(GridStrategyError::ConfigError(
+            "单笔最大亏损必须在0-100%之间".to_string(),
+        ));
+    }
+
+    if grid_config.max_daily_loss <= 0.0 || grid_config.max_daily_loss > 1.0 {
+        return Err(GridStrategyError::ConfigError(
+            "每日最大亏损必须在0-100%之间".to_string(),
+        ));
+    }
+
+    if grid_config.trailing_stop_ratio <= 0.0 || grid_config.trailing_stop_ratio > 0.5 {
+        return Err(GridStrategyError::ConfigError(
+            "浮动止损比例必须在0-50%之间".to_string(),
+        ));
+    }
+
+    if grid_config.close_slippage_step < 0.0 || grid_config.close_slippage_step > 0.05 {
+        return Err(GridStrategyError::ConfigError(
+            "清仓重试滑点步长必须在0-5%之间".to_string(),
+        ));
+    }
+
+    if grid_config.market_order_slippage <= 0.0 || grid_config.market_order_slippage > 0.1 {
+        return Err(GridStrategyError::ConfigError(
+            "市价单滑点 market_order_slippage 必须在0-10%之间".to_string(),
+        ));
+    }
+
+    if !(0.0..=1.0).contains(&grid_config.batch_min_success_rate) {
+        return Err(GridStrategyError::ConfigError(
+            "批量下单最低成功率 batch_min_success_rate 必须在0-1之间".to_string(),
+        ));
+    }
+
+    if grid_config.trailing_tp_ratio < 0.0 || grid_config.trailing_tp_ratio > 0.5 {
+        return Err(GridStrategyError::ConfigError(
+            "追踪止盈比例必须在0-50%之间".to_string(),
+        ));
+    }
+
+    if grid_config.trailing_tp_ratio > 0.0 && grid_config.min_take_profit_trigger <= 0.0 {
+        return Err(GridStrategyError::ConfigError(
+            "启用追踪止盈时，止盈激活阈值必须大于0".to_string(),
+        ));
+    }
+
+    // 检查杠杆倍数
+    if grid_config.leverage == 0 || grid_config.leverage > 100 {
+        return Err(GridStrategyError::ConfigError(
+            "杠杆倍数必须在1-100之间".to_string(),
+        ));
+    }
+
+    // 检查精度设置
+    if grid_config.price_precision > 8 {
+        return Err(GridStrategyError::ConfigError(
+            "价格精度不能超过8位小数".to_string(),
+        ));
+    }
+
+    if grid_config.quantity_precision > 8 {
+        return Err(GridStrategyError::ConfigError(
+            "数量精度不能超过8位小数".to_string(),
+        ));
+    }
+
+    // 检查时间参数
+    if grid_config.check_interval == 0 {
+        return Err(GridStrategyError::ConfigError(
+            "检查间隔必须大于0秒".to_string(),
+        ));
+    }
+
+    if grid_config.max_holding_time == 0 {
+        return Err(GridStrategyError::ConfigError(
+            "最大持仓时间必须大于0秒".to_string(),
+        ));
+    }
+
+    if grid_config.holding_timeout_close_mode != "market"
+        && grid_config.holding_timeout_close_mode != "limit"
+    {
+        return Err(GridStrategyError::ConfigError(
+            "持仓超时平仓方式必须是 market 或 limit".to_string(),
+        ));
+    }
+
+    if grid_config.post_only_reject_mode != "reprice" && grid_config.post_only_reject_mode != "skip"
+    {
+        return Err(GridStrategyError::ConfigError(
+            "post-only 被拒处理方式必须是 reprice 或 skip".to_string(),
+        ));
+    }
+
+    if !["normal", "reduce_only", "accumulate"].contains(&grid_config.grid_mode.as_str()) {
+        return Err(GridStrategyError::ConfigError(
+            "网格模式必须是 normal、reduce_only 或 accumulate".to_string(),
+        ));
+    }
+
+    if !["compound", "fixed_capital", "withdraw_to_base"]
+        .contains(&grid_config.profit_mode.as_str())
+    {
+        return Err(GridStrategyError::ConfigError(
+            "利润处理模式必须是 compound、fixed_capital 或 withdraw_to_base".to_string(),
+        ));
+    }
+
+    if !["rebuild", "pause", "alert"].contains(&grid_config.grid_breakout_action.as_str()) {
+        return Err(GridStrategyError::ConfigError(
+            "价格脱离网格处理方式必须是 rebuild、pause 或 alert".to_string(),
+        ));
+    }
+
+    if grid_config.iceberg_enabled
+        && !(grid_config.iceberg_slice_size.is_finite() && grid_config.iceberg_slice_size > 0.0)
+    {
+        return Err(GridStrategyError::ConfigError(
+            "开启冰山单时 iceberg_slice_size 必须大于0".to_string(),
+        ));
+    }
+
+    if !(-0.05..=0.05).contains(&grid_config.grid_price_offset) {
+        return Err(GridStrategyError::ConfigError(format!(
+            "网格价格偏移 grid_price_offset({:.2}%)必须在 ±5% 以内",
+            grid_config.grid_price_offset * 100.0
+        )));
+    }
+
+    if !(grid_config.min_notional.is_finite() && grid_config.min_notional >= 0.0) {
+        return Err(GridStrategyError::ConfigError(
+            "最小下单名义价值 min_notional 不能为负数".to_string(),
+        ));
+    }
+    if !(grid_config.position_reconcile_threshold.is_finite()
+        && grid_config.position_reconcile_threshold >= 0.0)
+    {
+        return Err(GridStrategyError::ConfigError(
+            "持仓对账偏差阈值 position_reconcile_threshold 不能为负数".to_string(),
+        ));
+    }
+    if !(grid_config.api_requests_per_second.is_finite()
+        && grid_config.api_requests_per_second >= 0.0)
+    {
+        return Err(GridStrategyError::ConfigError(
+            "API 限流 api_requests_per_second 不能为负数，0表示不限流".to_string(),
+        ));
+    }
+    if grid_config.message_queue_capacity == 0 {
+        return Err(GridStrategyError::ConfigError(
+            "订阅消息队列容量 message_queue_capacity 必须大于0".to_string(),
+        ));
+    }
+
+    if grid_config.price_stale_secs > 0 && grid_config.rest_price_poll_interval_secs == 0 {
+        return Err(GridStrategyError::ConfigError(
+            "启用 REST 价格兜底时 rest_price_poll_interval_secs 必须大于0".to_string(),
+        ));
+    }
+    if !["none", "http", "s3"].contains(&grid_config.state_remote_backend.as_str()) {
+        return Err(GridStrategyError::ConfigError(
+            "远程状态后端 state_remote_backend 必须是 none、http 或 s3".to_string(),
+        ));
+    }
+    if grid_config.state_remote_backend != "none"
+        && !(grid_config.state_remote_url.starts_with("http://")
+            || grid_config.state_remote_url.starts_with("https://"))
+    {
+        return Err(GridStrategyError::ConfigError(
+            "启用远程状态后端时 state_remote_url 必须是 http:// 或 https:// 开头的地址".to_string(),
+        ));
+    }
+    if grid_config.trade_amount < grid_config.min_notional {
+        warn!(
+            "⚠️ 每格交易金额({:.2})低于最小下单名义价值({:.2})，网格订单会被跳过",
+            grid_config.trade_amount, grid_config.min_notional
+        );
+    }
+    if grid_config.iceberg_enabled && grid_config.iceberg_slice_size < grid_config.min_notional {
+        warn!(
+            "⚠️ 冰山单切片金额({:.2})低于最小下单名义价值({:.2})，拆分时会按最小名义价值合并切片",
+            grid_config.iceberg_slice_size, grid_config.min_notional
+        );
+    }
+
+    // 检查交易时间窗口
+    parse_trading_windows(&grid_config.trading_windows)?;
+
+    // 检查K线周期
+    if grid_config.kline_intervals.is_empty() {
+        return Err(GridStrategyError::ConfigError(
+            "至少需要配置一个K线周期".to_string(),
+        ));
+    }
+    if let Some(interval) = grid_config
+        .kline_intervals
+        .iter()
+        .find(|interval| !SUPPORTED_INTERVALS.contains(&interval.as_str()))
+    {
+        return Err(GridStrategyError::ConfigError(format!(
+            "不支持的K线周期: {}，可选: {}",
+            interval,
+            SUPPORTED_INTERVALS.join("/")
+        )));
+    }
+    if grid_config.kline_capacity < 2 {
+        return Err(GridStrategyError::ConfigError(
+            "K线缓存数量必须至少为2".to_string(),
+        ));
+    }
+
+    // 检查网格间距模式
+    match grid_config.spacing_mode.as_str() {
+        "fixed" => {}
+        "atr" => {
+            if grid_config.atr_period == 0 || grid_config.atr_bar_size == 0 {
+                return Err(GridStrategyError::ConfigError(
+                    "ATR 周期和K线价格点数必须大于0".to_string(),
+                ));
+            }
+            if grid_config.atr_multiplier <= 0.0 {
+                return Err(GridStrategyError::ConfigError(
+                    "ATR 间距系数必须大于0".to_string(),
+                ));
+            }
+            if grid_config.history_length / grid_config.atr_bar_size <= grid_config.atr_period {
+                return Err(GridStrategyError::ConfigError(format!(
+                    "价格历史长度({})不足以计算ATR，需大于 atr_bar_size × atr_period = {}",
+                    grid_config.history_length,
+                    grid_config.atr_bar_size * grid_config.atr_period
+                )));
+            }
+        }
+        _ => {
+            return Err(GridStrategyError::ConfigError(
+                "网格间距模式必须是 fixed 或 atr".to_string(),
+            ));
+        }
+    }
+
+    // 检查网格布点算法
+    match grid_config.grid_layout.as_str() {
+        "geometric" => {}
+        "arithmetic" => {
+            if grid_config.grid_price_step < 0.0 {
+                return Err(GridStrategyError::ConfigError(
+                    "等差布点的价格步长不能为负数".to_string(),
+                ));
+            }
+        }
+        "spread" => {
+            if grid_config.maker_spread <= 0.0 || grid_config.maker_spread >= 0.1 {
+                return Err(GridStrategyError::ConfigError(format!(
+                    "做市价差必须在0-10%之间，当前为{:.2}%",
+                    grid_config.maker_spread * 100.0
+                )));
+            }
+        }
+        _ => {
+            return Err(GridStrategyError::ConfigError(
+                "网格布点算法必须是 geometric、arithmetic 或 spread".to_string(),
+            ));
+        }
+    }
+
+    // 检查分批建仓模型，反金字塔必须有加仓档数和总金额硬上限
+    match grid_config.position_sizing.as_str() {
+        "adaptive" | "equal" => {}
+        "pyramid" | "reverse_pyramid" => {
+            if !(grid_config.position_sizing_ratio > 1.0
+                && grid_config.position_sizing_ratio <= 5.0)
+            {
+                return Err(GridStrategyError::ConfigError(format!(
+                    "金字塔资金倍数必须在1-5之间（不含1），当前为{}",
+                    grid_config.position_sizing_ratio
+                )));
+            }
+            if grid_config.position_sizing == "reverse_pyramid" {
+                if grid_config.martingale_max_levels == 0 {
+                    return Err(GridStrategyError::ConfigError(
+                        "反金字塔建仓的最大加仓档数必须大于0".to_string(),
+                    ));
+                }
+                let max_exposure = grid_config.total_capital * grid_config.leverage.max(1) as f64;
+                if grid_config.martingale_max_funds <= 0.0
+                    || grid_config.martingale_max_funds > max_exposure
+                {
+                    return Err(GridStrategyError::ConfigError(format!(
+                        "反金字塔建仓必须设置总金额上限 martingale_max_funds，范围 0-{:.2}（总资金 × 杠杆），当前为{:.2}",
+                        max_exposure, grid_config.martingale_max_funds
+                    )));
+                }
+            }
+        }
+        _ => {
+            return Err(GridStrategyError::ConfigError(
+                "分批建仓模型必须是 adaptive、equal、pyramid 或 reverse_pyramid".to_string(),
+            ));
+        }
+    }
+
+    // 检查网格中心价模式
+    if !matches!(
+        grid_config.grid_center_mode.as_str(),
+        "fixed" | "short_ma" | "long_ma"
+    ) {
+        return Err(GridStrategyError::ConfigError(
+            "网格中心价模式必须是 fixed、short_ma 或 long_ma".to_string(),
+        ));
+    }
+    if grid_config.grid_center_mode != "fixed"
+        && (grid_config.grid_follow_threshold <= 0.0 || grid_config.grid_follow_threshold > 0.2)
+    {
+        return Err(GridStrategyError::ConfigError(
+            "网格跟随阈值 grid_follow_threshold 必须在0-20%之间".to_string(),
+        ));
+    }
+
+    if grid_config.cancel_protection
+        && !(0.0..=100.0).contains(&grid_config.cancel_protection_min_score)
+    {
+        return Err(GridStrategyError::ConfigError(format!(
+            "挂单保留评分阈值必须在0-100之间，当前为{:.1}",
+            grid_config.cancel_protection_min_score
+        )));
+    }
+
+    if grid_config.funding_cost_enabled
+        && !(grid_config.funding_holding_hours.is_finite()
+            && grid_config.funding_holding_hours >= 0.0)
+    {
+        return Err(GridStrategyError::ConfigError(format!(
+            "预估持仓时长不能为负数，当前为{}",
+            grid_config.funding_holding_hours
+        )));
+    }
+
+    // 检查保证金使用率
+    if grid_config.margin_usage_threshold <= 0.0 || grid_config.margin_usage_threshold > 1.0 {
+        return Err(GridStrategyError::ConfigError(
+            "保证金使用率阈值必须在0-100%之间".to_string(),
+        ));
+    }
+
+    // 进行增强的一致性检查
+    let validation_result = validate_grid_config_enhanced(grid_config);
+    validation_result.log_results("网格配置");
+
+    if !validation_result.is_valid {
+        return Err(GridStrategyError::ConfigError(
+            "网格配置验证失败，请检查参数设置".to_string(),
+        ));
+    }
+
+    info!("✅ 网格配置验证通过");
+    Ok(())
+}
+
+// 增强的网格配置验证
+pub(super) fn validate_grid_config_enhanced(
+    grid_config: &crate::config::GridConfig,
+) -> ValidationResult {
+    let mut result = ValidationResult::new();
+
+    // 1. 网格间距与手续费的关系验证
+    let min_profitable_spacing = break_even_spacing(grid_config.maker_fee()) * 1.5; // 至少是盈亏平衡间距的1.5倍
+    if grid_config.min_grid_spacing < min_profitable_spacing {
+        result.add_error(format!(
+            "最小网格间距({:.4}%)过小，无法覆盖手续费成本，建议至少设置为{:.4}%",
+            grid_config.min_grid_spacing * 100.0,
+            min_profitable_spacing * 100.0
+        ));
+    } else if grid_config.min_grid_spacing < min_profitable_spacing * 1.5 {
+        result.add_warning(format!(
+            "最小网格间距({:.4}%)较小，利润空间有限，建议设置为{:.4}%以上",
+            grid_config.min_grid_spacing * 100.0,
+            min_profitable_spacing * 1.5 * 100.0
+        ));
+    }
+
+    // 2. 网格间距比例验证
+    let spacing_ratio = grid_config.max_grid_spacing / grid_config.min_grid_spacing;
+    if spacing_ratio > 10.0 {
+        result.add_warning(format!(
+            "网格间距范围过大(比例: {:.1}:1)，可能导致策略不稳定",
+            spacing_ratio
+        ));
+        result.add_suggestion("建议将最大网格间距控制在最小间距的5倍以内".to_string());
+    } else if spacing_ratio < 1.5 {
+        result.add_warning(format!(
+            "网格间距范围过小(比例: {:.1}:1)，可能限制策略适应性",
+            spacing_ratio
+        ));
+        result.add_suggestion("建议将最大网格间距设置为最小间距的2-5倍".to_string());
+    }
+
+    // 3. 资金分配合理性验证 - 移除对total_capital的依赖
+    // 使用简化的验证逻辑，基于网格数量和交易金额的合理性
+    if grid_config.grid_count > 50 {
+        result.add_warning(format!(
+            "网格数量({})较多，可能导致资金过度分散",
+            grid_config.grid_count
+        ));
+        result.add_suggestion("建议将网格数量控制在20以内".to_string());
+    } else if grid_config.grid_count < 3 {
+        result.add_warning(format!(
+            "网格数量({})较少，可能限制策略效果",
+            grid_config.grid_count
+        ));
+        result.add_suggestion("建议将网格数量设置为5-15个".to_string());
+    }
+
+    // 4. 风险参数一致性验证
+    if grid_config.max_single_loss > grid_config.max_drawdown {
+        result.add_error(format!(
+            "单笔最大亏损({:.1}%)不应超过最大回撤({:.1}%)",
+            grid_config.max_single_loss * 100.0,
+            grid_config.max_drawdown * 100.0
+        ));
+    }
+
+    if grid_config.max_daily_loss > grid_config.max_drawdown {
+        result.add_warning(format!(
+            "每日最大亏损({:.1}%)超过最大回撤({:.1}%)，可能过于激进",
+            grid_config.max_daily_loss * 100.0,
+            grid_config.max_drawdown * 100.0
+        ));
+    }
+
+    // 5. 杠杆与风险的匹配验证
+    if grid_config.leverage > 5 && grid_config.max_drawdown > 0.2 {
+        result.add_warning(format!(
+            "高杠杆({})配合高回撤容忍度({:.1}%)风险较大",
+            grid_config.leverage,
+            grid_config.max_drawdown * 100.0
+        ));
+        result.add_suggestion("建议在高杠杆时降低最大回撤阈值".to_string());
+    }
+
+    // 6. 保证金安全阈值验证
+    let recommended_margin_threshold = 1.0 / grid_config.leverage as f64 * 3.0; // 杠杆倒数的3倍
+    if grid_config.margin_safety_threshold < recommended_margin_threshold {
+        result.add_warning(format!(
+            "保证金安全阈值({:.1}%)可能过低，建议设置为{:.1}%以上",
+            grid_config.margin_safety_threshold * 100.0,
+            recommended_margin_threshold * 100.0
+        ));
+    }
+
+    // 7. 最小利润与网格间距的关系验证
+    let required_profit_rate = min_profit_rate(grid_config);
+    if required_profit_rate > grid_config.min_grid_spacing * 0.5 {
+        result.add_warning(format!(
+            "最小利润要求({:.4}%)相对于网格间距({:.4}%)过高，可能影响成交频率",
+            required_profit_rate * 100.0,
+            grid_config.min_grid_spacing * 100.0
+        ));
+        result.add_suggestion("建议降低最小利润要求或增加网格间距".to_string());
+    }
+
+    // 8. 时间参数合理性验证
+    if grid_config.check_interval < 5 {
+        result.add_warning(format!(
+            "检查间隔({} 秒)过短，可能导致过度频繁的API调用",
+            grid_config.check_interval
+        ));
+        result.add_suggestion("建议将检查间隔设置为10-30秒".to_string());
+    } else if grid_config.check_interval > 300 {
+        result.add_warning(format!(
+            "检查间隔({} 秒)过长，可能错过重要的市场变化",
+            grid_config.check_interval
+        ));
+        result.add_suggestion("建议将检查间隔设置为10-60秒".to_string());
+    }
+
+    // 9. 批量订单参数验证
+    if grid_config.max_orders_per_batch > 50 {
+        result.add_warning(format!(
+            "批量订单数量({})较大，可能导致API限制",
+            grid_config.max_orders_per_batch
+        ));
+        result.add_suggestion("建议将批量订单数量控制在20-50之间".to_string());
+    }
+
+    if grid_config.api_requests_per_second <= 0.0 || grid_config.api_requests_per_second > 20.0 {
+        result.add_warning(format!(
+            "API 限流({} 次/秒)未开启或过高，突发时可能触发交易所429",
+            grid_config.api_requests_per_second
+        ));
+        result.add_suggestion("建议将 api_requests_per_second 设置在5-15之间".to_string());
+    }
+
+    result
+}
+
+// 验证动态参数的合理性和一致性
+pub(super) fn validate_dynamic_parameters(
+    dynamic_params: &DynamicGridParams,
+    grid_config: &crate::config::GridConfig,
+    context: &str,
+) -> ValidationResult {
+    let mut result = ValidationResult::new();
+
+    // 1. 基本范围验证
+    if dynamic_params.current_min_spacing <= 0.0 {
+        result.add_error("动态最小网格间距必须大于0".to_string());
+    }
+
+    if dynamic_params.current_max_spacing <= dynamic_params.current_min_spacing {
+        result.add_error("动态最大网格间距必须大于最小间距".to_string());
+    }
+
+    if dynamic_params.current_trade_amount <= 0.0 {
+        result.add_error("动态交易金额必须大于0".to_string());
+    }
+
+    // 2. 与原始配置的偏离度验证
+    let min_spacing_deviation =
+        (dynamic_params.current_min_spacing / grid_config.min_grid_spacing - 1.0).abs();
+    let max_spacing_deviation =
+        (dynamic_params.current_max_spacing / grid_config.max_grid_spacing - 1.0).abs();
+    let trade_amount_deviation =
+        (dynamic_params.current_trade_amount / grid_config.trade_amount - 1.0).abs();
+
+    if min_spacing_deviation > 0.5 {
+        result.add_warning(format!(
+            "最小网格间距偏离原始配置{:.1}%，当前: {:.4}%，原始: {:.4}%",
+            min_spacing_deviation * 100.0,
+            dynamic_params.current_min_spacing * 100.0,
+            grid_config.min_grid_spacing * 100.0
+        ));
+    }
+
+    if max_spacing_deviation > 0.5 {
+        result.add_warning(format!(
+            "最大网格间距偏离原始配置{:.1}%，当前: {:.4}%，原始: {:.4}%",
+            max_spacing_deviation * 100.0,
+            dynamic_params.current_max_spacing * 100.0,
+            grid_config.max_grid_spacing * 100.0
+        ));
+    }
+
+    if trade_amount_deviation > 0.3 {
+        result.add_warning(format!(
+            "交易金额偏离原始配置{:.1}%，当前: {:.2}，原始: {:.2}",
+            trade_amount_deviation * 100.0,
+            dynamic_params.current_trade_amount,
+            grid_config.trade_amount
+        ));
+    }
+
+    // 3. 安全边界验证 - 移除对total_capital的依赖
+    let min_safe_spacing = break_even_spacing(grid_config.maker_fee()) * 1.25;
+    let max_safe_spacing = grid_config.max_grid_spacing * 2.0;
+    let min_safe_amount = grid_config.trade_amount * 0.1;
+    let max_safe_amount = grid_config.trade_amount * 20.0; // 使用交易金额的20倍作为上限
+
+    if dynamic_params.current_min_spacing < min_safe_spacing {
+        result.add_error(format!(
+            "动态最小网格间距({:.4}%)低于安全下限({:.4}%)",
+            dynamic_params.current_min_spacing * 100.0,
+            min_safe_spacing * 100.0
+        ));
+    }
+
+    if dynamic_params.current_max_spacing > max_safe_spacing {
+        result.add_error(format!(
+            "动态最大网格间距({:.4}%)超过安全上限({:.4}%)",
+            dynamic_params.current_max_spacing * 100.0,
+            max_safe_spacing * 100.0
+        ));
+    }
+
+    if dynamic_params.current_trade_amount < min_safe_amount {
+        result.add_error(format!(
+            "动态交易金额({:.2})低于安全下限({:.2})",
+            dynamic_params.current_trade_amount, min_safe_amount
+        ));
+    }
+
+    if dynamic_params.current_trade_amount > max_safe_amount {
+        result.add_error(format!(
+            "动态交易金额({:.2})超过安全上限({:.2})",
+            dynamic_params.current_trade_amount, max_safe_amount
+        ));
+    }
+
+    // 4. 手续费覆盖验证
+    let min_profitable_spacing = break_even_spacing(grid_config.maker_fee()) * 1.5;
+    if dynamic_params.current_min_spacing < min_profitable_spacing {
+        result.add_error(format!(
+            "动态最小网格间距({:.4}%)无法覆盖手续费成本，需要至少{:.4}%",
+            dynamic_params.current_min_spacing * 100.0,
+            min_profitable_spacing * 100.0
+        ));
+    }
+
+    // 5. 网格间距比例验证
+    let spacing_ratio = dynamic_params.current_max_spacing / dynamic_params.current_min_spacing;
+    if spacing_ratio > 15.0 {
+        result.add_warning(format!(
+            "动态网格间距比例过大({:.1}:1)，可能导致策略不稳定",
+            spacing_ratio
+        ));
+        result.add_suggestion("建议调整参数使间距比例控制在10:1以内".to_string());
+    } else if spacing_ratio < 1.2 {
+        result.add_warning(format!(
+            "动态网格间距比例过小({:.1}:1)，可能限制策略灵活性",
+            spacing_ratio
+        ));
+        result.add_suggestion("建议调整参数使间距比例至少为1.5:1".to_string());
+    }
+
+    // 6. 优化频率验证
+    if dynamic_params.optimization_count > 100 {
+        result.add_warning(format!(
+            "参数优化次数过多({}次)，可能存在过度优化",
+            dynamic_params.optimization_count
+        ));
+        result.add_suggestion("建议检查优化逻辑，避免过度频繁的参数调整".to_string());
+    }
+
+    // 7. 检查点数量验证
+    if dynamic_params.checkpoints.len() > 20 {
+        result.add_warning(format!(
+            "检查点数量过多({}个)，建议清理旧的检查点",
+            dynamic_params.checkpoints.len()
+        ));
+    }
+
+    // 8. 性能窗口验证
+    if dynamic_params.performance_window.len() > 50 {
+        result.add_warning(format!(
+            "性能窗口数据过多({}个)，可能影响计算效率",
+            dynamic_params.performance_window.len()
+        ));
+    }
+
+    // 9. 回滚阈值验证
+    if dynamic_params.rollback_threshold <= 0.0 {
+        result.add_error(format!(
+            "回滚阈值必须大于0，当前: {:.1}",
+            dynamic_params.rollback_threshold
+        ));
+    } else if dynamic_params.rollback_threshold > 50.0 {
+        result.add_warning(format!(
+            "回滚阈值过高({:.1})，可能错过回滚时机",
+            dynamic_params.rollback_threshold
+        ));
+        result.add_suggestion("建议将回滚阈值设置在10-30之间".to_string());
+    } else if dynamic_params.rollback_threshold < 5.0 {
+        result.add_warning(format!(
+            "回滚阈值过低({:.1})，可能导致过度频繁的回滚",
+            dynamic_params.rollback_threshold
+        ));
+        result.add_suggestion("建议将回滚阈值设置在10-30之间".to_string());
+    }
+
+    // 10. 回滚冷却时间验证
+    if dynamic_params.rollback_min_secs < 10 * 60 {
+        result.add_warning(format!(
+            "回滚冷却时间过短({}秒)，参数优化效果可能来不及体现",
+            dynamic_params.rollback_min_secs
+        ));
+        result.add_suggestion("建议回滚冷却时间不少于30分钟".to_string());
+    } else if dynamic_params.rollback_min_secs > 7 * 24 * 60 * 60 {
+        result.add_warning(format!(
+            "回滚冷却时间过长({:.1}小时)，性能下降时可能无法及时回滚",
+            dynamic_params.rollback_min_secs as f64 / 3600.0
+        ));
+    }
+
+    // 11. 上下文特定验证
+    match context {
+        "优化前" => {
+            // 优化前的特殊检查
+            if dynamic_params.optimization_count == 0 {
+                result.add_suggestion("首次优化，建议保守调整参数".to_string());
+            }
+        }
+        "优化后" => {
+            // 优化后的特殊检查
+            if dynamic_params.checkpoints.is_empty() {
+                result.add_warning("优化后未创建检查点，无法回滚".to_string());
+            }
+        }
+        "加载时" => {
+            // 加载时的特殊检查
+            let current_time = safe_unix_timestamp();
+
+            if current_time - dynamic_params.last_optimization_time > 7 * 24 * 60 * 60 {
+                result.add_suggestion("参数已超过7天未优化，建议检查是否需要更新".to_string());
+            }
+        }
+        _ => {}
+    }
+
+    result
+}
+
+// 验证参数优化的合理性
+pub(super) fn validate_parameter_optimization(
+    old_params: &DynamicGridParams,
+    new_params: &DynamicGridParams,
+    grid_config: &crate::config::GridConfig,
+    performance_score: f64,
+) -> ValidationResult {
+    let mut result = ValidationResult::new();
+
+    // 1. 计算参数变化幅度
+    let min_spacing_change =
+        (new_params.current_min_spacing / old_params.current_min_spacing - 1.0).abs();
+    let max_spacing_change =
+        (new_params.current_max_spacing / old_params.current_max_spacing - 1.0).abs();
+    let trade_amount_change =
+        (new_params.current_trade_amount / old_params.current_trade_amount - 1.0).abs();
+
+    // 2. 验证变化幅度的合理性
+    if min_spacing_change > 0.2 {
+        result.add_warning(format!(
+            "最小网格间距变化幅度过大({:.1}%)，可能导致策略不稳定",
+            min_spacing_change * 100.0
+        ));
+        result.add_suggestion("建议单次优化的参数调整幅度控制在10%以内".to_string());
+    }
+
+    if max_spacing_change > 0.2 {
+        result.add_warning(format!(
+            "最大网格间距变化幅度过大({:.1}%)，可能导致策略不稳定",
+            max_spacing_change * 100.0
+        ));
+    }
+
+    if trade_amount_change > 0.15 {
+        result.add_warning(format!(
+            "交易金额变化幅度过大({:.1}%)，可能影响风险控制",
+            trade_amount_change * 100.0
+        ));
+    }
+
+    // 3. 验证优化方向的合理性
+    if performance_score < 30.0 {
+        // 低性能时应该保守调整
+        if min_spacing_change > 0.1 || max_spacing_change > 0.1 || trade_amount_change > 0.1 {
+            result.add_warning("性能较差时建议更保守地调整参数".to_string());
+        }
+
+        if new_params.current_trade_amount > old_params.current_trade_amount {
+            result.add_warning("性能较差时不建议增加交易金额".to_string());
+        }
+    } else if performance_score > 70.0 {
+        // 高性能时可以适度激进
+        if min_spacing_change < 0.02 && max_spacing_change < 0.02 && trade_amount_change < 0.02 {
+            result.add_suggestion("性能良好时可以适度增加参数调整幅度以获得更好收益".to_string());
+        }
+    }
+
+    // 4. 验证新参数的一致性
+    let new_validation = validate_dynamic_parameters(new_params, grid_config, "优化后");
+    if !new_validation.is_valid {
+        result.add_error("优化后的参数不满足基本要求".to_string());
+        result.errors.extend(new_validation.errors);
+    }
+
+    // 5. 验证优化频率
+    let time_since_last_optimization =
+        new_params.last_optimization_time - old_params.last_optimization_time;
+    if time_since_last_optimization < 12 * 60 * 60 {
+        result.add_warning(format!(
+            "距离上次优化时间过短({:.1}小时)，可能存在过度优化",
+            time_since_last_optimization as f64 / 3600.0
+        ));
+    }
+
+    // 6. 验证检查点创建
+    if new_params.checkpoints.len() <= old_params.checkpoints.len() {
+        result.add_warning("优化后未创建新的检查点，无法回滚".to_string());
+    }
+
+    result
+}
+
+// 自动优化网格参数
+pub(super) fn auto_optimize_grid_parameters(
+    grid_state: &mut GridState,
+    grid_config: &crate::config::GridConfig,
+    price_history: &[f64],
+) -> bool {
+    // 检查是否需要优化（每24小时最多优化一次）
+    let current_timestamp = safe_unix_timestamp();
+    if current_timestamp - grid_state.dynamic_params.last_optimization_time < 24 * 60 * 60 {
+        return false;
+    }
+
+    // 需要足够的历史数据
+    if grid_state.performance.records.len() < 20 {
+        info!(
+            "📊 历史数据不足({})，跳过自动优化",
+            grid_state.performance.records.len()
+        );
+        return false;
+    }
+
+    // 分析最近的表现
+    let recent_records: Vec<&PerformanceRecord> = grid_state
+        .performance
+        .records
+        .iter()
+        .rev()
+        .take(30) // 分析最近30笔交易
+        .collect();
+
+    let recent_profit: f64 = recent_records.iter().map(|r| r.profit).sum();
+    let recent_win_rate = recent_records.iter().filter(|r| r.profit > 0.0).count() as f64
+        / recent_records.len() as f64;
+
+    let avg_profit_per_trade = recent_profit / recent_records.len() as f64;
+
+    // 计算性能评分 (0-100)
+    let profit_score = if recent_profit > 0.0 { 50.0 } else { 0.0 };
+    let win_rate_score = recent_win_rate * 30.0;
+    let consistency_score = if avg_profit_per_trade > 0.0 {
+        20.0
+    } else {
+        0.0
+    };
+    let performance_score = profit_score + win_rate_score + consistency_score;
+
+    info!("📊 性能评分分析:");
+    info!("   最近30笔交易利润: {:.2}", recent_profit);
+    info!("   胜率: {:.1}%", recent_win_rate * 100.0);
+    info!("   平均每笔利润: {:.2}", avg_profit_per_trade);
+    info!("   综合评分: {:.1}/100", performance_score);
+
+    // 记录性能评分到滑动窗口
+    grid_state
+        .dynamic_params
+        .performance_window
+        .push_back(performance_score);
+    if grid_state.dynamic_params.performance_window.len() > 10 {
+        grid_state.dynamic_params.performance_window.pop_front();
+    }
+
+    let market_analysis = analyze_market_trend(
+        price_history,
+        &grid_state.indicators,
+        grid_state.klines.primary(),
+        grid_state.market_state_tracker.confirmed(),
+    );
+    let recent_performance: Vec<f64> = grid_state
+        .dynamic_params
+        .performance_window
+        .iter()
+        .copied()
+        .collect();
+
+    if grid_state.dynamic_params.optimize_parameters(
+        &recent_performance,
+        &market_analysis,
+        grid_config,
+    ) {
+        // 保存参数到文件
+        if let Err(e) = grid_state
+            .dynamic_params
+            .save_to_file(&state_file_path(grid_config, "dynamic_grid_params.json"))
+        {
+            warn!("⚠️ 保存动态参数失败: {:?}", e);
+        }
+
+        true
+    } else {
+        // 即使没有优化，也检查是否需要回滚
+        if let Some(checkpoint) = grid_state.dynamic_params.should_rollback(performance_score) {
+            warn!("🔄 性能下降，执行参数回滚");
+            let checkpoint_clone = checkpoint.clone();
+            grid_state
+                .dynamic_params
+                .rollback_to_checkpoint(&checkpoint_clone);
+
+            // 保存回滚后的参数
+            if let Err(e) = grid_state
+                .dynamic_params
+                .save_to_file(&state_file_path(grid_config, "dynamic_grid_params.json"))
+            {
+                warn!("⚠️ 保存回滚参数失败: {:?}", e);
+            }
+
+            return true; // 回滚也算是一种优化
+        }
+
+        false
+    }
+}
+
+// 分析网格性能并提供优化建议
+pub(super) fn analyze_grid_performance_and_suggest_optimization(
+    grid_config: &crate::config::GridConfig,
+    grid_state: &GridState,
+) {
+    if grid_state.performance.records.len() < 10 {
+        return; // 数据不足，无法分析
+    }
+
+    // 分析最近的表现
+    let recent_records: Vec<&PerformanceRecord> = grid_state
+        .performance
+        .records
+        .iter()
+        .rev()
+        .take(20)
+        .collect();
+
+    let recent_profit: f64 = recent_records.iter().map(|r| r.profit).sum();
+    let recent_win_rate = recent_records.iter().filter(|r| r.profit > 0.0).count() as f64
+        / recent_records.len() as f64;
+
+    let avg_profit_per_trade = recent_profit / recent_records.len() as f64;
+
+    info!("📊 网格性能分析:");
+    info!("   最近20笔交易利润: {:.2}", recent_profit);
+    info!("   胜率: {:.1}%", recent_win_rate * 100.0);
+    info!("   平均每笔利润: {:.2}", avg_profit_per_trade);
+
+    // 提供优化建议
+    if recent_profit > 0.0 && recent_win_rate > 0.6 {
+        info!("💡 优化建议: 表现良好，可考虑:");
+        info!(
+            "   - 适当增加网格间距({:.3}% -> {:.3}%)以获得更大利润",
+            grid_config.min_grid_spacing * 100.0,
+            (grid_config.min_grid_spacing * 1.05) * 100.0
+        );
+        info!(
+            "   - 或增加单格交易金额({:.2} -> {:.2})扩大收益",
+            grid_config.trade_amount,
+            grid_config.trade_amount * 1.1
+        );
+    } else if recent_profit < 0.0 || recent_win_rate < 0.4 {
+        info!("⚠️ 优化建议: 表现不佳，建议:");
+        info!(
+            "   - 减少网格间距({:.3}% -> {:.3}%)提高成交频率",
+            grid_config.min_grid_spacing * 100.0,
+            (grid_config.min_grid_spacing * 0.95) * 100.0
+        );
+        info!(
+            "   - 降低单格交易金额({:.2} -> {:.2})减少风险",
+            grid_config.trade_amount,
+            grid_config.trade_amount * 0.9
+        );
+        info!("   - 考虑调整止损参数以更好控制风险");
+    } else {
+        info!("📈 优化建议: 表现中等，可考虑:");
+        info!("   - 根据市场波动率动态调整网格间距");
+        info!("   - 优化资金分配策略");
+    }
+
+    // 分析交易频率
+    if recent_records.len() < 5 {
+        info!("⚠️ 交易频率建议: 成交频率较低，可考虑:");
+        info!("   - 减少网格间距增加成交机会");
+        info!("   - 增加网格数量覆盖更大价格范围");
+    } else if recent_records.len() > 15 {
+        info!("💡 交易频率建议: 成交频率较高，可考虑:");
+        info!("   - 适当增加网格间距减少频繁交易");
+        info!("   - 优化手续费成本");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{default_grid_config, test_grid_state};
+    use super::*;
+
+    fn config_error(grid_config: &crate::config::GridConfig) -> String {
+        match validate_grid_config(grid_config) {
+            Err(GridStrategyError::ConfigError(message)) => message,
+            other => panic!("expected ConfigError, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn adaptive_order_config_reads_old_state_with_missing_fields() {
+        // 旧版状态文件只保存了存活时间和部分因子
+        let old_state = r#"{
+            "base_max_age_minutes": 8.0,
+            "min_age_minutes": 1.0,
+            "max_age_minutes": 20.0,
+            "volatility_factor": 1.3,
+            "adjustment_count": 7
+        }"#;
+        let config: AdaptiveOrderConfig = serde_json::from_str(old_state).unwrap();
+
+        assert_eq!(config.base_max_age_minutes, 8.0);
+        assert_eq!(config.min_age_minutes, 1.0);
+        assert_eq!(config.max_age_minutes, 20.0);
+        assert_eq!(config.volatility_factor, 1.3);
+        assert_eq!(config.adjustment_count, 7);
+
+        assert_eq!(config.trend_factor, 1.0);
+        assert_eq!(config.liquidity_factor, 1.0);
+        assert_eq!(config.success_rate_factor, 1.0);
+        assert_eq!(config.profit_factor, 1.0);
+        assert!(config.adjustment_history.is_empty());
+        assert!(config.last_adjustment_time > 0);
+        assert_eq!(config.average_fill_time_minutes, 15.0);
+        assert_eq!(config.order_success_rate, 0.8);
+        assert_eq!(config.recent_volatility, 0.02);
+    }
+
+    #[test]
+    fn adaptive_order_config_empty_object_uses_defaults() {
+        let config: AdaptiveOrderConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config.base_max_age_minutes, 30.0);
+        assert_eq!(config.min_age_minutes, 0.5);
+        assert_eq!(config.max_age_minutes, 120.0);
+        assert_eq!(config.adjustment_count, 0);
+    }
+
+    #[test]
+    fn adaptive_order_config_round_trips() {
+        let mut config = AdaptiveOrderConfig::new();
+        config.adjustment_history.push_back(1.2);
+        config.adjustment_count = 3;
+
+        let json = serde_json::to_string(&config).unwrap();
+        let restored: AdaptiveOrderConfig = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.base_max_age_minutes, config.base_max_age_minutes);
+        assert_eq!(restored.adjustment_history, config.adjustment_history);
+        assert_eq!(restored.adjustment_count, 3);
+        assert_eq!(restored.last_adjustment_time, config.last_adjustment_time);
+    }
+
+    fn market(market_state: MarketState, trend: MarketTrend, volatility: f64) -> MarketAnalysis {
+        MarketAnalysis {
+            volatility,
+            trend,
+            rsi: 50.0,
+            short_ma: 100.0,
+            long_ma: 100.0,
+            price_change_5min: 0.0,
+            market_state,
+            liquidity_score: 50.0,
+            price_stability: 50.0,
+            volume_anomaly: 0.0,
+        }
+    }
+
+    #[test]
+    fn adaptive_max_age_is_clamped_to_min_and_max() {
+        let grid_config = default_grid_config();
+        let grid_state = test_grid_state(&grid_config);
+
+        // 基础5分钟，范围2–15分钟；成功率0.7、无盈亏时这两项因子为1
+        let cases = [
+            (MarketState::Normal, MarketTrend::Sideways, 0.02, 7.0),
+            (MarketState::Normal, MarketTrend::Upward, 0.02, 3.0),
+            (
+                MarketState::HighVolatility,
+                MarketTrend::Sideways,
+                0.03,
+                2.8,
+            ),
+            (MarketState::Flash, MarketTrend::Sideways, 0.08, 2.0),
+            (MarketState::Extreme, MarketTrend::Downward, 0.06, 2.0),
+            (MarketState::ThinLiquidity, MarketTrend::Sideways, 0.0, 15.0),
+            (
+                MarketState::Consolidation,
+                MarketTrend::Sideways,
+                0.005,
+                15.0,
+            ),
+        ];
+        for (market_state, trend, volatility, expected) in cases {
+            let mut config = AdaptiveOrderConfig::new();
+            let age = config.calculate_adaptive_max_age(
+                &market(market_state.clone(), trend.clone(), volatility),
+                &grid_state,
+                0.7,
+            );
+            assert!(
+                (age - expected).abs() < 1e-9,
+                "{:?}/{:?}/{}: expected {}, got {}",
+                market_state,
+                trend,
+                volatility,
+                expected,
+                age
+            );
+        }
+    }
+
+    #[test]
+    fn adaptive_max_age_combined_factor_is_clamped() {
+        let grid_config = default_grid_config();
+        let grid_state = test_grid_state(&grid_config);
+        let mut config = AdaptiveOrderConfig::new();
+        config.min_age_minutes = 0.0;
+        config.max_age_minutes = 1000.0;
+
+        // 2.0 × 1.4 × 2.0 = 5.6，限幅为5倍
+        let slow = market(MarketState::ThinLiquidity, MarketTrend::Sideways, 0.0);
+        assert_eq!(
+            config.calculate_adaptive_max_age(&slow, &grid_state, 0.7),
+            25.0
+        );
+
+        // 0.3 × 0.6 × 0.1 = 0.018，限幅为0.1倍
+        let fast = market(MarketState::Flash, MarketTrend::Downward, 0.08);
+        assert_eq!(
+            config.calculate_adaptive_max_age(&fast, &grid_state, 0.7),
+            0.5
+        );
+    }
+
+    #[test]
+    fn default_config_passes_validation() {
+        assert!(validate_grid_config(&default_grid_config()).is_ok());
+    }
+
+    #[test]
+    fn negative_maker_fee_is_a_rebate() {
+        let mut grid_config = default_grid_config();
+        grid_config.maker_fee_rate = Some(-0.0002);
+        assert!(validate_grid_config(&grid_config).is_ok());
+
+        // 返佣时盈亏平衡间距为负，零间距的一买一卖也能盈利
+        assert!(break_even_spacing(-0.0002) < 0.0);
+        assert!(calculate_expected_profit_rate(100.0, 100.0, -0.0002, 0.0) > 0.0);
+        assert!(calculate_expected_profit_rate(100.0, 100.0, 0.0002, 0.0) < 0.0);
+    }
+
+    #[test]
+    fn fee_rates_outside_bounds_are_rejected() {
+        let mut grid_config = default_grid_config();
+        grid_config.maker_fee_rate = Some(-0.01);
+        assert!(validate_grid_config(&grid_config).is_ok());
+
+        grid_config.maker_fee_rate = Some(-0.0101);
+        assert!(config_error(&grid_config).contains("maker费率"));
+
+        grid_config.maker_fee_rate = Some(0.1001);
+        assert!(config_error(&grid_config).contains("maker费率"));
+
+        grid_config.maker_fee_rate = None;
+        grid_config.taker_fee_rate = Some(-0.0001);
+        assert!(config_error(&grid_config).contains("taker费率"));
+
+        grid_config.taker_fee_rate = Some(0.1001);
+        assert!(config_error(&grid_config).contains("taker费率"));
+
+        // 基础费率在 maker/taker 都单独配置时仍需在范围内
+        grid_config.maker_fee_rate = Some(0.0002);
+        grid_config.taker_fee_rate = Some(0.0005);
+        assert!(validate_grid_config(&grid_config).is_ok());
+        grid_config.fee_rate = -0.0001;
+        assert!(config_error(&grid_config).contains("手续费率"));
+        grid_config.fee_rate = 0.1001;
+        assert!(config_error(&grid_config).contains("手续费率"));
+    }
+
+    #[test]
+    fn min_spacing_must_cover_break_even_with_margin() {
+        let mut grid_config = default_grid_config();
+        grid_config.maker_fee_rate = Some(0.001);
+        let required = break_even_spacing(0.001) * 1.25;
+
+        // 低于1.25倍：硬性检查直接给出间距错误
+        grid_config.min_grid_spacing = required * 0.99;
+        assert!(config_error(&grid_config).contains("最小网格间距"));
+
+        // 1.25倍到1.5倍之间：通过硬性检查，但增强检查仍判为无法覆盖成本
+        grid_config.min_grid_spacing = required * 1.01;
+        assert_eq!(
+            config_error(&grid_config),
+            "网格配置验证失败，请检查参数设置"
+        );
+        assert!(!validate_grid_config_enhanced(&grid_config).is_valid);
+
+        grid_config.min_grid_spacing = break_even_spacing(0.001) * 1.51;
+        assert!(validate_grid_config(&grid_config).is_ok());
+    }
+
+    #[test]
+    fn position_sizing_ratios_per_level() {
+        // (模型, 倍数, 第0/1/2档期望资金)
+        let cases = [
+            ("equal", 2.0, [100.0, 100.0, 100.0]),
+            ("adaptive", 2.0, [100.0, 100.0, 100.0]),
+            ("pyramid", 2.0, [100.0, 50.0, 25.0]),
+            ("pyramid", 1.5, [100.0, 100.0 / 1.5, 100.0 / 2.25]),
+            ("reverse_pyramid", 2.0, [100.0, 200.0, 400.0]),
+            ("reverse_pyramid", 1.5, [100.0, 150.0, 225.0]),
+        ];
+        for (sizing, ratio, expected) in cases {
+            let mut grid_config = default_grid_config();
+            grid_config.position_sizing = sizing.to_string();
+            grid_config.position_sizing_ratio = ratio;
+            for (level_index, expected_funds) in expected.iter().enumerate() {
+                let funds = position_sizing_funds(&grid_config, 100.0, level_index);
+                assert!(
+                    (funds - expected_funds).abs() < 1e-9,
+                    "{} ×{} 第{}档: expected {}, got {}",
+                    sizing,
+                    ratio,
+                    level_index,
+                    expected_funds,
+                    funds
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn max_buy_exposure_is_capped_by_martingale_funds() {
+        let mut grid_config = default_grid_config();
+        grid_config.max_position = 300.0;
+        grid_config.martingale_max_funds = 200.0;
+
+        // 只有反金字塔受 martingale_max_funds 限制
+        grid_config.position_sizing = "equal".to_string();
+        assert_eq!(max_buy_exposure(&grid_config), 300.0);
+
+        grid_config.position_sizing = "reverse_pyramid".to_string();
+        assert_eq!(max_buy_exposure(&grid_config), 200.0);
+
+        grid_config.martingale_max_funds = 500.0;
+        assert_eq!(max_buy_exposure(&grid_config), 300.0);
+    }
+
+    #[test]
+    fn pyramid_ratio_must_exceed_one() {
+        for sizing in ["pyramid", "reverse_pyramid"] {
+            let mut grid_config = default_grid_config();
+            grid_config.position_sizing = sizing.to_string();
+            grid_config.martingale_max_funds = 200.0;
+
+            grid_config.position_sizing_ratio = 1.5;
+            assert!(validate_grid_config(&grid_config).is_ok(), "{}", sizing);
+
+            for ratio in [1.0, 0.5, 0.0, 5.01] {
+                grid_config.position_sizing_ratio = ratio;
+                assert!(
+                    config_error(&grid_config).contains("金字塔资金倍数"),
+                    "{} ×{}",
+                    sizing,
+                    ratio
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn reverse_pyramid_requires_bounded_martingale_funds() {
+        let mut grid_config = default_grid_config();
+        grid_config.position_sizing = "reverse_pyramid".to_string();
+        grid_config.position_sizing_ratio = 1.5;
+        let max_exposure = grid_config.total_capital * grid_config.leverage.max(1) as f64;
+
+        grid_config.martingale_max_funds = max_exposure;
+        assert!(validate_grid_config(&grid_config).is_ok());
+
+        for funds in [0.0, -1.0, max_exposure + 0.01] {
+            grid_config.martingale_max_funds = funds;
+            assert!(
+                config_error(&grid_config).contains("martingale_max_funds"),
+                "martingale_max_funds = {}",
+                funds
+            );
+        }
+
+        grid_config.martingale_max_funds = 200.0;
+        grid_config.martingale_max_levels = 0;
+        assert!(config_error(&grid_config).contains("最大加仓档数"));
+    }
+}
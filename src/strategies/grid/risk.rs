@@ -0,0 +1,1704 @@
+// 风险控制：止损止盈、风险事件、保证金和持仓时间检查、清仓
+
+use super::*;
+
+// 止损状态枚举
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(super) enum StopLossStatus {
+    Normal,          // 正常
+    Monitoring,      // 监控中
+    PartialExecuted, // 部分执行
+    FullyExecuted,   // 完全执行
+    Failed,          // 执行失败
+    Disabled,        // 已禁用
+}
+
+impl StopLossStatus {
+    pub(super) fn as_str(&self) -> &'static str {
+        match self {
+            StopLossStatus::Normal => "正常",
+            StopLossStatus::Monitoring => "监控中",
+            StopLossStatus::PartialExecuted => "部分执行",
+            StopLossStatus::FullyExecuted => "完全执行",
+            StopLossStatus::Failed => "执行失败",
+            StopLossStatus::Disabled => "已禁用",
+        }
+    }
+
+    /// 获取英文名称
+    pub(super) fn as_english(&self) -> &'static str {
+        match self {
+            StopLossStatus::Normal => "Normal",
+            StopLossStatus::Monitoring => "Monitoring",
+            StopLossStatus::PartialExecuted => "Partial Executed",
+            StopLossStatus::FullyExecuted => "Fully Executed",
+            StopLossStatus::Failed => "Failed",
+            StopLossStatus::Disabled => "Disabled",
+        }
+    }
+
+    /// 判断是否为正常状态
+    #[allow(dead_code)]
+    pub(super) fn is_normal(&self) -> bool {
+        matches!(self, StopLossStatus::Normal)
+    }
+
+    /// 判断是否正在监控
+    #[allow(dead_code)]
+    pub(super) fn is_monitoring(&self) -> bool {
+        matches!(self, StopLossStatus::Monitoring)
+    }
+
+    /// 判断是否已执行（部分或完全）
+    #[allow(dead_code)]
+    pub(super) fn is_executed(&self) -> bool {
+        matches!(
+            self,
+            StopLossStatus::PartialExecuted | StopLossStatus::FullyExecuted
+        )
+    }
+
+    /// 判断是否执行失败
+    pub(super) fn is_failed(&self) -> bool {
+        matches!(self, StopLossStatus::Failed)
+    }
+
+    /// 判断是否可以继续交易
+    pub(super) fn can_continue_trading(&self) -> bool {
+        matches!(
+            self,
+            StopLossStatus::Normal | StopLossStatus::Monitoring | StopLossStatus::PartialExecuted
+        )
+    }
+}
+
+// 止损动作枚举
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum StopLossAction {
+    Normal,      // 正常
+    PartialStop, // 部分止损
+    FullStop,    // 已止损
+}
+
+impl StopLossAction {
+    pub(super) fn as_str(&self) -> &'static str {
+        match self {
+            StopLossAction::Normal => "正常",
+            StopLossAction::PartialStop => "部分止损",
+            StopLossAction::FullStop => "已止损",
+        }
+    }
+
+    /// 获取英文名称
+    pub(super) fn as_english(&self) -> &'static str {
+        match self {
+            StopLossAction::Normal => "Normal",
+            StopLossAction::PartialStop => "Partial Stop",
+            StopLossAction::FullStop => "Full Stop",
+        }
+    }
+
+    /// 判断是否需要执行止损
+    pub(super) fn requires_action(&self) -> bool {
+        !matches!(self, StopLossAction::Normal)
+    }
+
+    /// 判断是否为完全止损
+    pub(super) fn is_full_stop(&self) -> bool {
+        matches!(self, StopLossAction::FullStop)
+    }
+
+    /// 判断是否为部分止损
+    pub(super) fn is_partial_stop(&self) -> bool {
+        matches!(self, StopLossAction::PartialStop)
+    }
+}
+
+// 止损检查结果
+#[derive(Debug, Clone)]
+pub(super) struct StopLossResult {
+    pub(super) action: StopLossAction,
+    pub(super) reason: String,
+    pub(super) stop_quantity: f64,
+}
+
+// 止盈动作枚举
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum TakeProfitAction {
+    Normal,            // 正常
+    PartialTakeProfit, // 部分止盈
+}
+
+impl TakeProfitAction {
+    pub(super) fn as_str(&self) -> &'static str {
+        match self {
+            TakeProfitAction::Normal => "正常",
+            TakeProfitAction::PartialTakeProfit => "部分止盈",
+        }
+    }
+
+    /// 获取英文名称
+    pub(super) fn as_english(&self) -> &'static str {
+        match self {
+            TakeProfitAction::Normal => "Normal",
+            TakeProfitAction::PartialTakeProfit => "Partial Take Profit",
+        }
+    }
+
+    /// 判断是否需要执行止盈
+    pub(super) fn requires_action(&self) -> bool {
+        !matches!(self, TakeProfitAction::Normal)
+    }
+}
+
+// 止盈检查结果
+#[derive(Debug, Clone)]
+pub(super) struct TakeProfitResult {
+    pub(super) action: TakeProfitAction,
+    pub(super) reason: String,
+    pub(super) take_profit_quantity: f64,
+}
+
+// ===== 增强风险控制模块 =====
+
+/// 风险事件类型
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(super) enum RiskEventType {
+    MarginInsufficient,   // 保证金不足
+    MaxDrawdownExceeded,  // 最大回撤超限
+    DailyLossExceeded,    // 每日亏损超限
+    PositionSizeExceeded, // 持仓规模超限
+    VolatilitySpike,      // 波动率激增
+    LiquidityDrop,        // 流动性下降
+    NetworkIssue,         // 网络问题
+    OrderFailure,         // 订单失败
+    PriceGap,             // 价格跳空
+    SystemOverload,       // 系统过载
+    GridBreakout,         // 价格脱离网格区间
+    ConsecutiveLosses,    // 连续亏损熔断
+}
+
+impl RiskEventType {
+    pub(super) fn as_str(&self) -> &'static str {
+        match self {
+            RiskEventType::MarginInsufficient => "保证金不足",
+            RiskEventType::MaxDrawdownExceeded => "最大回撤超限",
+            RiskEventType::DailyLossExceeded => "每日亏损超限",
+            RiskEventType::PositionSizeExceeded => "持仓规模超限",
+            RiskEventType::VolatilitySpike => "波动率激增",
+            RiskEventType::LiquidityDrop => "流动性下降",
+            RiskEventType::NetworkIssue => "网络问题",
+            RiskEventType::OrderFailure => "订单失败",
+            RiskEventType::PriceGap => "价格跳空",
+            RiskEventType::SystemOverload => "系统过载",
+            RiskEventType::GridBreakout => "价格脱离网格",
+            RiskEventType::ConsecutiveLosses => "连续亏损熔断",
+        }
+    }
+
+    #[allow(dead_code)]
+    pub(super) fn as_english(&self) -> &'static str {
+        match self {
+            RiskEventType::MarginInsufficient => "Margin Insufficient",
+            RiskEventType::MaxDrawdownExceeded => "Max Drawdown Exceeded",
+            RiskEventType::DailyLossExceeded => "Daily Loss Exceeded",
+            RiskEventType::PositionSizeExceeded => "Position Size Exceeded",
+            RiskEventType::VolatilitySpike => "Volatility Spike",
+            RiskEventType::LiquidityDrop => "Liquidity Drop",
+            RiskEventType::NetworkIssue => "Network Issue",
+            RiskEventType::OrderFailure => "Order Failure",
+            RiskEventType::PriceGap => "Price Gap",
+            RiskEventType::SystemOverload => "System Overload",
+            RiskEventType::GridBreakout => "Grid Breakout",
+            RiskEventType::ConsecutiveLosses => "Consecutive Losses",
+        }
+    }
+
+    pub(super) fn severity_level(&self) -> u8 {
+        match self {
+            RiskEventType::MarginInsufficient => 5,   // 最高风险
+            RiskEventType::MaxDrawdownExceeded => 5,  // 最高风险
+            RiskEventType::DailyLossExceeded => 4,    // 高风险
+            RiskEventType::PositionSizeExceeded => 4, // 高风险
+            RiskEventType::VolatilitySpike => 3,      // 中等风险
+            RiskEventType::LiquidityDrop => 3,        // 中等风险
+            RiskEventType::PriceGap => 3,             // 中等风险
+            RiskEventType::NetworkIssue => 2,         // 低风险
+            RiskEventType::OrderFailure => 2,         // 低风险
+            RiskEventType::SystemOverload => 2,       // 低风险
+            RiskEventType::GridBreakout => 4,         // 高风险，网格失效可能单边套牢
+            RiskEventType::ConsecutiveLosses => 4,    // 高风险，策略可能已失效
+        }
+    }
+
+    #[allow(dead_code)]
+    pub(super) fn requires_immediate_action(&self) -> bool {
+        self.severity_level() >= 4
+    }
+
+    #[allow(dead_code)]
+    pub(super) fn should_pause_trading(&self) -> bool {
+        matches!(
+            self,
+            RiskEventType::MarginInsufficient
+                | RiskEventType::MaxDrawdownExceeded
+                | RiskEventType::DailyLossExceeded
+                | RiskEventType::VolatilitySpike
+        )
+    }
+}
+
+/// 风险事件
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(super) struct RiskEvent {
+    pub(super) event_type: RiskEventType,
+    #[serde(with = "system_time_serde")]
+    pub(super) timestamp: SystemTime,
+    pub(super) description: String,
+    pub(super) current_value: f64,
+    pub(super) threshold_value: f64,
+    pub(super) severity: u8,
+    pub(super) handled: bool,
+    pub(super) action_taken: Option<String>,
+}
+
+impl RiskEvent {
+    pub(super) fn new(
+        event_type: RiskEventType,
+        description: String,
+        current_value: f64,
+        threshold_value: f64,
+    ) -> Self {
+        Self {
+            severity: event_type.severity_level(),
+            event_type,
+            timestamp: SystemTime::now(),
+            description,
+            current_value,
+            threshold_value,
+            handled: false,
+            action_taken: None,
+        }
+    }
+
+    pub(super) fn mark_handled(&mut self, action: String) {
+        self.handled = true;
+        self.action_taken = Some(action);
+    }
+
+    pub(super) fn is_critical(&self) -> bool {
+        self.severity >= 4
+    }
+
+    #[allow(dead_code)]
+    pub(super) fn age_seconds(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(self.timestamp)
+            .unwrap_or_default()
+            .as_secs()
+    }
+}
+
+/// 风险检查结果
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub(super) struct RiskCheckResult {
+    pub(super) overall_risk_level: u8,       // 1-5级风险等级
+    pub(super) should_pause_trading: bool,   // 是否应暂停交易
+    pub(super) should_reduce_position: bool, // 是否应减仓
+    pub(super) should_emergency_exit: bool,  // 是否应紧急退出
+    pub(super) new_events: Vec<RiskEvent>,   // 新发现的风险事件
+    pub(super) recommendations: Vec<String>, // 风险控制建议
+    pub(super) margin_ratio: Option<f64>,    // 当前保证金率，无持仓时为 None
+    pub(super) drawdown_ratio: f64,          // 当前回撤率
+    pub(super) daily_loss_ratio: f64,        // 当前日亏损率
+    pub(super) position_risk_score: f64,     // 持仓风险评分 (0-100)
+}
+
+impl RiskCheckResult {
+    pub(super) fn new() -> Self {
+        Self {
+            overall_risk_level: 1,
+            should_pause_trading: false,
+            should_reduce_position: false,
+            should_emergency_exit: false,
+            new_events: Vec::new(),
+            recommendations: Vec::new(),
+            margin_ratio: None,
+            drawdown_ratio: 0.0,
+            daily_loss_ratio: 0.0,
+            position_risk_score: 0.0,
+        }
+    }
+
+    pub(super) fn add_event(&mut self, event: RiskEvent) {
+        if event.is_critical() {
+            self.overall_risk_level = self.overall_risk_level.max(event.severity);
+        }
+
+        if event.event_type.should_pause_trading() {
+            self.should_pause_trading = true;
+        }
+
+        if event.event_type.requires_immediate_action() {
+            self.should_reduce_position = true;
+        }
+
+        if event.severity >= 5 {
+            self.should_emergency_exit = true;
+        }
+
+        self.new_events.push(event);
+    }
+
+    pub(super) fn add_recommendation(&mut self, recommendation: String) {
+        self.recommendations.push(recommendation);
+    }
+
+    pub(super) fn has_critical_events(&self) -> bool {
+        self.new_events.iter().any(|e| e.is_critical())
+    }
+}
+
+/// 增强风险控制模块
+#[derive(Debug)]
+#[allow(dead_code)]
+pub(super) struct RiskControlModule {
+    pub(super) grid_state: Arc<Mutex<GridState>>,
+    pub(super) grid_config: Arc<crate::config::GridConfig>,
+    pub(super) stop_trading: Arc<AtomicBool>,
+    pub(super) risk_events: VecDeque<RiskEvent>,
+    pub(super) last_check_time: SystemTime,
+    pub(super) check_interval: Duration,
+    pub(super) daily_start_capital: f64,
+    pub(super) daily_start_time: SystemTime,
+    pub(super) consecutive_failures: u32,
+    pub(super) last_margin_ratio: Option<f64>, // 最近保证金率，无持仓时为 None
+    pub(super) risk_metrics_history: VecDeque<(SystemTime, Option<f64>, f64, f64)>, // (时间, 保证金率, 回撤率, 日亏损率)
+    pub(super) alert_manager: Arc<AlertManager>,
+}
+
+impl RiskControlModule {
+    /// 创建新的风险控制模块
+    pub(super) fn new(
+        grid_state: Arc<Mutex<GridState>>,
+        grid_config: Arc<crate::config::GridConfig>,
+        stop_trading: Arc<AtomicBool>,
+        alert_manager: Arc<AlertManager>,
+    ) -> Self {
+        let daily_start_capital = {
+            let state = lock_or_recover(&grid_state);
+            state.total_capital
+        };
+
+        Self {
+            grid_state,
+            grid_config,
+            stop_trading,
+            risk_events: VecDeque::new(),
+            last_check_time: SystemTime::now(),
+            check_interval: Duration::from_secs(30), // 30秒检查一次
+            daily_start_capital,
+            daily_start_time: SystemTime::now(),
+            consecutive_failures: 0,
+            last_margin_ratio: None,
+            risk_metrics_history: VecDeque::new(),
+            alert_manager,
+        }
+    }
+
+    /// 运行风险检查
+    pub(super) async fn run_checks(
+        &mut self,
+        current_price: f64,
+        price_history: &[f64],
+        exchange_client: &dyn OrderExecutor,
+        info_client: &InfoClient,
+        user_address: ethers::types::Address,
+    ) -> Result<RiskCheckResult, GridStrategyError> {
+        let now = SystemTime::now();
+
+        // 检查是否到了检查时间
+        if now.duration_since(self.last_check_time).unwrap_or_default() < self.check_interval {
+            return Ok(RiskCheckResult::new());
+        }
+
+        self.last_check_time = now;
+
+        let mut result = RiskCheckResult::new();
+
+        // 获取当前状态
+        let (liquid_capital, position_quantity, _realized_profit, max_drawdown, _total_capital) = {
+            let state = lock_or_recover(&self.grid_state);
+            (
+                state.available_funds + state.position_quantity * current_price, // 流动资产
+                state.position_quantity,
+                state.realized_profit,
+                state.performance.metrics.max_drawdown,
+                state.total_capital, // 初始总资产
+            )
+        };
+
+        // 1. 检查保证金率
+        match self
+            .check_margin_ratio(exchange_client, info_client, user_address)
+            .await
+        {
+            Ok(margin_ratio) => {
+                result.margin_ratio = margin_ratio;
+                self.last_margin_ratio = margin_ratio;
+
+                // 无持仓时没有保证金风险，跳过阈值比较
+                if let Some(margin_ratio) =
+                    margin_ratio.filter(|ratio| *ratio < self.grid_config.margin_safety_threshold)
+                {
+                    let event = RiskEvent::new(
+                        RiskEventType::MarginInsufficient,
+                        format!(
+                            "保证金率({:.1}%)低于安全阈值({:.1}%)",
+                            margin_ratio * 100.0,
+                            self.grid_config.margin_safety_threshold * 100.0
+                        ),
+                        margin_ratio,
+                        self.grid_config.margin_safety_threshold,
+                    );
+                    result.add_event(event);
+                }
+            }
+            Err(e) => {
+                warn!("⚠️ 保证金率检查失败: {:?}", e);
+                self.consecutive_failures += 1;
+
+                if self.consecutive_failures >= 3 {
+                    let event = RiskEvent::new(
+                        RiskEventType::NetworkIssue,
+                        format!("连续{}次保证金检查失败", self.consecutive_failures),
+                        self.consecutive_failures as f64,
+                        3.0,
+                    );
+                    result.add_event(event);
+                }
+            }
+        }
+
+        // 2. 检查最大回撤
+        result.drawdown_ratio = max_drawdown;
+        if max_drawdown > self.grid_config.max_drawdown {
+            let event = RiskEvent::new(
+                RiskEventType::MaxDrawdownExceeded,
+                format!(
+                    "最大回撤({:.2}%)超过限制({:.2}%)",
+                    max_drawdown * 100.0,
+                    self.grid_config.max_drawdown * 100.0
+                ),
+                max_drawdown,
+                self.grid_config.max_drawdown,
+            );
+            result.add_event(event);
+        }
+
+        // 3. 检查每日亏损
+        let daily_loss_ratio =
+            (self.daily_start_capital - liquid_capital) / self.daily_start_capital;
+        result.daily_loss_ratio = daily_loss_ratio;
+
+        if daily_loss_ratio > self.grid_config.max_daily_loss {
+            let event = RiskEvent::new(
+                RiskEventType::DailyLossExceeded,
+                format!(
+                    "每日亏损({:.2}%)超过限制({:.2}%)",
+                    daily_loss_ratio * 100.0,
+                    self.grid_config.max_daily_loss * 100.0
+                ),
+                daily_loss_ratio,
+                self.grid_config.max_daily_loss,
+            );
+            result.add_event(event);
+        }
+
+        // 4. 检查持仓规模
+        let position_value = position_quantity.abs() * current_price;
+        let position_ratio = position_value / liquid_capital;
+        result.position_risk_score = position_ratio * 100.0;
+
+        if position_value > self.grid_config.max_position {
+            let event = RiskEvent::new(
+                RiskEventType::PositionSizeExceeded,
+                format!(
+                    "持仓价值({:.2})超过最大限制({:.2})",
+                    position_value, self.grid_config.max_position
+                ),
+                position_value,
+                self.grid_config.max_position,
+            );
+            result.add_event(event);
+        }
+
+        // 5. 检查市场波动率
+        if price_history.len() >= 10 {
+            let volatility = calculate_market_volatility(price_history);
+            if volatility > 0.15 {
+                // 15%的波动率阈值
+                let event = RiskEvent::new(
+                    RiskEventType::VolatilitySpike,
+                    format!("市场波动率({:.2}%)异常高", volatility * 100.0),
+                    volatility,
+                    0.15,
+                );
+                result.add_event(event);
+            }
+        }
+
+        // 6. 检查价格跳空
+        if price_history.len() >= 2 {
+            let last_price = price_history[price_history.len() - 2];
+            let price_gap = ((current_price - last_price) / last_price).abs();
+
+            if price_gap > 0.05 {
+                // 5%的价格跳空阈值
+                let event = RiskEvent::new(
+                    RiskEventType::PriceGap,
+                    format!("价格跳空({:.2}%)过大", price_gap * 100.0),
+                    price_gap,
+                    0.05,
+                );
+                result.add_event(event);
+            }
+        }
+
+        // 7. 生成风险控制建议
+        self.generate_recommendations(&mut result);
+
+        // 8. 记录风险指标历史
+        self.risk_metrics_history.push_back((
+            now,
+            result.margin_ratio,
+            result.drawdown_ratio,
+            result.daily_loss_ratio,
+        ));
+
+        // 保留最近100条记录
+        if self.risk_metrics_history.len() > 100 {
+            self.risk_metrics_history.pop_front();
+        }
+
+        // 9. 检查是否需要重置每日统计
+        if now
+            .duration_since(self.daily_start_time)
+            .unwrap_or_default()
+            .as_secs()
+            >= 24 * 60 * 60
+        {
+            self.reset_daily_stats(current_price);
+        }
+
+        Ok(result)
+    }
+
+    /// 处理风险事件
+    pub(super) async fn handle_risk_event(
+        &mut self,
+        mut event: RiskEvent,
+    ) -> Result<(), GridStrategyError> {
+        tr_log!(
+            warn,
+            "🚨 处理风险事件: {} - {}",
+            "🚨 Handling risk event: {} - {}",
+            event.event_type.localized(),
+            event.description
+        );
+
+        let action = match event.event_type {
+            RiskEventType::MarginInsufficient => {
+                self.stop_trading.store(true, Ordering::SeqCst);
+                "暂停交易，等待保证金补充".to_string()
+            }
+            RiskEventType::MaxDrawdownExceeded => {
+                self.stop_trading.store(true, Ordering::SeqCst);
+                "触发最大回撤保护，暂停交易".to_string()
+            }
+            RiskEventType::DailyLossExceeded => {
+                self.stop_trading.store(true, Ordering::SeqCst);
+                "每日亏损超限，暂停交易".to_string()
+            }
+            RiskEventType::PositionSizeExceeded => "建议减仓，降低持仓风险".to_string(),
+            RiskEventType::VolatilitySpike => "市场波动加剧，建议减少网格密度".to_string(),
+            RiskEventType::LiquidityDrop => "流动性下降，建议暂停新订单".to_string(),
+            RiskEventType::NetworkIssue => "网络问题，增加重试机制".to_string(),
+            RiskEventType::OrderFailure => "订单失败，检查订单参数".to_string(),
+            RiskEventType::PriceGap => "价格跳空，暂停交易等待市场稳定".to_string(),
+            RiskEventType::SystemOverload => "系统过载，降低交易频率".to_string(),
+            RiskEventType::GridBreakout => "价格脱离网格区间，建议重建网格".to_string(),
+            RiskEventType::ConsecutiveLosses => "连续亏损熔断，暂停开新仓".to_string(),
+        };
+
+        // 高风险事件推送告警
+        if event.is_critical() {
+            self.alert_manager
+                .alert(
+                    Severity::from_level(event.severity),
+                    &format!(
+                        "{} 风险事件: {} - {}，处理: {}",
+                        self.grid_config.trading_asset,
+                        event.event_type.as_str(),
+                        event.description,
+                        action
+                    ),
+                )
+                .await;
+        }
+
+        event.mark_handled(action.clone());
+        self.risk_events.push_back(event);
+
+        // 保留最近50个风险事件
+        if self.risk_events.len() > 50 {
+            self.risk_events.pop_front();
+        }
+
+        info!("✅ 风险事件处理完成: {}", action);
+        Ok(())
+    }
+
+    /// 生成风险控制建议
+    pub(super) fn generate_recommendations(&self, result: &mut RiskCheckResult) {
+        if let Some(margin_ratio) = result.margin_ratio {
+            if margin_ratio < 1.5 {
+                result.add_recommendation("保证金率过低，建议立即补充保证金或减仓".to_string());
+            } else if margin_ratio < 2.0 {
+                result.add_recommendation("保证金率偏低，建议适当减仓".to_string());
+            }
+        }
+
+        if result.drawdown_ratio > 0.8 * self.grid_config.max_drawdown {
+            result.add_recommendation("回撤接近限制，建议降低风险敞口".to_string());
+        }
+
+        if result.daily_loss_ratio > 0.8 * self.grid_config.max_daily_loss {
+            result.add_recommendation("日亏损接近限制，建议暂停交易".to_string());
+        }
+
+        if result.position_risk_score > 80.0 {
+            result.add_recommendation("持仓风险过高，建议分批减仓".to_string());
+        }
+
+        if self.consecutive_failures > 0 {
+            result.add_recommendation(format!(
+                "连续{}次检查失败，建议检查网络连接",
+                self.consecutive_failures
+            ));
+        }
+    }
+
+    /// 检查保证金率，无持仓（未占用保证金）时返回 None
+    pub(super) async fn check_margin_ratio(
+        &self,
+        exchange_client: &dyn OrderExecutor,
+        info_client: &InfoClient,
+        user_address: ethers::types::Address,
+    ) -> Result<Option<f64>, GridStrategyError> {
+        match get_account_info(exchange_client, info_client, user_address).await {
+            Ok(account_info) => {
+                let margin_summary = &account_info.margin_summary;
+                let account_value = margin_summary.account_value.parse().unwrap_or(0.0);
+                let total_margin_used = margin_summary.total_margin_used.parse().unwrap_or(0.0);
+
+                if total_margin_used > 0.0 {
+                    Ok(Some(account_value / total_margin_used))
+                } else {
+                    Ok(None) // 无持仓时不存在保证金率
+                }
+            }
+            Err(e) => {
+                // 注意：这里不能修改self，因为方法是&self
+                // consecutive_failures的增加在run_checks方法中处理
+                Err(e)
+            }
+        }
+    }
+
+    /// 重置每日统计，以当前价格计算的流动资产作为新的每日起始资本
+    pub(super) fn reset_daily_stats(&mut self, current_price: f64) {
+        let current_capital = {
+            let state = lock_or_recover(&self.grid_state);
+            state.available_funds + state.position_quantity * current_price
+        };
+
+        self.daily_start_capital = current_capital;
+        self.daily_start_time = SystemTime::now();
+        self.consecutive_failures = 0;
+
+        info!(
+            "🔄 每日风险统计已重置 - 起始资本: {:.2} (价格: {:.4})",
+            current_capital, current_price
+        );
+    }
+
+    /// 获取风险事件历史
+    pub(super) fn get_recent_events(&self, hours: u64) -> Vec<&RiskEvent> {
+        let cutoff_time = SystemTime::now() - Duration::from_secs(hours * 3600);
+        self.risk_events
+            .iter()
+            .filter(|event| event.timestamp > cutoff_time)
+            .collect()
+    }
+
+    /// 获取风险统计报告
+    pub(super) fn get_risk_report(&self) -> String {
+        let recent_events = self.get_recent_events(24);
+        let critical_events = recent_events.iter().filter(|e| e.is_critical()).count();
+
+        format!(
+            "=== 风险控制报告 ===\n\
+            最近24小时风险事件: {}\n\
+            其中严重事件: {}\n\
+            连续失败次数: {}\n\
+            最近保证金率: {}\n\
+            风险指标历史记录: {} 条\n\
+            ==================",
+            recent_events.len(),
+            critical_events,
+            self.consecutive_failures,
+            self.last_margin_ratio
+                .map(|ratio| format!("{:.2}%", ratio * 100.0))
+                .unwrap_or_else(|| "无持仓".to_string()),
+            self.risk_metrics_history.len()
+        )
+    }
+}
+
+// 止损检查与执行
+pub(super) fn check_stop_loss(
+    grid_state: &mut GridState,
+    current_price: f64,
+    grid_config: &crate::config::GridConfig,
+    price_history: &[f64],
+    active_orders_count: usize,
+    account_total_value: Option<f64>, // 从外部传入真实的账户总价值
+) -> StopLossResult {
+    grid_state.update_unrealized_pnl(current_price);
+
+    // 1. 总资产止损 - 简化逻辑，使用外部传入的真实账户总价值
+    if let Some(real_total_value) = account_total_value {
+        // 使用真实的账户总价值进行计算
+        let asset_change_rate = if grid_state.total_capital > 0.0 {
+            (real_total_value - grid_state.total_capital) / grid_state.total_capital
+        } else {
+            0.0
+        };
+
+        // 只有在有显著持仓时才检查总资产止损
+        let has_significant_position = grid_state.position_quantity.abs() > 0.001;
+
+        // 使用保守的止损阈值（配置值的2倍）
+        let conservative_drawdown_threshold = grid_config.max_drawdown * 2.0;
+
+        if has_significant_position && asset_change_rate < -conservative_drawdown_threshold {
+            warn!(
+                "🚨 触发总资产止损 - 真实总资产: {:.2}, 初始资产: {:.2}, 亏损率: {:.2}%, 保守阈值: {:.1}% (配置: {:.1}%)",
+                real_total_value,
+                grid_state.total_capital,
+                asset_change_rate * 100.0,
+                conservative_drawdown_threshold * 100.0,
+                grid_config.max_drawdown * 100.0
+            );
+
+            return StopLossResult {
+                action: StopLossAction::FullStop,
+                reason: format!(
+                    "总资产亏损{:.2}%，超过保守止损阈值{:.1}%",
+                    (-asset_change_rate) * 100.0,
+                    conservative_drawdown_threshold * 100.0
+                ),
+                stop_quantity: grid_state.position_quantity,
+            };
+        } else if has_significant_position && asset_change_rate < -grid_config.max_drawdown {
+            // 在原始阈值和保守阈值之间，给出警告但不触发止损
+            warn!(
+                "⚠️ 资产亏损警告 - 真实总资产: {:.2}, 初始资产: {:.2}, 亏损率: {:.2}%, 已超过配置阈值{:.1}%但未达到保守阈值{:.1}%",
+                real_total_value,
+                grid_state.total_capital,
+                asset_change_rate * 100.0,
+                grid_config.max_drawdown * 100.0,
+                conservative_drawdown_threshold * 100.0
+            );
+        } else if !has_significant_position {
+            // 无持仓时只记录信息，不触发止损
+            info!(
+                "📊 无持仓状态 - 真实总资产: {:.2}, 初始资产: {:.2}, 变化: {:.2} ({:.2}%), 活跃挂单: {}",
+                real_total_value,
+                grid_state.total_capital,
+                real_total_value - grid_state.total_capital,
+                asset_change_rate * 100.0,
+                active_orders_count
+            );
+        }
+    } else {
+        // 如果没有传入真实账户价值，则跳过总资产止损检查
+        info!("📊 跳过总资产止损检查 - 未获取到真实账户总价值");
+    }
+
+    // 2. 浮动止损 (Trailing Stop) - 使用配置的浮动止损比例
+    if grid_state.position_quantity > 0.0 {
+        let trailing_stop_multiplier = 1.0 - grid_config.trailing_stop_ratio;
+
+        // 初始化最高价和止损价
+        if grid_state.highest_price_after_position < grid_state.position_avg_price {
+            grid_state.highest_price_after_position = grid_state.position_avg_price;
+            grid_state.trailing_stop_price =
+                grid_state.position_avg_price * trailing_stop_multiplier;
+        }
+
+        // 更新最高价和浮动止损价
+        if current_price > grid_state.highest_price_after_position {
+            grid_state.highest_price_after_position = current_price;
+            grid_state.trailing_stop_price = current_price * trailing_stop_multiplier;
+            info!(
+                "📈 更新浮动止损 - 新最高价: {:.4}, 新止损价: {:.4}, 止损比例: {:.1}%",
+                grid_state.highest_price_after_position,
+                grid_state.trailing_stop_price,
+                grid_config.trailing_stop_ratio * 100.0
+            );
+        }
+
+        // 检查是否触发浮动止损
+        if current_price < grid_state.trailing_stop_price {
+            warn!(
+                "🚨 触发浮动止损 - 当前价格: {:.4}, 止损价: {:.4}, 配置止损比例: {:.1}%",
+                current_price,
+                grid_state.trailing_stop_price,
+                grid_config.trailing_stop_ratio * 100.0
+            );
+
+            // 根据配置的浮动止损比例动态调整止损数量
+            let stop_ratio = (grid_config.trailing_stop_ratio * 5.0).min(0.8).max(0.3); // 30%-80%之间
+            let stop_quantity = grid_state.position_quantity * stop_ratio;
+            grid_state.highest_price_after_position = current_price;
+            grid_state.trailing_stop_price = current_price * trailing_stop_multiplier;
+
+            return StopLossResult {
+                action: StopLossAction::PartialStop,
+                reason: format!(
+                    "触发浮动止损，回撤{:.1}%",
+                    grid_config.trailing_stop_ratio * 100.0
+                ),
+                stop_quantity,
+            };
+        }
+    }
+
+    // 3. 单笔持仓止损 - 使用配置的最大单笔亏损参数
+    if grid_state.position_quantity > 0.0 && grid_state.position_avg_price > 0.0 {
+        let position_loss_rate =
+            (current_price - grid_state.position_avg_price) / grid_state.position_avg_price;
+
+        if position_loss_rate < -grid_config.max_single_loss {
+            warn!("🚨 触发单笔持仓止损 - 持仓均价: {:.4}, 当前价格: {:.4}, 亏损率: {:.2}%, 未实现盈亏: {:.2}, 配置阈值: {:.1}%",
+                grid_state.position_avg_price, current_price, position_loss_rate * 100.0, grid_state.unrealized_pnl, grid_config.max_single_loss * 100.0);
+
+            // 根据亏损程度动态调整止损比例
+            let loss_severity = position_loss_rate.abs() / grid_config.max_single_loss;
+            let stop_ratio = (0.3 * loss_severity).min(0.8); // 最少30%，最多80%
+            let stop_quantity = grid_state.position_quantity * stop_ratio;
+
+            return StopLossResult {
+                action: StopLossAction::PartialStop,
+                reason: format!(
+                    "单笔持仓亏损超过{:.1}%",
+                    grid_config.max_single_loss * 100.0
+                ),
+                stop_quantity,
+            };
+        }
+    }
+
+    // 4. 加速下跌止损 - 基于每日最大亏损参数的动态阈值
+    if price_history.len() >= 5 {
+        let recent_price = price_history[price_history.len() - 1];
+        let old_price = price_history[price_history.len() - 5];
+        let short_term_change = (recent_price - old_price) / old_price;
+
+        // 使用每日最大亏损的一半作为短期下跌阈值
+        let rapid_decline_threshold = -(grid_config.max_daily_loss * 0.5);
+
+        if short_term_change < rapid_decline_threshold && grid_state.position_quantity > 0.0 {
+            warn!(
+                "🚨 触发加速下跌止损 - 5分钟价格变化率: {:.2}%, 阈值: {:.2}%",
+                short_term_change * 100.0,
+                rapid_decline_threshold * 100.0
+            );
+
+            // 根据下跌幅度和配置的每日最大亏损动态计算止损比例
+            let decline_severity = short_term_change.abs() / grid_config.max_daily_loss;
+            let stop_ratio = (0.2 + decline_severity * 0.3).min(0.6); // 20%-60%之间
+            let stop_quantity = grid_state.position_quantity * stop_ratio;
+
+            return StopLossResult {
+                action: StopLossAction::PartialStop,
+                reason: format!(
+                    "加速下跌{:.1}%，超过阈值{:.1}%",
+                    short_term_change.abs() * 100.0,
+                    rapid_decline_threshold.abs() * 100.0
+                ),
+                stop_quantity,
+            };
+        }
+    }
+
+    StopLossResult {
+        action: StopLossAction::Normal,
+        reason: "".to_string(),
+        stop_quantity: 0.0,
+    }
+}
+
+// 追踪止盈检查
+// 与浮动止损共用 highest_price_after_position：最高浮盈达到激活阈值后，
+// 价格从高点回落超过 trailing_tp_ratio 且仍处于盈利时部分平仓锁定利润
+pub(super) fn check_take_profit(
+    grid_state: &mut GridState,
+    current_price: f64,
+    grid_config: &crate::config::GridConfig,
+) -> TakeProfitResult {
+    let normal = TakeProfitResult {
+        action: TakeProfitAction::Normal,
+        reason: "".to_string(),
+        take_profit_quantity: 0.0,
+    };
+
+    if grid_config.trailing_tp_ratio <= 0.0
+        || grid_state.position_quantity <= 0.0
+        || grid_state.position_avg_price <= 0.0
+    {
+        return normal;
+    }
+
+    if current_price > grid_state.highest_price_after_position {
+        grid_state.highest_price_after_position = current_price;
+    }
+
+    let highest_price = grid_state.highest_price_after_position;
+    let peak_profit_rate =
+        (highest_price - grid_state.position_avg_price) / grid_state.position_avg_price;
+
+    // 最高浮盈未达到激活阈值时不追踪
+    if peak_profit_rate < grid_config.min_take_profit_trigger {
+        return normal;
+    }
+
+    let take_profit_price = highest_price * (1.0 - grid_config.trailing_tp_ratio);
+    let current_profit_rate =
+        (current_price - grid_state.position_avg_price) / grid_state.position_avg_price;
+
+    if current_price <= take_profit_price && current_profit_rate > 0.0 {
+        info!(
+            "💰 触发追踪止盈 - 当前价格: {:.4}, 盈利高点: {:.4}, 止盈价: {:.4}, 最高浮盈: {:.2}%, 当前浮盈: {:.2}%",
+            current_price,
+            highest_price,
+            take_profit_price,
+            peak_profit_rate * 100.0,
+            current_profit_rate * 100.0
+        );
+
+        // 回撤占最高浮盈的比例越大，平仓比例越高，30%-70%之间
+        let giveback_ratio =
+            (highest_price - current_price) / (highest_price - grid_state.position_avg_price);
+        let take_profit_ratio = (0.3 + giveback_ratio * 0.4).clamp(0.3, 0.7);
+        let take_profit_quantity = grid_state.position_quantity * take_profit_ratio;
+
+        // 重置跟踪高点，避免每个tick重复触发
+        grid_state.highest_price_after_position = current_price;
+        grid_state.trailing_stop_price = current_price * (1.0 - grid_config.trailing_stop_ratio);
+
+        return TakeProfitResult {
+            action: TakeProfitAction::PartialTakeProfit,
+            reason: format!(
+                "浮盈从{:.2}%回落至{:.2}%，超过追踪止盈比例{:.1}%",
+                peak_profit_rate * 100.0,
+                current_profit_rate * 100.0,
+                grid_config.trailing_tp_ratio * 100.0
+            ),
+            take_profit_quantity,
+        };
+    }
+
+    normal
+}
+
+// 清仓函数，返回仍未平掉的多头和空头数量
+pub(super) async fn close_all_positions(
+    exchange_client: &dyn OrderExecutor,
+    grid_config: &crate::config::GridConfig,
+    order_book: &OrderBook,
+    expected_prices: &mut ExpectedFillPrices,
+    long_position: f64,
+    short_position: f64,
+    current_price: f64,
+) -> Result<(f64, f64), GridStrategyError> {
+    let remaining_long = if long_position > 0.0 {
+        // 多头清仓：卖出
+        close_position_with_retry(
+            exchange_client,
+            grid_config,
+            order_book,
+            expected_prices,
+            false,
+            long_position,
+            current_price,
+        )
+        .await?
+    } else {
+        0.0
+    };
+
+    let remaining_short = if short_position > 0.0 {
+        // 空头清仓：买入
+        close_position_with_retry(
+            exchange_client,
+            grid_config,
+            order_book,
+            expected_prices,
+            true,
+            short_position,
+            current_price,
+        )
+        .await?
+    } else {
+        0.0
+    };
+
+    Ok((remaining_long, remaining_short))
+}
+
+// IOC 清仓，未完全成交时逐步扩大滑点重试，返回剩余未成交数量
+pub(super) async fn close_position_with_retry(
+    exchange_client: &dyn OrderExecutor,
+    grid_config: &crate::config::GridConfig,
+    order_book: &OrderBook,
+    expected_prices: &mut ExpectedFillPrices,
+    is_buy: bool,
+    quantity: f64,
+    current_price: f64,
+) -> Result<f64, GridStrategyError> {
+    let side = if is_buy { "空头" } else { "多头" };
+    let min_quantity = 1.0 / 10f64.powi(grid_config.quantity_precision as i32);
+    let mut remaining = format_price(quantity, grid_config.quantity_precision);
+    let mut last_error = None;
+
+    for attempt in 0..=grid_config.close_max_retries {
+        if remaining < min_quantity {
+            break;
+        }
+
+        // 参考盘口深度估算需要吃到的最差价格，冲击成本大于滑点容忍度时以冲击成本为基础
+        let depth_slippage = match order_book.estimate_fill_price(is_buy, remaining) {
+            Some(estimate) => {
+                info!(
+                    "📖 盘口估算 - 清仓{}数量: {:.4}, 估算均价: {:.4}, 最差档位: {:.4}",
+                    side, remaining, estimate.avg_price, estimate.worst_price
+                );
+                if !estimate.fully_filled {
+                    warn!(
+                        "⚠️ 盘口深度不足 - 清仓{}数量: {:.4}, 盘口可成交: {:.4}",
+                        side, remaining, estimate.filled_size
+                    );
+                }
+                (estimate.worst_price - current_price).abs() / current_price
+            }
+            None => 0.0,
+        };
+
+        // 每次重试增加一档滑点，最大不超过20%
+        let slippage = (grid_config.slippage_tolerance.max(depth_slippage)
+            + grid_config.close_slippage_step * attempt as f64)
+            .min(0.2);
+        let limit_px = if is_buy {
+            current_price * (1.0 + slippage)
+        } else {
+            current_price * (1.0 - slippage)
+        };
+        let limit_px = format_price(limit_px, grid_config.price_precision);
+
+        info!(
+            "🔄 清仓{} - 第{}次尝试, 数量: {:.4}, 价格: {:.4} (含滑点: {:.2}%)",
+            side,
+            attempt + 1,
+            remaining,
+            limit_px,
+            slippage * 100.0
+        );
+
+        let cloid = new_cloid();
+        expected_prices.register(cloid, current_price);
+        let order = ClientOrderRequest {
+            asset: grid_config.trading_asset.clone(),
+            is_buy,
+            reduce_only: true,
+            limit_px,
+            sz: remaining,
+            cloid: Some(cloid),
+            order_type: ClientOrder::Limit(ClientLimit {
+                tif: "Ioc".to_string(), // 使用IOC确保快速成交
+            }),
+        };
+
+        let mut filled = 0.0;
+        match exchange_client.order(order, None).await {
+            Ok(ExchangeResponseStatus::Ok(response)) => {
+                if let Some(data) = response.data {
+                    for status in data.statuses {
+                        match status {
+                            ExchangeDataStatus::Filled(order) => {
+                                filled += order.total_sz.parse::<f64>().unwrap_or(0.0);
+                            }
+                            ExchangeDataStatus::Error(e) => {
+                                warn!("⚠️ 清仓{}订单未成交: {}", side, e);
+                                last_error = Some(e);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            Ok(ExchangeResponseStatus::Err(e)) => {
+                warn!("⚠️ 清仓{}请求被拒绝: {:?}", side, e);
+                last_error = Some(format!("{:?}", e));
+            }
+            Err(e) => {
+                warn!("⚠️ 清仓{}请求失败: {:?}", side, e);
+                last_error = Some(format!("{:?}", e));
+            }
+        }
+
+        remaining = format_price(
+            (remaining - filled).max(0.0),
+            grid_config.quantity_precision,
+        );
+        if remaining < min_quantity {
+            info!("✅ 清仓{}完成 - 第{}次尝试全部成交", side, attempt + 1);
+            return Ok(0.0);
+        }
+
+        warn!(
+            "⚠️ 清仓{}未完全成交 - 本次成交: {:.4}, 剩余未平: {:.4}",
+            side, filled, remaining
+        );
+        sleep(Duration::from_millis(200)).await;
+    }
+
+    if remaining >= min_quantity && remaining >= quantity {
+        // 一笔都没有成交
+        return Err(GridStrategyError::OrderError(format!(
+            "清仓{}失败，{}次尝试均未成交，剩余未平: {:.4}，最后错误: {}",
+            side,
+            grid_config.close_max_retries + 1,
+            remaining,
+            last_error.unwrap_or_default()
+        )));
+    }
+
+    if remaining >= min_quantity {
+        error!(
+            "🚨 清仓{}重试{}次后仍有剩余未平数量: {:.4}，请手动处理",
+            side,
+            grid_config.close_max_retries + 1,
+            remaining
+        );
+    }
+
+    Ok(remaining)
+}
+
+// 执行止损操作
+pub(super) async fn execute_stop_loss(
+    exchange_client: &dyn OrderExecutor,
+    grid_config: &crate::config::GridConfig,
+    grid_state: &mut GridState,
+    stop_result: &StopLossResult,
+    active_orders: &mut Vec<u64>,
+    buy_orders: &mut HashMap<u64, OrderInfo>,
+    sell_orders: &mut HashMap<u64, OrderInfo>,
+    current_price: f64,
+) -> Result<(), GridStrategyError> {
+    tr_log!(
+        warn,
+        "🚨 执行止损操作: {}, 原因: {}, 止损数量: {:.4}",
+        "🚨 Executing stop loss: {}, reason: {}, quantity: {:.4}",
+        stop_result.action.localized(),
+        stop_result.reason,
+        stop_result.stop_quantity
+    );
+
+    if stop_result.action.is_full_stop() {
+        grid_state.stop_loss_status = StopLossStatus::Monitoring;
+
+        // 使用带重试的清仓函数
+        if grid_state.position_quantity > 0.0 {
+            // 优先使用当前市场价格，缺失时以持仓均价作为参考
+            let close_price = if current_price > 0.0 {
+                current_price
+            } else {
+                grid_state.position_avg_price
+            };
+
+            match close_all_positions(
+                exchange_client,
+                grid_config,
+                &grid_state.order_book,
+                &mut grid_state.expected_fill_prices,
+                grid_state.position_quantity,
+                0.0, // 假设只有多头持仓
+                close_price,
+            )
+            .await
+            {
+                Ok((remaining_long, _)) if remaining_long > 0.0 => {
+                    warn!(
+                        "⚠️ 全部清仓未完成，已平: {:.4}, 剩余: {:.4}",
+                        grid_state.position_quantity - remaining_long,
+                        remaining_long
+                    );
+                    grid_state.position_quantity = remaining_long;
+                    grid_state.stop_loss_status = StopLossStatus::PartialExecuted;
+                }
+                Ok(_) => {
+                    info!("✅ 全部清仓完成，数量: {:.4}", grid_state.position_quantity);
+                    grid_state.position_quantity = 0.0;
+                    grid_state.position_avg_price = 0.0;
+                    grid_state.stop_loss_status = StopLossStatus::FullyExecuted;
+                }
+                Err(e) => {
+                    error!("❌ 全部清仓失败: {:?}", e);
+                    grid_state.stop_loss_status = StopLossStatus::Failed;
+                    return Err(e);
+                }
+            }
+        } else {
+            grid_state.stop_loss_status = StopLossStatus::FullyExecuted;
+        }
+
+        // 取消所有订单
+        cancel_all_orders(exchange_client, active_orders, &grid_config.trading_asset).await?;
+        buy_orders.clear();
+        sell_orders.clear();
+    } else if stop_result.action.is_partial_stop() && stop_result.stop_quantity > 0.0 {
+        grid_state.stop_loss_status = StopLossStatus::Monitoring;
+
+        // 部分清仓 - 智能滑点处理
+        let base_price = if grid_state.position_avg_price > 0.0 {
+            grid_state.position_avg_price
+        } else {
+            current_price
+        };
+
+        // 智能滑点计算：根据市场波动率和紧急程度调整
+        let market_volatility = grid_state.historical_volatility.max(0.001); // 最小波动率0.1%
+        let urgency_multiplier = match stop_result.action {
+            StopLossAction::FullStop => 2.0,    // 全部止损时使用更大滑点
+            StopLossAction::PartialStop => 1.5, // 部分止损时使用中等滑点
+            _ => 1.0,
+        };
+
+        // 动态滑点 = 基础滑点 + 市场波动率调整 + 紧急程度调整
+        let dynamic_slippage = grid_config.slippage_tolerance
+            + (market_volatility * 0.5)
+            + (grid_config.slippage_tolerance * (urgency_multiplier - 1.0));
+        let final_slippage = dynamic_slippage.min(0.05); // 最大滑点5%
+
+        let sell_price_with_slippage = base_price * (1.0 - final_slippage);
+
+        info!("🎯 智能滑点计算 - 基础价格: {:.4}, 基础滑点: {:.2}%, 市场波动率: {:.2}%, 紧急系数: {:.1}, 最终滑点: {:.2}%, 目标价格: {:.4}",
+            base_price,
+            grid_config.slippage_tolerance * 100.0,
+            market_volatility * 100.0,
+            urgency_multiplier,
+            final_slippage * 100.0,
+            sell_price_with_slippage
+        );
+
+        let cloid = new_cloid();
+        grid_state.expected_fill_prices.register(cloid, base_price);
+        let market_sell_order = ClientOrderRequest {
+            asset: grid_config.trading_asset.clone(),
+            is_buy: false,
+            reduce_only: true,
+            limit_px: sell_price_with_slippage,
+            sz: stop_result.stop_quantity,
+            cloid: Some(cloid),
+            order_type: ClientOrder::Limit(ClientLimit {
+                tif: "Ioc".to_string(), // IOC确保快速成交或取消
+            }),
+        };
+
+        info!(
+            "🔄 执行部分止损 - 价格: {:.4} (含滑点: {:.2}%)",
+            sell_price_with_slippage,
+            grid_config.slippage_tolerance * 100.0
+        );
+
+        match exchange_client.order(market_sell_order, None).await {
+            Ok(_) => {
+                info!("✅ 部分清仓完成，数量: {:.4}", stop_result.stop_quantity);
+                grid_state.position_quantity -= stop_result.stop_quantity;
+                grid_state.stop_loss_status = StopLossStatus::PartialExecuted;
+
+                // 取消部分高价位卖单
+                let sell_orders_vec: Vec<_> =
+                    sell_orders.iter().map(|(k, v)| (*k, v.clone())).collect();
+                let mut sorted_orders = sell_orders_vec;
+                sorted_orders.sort_by(|a, b| {
+                    b.1.price
+                        .partial_cmp(&a.1.price)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+
+                let cancel_count = (sorted_orders.len() / 2).max(1);
+                for (oid, _) in sorted_orders.iter().take(cancel_count) {
+                    if !sell_orders.contains_key(oid) {
+                        continue; // 已随所在冰山组一起撤销
+                    }
+                    for group_oid in iceberg_group_oids(sell_orders, *oid) {
+                        if let Err(e) = cancel_order(exchange_client, group_oid).await {
+                            warn!("取消卖单失败: {:?}", e);
+                        } else {
+                            active_orders.retain(|&x| x != group_oid);
+                            sell_orders.remove(&group_oid);
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                error!("❌ 部分清仓失败: {:?}", e);
+                grid_state.stop_loss_status = StopLossStatus::Failed;
+                return Err(GridStrategyError::OrderError(format!(
+                    "部分清仓失败: {:?}",
+                    e
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// 执行追踪止盈操作
+pub(super) async fn execute_take_profit(
+    exchange_client: &dyn OrderExecutor,
+    grid_config: &crate::config::GridConfig,
+    grid_state: &mut GridState,
+    take_profit_result: &TakeProfitResult,
+    active_orders: &mut Vec<u64>,
+    sell_orders: &mut HashMap<u64, OrderInfo>,
+    current_price: f64,
+) -> Result<(), GridStrategyError> {
+    let quantity = take_profit_result
+        .take_profit_quantity
+        .min(grid_state.position_quantity);
+    if quantity <= 0.0 {
+        return Ok(());
+    }
+
+    let sell_price = current_price * (1.0 - grid_config.slippage_tolerance);
+
+    info!(
+        "💰 执行追踪止盈 - 原因: {}, 数量: {:.4}, 价格: {:.4} (含滑点: {:.2}%)",
+        take_profit_result.reason,
+        quantity,
+        sell_price,
+        grid_config.slippage_tolerance * 100.0
+    );
+
+    let cloid = new_cloid();
+    grid_state
+        .expected_fill_prices
+        .register(cloid, current_price);
+    let take_profit_order = ClientOrderRequest {
+        asset: grid_config.trading_asset.clone(),
+        is_buy: false,
+        reduce_only: true,
+        limit_px: sell_price,
+        sz: quantity,
+        cloid: Some(cloid),
+        order_type: ClientOrder::Limit(ClientLimit {
+            tif: "Ioc".to_string(), // IOC确保快速成交或取消
+        }),
+    };
+
+    match exchange_client.order(take_profit_order, None).await {
+        Ok(_) => {
+            // IOC 止盈按 taker 费率扣除手续费
+            let profit = (sell_price * (1.0 - grid_config.taker_fee())
+                - grid_state.position_avg_price)
+                * quantity;
+            grid_state.position_quantity -= quantity;
+            grid_state.settle_realized_profit(profit, false, &grid_config.profit_mode);
+            info!(
+                "✅ 追踪止盈完成，数量: {:.4}, 预估利润: {:.2}, 剩余持仓: {:.4}",
+                quantity, profit, grid_state.position_quantity
+            );
+
+            // 剩余持仓不足以覆盖卖单时，取消价格最高的卖单
+            let mut sorted_orders: Vec<_> =
+                sell_orders.iter().map(|(k, v)| (*k, v.clone())).collect();
+            sorted_orders.sort_by(|a, b| {
+                b.1.price
+                    .partial_cmp(&a.1.price)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+
+            let mut pending_sell_quantity: f64 =
+                sorted_orders.iter().map(|(_, o)| o.quantity).sum();
+            for (oid, _) in sorted_orders {
+                if pending_sell_quantity <= grid_state.position_quantity {
+                    break;
+                }
+                // 冰山单按整组撤单
+                for group_oid in iceberg_group_oids(sell_orders, oid) {
+                    let Some(quantity) = sell_orders.get(&group_oid).map(|o| o.quantity) else {
+                        continue;
+                    };
+                    if let Err(e) = cancel_order(exchange_client, group_oid).await {
+                        warn!("取消卖单失败: {:?}", e);
+                    } else {
+                        active_orders.retain(|&x| x != group_oid);
+                        sell_orders.remove(&group_oid);
+                        pending_sell_quantity -= quantity;
+                    }
+                }
+            }
+
+            Ok(())
+        }
+        Err(e) => {
+            error!("❌ 追踪止盈下单失败: {:?}", e);
+            Err(GridStrategyError::OrderError(format!(
+                "追踪止盈失败: {:?}",
+                e
+            )))
+        }
+    }
+}
+
+// 检查持仓时间，超过最大持仓时间的批次按配置以市价或限价平仓
+pub(super) async fn check_holding_timeout(
+    exchange_client: &dyn OrderExecutor,
+    grid_config: &crate::config::GridConfig,
+    grid_state: &mut GridState,
+    active_orders: &mut Vec<u64>,
+    sell_orders: &mut HashMap<u64, OrderInfo>,
+    current_price: f64,
+) -> Result<(), GridStrategyError> {
+    grid_state.sync_position_lots();
+
+    let (expired_quantity, oldest_secs) =
+        grid_state.expired_position(grid_config.max_holding_time, sell_orders);
+    let quantity = format_price(
+        expired_quantity.min(grid_state.position_quantity),
+        grid_config.quantity_precision,
+    );
+    if quantity <= 0.0 {
+        return Ok(());
+    }
+
+    let use_market = grid_config.holding_timeout_close_mode != "limit";
+    let (limit_px, tif) = if use_market {
+        (
+            current_price * (1.0 - grid_config.slippage_tolerance),
+            "Ioc",
+        )
+    } else {
+        (current_price, "Gtc")
+    };
+    let limit_px = format_price(limit_px, grid_config.price_precision);
+
+    warn!(
+        "⏰ 持仓超时 - 最长持仓: {:.1}小时, 上限: {:.1}小时, 平仓数量: {:.4}, 方式: {}, 价格: {:.4}",
+        oldest_secs as f64 / 3600.0,
+        grid_config.max_holding_time as f64 / 3600.0,
+        quantity,
+        if use_market { "市价" } else { "限价" },
+        limit_px
+    );
+
+    let cloid = new_cloid();
+    if use_market {
+        grid_state
+            .expected_fill_prices
+            .register(cloid, current_price);
+    }
+    let close_order = ClientOrderRequest {
+        asset: grid_config.trading_asset.clone(),
+        is_buy: false,
+        reduce_only: true,
+        limit_px,
+        sz: quantity,
+        cloid: Some(cloid),
+        order_type: ClientOrder::Limit(ClientLimit {
+            tif: tif.to_string(),
+        }),
+    };
+
+    let response = exchange_client
+        .order(close_order, None)
+        .await
+        .map_err(|e| GridStrategyError::OrderError(format!("持仓超时平仓失败: {:?}", e)))?;
+    let oid = parse_order_response(&response)
+        .map_err(|e| GridStrategyError::OrderError(format!("持仓超时平仓被拒绝: {}", e)))?;
+
+    // 立即成交时按回报直接结算持仓、利润和批次，不记入卖单列表，随后到达的成交回报会被跳过
+    if let Some((oid, fill_size, fill_price)) = immediate_fill(&response) {
+        let profit = grid_state
+            .settle_immediate_fill(oid, false, fill_price, fill_size, None, grid_config)
+            .unwrap_or(0.0);
+        info!(
+            "✅ 持仓超时平仓已成交: ID={}, 数量={:.4}, 均价={:.4}, 利润: {:.2}",
+            oid, fill_size, fill_price, profit
+        );
+        return Ok(());
+    }
+
+    // 挂单由成交回报按卖单列表结算持仓、利润和批次
+    active_orders.push(oid);
+    sell_orders.insert(
+        oid,
+        OrderInfo {
+            is_buy: false,
+            price: limit_px,
+            quantity,
+            cost_price: Some(grid_state.position_avg_price),
+            potential_sell_price: None,
+            allocated_funds: 0.0,
+            iceberg: None,
+            created_at: safe_unix_timestamp(),
+            cloid: Some(cloid),
+            filled_quantity: 0.0,
+            unhedged_quantity: 0.0,
+            grid_pair: false,
+        },
+    );
+    info!(
+        "📋 持仓超时{}平仓单已提交: ID={}",
+        if use_market { "市价" } else { "限价" },
+        oid
+    );
+
+    // 只标记批次避免重复下单
+    grid_state.mark_expired_lots_closing(grid_config.max_holding_time, Some(oid));
+    Ok(())
+}
+
+// 检查保证金率 - 改进版本，包含健壮的错误处理
+pub(super) async fn check_margin_ratio(
+    exchange_client: &dyn OrderExecutor,
+    info_client: &InfoClient,
+    user_address: ethers::types::Address,
+    grid_config: &crate::config::GridConfig,
+) -> Result<f64, GridStrategyError> {
+    // 获取账户信息，包含重试机制
+    let account_info = match get_account_info(exchange_client, info_client, user_address).await {
+        Ok(info) => info,
+        Err(e) => {
+            warn!("⚠️ 获取账户信息失败，无法检查保证金率: {:?}", e);
+            return Err(GridStrategyError::ClientError(format!(
+                "获取账户信息失败: {:?}",
+                e
+            )));
+        }
+    };
+
+    // 检查margin_summary字段是否存在
+    let margin_summary = &account_info.margin_summary;
+
+    // 安全解析账户价值
+    let account_value = safe_parse_f64(&margin_summary.account_value, "account_value", 0.0)?;
+
+    // 安全解析已使用保证金
+    let total_margin_used =
+        safe_parse_f64(&margin_summary.total_margin_used, "total_margin_used", 0.0)?;
+
+    // 尝试解析其他相关字段以获得更完整的保证金信息
+    let total_ntl_pos =
+        safe_parse_f64(&margin_summary.total_ntl_pos, "total_ntl_pos", 0.0).unwrap_or(0.0);
+
+    let total_raw_usd =
+        safe_parse_f64(&margin_summary.total_raw_usd, "total_raw_usd", 0.0).unwrap_or(0.0);
+
+    info!("💳 保证金详细信息:");
+    info!("   账户价值: {:.2}", account_value);
+    info!("   已使用保证金: {:.2}", total_margin_used);
+    info!("   总持仓价值: {:.2}", total_ntl_pos);
+    info!("   总USD价值: {:.2}", total_raw_usd);
+
+    // 计算保证金率 - 使用多种方法确保准确性
+    let margin_ratio = if total_margin_used > 0.0 {
+        // 标准计算方法：可用资金 / 已使用保证金
+        account_value / total_margin_used
+    } else if total_ntl_pos > 0.0 {
+        // 备用计算方法：使用持仓价值
+        warn!("⚠️ total_margin_used为0，使用持仓价值计算保证金率");
+        account_value / (total_ntl_pos * 0.1) // 假设10%的保证金要求
+    } else {
+        // 没有持仓或保证金要求，认为是安全的
+        info!("💡 没有持仓或保证金要求，保证金率设为安全值");
+        10.0 // 设置一个安全的高值
+    };
+
+    // 验证计算结果的合理性
+    if !margin_ratio.is_finite() {
+        warn!("⚠️ 保证金率计算结果无效: {}，使用默认安全值", margin_ratio);
+        return Ok(10.0); // 返回安全值
+    }
+
+    if margin_ratio < 0.0 {
+        warn!("⚠️ 保证金率为负值: {:.2}，可能存在数据异常", margin_ratio);
+        return Err(GridStrategyError::MarginInsufficient(format!(
+            "保证金率异常: {:.2}%，可能存在账户数据问题",
+            margin_ratio * 100.0
+        )));
+    }
+
+    // 检查保证金安全阈值
+    if margin_ratio < grid_config.margin_safety_threshold {
+        warn!(
+            "🚨 保证金率过低: {:.2}%, 低于安全阈值: {:.2}%",
+            margin_ratio * 100.0,
+            grid_config.margin_safety_threshold * 100.0
+        );
+
+        // 提供详细的风险信息
+        let risk_level = if margin_ratio < grid_config.margin_safety_threshold * 0.5 {
+            "极高风险"
+        } else if margin_ratio < grid_config.margin_safety_threshold * 0.8 {
+            "高风险"
+        } else {
+            "中等风险"
+        };
+
+        warn!("🚨 风险等级: {} - 建议立即减仓或增加保证金", risk_level);
+
+        return Err(GridStrategyError::MarginInsufficient(format!(
+            "保证金率过低: {:.2}% (风险等级: {})",
+            margin_ratio * 100.0,
+            risk_level
+        )));
+    }
+
+    // 提供保证金健康度反馈
+    let health_status = if margin_ratio > grid_config.margin_safety_threshold * 3.0 {
+        "优秀"
+    } else if margin_ratio > grid_config.margin_safety_threshold * 2.0 {
+        "良好"
+    } else if margin_ratio > grid_config.margin_safety_threshold * 1.5 {
+        "一般"
+    } else {
+        "需要关注"
+    };
+
+    info!(
+        "💳 保证金健康度: {} (比率: {:.2}%)",
+        health_status,
+        margin_ratio * 100.0
+    );
+
+    Ok(margin_ratio)
+}
@@ -0,0 +1,279 @@
+// 部署前校验：配置参数、钱包和交易所连通性，不下单
+
+use super::*;
+use crate::tr_println;
+
+/// 单项校验结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Passed,
+    Warning,
+    Failed,
+}
+
+impl CheckStatus {
+    fn icon(&self) -> &'static str {
+        match self {
+            CheckStatus::Passed => "✅",
+            CheckStatus::Warning => "⚠️",
+            CheckStatus::Failed => "❌",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ValidateCheck {
+    pub status: CheckStatus,
+    pub name: String,
+    pub detail: String,
+}
+
+/// `validate` 子命令的校验清单
+#[derive(Debug, Clone, Default)]
+pub struct ValidateReport {
+    pub checks: Vec<ValidateCheck>,
+}
+
+impl ValidateReport {
+    fn record(&mut self, status: CheckStatus, name: impl Into<String>, detail: impl Into<String>) {
+        self.checks.push(ValidateCheck {
+            status,
+            name: name.into(),
+            detail: detail.into(),
+        });
+    }
+
+    fn pass(&mut self, name: impl Into<String>, detail: impl Into<String>) {
+        self.record(CheckStatus::Passed, name, detail);
+    }
+
+    fn warn(&mut self, name: impl Into<String>, detail: impl Into<String>) {
+        self.record(CheckStatus::Warning, name, detail);
+    }
+
+    fn fail(&mut self, name: impl Into<String>, detail: impl Into<String>) {
+        self.record(CheckStatus::Failed, name, detail);
+    }
+
+    /// 参数校验结果逐条计入清单，没有错误和警告时记为一项通过
+    fn merge_validation(&mut self, name: &str, result: &ValidationResult) {
+        for error in &result.errors {
+            self.fail(name, error);
+        }
+        for warning in &result.warnings {
+            self.warn(name, warning);
+        }
+        if result.errors.is_empty() && result.warnings.is_empty() {
+            self.pass(name, "参数验证通过");
+        }
+    }
+
+    pub fn count(&self, status: CheckStatus) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.status == status)
+            .count()
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.count(CheckStatus::Failed) == 0
+    }
+
+    /// 输出校验清单
+    pub fn print(&self) {
+        for check in &self.checks {
+            println!("{} {}: {}", check.status.icon(), check.name, check.detail);
+        }
+        tr_println!(
+            "校验完成: 通过 {} 项, 警告 {} 项, 错误 {} 项",
+            "Validation finished: {} passed, {} warnings, {} errors",
+            self.count(CheckStatus::Passed),
+            self.count(CheckStatus::Warning),
+            self.count(CheckStatus::Failed)
+        );
+    }
+}
+
+/// 只校验配置、钱包和交易所连通性，不启动策略也不下单
+///
+/// 依次检查网格参数（`validate_grid_config` 及其一致性检查）、初始和已保存的动态参数、
+/// 每个账户的钱包和查询地址，最后连接交易所确认能拉取交易对、价格和账户信息。
+pub async fn validate_deployment(app_config: &crate::config::AppConfig) -> ValidateReport {
+    let mut report = ValidateReport::default();
+    let grid_config = &app_config.grid;
+
+    // 1. 网格参数：基础检查失败时直接给出原因，一致性检查的问题逐条列出
+    let enhanced = validate_grid_config_enhanced(grid_config);
+    match validate_grid_config(grid_config) {
+        Ok(()) => report.pass("网格配置", "基础参数检查通过"),
+        Err(GridStrategyError::ConfigError(message)) if enhanced.is_valid => {
+            report.fail("网格配置", message)
+        }
+        Err(e) if enhanced.is_valid => report.fail("网格配置", e.to_string()),
+        Err(_) => {}
+    }
+    report.merge_validation("网格参数一致性", &enhanced);
+
+    // 2. 动态参数：按配置初始化的参数和上次运行保存的参数
+    let initial_params = DynamicGridParams::new(grid_config);
+    report.merge_validation(
+        "初始动态参数",
+        &validate_dynamic_parameters(&initial_params, grid_config, "配置校验"),
+    );
+
+    let accounts: Vec<(String, &crate::config::AccountConfig)> = if app_config.accounts.is_empty() {
+        vec![(account_label(&app_config.account, 0), &app_config.account)]
+    } else {
+        app_config
+            .accounts
+            .iter()
+            .enumerate()
+            .map(|(index, account)| (account_label(account, index), account))
+            .collect()
+    };
+    let multi_account = !app_config.accounts.is_empty();
+
+    for (label, _) in &accounts {
+        let mut account_grid = grid_config.clone();
+        if account_grid.dry_run {
+            account_grid.state_file_prefix = format!("dry_run_{}", account_grid.state_file_prefix);
+        }
+        if multi_account {
+            account_grid.state_file_prefix =
+                format!("{}{}_", account_grid.state_file_prefix, label);
+        }
+        let path = state_file_path(&account_grid, "dynamic_grid_params.json");
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let name = format!("已保存动态参数 ({})", label);
+        match serde_json::from_str::<DynamicGridParams>(&content) {
+            Ok(params) => {
+                let result = validate_dynamic_parameters(&params, grid_config, "配置校验");
+                // 加载时会自动修复或回退到默认参数，这里只提示
+                for error in result.errors.iter().chain(&result.warnings) {
+                    report.warn(&name, format!("{}（启动时将自动修复）", error));
+                }
+                if result.errors.is_empty() && result.warnings.is_empty() {
+                    report.pass(&name, path);
+                }
+            }
+            Err(e) => report.warn(
+                &name,
+                format!("{} 解析失败，启动时将使用默认参数: {}", path, e),
+            ),
+        }
+    }
+
+    // 3. 钱包和查询地址
+    let mut addresses = Vec::new();
+    for (label, account) in &accounts {
+        let name = format!("钱包 ({})", label);
+        let has_wallet_config = !account.private_key.trim().is_empty()
+            || account
+                .private_key_file
+                .as_deref()
+                .is_some_and(|path| !path.trim().is_empty());
+        let wallet_address = if grid_config.dry_run && !has_wallet_config {
+            report.warn(&name, "dry-run 模式未配置私钥，运行时使用临时随机钱包");
+            None
+        } else {
+            match load_wallet(account) {
+                Ok(wallet) => {
+                    report.pass(&name, format!("{:?}", wallet.address()));
+                    Some(wallet.address())
+                }
+                Err(e) => {
+                    report.fail(&name, e.to_string());
+                    None
+                }
+            }
+        };
+
+        let address = match account.real_account_address.as_deref() {
+            Some(address) => match address.trim().parse::<ethers::types::Address>() {
+                Ok(parsed) => {
+                    report.pass(format!("查询地址 ({})", label), format!("{:?}", parsed));
+                    Some(parsed)
+                }
+                Err(e) => {
+                    report.fail(
+                        format!("查询地址 ({})", label),
+                        format!("real_account_address \"{}\" 格式错误: {}", address, e),
+                    );
+                    None
+                }
+            },
+            None => wallet_address,
+        };
+        if let Some(address) = address {
+            addresses.push((label.clone(), address));
+        }
+    }
+
+    // 4. 交易所连通性
+    let info_client = match InfoClient::new(None, Some(BaseUrl::Mainnet)).await {
+        Ok(client) => {
+            report.pass("交易所连接", "信息客户端初始化成功");
+            client
+        }
+        Err(e) => {
+            report.fail("交易所连接", format!("信息客户端初始化失败: {:?}", e));
+            return report;
+        }
+    };
+
+    let asset = &grid_config.trading_asset;
+    acquire_api_permit().await;
+    match info_client.meta().await {
+        Ok(meta) => match meta.universe.iter().find(|a| &a.name == asset) {
+            Some(asset_meta) => report.pass(
+                "交易对",
+                format!(
+                    "{} 已上线, 数量精度 {} 位小数",
+                    asset, asset_meta.sz_decimals
+                ),
+            ),
+            None => report.fail("交易对", format!("交易所不存在交易对 {}", asset)),
+        },
+        Err(e) => report.fail("交易对", format!("获取交易所元数据失败: {:?}", e)),
+    }
+
+    acquire_api_permit().await;
+    match info_client.all_mids().await {
+        Ok(mids) => match mids.get(asset).and_then(|p| p.parse::<f64>().ok()) {
+            Some(price) if price > 0.0 => {
+                report.pass("当前价格", format!("{} {:.6}", asset, price))
+            }
+            _ => report.fail("当前价格", format!("无法获取 {} 的中间价", asset)),
+        },
+        Err(e) => report.fail("当前价格", format!("获取中间价失败: {:?}", e)),
+    }
+
+    let required_funds = grid_config.grid_count as f64 * grid_config.trade_amount;
+    for (label, address) in addresses {
+        let name = format!("账户信息 ({})", label);
+        acquire_api_permit().await;
+        match info_client.user_state(address).await {
+            Ok(state) => {
+                let account_value = state
+                    .margin_summary
+                    .account_value
+                    .parse::<f64>()
+                    .unwrap_or(0.0);
+                let detail = format!(
+                    "账户价值 {:.2}，网格所需 {:.2} ({} 格 × {:.2})",
+                    account_value, required_funds, grid_config.grid_count, grid_config.trade_amount
+                );
+                if account_value >= required_funds || grid_config.dry_run {
+                    report.pass(&name, detail);
+                } else {
+                    report.warn(&name, detail);
+                }
+            }
+            Err(e) => report.fail(&name, format!("获取账户信息失败: {:?}", e)),
+        }
+    }
+
+    report
+}
@@ -0,0 +1,187 @@
+#![allow(dead_code)]
+
+use hyperliquid_rust_sdk::{CandleData, CandlesSnapshotResponse};
+use std::collections::{HashMap, VecDeque};
+
+/// Hyperliquid 支持的K线周期
+pub const SUPPORTED_INTERVALS: [&str; 14] = [
+    "1m", "3m", "5m", "15m", "30m", "1h", "2h", "4h", "8h", "12h", "1d", "3d", "1w", "1M",
+];
+
+/// K线周期对应的毫秒数，不支持的周期返回 None
+pub fn interval_millis(interval: &str) -> Option<u64> {
+    const MINUTE: u64 = 60 * 1000;
+    let millis = match interval {
+        "1m" => MINUTE,
+        "3m" => 3 * MINUTE,
+        "5m" => 5 * MINUTE,
+        "15m" => 15 * MINUTE,
+        "30m" => 30 * MINUTE,
+        "1h" => 60 * MINUTE,
+        "2h" => 2 * 60 * MINUTE,
+        "4h" => 4 * 60 * MINUTE,
+        "8h" => 8 * 60 * MINUTE,
+        "12h" => 12 * 60 * MINUTE,
+        "1d" => 24 * 60 * MINUTE,
+        "3d" => 3 * 24 * 60 * MINUTE,
+        "1w" => 7 * 24 * 60 * MINUTE,
+        "1M" => 30 * 24 * 60 * MINUTE,
+        _ => return None,
+    };
+    Some(millis)
+}
+
+/// K线（OHLCV）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Kline {
+    pub time_open: u64, // 开盘时间（毫秒）
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+impl Kline {
+    /// 从 Candle 订阅推送转换，价格解析失败时返回 None
+    pub fn from_candle(data: &CandleData) -> Option<Self> {
+        Some(Self {
+            time_open: data.time_open,
+            open: data.open.parse().ok()?,
+            high: data.high.parse().ok()?,
+            low: data.low.parse().ok()?,
+            close: data.close.parse().ok()?,
+            volume: data.volume.parse().unwrap_or(0.0),
+        })
+    }
+
+    /// 从历史K线快照转换，价格解析失败时返回 None
+    pub fn from_snapshot(data: &CandlesSnapshotResponse) -> Option<Self> {
+        Some(Self {
+            time_open: data.time_open,
+            open: data.open.parse().ok()?,
+            high: data.high.parse().ok()?,
+            low: data.low.parse().ok()?,
+            close: data.close.parse().ok()?,
+            volume: data.vlm.parse().unwrap_or(0.0),
+        })
+    }
+}
+
+/// 多周期K线缓存，每个周期按开盘时间升序保存最近 `capacity` 根
+///
+/// 每个周期是预留两倍容量的环形缓冲区，超出容量时从队首弹出，
+/// 更新后保持内存连续，使 `make_contiguous` 的搬移开销均摊为 O(1)，并能以切片返回。
+#[derive(Debug, Clone)]
+pub struct KlineBuffer {
+    capacity: usize,
+    primary_interval: String,
+    series: HashMap<String, VecDeque<Kline>>,
+}
+
+impl Default for KlineBuffer {
+    fn default() -> Self {
+        Self::new(&["1m".to_string()], 200)
+    }
+}
+
+impl KlineBuffer {
+    /// 创建缓存，第一个周期作为指标计算的主周期
+    pub fn new(intervals: &[String], capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            primary_interval: intervals
+                .first()
+                .cloned()
+                .unwrap_or_else(|| "1m".to_string()),
+            series: intervals
+                .iter()
+                .map(|interval| (interval.clone(), VecDeque::with_capacity(capacity * 2)))
+                .collect(),
+        }
+    }
+
+    pub fn primary_interval(&self) -> &str {
+        &self.primary_interval
+    }
+
+    pub fn intervals(&self) -> impl Iterator<Item = &String> {
+        self.series.keys()
+    }
+
+    /// 更新K线：开盘时间相同则替换（未收盘K线会持续推送），更新的K线追加到末尾
+    pub fn update(&mut self, interval: &str, kline: Kline) {
+        let Some(series) = self.series.get_mut(interval) else {
+            return;
+        };
+
+        match series.back_mut() {
+            Some(last) if last.time_open == kline.time_open => *last = kline,
+            Some(last) if last.time_open > kline.time_open => {
+                // 乱序的旧K线，按开盘时间插入或替换
+                match series.binary_search_by_key(&kline.time_open, |k| k.time_open) {
+                    Ok(index) => series[index] = kline,
+                    Err(index) => series.insert(index, kline),
+                }
+            }
+            _ => series.push_back(kline),
+        }
+
+        while series.len() > self.capacity {
+            series.pop_front();
+        }
+        series.make_contiguous();
+    }
+
+    /// 批量载入历史K线
+    pub fn extend(&mut self, interval: &str, klines: impl IntoIterator<Item = Kline>) {
+        for kline in klines {
+            self.update(interval, kline);
+        }
+    }
+
+    pub fn get(&self, interval: &str) -> &[Kline] {
+        self.series
+            .get(interval)
+            .map(|series| series.as_slices().0)
+            .unwrap_or_default()
+    }
+
+    /// 主周期K线
+    pub fn primary(&self) -> &[Kline] {
+        self.get(&self.primary_interval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kline(time_open: u64, close: f64) -> Kline {
+        Kline {
+            time_open,
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1.0,
+        }
+    }
+
+    #[test]
+    fn update_keeps_latest_klines_in_order() {
+        let mut buffer = KlineBuffer::new(&["1m".to_string()], 3);
+        buffer.extend("1m", (0..10).map(|i| kline(i * 60_000, i as f64)));
+        // 未收盘K线持续推送时替换最后一根，乱序的旧K线按开盘时间插入
+        buffer.update("1m", kline(9 * 60_000, 9.5));
+        buffer.update("1m", kline(8 * 60_000, 8.5));
+
+        let closes: Vec<f64> = buffer.primary().iter().map(|k| k.close).collect();
+        assert_eq!(closes, vec![7.0, 8.5, 9.5]);
+
+        // 未订阅的周期忽略
+        buffer.update("5m", kline(0, 1.0));
+        assert!(buffer.get("5m").is_empty());
+    }
+}
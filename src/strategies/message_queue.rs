@@ -0,0 +1,323 @@
+// 订阅消息的有界转发：行情类消息只保留最新值，其余消息按顺序排队，积压超过上限时优先丢弃K线等非关键消息
+
+use hyperliquid_rust_sdk::Message;
+use log::{debug, error, warn};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{self, error::TrySendError, unbounded_channel, UnboundedSender};
+use tokio::sync::Notify;
+
+/// 每合并这么多条过期行情输出一次调试日志
+const COALESCE_LOG_INTERVAL: u64 = 1000;
+
+/// 积压持续期间重复告警的间隔
+const BACKLOG_WARN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 可合并消息的键，同一个键只保留最新一条
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CoalesceKey {
+    AllMids,
+    L2Book(String),
+}
+
+/// 价格和盘口只关心最新值；成交、K线等其余消息都不能丢
+fn coalesce_key(message: &Message) -> Option<CoalesceKey> {
+    match message {
+        Message::AllMids(_) => Some(CoalesceKey::AllMids),
+        Message::L2Book(book) => Some(CoalesceKey::L2Book(book.data.coin.clone())),
+        _ => None,
+    }
+}
+
+/// 成交回报和订单更新：积压时尽量保留，只有暂存区全是这类消息时才丢弃最早的一条
+fn is_critical(message: &Message) -> bool {
+    matches!(
+        message,
+        Message::User(_) | Message::UserFills(_) | Message::OrderUpdates(_)
+    )
+}
+
+/// 暂存区超出上限时丢弃一条消息，优先丢弃最早的非关键消息（K线、逐笔成交等），返回是否丢弃了关键消息
+fn shed_one(overflow: &mut VecDeque<Message>) -> bool {
+    match overflow.iter().position(|message| !is_critical(message)) {
+        Some(index) => {
+            overflow.remove(index);
+            false
+        }
+        None => {
+            overflow.pop_front();
+            true
+        }
+    }
+}
+
+/// 每个键最新的一条行情，主循环取走前新消息直接覆盖旧值
+#[derive(Default)]
+struct LatestMessages {
+    slots: Mutex<Vec<(CoalesceKey, Message)>>,
+    notify: Notify,
+    coalesced: AtomicU64,
+}
+
+impl LatestMessages {
+    fn replace(&self, key: CoalesceKey, message: Message) {
+        {
+            let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+            match slots.iter_mut().find(|(slot_key, _)| *slot_key == key) {
+                Some(slot) => {
+                    slot.1 = message;
+                    let coalesced = self.coalesced.fetch_add(1, Ordering::Relaxed) + 1;
+                    if coalesced.is_multiple_of(COALESCE_LOG_INTERVAL) {
+                        debug!("⏩ 处理慢于行情推送，已累计丢弃{}条过期行情", coalesced);
+                    }
+                }
+                None => slots.push((key, message)),
+            }
+        }
+        self.notify.notify_one();
+    }
+
+    fn take(&self) -> Option<Message> {
+        let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        if slots.is_empty() {
+            None
+        } else {
+            Some(slots.remove(0).1)
+        }
+    }
+}
+
+/// 主循环使用的接收端：优先取排队的消息，其次取各键最新的行情
+pub struct MessageReceiver {
+    queue: mpsc::Receiver<Message>,
+    latest: Arc<LatestMessages>,
+}
+
+impl MessageReceiver {
+    /// 等待下一条消息，转发任务结束且没有剩余消息时返回 None
+    pub async fn recv(&mut self) -> Option<Message> {
+        loop {
+            if let Ok(message) = self.queue.try_recv() {
+                return Some(message);
+            }
+            if let Some(message) = self.latest.take() {
+                return Some(message);
+            }
+            tokio::select! {
+                message = self.queue.recv() => {
+                    return message.or_else(|| self.latest.take());
+                }
+                _ = self.latest.notify.notified() => {}
+            }
+        }
+    }
+}
+
+/// 在订阅通道和主循环之间插入有界转发任务，返回供订阅使用的发送端和主循环的接收端
+///
+/// SDK 的订阅只接受无界发送端，转发任务立即取走每条消息：`coalesce` 开启时价格和盘口
+/// 按键覆盖只保留最新值，其余消息进入容量为 `capacity` 的有界队列；队列满时先放入转发任务的
+/// 暂存区按顺序补发，暂存区同样最多 `capacity` 条，超出后优先丢弃K线等非关键消息，全是成交回报
+/// 和订单更新时丢弃最早的一条，漏掉的成交由订单对账补发。
+///
+/// 回放模式关闭 `coalesce`，所有消息按顺序排队且不丢弃：暂存区满时暂停读取上游，
+/// 回放数据已整体加载在内存中，暂停读取不会再增加占用，保持回放结果可复现。
+pub fn spawn_message_relay(
+    capacity: usize,
+    coalesce: bool,
+) -> (UnboundedSender<Message>, MessageReceiver) {
+    let (sender, mut upstream) = unbounded_channel::<Message>();
+    let (queue_sender, queue) = mpsc::channel::<Message>(capacity.max(1));
+    let latest = Arc::new(LatestMessages::default());
+    let relay_latest = latest.clone();
+
+    let overflow_limit = capacity.max(1);
+
+    tokio::spawn(async move {
+        let mut overflow: VecDeque<Message> = VecDeque::new();
+        let mut dropped = 0u64;
+        let mut dropped_critical = 0u64;
+        let mut last_backlog_warn: Option<Instant> = None;
+
+        loop {
+            tokio::select! {
+                message = upstream.recv(), if coalesce || overflow.len() < overflow_limit => {
+                    let Some(message) = message else { break };
+                    match coalesce_key(&message) {
+                        Some(key) if coalesce => relay_latest.replace(key, message),
+                        _ => {
+                            overflow.push_back(message);
+                            if overflow.len() > overflow_limit {
+                                dropped += 1;
+                                if shed_one(&mut overflow) {
+                                    dropped_critical += 1;
+                                    error!(
+                                        "❌ 消息暂存区已满且全部为成交/订单事件，丢弃最早一条（累计{}条），漏掉的成交由订单对账补发",
+                                        dropped_critical
+                                    );
+                                }
+                            }
+                        }
+                    }
+                }
+                permit = queue_sender.reserve(), if !overflow.is_empty() => {
+                    let Ok(permit) = permit else { return };
+                    if let Some(message) = overflow.pop_front() {
+                        permit.send(message);
+                    }
+                }
+            }
+
+            // 队列有空位时直接放入，满了留在暂存区等待主循环取走
+            while let Some(message) = overflow.pop_front() {
+                match queue_sender.try_send(message) {
+                    Ok(()) => {}
+                    Err(TrySendError::Full(message)) => {
+                        overflow.push_front(message);
+                        break;
+                    }
+                    Err(TrySendError::Closed(_)) => return,
+                }
+            }
+            if overflow.len() >= overflow_limit {
+                if last_backlog_warn.is_none_or(|at| at.elapsed() >= BACKLOG_WARN_INTERVAL) {
+                    warn!(
+                        "⚠️ 消息队列已满，暂存{}条消息等待处理，累计丢弃{}条，主循环处理速度跟不上推送",
+                        overflow.len(),
+                        dropped
+                    );
+                    last_backlog_warn = Some(Instant::now());
+                }
+            } else if overflow.is_empty() {
+                last_backlog_warn = None;
+            }
+        }
+
+        // 上游关闭后把暂存的消息补发完
+        for message in overflow {
+            if queue_sender.send(message).await.is_err() {
+                return;
+            }
+        }
+    });
+
+    (sender, MessageReceiver { queue, latest })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mids_message(price: f64) -> Message {
+        serde_json::from_value(serde_json::json!({
+            "channel": "allMids",
+            "data": { "mids": { "BTC": price.to_string() } },
+        }))
+        .expect("allMids 消息格式")
+    }
+
+    fn fill_message(oid: u64) -> Message {
+        serde_json::from_value(serde_json::json!({
+            "channel": "user",
+            "data": { "fills": [{
+                "coin": "BTC",
+                "side": "B",
+                "px": "100.0",
+                "sz": "0.1",
+                "time": oid,
+                "hash": "0x0",
+                "startPosition": "0.0",
+                "dir": "Open Long",
+                "closedPnl": "0.0",
+                "oid": oid,
+                "cloid": null,
+                "crossed": false,
+                "fee": "0.0",
+                "tid": oid,
+                "feeToken": "USDC",
+            }] },
+        }))
+        .expect("user 消息格式")
+    }
+
+    /// 关闭发送端，等转发任务处理完全部消息后按顺序取出
+    async fn drain(
+        sender: UnboundedSender<Message>,
+        mut receiver: MessageReceiver,
+    ) -> Vec<Message> {
+        drop(sender);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let mut messages = Vec::new();
+        while let Some(message) = receiver.recv().await {
+            messages.push(message);
+        }
+        messages
+    }
+
+    fn fill_oids(messages: &[Message]) -> Vec<u64> {
+        messages
+            .iter()
+            .filter_map(|message| match message {
+                Message::User(user) => match &user.data {
+                    hyperliquid_rust_sdk::UserData::Fills(fills) => Some(fills[0].oid),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn coalesces_mids_and_keeps_user_events_in_order() {
+        let (sender, receiver) = spawn_message_relay(4, true);
+        for i in 0..200u64 {
+            sender.send(mids_message(100.0 + i as f64)).unwrap();
+            if i % 50 == 0 {
+                sender.send(fill_message(i / 50 + 1)).unwrap();
+            }
+        }
+
+        let messages = drain(sender, receiver).await;
+        assert_eq!(fill_oids(&messages), vec![1, 2, 3, 4]);
+
+        let mids: Vec<&str> = messages
+            .iter()
+            .filter_map(|message| match message {
+                Message::AllMids(all_mids) => all_mids.data.mids.get("BTC").map(String::as_str),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(mids, vec!["299"]);
+    }
+
+    #[tokio::test]
+    async fn sheds_non_critical_messages_before_user_events() {
+        let (sender, receiver) = spawn_message_relay(2, true);
+        sender.send(fill_message(1)).unwrap();
+        for _ in 0..10 {
+            sender.send(Message::Pong).unwrap();
+        }
+        sender.send(fill_message(2)).unwrap();
+        sender.send(fill_message(3)).unwrap();
+
+        let messages = drain(sender, receiver).await;
+        assert_eq!(fill_oids(&messages), vec![1, 2, 3]);
+        // 有界队列和暂存区各 2 条，其余 Pong 被丢弃
+        assert_eq!(messages.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn replay_mode_keeps_every_message() {
+        let (sender, receiver) = spawn_message_relay(2, false);
+        for i in 0..20u64 {
+            sender.send(mids_message(100.0 + i as f64)).unwrap();
+        }
+        sender.send(fill_message(1)).unwrap();
+
+        let messages = drain(sender, receiver).await;
+        assert_eq!(messages.len(), 21);
+        assert_eq!(fill_oids(&messages), vec![1]);
+    }
+}
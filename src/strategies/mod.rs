@@ -1,4 +1,20 @@
+pub mod backtest;
 pub mod batch_optimizer;
 pub mod error;
+pub mod executor;
+pub mod export;
 pub mod grid;
+pub mod kline;
+pub mod message_queue;
+pub mod optimize;
+pub mod orderbook;
 pub mod performance;
+pub mod preflight;
+pub mod price_feed;
+pub mod rate_limiter;
+pub mod replay;
+pub mod risk_log;
+pub mod spot;
+pub mod state_backend;
+pub mod trading_window;
+pub mod triangle;
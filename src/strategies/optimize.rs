@@ -0,0 +1,335 @@
+#![allow(dead_code)]
+
+use log::{info, warn};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use super::backtest::{backtest_grid, BacktestResult, GridParams};
+use super::error::GridStrategyError;
+use crate::config::GridConfig;
+
+/// 参数搜索方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMethod {
+    Grid,   // 网格搜索：遍历所有组合
+    Random, // 随机搜索：从搜索空间中随机抽取组合
+}
+
+impl SearchMethod {
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.trim().to_lowercase().as_str() {
+            "grid" => Some(SearchMethod::Grid),
+            "random" => Some(SearchMethod::Random),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchMethod::Grid => "网格搜索",
+            SearchMethod::Random => "随机搜索",
+        }
+    }
+
+    pub fn as_english(&self) -> &'static str {
+        match self {
+            SearchMethod::Grid => "Grid Search",
+            SearchMethod::Random => "Random Search",
+        }
+    }
+}
+
+crate::impl_bilingual!(SearchMethod);
+
+/// 结果排序指标
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RankMetric {
+    Roi,         // 收益率从高到低
+    Sharpe,      // 夏普比率从高到低
+    MaxDrawdown, // 最大回撤从低到高
+}
+
+impl RankMetric {
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.trim().to_lowercase().as_str() {
+            "roi" => Some(RankMetric::Roi),
+            "sharpe" => Some(RankMetric::Sharpe),
+            "drawdown" | "max_drawdown" => Some(RankMetric::MaxDrawdown),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RankMetric::Roi => "收益率",
+            RankMetric::Sharpe => "夏普比率",
+            RankMetric::MaxDrawdown => "最大回撤",
+        }
+    }
+
+    pub fn as_english(&self) -> &'static str {
+        match self {
+            RankMetric::Roi => "ROI",
+            RankMetric::Sharpe => "Sharpe Ratio",
+            RankMetric::MaxDrawdown => "Max Drawdown",
+        }
+    }
+}
+
+crate::impl_bilingual!(RankMetric);
+
+/// 参数搜索空间，未指定的参数使用配置中的当前值
+#[derive(Debug, Clone)]
+pub struct SearchSpace {
+    pub min_grid_spacing: Vec<f64>,
+    pub grid_count: Vec<u32>,
+    pub trade_amount: Vec<f64>,
+}
+
+impl SearchSpace {
+    /// 以配置为基础，用命令行给出的取值覆盖对应参数
+    pub fn new(
+        grid_config: &GridConfig,
+        min_grid_spacing: Option<&str>,
+        grid_count: Option<&str>,
+        trade_amount: Option<&str>,
+    ) -> Result<Self, GridStrategyError> {
+        let min_grid_spacing = match min_grid_spacing {
+            Some(spec) => parse_values(spec, "min_grid_spacing")?,
+            None => vec![grid_config.min_grid_spacing],
+        };
+        let grid_count = match grid_count {
+            Some(spec) => parse_values(spec, "grid_count")?
+                .into_iter()
+                .map(|v| v.round() as u32)
+                .collect(),
+            None => vec![grid_config.grid_count],
+        };
+        let trade_amount = match trade_amount {
+            Some(spec) => parse_values(spec, "trade_amount")?,
+            None => vec![grid_config.trade_amount],
+        };
+
+        if min_grid_spacing.iter().any(|v| *v <= 0.0 || *v >= 1.0) {
+            return Err(GridStrategyError::ConfigError(
+                "min_grid_spacing 取值必须在 0 到 1 之间".to_string(),
+            ));
+        }
+        if grid_count.contains(&0) {
+            return Err(GridStrategyError::ConfigError(
+                "grid_count 取值必须大于0".to_string(),
+            ));
+        }
+        if trade_amount.iter().any(|v| *v <= 0.0) {
+            return Err(GridStrategyError::ConfigError(
+                "trade_amount 取值必须大于0".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            min_grid_spacing,
+            grid_count,
+            trade_amount,
+        })
+    }
+
+    /// 组合总数
+    pub fn size(&self) -> usize {
+        self.min_grid_spacing.len() * self.grid_count.len() * self.trade_amount.len()
+    }
+
+    fn params_at(&self, index: usize) -> GridParams {
+        let spacing = index % self.min_grid_spacing.len();
+        let rest = index / self.min_grid_spacing.len();
+        let count = rest % self.grid_count.len();
+        let amount = rest / self.grid_count.len();
+        GridParams {
+            min_grid_spacing: self.min_grid_spacing[spacing],
+            grid_count: self.grid_count[count],
+            trade_amount: self.trade_amount[amount],
+        }
+    }
+
+    /// 按搜索方式生成待回测的参数组合，最多 `max_iterations` 组
+    pub fn candidates(
+        &self,
+        method: SearchMethod,
+        max_iterations: usize,
+        seed: u64,
+    ) -> Vec<GridParams> {
+        let total = self.size();
+        let limit = max_iterations.min(total);
+
+        match method {
+            SearchMethod::Grid => {
+                if total > max_iterations {
+                    warn!(
+                        "⚠️ 搜索空间共{}组参数，超过最大迭代数{}，只评估前{}组",
+                        total, max_iterations, limit
+                    );
+                }
+                (0..limit).map(|i| self.params_at(i)).collect()
+            }
+            SearchMethod::Random => {
+                let mut rng = XorShift::new(seed);
+                let mut picked = HashSet::with_capacity(limit);
+                let mut candidates = Vec::with_capacity(limit);
+                while candidates.len() < limit {
+                    let index = (rng.next() % total as u64) as usize;
+                    if picked.insert(index) {
+                        candidates.push(self.params_at(index));
+                    }
+                }
+                candidates
+            }
+        }
+    }
+}
+
+/// 解析参数取值：逗号分隔的列表（如 `0.002,0.004`）或 `起始:结束:步长` 区间（含结束值）
+pub fn parse_values(spec: &str, name: &str) -> Result<Vec<f64>, GridStrategyError> {
+    let invalid =
+        || GridStrategyError::ConfigError(format!("无法解析参数 {} 的取值: {}", name, spec));
+
+    let values: Vec<f64> = if spec.contains(':') {
+        let parts: Vec<f64> = spec
+            .split(':')
+            .map(|part| part.trim().parse::<f64>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid())?;
+        let [start, end, step] = parts[..] else {
+            return Err(invalid());
+        };
+        if step <= 0.0 || end < start {
+            return Err(invalid());
+        }
+        let steps = ((end - start) / step + 1e-9).floor() as usize;
+        (0..=steps).map(|i| start + step * i as f64).collect()
+    } else {
+        spec.split(',')
+            .map(|part| part.trim().parse::<f64>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid())?
+    };
+
+    if values.is_empty() || values.iter().any(|v| !v.is_finite()) {
+        return Err(invalid());
+    }
+    Ok(values)
+}
+
+/// 并行回测所有参数组合，每个组合在独立的阻塞任务中运行
+pub async fn run_optimization(
+    prices: Arc<Vec<f64>>,
+    grid_config: Arc<GridConfig>,
+    candidates: Vec<GridParams>,
+) -> Result<Vec<BacktestResult>, GridStrategyError> {
+    let total = candidates.len();
+    let parallelism = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4);
+    let semaphore = Arc::new(Semaphore::new(parallelism));
+    let mut tasks = JoinSet::new();
+
+    info!(
+        "🔬 开始参数优化 - 组合数: {}, 价格点数: {}, 并行度: {}",
+        total,
+        prices.len(),
+        parallelism
+    );
+
+    for params in candidates {
+        let prices = prices.clone();
+        let grid_config = grid_config.clone();
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            tokio::task::spawn_blocking(move || backtest_grid(&prices, params, &grid_config)).await
+        });
+    }
+
+    let mut results = Vec::with_capacity(total);
+    let report_every = (total / 10).max(1);
+    while let Some(joined) = tasks.join_next().await {
+        let result = joined
+            .map_err(|e| GridStrategyError::ConfigError(format!("回测任务异常退出: {:?}", e)))?
+            .map_err(|e| GridStrategyError::ConfigError(format!("回测任务异常退出: {:?}", e)))?;
+        results.push(result);
+
+        let done = results.len();
+        if done % report_every == 0 || done == total {
+            info!(
+                "⏳ 优化进度: {}/{} ({:.0}%)",
+                done,
+                total,
+                done as f64 / total as f64 * 100.0
+            );
+        }
+    }
+
+    Ok(results)
+}
+
+/// 按指标排序并保留前 `top_n` 组
+pub fn rank_results(
+    mut results: Vec<BacktestResult>,
+    metric: RankMetric,
+    top_n: usize,
+) -> Vec<BacktestResult> {
+    results.sort_by(|a, b| match metric {
+        RankMetric::Roi => b.roi.total_cmp(&a.roi),
+        RankMetric::Sharpe => b.sharpe_ratio.total_cmp(&a.sharpe_ratio),
+        RankMetric::MaxDrawdown => a.max_drawdown.total_cmp(&b.max_drawdown),
+    });
+    results.truncate(top_n);
+    results
+}
+
+/// 将排名结果写入 JSON 文件
+pub fn write_results(
+    path: &Path,
+    method: SearchMethod,
+    metric: RankMetric,
+    evaluated: usize,
+    results: &[BacktestResult],
+) -> Result<(), GridStrategyError> {
+    let report = serde_json::json!({
+        "method": method.as_english(),
+        "rank_by": metric.as_english(),
+        "evaluated": evaluated,
+        "top": results,
+    });
+    let contents = serde_json::to_string_pretty(&report)
+        .map_err(|e| GridStrategyError::ConfigError(format!("序列化优化结果失败: {:?}", e)))?;
+    std::fs::write(path, contents).map_err(|e| {
+        GridStrategyError::ConfigError(format!("写入优化结果 {} 失败: {:?}", path.display(), e))
+    })
+}
+
+/// 未指定随机种子时使用当前时间
+pub fn default_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0x2545_F491_4F6C_DD1D)
+}
+
+// 随机搜索用的 xorshift64 生成器，不需要密码学强度
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
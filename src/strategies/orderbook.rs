@@ -0,0 +1,189 @@
+#![allow(dead_code)]
+
+use hyperliquid_rust_sdk::{BookLevel, L2BookData};
+use std::time::{Duration, SystemTime};
+
+/// 盘口快照超过该时间未更新视为过期，不再参与估算
+pub const ORDER_BOOK_STALE_SECS: u64 = 30;
+
+/// 盘口档位
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceLevel {
+    pub price: f64,
+    pub size: f64,
+}
+
+/// 深度估算结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FillEstimate {
+    pub avg_price: f64,     // 估算成交均价
+    pub worst_price: f64,   // 吃到的最差一档价格
+    pub filled_size: f64,   // 盘口可成交数量
+    pub fully_filled: bool, // 盘口深度是否足够全部成交
+}
+
+/// 本地订单簿快照，由 `Subscription::L2Book` 推送更新
+#[derive(Debug, Clone)]
+pub struct OrderBook {
+    bids: Vec<PriceLevel>, // 买盘，价格从高到低
+    asks: Vec<PriceLevel>, // 卖盘，价格从低到高
+    updated_at: Option<SystemTime>,
+}
+
+impl Default for OrderBook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OrderBook {
+    pub fn new() -> Self {
+        Self {
+            bids: Vec::new(),
+            asks: Vec::new(),
+            updated_at: None,
+        }
+    }
+
+    /// 用 L2Book 推送替换本地快照（levels[0] 为买盘，levels[1] 为卖盘）
+    pub fn update(&mut self, data: &L2BookData) {
+        self.bids = data
+            .levels
+            .first()
+            .map(|levels| parse_levels(levels))
+            .unwrap_or_default();
+        self.asks = data
+            .levels
+            .get(1)
+            .map(|levels| parse_levels(levels))
+            .unwrap_or_default();
+
+        self.bids.sort_by(|a, b| {
+            b.price
+                .partial_cmp(&a.price)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        self.asks.sort_by(|a, b| {
+            a.price
+                .partial_cmp(&b.price)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        self.updated_at = Some(SystemTime::now());
+    }
+
+    /// 快照是否可用（有双边盘口且未过期）
+    pub fn is_ready(&self) -> bool {
+        !self.bids.is_empty()
+            && !self.asks.is_empty()
+            && self
+                .updated_at
+                .and_then(|t| t.elapsed().ok())
+                .map(|age| age < Duration::from_secs(ORDER_BOOK_STALE_SECS))
+                .unwrap_or(false)
+    }
+
+    pub fn best_bid(&self) -> Option<f64> {
+        self.bids.first().map(|l| l.price)
+    }
+
+    pub fn best_ask(&self) -> Option<f64> {
+        self.asks.first().map(|l| l.price)
+    }
+
+    pub fn mid_price(&self) -> Option<f64> {
+        match (self.best_bid(), self.best_ask()) {
+            (Some(bid), Some(ask)) => Some((bid + ask) / 2.0),
+            _ => None,
+        }
+    }
+
+    /// 估算以市价吃单 `size` 数量的成交均价，买单吃卖盘、卖单吃买盘
+    ///
+    /// 快照不可用时返回 None；深度不足时只统计盘口上能成交的部分。
+    pub fn estimate_fill_price(&self, is_buy: bool, size: f64) -> Option<FillEstimate> {
+        if !self.is_ready() || size <= 0.0 {
+            return None;
+        }
+
+        let levels = if is_buy { &self.asks } else { &self.bids };
+        let mut remaining = size;
+        let mut notional = 0.0;
+        let mut worst_price = levels[0].price;
+
+        for level in levels {
+            if remaining <= 0.0 {
+                break;
+            }
+            let take = remaining.min(level.size);
+            notional += take * level.price;
+            remaining -= take;
+            worst_price = level.price;
+        }
+
+        let filled_size = size - remaining.max(0.0);
+        if filled_size <= 0.0 {
+            return None;
+        }
+
+        Some(FillEstimate {
+            avg_price: notional / filled_size,
+            worst_price,
+            filled_size,
+            fully_filled: remaining <= 0.0,
+        })
+    }
+
+    /// 成交均价相对中间价的偏离不超过 `max_slippage` 时，最多可以成交的数量
+    pub fn max_size_within_slippage(&self, is_buy: bool, max_slippage: f64) -> Option<f64> {
+        let mid = self.mid_price()?;
+        if !self.is_ready() {
+            return None;
+        }
+
+        let limit_avg = if is_buy {
+            mid * (1.0 + max_slippage)
+        } else {
+            mid * (1.0 - max_slippage)
+        };
+        let levels = if is_buy { &self.asks } else { &self.bids };
+        let mut size = 0.0;
+        let mut notional = 0.0;
+
+        for level in levels {
+            let within = if is_buy {
+                level.price <= limit_avg
+            } else {
+                level.price >= limit_avg
+            };
+            if within {
+                size += level.size;
+                notional += level.size * level.price;
+                continue;
+            }
+
+            // 部分吃入该档，使整体均价刚好等于限价：(notional + x*p) / (size + x) = limit_avg
+            let partial = (limit_avg * size - notional) / (level.price - limit_avg);
+            if partial > 0.0 {
+                size += partial.min(level.size);
+            }
+            break;
+        }
+
+        Some(size)
+    }
+}
+
+fn parse_levels(levels: &[BookLevel]) -> Vec<PriceLevel> {
+    levels
+        .iter()
+        .filter_map(|level| {
+            let price = level.px.parse::<f64>().ok()?;
+            let size = level.sz.parse::<f64>().ok()?;
+            if price > 0.0 && size > 0.0 {
+                Some(PriceLevel { price, size })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
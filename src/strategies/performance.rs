@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::VecDeque;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 /// 性能指标结构体
@@ -212,6 +213,44 @@ impl PerformanceMetrics {
     }
 }
 
+/// 记录一笔平仓交易并增量更新实盘性能指标
+///
+/// 口径与网格策略按历史记录重算指标时一致：胜率为 0-1 的比例，平均亏损和最大单笔亏损取绝对值，
+/// 没有亏损交易时盈亏比为无穷大。最大回撤和夏普比率不在此更新。
+pub fn record_trade(metrics: &mut PerformanceMetrics, profit: f64) {
+    // 由平均值还原累计盈亏，避免额外保存累计字段
+    let mut total_wins = metrics.average_win * metrics.winning_trades as f64;
+    let mut total_losses = metrics.average_loss * metrics.losing_trades as f64;
+
+    metrics.total_trades += 1;
+    metrics.total_profit += profit;
+
+    if profit > 0.0 {
+        metrics.winning_trades += 1;
+        total_wins += profit;
+        metrics.largest_win = metrics.largest_win.max(profit);
+    } else if profit < 0.0 {
+        metrics.losing_trades += 1;
+        total_losses += profit.abs();
+        metrics.largest_loss = metrics.largest_loss.max(profit.abs());
+    }
+
+    metrics.win_rate = metrics.winning_trades as f64 / metrics.total_trades as f64;
+    if metrics.winning_trades > 0 {
+        metrics.average_win = total_wins / metrics.winning_trades as f64;
+    }
+    if metrics.losing_trades > 0 {
+        metrics.average_loss = total_losses / metrics.losing_trades as f64;
+    }
+    metrics.profit_factor = if total_losses > 0.0 {
+        total_wins / total_losses
+    } else if total_wins > 0.0 {
+        f64::INFINITY
+    } else {
+        0.0
+    };
+}
+
 impl Default for PerformanceMetrics {
     fn default() -> Self {
         Self::new()
@@ -223,10 +262,19 @@ impl Default for PerformanceMetrics {
 pub struct PerformanceRecord {
     #[serde(with = "system_time_serde")]
     pub timestamp: SystemTime,
+    #[serde(deserialize_with = "nullable_f64::deserialize")]
     pub price: f64,
     pub action: String,
+    #[serde(deserialize_with = "nullable_f64::deserialize")]
     pub profit: f64,
+    #[serde(deserialize_with = "nullable_f64::deserialize")]
     pub total_capital: f64,
+    #[serde(default)]
+    pub side: String, // 成交方向（buy/sell），旧版本记录为空
+    #[serde(default)]
+    pub fee: f64, // 该笔成交支付的手续费
+    #[serde(default)]
+    pub order_id: Option<u64>, // 对应的交易所订单ID
 }
 
 impl PerformanceRecord {
@@ -238,9 +286,20 @@ impl PerformanceRecord {
             action,
             profit,
             total_capital,
+            side: String::new(),
+            fee: 0.0,
+            order_id: None,
         }
     }
 
+    /// 补充成交方向、手续费和订单ID，用于对账
+    pub fn with_fill(mut self, side: &str, fee: f64, order_id: Option<u64>) -> Self {
+        self.side = side.to_string();
+        self.fee = fee;
+        self.order_id = order_id;
+        self
+    }
+
     /// 创建买入记录
     pub fn buy_record(price: f64, quantity: f64, total_capital: f64) -> Self {
         Self::new(
@@ -249,6 +308,7 @@ impl PerformanceRecord {
             0.0, // 买入时利润为0
             total_capital,
         )
+        .with_fill("buy", 0.0, None)
     }
 
     /// 创建卖出记录
@@ -259,6 +319,7 @@ impl PerformanceRecord {
             profit,
             total_capital,
         )
+        .with_fill("sell", 0.0, None)
     }
 
     /// 获取记录的年龄（秒）
@@ -371,14 +432,130 @@ impl PerformanceSnapshot {
     }
 }
 
-/// 性能分析器
-#[derive(Debug)]
+/// 周期（日/周）交易汇总
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PeriodSummary {
+    pub period: String, // 周期标识，如 2025-05-27 或 2025-W22
+    pub start_time: u64,
+    pub end_time: u64,
+    pub trade_count: usize,
+    pub winning_trades: usize,
+    pub losing_trades: usize,
+    pub realized_profit: f64, // 已扣除手续费
+    pub total_fees: f64,
+    pub max_drawdown: f64, // 周期内按成交后总资产计算的最大回撤（0-1）
+}
+
+impl PeriodSummary {
+    /// 汇总 `[start, end)` 时间段内的成交记录
+    pub fn from_records(
+        period: String,
+        records: &[PerformanceRecord],
+        start: SystemTime,
+        end: SystemTime,
+    ) -> Self {
+        let in_period: Vec<&PerformanceRecord> = records
+            .iter()
+            .filter(|r| r.timestamp >= start && r.timestamp < end)
+            .collect();
+
+        // 以第一笔成交前的总资产为起点，逐笔计算回撤
+        let mut peak = in_period
+            .first()
+            .map(|r| r.total_capital - r.profit)
+            .unwrap_or(0.0);
+        let mut max_drawdown: f64 = 0.0;
+        for record in &in_period {
+            peak = peak.max(record.total_capital);
+            if peak > 0.0 {
+                max_drawdown = max_drawdown.max((peak - record.total_capital) / peak);
+            }
+        }
+
+        Self {
+            period,
+            start_time: start
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            end_time: end.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+            trade_count: in_period.len(),
+            winning_trades: in_period.iter().filter(|r| r.profit > 0.0).count(),
+            losing_trades: in_period.iter().filter(|r| r.profit < 0.0).count(),
+            realized_profit: in_period.iter().map(|r| r.profit).sum(),
+            total_fees: in_period.iter().map(|r| r.fee).sum(),
+            max_drawdown,
+        }
+    }
+
+    /// 生成文本报告
+    pub fn generate_report(&self, title: &str) -> String {
+        let win_rate = if self.trade_count > 0 {
+            self.winning_trades as f64 / self.trade_count as f64 * 100.0
+        } else {
+            0.0
+        };
+        format!(
+            "{} ({})\n\
+            ==================\n\
+            成交数: {} (盈利 {} / 亏损 {})\n\
+            胜率: {:.1}%\n\
+            已实现盈亏(已扣手续费): {:.4}\n\
+            手续费支出: {:.4}\n\
+            最大回撤: {:.2}%",
+            title,
+            self.period,
+            self.trade_count,
+            self.winning_trades,
+            self.losing_trades,
+            win_rate,
+            self.realized_profit,
+            self.total_fees,
+            self.max_drawdown * 100.0
+        )
+    }
+}
+
+/// 资金曲线上的一个点（每笔成交后的总资产）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct EquityPoint {
+    pub timestamp: u64,
+    pub equity: f64,
+}
+
+/// 回撤序列上的一个点，drawdown 为相对历史峰值的回撤比例（0-1）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DrawdownPoint {
+    pub timestamp: u64,
+    pub equity: f64,
+    pub peak: f64,
+    pub drawdown: f64,
+}
+
+/// 滚动夏普比率上的一个点，按最近 window 笔成交的收益率计算
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RollingSharpePoint {
+    pub timestamp: u64,
+    pub window: usize,
+    pub sharpe_ratio: f64,
+}
+
+/// 性能分析器：保存成交记录并维护实盘性能指标
+///
+/// 序列化字段名沿用网格状态文件中的 `performance_history`/`current_metrics`，旧版本状态文件可以直接加载。
+/// 指标口径与 `record_trade` 一致：胜率为 0-1 的比例，平均亏损和最大单笔亏损取绝对值。
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct PerformanceAnalyzer {
+    #[serde(rename = "current_metrics")]
     pub metrics: PerformanceMetrics,
+    #[serde(rename = "performance_history")]
     pub records: Vec<PerformanceRecord>,
-    pub snapshots: Vec<PerformanceSnapshot>,
-    pub max_records: usize,
-    pub max_snapshots: usize,
+    #[serde(skip)]
+    pub snapshots: VecDeque<PerformanceSnapshot>,
+    #[serde(skip)]
+    pub max_records: usize, // 最多保留的成交记录数，0表示不限制
+    #[serde(skip)]
+    pub max_snapshots: usize, // 最多保留的快照数，0表示不保留
 }
 
 impl PerformanceAnalyzer {
@@ -387,33 +564,35 @@ impl PerformanceAnalyzer {
         Self {
             metrics: PerformanceMetrics::new(),
             records: Vec::new(),
-            snapshots: Vec::new(),
+            snapshots: VecDeque::new(),
             max_records,
             max_snapshots,
         }
     }
 
-    /// 添加交易记录
-    pub fn add_trade_record(&mut self, record: PerformanceRecord) {
-        // 更新指标
-        self.metrics.update_trade(record.profit);
+    /// 不限制成交记录数量的分析器，网格策略按全部成交生成日报/周报和导出
+    pub fn unbounded() -> Self {
+        Self::new(0, 0)
+    }
 
-        // 添加记录
+    /// 添加一笔成交记录并增量更新指标（最大回撤和夏普比率由 `recalculate_metrics` 更新）
+    pub fn add_trade(&mut self, record: PerformanceRecord) {
+        record_trade(&mut self.metrics, record.profit);
         self.records.push(record);
 
-        // 限制记录数量
-        if self.records.len() > self.max_records {
-            self.records.remove(0);
+        if self.max_records > 0 && self.records.len() > self.max_records {
+            let excess = self.records.len() - self.max_records;
+            self.records.drain(..excess);
         }
     }
 
     /// 添加性能快照
     pub fn add_snapshot(&mut self, snapshot: PerformanceSnapshot) {
-        self.snapshots.push(snapshot);
+        self.snapshots.push_back(snapshot);
 
         // 限制快照数量
         if self.snapshots.len() > self.max_snapshots {
-            self.snapshots.remove(0);
+            self.snapshots.pop_front();
         }
     }
 
@@ -425,29 +604,90 @@ impl PerformanceAnalyzer {
             .collect()
     }
 
-    /// 计算收益率序列
+    /// 每笔成交的收益率（成交盈亏 / 成交后总资产）
     pub fn calculate_returns(&self) -> Vec<f64> {
-        if self.records.len() < 2 {
-            return Vec::new();
-        }
+        self.records
+            .iter()
+            .map(|r| r.profit / r.total_capital)
+            .collect()
+    }
 
-        let mut returns = Vec::new();
-        for i in 1..self.records.len() {
-            let prev_capital = self.records[i - 1].total_capital;
-            let curr_capital = self.records[i].total_capital;
-            if prev_capital > 0.0 {
-                let return_rate = (curr_capital - prev_capital) / prev_capital;
-                returns.push(return_rate);
-            }
+    /// 资金曲线，每笔成交后的总资产
+    pub fn equity_curve(&self) -> Vec<EquityPoint> {
+        self.records
+            .iter()
+            .map(|r| EquityPoint {
+                timestamp: unix_secs(r.timestamp),
+                equity: r.total_capital,
+            })
+            .collect()
+    }
+
+    /// 回撤序列，峰值从 `initial_equity` 开始计算
+    pub fn drawdown_series(&self, initial_equity: f64) -> Vec<DrawdownPoint> {
+        let mut peak = initial_equity;
+        self.records
+            .iter()
+            .map(|r| {
+                peak = peak.max(r.total_capital);
+                DrawdownPoint {
+                    timestamp: unix_secs(r.timestamp),
+                    equity: r.total_capital,
+                    peak,
+                    drawdown: if peak > 0.0 {
+                        (peak - r.total_capital) / peak
+                    } else {
+                        0.0
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// 滚动夏普比率，成交数不足一个窗口时返回空序列
+    pub fn rolling_sharpe(&self, window: usize) -> Vec<RollingSharpePoint> {
+        if window < 2 {
+            return Vec::new();
         }
+        let returns = self.calculate_returns();
         returns
+            .windows(window)
+            .zip(self.records.iter().skip(window - 1))
+            .map(|(window_returns, record)| RollingSharpePoint {
+                timestamp: unix_secs(record.timestamp),
+                window,
+                sharpe_ratio: sharpe_ratio(window_returns),
+            })
+            .collect()
     }
 
-    /// 更新夏普比率
-    pub fn update_sharpe_ratio(&mut self, risk_free_rate: f64) {
-        let returns = self.calculate_returns();
-        self.metrics
-            .calculate_sharpe_ratio(&returns, risk_free_rate);
+    /// 按全部成交记录重算指标并保存
+    pub fn recalculate_metrics(&mut self, initial_capital: f64, realized_profit: f64) {
+        self.metrics = self.compute_metrics(initial_capital, realized_profit);
+    }
+
+    /// 按全部成交记录计算指标，包括最大回撤和夏普比率
+    ///
+    /// 总盈亏取策略累计的已实现利润（已扣除手续费），回撤峰值从 `initial_capital` 开始。
+    pub fn compute_metrics(
+        &self,
+        initial_capital: f64,
+        realized_profit: f64,
+    ) -> PerformanceMetrics {
+        let mut metrics = PerformanceMetrics::new();
+        for record in &self.records {
+            record_trade(&mut metrics, record.profit);
+        }
+        if metrics.total_trades > 0 {
+            metrics.total_profit = realized_profit;
+            metrics.max_drawdown = self
+                .drawdown_series(initial_capital)
+                .iter()
+                .map(|point| point.drawdown)
+                .fold(0.0, f64::max);
+            metrics.sharpe_ratio = sharpe_ratio(&self.calculate_returns());
+        }
+        metrics
     }
 
     /// 生成详细报告
@@ -456,21 +696,23 @@ impl PerformanceAnalyzer {
         let recent_profit: f64 = recent_trades.iter().map(|r| r.profit).sum();
 
         format!(
-            "{}\n\n\
+            "总交易数: {} (盈利 {} / 亏损 {})\n\
+            胜率: {:.1}%\n\
+            盈亏比: {:.2}\n\
+            夏普比率: {:.2}\n\
+            最大回撤: {:.2}%\n\n\
             最近24小时统计:\n\
             交易次数: {}\n\
-            净利润: {:.4}\n\n\
-            风险评分: {:.1}/100\n\
-            性能状态: {}",
-            self.metrics.get_summary(),
+            净利润: {:.4}",
+            self.metrics.total_trades,
+            self.metrics.winning_trades,
+            self.metrics.losing_trades,
+            self.metrics.win_rate * 100.0,
+            self.metrics.profit_factor,
+            self.metrics.sharpe_ratio,
+            self.metrics.max_drawdown * 100.0,
             recent_trades.len(),
             recent_profit,
-            self.metrics.get_risk_score(),
-            if self.metrics.is_performing_well() {
-                "良好"
-            } else {
-                "需要关注"
-            }
         )
     }
 
@@ -488,6 +730,185 @@ impl Default for PerformanceAnalyzer {
     }
 }
 
+/// 样本标准差口径的夏普比率（无风险利率按0），样本不足或没有波动时为0
+fn sharpe_ratio(returns: &[f64]) -> f64 {
+    if returns.len() < 2 {
+        return 0.0;
+    }
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance =
+        returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+    let std_dev = variance.sqrt();
+    if std_dev > 0.0 {
+        mean / std_dev
+    } else {
+        0.0
+    }
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// 挂单到成交的耗时统计，买单和卖单分别保留最近 `FILL_LATENCY_WINDOW` 笔
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct FillLatencyStats {
+    buy_secs: VecDeque<u64>,
+    sell_secs: VecDeque<u64>,
+}
+
+/// 成交耗时分位数汇总（分钟），没有样本时为 None
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct FillLatencySummary {
+    pub samples: usize,
+    pub p50_minutes: Option<f64>,
+    pub p90_minutes: Option<f64>,
+}
+
+const FILL_LATENCY_WINDOW: usize = 200;
+
+impl FillLatencyStats {
+    /// 记录一笔成交的耗时（秒）
+    pub fn record(&mut self, is_buy: bool, latency_secs: u64) {
+        let samples = if is_buy {
+            &mut self.buy_secs
+        } else {
+            &mut self.sell_secs
+        };
+        samples.push_back(latency_secs);
+        if samples.len() > FILL_LATENCY_WINDOW {
+            samples.pop_front();
+        }
+    }
+
+    pub fn buy_summary(&self) -> FillLatencySummary {
+        summarize_latency(&self.buy_secs)
+    }
+
+    pub fn sell_summary(&self) -> FillLatencySummary {
+        summarize_latency(&self.sell_secs)
+    }
+
+    /// 买卖单合计的平均成交耗时（分钟），没有样本时为 None
+    pub fn average_minutes(&self) -> Option<f64> {
+        let count = self.buy_secs.len() + self.sell_secs.len();
+        if count == 0 {
+            return None;
+        }
+        let total: u64 = self.buy_secs.iter().chain(self.sell_secs.iter()).sum();
+        Some(total as f64 / count as f64 / 60.0)
+    }
+}
+
+fn summarize_latency(samples: &VecDeque<u64>) -> FillLatencySummary {
+    let mut sorted: Vec<u64> = samples.iter().copied().collect();
+    sorted.sort_unstable();
+    // 最近秩法取分位数
+    let percentile = |p: f64| -> Option<f64> {
+        if sorted.is_empty() {
+            return None;
+        }
+        let rank = ((p * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len());
+        Some(sorted[rank - 1] as f64 / 60.0)
+    };
+    FillLatencySummary {
+        samples: sorted.len(),
+        p50_minutes: percentile(0.5),
+        p90_minutes: percentile(0.9),
+    }
+}
+
+impl FillLatencySummary {
+    /// 格式化为 "P50 x分钟 / P90 y分钟 (n笔)"
+    pub fn describe(&self) -> String {
+        match (self.p50_minutes, self.p90_minutes) {
+            (Some(p50), Some(p90)) => format!(
+                "P50 {:.1}分钟 / P90 {:.1}分钟 ({}笔)",
+                p50, p90, self.samples
+            ),
+            _ => "暂无数据".to_string(),
+        }
+    }
+}
+
+/// 按滑点价下单（清仓、止损、止盈、过期转市价）的实际成交滑点统计
+///
+/// 滑点为相对预期价的不利偏离比例：买入成交价高于预期、卖出成交价低于预期为正，价格更优时为负。
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SlippageStats {
+    samples: u64,
+    total_slippage: f64, // 各笔滑点比例之和
+    total_cost: f64,     // 累计滑点成本（USDC）
+    max_slippage: f64,
+    recent: VecDeque<f64>, // 最近 SLIPPAGE_WINDOW 笔的滑点比例
+}
+
+const SLIPPAGE_WINDOW: usize = 20;
+
+impl SlippageStats {
+    /// 记录一笔成交，返回本笔滑点比例
+    pub fn record(&mut self, is_buy: bool, expected_price: f64, fill_price: f64, size: f64) -> f64 {
+        if expected_price <= 0.0 {
+            return 0.0;
+        }
+        let deviation = if is_buy {
+            fill_price - expected_price
+        } else {
+            expected_price - fill_price
+        };
+        let slippage = deviation / expected_price;
+
+        self.samples += 1;
+        self.total_slippage += slippage;
+        self.total_cost += deviation * size;
+        self.max_slippage = self.max_slippage.max(slippage);
+        self.recent.push_back(slippage);
+        if self.recent.len() > SLIPPAGE_WINDOW {
+            self.recent.pop_front();
+        }
+        slippage
+    }
+
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    pub fn total_cost(&self) -> f64 {
+        self.total_cost
+    }
+
+    pub fn max_slippage(&self) -> f64 {
+        self.max_slippage
+    }
+
+    /// 全部样本的平均滑点，没有样本时为 None
+    pub fn average(&self) -> Option<f64> {
+        (self.samples > 0).then(|| self.total_slippage / self.samples as f64)
+    }
+
+    /// 最近一个窗口的平均滑点，样本不足一个窗口时为 None
+    pub fn recent_average(&self) -> Option<f64> {
+        (self.recent.len() >= SLIPPAGE_WINDOW)
+            .then(|| self.recent.iter().sum::<f64>() / self.recent.len() as f64)
+    }
+
+    /// 格式化为 "平均 x% / 最大 y% / 累计成本 z (n笔)"
+    pub fn describe(&self) -> String {
+        match self.average() {
+            Some(average) => format!(
+                "平均 {:.3}% / 最大 {:.3}% / 累计成本 {:.4} ({}笔)",
+                average * 100.0,
+                self.max_slippage * 100.0,
+                self.total_cost,
+                self.samples
+            ),
+            None => "暂无数据".to_string(),
+        }
+    }
+}
+
 /// SystemTime 序列化辅助模块
 pub mod system_time_serde {
     use super::*;
@@ -496,7 +917,7 @@ pub mod system_time_serde {
     where
         S: Serializer,
     {
-        let duration = time.duration_since(UNIX_EPOCH).unwrap();
+        let duration = time.duration_since(UNIX_EPOCH).unwrap_or_default();
         duration.as_secs().serialize(serializer)
     }
 
@@ -508,3 +929,84 @@ pub mod system_time_serde {
         Ok(UNIX_EPOCH + std::time::Duration::from_secs(secs))
     }
 }
+
+/// f64 反序列化辅助模块
+///
+/// serde_json 会把 NaN/Inf 序列化为 null，读取时将 null 还原为 NaN，避免整个状态文件加载失败。
+pub mod nullable_f64 {
+    use super::*;
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<f64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Option::<f64>::deserialize(deserializer)?.unwrap_or(f64::NAN))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "actual {}, expected {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn slippage_sign_follows_order_side() {
+        let mut stats = SlippageStats::default();
+        // 买单成交价高于预期、卖单成交价低于预期都是不利滑点
+        assert_close(stats.record(true, 100.0, 101.0, 2.0), 0.01);
+        assert_close(stats.record(false, 100.0, 99.0, 2.0), 0.01);
+        assert_eq!(stats.samples(), 2);
+        assert_close(stats.total_cost(), 4.0);
+        assert_close(stats.max_slippage(), 0.01);
+        assert_close(stats.average().unwrap(), 0.01);
+    }
+
+    #[test]
+    fn better_price_counts_as_negative_slippage() {
+        let mut stats = SlippageStats::default();
+        assert_close(stats.record(true, 100.0, 99.5, 1.0), -0.005);
+        assert_close(stats.record(false, 100.0, 100.5, 1.0), -0.005);
+        assert_close(stats.average().unwrap(), -0.005);
+        assert_close(stats.total_cost(), -1.0);
+        // 最大滑点只统计不利方向
+        assert_close(stats.max_slippage(), 0.0);
+    }
+
+    #[test]
+    fn invalid_expected_price_is_ignored() {
+        let mut stats = SlippageStats::default();
+        assert_eq!(stats.record(true, 0.0, 100.0, 1.0), 0.0);
+        assert_eq!(stats.samples(), 0);
+        assert!(stats.average().is_none());
+        assert_eq!(stats.describe(), "暂无数据");
+    }
+
+    #[test]
+    fn recent_average_needs_a_full_window() {
+        let mut stats = SlippageStats::default();
+        for _ in 0..SLIPPAGE_WINDOW - 1 {
+            stats.record(true, 100.0, 100.2, 1.0);
+        }
+        assert!(stats.recent_average().is_none());
+
+        stats.record(true, 100.0, 100.2, 1.0);
+        assert_close(stats.recent_average().unwrap(), 0.002);
+
+        // 窗口只保留最近的样本，旧的大滑点移出后不再影响
+        let mut stats = SlippageStats::default();
+        stats.record(true, 100.0, 110.0, 1.0);
+        for _ in 0..SLIPPAGE_WINDOW {
+            stats.record(true, 100.0, 100.1, 1.0);
+        }
+        assert_close(stats.recent_average().unwrap(), 0.001);
+        assert_close(stats.max_slippage(), 0.1);
+    }
+}
@@ -0,0 +1,274 @@
+#![allow(dead_code)]
+
+use hyperliquid_rust_sdk::{ExchangeResponseStatus, InfoClient, UserStateResponse};
+use log::{error, info, warn};
+
+use super::executor::OrderExecutor;
+use super::rate_limiter::acquire_api_permit;
+use crate::config::GridConfig;
+
+/// Hyperliquid 永续合约价格的最大小数位数，实际上限为该值减去数量精度（szDecimals）
+const PERP_MAX_PRICE_DECIMALS: u32 = 6;
+
+/// Hyperliquid 价格最多保留的有效数字位数（整数价格不受限制）
+const MAX_PRICE_SIGNIFICANT_FIGURES: i32 = 5;
+
+/// 单项就绪检查结果
+#[derive(Debug, Clone)]
+pub struct PreflightCheck {
+    pub name: &'static str,
+    pub passed: bool,
+    pub required: bool, // 硬性检查，失败时拒绝启动
+    pub detail: String,
+}
+
+/// 启动前就绪检查报告
+#[derive(Debug, Clone, Default)]
+pub struct PreflightReport {
+    pub checks: Vec<PreflightCheck>,
+    pub current_price: Option<f64>,
+    pub margin_ratio: Option<f64>, // 无保证金占用时为 None
+}
+
+impl PreflightReport {
+    fn record(&mut self, name: &'static str, passed: bool, required: bool, detail: String) {
+        self.checks.push(PreflightCheck {
+            name,
+            passed,
+            required,
+            detail,
+        });
+    }
+
+    /// 所有硬性检查是否通过
+    pub fn is_ready(&self) -> bool {
+        self.checks.iter().all(|c| c.passed || !c.required)
+    }
+
+    /// 未通过的硬性检查
+    pub fn blocking_failures(&self) -> Vec<&PreflightCheck> {
+        self.checks
+            .iter()
+            .filter(|c| !c.passed && c.required)
+            .collect()
+    }
+
+    /// 逐项输出检查结果
+    pub fn log(&self) {
+        info!("===== 启动就绪检查 =====");
+        for check in &self.checks {
+            if check.passed {
+                info!("✅ {}: {}", check.name, check.detail);
+            } else if check.required {
+                error!("❌ {}: {}", check.name, check.detail);
+            } else {
+                warn!("⚠️ {}: {}", check.name, check.detail);
+            }
+        }
+        info!(
+            "===== 就绪检查{} =====",
+            if self.is_ready() {
+                "通过"
+            } else {
+                "未通过"
+            }
+        );
+    }
+}
+
+/// 启动前检查交易对、价格区间、账户余额、杠杆设置和初始保证金率
+///
+/// 杠杆设置在此完成。dry-run 模式下余额不足只给出警告。
+pub async fn run_preflight_checks(
+    info_client: &InfoClient,
+    exchange_client: &dyn OrderExecutor,
+    account_info: &UserStateResponse,
+    grid_config: &GridConfig,
+) -> PreflightReport {
+    let mut report = PreflightReport::default();
+    let asset = &grid_config.trading_asset;
+
+    // 1. 交易对是否存在
+    acquire_api_permit().await;
+    match info_client.meta().await {
+        Ok(meta) => {
+            let exists = meta.universe.iter().any(|a| &a.name == asset);
+            report.record(
+                "交易对",
+                exists,
+                true,
+                if exists {
+                    format!("{} 已上线", asset)
+                } else {
+                    format!("交易所不存在交易对 {}", asset)
+                },
+            );
+        }
+        Err(e) => report.record(
+            "交易对",
+            false,
+            true,
+            format!("获取交易所元数据失败: {:?}", e),
+        ),
+    }
+
+    // 2. 当前价格及预期区间
+    acquire_api_permit().await;
+    match info_client.all_mids().await {
+        Ok(mids) => match mids.get(asset).and_then(|p| p.parse::<f64>().ok()) {
+            Some(price) if price > 0.0 => {
+                report.current_price = Some(price);
+                report.record("当前价格", true, true, format!("{:.4}", price));
+
+                let min = grid_config.expected_price_min;
+                let max = grid_config.expected_price_max;
+                if min.is_some() || max.is_some() {
+                    let in_range =
+                        !min.is_some_and(|m| price < m) && !max.is_some_and(|m| price > m);
+                    report.record(
+                        "价格区间",
+                        in_range,
+                        true,
+                        format!(
+                            "当前价格 {:.4}，预期区间 [{}, {}]",
+                            price,
+                            min.map_or("-".to_string(), |m| format!("{:.4}", m)),
+                            max.map_or("-".to_string(), |m| format!("{:.4}", m))
+                        ),
+                    );
+                }
+            }
+            _ => report.record(
+                "当前价格",
+                false,
+                true,
+                format!("无法获取 {} 的中间价", asset),
+            ),
+        },
+        Err(e) => report.record("当前价格", false, true, format!("获取中间价失败: {:?}", e)),
+    }
+
+    // 3. 账户余额是否覆盖 grid_count × trade_amount
+    let account_value = account_info
+        .margin_summary
+        .account_value
+        .parse::<f64>()
+        .unwrap_or(0.0);
+    let required_funds = grid_config.grid_count as f64 * grid_config.trade_amount;
+    report.record(
+        "账户余额",
+        account_value >= required_funds,
+        !grid_config.dry_run,
+        format!(
+            "账户价值 {:.2}，网格所需 {:.2} ({} 格 × {:.2})",
+            account_value, required_funds, grid_config.grid_count, grid_config.trade_amount
+        ),
+    );
+
+    // 4. 杠杆设置
+    let leverage_result = exchange_client
+        .update_leverage(grid_config.leverage, asset, false, None)
+        .await;
+    match leverage_result {
+        Ok(ExchangeResponseStatus::Err(e)) => {
+            report.record("杠杆设置", false, true, format!("交易所拒绝: {}", e))
+        }
+        Ok(_) => report.record("杠杆设置", true, true, format!("{}x", grid_config.leverage)),
+        Err(e) => report.record("杠杆设置", false, true, format!("请求失败: {:?}", e)),
+    }
+
+    // 5. 初始保证金率
+    let margin_used = account_info
+        .margin_summary
+        .total_margin_used
+        .parse::<f64>()
+        .unwrap_or(0.0);
+    if margin_used > 0.0 {
+        let ratio = account_value / margin_used;
+        report.margin_ratio = Some(ratio);
+        report.record(
+            "保证金率",
+            ratio >= grid_config.margin_safety_threshold,
+            true,
+            format!(
+                "{:.2}% (安全阈值 {:.2}%)",
+                ratio * 100.0,
+                grid_config.margin_safety_threshold * 100.0
+            ),
+        );
+    } else {
+        report.record("保证金率", true, false, "无持仓".to_string());
+    }
+
+    report
+}
+
+/// 从交易所元数据获取交易对精度并校验配置
+///
+/// 数量精度取 szDecimals；价格精度取 `6 - szDecimals` 与当前价格下5位有效数字所允许的小数位中的较小值。
+/// `auto_precision` 开启时以交易所为准覆盖配置，关闭时只在不一致时警告。获取失败时保留配置值。
+pub async fn resolve_asset_precision(info_client: &InfoClient, grid_config: &mut GridConfig) {
+    let asset = grid_config.trading_asset.clone();
+    acquire_api_permit().await;
+    let sz_decimals = match info_client.meta().await {
+        Ok(meta) => match meta.universe.iter().find(|a| a.name == asset) {
+            Some(asset_meta) => asset_meta.sz_decimals,
+            None => {
+                warn!("⚠️ 交易所元数据中没有 {}，使用配置的精度", asset);
+                return;
+            }
+        },
+        Err(e) => {
+            warn!("⚠️ 获取交易所元数据失败，使用配置的精度: {:?}", e);
+            return;
+        }
+    };
+
+    let mut price_decimals = PERP_MAX_PRICE_DECIMALS.saturating_sub(sz_decimals);
+    acquire_api_permit().await;
+    let current_price = match info_client.all_mids().await {
+        Ok(mids) => mids.get(&asset).and_then(|p| p.parse::<f64>().ok()),
+        Err(_) => None,
+    };
+    if let Some(price) = current_price.filter(|p| *p > 0.0) {
+        let integer_digits = price.log10().floor() as i32 + 1;
+        let significant_decimals = (MAX_PRICE_SIGNIFICANT_FIGURES - integer_digits).max(0) as u32;
+        price_decimals = price_decimals.min(significant_decimals);
+    }
+
+    let quantity_matches = grid_config.quantity_precision == sz_decimals;
+    let price_matches = grid_config.price_precision == price_decimals;
+    if quantity_matches && price_matches {
+        info!(
+            "✅ {} 精度与交易所一致 - 价格: {}位小数, 数量: {}位小数",
+            asset, price_decimals, sz_decimals
+        );
+        return;
+    }
+
+    if grid_config.auto_precision {
+        warn!(
+            "⚠️ {} 配置精度与交易所不一致，已按交易所覆盖 - 价格: {} -> {}, 数量: {} -> {}",
+            asset,
+            grid_config.price_precision,
+            price_decimals,
+            grid_config.quantity_precision,
+            sz_decimals
+        );
+        grid_config.price_precision = price_decimals;
+        grid_config.quantity_precision = sz_decimals;
+    } else {
+        if !quantity_matches {
+            warn!(
+                "⚠️ {} 数量精度配置为{}位小数，交易所要求{}位，下单可能被拒",
+                asset, grid_config.quantity_precision, sz_decimals
+            );
+        }
+        if !price_matches {
+            warn!(
+                "⚠️ {} 价格精度配置为{}位小数，交易所当前允许{}位，下单可能被拒或网格间距失真",
+                asset, grid_config.price_precision, price_decimals
+            );
+        }
+    }
+}
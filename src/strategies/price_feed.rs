@@ -0,0 +1,172 @@
+#![allow(dead_code)]
+
+use std::time::{Duration, Instant};
+
+/// 当前使用的价格来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceSource {
+    WebSocket, // WebSocket 推送（主价格源）
+    Rest,      // REST 轮询（推送中断时的兜底）
+}
+
+impl PriceSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PriceSource::WebSocket => "WebSocket推送",
+            PriceSource::Rest => "REST轮询",
+        }
+    }
+
+    pub fn as_english(&self) -> &'static str {
+        match self {
+            PriceSource::WebSocket => "websocket",
+            PriceSource::Rest => "rest",
+        }
+    }
+}
+
+/// 价格源健康监控
+///
+/// WebSocket 为主价格源，超过 `stale_after` 没有收到价格推送时改为每隔
+/// `rest_poll_interval` 通过 REST 拉取一次价格，收到新的推送后自动切回。
+#[derive(Debug, Clone)]
+pub struct PriceFeedMonitor {
+    stale_after: Option<Duration>, // None 表示不启用 REST 兜底
+    rest_poll_interval: Duration,
+    source: PriceSource,
+    last_rest_poll: Option<Instant>,
+    fallback_since: Option<Instant>,
+    fallback_count: u32,
+    total_fallback: Duration,
+    rest_polls: u64,
+    rest_failures: u64,
+}
+
+impl PriceFeedMonitor {
+    /// `stale_after_secs` 为0时不启用 REST 兜底，只使用 WebSocket 推送
+    pub fn new(stale_after_secs: u64, rest_poll_interval_secs: u64) -> Self {
+        Self {
+            stale_after: (stale_after_secs > 0).then(|| Duration::from_secs(stale_after_secs)),
+            rest_poll_interval: Duration::from_secs(rest_poll_interval_secs.max(1)),
+            source: PriceSource::WebSocket,
+            last_rest_poll: None,
+            fallback_since: None,
+            fallback_count: 0,
+            total_fallback: Duration::ZERO,
+            rest_polls: 0,
+            rest_failures: 0,
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self::new(0, 1)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.stale_after.is_some()
+    }
+
+    pub fn source(&self) -> PriceSource {
+        self.source
+    }
+
+    /// WebSocket 价格推送是否已超时
+    pub fn is_ws_stale(&self, last_ws_data: Instant) -> bool {
+        self.stale_after
+            .is_some_and(|stale_after| last_ws_data.elapsed() >= stale_after)
+    }
+
+    /// 是否需要立即通过 REST 拉取价格
+    pub fn should_poll_rest(&self, last_ws_data: Instant) -> bool {
+        self.is_ws_stale(last_ws_data)
+            && self
+                .last_rest_poll
+                .is_none_or(|last| last.elapsed() >= self.rest_poll_interval)
+    }
+
+    /// 等待 WebSocket 消息的最长时间，到期后重新检查是否需要 REST 轮询；None 表示一直等待
+    pub fn next_wait(&self, last_ws_data: Instant) -> Option<Duration> {
+        let stale_after = self.stale_after?;
+        if !self.is_ws_stale(last_ws_data) {
+            return Some(stale_after.saturating_sub(last_ws_data.elapsed()));
+        }
+        let since_poll = self
+            .last_rest_poll
+            .map(|last| last.elapsed())
+            .unwrap_or(self.rest_poll_interval);
+        Some(self.rest_poll_interval.saturating_sub(since_poll))
+    }
+
+    /// 记录一次 REST 轮询，返回是否刚从 WebSocket 切换到 REST
+    pub fn record_rest_poll(&mut self, success: bool) -> bool {
+        self.last_rest_poll = Some(Instant::now());
+        self.rest_polls += 1;
+        if !success {
+            self.rest_failures += 1;
+        }
+
+        if self.source == PriceSource::Rest {
+            return false;
+        }
+        self.source = PriceSource::Rest;
+        self.fallback_since = Some(Instant::now());
+        self.fallback_count += 1;
+        true
+    }
+
+    /// 收到 WebSocket 价格推送，从 REST 切回时返回本次兜底持续的时间
+    pub fn on_ws_price(&mut self) -> Option<Duration> {
+        if self.source == PriceSource::WebSocket {
+            return None;
+        }
+        self.source = PriceSource::WebSocket;
+        self.last_rest_poll = None;
+        let duration = self
+            .fallback_since
+            .take()
+            .map(|since| since.elapsed())
+            .unwrap_or_default();
+        self.total_fallback += duration;
+        Some(duration)
+    }
+
+    /// 累计使用 REST 兜底的时间（含进行中的一次）
+    pub fn total_fallback(&self) -> Duration {
+        self.total_fallback
+            + self
+                .fallback_since
+                .map(|since| since.elapsed())
+                .unwrap_or_default()
+    }
+
+    /// 状态报告中的一行价格源健康信息
+    pub fn status_line(&self, last_ws_data: Instant) -> String {
+        format!(
+            "{} (WebSocket {}秒前更新, 兜底 {} 次/累计 {}秒, REST 轮询 {} 次/失败 {} 次{})",
+            self.source.as_str(),
+            last_ws_data.elapsed().as_secs(),
+            self.fallback_count,
+            self.total_fallback().as_secs(),
+            self.rest_polls,
+            self.rest_failures,
+            if self.is_enabled() {
+                ""
+            } else {
+                ", REST 兜底未启用"
+            }
+        )
+    }
+
+    /// 结构化的价格源健康信息，供控制接口 status 指令返回
+    pub fn status_json(&self, last_ws_data: Instant) -> serde_json::Value {
+        serde_json::json!({
+            "source": self.source.as_english(),
+            "rest_fallback_enabled": self.is_enabled(),
+            "ws_last_update_secs": last_ws_data.elapsed().as_secs(),
+            "fallback_count": self.fallback_count,
+            "total_fallback_secs": self.total_fallback().as_secs(),
+            "rest_polls": self.rest_polls,
+            "rest_failures": self.rest_failures,
+        })
+    }
+}
@@ -0,0 +1,197 @@
+#![allow(dead_code)]
+
+use log::{debug, info, warn};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// 令牌桶状态
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// 令牌桶限流器
+///
+/// 每秒补充 `requests_per_second` 个令牌，最多累积 `burst` 个；每次请求消耗一个令牌，
+/// 令牌不足时等待补充。等待期间持有锁，多个调用方按到达顺序依次放行。
+pub struct RateLimiter {
+    requests_per_second: f64,
+    burst: f64,
+    bucket: tokio::sync::Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        let burst = burst.max(1) as f64;
+        Self {
+            requests_per_second,
+            burst,
+            bucket: tokio::sync::Mutex::new(Bucket {
+                tokens: burst,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// 每秒请求数不大于0时不限流
+    pub fn is_enabled(&self) -> bool {
+        self.requests_per_second > 0.0
+    }
+
+    /// 获取一个令牌，令牌不足时等待
+    pub async fn acquire(&self) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut bucket = self.bucket.lock().await;
+        loop {
+            let Err(wait) = self.try_take(&mut bucket, Instant::now()) else {
+                return;
+            };
+            debug!("⏱️ API 限流，等待 {}ms", wait.as_millis());
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// 按 `now` 补充令牌后尝试取一个，令牌不足时返回还需等待的时间
+    fn try_take(&self, bucket: &mut Bucket, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.requests_per_second).min(self.burst);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        Err(Duration::from_secs_f64(
+            (1.0 - bucket.tokens) / self.requests_per_second,
+        ))
+    }
+}
+
+/// 进程内所有账户共用的交易所 API 限流器，交易所按 IP 统计请求权重
+static GLOBAL_RATE_LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+
+/// 初始化全局限流器，只有第一次调用生效
+pub fn init_global_rate_limiter(requests_per_second: f64, burst: u32) {
+    let limiter = RateLimiter::new(requests_per_second, burst);
+    let enabled = limiter.is_enabled();
+    if GLOBAL_RATE_LIMITER.set(limiter).is_err() {
+        warn!("⚠️ 全局 API 限流器已初始化，忽略新的限流配置");
+        return;
+    }
+
+    if enabled {
+        info!(
+            "🚦 交易所 API 限流已启用 - 每秒 {} 次，突发 {} 次",
+            requests_per_second,
+            burst.max(1)
+        );
+    } else {
+        warn!("⚠️ 交易所 API 限流已关闭，突发请求可能触发交易所 429");
+    }
+}
+
+/// 调用交易所 API 前获取令牌，未初始化时不限流
+pub async fn acquire_api_permit() {
+    if let Some(limiter) = GLOBAL_RATE_LIMITER.get() {
+        limiter.acquire().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn full_bucket(limiter: &RateLimiter, now: Instant) -> Bucket {
+        Bucket {
+            tokens: limiter.burst,
+            last_refill: now,
+        }
+    }
+
+    fn assert_wait(result: Result<(), Duration>, expected_ms: f64) {
+        let wait = result.expect_err("令牌不足时应返回等待时间");
+        assert!(
+            (wait.as_secs_f64() * 1000.0 - expected_ms).abs() < 1e-6,
+            "expected {}ms, got {:?}",
+            expected_ms,
+            wait
+        );
+    }
+
+    #[test]
+    fn burst_tokens_are_available_immediately() {
+        let limiter = RateLimiter::new(10.0, 3);
+        let start = Instant::now();
+        let mut bucket = full_bucket(&limiter, start);
+
+        for _ in 0..3 {
+            assert!(limiter.try_take(&mut bucket, start).is_ok());
+        }
+        assert_wait(limiter.try_take(&mut bucket, start), 100.0);
+
+        // burst 为0时按1处理
+        assert_eq!(RateLimiter::new(10.0, 0).burst, 1.0);
+    }
+
+    #[test]
+    fn tokens_refill_at_configured_rate() {
+        let limiter = RateLimiter::new(10.0, 3);
+        let start = Instant::now();
+        let mut bucket = full_bucket(&limiter, start);
+        for _ in 0..3 {
+            limiter.try_take(&mut bucket, start).unwrap();
+        }
+
+        // 250ms 补充 2.5 个令牌：取两个后剩 0.5 个，还需等 50ms
+        let later = start + Duration::from_millis(250);
+        assert!(limiter.try_take(&mut bucket, later).is_ok());
+        assert!(limiter.try_take(&mut bucket, later).is_ok());
+        assert_wait(limiter.try_take(&mut bucket, later), 50.0);
+        assert!(limiter
+            .try_take(&mut bucket, later + Duration::from_millis(50))
+            .is_ok());
+    }
+
+    #[test]
+    fn idle_refill_is_capped_at_burst() {
+        let limiter = RateLimiter::new(10.0, 3);
+        let start = Instant::now();
+        let mut bucket = full_bucket(&limiter, start);
+        for _ in 0..3 {
+            limiter.try_take(&mut bucket, start).unwrap();
+        }
+
+        // 空闲一分钟也最多攒 burst 个令牌，超出部分仍需按速率等待
+        let later = start + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert!(limiter.try_take(&mut bucket, later).is_ok());
+        }
+        assert_wait(limiter.try_take(&mut bucket, later), 100.0);
+    }
+
+    #[tokio::test]
+    async fn acquire_waits_when_over_capacity() {
+        let limiter = RateLimiter::new(20.0, 2);
+        let start = Instant::now();
+        limiter.acquire().await;
+        limiter.acquire().await;
+
+        // 第三个请求超出突发容量，需等待约 1/20 秒
+        limiter.acquire().await;
+        assert!(start.elapsed() >= Duration::from_millis(45));
+    }
+
+    #[tokio::test]
+    async fn disabled_limiter_never_waits() {
+        let limiter = RateLimiter::new(0.0, 1);
+        assert!(!limiter.is_enabled());
+        let start = Instant::now();
+        for _ in 0..100 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,239 @@
+#![allow(dead_code)]
+
+use hyperliquid_rust_sdk::{Message, TradeInfo, UserData};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, LineWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+use super::error::GridStrategyError;
+
+/// 录制文件中的一行：接收时间（毫秒）和与 websocket 推送格式一致的消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedMessage {
+    pub ts: u64,
+    pub message: serde_json::Value,
+}
+
+/// 回放参数
+#[derive(Debug, Clone)]
+pub struct ReplayOptions {
+    pub path: PathBuf,
+    pub speed: f64,          // 回放倍速，1.0 为按原时间间隔
+    pub include_fills: bool, // 是否同时回放录制的成交事件
+}
+
+/// 将消息转换为 websocket 推送格式的 JSON，只处理价格和成交事件，其余返回 None
+///
+/// SDK 的消息类型只支持反序列化，这里按推送格式还原，回放时再用 serde 解析回 `Message`。
+pub fn message_to_json(message: &Message) -> Option<serde_json::Value> {
+    match message {
+        Message::AllMids(all_mids) => Some(serde_json::json!({
+            "channel": "allMids",
+            "data": { "mids": all_mids.data.mids },
+        })),
+        Message::User(user) => match &user.data {
+            UserData::Fills(fills) => Some(serde_json::json!({
+                "channel": "user",
+                "data": { "fills": fills.iter().map(trade_info_to_json).collect::<Vec<_>>() },
+            })),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn trade_info_to_json(fill: &TradeInfo) -> serde_json::Value {
+    serde_json::json!({
+        "coin": fill.coin,
+        "side": fill.side,
+        "px": fill.px,
+        "sz": fill.sz,
+        "time": fill.time,
+        "hash": fill.hash,
+        "startPosition": fill.start_position,
+        "dir": fill.dir,
+        "closedPnl": fill.closed_pnl,
+        "oid": fill.oid,
+        "cloid": fill.cloid,
+        "crossed": fill.crossed,
+        "fee": fill.fee,
+        "tid": fill.tid,
+        "feeToken": "USDC",
+    })
+}
+
+fn is_fill_message(value: &serde_json::Value) -> bool {
+    value.get("channel").and_then(|c| c.as_str()) == Some("user")
+}
+
+/// 行情录制器，每条消息追加写入一行 JSON
+pub struct MessageRecorder {
+    writer: LineWriter<File>,
+    recorded: u64,
+}
+
+impl MessageRecorder {
+    pub fn open(path: &Path) -> Result<Self, GridStrategyError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| {
+                GridStrategyError::ConfigError(format!(
+                    "打开行情录制文件 {} 失败: {:?}",
+                    path.display(),
+                    e
+                ))
+            })?;
+        Ok(Self {
+            writer: LineWriter::new(file),
+            recorded: 0,
+        })
+    }
+
+    /// 录制一条消息，不支持录制的消息类型直接忽略
+    pub fn record(&mut self, message: &Message) -> std::io::Result<()> {
+        let Some(value) = message_to_json(message) else {
+            return Ok(());
+        };
+        let record = RecordedMessage {
+            ts: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            message: value,
+        };
+        let line = serde_json::to_string(&record)?;
+        writeln!(self.writer, "{}", line)?;
+        self.recorded += 1;
+        Ok(())
+    }
+
+    pub fn recorded(&self) -> u64 {
+        self.recorded
+    }
+}
+
+/// 在订阅通道和策略主循环之间插入录制转发任务，返回供订阅使用的发送端
+///
+/// 录制失败只记录一次日志，不影响消息转发。
+pub fn spawn_recording_relay(
+    path: &Path,
+    target: UnboundedSender<Message>,
+) -> Result<UnboundedSender<Message>, GridStrategyError> {
+    let mut recorder = MessageRecorder::open(path)?;
+    let (sender, mut receiver) = unbounded_channel::<Message>();
+    let display_path = path.display().to_string();
+    info!("⏺️ 行情录制已启用: {}", display_path);
+
+    tokio::spawn(async move {
+        let mut write_failed = false;
+        while let Some(message) = receiver.recv().await {
+            if let Err(e) = recorder.record(&message) {
+                if !write_failed {
+                    warn!("⚠️ 写入行情录制文件 {} 失败: {:?}", display_path, e);
+                    write_failed = true;
+                }
+            }
+            if target.send(message).is_err() {
+                break;
+            }
+        }
+        info!(
+            "⏹️ 行情录制结束，共录制{}条消息: {}",
+            recorder.recorded(),
+            display_path
+        );
+    });
+
+    Ok(sender)
+}
+
+/// 读取录制文件，无法解析的行跳过
+pub fn load_recording(path: &Path) -> Result<Vec<RecordedMessage>, GridStrategyError> {
+    let file = File::open(path).map_err(|e| {
+        GridStrategyError::ConfigError(format!("读取录制文件 {} 失败: {:?}", path.display(), e))
+    })?;
+
+    let mut records = Vec::new();
+    let mut skipped = 0;
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| {
+            GridStrategyError::ConfigError(format!("读取录制文件 {} 失败: {:?}", path.display(), e))
+        })?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<RecordedMessage>(&line) {
+            Ok(record) => records.push(record),
+            Err(_) => skipped += 1,
+        }
+    }
+
+    if skipped > 0 {
+        warn!("⚠️ 录制文件中有{}行无法解析，已跳过", skipped);
+    }
+    if records.is_empty() {
+        return Err(GridStrategyError::ConfigError(format!(
+            "录制文件 {} 中没有可回放的消息",
+            path.display()
+        )));
+    }
+    Ok(records)
+}
+
+/// 按录制时的时间间隔把消息推送到策略主循环，结束后发送 `Message::NoData` 作为结束标记
+pub fn spawn_replay(
+    records: Vec<RecordedMessage>,
+    options: &ReplayOptions,
+    sender: UnboundedSender<Message>,
+) {
+    let speed = options.speed;
+    let include_fills = options.include_fills;
+    info!(
+        "▶️ 开始回放 {}，共{}条消息，倍速 {}x{}",
+        options.path.display(),
+        records.len(),
+        speed,
+        if include_fills {
+            "，包含成交事件"
+        } else {
+            ""
+        }
+    );
+
+    tokio::spawn(async move {
+        let mut previous_ts: Option<u64> = None;
+        let mut replayed = 0;
+        for record in records {
+            if !include_fills && is_fill_message(&record.message) {
+                continue;
+            }
+
+            if let Some(previous) = previous_ts {
+                let gap_ms = record.ts.saturating_sub(previous) as f64 / speed;
+                if gap_ms >= 1.0 {
+                    tokio::time::sleep(Duration::from_millis(gap_ms as u64)).await;
+                }
+            }
+            previous_ts = Some(record.ts);
+
+            match serde_json::from_value::<Message>(record.message) {
+                Ok(message) => {
+                    if sender.send(message).is_err() {
+                        return;
+                    }
+                    replayed += 1;
+                }
+                Err(e) => warn!("⚠️ 回放消息解析失败: {:?}", e),
+            }
+        }
+
+        info!("⏹️ 录制消息已全部推送，共{}条", replayed);
+        let _ = sender.send(Message::NoData);
+    });
+}
@@ -0,0 +1,247 @@
+#![allow(dead_code)]
+
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::error::GridStrategyError;
+use super::performance::{nullable_f64, system_time_serde};
+
+/// 风险事件日志（JSONL），每个事件追加写入一行
+///
+/// 当前文件超过 `max_bytes` 时滚动：`risk_events.jsonl` 改名为 `risk_events.jsonl.1`，
+/// 已有的 `.N` 依次后移，超过 `max_files` 的最旧文件被删除。
+#[derive(Debug, Clone)]
+pub struct RiskEventLog {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+}
+
+impl RiskEventLog {
+    pub fn new(path: impl Into<PathBuf>, max_bytes: u64, max_files: usize) -> Self {
+        Self {
+            path: path.into(),
+            max_bytes,
+            max_files,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 追加一条风险事件，写入前按大小检查是否需要滚动
+    pub fn append<T: Serialize>(&self, event: &T) -> Result<(), GridStrategyError> {
+        let line = serde_json::to_string(event)
+            .map_err(|e| GridStrategyError::ConfigError(format!("序列化风险事件失败: {:?}", e)))?;
+
+        if self.max_bytes > 0 {
+            let size = std::fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+            if size > 0 && size + line.len() as u64 + 1 > self.max_bytes {
+                self.rotate();
+            }
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| self.io_error("打开", e))?;
+        writeln!(file, "{}", line).map_err(|e| self.io_error("写入", e))
+    }
+
+    /// 滚动日志文件，失败只记录日志，继续写入当前文件
+    fn rotate(&self) {
+        if self.max_files == 0 {
+            if let Err(e) = std::fs::remove_file(&self.path) {
+                warn!("⚠️ 清理风险事件日志 {} 失败: {:?}", self.path.display(), e);
+            }
+            return;
+        }
+
+        let oldest = rotated_path(&self.path, self.max_files);
+        if oldest.exists() {
+            if let Err(e) = std::fs::remove_file(&oldest) {
+                warn!("⚠️ 删除旧风险事件日志 {} 失败: {:?}", oldest.display(), e);
+            }
+        }
+        for index in (1..self.max_files).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                let _ = std::fs::rename(&from, rotated_path(&self.path, index + 1));
+            }
+        }
+        if let Err(e) = std::fs::rename(&self.path, rotated_path(&self.path, 1)) {
+            warn!("⚠️ 滚动风险事件日志 {} 失败: {:?}", self.path.display(), e);
+        }
+    }
+
+    fn io_error(&self, action: &str, e: std::io::Error) -> GridStrategyError {
+        GridStrategyError::ConfigError(format!(
+            "{}风险事件日志 {} 失败: {:?}",
+            action,
+            self.path.display(),
+            e
+        ))
+    }
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+/// 日志中的一条风险事件，字段与策略中的 `RiskEvent` 一致
+#[derive(Debug, Clone, Deserialize)]
+pub struct RiskLogEntry {
+    pub event_type: String,
+    #[serde(with = "system_time_serde")]
+    pub timestamp: SystemTime,
+    pub description: String,
+    #[serde(deserialize_with = "nullable_f64::deserialize")]
+    pub current_value: f64,
+    #[serde(deserialize_with = "nullable_f64::deserialize")]
+    pub threshold_value: f64,
+    pub severity: u8,
+    #[serde(default)]
+    pub handled: bool,
+    #[serde(default)]
+    pub action_taken: Option<String>,
+}
+
+/// 风险事件查询条件，未设置的条件不过滤
+#[derive(Debug, Clone, Default)]
+pub struct RiskLogFilter {
+    pub since: Option<SystemTime>,
+    pub until: Option<SystemTime>,
+    pub event_type: Option<String>, // 事件类型，如 MarginInsufficient 或 margin_insufficient
+    pub min_severity: Option<u8>,   // 最低严重程度 (1-5)
+}
+
+impl RiskLogFilter {
+    fn matches(&self, entry: &RiskLogEntry) -> bool {
+        if self.since.is_some_and(|since| entry.timestamp < since) {
+            return false;
+        }
+        if self.until.is_some_and(|until| entry.timestamp > until) {
+            return false;
+        }
+        if self
+            .min_severity
+            .is_some_and(|severity| entry.severity < severity)
+        {
+            return false;
+        }
+        match &self.event_type {
+            Some(event_type) => {
+                normalize_event_type(event_type) == normalize_event_type(&entry.event_type)
+            }
+            None => true,
+        }
+    }
+}
+
+/// 忽略大小写和下划线比较事件类型
+fn normalize_event_type(event_type: &str) -> String {
+    event_type
+        .chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// 解析查询时间，支持 RFC3339 和 YYYY-MM-DD（按 UTC 零点）
+pub fn parse_time_filter(value: &str) -> Result<SystemTime, GridStrategyError> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc).into());
+    }
+    if let Some(time) = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+    {
+        return Ok(time.and_utc().into());
+    }
+    Err(GridStrategyError::ConfigError(format!(
+        "无法解析时间: {}（支持 RFC3339 或 YYYY-MM-DD）",
+        value
+    )))
+}
+
+/// 按时间顺序读取风险事件日志（含已滚动的文件）并过滤，无法解析的行跳过
+pub fn load_risk_events(
+    path: &Path,
+    filter: &RiskLogFilter,
+) -> Result<Vec<RiskLogEntry>, GridStrategyError> {
+    // 滚动文件序号越大越旧，先读最旧的
+    let mut files: Vec<PathBuf> = (1..)
+        .map(|index| rotated_path(path, index))
+        .take_while(|rotated| rotated.exists())
+        .collect();
+    files.reverse();
+    if path.exists() {
+        files.push(path.to_path_buf());
+    }
+    if files.is_empty() {
+        return Err(GridStrategyError::ConfigError(format!(
+            "风险事件日志 {} 不存在",
+            path.display()
+        )));
+    }
+
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    for file_path in &files {
+        let file = File::open(file_path).map_err(|e| {
+            GridStrategyError::ConfigError(format!(
+                "读取风险事件日志 {} 失败: {:?}",
+                file_path.display(),
+                e
+            ))
+        })?;
+        for line in BufReader::new(file).lines() {
+            let Ok(line) = line else {
+                skipped += 1;
+                continue;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<RiskLogEntry>(&line) {
+                Ok(entry) if filter.matches(&entry) => entries.push(entry),
+                Ok(_) => {}
+                Err(_) => skipped += 1,
+            }
+        }
+    }
+
+    if skipped > 0 {
+        warn!("⚠️ 风险事件日志中有{}行无法解析，已跳过", skipped);
+    }
+    entries.sort_by_key(|entry| entry.timestamp);
+    Ok(entries)
+}
+
+/// 格式化一条风险事件用于终端输出
+pub fn format_entry(entry: &RiskLogEntry) -> String {
+    format!(
+        "{} [{}] {} - {} (当前值: {:.4}, 阈值: {:.4}){}",
+        DateTime::<Utc>::from(entry.timestamp).to_rfc3339_opts(SecondsFormat::Secs, true),
+        entry.severity,
+        entry.event_type,
+        entry.description,
+        entry.current_value,
+        entry.threshold_value,
+        entry
+            .action_taken
+            .as_deref()
+            .map(|action| format!(" 处理: {}", action))
+            .unwrap_or_default()
+    )
+}
@@ -0,0 +1,450 @@
+#![allow(dead_code)]
+
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+
+use super::triangle::{fetch_book_ticker, BookTicker, Exchange, TriangleError};
+use crate::config::SpotConfig;
+
+/// 现货跨所套利错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum SpotError {
+    #[error("现货套利配置错误: {0}")]
+    Config(String),
+    #[error(transparent)]
+    Exchange(#[from] TriangleError),
+}
+
+/// 参与比价的交易所及其手续费、提币到账时间
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpotVenue {
+    pub exchange: Exchange,
+    pub fee_rate: f64,              // 单笔手续费率
+    pub withdrawal_delay: Duration, // 从该所提币到其他交易所的到账时间
+}
+
+/// 跨所套利路线：在低价所买入，同时在高价所卖出
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpotRoute {
+    pub buy: Exchange,
+    pub sell: Exchange,
+    pub buy_price: f64,   // 买入所卖一价
+    pub sell_price: f64,  // 卖出所买一价
+    pub profit_rate: f64, // 扣除双边手续费后的收益率
+}
+
+impl SpotRoute {
+    pub fn describe(&self) -> String {
+        format!(
+            "{}买入@{:.6} -> {}卖出@{:.6}",
+            self.buy.as_str(),
+            self.buy_price,
+            self.sell.as_str(),
+            self.sell_price
+        )
+    }
+}
+
+/// 按盘口买一卖一估算一组买卖所的收益率（已扣双边手续费），报价无效时返回 None
+pub fn route_profit_rate(
+    buy: &SpotVenue,
+    buy_quote: &BookTicker,
+    sell: &SpotVenue,
+    sell_quote: &BookTicker,
+) -> Option<f64> {
+    if buy_quote.ask <= 0.0 || sell_quote.bid <= 0.0 {
+        return None;
+    }
+    let received = sell_quote.bid * (1.0 - sell.fee_rate) * (1.0 - buy.fee_rate) / buy_quote.ask;
+    received.is_finite().then_some(received - 1.0)
+}
+
+/// 在全部交易所中两两组合买卖所，按扣费后的收益率从高到低排序
+///
+/// 不扣手续费时最优组合就是最低卖一价和最高买一价所在的交易所，各所手续费不同时
+/// 以扣费后的收益率为准。
+pub fn rank_routes(venues: &[SpotVenue], quotes: &HashMap<Exchange, BookTicker>) -> Vec<SpotRoute> {
+    let mut routes = Vec::new();
+    for buy in venues {
+        let Some(buy_quote) = quotes.get(&buy.exchange) else {
+            continue;
+        };
+        for sell in venues {
+            if sell.exchange == buy.exchange {
+                continue;
+            }
+            let Some(sell_quote) = quotes.get(&sell.exchange) else {
+                continue;
+            };
+            if let Some(profit_rate) = route_profit_rate(buy, buy_quote, sell, sell_quote) {
+                routes.push(SpotRoute {
+                    buy: buy.exchange,
+                    sell: sell.exchange,
+                    buy_price: buy_quote.ask,
+                    sell_price: sell_quote.bid,
+                    profit_rate,
+                });
+            }
+        }
+    }
+    routes.sort_by(|a, b| {
+        b.profit_rate
+            .partial_cmp(&a.profit_rate)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    routes
+}
+
+/// 在途的提币划转，到账前不可用
+#[derive(Debug, Clone, PartialEq)]
+struct PendingTransfer {
+    asset: Asset,
+    from: Exchange,
+    to: Exchange,
+    amount: f64,
+    arrives_at: Instant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Asset {
+    Base,  // 基础币
+    Quote, // 计价币
+}
+
+/// 各交易所的资金分布
+///
+/// 跨所套利在低价所用计价币买入、在高价所卖出基础币，两边都需要预先存放资金。
+/// 套利后资金会向一侧倾斜，某所余额不足时从余额最多的交易所提币补足，到账前不计入可用余额。
+#[derive(Debug, Clone, Default)]
+pub struct SpotInventory {
+    balances: HashMap<Exchange, (f64, f64)>, // (基础币, 计价币)
+    pending: Vec<PendingTransfer>,
+}
+
+impl SpotInventory {
+    pub fn new(balances: HashMap<Exchange, (f64, f64)>) -> Self {
+        Self {
+            balances,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn base(&self, exchange: Exchange) -> f64 {
+        self.balances.get(&exchange).map(|b| b.0).unwrap_or(0.0)
+    }
+
+    pub fn quote(&self, exchange: Exchange) -> f64 {
+        self.balances.get(&exchange).map(|b| b.1).unwrap_or(0.0)
+    }
+
+    /// 两边余额都允许时本次路线可成交的基础币数量
+    pub fn executable_quantity(&self, route: &SpotRoute, max_quote_amount: f64) -> f64 {
+        let affordable = self.quote(route.buy).min(max_quote_amount) / route.buy_price;
+        affordable.min(self.base(route.sell)).max(0.0)
+    }
+
+    /// 记录一次套利成交：买入所花计价币得基础币，卖出所卖基础币得计价币
+    pub fn apply_route(&mut self, route: &SpotRoute, quantity: f64, venues: &[SpotVenue]) {
+        let fee_of = |exchange: Exchange| {
+            venues
+                .iter()
+                .find(|venue| venue.exchange == exchange)
+                .map(|venue| venue.fee_rate)
+                .unwrap_or(0.0)
+        };
+        let buy = self.balances.entry(route.buy).or_default();
+        buy.1 -= quantity * route.buy_price;
+        buy.0 += quantity * (1.0 - fee_of(route.buy));
+        let sell = self.balances.entry(route.sell).or_default();
+        sell.0 -= quantity;
+        sell.1 += quantity * route.sell_price * (1.0 - fee_of(route.sell));
+    }
+
+    /// 到账的提币计入目标交易所余额，返回本次到账的划转
+    fn settle_transfers(&mut self, now: Instant) -> Vec<PendingTransfer> {
+        let (arrived, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|transfer| transfer.arrives_at <= now);
+        self.pending = pending;
+        for transfer in &arrived {
+            let balance = self.balances.entry(transfer.to).or_default();
+            match transfer.asset {
+                Asset::Base => balance.0 += transfer.amount,
+                Asset::Quote => balance.1 += transfer.amount,
+            }
+        }
+        arrived
+    }
+
+    fn has_pending(&self, asset: Asset, to: Exchange) -> bool {
+        self.pending
+            .iter()
+            .any(|transfer| transfer.asset == asset && transfer.to == to)
+    }
+
+    /// 目标所余额低于 `target` 时，从余额最多的交易所提币补足一半差额
+    fn rebalance(
+        &mut self,
+        asset: Asset,
+        to: Exchange,
+        target: f64,
+        venues: &[SpotVenue],
+        now: Instant,
+    ) -> Option<PendingTransfer> {
+        let amount_of = |inventory: &Self, exchange: Exchange| match asset {
+            Asset::Base => inventory.base(exchange),
+            Asset::Quote => inventory.quote(exchange),
+        };
+        if amount_of(self, to) >= target || self.has_pending(asset, to) {
+            return None;
+        }
+        let source = venues
+            .iter()
+            .filter(|venue| venue.exchange != to)
+            .max_by(|a, b| {
+                amount_of(self, a.exchange)
+                    .partial_cmp(&amount_of(self, b.exchange))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })?;
+        let surplus = amount_of(self, source.exchange) - target;
+        let amount = (target - amount_of(self, to)).min(surplus / 2.0);
+        if amount <= 0.0 {
+            return None;
+        }
+
+        let balance = self.balances.entry(source.exchange).or_default();
+        match asset {
+            Asset::Base => balance.0 -= amount,
+            Asset::Quote => balance.1 -= amount,
+        }
+        let transfer = PendingTransfer {
+            asset,
+            from: source.exchange,
+            to,
+            amount,
+            arrives_at: now + source.withdrawal_delay,
+        };
+        self.pending.push(transfer.clone());
+        Some(transfer)
+    }
+}
+
+/// 按配置解析参与比价的交易所
+pub fn venues_from_config(config: &SpotConfig) -> Result<Vec<SpotVenue>, SpotError> {
+    let exchanges = config.exchange_list();
+    if exchanges.len() < 2 {
+        return Err(SpotError::Config(format!(
+            "至少需要两家交易所，当前为 {:?}",
+            exchanges
+        )));
+    }
+    exchanges
+        .iter()
+        .map(|code| {
+            let fee_rate = config.fee_rate_for(code);
+            if !(0.0..1.0).contains(&fee_rate) {
+                return Err(SpotError::Config(format!(
+                    "{} 的手续费率 {} 必须在[0,1)之间",
+                    code, fee_rate
+                )));
+            }
+            Ok(SpotVenue {
+                exchange: Exchange::from_code(code)?,
+                fee_rate,
+                withdrawal_delay: Duration::from_secs(config.withdrawal_delay_for(code)),
+            })
+        })
+        .collect()
+}
+
+/// 并行拉取各交易所的盘口报价，失败的交易所本轮不参与比价
+async fn fetch_quotes(
+    client: &reqwest::Client,
+    venues: &[SpotVenue],
+    base: &str,
+    quote: &str,
+) -> HashMap<Exchange, BookTicker> {
+    let mut tasks = JoinSet::new();
+    for venue in venues {
+        let client = client.clone();
+        let exchange = venue.exchange;
+        let symbol = exchange.pair_symbol(base, quote);
+        tasks.spawn(async move {
+            let result = fetch_book_ticker(&client, exchange, &symbol).await;
+            (exchange, result)
+        });
+    }
+
+    let mut quotes = HashMap::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((exchange, Ok(ticker))) => {
+                quotes.insert(exchange, ticker);
+            }
+            Ok((exchange, Err(e))) => warn!("⚠️ 获取{}报价失败: {}", exchange.as_str(), e),
+            Err(e) => warn!("⚠️ 报价任务异常退出: {}", e),
+        }
+    }
+    quotes
+}
+
+/// 持续扫描全部交易所的买卖价差，按扣费后收益最高且资金允许的路线套利
+pub async fn run_spot_strategy(config: &SpotConfig) -> Result<(), SpotError> {
+    env_logger::init();
+
+    let venues = venues_from_config(config)?;
+    let (base, quote) = config
+        .symbol
+        .split_once('/')
+        .map(|(base, quote)| (base.trim(), quote.trim()))
+        .filter(|(base, quote)| !base.is_empty() && !quote.is_empty())
+        .ok_or_else(|| {
+            SpotError::Config(format!(
+                "交易对 \"{}\" 格式错误，应为 BASE/QUOTE",
+                config.symbol
+            ))
+        })?;
+
+    let mut inventory = SpotInventory::new(
+        venues
+            .iter()
+            .zip(config.exchange_list())
+            .map(|(venue, code)| {
+                let balance = config.balances.get(&code).copied().unwrap_or_default();
+                (venue.exchange, (balance.base, balance.quote))
+            })
+            .collect(),
+    );
+
+    info!(
+        "💱 现货跨所套利已启动 - 交易对: {}, 交易所: {}, 最小收益: {:.4}%, 单次金额上限: {:.2} {}",
+        config.symbol,
+        venues
+            .iter()
+            .map(|venue| format!(
+                "{}(手续费{:.3}%, 提币{}分钟)",
+                venue.exchange.as_str(),
+                venue.fee_rate * 100.0,
+                venue.withdrawal_delay.as_secs() / 60
+            ))
+            .collect::<Vec<_>>()
+            .join(", "),
+        config.min_profit_rate * 100.0,
+        config.trade_amount,
+        quote
+    );
+
+    let client = reqwest::Client::new();
+    loop {
+        for transfer in inventory.settle_transfers(Instant::now()) {
+            info!(
+                "📥 提币到账 - {} -> {}: {:.6} {}",
+                transfer.from.as_str(),
+                transfer.to.as_str(),
+                transfer.amount,
+                if transfer.asset == Asset::Base {
+                    base
+                } else {
+                    quote
+                }
+            );
+        }
+
+        let quotes = fetch_quotes(&client, &venues, base, quote).await;
+        let routes = rank_routes(&venues, &quotes);
+        for route in routes.iter().take(3) {
+            debug!(
+                "   {} 预期收益: {:.4}%",
+                route.describe(),
+                route.profit_rate * 100.0
+            );
+        }
+
+        // 收益达标的路线中选资金允许成交的最优一条
+        let mut executed = false;
+        for route in routes
+            .iter()
+            .filter(|route| route.profit_rate >= config.min_profit_rate)
+        {
+            let quantity = inventory.executable_quantity(route, config.trade_amount);
+            if quantity * route.buy_price < config.trade_amount * 0.1 {
+                debug!(
+                    "💤 {} 资金不足 - {}可用{:.2} {}, {}可用{:.6} {}",
+                    route.describe(),
+                    route.buy.as_str(),
+                    inventory.quote(route.buy),
+                    quote,
+                    route.sell.as_str(),
+                    inventory.base(route.sell),
+                    base
+                );
+                continue;
+            }
+
+            info!(
+                "💰 发现跨所套利机会 - {}, 数量: {:.6} {}, 预期收益: {:.4}%",
+                route.describe(),
+                quantity,
+                base,
+                route.profit_rate * 100.0
+            );
+            // TODO: 接入各交易所下单接口后在两所同时下单，目前只按报价记录资金变化
+            inventory.apply_route(route, quantity, &venues);
+            executed = true;
+            break;
+        }
+        if !executed {
+            if let Some(best) = routes.first() {
+                debug!(
+                    "🔍 最优路线 {} 预期收益 {:.4}%，未达到要求或资金不足",
+                    best.describe(),
+                    best.profit_rate * 100.0
+                );
+            }
+        }
+
+        // 套利后资金向一侧倾斜，余额不足单次套利的交易所从其他所提币补足
+        let now = Instant::now();
+        for venue in &venues {
+            let reference_price = quotes.get(&venue.exchange).map(|ticker| ticker.ask);
+            let transfers = [
+                inventory.rebalance(
+                    Asset::Quote,
+                    venue.exchange,
+                    config.trade_amount,
+                    &venues,
+                    now,
+                ),
+                reference_price
+                    .filter(|price| *price > 0.0)
+                    .and_then(|price| {
+                        inventory.rebalance(
+                            Asset::Base,
+                            venue.exchange,
+                            config.trade_amount / price,
+                            &venues,
+                            now,
+                        )
+                    }),
+            ];
+            for transfer in transfers.into_iter().flatten() {
+                info!(
+                    "🚚 资金再平衡 - 从{}提币 {:.6} {} 到{}，预计{}分钟后到账",
+                    transfer.from.as_str(),
+                    transfer.amount,
+                    if transfer.asset == Asset::Base {
+                        base
+                    } else {
+                        quote
+                    },
+                    transfer.to.as_str(),
+                    transfer.arrives_at.saturating_duration_since(now).as_secs() / 60
+                );
+            }
+        }
+
+        tokio::time::sleep(Duration::from_secs(config.scan_interval_secs.max(1))).await;
+    }
+}
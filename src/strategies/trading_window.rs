@@ -0,0 +1,312 @@
+#![allow(dead_code)]
+
+use chrono::{DateTime, Datelike, Timelike, Utc, Weekday};
+use std::fmt;
+
+use super::error::GridStrategyError;
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// 一周七天全选的星期掩码
+const ALL_WEEKDAYS: u8 = 0x7f;
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+/// UTC 交易时间窗口，左闭右开；结束时间早于开始时间表示跨午夜，如 22:00-02:00
+///
+/// 可选的星期前缀按窗口开始的那天匹配，如 "Fri 22:00-02:00" 覆盖周五 22:00 到周六 02:00。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TradingWindow {
+    weekdays: u8,      // 生效的星期掩码，第0位为周一
+    start_minute: u32, // 当天第几分钟开始（0-1439）
+    end_minute: u32,   // 当天第几分钟结束（1-1440，24:00 表示到当天结束）
+}
+
+impl TradingWindow {
+    /// 解析 "[星期] HH:MM-HH:MM" 格式的时间窗口，结束时间允许写 24:00
+    ///
+    /// 星期用英文缩写，多个用逗号分隔或写成区间，如 "Mon-Fri 08:00-16:00"、"Sat,Sun 00:00-24:00"；省略时每天生效。
+    pub fn parse(spec: &str) -> Result<Self, GridStrategyError> {
+        let invalid = |reason: &str| {
+            GridStrategyError::ConfigError(format!(
+                "交易时间窗口 \"{}\" 无效: {}（格式为 [Mon-Fri] HH:MM-HH:MM，UTC）",
+                spec, reason
+            ))
+        };
+
+        let (weekdays, times) = match spec.trim().split_once(char::is_whitespace) {
+            Some((days, times)) => (
+                parse_weekdays(days).ok_or_else(|| invalid("星期格式错误"))?,
+                times,
+            ),
+            None => (ALL_WEEKDAYS, spec),
+        };
+        let (start, end) = times
+            .trim()
+            .split_once('-')
+            .ok_or_else(|| invalid("缺少 '-' 分隔符"))?;
+        let start_minute = parse_minute(start).ok_or_else(|| invalid("开始时间格式错误"))?;
+        let end_minute = parse_minute(end).ok_or_else(|| invalid("结束时间格式错误"))?;
+
+        if start_minute >= MINUTES_PER_DAY {
+            return Err(invalid("开始时间不能为 24:00"));
+        }
+        if start_minute == end_minute {
+            return Err(invalid("开始和结束时间相同"));
+        }
+
+        Ok(Self {
+            weekdays,
+            start_minute,
+            end_minute,
+        })
+    }
+
+    /// 是否跨越 UTC 午夜
+    pub fn crosses_midnight(&self) -> bool {
+        self.end_minute < self.start_minute
+    }
+
+    fn active_on(&self, weekday: Weekday) -> bool {
+        self.weekdays & weekday_bit(weekday) != 0
+    }
+
+    /// `weekday` 当天第 `minute` 分钟（UTC）是否在窗口内，跨午夜的后半段按前一天的星期匹配
+    pub fn contains(&self, weekday: Weekday, minute: u32) -> bool {
+        if self.crosses_midnight() {
+            (minute >= self.start_minute && self.active_on(weekday))
+                || (minute < self.end_minute && self.active_on(weekday.pred()))
+        } else {
+            minute >= self.start_minute && minute < self.end_minute && self.active_on(weekday)
+        }
+    }
+}
+
+impl fmt::Display for TradingWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.weekdays != ALL_WEEKDAYS {
+            let days: Vec<String> = WEEKDAYS
+                .iter()
+                .filter(|day| self.active_on(**day))
+                .map(|day| day.to_string())
+                .collect();
+            write!(f, "{} ", days.join(","))?;
+        }
+        write!(
+            f,
+            "{:02}:{:02}-{:02}:{:02}",
+            self.start_minute / 60,
+            self.start_minute % 60,
+            self.end_minute / 60,
+            self.end_minute % 60
+        )
+    }
+}
+
+/// 解析 "HH:MM"，小时 0-24，24 只允许 24:00
+fn parse_minute(value: &str) -> Option<u32> {
+    let (hour, minute) = value.trim().split_once(':')?;
+    if hour.is_empty() || hour.len() > 2 || minute.len() != 2 {
+        return None;
+    }
+    let hour: u32 = hour.parse().ok()?;
+    let minute: u32 = minute.parse().ok()?;
+    if minute >= 60 || hour > 24 || (hour == 24 && minute != 0) {
+        return None;
+    }
+    Some(hour * 60 + minute)
+}
+
+fn weekday_bit(weekday: Weekday) -> u8 {
+    1 << weekday.num_days_from_monday()
+}
+
+/// 解析 "Mon-Fri"、"Sat,Sun" 形式的星期列表，区间可以跨周末（如 Fri-Mon）
+fn parse_weekdays(value: &str) -> Option<u8> {
+    let mut mask = 0u8;
+    for item in value.split(',') {
+        let (first, last) = match item.split_once('-') {
+            Some((first, last)) => (
+                first.trim().parse::<Weekday>().ok()?,
+                last.trim().parse::<Weekday>().ok()?,
+            ),
+            None => {
+                let day = item.trim().parse::<Weekday>().ok()?;
+                (day, day)
+            }
+        };
+        let mut day = first;
+        loop {
+            mask |= weekday_bit(day);
+            if day == last {
+                break;
+            }
+            day = day.succ();
+        }
+    }
+    Some(mask)
+}
+
+/// 解析配置中的全部交易时间窗口
+pub fn parse_trading_windows(specs: &[String]) -> Result<Vec<TradingWindow>, GridStrategyError> {
+    specs
+        .iter()
+        .map(|spec| TradingWindow::parse(spec))
+        .collect()
+}
+
+/// 当前时间是否在任一交易窗口内，未配置窗口时全天可交易
+pub fn is_in_trading_window(windows: &[TradingWindow], now: DateTime<Utc>) -> bool {
+    if windows.is_empty() {
+        return true;
+    }
+    let minute = now.hour() * 60 + now.minute();
+    windows
+        .iter()
+        .any(|window| window.contains(now.weekday(), minute))
+}
+
+/// 交易时间窗口的文字描述，用于日志和状态报告
+pub fn describe_trading_windows(windows: &[TradingWindow]) -> String {
+    if windows.is_empty() {
+        return "全天".to_string();
+    }
+    format!(
+        "{} UTC",
+        windows
+            .iter()
+            .map(|window| window.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        // 2026-10-12 是周一
+        Utc.with_ymd_and_hms(2026, 10, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    fn windows(specs: &[&str]) -> Vec<TradingWindow> {
+        let specs: Vec<String> = specs.iter().map(|spec| spec.to_string()).collect();
+        parse_trading_windows(&specs).unwrap()
+    }
+
+    #[test]
+    fn window_crossing_midnight_covers_both_days() {
+        let window = windows(&["22:00-02:00"]);
+        assert!(window[0].crosses_midnight());
+
+        for (hour, minute, expected) in [
+            (21, 59, false),
+            (22, 0, true),
+            (23, 59, true),
+            (0, 0, true),
+            (1, 59, true),
+            (2, 0, false),
+            (12, 0, false),
+        ] {
+            assert_eq!(
+                is_in_trading_window(&window, at(13, hour, minute)),
+                expected,
+                "{:02}:{:02}",
+                hour,
+                minute
+            );
+        }
+    }
+
+    #[test]
+    fn end_of_day_is_written_as_24_00() {
+        let window = windows(&["18:00-24:00"]);
+        assert!(!window[0].crosses_midnight());
+        assert!(is_in_trading_window(&window, at(13, 23, 59)));
+        assert!(!is_in_trading_window(&window, at(13, 0, 0)));
+        assert!(!is_in_trading_window(&window, at(13, 17, 59)));
+        assert_eq!(window[0].to_string(), "18:00-24:00");
+
+        let all_day = windows(&["00:00-24:00"]);
+        assert!(is_in_trading_window(&all_day, at(13, 0, 0)));
+        assert!(is_in_trading_window(&all_day, at(13, 23, 59)));
+    }
+
+    #[test]
+    fn invalid_window_strings_are_rejected() {
+        for spec in [
+            "",
+            "08:00",
+            "08:00-08:00",
+            "24:00-02:00",
+            "08:00-24:30",
+            "25:00-26:00",
+            "08:60-09:00",
+            "8:0-9:00",
+            "ab:cd-09:00",
+            "123:00-09:00",
+            "Funday 08:00-09:00",
+            "Mon- 08:00-09:00",
+        ] {
+            assert!(
+                matches!(
+                    TradingWindow::parse(spec),
+                    Err(GridStrategyError::ConfigError(_))
+                ),
+                "{:?} 应解析失败",
+                spec
+            );
+        }
+        assert!(TradingWindow::parse(" 8:00-09:30 ").is_ok());
+    }
+
+    #[test]
+    fn weekday_prefix_limits_window_to_listed_days() {
+        let weekdays = windows(&["Mon-Fri 08:00-16:00"]);
+        assert!(is_in_trading_window(&weekdays, at(12, 9, 0))); // 周一
+        assert!(is_in_trading_window(&weekdays, at(16, 9, 0))); // 周五
+        assert!(!is_in_trading_window(&weekdays, at(17, 9, 0))); // 周六
+        assert!(!is_in_trading_window(&weekdays, at(18, 9, 0))); // 周日
+        assert_eq!(weekdays[0].to_string(), "Mon,Tue,Wed,Thu,Fri 08:00-16:00");
+
+        let weekend = windows(&["Sat,Sun 00:00-24:00"]);
+        assert!(is_in_trading_window(&weekend, at(17, 12, 0)));
+        assert!(!is_in_trading_window(&weekend, at(16, 12, 0)));
+
+        // 跨周末的区间
+        let wrap = windows(&["Sat-Mon 10:00-11:00"]);
+        assert!(is_in_trading_window(&wrap, at(12, 10, 30)));
+        assert!(!is_in_trading_window(&wrap, at(13, 10, 30)));
+    }
+
+    #[test]
+    fn weekday_of_midnight_window_follows_start_day() {
+        // 周五 22:00 开始，延续到周六 02:00；周日晚上不生效
+        let friday_night = windows(&["fri 22:00-02:00"]);
+        assert!(is_in_trading_window(&friday_night, at(16, 23, 0)));
+        assert!(is_in_trading_window(&friday_night, at(17, 1, 0)));
+        assert!(!is_in_trading_window(&friday_night, at(17, 23, 0)));
+        assert!(!is_in_trading_window(&friday_night, at(16, 1, 0)));
+    }
+
+    #[test]
+    fn empty_window_list_trades_all_day() {
+        assert!(is_in_trading_window(&[], at(13, 3, 0)));
+        assert_eq!(describe_trading_windows(&[]), "全天");
+        assert_eq!(
+            describe_trading_windows(&windows(&["00:00-06:00", "Sat 22:00-02:00"])),
+            "00:00-06:00, Sat 22:00-02:00 UTC"
+        );
+    }
+}
@@ -0,0 +1,681 @@
+#![allow(dead_code)]
+
+use log::{debug, error, info, warn};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use crate::config::TriangleConfig;
+
+/// 三角套利错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum TriangleError {
+    #[error("不支持的交易所: {0}")]
+    UnsupportedExchange(String),
+    #[error("请求交易所接口失败: {0}")]
+    Request(String),
+    #[error("解析交易所数据失败: {0}")]
+    Parse(String),
+    #[error("没有可用的三角路径: {0}")]
+    NoPaths(String),
+}
+
+/// 支持自动发现路径的交易所
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Exchange {
+    Binance, // 币安
+    Okx,     // 欧易
+    Bybit,   // Bybit
+}
+
+impl Exchange {
+    pub fn from_code(code: &str) -> Result<Self, TriangleError> {
+        match code.trim().to_lowercase().as_str() {
+            "binance" => Ok(Exchange::Binance),
+            "okx" | "okex" => Ok(Exchange::Okx),
+            "bybit" => Ok(Exchange::Bybit),
+            _ => Err(TriangleError::UnsupportedExchange(code.to_string())),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Exchange::Binance => "币安",
+            Exchange::Okx => "欧易",
+            Exchange::Bybit => "Bybit",
+        }
+    }
+
+    pub fn as_english(&self) -> &'static str {
+        match self {
+            Exchange::Binance => "Binance",
+            Exchange::Okx => "OKX",
+            Exchange::Bybit => "Bybit",
+        }
+    }
+
+    /// 交易所原始交易对代码，如 BTC/USDT 在币安为 BTCUSDT，在欧易为 BTC-USDT
+    pub fn pair_symbol(&self, base: &str, quote: &str) -> String {
+        let (base, quote) = (base.to_uppercase(), quote.to_uppercase());
+        match self {
+            Exchange::Binance | Exchange::Bybit => format!("{}{}", base, quote),
+            Exchange::Okx => format!("{}-{}", base, quote),
+        }
+    }
+}
+
+crate::impl_bilingual!(Exchange);
+
+/// 交易对元数据
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TradingPair {
+    pub symbol: String, // 交易所原始交易对代码，如 BTCUSDT / BTC-USDT
+    pub base: String,   // 基础币
+    pub quote: String,  // 计价币
+}
+
+/// 交易对写法归一化：BTC/USDT、BTC-USDT、btcusdt 都视为 BTCUSDT
+fn normalize_pair(pair: &str) -> String {
+    pair.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// 按配置的黑名单、白名单和最小24小时成交额过滤交易对
+#[derive(Debug, Clone, Default)]
+pub struct PairFilter {
+    blacklist: HashSet<String>,
+    whitelist: HashSet<String>,
+    pub min_quote_volume_24h: f64,
+}
+
+impl PairFilter {
+    pub fn from_config(config: &TriangleConfig) -> Self {
+        Self {
+            blacklist: config
+                .blacklist_pairs
+                .iter()
+                .map(|p| normalize_pair(p))
+                .collect(),
+            whitelist: config
+                .whitelist_pairs
+                .iter()
+                .map(|p| normalize_pair(p))
+                .collect(),
+            min_quote_volume_24h: config.min_quote_volume_24h,
+        }
+    }
+
+    /// 交易对是否可用；`quote_volumes` 为空时不检查成交额
+    pub fn allows(&self, pair: &TradingPair, quote_volumes: &HashMap<String, f64>) -> bool {
+        let key = normalize_pair(&format!("{}{}", pair.base, pair.quote));
+        if self.blacklist.contains(&key) {
+            return false;
+        }
+        if !self.whitelist.is_empty() && !self.whitelist.contains(&key) {
+            return false;
+        }
+        if self.min_quote_volume_24h > 0.0 && !quote_volumes.is_empty() {
+            let volume = quote_volumes.get(&pair.symbol).copied().unwrap_or(0.0);
+            if volume < self.min_quote_volume_24h {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// 过滤条件描述，用于路径为空时的错误提示
+    pub fn describe(&self) -> String {
+        format!(
+            "黑名单 {} 个, 白名单 {} 个, 最小24h成交额 {}",
+            self.blacklist.len(),
+            self.whitelist.len(),
+            self.min_quote_volume_24h
+        )
+    }
+}
+
+/// 三角路径中的一步
+#[derive(Debug, Clone, PartialEq)]
+pub struct TriangleLeg {
+    pub pair: TradingPair,
+    pub is_buy: bool, // true: 用计价币买入基础币；false: 卖出基础币换计价币
+}
+
+impl TriangleLeg {
+    /// 这一步花出去的币
+    pub fn spend_currency(&self) -> &str {
+        if self.is_buy {
+            &self.pair.quote
+        } else {
+            &self.pair.base
+        }
+    }
+
+    /// 这一步得到的币
+    pub fn receive_currency(&self) -> &str {
+        if self.is_buy {
+            &self.pair.base
+        } else {
+            &self.pair.quote
+        }
+    }
+}
+
+/// 从基准币出发并回到基准币的三角路径
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrianglePath {
+    pub legs: [TriangleLeg; 3],
+}
+
+impl TrianglePath {
+    /// 路径描述，如 USDT -> BTC -> ETH -> USDT
+    pub fn describe(&self) -> String {
+        let mut currencies = vec![self.legs[0].spend_currency().to_string()];
+        currencies.extend(
+            self.legs
+                .iter()
+                .map(|leg| leg.receive_currency().to_string()),
+        );
+        currencies.join(" -> ")
+    }
+
+    /// 按盘口买一卖一估算走完一圈的收益率（已扣手续费），缺少报价时返回 None
+    pub fn estimate_return(
+        &self,
+        tickers: &HashMap<String, BookTicker>,
+        fee_rate: f64,
+    ) -> Option<f64> {
+        let mut amount = 1.0;
+        for leg in &self.legs {
+            let ticker = tickers.get(&leg.pair.symbol)?;
+            amount = if leg.is_buy {
+                if ticker.ask <= 0.0 {
+                    return None;
+                }
+                amount / ticker.ask
+            } else {
+                if ticker.bid <= 0.0 {
+                    return None;
+                }
+                amount * ticker.bid
+            } * (1.0 - fee_rate);
+        }
+        Some(amount - 1.0)
+    }
+}
+
+/// 买一卖一报价
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BookTicker {
+    pub bid: f64,
+    pub ask: f64,
+}
+
+/// 在交易对构成的货币图中找出所有从 `base_currency` 出发回到自身的三角路径
+///
+/// 同一组交易对的正反两个方向是不同的路径，都会返回。
+pub fn find_triangles(pairs: &[TradingPair], base_currency: &str) -> Vec<TrianglePath> {
+    let base_currency = base_currency.to_uppercase();
+
+    // 货币图：币种 -> 可直接兑换的 (目标币种, 交易步骤)
+    let mut graph: HashMap<&str, Vec<TriangleLeg>> = HashMap::new();
+    for pair in pairs {
+        graph
+            .entry(pair.quote.as_str())
+            .or_default()
+            .push(TriangleLeg {
+                pair: pair.clone(),
+                is_buy: true,
+            });
+        graph
+            .entry(pair.base.as_str())
+            .or_default()
+            .push(TriangleLeg {
+                pair: pair.clone(),
+                is_buy: false,
+            });
+    }
+
+    let mut paths = Vec::new();
+    let Some(first_legs) = graph.get(base_currency.as_str()) else {
+        return paths;
+    };
+
+    for first in first_legs {
+        let middle = first.receive_currency();
+        let Some(second_legs) = graph.get(middle) else {
+            continue;
+        };
+        for second in second_legs {
+            let last = second.receive_currency();
+            if last == base_currency || last == middle {
+                continue;
+            }
+            let Some(third_legs) = graph.get(last) else {
+                continue;
+            };
+            for third in third_legs {
+                if third.receive_currency() == base_currency {
+                    paths.push(TrianglePath {
+                        legs: [first.clone(), second.clone(), third.clone()],
+                    });
+                }
+            }
+        }
+    }
+
+    paths
+}
+
+/// 三角路径扫描器，缓存交易对元数据，避免每轮都拉取全量交易对
+pub struct TriangleScanner {
+    client: reqwest::Client,
+    cache_ttl: Duration,
+    pairs_cache: HashMap<Exchange, (Instant, Vec<TradingPair>)>,
+    volume_cache: HashMap<Exchange, (Instant, HashMap<String, f64>)>,
+    filter: PairFilter,
+}
+
+impl TriangleScanner {
+    pub fn new(cache_ttl: Duration) -> Self {
+        Self::with_filter(cache_ttl, PairFilter::default())
+    }
+
+    pub fn with_filter(cache_ttl: Duration, filter: PairFilter) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            cache_ttl,
+            pairs_cache: HashMap::new(),
+            volume_cache: HashMap::new(),
+            filter,
+        }
+    }
+
+    /// 获取交易所全部可交易现货交易对，缓存未过期时直接返回缓存
+    pub async fn trading_pairs(
+        &mut self,
+        exchange: Exchange,
+    ) -> Result<&[TradingPair], TriangleError> {
+        let expired = self
+            .pairs_cache
+            .get(&exchange)
+            .map(|(fetched_at, _)| fetched_at.elapsed() >= self.cache_ttl)
+            .unwrap_or(true);
+
+        if expired {
+            let pairs = fetch_trading_pairs(&self.client, exchange).await?;
+            info!(
+                "📚 已刷新{}交易对元数据: {}个",
+                exchange.as_str(),
+                pairs.len()
+            );
+            self.pairs_cache.insert(exchange, (Instant::now(), pairs));
+        }
+
+        Ok(self
+            .pairs_cache
+            .get(&exchange)
+            .map(|(_, pairs)| pairs.as_slice())
+            .unwrap_or_default())
+    }
+
+    /// 获取各交易对24小时成交额（计价币），与交易对元数据使用同样的缓存时间
+    pub async fn quote_volumes(
+        &mut self,
+        exchange: Exchange,
+    ) -> Result<&HashMap<String, f64>, TriangleError> {
+        let expired = self
+            .volume_cache
+            .get(&exchange)
+            .map(|(fetched_at, _)| fetched_at.elapsed() >= self.cache_ttl)
+            .unwrap_or(true);
+
+        if expired {
+            let volumes = fetch_quote_volumes(&self.client, exchange).await?;
+            self.volume_cache
+                .insert(exchange, (Instant::now(), volumes));
+        }
+
+        Ok(&self.volume_cache[&exchange].1)
+    }
+
+    /// 发现交易所上从基准币出发的三角路径，经过黑名单、白名单和成交额过滤
+    ///
+    /// 过滤后没有任何路径时返回 `TriangleError::NoPaths`。
+    pub async fn discover_triangles(
+        &mut self,
+        exchange: Exchange,
+        base_currency: &str,
+    ) -> Result<Vec<TrianglePath>, TriangleError> {
+        let volumes = if self.filter.min_quote_volume_24h > 0.0 {
+            self.quote_volumes(exchange).await?.clone()
+        } else {
+            HashMap::new()
+        };
+        let filter = self.filter.clone();
+        let pairs: Vec<TradingPair> = self
+            .trading_pairs(exchange)
+            .await?
+            .iter()
+            .filter(|pair| filter.allows(pair, &volumes))
+            .cloned()
+            .collect();
+
+        let paths = find_triangles(&pairs, base_currency);
+        if paths.is_empty() {
+            return Err(TriangleError::NoPaths(format!(
+                "{}上以{}为基准，过滤后剩余交易对 {} 个（{}）",
+                exchange.as_str(),
+                base_currency,
+                pairs.len(),
+                filter.describe()
+            )));
+        }
+        debug!(
+            "🔺 {}上发现{}条以{}为基准的三角路径（可用交易对 {} 个）",
+            exchange.as_str(),
+            paths.len(),
+            base_currency,
+            pairs.len()
+        );
+        Ok(paths)
+    }
+
+    /// 拉取最新买一卖一，按收益率从高到低返回候选路径
+    pub async fn rank_triangles(
+        &mut self,
+        exchange: Exchange,
+        base_currency: &str,
+        fee_rate: f64,
+    ) -> Result<Vec<(TrianglePath, f64)>, TriangleError> {
+        let paths = self.discover_triangles(exchange, base_currency).await?;
+        if paths.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let tickers = fetch_book_tickers(&self.client, exchange).await?;
+        let mut ranked: Vec<(TrianglePath, f64)> = paths
+            .into_iter()
+            .filter_map(|path| {
+                let profit = path.estimate_return(&tickers, fee_rate)?;
+                Some((path, profit))
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(ranked)
+    }
+}
+
+/// 按配置自动发现三角路径并持续扫描收益最高的路径
+pub async fn run_triangle_scanner(config: &TriangleConfig) -> Result<(), TriangleError> {
+    env_logger::init();
+
+    let exchange = Exchange::from_code(&config.exchange)?;
+    let mut scanner = TriangleScanner::with_filter(
+        Duration::from_secs(config.pairs_cache_secs),
+        PairFilter::from_config(config),
+    );
+
+    info!(
+        "🔺 三角套利路径扫描已启动 - 交易所: {}, 基准币: {}, 手续费率: {:.4}%, 最小收益: {:.4}%",
+        exchange.as_str(),
+        config.base_currency,
+        config.fee_rate * 100.0,
+        config.min_profit_rate * 100.0
+    );
+    if !config.blacklist_pairs.is_empty() || !config.whitelist_pairs.is_empty() {
+        info!(
+            "🚫 交易对过滤 - 黑名单: {:?}, 白名单: {:?}",
+            config.blacklist_pairs, config.whitelist_pairs
+        );
+    }
+
+    loop {
+        match scanner
+            .rank_triangles(exchange, &config.base_currency, config.fee_rate)
+            .await
+        {
+            Ok(ranked) if ranked.is_empty() => {
+                warn!("⚠️ 未找到以{}为基准的可用三角路径", config.base_currency);
+            }
+            Ok(ranked) => {
+                for (path, profit) in ranked.iter().take(5) {
+                    debug!("   {} 预期收益: {:.4}%", path.describe(), profit * 100.0);
+                }
+                let (best, profit) = &ranked[0];
+                if *profit >= config.min_profit_rate {
+                    info!(
+                        "💰 发现套利机会 - 路径: {}, 预期收益: {:.4}%",
+                        best.describe(),
+                        profit * 100.0
+                    );
+                    // TODO: 接入交易所下单接口后按该路径依次执行三笔交易
+                } else {
+                    debug!(
+                        "🔍 最优路径 {} 预期收益 {:.4}%，未达到最小收益要求",
+                        best.describe(),
+                        profit * 100.0
+                    );
+                }
+            }
+            // 过滤条件排除了全部路径，继续扫描也不会有结果
+            Err(e @ TriangleError::NoPaths(_)) => {
+                error!(
+                    "❌ {}，请检查 blacklist_pairs/whitelist_pairs/min_quote_volume_24h",
+                    e
+                );
+                return Err(e);
+            }
+            Err(e) => warn!("⚠️ 扫描三角路径失败: {}", e),
+        }
+
+        tokio::time::sleep(Duration::from_secs(config.scan_interval_secs)).await;
+    }
+}
+
+async fn get_json(client: &reqwest::Client, url: &str) -> Result<serde_json::Value, TriangleError> {
+    client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| TriangleError::Request(format!("{:?}", e)))?
+        .json()
+        .await
+        .map_err(|e| TriangleError::Parse(format!("{:?}", e)))
+}
+
+fn json_str<'a>(value: &'a serde_json::Value, key: &str) -> Option<&'a str> {
+    value.get(key).and_then(|v| v.as_str())
+}
+
+fn json_f64(value: &serde_json::Value, key: &str) -> Option<f64> {
+    json_str(value, key).and_then(|v| v.parse().ok())
+}
+
+/// 按字段路径取出数组，空路径表示响应本身就是数组
+fn json_array<'a>(
+    value: &'a serde_json::Value,
+    path: &[&str],
+) -> Option<&'a Vec<serde_json::Value>> {
+    path.iter()
+        .try_fold(value, |node, key| node.get(key))?
+        .as_array()
+}
+
+/// 各交易所盘口报价接口的字段名：(交易对, 买一价, 卖一价)
+fn ticker_keys(exchange: Exchange) -> (&'static str, &'static str, &'static str) {
+    match exchange {
+        Exchange::Binance => ("symbol", "bidPrice", "askPrice"),
+        Exchange::Okx => ("instId", "bidPx", "askPx"),
+        Exchange::Bybit => ("symbol", "bid1Price", "ask1Price"),
+    }
+}
+
+fn parse_book_ticker(exchange: Exchange, item: &serde_json::Value) -> Option<(String, BookTicker)> {
+    let (symbol_key, bid_key, ask_key) = ticker_keys(exchange);
+    Some((
+        json_str(item, symbol_key)?.to_string(),
+        BookTicker {
+            bid: json_f64(item, bid_key)?,
+            ask: json_f64(item, ask_key)?,
+        },
+    ))
+}
+
+async fn fetch_trading_pairs(
+    client: &reqwest::Client,
+    exchange: Exchange,
+) -> Result<Vec<TradingPair>, TriangleError> {
+    let (url, list_path) = match exchange {
+        Exchange::Binance => (
+            "https://api.binance.com/api/v3/exchangeInfo",
+            &["symbols"][..],
+        ),
+        Exchange::Okx => (
+            "https://www.okx.com/api/v5/public/instruments?instType=SPOT",
+            &["data"][..],
+        ),
+        Exchange::Bybit => (
+            "https://api.bybit.com/v5/market/instruments-info?category=spot",
+            &["result", "list"][..],
+        ),
+    };
+    let response = get_json(client, url).await?;
+    let items = json_array(&response, list_path)
+        .ok_or_else(|| TriangleError::Parse(format!("缺少 {} 字段", list_path.join("."))))?;
+
+    let mut seen = HashSet::new();
+    let pairs = items
+        .iter()
+        .filter_map(|item| {
+            let (symbol, base, quote, tradable) = match exchange {
+                Exchange::Binance => (
+                    json_str(item, "symbol")?,
+                    json_str(item, "baseAsset")?,
+                    json_str(item, "quoteAsset")?,
+                    json_str(item, "status") == Some("TRADING"),
+                ),
+                Exchange::Okx => (
+                    json_str(item, "instId")?,
+                    json_str(item, "baseCcy")?,
+                    json_str(item, "quoteCcy")?,
+                    json_str(item, "state") == Some("live"),
+                ),
+                Exchange::Bybit => (
+                    json_str(item, "symbol")?,
+                    json_str(item, "baseCoin")?,
+                    json_str(item, "quoteCoin")?,
+                    json_str(item, "status") == Some("Trading"),
+                ),
+            };
+            if !tradable || !seen.insert(symbol.to_string()) {
+                return None;
+            }
+            Some(TradingPair {
+                symbol: symbol.to_string(),
+                base: base.to_uppercase(),
+                quote: quote.to_uppercase(),
+            })
+        })
+        .collect();
+
+    Ok(pairs)
+}
+
+async fn fetch_book_tickers(
+    client: &reqwest::Client,
+    exchange: Exchange,
+) -> Result<HashMap<String, BookTicker>, TriangleError> {
+    let (url, list_path) = match exchange {
+        Exchange::Binance => ("https://api.binance.com/api/v3/ticker/bookTicker", &[][..]),
+        Exchange::Okx => (
+            "https://www.okx.com/api/v5/market/tickers?instType=SPOT",
+            &["data"][..],
+        ),
+        Exchange::Bybit => (
+            "https://api.bybit.com/v5/market/tickers?category=spot",
+            &["result", "list"][..],
+        ),
+    };
+    let response = get_json(client, url).await?;
+    let items = json_array(&response, list_path)
+        .ok_or_else(|| TriangleError::Parse("报价数据格式错误".to_string()))?;
+
+    Ok(items
+        .iter()
+        .filter_map(|item| parse_book_ticker(exchange, item))
+        .collect())
+}
+
+/// 拉取全部交易对的24小时成交额（计价币计）
+async fn fetch_quote_volumes(
+    client: &reqwest::Client,
+    exchange: Exchange,
+) -> Result<HashMap<String, f64>, TriangleError> {
+    let (url, list_path, symbol_key, volume_key) = match exchange {
+        Exchange::Binance => (
+            "https://api.binance.com/api/v3/ticker/24hr",
+            &[][..],
+            "symbol",
+            "quoteVolume",
+        ),
+        Exchange::Okx => (
+            "https://www.okx.com/api/v5/market/tickers?instType=SPOT",
+            &["data"][..],
+            "instId",
+            "volCcy24h",
+        ),
+        Exchange::Bybit => (
+            "https://api.bybit.com/v5/market/tickers?category=spot",
+            &["result", "list"][..],
+            "symbol",
+            "turnover24h",
+        ),
+    };
+    let response = get_json(client, url).await?;
+    let items = json_array(&response, list_path)
+        .ok_or_else(|| TriangleError::Parse("成交额数据格式错误".to_string()))?;
+
+    Ok(items
+        .iter()
+        .filter_map(|item| {
+            Some((
+                json_str(item, symbol_key)?.to_string(),
+                json_f64(item, volume_key)?,
+            ))
+        })
+        .collect())
+}
+
+/// 拉取单个交易对的买一卖一报价
+pub(super) async fn fetch_book_ticker(
+    client: &reqwest::Client,
+    exchange: Exchange,
+    symbol: &str,
+) -> Result<BookTicker, TriangleError> {
+    let url = match exchange {
+        Exchange::Binance => format!(
+            "https://api.binance.com/api/v3/ticker/bookTicker?symbol={}",
+            symbol
+        ),
+        Exchange::Okx => format!("https://www.okx.com/api/v5/market/ticker?instId={}", symbol),
+        Exchange::Bybit => format!(
+            "https://api.bybit.com/v5/market/tickers?category=spot&symbol={}",
+            symbol
+        ),
+    };
+    let response = get_json(client, &url).await?;
+    let item = match exchange {
+        Exchange::Binance => Some(&response),
+        Exchange::Okx => json_array(&response, &["data"]).and_then(|items| items.first()),
+        Exchange::Bybit => {
+            json_array(&response, &["result", "list"]).and_then(|items| items.first())
+        }
+    };
+    item.and_then(|item| parse_book_ticker(exchange, item))
+        .map(|(_, ticker)| ticker)
+        .ok_or_else(|| {
+            TriangleError::Parse(format!("{} 缺少 {} 的盘口报价", exchange.as_str(), symbol))
+        })
+}